@@ -0,0 +1,584 @@
+//! HTTP record/replay for deterministic agent tests
+//!
+//! A cassette is a JSON file of request/response pairs. While recording,
+//! every request sent through [`crate::ai::http_client::send`] goes to the
+//! network and the exchange is appended to the file. While replaying, the
+//! response comes from the file and nothing leaves the machine. Streaming
+//! (SSE) responses are stored as raw text and replayed in a single chunk.
+//!
+//! The module is only compiled into tests and builds with the `cassette`
+//! feature, so release binaries never read the variables below. In such a
+//! build a cassette is activated with environment variables:
+//!
+//! - `SENTINEL_CASSETTE` - path of the cassette file
+//! - `SENTINEL_CASSETTE_MODE` - `record` or `replay` (default)
+//! - `SENTINEL_CASSETTE_ROOT` - folder path stored as `{{ROOT}}`
+//!
+//! Request headers are never written, so API keys stay out of cassettes.
+//! Machine-specific strings (e.g. a temp folder path) can be swapped for
+//! named placeholders with [`Cassette::with_placeholder`].
+//!
+//! The fixtures in `tests/cassettes` are hand-written, not recorded from
+//! live providers: they store no request bodies, match on endpoint
+//! (`"matchOn": "endpoint"`) and replay in order. Re-record one with
+//! `SENTINEL_CASSETTE_MODE=record` when a test needs real provider output.
+
+use crate::ai::http_client::SendError;
+use crate::wal::io::atomic_write;
+use once_cell::sync::Lazy;
+use reqwest::{Client, Request, Response};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+
+/// Current cassette file format
+const CASSETTE_VERSION: u32 = 1;
+
+/// Response headers that are never recorded
+const SKIPPED_HEADERS: &[&str] = &["set-cookie", "content-length", "transfer-encoding"];
+
+/// Cassette used by `http_client::send`, if any
+static ACTIVE: Lazy<RwLock<Option<Arc<Cassette>>>> =
+    Lazy::new(|| RwLock::new(Cassette::from_env().map(Arc::new)));
+
+/// Whether requests are recorded from the network or replayed from disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    Record,
+    Replay,
+}
+
+/// How a replayed request is matched to a recorded one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchOn {
+    /// Method, URL and request body must all match
+    #[default]
+    Body,
+    /// Method and URL only; repeated requests replay in recorded order.
+    /// Suited to hand-written cassettes where the exact prompt is irrelevant.
+    Endpoint,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CassetteFile {
+    version: u32,
+    #[serde(default)]
+    match_on: MatchOn,
+    #[serde(default)]
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Interaction {
+    /// SHA-256 of method, URL and normalized body (empty in hand-written files)
+    #[serde(default)]
+    fingerprint: String,
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordedRequest {
+    method: String,
+    url: String,
+    /// JSON bodies are stored as JSON, anything else as a string
+    #[serde(default)]
+    body: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordedResponse {
+    status: u16,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    /// JSON bodies are stored as JSON, anything else (SSE) as a string
+    #[serde(default)]
+    body: Value,
+}
+
+struct CassetteState {
+    file: CassetteFile,
+    /// Interactions already replayed
+    used: Vec<bool>,
+}
+
+/// A set of recorded HTTP exchanges
+pub struct Cassette {
+    path: PathBuf,
+    mode: CassetteMode,
+    /// (name, value) pairs, longest value first
+    placeholders: Vec<(String, String)>,
+    state: Mutex<CassetteState>,
+}
+
+impl Cassette {
+    fn with_file(path: PathBuf, mode: CassetteMode, file: CassetteFile) -> Self {
+        let used = vec![false; file.interactions.len()];
+        Self {
+            path,
+            mode,
+            placeholders: Vec::new(),
+            state: Mutex::new(CassetteState { file, used }),
+        }
+    }
+
+    /// Start a new recording, replacing any existing file at `path`
+    pub fn record(path: impl Into<PathBuf>) -> Self {
+        Self::with_file(
+            path.into(),
+            CassetteMode::Record,
+            CassetteFile {
+                version: CASSETTE_VERSION,
+                match_on: MatchOn::Body,
+                interactions: Vec::new(),
+            },
+        )
+    }
+
+    /// Load a cassette for replay
+    pub fn replay(path: impl Into<PathBuf>) -> Result<Self, String> {
+        let path = path.into();
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read cassette {}: {}", path.display(), e))?;
+        let file: CassetteFile = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse cassette {}: {}", path.display(), e))?;
+        if file.version != CASSETTE_VERSION {
+            return Err(format!(
+                "Unsupported cassette version {} in {}",
+                file.version,
+                path.display()
+            ));
+        }
+        Ok(Self::with_file(path, CassetteMode::Replay, file))
+    }
+
+    /// Store `value` as `{{name}}` in the cassette.
+    ///
+    /// Applied to request bodies before fingerprinting and to recorded
+    /// response bodies, so a session recorded in one folder replays in
+    /// another.
+    pub fn with_placeholder(mut self, name: &str, value: impl Into<String>) -> Self {
+        let value = value.into();
+        if !value.is_empty() {
+            self.placeholders.push((format!("{{{{{}}}}}", name), value));
+            self.placeholders.sort_by_key(|(_, value)| std::cmp::Reverse(value.len()));
+        }
+        self
+    }
+
+    /// Activate from the `SENTINEL_CASSETTE*` environment variables
+    fn from_env() -> Option<Self> {
+        let path = PathBuf::from(std::env::var("SENTINEL_CASSETTE").ok()?);
+        let mode = std::env::var("SENTINEL_CASSETTE_MODE").unwrap_or_default();
+        let root = std::env::var("SENTINEL_CASSETTE_ROOT").unwrap_or_default();
+
+        if mode.eq_ignore_ascii_case("record") {
+            tracing::info!(path = %path.display(), "Recording HTTP cassette");
+            return Some(Self::record(path).with_placeholder("ROOT", root));
+        }
+
+        let cassette = match Self::replay(&path) {
+            Ok(cassette) => {
+                tracing::info!(path = %path.display(), "Replaying HTTP cassette");
+                cassette
+            }
+            Err(e) => {
+                // Stay in replay mode so requests fail instead of silently
+                // reaching the network
+                tracing::error!(error = %e, "Failed to load HTTP cassette");
+                Self::with_file(
+                    path,
+                    CassetteMode::Replay,
+                    CassetteFile {
+                        version: CASSETTE_VERSION,
+                        match_on: MatchOn::Body,
+                        interactions: Vec::new(),
+                    },
+                )
+            }
+        };
+        Some(cassette.with_placeholder("ROOT", root))
+    }
+
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    /// Number of recorded interactions not yet replayed
+    #[cfg(test)]
+    pub(crate) fn remaining(&self) -> usize {
+        self.state
+            .lock()
+            .map(|s| s.used.iter().filter(|used| !**used).count())
+            .unwrap_or(0)
+    }
+
+    fn mask(&self, text: &str) -> String {
+        self.placeholders
+            .iter()
+            .fold(text.to_string(), |acc, (name, value)| {
+                acc.replace(value, name)
+            })
+    }
+
+    fn unmask(&self, text: &str) -> String {
+        self.placeholders
+            .iter()
+            .fold(text.to_string(), |acc, (name, value)| {
+                acc.replace(name, value)
+            })
+    }
+
+    /// Serve a request from the cassette, or send and record it
+    pub(crate) async fn send(
+        &self,
+        client: &Client,
+        request: Request,
+    ) -> Result<Response, SendError> {
+        let method = request.method().as_str().to_string();
+        let url = self.mask(request.url().as_str());
+        let body = self.mask(&String::from_utf8_lossy(
+            request
+                .body()
+                .and_then(|b| b.as_bytes())
+                .unwrap_or_default(),
+        ));
+        let fingerprint = fingerprint(&method, &url, &body);
+
+        match self.mode {
+            CassetteMode::Replay => self.replay_response(&method, &url, &fingerprint),
+            CassetteMode::Record => {
+                let response = client.execute(request).await.map_err(SendError::Http)?;
+                let status = response.status().as_u16();
+                let headers: BTreeMap<String, String> = response
+                    .headers()
+                    .iter()
+                    .filter(|(name, _)| !SKIPPED_HEADERS.contains(&name.as_str()))
+                    .filter_map(|(name, value)| {
+                        Some((name.as_str().to_string(), value.to_str().ok()?.to_string()))
+                    })
+                    .collect();
+                let response_body = response.text().await.map_err(SendError::Http)?;
+
+                let recorded = RecordedResponse {
+                    status,
+                    headers,
+                    body: stored_body(&self.mask(&response_body)),
+                };
+                self.append(Interaction {
+                    fingerprint,
+                    request: RecordedRequest {
+                        method,
+                        url,
+                        body: stored_body(&body),
+                    },
+                    response: recorded.clone(),
+                })?;
+                build_response(&recorded, response_body)
+            }
+        }
+    }
+
+    fn replay_response(
+        &self,
+        method: &str,
+        url: &str,
+        fingerprint: &str,
+    ) -> Result<Response, SendError> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| SendError::Cassette("Cassette lock poisoned".to_string()))?;
+        let match_on = state.file.match_on;
+
+        let index = state
+            .file
+            .interactions
+            .iter()
+            .enumerate()
+            .position(|(i, interaction)| {
+                !state.used[i]
+                    && match match_on {
+                        MatchOn::Body => interaction.fingerprint == fingerprint,
+                        MatchOn::Endpoint => {
+                            interaction.request.method.eq_ignore_ascii_case(method)
+                                && interaction.request.url == url
+                        }
+                    }
+            })
+            .ok_or_else(|| {
+                SendError::Cassette(format!(
+                    "No recorded response in {} for {} {} (fingerprint {})",
+                    self.path.display(),
+                    method,
+                    url,
+                    fingerprint
+                ))
+            })?;
+
+        state.used[index] = true;
+        let recorded = &state.file.interactions[index].response;
+        build_response(recorded, self.unmask(&body_text(&recorded.body)))
+    }
+
+    fn append(&self, interaction: Interaction) -> Result<(), SendError> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| SendError::Cassette("Cassette lock poisoned".to_string()))?;
+        state.file.interactions.push(interaction);
+        state.used.push(true);
+        save(&self.path, &state.file).map_err(SendError::Cassette)
+    }
+}
+
+/// SHA-256 over method, URL and body; JSON bodies are normalized first
+fn fingerprint(method: &str, url: &str, body: &str) -> String {
+    let body = serde_json::from_str::<Value>(body)
+        .map(|v| v.to_string())
+        .unwrap_or_else(|_| body.to_string());
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_bytes());
+    hasher.update(b" ");
+    hasher.update(url.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(body.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+fn stored_body(text: &str) -> Value {
+    if text.is_empty() {
+        return Value::Null;
+    }
+    serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()))
+}
+
+fn body_text(body: &Value) -> String {
+    match body {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn build_response(recorded: &RecordedResponse, body: String) -> Result<Response, SendError> {
+    let mut builder = http::Response::builder().status(recorded.status);
+    for (name, value) in &recorded.headers {
+        builder = builder.header(name.as_str(), value.as_str());
+    }
+    let response = builder
+        .body(body)
+        .map_err(|e| SendError::Cassette(format!("Invalid recorded response: {}", e)))?;
+    Ok(Response::from(response))
+}
+
+fn save(path: &Path, file: &CassetteFile) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create cassette directory: {}", e))?;
+    }
+    let json = serde_json::to_vec_pretty(file)
+        .map_err(|e| format!("Failed to serialize cassette: {}", e))?;
+    atomic_write(path, &json)?;
+    Ok(())
+}
+
+/// The cassette requests currently go through, if any
+pub fn active() -> Option<Arc<Cassette>> {
+    ACTIVE.read().ok().and_then(|c| c.clone())
+}
+
+/// Whether responses are being served from a cassette.
+///
+/// Credentials are not needed while replaying.
+pub(crate) fn is_replaying() -> bool {
+    active().is_some_and(|c| c.mode() == CassetteMode::Replay)
+}
+
+/// Serializes tests that install a cassette (the active cassette is global)
+#[cfg(test)]
+static INSTALL_LOCK: Lazy<Arc<tokio::sync::Mutex<()>>> =
+    Lazy::new(|| Arc::new(tokio::sync::Mutex::new(())));
+
+/// Keeps a cassette active until dropped
+#[cfg(test)]
+pub(crate) struct CassetteGuard {
+    cassette: Arc<Cassette>,
+    _lock: tokio::sync::OwnedMutexGuard<()>,
+}
+
+#[cfg(test)]
+impl CassetteGuard {
+    pub(crate) fn cassette(&self) -> &Cassette {
+        &self.cassette
+    }
+}
+
+#[cfg(test)]
+impl Drop for CassetteGuard {
+    fn drop(&mut self) {
+        if let Ok(mut active) = ACTIVE.write() {
+            *active = None;
+        }
+    }
+}
+
+/// Route all requests through `cassette` for the lifetime of the guard
+#[cfg(test)]
+pub(crate) async fn install(cassette: Cassette) -> CassetteGuard {
+    let lock = INSTALL_LOCK.clone().lock_owned().await;
+    let cassette = Arc::new(cassette);
+    if let Ok(mut active) = ACTIVE.write() {
+        *active = Some(cassette.clone());
+    }
+    CassetteGuard {
+        cassette,
+        _lock: lock,
+    }
+}
+
+/// Path of a cassette checked into `src-tauri/tests/cassettes`
+#[cfg(test)]
+pub(crate) fn fixture_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("cassettes")
+        .join(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::http_client::{anthropic_client, send};
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn write_cassette(dir: &TempDir, file: &CassetteFile) -> PathBuf {
+        let path = dir.path().join("cassette.json");
+        save(&path, file).unwrap();
+        path
+    }
+
+    fn interaction(fingerprint: &str, url: &str, body: Value) -> Interaction {
+        Interaction {
+            fingerprint: fingerprint.to_string(),
+            request: RecordedRequest {
+                method: "POST".to_string(),
+                url: url.to_string(),
+                body: Value::Null,
+            },
+            response: RecordedResponse {
+                status: 200,
+                headers: BTreeMap::from([(
+                    "x-ratelimit-remaining-requests".to_string(),
+                    "42".to_string(),
+                )]),
+                body,
+            },
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_json_formatting() {
+        let a = fingerprint("POST", "https://x/v1", "{\"a\": 1,\n \"b\": [2]}");
+        let b = fingerprint("POST", "https://x/v1", "{\"a\":1,\"b\":[2]}");
+        assert_eq!(a, b);
+        assert_ne!(
+            a,
+            fingerprint("POST", "https://x/v1", "{\"a\":2,\"b\":[2]}")
+        );
+        assert_ne!(a, fingerprint("GET", "https://x/v1", "{\"a\":1,\"b\":[2]}"));
+    }
+
+    #[test]
+    fn test_placeholders_round_trip() {
+        let cassette = Cassette::record("unused.json")
+            .with_placeholder("ROOT", "/tmp/abc")
+            .with_placeholder("FILE", "/tmp/abc/report.pdf");
+        let masked = cassette.mask("move /tmp/abc/report.pdf into /tmp/abc/Docs");
+        assert_eq!(masked, "move {{FILE}} into {{ROOT}}/Docs");
+        assert_eq!(
+            cassette.unmask(&masked),
+            "move /tmp/abc/report.pdf into /tmp/abc/Docs"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replay_by_fingerprint() {
+        let dir = TempDir::new().unwrap();
+        let url = "https://api.example.test/v1/messages";
+        let request_body = json!({ "model": "m", "prompt": "{{ROOT}}/a.txt" });
+        let fp = fingerprint("POST", url, &request_body.to_string());
+        let path = write_cassette(
+            &dir,
+            &CassetteFile {
+                version: CASSETTE_VERSION,
+                match_on: MatchOn::Body,
+                interactions: vec![interaction(&fp, url, json!({ "path": "{{ROOT}}/b.txt" }))],
+            },
+        );
+
+        let guard = install(
+            Cassette::replay(&path)
+                .unwrap()
+                .with_placeholder("ROOT", "/home/me/inbox"),
+        )
+        .await;
+        assert!(is_replaying());
+
+        let response = send(
+            anthropic_client()
+                .post(url)
+                .json(&json!({ "model": "m", "prompt": "/home/me/inbox/a.txt" })),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(response.headers()["x-ratelimit-remaining-requests"], "42");
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["path"], "/home/me/inbox/b.txt");
+        assert_eq!(guard.cassette().remaining(), 0);
+
+        // Recorded once, so a second identical request misses
+        let err = send(
+            anthropic_client()
+                .post(url)
+                .json(&json!({ "model": "m", "prompt": "/home/me/inbox/a.txt" })),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, SendError::Cassette(_)));
+    }
+
+    #[tokio::test]
+    async fn test_endpoint_matching_replays_in_order() {
+        let dir = TempDir::new().unwrap();
+        let url = "https://api.example.test/v1/chat/completions";
+        let path = write_cassette(
+            &dir,
+            &CassetteFile {
+                version: CASSETTE_VERSION,
+                match_on: MatchOn::Endpoint,
+                interactions: vec![
+                    interaction("", url, Value::String("data: first\n\n".to_string())),
+                    interaction("", url, Value::String("data: second\n\n".to_string())),
+                ],
+            },
+        );
+
+        let _guard = install(Cassette::replay(&path).unwrap()).await;
+        for expected in ["data: first\n\n", "data: second\n\n"] {
+            let response = send(anthropic_client().post(url).body("anything"))
+                .await
+                .unwrap();
+            assert_eq!(response.text().await.unwrap(), expected);
+        }
+    }
+}
@@ -6,20 +6,25 @@
 //! 3. Tool is executed, result fed back
 //! 4. Loop until final response
 //!
-//! Supports true streaming of text responses via SSE parsing.
+//! Requests go through the Anthropic `LlmProvider` backend, which streams
+//! text, extended thinking and tool calls as they arrive.
 
 use crate::ai::chat::context::{hydrate_context, ContextItem, HydratedContext};
-use crate::ai::chat::tools::{execute_chat_tool, get_chat_tools, ChatToolResult};
-use crate::ai::credentials::CredentialManager;
-use crate::ai::http_client::anthropic_client;
-use futures::StreamExt;
+use crate::ai::chat::tools::{execute_chat_tool, ChatToolResult};
+use crate::ai::chat::tools_staging::{is_staging_tool, STAGED_CHANGED_EVENT};
+use crate::commands::vfs::VFSState;
+use crate::ai::provider::{
+    AnthropicProvider, ChatMessage, ChatRequest, ChatResponse, ContentPart, LlmProvider,
+    StopReason, StreamEvent,
+};
+use crate::ai::tools::ToolDefinition;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
+use serde_json::json;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
 use tokio::time::sleep;
 use tracing::{debug, info, warn};
 
@@ -28,9 +33,9 @@ use tracing::{debug, info, warn};
 /// Tool definitions are static and don't change during runtime, so we cache
 /// them once at first use. This saves ~50us per request from repeated
 /// serde_json serialization.
-static CACHED_TOOLS: Lazy<Vec<Value>> = Lazy::new(|| {
+static CACHED_TOOLS: Lazy<Vec<ToolDefinition>> = Lazy::new(|| {
     debug!("Initializing cached tool definitions");
-    get_chat_tools()
+    super::provider_agent::chat_tool_definitions()
 });
 
 /// Helper macro to emit events with proper error logging
@@ -56,18 +61,9 @@ const MAX_TOKENS: u32 = 16000;
 const THINKING_BUDGET: u32 = 10000;
 
 /// Maximum buffer sizes to prevent OOM from malformed API responses
-const MAX_SSE_BUFFER_SIZE: usize = 1_000_000; // 1MB for SSE line buffer
-const MAX_TEXT_BLOCK_SIZE: usize = 500_000; // 500KB for text accumulation
 const MAX_THINKING_BLOCK_SIZE: usize = 1_000_000; // 1MB for thinking (can be large)
-const MAX_TOOL_INPUT_SIZE: usize = 100_000; // 100KB for tool input JSON
 const MAX_FINAL_RESPONSE_SIZE: usize = 2_000_000; // 2MB for final accumulated response
 
-/// Anthropic API URL
-const ANTHROPIC_API_URL: &str = "https://api.anthropic.com/v1/messages";
-
-/// Anthropic API version (updated for extended thinking support)
-const ANTHROPIC_VERSION: &str = "2023-06-01";
-
 /// Token batching configuration
 /// Batches token emissions to reduce IPC overhead (~90% fewer events)
 const TOKEN_BATCH_WINDOW_MS: u64 = 16; // ~60fps update rate
@@ -90,7 +86,7 @@ impl TokenBatcher {
     }
 
     /// Add a chunk to the buffer, potentially flushing if threshold met
-    fn add(&mut self, chunk: &str, app: &AppHandle) {
+    fn add<R: Runtime>(&mut self, chunk: &str, app: &AppHandle<R>) {
         self.buffer.push_str(chunk);
 
         let should_flush = self.buffer.len() >= TOKEN_BATCH_MAX_CHARS
@@ -104,7 +100,7 @@ impl TokenBatcher {
     }
 
     /// Flush any remaining content in the buffer
-    fn flush(&mut self, app: &AppHandle) {
+    fn flush<R: Runtime>(&mut self, app: &AppHandle<R>) {
         if !self.buffer.is_empty() {
             emit_logged!(app, "chat:token", json!({ "chunk": &self.buffer }));
             self.buffer.clear();
@@ -138,28 +134,6 @@ pub struct ChatAgentResult {
     pub usage: TokenUsage,
 }
 
-/// Streaming event types from Anthropic API
-#[derive(Debug, Deserialize)]
-#[serde(tag = "type")]
-enum StreamEvent {
-    #[serde(rename = "message_start")]
-    MessageStart { message: Value },
-    #[serde(rename = "content_block_start")]
-    ContentBlockStart { #[allow(dead_code)] index: usize, content_block: Value },
-    #[serde(rename = "content_block_delta")]
-    ContentBlockDelta { #[allow(dead_code)] index: usize, delta: Value },
-    #[serde(rename = "content_block_stop")]
-    ContentBlockStop { #[allow(dead_code)] index: usize },
-    #[serde(rename = "message_delta")]
-    MessageDelta { delta: Value, usage: Option<Value> },
-    #[serde(rename = "message_stop")]
-    MessageStop,
-    #[serde(rename = "ping")]
-    Ping,
-    #[serde(rename = "error")]
-    Error { error: Value },
-}
-
 /// Run the chat agent loop with streaming
 ///
 /// # Arguments
@@ -181,8 +155,8 @@ enum StreamEvent {
 /// * `chat:complete` - Finished
 /// * `chat:error` - Error occurred
 /// * `chat:aborted` - Aborted by user
-pub async fn run_chat_agent(
-    app: &AppHandle,
+pub async fn run_chat_agent<R: Runtime>(
+    app: &AppHandle<R>,
     message: &str,
     context_items: &[ContextItem],
     model: &str,
@@ -213,8 +187,8 @@ pub async fn run_chat_agent(
         });
     }
 
-    // 1. Get API key
-    let api_key = CredentialManager::get_api_key("anthropic")?;
+    // 1. Anthropic backend with the stored API key
+    let provider = AnthropicProvider::from_credentials()?;
 
     // 2. Hydrate context (files → text, folders → holograms)
     let hydrated: HydratedContext = hydrate_context(context_items)?;
@@ -234,10 +208,7 @@ pub async fn run_chat_agent(
     // 6. Get cached tool definitions (lazy-initialized singleton)
     let tools = &*CACHED_TOOLS;
 
-    // 7. Use shared HTTP client with connection pooling
-    let client = anthropic_client();
-
-    // 8. ReAct Loop with streaming
+    // 7. ReAct Loop with streaming
     let mut final_response = String::new();
 
     for iteration in 0..MAX_ITERATIONS {
@@ -258,74 +229,55 @@ pub async fn run_chat_agent(
             sleep(Duration::from_millis(REQUEST_DELAY_MS)).await;
         }
 
-        // Build request with streaming
-        let mut request_body = json!({
-            "model": model,
-            "max_tokens": MAX_TOKENS,
-            "system": system_prompt,
-            "messages": messages,
-            "tools": tools,
-            "stream": true,
-        });
+        // Build request
+        let mut request = ChatRequest::new(model, MAX_TOKENS)
+            .with_system(system_prompt.clone())
+            .with_messages(messages.clone())
+            .with_tools(tools.clone());
 
         // Conditionally enable extended thinking
         if extended_thinking {
-            request_body["thinking"] = json!({
-                "type": "enabled",
-                "budget_tokens": THINKING_BUDGET
-            });
+            request = request.with_thinking(THINKING_BUDGET);
             debug!(budget_tokens = THINKING_BUDGET, "Extended thinking enabled");
         }
 
-        // Send streaming request
-        let response = client
-            .post(ANTHROPIC_API_URL)
-            .header("x-api-key", &api_key)
-            .header("anthropic-version", ANTHROPIC_VERSION)
-            .header("content-type", "application/json")
-            .json(&request_body)
-            .send()
-            .await
-            .map_err(|e| format!("HTTP request failed: {}", e))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(format!("API error {}: {}", status, error_text));
-        }
-
-        // Process streaming response (returns usage for this iteration)
-        let (stop_reason, has_tool_use, assistant_content, tool_results, _iteration_text, iteration_usage) =
-            process_stream(app, response, &mut final_response).await?;
+        // Stream the response, forwarding text, thinking and tool starts
+        let response = stream_response(app, &provider, &request).await?;
 
         // Accumulate token usage from this iteration
-        total_usage.input_tokens += iteration_usage.input_tokens;
-        total_usage.output_tokens += iteration_usage.output_tokens;
-        total_usage.cache_creation_input_tokens += iteration_usage.cache_creation_input_tokens;
-        total_usage.cache_read_input_tokens += iteration_usage.cache_read_input_tokens;
+        total_usage.input_tokens += response.usage.input_tokens;
+        total_usage.output_tokens += response.usage.output_tokens;
+        total_usage.cache_creation_input_tokens += response.usage.cache_creation_input_tokens;
+        total_usage.cache_read_input_tokens += response.usage.cache_read_input_tokens;
+
+        let text = response.text();
+        if final_response.len() + text.len() <= MAX_FINAL_RESPONSE_SIZE {
+            final_response.push_str(&text);
+        } else {
+            warn!(max_size = MAX_FINAL_RESPONSE_SIZE, "Response truncated");
+        }
 
         // Add assistant message to history
-        messages.push(json!({
-            "role": "assistant",
-            "content": assistant_content,
-        }));
+        // Note: Thinking blocks are kept so the signed reasoning is sent back
+        if !response.content.is_empty() {
+            messages.push(ChatMessage::assistant_parts(response.content.clone()));
+        }
 
         // If tool was used, add results and continue loop
+        let has_tool_use = response.has_tool_use();
+        let tool_results = execute_tool_calls(app, &response.content).await;
         if has_tool_use && !tool_results.is_empty() {
-            messages.push(json!({
-                "role": "user",
-                "content": tool_results,
-            }));
+            messages.push(ChatMessage::user_parts(tool_results));
         }
 
         // Check stop condition
-        if stop_reason == Some("end_turn".to_string()) && !has_tool_use {
+        if response.stop_reason == StopReason::EndTurn && !has_tool_use {
             info!(iterations = iteration + 1, "Chat completed");
             break;
         }
     }
 
-    // 9. Emit completion
+    // 8. Emit completion
     app.emit("chat:complete", json!({}))
         .map_err(|e| format!("Event emit failed: {}", e))?;
 
@@ -343,378 +295,166 @@ pub async fn run_chat_agent(
     })
 }
 
-/// Initial SSE buffer capacity (16KB - typical for streaming responses)
-const INITIAL_SSE_BUFFER_CAPACITY: usize = 16_384;
-
-/// Initial text block capacity (8KB - typical response paragraph)
-const INITIAL_TEXT_BLOCK_CAPACITY: usize = 8_192;
-
-/// Initial thinking block capacity (32KB - thinking can be verbose)
-const INITIAL_THINKING_BLOCK_CAPACITY: usize = 32_768;
-
-/// Process the streaming response from Anthropic API
-/// Returns: (stop_reason, has_tool_use, assistant_content, tool_results, iteration_text, usage)
-async fn process_stream(
-    app: &AppHandle,
-    response: reqwest::Response,
-    final_response: &mut String,
-) -> Result<(Option<String>, bool, Vec<Value>, Vec<Value>, String, TokenUsage), String> {
-    let mut stream = response.bytes_stream();
-
-    // Pre-allocate buffer with typical capacity to reduce reallocations
-    let mut buffer = String::with_capacity(INITIAL_SSE_BUFFER_CAPACITY);
-    let mut stop_reason: Option<String> = None;
-    let mut has_tool_use = false;
-    let mut assistant_content: Vec<Value> = Vec::with_capacity(4); // Typical: 1-3 content blocks
-    let mut tool_results: Vec<Value> = Vec::with_capacity(2); // Typical: 0-2 tool calls
-    let mut iteration_text = String::with_capacity(INITIAL_TEXT_BLOCK_CAPACITY);
-
-    // Track token usage from API response
-    let mut usage = TokenUsage::default();
-
-    // Track current content blocks being built (with pre-allocation)
-    let mut current_text_block: Option<String> = None;
-    let mut current_tool_block: Option<(String, String, String)> = None; // (id, name, input_json_string)
-    let mut current_thinking_block: Option<String> = None; // Extended thinking content
-    let mut thinking_signature: Option<String> = None;
-
+/// Stream one model turn from the Anthropic backend
+///
+/// Text is forwarded as batched `chat:token` events, extended thinking as
+/// `chat:thinking` events and each tool call start as a running `chat:thought`.
+async fn stream_response<R: Runtime>(
+    app: &AppHandle<R>,
+    provider: &AnthropicProvider,
+    request: &ChatRequest,
+) -> Result<ChatResponse, String> {
     // Token batcher for reducing event frequency (~90% fewer IPC calls)
-    let mut token_batcher = TokenBatcher::new();
-
-    while let Some(chunk_result) = stream.next().await {
-        let chunk = chunk_result.map_err(|e| format!("Stream error: {}", e))?;
-        let chunk_str = String::from_utf8_lossy(&chunk);
-
-        // Bounded buffer check to prevent OOM from malformed responses
-        if buffer.len() + chunk_str.len() > MAX_SSE_BUFFER_SIZE {
-            return Err(format!(
-                "SSE buffer exceeded maximum size of {} bytes (possible malformed response)",
-                MAX_SSE_BUFFER_SIZE
-            ));
+    let token_batcher = Mutex::new(TokenBatcher::new());
+    let thinking_block = Mutex::new(String::new());
+
+    let on_event = |event: StreamEvent| match event {
+        StreamEvent::TextDelta(text) => {
+            // Batch text chunks to reduce IPC overhead
+            // Emits every 16ms or 50 chars instead of per-token
+            if let Ok(mut batcher) = token_batcher.lock() {
+                batcher.add(&text, app);
+            }
         }
-        buffer.push_str(&chunk_str);
-
-        // Process complete lines (SSE format) - zero-copy where possible
-        while let Some(newline_pos) = buffer.find('\n') {
-            // Extract line without allocation for simple checks
-            let line_slice = buffer[..newline_pos].trim();
-
-            // Skip empty lines and event type lines (no allocation needed)
-            if line_slice.is_empty() || line_slice.starts_with("event:") {
-                // Drain processed portion efficiently
-                buffer.drain(..=newline_pos);
-                continue;
+        StreamEvent::ThinkingStart => {
+            if let Ok(mut block) = thinking_block.lock() {
+                block.clear();
+            }
+            debug!("Extended thinking started");
+
+            // Emit thinking started event
+            emit_logged!(
+                app,
+                "chat:thinking",
+                json!({
+                    "status": "started",
+                    "timestamp": chrono::Utc::now().timestamp_millis(),
+                })
+            );
+        }
+        StreamEvent::ThinkingDelta(thinking) => {
+            // Emit thinking chunk for streaming
+            emit_logged!(app, "chat:thinking", json!({
+                "status": "streaming",
+                "chunk": &thinking,
+            }));
+
+            // Accumulate thinking with bounds check
+            if let Ok(mut block) = thinking_block.lock() {
+                if block.len() + thinking.len() <= MAX_THINKING_BLOCK_SIZE {
+                    block.push_str(&thinking);
+                } else {
+                    warn!(max_size = MAX_THINKING_BLOCK_SIZE, "Thinking block truncated");
+                }
             }
+        }
+        StreamEvent::ThinkingStop => {
+            let thinking = thinking_block
+                .lock()
+                .map(|mut block| std::mem::take(&mut *block))
+                .unwrap_or_default();
+            debug!(chars = thinking.len(), "Extended thinking completed");
+
+            // Emit thinking completed event
+            emit_logged!(
+                app,
+                "chat:thinking",
+                json!({
+                    "status": "complete",
+                    "content": &thinking,
+                })
+            );
+        }
+        StreamEvent::ToolUseStart { id, name } => {
+            // Emit thought step (running) - input will be updated when complete
+            emit_logged!(
+                app,
+                "chat:thought",
+                json!({
+                    "id": &id,
+                    "tool": &name,
+                    "input": "",  // Placeholder until we have full input
+                    "status": "running",
+                    "timestamp": chrono::Utc::now().timestamp_millis(),
+                })
+            );
+        }
+    };
 
-            // Parse data lines - clone data before draining buffer
-            let data_opt = line_slice.strip_prefix("data: ").map(|s| s.to_string());
+    let result = provider.stream(request, &on_event).await;
 
-            // Drain the processed line from buffer (single operation vs two string allocations)
-            buffer.drain(..=newline_pos);
+    // Flush any remaining batched tokens
+    if let Ok(mut batcher) = token_batcher.lock() {
+        batcher.flush(app);
+    }
 
-            if let Some(data) = data_opt {
-                if data == "[DONE]" {
-                    continue;
-                }
+    result.map_err(String::from)
+}
 
-                match serde_json::from_str::<StreamEvent>(&data) {
-                    Ok(event) => {
-                        match event {
-                            StreamEvent::ContentBlockStart { content_block, .. } => {
-                                let block_type = content_block
-                                    .get("type")
-                                    .and_then(|t| t.as_str())
-                                    .unwrap_or("");
-
-                                match block_type {
-                                    "thinking" => {
-                                        // Extended thinking block started - pre-allocate for typical size
-                                        current_thinking_block = Some(String::with_capacity(INITIAL_THINKING_BLOCK_CAPACITY));
-                                        debug!("Extended thinking started");
-
-                                        // Emit thinking started event
-                                        app.emit(
-                                            "chat:thinking",
-                                            json!({
-                                                "status": "started",
-                                                "timestamp": chrono::Utc::now().timestamp_millis(),
-                                            }),
-                                        )
-                                        .ok();
-                                    }
-                                    "text" => {
-                                        // Pre-allocate text block for typical paragraph size
-                                        current_text_block = Some(String::with_capacity(INITIAL_TEXT_BLOCK_CAPACITY));
-                                    }
-                                    "tool_use" => {
-                                        has_tool_use = true;
-                                        let id = content_block
-                                            .get("id")
-                                            .and_then(|i| i.as_str())
-                                            .unwrap_or("unknown")
-                                            .to_string();
-                                        let name = content_block
-                                            .get("name")
-                                            .and_then(|n| n.as_str())
-                                            .unwrap_or("unknown")
-                                            .to_string();
-
-                                        // Emit thought step (running) - input will be updated when complete
-                                        app.emit(
-                                            "chat:thought",
-                                            json!({
-                                                "id": &id,
-                                                "tool": &name,
-                                                "input": "",  // Placeholder until we have full input
-                                                "status": "running",
-                                                "timestamp": chrono::Utc::now().timestamp_millis(),
-                                            }),
-                                        )
-                                        .ok();
-
-                                        current_tool_block = Some((id, name, String::new()));
-                                    }
-                                    _ => {}
-                                }
-                            }
-                            StreamEvent::ContentBlockDelta { delta, .. } => {
-                                let delta_type = delta
-                                    .get("type")
-                                    .and_then(|t| t.as_str())
-                                    .unwrap_or("");
-
-                                match delta_type {
-                                    "thinking_delta" => {
-                                        // Extended thinking content streaming
-                                        if let Some(thinking) =
-                                            delta.get("thinking").and_then(|t| t.as_str())
-                                        {
-                                            // Emit thinking chunk for streaming
-                                            emit_logged!(app, "chat:thinking", json!({
-                                                "status": "streaming",
-                                                "chunk": thinking,
-                                            }));
-
-                                            // Accumulate thinking with bounds check
-                                            if let Some(ref mut block) = current_thinking_block {
-                                                if block.len() + thinking.len() <= MAX_THINKING_BLOCK_SIZE {
-                                                    block.push_str(thinking);
-                                                } else {
-                                                    warn!(max_size = MAX_THINKING_BLOCK_SIZE, "Thinking block truncated");
-                                                }
-                                            }
-                                        }
-                                    }
-                                    "signature_delta" => {
-                                        // Thinking block signature (for verification)
-                                        if let Some(sig) =
-                                            delta.get("signature").and_then(|s| s.as_str())
-                                        {
-                                            if let Some(ref mut sig_acc) = thinking_signature {
-                                                sig_acc.push_str(sig);
-                                            } else {
-                                                thinking_signature = Some(sig.to_string());
-                                            }
-                                        }
-                                    }
-                                    "text_delta" => {
-                                        if let Some(text) =
-                                            delta.get("text").and_then(|t| t.as_str())
-                                        {
-                                            // Batch text chunks to reduce IPC overhead
-                                            // Emits every 16ms or 50 chars instead of per-token
-                                            token_batcher.add(text, app);
-
-                                            // Accumulate text with bounds checks
-                                            if let Some(ref mut block) = current_text_block {
-                                                if block.len() + text.len() <= MAX_TEXT_BLOCK_SIZE {
-                                                    block.push_str(text);
-                                                }
-                                            }
-                                            if final_response.len() + text.len() <= MAX_FINAL_RESPONSE_SIZE {
-                                                final_response.push_str(text);
-                                            } else {
-                                                warn!(max_size = MAX_FINAL_RESPONSE_SIZE, "Response truncated");
-                                            }
-                                            iteration_text.push_str(text);
-                                        }
-                                    }
-                                    "input_json_delta" => {
-                                        if let Some((_, _, ref mut input_json)) = current_tool_block {
-                                            if let Some(partial) =
-                                                delta.get("partial_json").and_then(|p| p.as_str())
-                                            {
-                                                // Accumulate JSON string fragments with bounds check
-                                                if input_json.len() + partial.len() <= MAX_TOOL_INPUT_SIZE {
-                                                    input_json.push_str(partial);
-                                                } else {
-                                                    warn!(max_size = MAX_TOOL_INPUT_SIZE, "Tool input truncated");
-                                                }
-                                            }
-                                        }
-                                    }
-                                    _ => {}
-                                }
-                            }
-                            StreamEvent::ContentBlockStop { .. } => {
-                                // Finalize thinking block
-                                if let Some(thinking) = current_thinking_block.take() {
-                                    let sig = thinking_signature.take();
-                                    debug!(chars = thinking.len(), "Extended thinking completed");
-
-                                    // Emit thinking completed event
-                                    app.emit(
-                                        "chat:thinking",
-                                        json!({
-                                            "status": "complete",
-                                            "content": &thinking,
-                                        }),
-                                    )
-                                    .ok();
-
-                                    // Add to assistant content for conversation history
-                                    // Note: Thinking blocks should be preserved for context
-                                    assistant_content.push(json!({
-                                        "type": "thinking",
-                                        "thinking": thinking,
-                                        "signature": sig.unwrap_or_default(),
-                                    }));
-                                }
-
-                                // Finalize text block
-                                if let Some(text) = current_text_block.take() {
-                                    assistant_content.push(json!({
-                                        "type": "text",
-                                        "text": text,
-                                    }));
-                                }
-
-                                // Finalize and execute tool block
-                                if let Some((id, name, input_json)) = current_tool_block.take() {
-                                    // Parse the accumulated JSON string
-                                    let tool_input: Value = if input_json.is_empty() {
-                                        json!({})
-                                    } else {
-                                        serde_json::from_str(&input_json).unwrap_or_else(|e| {
-                                            warn!(error = %e, "Failed to parse tool input JSON");
-                                            json!({})
-                                        })
-                                    };
-
-                                    debug!(tool = name, input = ?tool_input, "Tool input parsed");
-
-                                    // Add to assistant content
-                                    assistant_content.push(json!({
-                                        "type": "tool_use",
-                                        "id": &id,
-                                        "name": &name,
-                                        "input": &tool_input,
-                                    }));
-
-                                    // Execute tool
-                                    let result = execute_chat_tool(&name, &tool_input).await;
-
-                                    // Emit result
-                                    let (result_content, is_error) = match &result {
-                                        ChatToolResult::Success(s) => (s.clone(), false),
-                                        ChatToolResult::Error(e) => (e.clone(), true),
-                                    };
-
-                                    // Format input for display (UTF-8 safe truncation)
-                                    let input_display = tool_input.to_string();
-                                    let input_display = if input_display.len() > 200 {
-                                        let truncate_at = (0..=200).rev().find(|&i| input_display.is_char_boundary(i)).unwrap_or(0);
-                                        format!("{}...", &input_display[..truncate_at])
-                                    } else {
-                                        input_display
-                                    };
-
-                                    // UTF-8 safe output truncation
-                                    let output_max = result_content.len().min(500);
-                                    let output_truncate_at = (0..=output_max).rev().find(|&i| result_content.is_char_boundary(i)).unwrap_or(0);
-
-                                    app.emit(
-                                        "chat:thought",
-                                        json!({
-                                            "id": &id,
-                                            "tool": &name,
-                                            "input": input_display,
-                                            "output": &result_content[..output_truncate_at],
-                                            "status": if is_error { "error" } else { "complete" },
-                                            "timestamp": chrono::Utc::now().timestamp_millis(),
-                                        }),
-                                    )
-                                    .ok();
-
-                                    tool_results.push(json!({
-                                        "type": "tool_result",
-                                        "tool_use_id": &id,
-                                        "content": result_content,
-                                        "is_error": is_error,
-                                    }));
-                                }
-                            }
-                            StreamEvent::MessageStart { message } => {
-                                // Extract input token usage from message_start
-                                if let Some(msg_usage) = message.get("usage") {
-                                    if let Some(input) = msg_usage.get("input_tokens").and_then(|t| t.as_u64()) {
-                                        usage.input_tokens = input;
-                                    }
-                                    if let Some(cache_creation) = msg_usage.get("cache_creation_input_tokens").and_then(|t| t.as_u64()) {
-                                        usage.cache_creation_input_tokens = cache_creation;
-                                    }
-                                    if let Some(cache_read) = msg_usage.get("cache_read_input_tokens").and_then(|t| t.as_u64()) {
-                                        usage.cache_read_input_tokens = cache_read;
-                                    }
-                                }
-                            }
-                            StreamEvent::MessageDelta { delta, usage: delta_usage } => {
-                                // Extract stop reason
-                                if let Some(reason) =
-                                    delta.get("stop_reason").and_then(|r| r.as_str())
-                                {
-                                    stop_reason = Some(reason.to_string());
-                                }
-
-                                // Extract output token usage from message_delta
-                                if let Some(msg_usage) = delta_usage {
-                                    if let Some(output) = msg_usage.get("output_tokens").and_then(|t| t.as_u64()) {
-                                        usage.output_tokens = output;
-                                    }
-                                }
-                            }
-                            StreamEvent::MessageStop => {
-                                // Message complete
-                            }
-                            StreamEvent::Error { error } => {
-                                let error_msg = error
-                                    .get("message")
-                                    .and_then(|m| m.as_str())
-                                    .unwrap_or("Unknown streaming error");
-                                return Err(format!("Stream error: {}", error_msg));
-                            }
-                            _ => {}
-                        }
-                    }
-                    Err(_) => {
-                        // Skip malformed JSON - common during streaming
-                    }
-                }
-            }
+/// Execute the tool calls of an assistant turn
+/// Emits a completed `chat:thought` per call and returns the tool results
+async fn execute_tool_calls<R: Runtime>(
+    app: &AppHandle<R>,
+    content: &[ContentPart],
+) -> Vec<ContentPart> {
+    let mut tool_results = Vec::new();
+
+    for part in content {
+        let ContentPart::ToolUse {
+            id,
+            name,
+            input: tool_input,
+        } = part
+        else {
+            continue;
+        };
+
+        debug!(tool = %name, input = ?tool_input, "Tool input parsed");
+
+        // Execute tool
+        let vfs_state = app.state::<VFSState>();
+        let result = execute_chat_tool(name, tool_input, &vfs_state).await;
+
+        // Emit result
+        let (result_content, is_error) = match &result {
+            ChatToolResult::Success(s) => (s.clone(), false),
+            ChatToolResult::Error(e) => (e.clone(), true),
+        };
+
+        // Format input for display (UTF-8 safe truncation)
+        let input_display = tool_input.to_string();
+        let input_display = if input_display.len() > 200 {
+            let truncate_at = (0..=200).rev().find(|&i| input_display.is_char_boundary(i)).unwrap_or(0);
+            format!("{}...", &input_display[..truncate_at])
+        } else {
+            input_display
+        };
+
+        // UTF-8 safe output truncation
+        let output_max = result_content.len().min(500);
+        let output_truncate_at = (0..=output_max).rev().find(|&i| result_content.is_char_boundary(i)).unwrap_or(0);
+
+        if !is_error && is_staging_tool(name) {
+            emit_logged!(app, STAGED_CHANGED_EVENT, json!({ "tool": name }));
         }
+
+        app.emit(
+            "chat:thought",
+            json!({
+                "id": id,
+                "tool": name,
+                "input": input_display,
+                "output": &result_content[..output_truncate_at],
+                "status": if is_error { "error" } else { "complete" },
+                "timestamp": chrono::Utc::now().timestamp_millis(),
+            }),
+        )
+        .ok();
+
+        tool_results.push(ContentPart::tool_result(id.clone(), result_content, is_error));
     }
 
-    // Flush any remaining batched tokens
-    token_batcher.flush(app);
-
-    Ok((
-        stop_reason,
-        has_tool_use,
-        assistant_content,
-        tool_results,
-        iteration_text,
-        usage,
-    ))
+    tool_results
 }
 
 /// Build the chat system prompt
@@ -729,13 +469,14 @@ fn build_chat_system_prompt(context_addition: &str, previous_context: &str) -> S
 - **inspect_pattern**: Sample files matching a regex pattern
 - **bash**: Execute shell commands (ls, find, cat, head, wc, git status, etc.)
 - **grep**: Search file contents with regex (uses ripgrep for speed)
+- **stage_move**, **stage_rename**, **stage_create_folder**, **stage_quarantine**: Stage changes to the open folder for the user to approve
 
 ## Guidelines
 1. Use `grep` for searching inside file contents
 2. Use `bash` with `ls -la` or `find` for exploring directories
 3. Use `read_file` for reading specific file contents
 4. Cite file paths when referencing content
-5. You are READ-ONLY - never suggest rm, mv, or destructive commands
+5. Never change files directly or suggest rm, mv, or destructive commands. To reorganize, use the `stage_*` tools: they only stage changes, which the user must review and approve in the app
 6. Be concise and helpful
 
 ## Security
@@ -783,8 +524,8 @@ fn build_message_history(
     history: &[ConversationMessage],
     current_message: &str,
     hydrated: &HydratedContext,
-) -> Result<Vec<Value>, String> {
-    let mut messages: Vec<Value> = Vec::new();
+) -> Result<Vec<ChatMessage>, String> {
+    let mut messages: Vec<ChatMessage> = Vec::new();
 
     // Add previous messages (limit to last 20)
     let start = if history.len() > 20 {
@@ -793,44 +534,29 @@ fn build_message_history(
         0
     };
     for msg in &history[start..] {
-        messages.push(json!({
-            "role": msg.role,
-            "content": msg.content,
-        }));
+        if msg.role == "assistant" {
+            messages.push(ChatMessage::assistant(msg.content.clone()));
+        } else {
+            messages.push(ChatMessage::user(msg.content.clone()));
+        }
     }
 
     // Add current user message
     // If there are images, use multimodal format
     if hydrated.images.is_empty() {
-        messages.push(json!({
-            "role": "user",
-            "content": current_message,
-        }));
+        messages.push(ChatMessage::user(current_message));
     } else {
-        let mut content: Vec<Value> = Vec::new();
-
         // Add images first
-        for img in &hydrated.images {
-            content.push(json!({
-                "type": "image",
-                "source": {
-                    "type": "base64",
-                    "media_type": img.mime_type,
-                    "data": img.base64,
-                }
-            }));
-        }
+        let mut content: Vec<ContentPart> = hydrated
+            .images
+            .iter()
+            .map(|img| ContentPart::image(img.mime_type.clone(), img.base64.clone()))
+            .collect();
 
         // Add text
-        content.push(json!({
-            "type": "text",
-            "text": current_message,
-        }));
-
-        messages.push(json!({
-            "role": "user",
-            "content": content,
-        }));
+        content.push(ContentPart::text(current_message));
+
+        messages.push(ChatMessage::user_parts(content));
     }
 
     Ok(messages)
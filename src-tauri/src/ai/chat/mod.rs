@@ -5,15 +5,18 @@
 //! - Read file contents
 //! - Inspect folder patterns using V5 Hologram
 //! - Execute shell commands (bash, grep)
+//! - Stage moves, renames, new folders and quarantines for the user to approve
 //! - Answer questions about the filesystem
 //!
-//! Supports both Anthropic Claude and OpenAI GPT models.
+//! Supports Anthropic Claude directly and any other backend (OpenAI GPT,
+//! xAI Grok, local OpenAI-compatible servers) through `ai::provider`.
 
 pub mod agent;
 pub mod context;
-pub mod openai_provider;
+pub mod provider_agent;
 pub mod tool_conversion;
 pub mod tools;
+pub mod tools_staging;
 pub mod tools_terminal;
 
 #[cfg(test)]
@@ -24,6 +27,6 @@ pub use agent::{run_chat_agent, ChatAgentResult, ConversationMessage, TokenUsage
 #[allow(unused_imports)]
 pub use context::{hydrate_context, ContextItem, HydratedContext};
 #[allow(unused_imports)]
-pub use openai_provider::run_openai_chat_agent;
+pub use provider_agent::{chat_provider_for_model, run_provider_chat_agent};
 #[allow(unused_imports)]
 pub use tools::{execute_chat_tool, get_chat_tools, ChatToolResult};
@@ -0,0 +1,427 @@
+//! Provider-generic Chat Agent
+//!
+//! Implements the ReAct agent loop on top of `ai::provider::LlmProvider`,
+//! used for GPT and Grok models and whenever a non-Anthropic backend (such as
+//! a local OpenAI-compatible server) is selected in settings. Claude models on
+//! the Anthropic backend keep using `agent::run_chat_agent`, which supports
+//! extended thinking.
+
+use crate::ai::chat::context::{hydrate_context, ContextItem, HydratedContext};
+use crate::ai::chat::tools::{execute_chat_tool, get_chat_tools, ChatToolResult};
+use crate::ai::chat::tools_staging::{is_staging_tool, STAGED_CHANGED_EVENT};
+use crate::commands::vfs::VFSState;
+use crate::ai::provider::{
+    self, ChatMessage, ChatRequest, ContentPart, LlmProvider, ProviderKind, StopReason, StreamEvent,
+};
+use crate::ai::tools::ToolDefinition;
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::time::sleep;
+use tracing::{debug, info, warn};
+
+use super::agent::{ChatAgentResult, ConversationMessage, TokenUsage};
+
+/// Helper macro to emit events with proper error logging
+macro_rules! emit_logged {
+    ($app:expr, $event:expr, $payload:expr) => {
+        if let Err(e) = $app.emit($event, $payload) {
+            warn!(event = $event, error = %e, "Failed to emit event");
+        }
+    };
+}
+
+/// Maximum ReAct loop iterations
+const MAX_ITERATIONS: usize = 8;
+
+/// Delay between API requests (rate limiting)
+const REQUEST_DELAY_MS: u64 = 500;
+
+/// Maximum tokens per response
+const MAX_TOKENS: u32 = 16000;
+
+/// Maximum accumulated response size
+const MAX_FINAL_RESPONSE_SIZE: usize = 2_000_000;
+
+/// Pick the backend for a chat model.
+///
+/// Returns `None` when the request should go to the Anthropic agent: a Claude
+/// model while Anthropic is the selected provider.
+pub fn chat_provider_for_model(model: &str) -> Result<Option<Arc<dyn LlmProvider>>, String> {
+    if model.starts_with("gpt-") {
+        return provider::provider_for(ProviderKind::OpenAI)
+            .map(Some)
+            .map_err(|_| {
+                "OpenAI API key not configured. GPT models require an API key.".to_string()
+            });
+    }
+    if model.starts_with("grok-") {
+        return provider::provider_for(ProviderKind::Xai).map(Some);
+    }
+    match provider::settings::current_settings().provider {
+        ProviderKind::Anthropic => Ok(None),
+        _ => provider::active_provider().map(Some),
+    }
+}
+
+/// Run the chat agent loop with streaming against any provider
+pub async fn run_provider_chat_agent(
+    app: &AppHandle,
+    provider: &dyn LlmProvider,
+    message: &str,
+    context_items: &[ContextItem],
+    model: &str,
+    history: &[ConversationMessage],
+    abort_flag: Option<Arc<AtomicBool>>,
+) -> Result<ChatAgentResult, String> {
+    info!(
+        provider = provider.kind().as_str(),
+        model = %provider.resolve_model(model),
+        context_items = context_items.len(),
+        "Starting provider chat agent"
+    );
+
+    let mut total_usage = TokenUsage::default();
+
+    let is_aborted = || -> bool {
+        abort_flag
+            .as_ref()
+            .map(|f| f.load(Ordering::SeqCst))
+            .unwrap_or(false)
+    };
+
+    if is_aborted() {
+        info!("Chat aborted before starting");
+        emit_logged!(
+            app,
+            "chat:aborted",
+            json!({"reason": "User requested abort"})
+        );
+        return Ok(ChatAgentResult {
+            response: String::new(),
+            usage: total_usage,
+        });
+    }
+
+    // Hydrate context
+    let hydrated: HydratedContext = hydrate_context(context_items)?;
+    if !hydrated.images.is_empty() && !provider.supports_vision() {
+        return Err(format!(
+            "The {} provider is not configured for images. Remove the attached images or enable vision in settings.",
+            provider.kind().as_str()
+        ));
+    }
+
+    let system_prompt = build_provider_system_prompt(&hydrated.system_addition, history);
+    let mut messages = build_message_history(history, message, &hydrated);
+    let tools = chat_tool_definitions();
+
+    let mut final_response = String::new();
+
+    for iteration in 0..MAX_ITERATIONS {
+        if is_aborted() {
+            info!(iteration = iteration + 1, "Chat aborted");
+            emit_logged!(
+                app,
+                "chat:aborted",
+                json!({"reason": "User requested abort"})
+            );
+            return Ok(ChatAgentResult {
+                response: final_response,
+                usage: total_usage,
+            });
+        }
+
+        debug!(
+            iteration = iteration + 1,
+            max = MAX_ITERATIONS,
+            "Provider ReAct iteration"
+        );
+
+        if iteration > 0 {
+            sleep(Duration::from_millis(REQUEST_DELAY_MS)).await;
+        }
+
+        let request = ChatRequest::new(model, MAX_TOKENS)
+            .with_system(system_prompt.clone())
+            .with_messages(messages.clone())
+            .with_tools(tools.clone());
+
+        let on_event = |event: StreamEvent| match event {
+            StreamEvent::TextDelta(chunk) => {
+                emit_logged!(app, "chat:token", json!({ "chunk": chunk }));
+            }
+            StreamEvent::ThinkingDelta(chunk) => {
+                emit_logged!(
+                    app,
+                    "chat:thinking",
+                    json!({
+                        "status": "streaming",
+                        "chunk": chunk,
+                    })
+                );
+            }
+            // Block boundaries only come from the Anthropic backend
+            StreamEvent::ThinkingStart | StreamEvent::ThinkingStop => {}
+            StreamEvent::ToolUseStart { id, name } => {
+                emit_logged!(
+                    app,
+                    "chat:thought",
+                    json!({
+                        "id": id,
+                        "tool": name,
+                        "input": "",
+                        "status": "running",
+                        "timestamp": chrono::Utc::now().timestamp_millis(),
+                    })
+                );
+            }
+        };
+
+        let response = provider.stream(&request, &on_event).await?;
+
+        total_usage.input_tokens += response.usage.input_tokens;
+        total_usage.output_tokens += response.usage.output_tokens;
+        total_usage.cache_creation_input_tokens += response.usage.cache_creation_input_tokens;
+        total_usage.cache_read_input_tokens += response.usage.cache_read_input_tokens;
+
+        let text = response.text();
+        if final_response.len() + text.len() <= MAX_FINAL_RESPONSE_SIZE {
+            final_response.push_str(&text);
+        }
+
+        // Keep the assistant turn (text and tool calls) in the conversation
+        if !response.content.is_empty() {
+            messages.push(ChatMessage::assistant_parts(response.content.clone()));
+        }
+
+        if !response.has_tool_use() {
+            if response.stop_reason != StopReason::EndTurn {
+                debug!(stop_reason = ?response.stop_reason, "Provider chat stopped early");
+            }
+            info!(iterations = iteration + 1, "Provider chat completed");
+            break;
+        }
+
+        // Execute each tool and feed the results back
+        let mut tool_results = Vec::new();
+        for part in &response.content {
+            let ContentPart::ToolUse { id, name, input } = part else {
+                continue;
+            };
+
+            debug!(tool = %name, id = %id, "Executing tool");
+
+            let result = execute_chat_tool(name, input, &app.state::<VFSState>()).await;
+
+            let (result_content, is_error) = match &result {
+                ChatToolResult::Success(s) => (s.clone(), false),
+                ChatToolResult::Error(e) => (e.clone(), true),
+            };
+
+            // Emit thought event
+            let input_display = input.to_string();
+            let input_display = if input_display.len() > 200 {
+                format!("{}...", input_display.chars().take(200).collect::<String>())
+            } else {
+                input_display
+            };
+
+            if !is_error && is_staging_tool(name) {
+                emit_logged!(app, STAGED_CHANGED_EVENT, json!({ "tool": name }));
+            }
+
+            emit_logged!(
+                app,
+                "chat:thought",
+                json!({
+                    "id": id,
+                    "tool": name,
+                    "input": input_display,
+                    "output": result_content.chars().take(500).collect::<String>(),
+                    "status": if is_error { "error" } else { "complete" },
+                    "timestamp": chrono::Utc::now().timestamp_millis(),
+                })
+            );
+
+            tool_results.push(ContentPart::tool_result(
+                id.clone(),
+                result_content,
+                is_error,
+            ));
+        }
+        messages.push(ChatMessage::user_parts(tool_results));
+    }
+
+    // Emit completion
+    app.emit("chat:complete", json!({}))
+        .map_err(|e| format!("Event emit failed: {}", e))?;
+
+    info!(
+        input_tokens = total_usage.input_tokens,
+        output_tokens = total_usage.output_tokens,
+        "Provider chat agent finished"
+    );
+
+    Ok(ChatAgentResult {
+        response: final_response,
+        usage: total_usage,
+    })
+}
+
+/// Chat tools as provider-neutral definitions
+pub(super) fn chat_tool_definitions() -> Vec<ToolDefinition> {
+    get_chat_tools()
+        .iter()
+        .map(|tool| ToolDefinition {
+            name: tool["name"].as_str().unwrap_or_default().to_string(),
+            description: tool["description"].as_str().unwrap_or_default().to_string(),
+            input_schema: tool
+                .get("input_schema")
+                .cloned()
+                .unwrap_or_else(|| json!({"type": "object", "properties": {}})),
+        })
+        .collect()
+}
+
+/// Build the chat system prompt for non-Claude models
+fn build_provider_system_prompt(context_addition: &str, history: &[ConversationMessage]) -> String {
+    let previous_context = collect_previous_context(history);
+
+    format!(
+        r#"You are Sentinel Chat, an intelligent assistant for file management and organization.
+
+## Tools Available
+- **search_hybrid**: Semantic + keyword search in files
+- **read_file**: Read file contents
+- **list_directory**: List directory contents
+- **inspect_pattern**: Sample files matching a regex pattern
+- **bash**: Execute shell commands (ls, find, cat, head, wc, git status, etc.)
+- **grep**: Search file contents with regex (uses ripgrep for speed)
+- **stage_move**, **stage_rename**, **stage_create_folder**, **stage_quarantine**: Stage changes to the open folder for the user to approve
+
+## Guidelines
+1. Use `grep` for searching inside file contents
+2. Use `bash` with `ls -la` or `find` for exploring directories
+3. Use `read_file` for reading specific file contents
+4. Cite file paths when referencing content
+5. Never change files directly or suggest rm, mv, or destructive commands. To reorganize, use the `stage_*` tools: they only stage changes, which the user must review and approve in the app
+6. Be concise and helpful
+
+## Security
+- Only access files the user has shared or in allowed directories
+- Destructive commands are blocked for safety
+{}{}"#,
+        previous_context, context_addition
+    )
+}
+
+/// Collect references to files attached in previous messages
+fn collect_previous_context(history: &[ConversationMessage]) -> String {
+    let mut context_refs: Vec<(String, String)> = Vec::new();
+
+    for msg in history.iter().rev().take(10) {
+        if msg.role == "user" && !msg.context_items.is_empty() {
+            for item in &msg.context_items {
+                if !context_refs.iter().any(|(_, p)| p == &item.path) {
+                    context_refs.push((item.name.clone(), item.path.clone()));
+                }
+            }
+        }
+    }
+
+    if context_refs.is_empty() {
+        return String::new();
+    }
+
+    let mut section = String::from("\n\n## Previously Attached Files\n");
+    section.push_str("The user has attached these files in earlier messages. ");
+    section.push_str("Use the `read_file` tool if you need to access their content:\n\n");
+
+    for (name, path) in &context_refs {
+        section.push_str(&format!("- `{}` at `{}`\n", name, path));
+    }
+
+    section
+}
+
+/// Build the conversation for the provider request
+fn build_message_history(
+    history: &[ConversationMessage],
+    current_message: &str,
+    hydrated: &HydratedContext,
+) -> Vec<ChatMessage> {
+    let mut messages = Vec::new();
+
+    // Add previous messages (limit to last 20)
+    let start = history.len().saturating_sub(20);
+    for msg in &history[start..] {
+        if msg.role == "assistant" {
+            messages.push(ChatMessage::assistant(msg.content.clone()));
+        } else {
+            messages.push(ChatMessage::user(msg.content.clone()));
+        }
+    }
+
+    // Add current user message, images first
+    let mut content: Vec<ContentPart> = hydrated
+        .images
+        .iter()
+        .map(|img| ContentPart::image(img.mime_type.clone(), img.base64.clone()))
+        .collect();
+    content.push(ContentPart::text(current_message));
+    messages.push(ChatMessage::user_parts(content));
+
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::provider::Role;
+
+    #[test]
+    fn test_build_provider_system_prompt() {
+        let prompt = build_provider_system_prompt("", &[]);
+        assert!(prompt.contains("Sentinel Chat"));
+        assert!(prompt.contains("search_hybrid"));
+    }
+
+    #[test]
+    fn test_chat_tool_definitions_keep_schema() {
+        let tools = chat_tool_definitions();
+        assert!(!tools.is_empty());
+        assert!(tools.iter().all(|t| !t.name.is_empty()));
+        assert!(tools.iter().all(|t| t.input_schema.is_object()));
+    }
+
+    #[test]
+    fn test_build_message_history() {
+        let history = vec![
+            ConversationMessage {
+                role: "user".to_string(),
+                content: "hi".to_string(),
+                context_items: Vec::new(),
+            },
+            ConversationMessage {
+                role: "assistant".to_string(),
+                content: "hello".to_string(),
+                context_items: Vec::new(),
+            },
+        ];
+        let hydrated = HydratedContext {
+            system_addition: String::new(),
+            images: Vec::new(),
+        };
+
+        let messages = build_message_history(&history, "find invoices", &hydrated);
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1].role, Role::Assistant);
+        assert_eq!(
+            messages[2].content,
+            vec![ContentPart::text("find invoices")]
+        );
+    }
+}
@@ -143,4 +143,53 @@ mod tests {
             assert!(found, "Required tool '{}' should exist", name);
         }
     }
+
+    #[tokio::test]
+    async fn test_chat_agent_replays_recorded_session() {
+        use crate::ai::cassette::{fixture_path, install, Cassette};
+        use crate::ai::chat::run_chat_agent;
+        use crate::commands::vfs::create_vfs_state;
+        use crate::vfs::ShadowVFS;
+        use tauri::Manager;
+
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+
+        // The folder open in the app, which the staging tools work on
+        let vfs_state = create_vfs_state();
+        *vfs_state.write().await = Some(ShadowVFS::new(root.clone()));
+        let app = tauri::test::mock_app();
+        app.manage(vfs_state.clone());
+
+        // Two streamed turns: stage_create_folder, then the final answer
+        let cassette = Cassette::replay(fixture_path("chat_stage_folder.json")).unwrap();
+        let guard = install(cassette).await;
+
+        let result = run_chat_agent(
+            app.handle(),
+            "Make a folder for my receipts",
+            &[],
+            "claude-sonnet-4-5",
+            &[],
+            false,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(guard.cassette().remaining(), 0);
+        assert_eq!(
+            result.response,
+            "I'll set up a folder for your receipts. Staged a Receipts folder. \
+             Approve it in the app to create it."
+        );
+        assert_eq!(result.usage.input_tokens, 1830 + 2014);
+        assert_eq!(result.usage.output_tokens, 52 + 21);
+
+        // The folder is only staged; nothing was created on disk
+        let state = vfs_state.read().await;
+        let vfs = state.as_ref().unwrap();
+        assert!(vfs.staged_creates().contains(&root.join("Receipts")));
+        assert!(!root.join("Receipts").exists());
+    }
 }
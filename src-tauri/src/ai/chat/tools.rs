@@ -7,9 +7,17 @@
 //! - list_directory: List directory contents
 //! - shell: Execute safe shell commands (allowlist only)
 //! - grep: Search file contents with regex
+//! - stage_move, stage_rename, stage_create_folder, stage_quarantine: Stage
+//!   changes on the shadow VFS for the user to approve
 
+use super::tools_staging::{
+    execute_stage_create_folder, execute_stage_move, execute_stage_quarantine,
+    execute_stage_rename, get_staging_tools,
+};
 use super::tools_terminal::{execute_bash, execute_grep, execute_shell, get_terminal_tools};
-use crate::ai::grok::document_parser::{is_parseable, parse_document};
+use crate::commands::vfs::VFSState;
+use crate::ai::grok::document_parser::{is_parseable, parse_document, DocumentParser};
+use crate::archive;
 use crate::security::{safe_regex, PathValidator};
 use regex::Regex;
 use serde_json::{json, Value};
@@ -69,7 +77,7 @@ pub fn get_chat_tools() -> Vec<Value> {
     let mut tools = vec![
         json!({
             "name": "search_hybrid",
-            "description": "Search files using semantic understanding and keyword matching. Use when user asks to find files.",
+            "description": "Search files using semantic understanding and keyword matching. Use when user asks to find files. Also searches inside ZIP/TAR/7z archives; matches there are returned as paths below the archive (e.g. /docs/backup.zip/2024/invoice.pdf).",
             "input_schema": {
                 "type": "object",
                 "properties": {
@@ -97,7 +105,7 @@ pub fn get_chat_tools() -> Vec<Value> {
         }),
         json!({
             "name": "read_file",
-            "description": "Read the text content of a file. Use when you need to examine file contents.",
+            "description": "Read the text content of a file. Use when you need to examine file contents. Archives return their member listing; a path below an archive (e.g. /docs/backup.zip/notes.txt) reads that member without extracting it.",
             "input_schema": {
                 "type": "object",
                 "properties": {
@@ -160,11 +168,17 @@ pub fn get_chat_tools() -> Vec<Value> {
 
     // Add terminal tools (bash, grep)
     tools.extend(get_terminal_tools());
+    // Add staging tools (stage_move, stage_rename, stage_create_folder, stage_quarantine)
+    tools.extend(get_staging_tools());
     tools
 }
 
 /// Execute a chat tool
-pub async fn execute_chat_tool(name: &str, input: &Value) -> ChatToolResult {
+pub async fn execute_chat_tool(
+    name: &str,
+    input: &Value,
+    vfs_state: &VFSState,
+) -> ChatToolResult {
     eprintln!("[ChatTool] Executing: {} with input: {:?}", name, input);
 
     match name {
@@ -185,6 +199,22 @@ pub async fn execute_chat_tool(name: &str, input: &Value) -> ChatToolResult {
             Ok(output) => ChatToolResult::Success(output),
             Err(e) => ChatToolResult::Error(e),
         },
+        "stage_move" => match execute_stage_move(input, vfs_state).await {
+            Ok(output) => ChatToolResult::Success(output),
+            Err(e) => ChatToolResult::Error(e),
+        },
+        "stage_rename" => match execute_stage_rename(input, vfs_state).await {
+            Ok(output) => ChatToolResult::Success(output),
+            Err(e) => ChatToolResult::Error(e),
+        },
+        "stage_create_folder" => match execute_stage_create_folder(input, vfs_state).await {
+            Ok(output) => ChatToolResult::Success(output),
+            Err(e) => ChatToolResult::Error(e),
+        },
+        "stage_quarantine" => match execute_stage_quarantine(input, vfs_state).await {
+            Ok(output) => ChatToolResult::Success(output),
+            Err(e) => ChatToolResult::Error(e),
+        },
         _ => ChatToolResult::Error(format!("Unknown tool: {}", name)),
     }
 }
@@ -292,6 +322,58 @@ fn glob_to_regex(glob: &str) -> Result<Regex, String> {
     Regex::new(&regex).map_err(|e| format!("Invalid glob pattern: {}", e))
 }
 
+/// Score the members of an archive like files on disk and add matches as
+/// virtual paths below the archive
+fn search_archive(
+    path: &Path,
+    tokens: &[String],
+    expanded_patterns: &[String],
+    glob_regex: &Option<Regex>,
+    file_types: &Option<Vec<String>>,
+    results: &mut Vec<(String, usize)>,
+) {
+    let listing = match archive::list(path) {
+        Ok(listing) => listing,
+        Err(e) => {
+            tracing::debug!(path = %path.display(), error = %e, "Skipping archive in search");
+            return;
+        }
+    };
+
+    for member in listing.files() {
+        if let Some(types) = file_types.as_ref().filter(|t| !t.is_empty()) {
+            if !member.extension().is_some_and(|ext| types.contains(&ext)) {
+                continue;
+            }
+        }
+
+        let name = member.file_name();
+        let name_lower = name.to_lowercase();
+        let score = match glob_regex {
+            Some(regex) => {
+                if regex.is_match(name) {
+                    10
+                } else {
+                    0
+                }
+            }
+            None => {
+                let pattern_score = expanded_patterns
+                    .iter()
+                    .filter(|p| name_lower.contains(p.as_str()))
+                    .count();
+                score_filename(&name_lower, tokens).max(pattern_score)
+            }
+        };
+
+        if score > 0 {
+            let member_path = archive::member_path(path, &member.name);
+            let boost = if is_document_extension(&member_path) { 2 } else { 0 };
+            results.push((member_path.display().to_string(), score + boost));
+        }
+    }
+}
+
 async fn execute_search_hybrid(input: &Value) -> ChatToolResult {
     let query = match input.get("query").and_then(|q| q.as_str()) {
         Some(q) => q,
@@ -389,6 +471,18 @@ async fn execute_search_hybrid(input: &Value) -> ChatToolResult {
                     continue;
                 }
 
+                // Match archive members by name, whatever the archive's own type
+                if path.is_file() && archive::is_archive(&path) {
+                    search_archive(
+                        &path,
+                        tokens,
+                        expanded_patterns,
+                        glob_regex,
+                        file_types,
+                        results,
+                    );
+                }
+
                 // Check file type filter if specified
                 if let Some(types) = file_types {
                     if !types.is_empty() {
@@ -620,6 +714,14 @@ fn execute_read_file(input: &Value) -> ChatToolResult {
 
     // Security: Validate path using PathValidator
     let path_buf = PathBuf::from(path);
+
+    // Paths below an archive name one of its members
+    if !path_buf.exists() {
+        if let Some((archive_path, member)) = archive::split_member_path(&path_buf) {
+            return read_archive_member(&archive_path, &member, max_lines);
+        }
+    }
+
     let validated_path = match PathValidator::validate_for_read(&path_buf, None) {
         Ok(p) => p,
         Err(e) => return ChatToolResult::Error(format!("Path validation failed: {}", e)),
@@ -724,6 +826,56 @@ fn execute_read_file(input: &Value) -> ChatToolResult {
     }
 }
 
+/// Read one member of an archive in memory, parsing documents to text
+fn read_archive_member(archive_path: &Path, member: &str, max_lines: usize) -> ChatToolResult {
+    let validated_archive = match PathValidator::validate_for_read(archive_path, None) {
+        Ok(p) => p,
+        Err(e) => return ChatToolResult::Error(format!("Path validation failed: {}", e)),
+    };
+
+    let bytes = match archive::read_member(&validated_archive, member) {
+        Ok(bytes) => bytes,
+        Err(e) => return ChatToolResult::Error(format!("Failed to read archive member: {}", e)),
+    };
+
+    let extension = Path::new(member).extension().and_then(|e| e.to_str());
+    let text = if DocumentParser::parses_in_memory(extension) {
+        match DocumentParser::new().parse_bytes(member, &bytes) {
+            Ok(parsed) => parsed.text,
+            Err(e) => return ChatToolResult::Error(format!("Failed to parse document: {}", e)),
+        }
+    } else {
+        match String::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(_) => {
+                return ChatToolResult::Error(format!(
+                    "Cannot read archive member as text: {} appears to be a binary file.",
+                    member
+                ))
+            }
+        }
+    };
+
+    let lines: Vec<&str> = text.lines().take(max_lines).collect();
+    let truncated = lines.len() < text.lines().count();
+    let header = format!(
+        "[Archive member: {} in {}]\n\n",
+        member,
+        validated_archive.file_name().unwrap_or_default().to_string_lossy()
+    );
+
+    if truncated {
+        ChatToolResult::Success(format!(
+            "{}{}\n\n[Truncated at {} lines]",
+            header,
+            lines.join("\n"),
+            max_lines
+        ))
+    } else {
+        ChatToolResult::Success(format!("{}{}", header, lines.join("\n")))
+    }
+}
+
 fn execute_inspect_pattern(input: &Value) -> ChatToolResult {
     let pattern = match input.get("pattern").and_then(|p| p.as_str()) {
         Some(p) => p,
@@ -851,7 +1003,7 @@ mod tests {
     #[test]
     fn test_get_chat_tools() {
         let tools = get_chat_tools();
-        assert_eq!(tools.len(), 6); // 4 original + 2 terminal tools (shell, grep)
+        assert_eq!(tools.len(), 10); // 4 original + 2 terminal tools + 4 staging tools
 
         // Verify tool names
         let names: Vec<&str> = tools
@@ -864,5 +1016,47 @@ mod tests {
         assert!(names.contains(&"list_directory"));
         assert!(names.contains(&"shell")); // Renamed from "bash" to "shell"
         assert!(names.contains(&"grep"));
+        assert!(names.contains(&"stage_move"));
+        assert!(names.contains(&"stage_rename"));
+        assert!(names.contains(&"stage_create_folder"));
+        assert!(names.contains(&"stage_quarantine"));
+    }
+
+    #[test]
+    fn test_read_and_search_archive_members() {
+        use std::io::Write;
+
+        let temp = tempfile::TempDir::new().unwrap();
+        let zip_path = temp.path().join("backup.zip");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&zip_path).unwrap());
+        zip.start_file(
+            "letters/lease-renewal.txt",
+            zip::write::SimpleFileOptions::default(),
+        )
+        .unwrap();
+        zip.write_all(b"The lease renews in May.").unwrap();
+        zip.finish().unwrap();
+
+        let member = zip_path.join("letters").join("lease-renewal.txt");
+        match execute_read_file(&json!({ "path": member.to_string_lossy() })) {
+            ChatToolResult::Success(text) => {
+                assert!(text
+                    .starts_with("[Archive member: letters/lease-renewal.txt in backup.zip]"));
+                assert!(text.contains("renews in May"));
+            }
+            ChatToolResult::Error(e) => panic!("read failed: {}", e),
+        }
+
+        let mut results = Vec::new();
+        search_archive(
+            &zip_path,
+            &tokenize_query("lease"),
+            &[],
+            &None,
+            &None,
+            &mut results,
+        );
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, member.display().to_string());
     }
 }
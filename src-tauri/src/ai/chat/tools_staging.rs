@@ -0,0 +1,451 @@
+//! Staging tools for the chat agent (stage_move, stage_rename,
+//! stage_create_folder, stage_quarantine)
+//!
+//! The chat agent never changes files itself. These tools stage operations
+//! on the shadow VFS of the folder open in the app, where the usual
+//! collision, cycle and archive checks apply, and return a preview of
+//! everything staged so far. The agents emit [`STAGED_CHANGED_EVENT`] after
+//! each successful staging tool. Nothing touches the disk until the user
+//! approves the changes (`vfs_preview_staged` / `vfs_execute_staged`), which
+//! runs them through the WAL and records the session in history;
+//! quarantined items go to the quarantine manager.
+
+use crate::commands::vfs::VFSState;
+use crate::execution::plan_from_staged;
+use crate::security::PathValidator;
+use crate::vfs::ShadowVFS;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+/// Most operations listed in a preview
+const MAX_PREVIEW_OPERATIONS: usize = 50;
+
+/// Event telling the frontend the staged operations changed, so it can
+/// refresh its approval view with `vfs_preview_staged`
+pub const STAGED_CHANGED_EVENT: &str = "vfs-staged-changed";
+
+const NO_FOLDER_LOADED: &str =
+    "No folder is open for organizing. Ask the user to open the folder in Sentinel first.";
+
+/// Tool definitions for Anthropic API
+pub fn get_staging_tools() -> Vec<Value> {
+    vec![
+        json!({
+            "name": "stage_move",
+            "description": "Stage moving files or folders into a destination folder, which is created if missing. Name collisions get a numeric suffix. Nothing is moved until the user approves the staged changes in the app.",
+            "input_schema": {
+                "type": "object",
+                "properties": {
+                    "sources": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Paths of the files or folders to move"
+                    },
+                    "destination_folder": {
+                        "type": "string",
+                        "description": "Folder to move them into, absolute or relative to the open folder (e.g., 'Projects/Henderson')"
+                    }
+                },
+                "required": ["sources", "destination_folder"]
+            }
+        }),
+        json!({
+            "name": "stage_rename",
+            "description": "Stage renaming a file or folder in place. Nothing is renamed until the user approves the staged changes in the app.",
+            "input_schema": {
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Path of the file or folder to rename"
+                    },
+                    "new_name": {
+                        "type": "string",
+                        "description": "New name, without a directory (e.g., 'Henderson Contract 2024.pdf')"
+                    }
+                },
+                "required": ["path", "new_name"]
+            }
+        }),
+        json!({
+            "name": "stage_create_folder",
+            "description": "Stage creating a folder and any missing parent folders. Nothing is created until the user approves the staged changes in the app.",
+            "input_schema": {
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Folder to create, absolute or relative to the open folder"
+                    }
+                },
+                "required": ["path"]
+            }
+        }),
+        json!({
+            "name": "stage_quarantine",
+            "description": "Stage moving files or folders to Sentinel's quarantine, where the user can restore them from. Use this instead of deleting. Nothing is quarantined until the user approves the staged changes in the app.",
+            "input_schema": {
+                "type": "object",
+                "properties": {
+                    "paths": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Paths of the files or folders to quarantine"
+                    }
+                },
+                "required": ["paths"]
+            }
+        }),
+    ]
+}
+
+/// Stage moves of one or more paths into a folder
+pub async fn execute_stage_move(input: &Value, vfs_state: &VFSState) -> Result<String, String> {
+    let sources = string_list(input, "sources")?;
+    let destination = input
+        .get("destination_folder")
+        .and_then(|d| d.as_str())
+        .ok_or("Missing 'destination_folder' parameter")?;
+
+    stage_with(vfs_state, |vfs| {
+        let folder = resolve(vfs, destination)?;
+        let mut staged = Vec::new();
+        let mut failed = Vec::new();
+
+        for source in &sources {
+            let result = resolve(vfs, source).and_then(|path| {
+                let name = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .ok_or_else(|| format!("{} has no file name", path.display()))?;
+                match vfs.stage_placement(&path, &folder, &name) {
+                    Ok(Some(placement)) => Ok(format!(
+                        "Move {} -> {}",
+                        path.display(),
+                        placement.destination.display()
+                    )),
+                    Ok(None) => Err(format!("{} is already in that folder", path.display())),
+                    Err(e) => Err(e.to_string()),
+                }
+            });
+            match result {
+                Ok(line) => staged.push(line),
+                Err(e) => failed.push(format!("{}: {}", source, e)),
+            }
+        }
+
+        outcome(staged, failed)
+    })
+    .await
+}
+
+/// Stage renaming a path in place
+pub async fn execute_stage_rename(input: &Value, vfs_state: &VFSState) -> Result<String, String> {
+    let path = input
+        .get("path")
+        .and_then(|p| p.as_str())
+        .ok_or("Missing 'path' parameter")?;
+    let new_name = input
+        .get("new_name")
+        .and_then(|n| n.as_str())
+        .map(str::trim)
+        .ok_or("Missing 'new_name' parameter")?;
+
+    if new_name.is_empty() || new_name == "." || new_name == ".." || new_name.contains(['/', '\\'])
+    {
+        return Err(format!(
+            "Invalid name '{}': use stage_move to change folders",
+            new_name
+        ));
+    }
+
+    stage_with(vfs_state, |vfs| {
+        let source = resolve(vfs, path)?;
+        let parent = source
+            .parent()
+            .ok_or_else(|| format!("Cannot rename {}", source.display()))?;
+        let destination = parent.join(new_name);
+
+        vfs.stage_move(source.clone(), destination)
+            .map_err(|e| e.to_string())?;
+        Ok(format!(
+            "Staged: rename {} to {}",
+            source.display(),
+            new_name
+        ))
+    })
+    .await
+}
+
+/// Stage creating a folder and its missing parents
+pub async fn execute_stage_create_folder(
+    input: &Value,
+    vfs_state: &VFSState,
+) -> Result<String, String> {
+    let path = input
+        .get("path")
+        .and_then(|p| p.as_str())
+        .ok_or("Missing 'path' parameter")?;
+
+    stage_with(vfs_state, |vfs| {
+        let folder = resolve(vfs, path)?;
+        let created = vfs
+            .stage_create_folder_all(&folder)
+            .map_err(|e| e.to_string())?;
+        if created.is_empty() {
+            return Err(format!(
+                "{} already exists or is already staged",
+                folder.display()
+            ));
+        }
+        Ok(format!("Staged: create folder {}", folder.display()))
+    })
+    .await
+}
+
+/// Stage quarantining one or more paths
+pub async fn execute_stage_quarantine(
+    input: &Value,
+    vfs_state: &VFSState,
+) -> Result<String, String> {
+    let paths = string_list(input, "paths")?;
+
+    stage_with(vfs_state, |vfs| {
+        let mut staged = Vec::new();
+        let mut failed = Vec::new();
+
+        for raw in &paths {
+            let result = resolve(vfs, raw).and_then(|path| {
+                PathValidator::validate_for_delete(&path)?;
+                if vfs.staged_moves().contains_key(&path) {
+                    return Err(format!("{} is already staged for a move", path.display()));
+                }
+                if !vfs.exists(&path) {
+                    return Err(format!("Path not found: {}", path.display()));
+                }
+                vfs.stage_delete(path.clone()).map_err(|e| e.to_string())?;
+                Ok(format!("Quarantine {}", path.display()))
+            });
+            match result {
+                Ok(line) => staged.push(line),
+                Err(e) => failed.push(format!("{}: {}", raw, e)),
+            }
+        }
+
+        outcome(staged, failed)
+    })
+    .await
+}
+
+/// Everything staged on the VFS, in execution order
+pub fn staged_preview(vfs: &ShadowVFS) -> String {
+    let plan = plan_from_staged(vfs, String::new(), String::new());
+    if plan.operations.is_empty() {
+        return "No changes are staged.".to_string();
+    }
+
+    let mut lines: Vec<String> = plan
+        .operations
+        .iter()
+        .take(MAX_PREVIEW_OPERATIONS)
+        .map(|op| {
+            let path = op.path.as_deref().unwrap_or_default();
+            match op.op_type.as_str() {
+                "create_folder" => format!("- Create folder {}", path),
+                "rename" => format!(
+                    "- Rename {} to {}",
+                    path,
+                    op.new_name.as_deref().unwrap_or_default()
+                ),
+                "quarantine" => format!("- Quarantine {}", path),
+                _ => format!(
+                    "- Move {} -> {}",
+                    op.source.as_deref().unwrap_or_default(),
+                    op.destination.as_deref().unwrap_or_default()
+                ),
+            }
+        })
+        .collect();
+    if plan.operations.len() > MAX_PREVIEW_OPERATIONS {
+        lines.push(format!(
+            "- ... and {} more",
+            plan.operations.len() - MAX_PREVIEW_OPERATIONS
+        ));
+    }
+
+    format!(
+        "All staged changes in {} ({} operations):\n{}\n\nNothing has been changed on disk yet. Tell the user what you staged; they must review and approve the changes in the app before anything is applied.",
+        plan.target_folder,
+        plan.operations.len(),
+        lines.join("\n")
+    )
+}
+
+/// Whether `tool` is one of the staging tools
+pub fn is_staging_tool(tool: &str) -> bool {
+    matches!(
+        tool,
+        "stage_move" | "stage_rename" | "stage_create_folder" | "stage_quarantine"
+    )
+}
+
+/// Run a staging step on the loaded VFS and append the preview
+async fn stage_with<F>(vfs_state: &VFSState, stage: F) -> Result<String, String>
+where
+    F: FnOnce(&mut ShadowVFS) -> Result<String, String>,
+{
+    let mut state = vfs_state.write().await;
+    let vfs = state.as_mut().ok_or(NO_FOLDER_LOADED)?;
+
+    let summary = stage(vfs)?;
+    Ok(format!("{}\n\n{}", summary, staged_preview(vfs)))
+}
+
+/// Resolve a tool path against the VFS root; paths may not leave it
+fn resolve(vfs: &ShadowVFS, path: &str) -> Result<PathBuf, String> {
+    PathValidator::validate_destination(path.trim(), vfs.root(), true)
+        .map_err(|e| format!("Path validation failed: {}", e))
+}
+
+/// Summarize a batch of staged operations, failing only if none were staged
+fn outcome(staged: Vec<String>, failed: Vec<String>) -> Result<String, String> {
+    if staged.is_empty() {
+        return Err(format!("Nothing was staged:\n{}", failed.join("\n")));
+    }
+
+    let mut summary = format!("Staged {} operations:\n{}", staged.len(), staged.join("\n"));
+    if !failed.is_empty() {
+        summary.push_str(&format!("\n\nNot staged:\n{}", failed.join("\n")));
+    }
+    Ok(summary)
+}
+
+/// Read a list of strings, also accepting a single string
+fn string_list(input: &Value, key: &str) -> Result<Vec<String>, String> {
+    let values: Vec<String> = match input.get(key) {
+        Some(Value::String(s)) => vec![s.clone()],
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|v| v.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    if values.is_empty() {
+        Err(format!("Missing '{}' parameter", key))
+    } else {
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::FileNode;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+
+    fn create_test_state(root: &std::path::Path) -> VFSState {
+        let mut vfs = ShadowVFS::new(root.to_path_buf());
+        vfs.insert_linked(FileNode::directory(root.join("Inbox")));
+        vfs.insert_linked(FileNode::file(root.join("Inbox/contract.pdf")));
+        vfs.insert_linked(FileNode::file(root.join("Inbox/invoice.pdf")));
+        vfs.insert_linked(FileNode::file(root.join("notes.txt")));
+        Arc::new(RwLock::new(Some(vfs)))
+    }
+
+    #[tokio::test]
+    async fn test_stage_tools_build_preview() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let state = create_test_state(&root);
+
+        let output = execute_stage_move(
+            &json!({ "sources": ["Inbox/contract.pdf", "Inbox/missing.pdf"], "destination_folder": "Clients/Henderson" }),
+            &state,
+        )
+        .await
+        .unwrap();
+        assert!(output.contains("Staged 1 operations"));
+        assert!(output.contains("Not staged:\nInbox/missing.pdf"));
+
+        execute_stage_rename(
+            &json!({ "path": "notes.txt", "new_name": "Meeting notes.txt" }),
+            &state,
+        )
+        .await
+        .unwrap();
+        execute_stage_quarantine(&json!({ "paths": ["Inbox/invoice.pdf"] }), &state)
+            .await
+            .unwrap();
+        let output = execute_stage_create_folder(&json!({ "path": "Archive/2024" }), &state)
+            .await
+            .unwrap();
+
+        assert!(output.contains("(7 operations)"));
+        assert!(output.contains("- Create folder"));
+        assert!(output.contains("Clients/Henderson/contract.pdf"));
+        assert!(output.contains("- Rename"));
+        assert!(output.contains("- Quarantine"));
+        assert!(output.contains("Nothing has been changed on disk yet"));
+
+        // Only staged: the files are untouched
+        assert!(!root.join("Clients").exists());
+        let guard = state.read().await;
+        let vfs = guard.as_ref().unwrap();
+        assert!(vfs
+            .staged_moves()
+            .contains_key(&root.join("Inbox/contract.pdf")));
+        assert!(vfs
+            .staged_deletes()
+            .contains(&root.join("Inbox/invoice.pdf")));
+    }
+
+    #[tokio::test]
+    async fn test_stage_tools_reject_invalid_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let state = create_test_state(&root);
+
+        // Outside the open folder
+        assert!(execute_stage_move(
+            &json!({ "sources": ["notes.txt"], "destination_folder": "../elsewhere" }),
+            &state,
+        )
+        .await
+        .is_err());
+        // Names may not change folders
+        assert!(execute_stage_rename(
+            &json!({ "path": "notes.txt", "new_name": "../notes.txt" }),
+            &state
+        )
+        .await
+        .is_err());
+        // Existing folder
+        assert!(
+            execute_stage_create_folder(&json!({ "path": "Inbox" }), &state)
+                .await
+                .is_err()
+        );
+
+        // A file staged for a move cannot also be quarantined
+        execute_stage_move(
+            &json!({ "sources": "notes.txt", "destination_folder": "Inbox" }),
+            &state,
+        )
+        .await
+        .unwrap();
+        assert!(
+            execute_stage_quarantine(&json!({ "paths": ["notes.txt"] }), &state)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stage_tools_require_open_folder() {
+        let state: VFSState = Arc::new(RwLock::new(None));
+        let result = execute_stage_create_folder(&json!({ "path": "/tmp/new" }), &state).await;
+        assert_eq!(result.unwrap_err(), NO_FOLDER_LOADED);
+    }
+}
@@ -1,12 +1,8 @@
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use serde::Serialize;
 use tracing::error;
 
-use super::credentials::CredentialManager;
-
-const ANTHROPIC_API_URL: &str = "https://api.anthropic.com/v1/messages";
-const ANTHROPIC_VERSION: &str = "2023-06-01";
+use super::http_client::validation_client;
+use super::provider::{active_provider, AnthropicProvider, ChatMessage, ChatRequest, LlmProvider};
 
 /// Sanitize API errors to prevent leaking internal details to users
 /// Logs full error details internally, returns user-friendly message
@@ -60,99 +56,18 @@ impl CacheControl {
     }
 }
 
-/// Message content for API request with optional cache control
-#[derive(Serialize, Clone)]
-pub struct MessageContent {
-    #[serde(rename = "type")]
-    content_type: String,
-    text: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    cache_control: Option<CacheControl>,
-}
-
-impl MessageContent {
-    /// Create a text content block (no caching)
-    pub fn text(text: &str) -> Self {
-        Self {
-            content_type: "text".to_string(),
-            text: text.to_string(),
-            cache_control: None,
-        }
-    }
-
-    /// Create a text content block with ephemeral caching
-    /// Use this for large, repeated context like file trees
-    #[allow(dead_code)]
-    pub fn text_cached(text: &str) -> Self {
-        Self {
-            content_type: "text".to_string(),
-            text: text.to_string(),
-            cache_control: Some(CacheControl::ephemeral()),
-        }
-    }
-}
-
-/// Message in conversation
-#[derive(Serialize)]
-struct Message {
-    role: String,
-    content: Vec<MessageContent>,
-}
-
-/// API request body
-#[derive(Serialize)]
-struct ApiRequest {
-    model: String,
-    max_tokens: u32,
-    system: String,
-    messages: Vec<Message>,
-}
-
-/// Content block in API response
-#[derive(Deserialize)]
-struct ContentBlock {
-    #[serde(rename = "type")]
-    content_type: String,
-    text: Option<String>,
-}
-
-/// API response body
-#[derive(Deserialize)]
-struct ApiResponse {
-    content: Vec<ContentBlock>,
-    #[allow(dead_code)]
-    stop_reason: Option<String>,
-}
-
-/// API error response
-#[allow(dead_code)]
-#[derive(Deserialize)]
-struct ApiError {
-    error: ApiErrorDetail,
-}
-
-#[allow(dead_code)]
-#[derive(Deserialize)]
-struct ApiErrorDetail {
-    message: String,
-}
-
-/// Anthropic API client
-pub struct AnthropicClient {
-    client: Client,
-}
+/// Simple single-turn client for short completions
+///
+/// Requests go through the active LLM provider (see `ai::provider`); the
+/// model tier is mapped to the provider's configured fast/smart model.
+pub struct AnthropicClient;
 
 impl AnthropicClient {
     pub fn new() -> Self {
-        Self {
-            client: Client::builder()
-                .timeout(Duration::from_secs(120))
-                .build()
-                .expect("Failed to create HTTP client"),
-        }
+        Self
     }
 
-    /// Send a message to Claude
+    /// Send a message to the active provider
     pub async fn send_message(
         &self,
         model: ClaudeModel,
@@ -160,57 +75,19 @@ impl AnthropicClient {
         user_message: &str,
         max_tokens: u32,
     ) -> Result<String, String> {
-        let api_key = CredentialManager::get_api_key("anthropic")?;
+        let provider = active_provider()?;
 
-        let request = ApiRequest {
-            model: model.as_str().to_string(),
-            max_tokens,
-            system: system_prompt.to_string(),
-            messages: vec![Message {
-                role: "user".to_string(),
-                content: vec![MessageContent::text(user_message)],
-            }],
-        };
+        let request = ChatRequest::new(model.as_str(), max_tokens)
+            .with_system(system_prompt)
+            .with_messages(vec![ChatMessage::user(user_message)]);
 
-        let response = self
-            .client
-            .post(ANTHROPIC_API_URL)
-            .header("x-api-key", &api_key)
-            .header("anthropic-version", ANTHROPIC_VERSION)
-            .header("content-type", "application/json")
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
-
-        let status = response.status();
-
-        if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_default();
+        let response = provider.complete(&request).await.map_err(|e| match e.status {
             // Log full error for debugging, return sanitized message to user
-            return Err(sanitize_api_error(status.as_u16(), &error_text));
-        }
-
-        let api_response: ApiResponse = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
-
-        // Extract text from response
-        let text = api_response
-            .content
-            .iter()
-            .filter_map(|block| {
-                if block.content_type == "text" {
-                    block.text.clone()
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<_>>()
-            .join("");
+            Some(status) => sanitize_api_error(status, &e.message),
+            None => e.message,
+        })?;
 
-        Ok(text.trim().to_string())
+        Ok(response.text().trim().to_string())
     }
 
     /// Generate a rename suggestion using Claude Sonnet
@@ -239,32 +116,19 @@ impl AnthropicClient {
 
     /// Validate API key by making a minimal request
     pub async fn validate_api_key(api_key: &str) -> Result<bool, String> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30)) // Shorter timeout for validation
-            .build()
-            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
-
-        let request = ApiRequest {
-            model: ClaudeModel::Haiku.as_str().to_string(),
-            max_tokens: 10,
-            system: "Say 'ok'".to_string(),
-            messages: vec![Message {
-                role: "user".to_string(),
-                content: vec![MessageContent::text("test")],
-            }],
-        };
-
-        let response = client
-            .post(ANTHROPIC_API_URL)
-            .header("x-api-key", api_key)
-            .header("anthropic-version", ANTHROPIC_VERSION)
-            .header("content-type", "application/json")
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
-
-        Ok(response.status().is_success())
+        // Shorter timeout for validation
+        let provider = AnthropicProvider::new(api_key).with_client(validation_client());
+
+        let request = ChatRequest::new(ClaudeModel::Haiku.as_str(), 10)
+            .with_system("Say 'ok'")
+            .with_messages(vec![ChatMessage::user("test")]);
+
+        match provider.complete(&request).await {
+            Ok(_) => Ok(true),
+            // The API answered, so the key was checked and rejected
+            Err(e) if e.status.is_some() => Ok(false),
+            Err(e) => Err(format!("Request failed: {}", e)),
+        }
     }
 }
 
@@ -4,10 +4,8 @@
 //! Uses content hash (SHA-256) as key so analyses survive file moves.
 
 use super::types::{AnalysisMethod, DocumentAnalysis, DocumentType};
-use sha2::{Digest, Sha256};
-use std::fs::File;
-use std::io::Read;
-use std::path::Path;
+use crate::services::hashing::HashService;
+use std::path::{Path, PathBuf};
 
 /// SQLite-backed content cache
 pub struct ContentCache {
@@ -15,6 +13,14 @@ pub struct ContentCache {
 }
 
 impl ContentCache {
+    /// Cache directory shared by the app, the CLI and the rule engine.
+    ///
+    /// Same as Tauri's `app_cache_dir()` for the bundle identifier,
+    /// joined with `grok_cache`, so it resolves without an `AppHandle`.
+    pub fn default_dir() -> Option<PathBuf> {
+        dirs::cache_dir().map(|p| p.join("com.sentinel.filemanager").join("grok_cache"))
+    }
+
     /// Open or create the cache database
     pub fn open(cache_dir: &Path) -> Result<Self, String> {
         std::fs::create_dir_all(cache_dir)
@@ -73,23 +79,7 @@ impl ContentCache {
 
     /// Compute SHA-256 hash of file content
     pub fn hash_file(path: &Path) -> Result<String, String> {
-        let mut file = File::open(path)
-            .map_err(|e| format!("Failed to open file for hashing: {}", e))?;
-
-        let mut hasher = Sha256::new();
-        let mut buffer = [0u8; 8192];
-
-        loop {
-            let bytes_read = file
-                .read(&mut buffer)
-                .map_err(|e| format!("Failed to read file: {}", e))?;
-            if bytes_read == 0 {
-                break;
-            }
-            hasher.update(&buffer[..bytes_read]);
-        }
-
-        Ok(format!("{:x}", hasher.finalize()))
+        HashService::global().sha256(path)
     }
 
     /// Check if a file is already analyzed (by content hash)
@@ -4,20 +4,65 @@
 //! - Vision API for document image analysis
 //! - Rate limiting and retry logic
 //! - Token usage tracking
+//!
+//! Requests go through `ai::provider`. When a local provider with vision is
+//! selected in settings, document images are analyzed by the local model
+//! instead of Grok.
 
 use super::types::*;
 use super::utils::extract_json_object;
+use crate::ai::provider::settings::current_settings;
+use crate::ai::provider::{
+    self, complete_with_retry, ChatMessage, ChatRequest, ContentPart, LlmProvider,
+    OpenAICompatProvider, ProviderKind,
+};
 use base64::Engine;
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::{Mutex, Semaphore};
 
+/// Provider for Grok text requests.
+///
+/// Uses the local model when one is selected in settings, otherwise xAI with
+/// `api_key` (or the configured xAI key when `None`).
+pub(crate) fn grok_text_provider(api_key: Option<&str>) -> Result<Arc<dyn LlmProvider>, String> {
+    if current_settings().provider == ProviderKind::Local {
+        return provider::active_provider();
+    }
+    match api_key {
+        Some(key) => Ok(Arc::new(OpenAICompatProvider::xai(key))),
+        None => provider::provider_for(ProviderKind::Xai),
+    }
+}
+
+/// Send a single-turn text prompt and return the response text
+pub(crate) async fn grok_complete_text(
+    provider: &dyn LlmProvider,
+    model: &str,
+    prompt: String,
+    max_tokens: u32,
+    temperature: f32,
+) -> Result<String, String> {
+    let request = ChatRequest::new(model, max_tokens)
+        .with_temperature(temperature)
+        .with_messages(vec![ChatMessage::user(prompt)]);
+    let response = provider
+        .complete(&request)
+        .await
+        .map_err(|e| format!("Grok API error: {}", e))?;
+
+    let content = response.text();
+    if content.is_empty() {
+        return Err("No response from Grok".to_string());
+    }
+    Ok(content)
+}
+
 /// Grok API client with rate limiting
 pub struct GrokClient {
-    client: Client,
+    provider: Arc<dyn LlmProvider>,
     config: GrokConfig,
     rate_limiter: Arc<RateLimiter>,
     tokens_used: AtomicU32,
@@ -26,10 +71,20 @@ pub struct GrokClient {
 impl GrokClient {
     /// Create a new Grok client
     pub fn new(config: GrokConfig) -> Result<Self, String> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(120))
-            .build()
-            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+        let settings = current_settings();
+        let provider: Arc<dyn LlmProvider> =
+            if settings.provider == ProviderKind::Local && settings.vision {
+                provider::active_provider()?
+            } else {
+                Arc::new(OpenAICompatProvider::new(
+                    ProviderKind::Xai,
+                    format!("{}/v1", config.base_url.trim_end_matches('/')),
+                    Some(config.api_key.clone()),
+                    config.model.clone(),
+                    config.model.clone(),
+                    true,
+                ))
+            };
 
         let rate_limiter = Arc::new(RateLimiter::new(
             config.max_concurrent_requests,
@@ -37,7 +92,7 @@ impl GrokClient {
         ));
 
         Ok(Self {
-            client,
+            provider,
             config,
             rate_limiter,
             tokens_used: AtomicU32::new(0),
@@ -57,7 +112,6 @@ impl GrokClient {
 
         // Detect image format from magic bytes
         let mime_type = detect_image_mime(image_data);
-        let data_url = format!("data:{};base64,{}", mime_type, base64_image);
 
         let context_text = context.unwrap_or("");
         let prompt = format!(
@@ -106,80 +160,36 @@ FOCUS ON: Company/client names, project names, people names, specific dates, dol
             if context_text.is_empty() { String::new() } else { format!("Context: {}", context_text) }
         );
 
-        let request = GrokChatRequest {
-            model: self.config.model.clone(),
-            messages: vec![GrokMessage {
-                role: "user".to_string(),
-                content: vec![
-                    ContentPart::Text { text: prompt },
-                    ContentPart::ImageUrl {
-                        image_url: ImageUrlContent {
-                            url: data_url,
-                            detail: "low".to_string(), // Cost optimization
-                        },
-                    },
-                ],
-            }],
-            max_tokens: 500,
-            temperature: 0.1,
-        };
+        let request = ChatRequest::new(self.config.model.clone(), 500)
+            .with_temperature(0.1)
+            .with_messages(vec![ChatMessage::user_parts(vec![
+                ContentPart::text(prompt),
+                ContentPart::image(mime_type, base64_image),
+            ])]);
 
-        let response = self.send_request(&request).await?;
+        // Send request with retry logic
+        let max_retries = 3;
+        let response = complete_with_retry(
+            self.provider.as_ref(),
+            &request,
+            max_retries,
+            Duration::from_secs(2),
+            |retry, _| tracing::warn!("Rate limited, retry {}/{}", retry, max_retries),
+        )
+        .await
+        .map_err(|e| format!("API error: {}", e))?;
 
         // Track token usage
-        self.tokens_used.fetch_add(response.usage.total_tokens, Ordering::Relaxed);
+        let total_tokens = response.usage.input_tokens + response.usage.output_tokens;
+        self.tokens_used.fetch_add(total_tokens as u32, Ordering::Relaxed);
 
         // Parse the response
-        let content = response.choices.first()
-            .ok_or("No response from Grok")?
-            .message.content.as_str();
-
-        self.parse_analysis_response(content, filename)
-    }
-
-    /// Send request with retry logic
-    async fn send_request(&self, request: &GrokChatRequest) -> Result<GrokChatResponse, String> {
-        let mut retry_delay = Duration::from_secs(2);
-        let max_retries = 3;
-
-        for retry in 0..=max_retries {
-            if retry > 0 {
-                tokio::time::sleep(retry_delay).await;
-                retry_delay *= 2;
-            }
-
-            let resp = self.client
-                .post(format!("{}/v1/chat/completions", self.config.base_url))
-                .header("Authorization", format!("Bearer {}", self.config.api_key))
-                .header("Content-Type", "application/json")
-                .json(request)
-                .send()
-                .await;
-
-            match resp {
-                Ok(r) if r.status() == 429 => {
-                    tracing::warn!("Rate limited, retry {}/{}", retry + 1, max_retries);
-                    continue;
-                }
-                Ok(r) if r.status().is_success() => {
-                    return r.json().await
-                        .map_err(|e| format!("Failed to parse response: {}", e));
-                }
-                Ok(r) => {
-                    let status = r.status();
-                    let text = r.text().await.unwrap_or_default();
-                    return Err(format!("API error ({}): {}", status, text));
-                }
-                Err(e) => {
-                    if retry == max_retries {
-                        return Err(format!("Request failed after retries: {}", e));
-                    }
-                    continue;
-                }
-            }
+        let content = response.text();
+        if content.is_empty() {
+            return Err("No response from Grok".to_string());
         }
 
-        Err("Max retries exceeded".to_string())
+        self.parse_analysis_response(&content, filename)
     }
 
     /// Parse analysis response from Grok
@@ -249,58 +259,6 @@ impl RateLimiter {
     }
 }
 
-// API request/response types
-
-#[derive(Serialize)]
-struct GrokChatRequest {
-    model: String,
-    messages: Vec<GrokMessage>,
-    max_tokens: u32,
-    temperature: f32,
-}
-
-#[derive(Serialize)]
-struct GrokMessage {
-    role: String,
-    content: Vec<ContentPart>,
-}
-
-#[derive(Serialize)]
-#[serde(tag = "type")]
-enum ContentPart {
-    #[serde(rename = "text")]
-    Text { text: String },
-    #[serde(rename = "image_url")]
-    ImageUrl { image_url: ImageUrlContent },
-}
-
-#[derive(Serialize)]
-struct ImageUrlContent {
-    url: String,
-    detail: String,
-}
-
-#[derive(Deserialize)]
-struct GrokChatResponse {
-    choices: Vec<Choice>,
-    usage: Usage,
-}
-
-#[derive(Deserialize)]
-struct Choice {
-    message: ResponseMessage,
-}
-
-#[derive(Deserialize)]
-struct ResponseMessage {
-    content: String,
-}
-
-#[derive(Deserialize)]
-struct Usage {
-    total_tokens: u32,
-}
-
 /// Detect image MIME type from magic bytes
 fn detect_image_mime(data: &[u8]) -> &'static str {
     if data.starts_with(&[0x89, 0x50, 0x4E, 0x47]) {
@@ -1,18 +1,29 @@
 //! Document Parser Module
 //!
 //! Pure Rust text extraction from documents - no external dependencies required.
-//! Works out of the box without Tesseract, pdfium, or any other system libraries.
+//! Works out of the box without Tesseract, pdfium, or any other system libraries
+//! (local OCR of scanned documents is the optional `ocr` feature, see `ocr.rs`).
 //!
 //! ## Supported Formats
 //! - PDF: Text extraction via pdf-extract
 //! - Excel: .xlsx, .xls via calamine
 //! - Word: .docx via docx-rs
+//! - PowerPoint: .pptx (slides and speaker notes)
+//! - OpenDocument: .odt, .ods, .odp
+//! - E-books: .epub (chapters in reading order)
+//! - Rich Text: .rtf
+//! - Email: .eml, .mbox, .msg (headers and body)
+//! - Archives: .zip, .tar, .tar.gz, .7z (member listing plus the text of
+//!   members that parse in memory; nothing is extracted to disk)
 //! - Text: .txt, .md, .csv, .json, .xml, .html (direct read)
 //!
 //! ## Strategy
 //! 1. Try text extraction first (fast, pure Rust)
-//! 2. For scanned/image PDFs, fall back to Vision API
+//! 2. For scanned/image PDFs, fall back to local OCR and/or the Vision API,
+//!    in the order the folder's `OcrPolicy` gives
 
+use super::formats::{self, Extracted};
+use crate::archive;
 use calamine::{open_workbook, Reader, Xlsx, Xls};
 use std::path::Path;
 
@@ -22,6 +33,9 @@ const MAX_TEXT_LENGTH: usize = 500_000; // ~500KB of text
 /// Minimum text length to consider extraction successful
 const MIN_TEXT_LENGTH: usize = 50;
 
+/// Archive members whose text is included when parsing an archive
+const MAX_ARCHIVE_DOCUMENTS: usize = 20;
+
 /// Result of document parsing
 #[derive(Debug, Clone)]
 pub struct ParsedDocument {
@@ -29,13 +43,31 @@ pub struct ParsedDocument {
     pub text: String,
     /// Document metadata (title, author, etc.)
     pub metadata: DocumentMetadata,
-    /// Whether OCR was used (always false for pure Rust - Vision API handles OCR)
+    /// Whether the text came from local OCR rather than the file itself
     #[allow(dead_code)]
     pub used_ocr: bool,
     /// Extraction method used
     pub method: ExtractionMethod,
 }
 
+impl ParsedDocument {
+    /// Wrap text recognized by local OCR
+    pub fn from_ocr(text: &str, page_count: Option<u32>) -> Self {
+        let text = DocumentParser::truncate_text(&DocumentParser::clean_text(text));
+        let word_count = text.split_whitespace().count() as u32;
+        Self {
+            text,
+            metadata: DocumentMetadata {
+                page_count,
+                word_count: Some(word_count),
+                ..Default::default()
+            },
+            used_ocr: true,
+            method: ExtractionMethod::Ocr,
+        }
+    }
+}
+
 /// Document metadata from extraction
 #[derive(Debug, Clone, Default)]
 #[allow(dead_code)]
@@ -56,7 +88,7 @@ pub struct DocumentMetadata {
 pub enum ExtractionMethod {
     /// Native text extraction (fastest)
     NativeText,
-    /// OCR (handled by Vision API, not this module)
+    /// Local OCR of page renders or images (see `ocr.rs`)
     Ocr,
     /// Simple file read (for plain text files)
     DirectRead,
@@ -82,6 +114,9 @@ impl DocumentParser {
             .map(|s| s.to_lowercase());
 
         match ext.as_deref() {
+            // Archives - before anything else so .tar.gz isn't read as "gz"
+            Some(_) if archive::is_archive(path) => self.extract_archive(path),
+
             // Plain text files - read directly
             Some(e) if Self::is_plain_text_ext(e) => self.read_plain_text(path),
 
@@ -95,6 +130,19 @@ impl DocumentParser {
             // Word documents
             Some("docx") => self.extract_docx(path),
 
+            // Presentations, OpenDocument, e-books and RTF
+            Some("pptx") => self.extract_native(path, "PPTX", formats::ooxml::extract_pptx),
+            Some("odt") | Some("ods") | Some("odp") => {
+                self.extract_native(path, "OpenDocument", formats::odf::extract)
+            }
+            Some("epub") => self.extract_native(path, "EPUB", formats::epub::extract),
+            Some("rtf") => self.extract_native(path, "RTF", formats::rtf::extract),
+
+            // Email
+            Some("eml") => self.extract_native(path, "EML", formats::email::extract_eml),
+            Some("mbox") => self.extract_native(path, "MBOX", formats::email::extract_mbox),
+            Some("msg") => self.extract_native(path, "MSG", formats::email::extract_msg),
+
             // HTML files
             Some("html") | Some("htm") => self.read_plain_text(path),
 
@@ -103,6 +151,51 @@ impl DocumentParser {
         }
     }
 
+    /// Parse a document held in memory, e.g. an archive member
+    ///
+    /// `name` is only used for the file type and for logging. Supports the
+    /// formats listed by `parses_in_memory`.
+    pub fn parse_bytes(&self, name: &str, bytes: &[u8]) -> Result<ParsedDocument, String> {
+        let ext = Path::new(name)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|s| s.to_lowercase());
+
+        match ext.as_deref() {
+            Some(e) if Self::is_plain_text_ext(e) || e == "html" || e == "htm" => {
+                let text = Self::truncate_text(&String::from_utf8_lossy(bytes));
+                let word_count = text.split_whitespace().count() as u32;
+                Ok(ParsedDocument {
+                    text,
+                    metadata: DocumentMetadata {
+                        word_count: Some(word_count),
+                        ..Default::default()
+                    },
+                    used_ocr: false,
+                    method: ExtractionMethod::DirectRead,
+                })
+            }
+            Some("pdf") => self.extract_pdf_bytes(bytes, name),
+            Some("docx") => self.extract_docx_bytes(bytes, name),
+            Some("rtf") => Self::finish_native("RTF", name, formats::rtf::extract_bytes(bytes)?),
+            Some("eml") => {
+                Self::finish_native("EML", name, formats::email::extract_eml_bytes(bytes)?)
+            }
+            _ => Err(format!("Unsupported file type for in-memory extraction: {:?}", ext)),
+        }
+    }
+
+    /// Check if `parse_bytes` supports a file type
+    pub fn parses_in_memory(ext: Option<&str>) -> bool {
+        match ext.map(|e| e.to_lowercase()) {
+            Some(e) => {
+                Self::is_plain_text_ext(&e)
+                    || matches!(e.as_str(), "html" | "htm" | "pdf" | "docx" | "rtf" | "eml")
+            }
+            None => false,
+        }
+    }
+
     /// Check if extension is plain text
     fn is_plain_text_ext(ext: &str) -> bool {
         matches!(
@@ -146,18 +239,23 @@ impl DocumentParser {
         let bytes = std::fs::read(path)
             .map_err(|e| format!("Failed to read PDF file: {}", e))?;
 
+        self.extract_pdf_bytes(&bytes, &path.display().to_string())
+    }
+
+    /// Extract text from PDF bytes; `source` names the document in logs
+    fn extract_pdf_bytes(&self, bytes: &[u8], source: &str) -> Result<ParsedDocument, String> {
         tracing::debug!("[DocumentParser] PDF file size: {} bytes", bytes.len());
 
         // Use catch_unwind to handle panics from malformed PDFs
         // The pdf_extract crate (and its cff-parser dependency) can panic on certain fonts/glyphs
         let text = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-            pdf_extract::extract_text_from_mem(&bytes)
+            pdf_extract::extract_text_from_mem(bytes)
         })) {
             Ok(Ok(t)) => t,
             Ok(Err(e)) => {
                 tracing::warn!(
                     "[DocumentParser] PDF extraction FAILED for {}: {}",
-                    path.display(),
+                    source,
                     e
                 );
                 return Err(format!("PDF extraction failed: {}", e));
@@ -165,7 +263,7 @@ impl DocumentParser {
             Err(_panic) => {
                 tracing::error!(
                     "[DocumentParser] PDF extraction PANICKED for {} - likely malformed font/glyph",
-                    path.display()
+                    source
                 );
                 return Err("PDF extraction panicked - likely contains malformed fonts".to_string());
             }
@@ -178,7 +276,7 @@ impl DocumentParser {
             "[DocumentParser] PDF raw extraction: {} chars -> {} chars after cleaning from {}",
             raw_len,
             text.len(),
-            path.file_name().unwrap_or_default().to_string_lossy()
+            source
         );
 
         // Show first 200 chars for debugging
@@ -192,7 +290,7 @@ impl DocumentParser {
                 "[DocumentParser] PDF text too short ({} chars < {}) - likely scanned/image: {}",
                 text.len(),
                 MIN_TEXT_LENGTH,
-                path.display()
+                source
             );
             return Err(format!(
                 "PDF text too short ({} chars) - likely scanned/image-based",
@@ -211,7 +309,7 @@ impl DocumentParser {
             text.len(),
             word_count,
             page_count,
-            path.file_name().unwrap_or_default().to_string_lossy()
+            source
         );
 
         Ok(ParsedDocument {
@@ -280,7 +378,7 @@ impl DocumentParser {
             metadata: DocumentMetadata {
                 word_count: Some(word_count),
                 page_count: Some(sheet_names.len() as u32),
-                ..Default::default()
+                ..Self::office_properties(path)
             },
             used_ocr: false,
             method: ExtractionMethod::NativeText,
@@ -354,7 +452,19 @@ impl DocumentParser {
         let bytes = std::fs::read(path)
             .map_err(|e| format!("Failed to read DOCX file: {}", e))?;
 
-        let doc = docx_rs::read_docx(&bytes)
+        let parsed = self.extract_docx_bytes(&bytes, &path.display().to_string())?;
+        Ok(ParsedDocument {
+            metadata: DocumentMetadata {
+                word_count: parsed.metadata.word_count,
+                ..Self::office_properties(path)
+            },
+            ..parsed
+        })
+    }
+
+    /// Extract text from DOCX bytes; `source` names the document in logs
+    fn extract_docx_bytes(&self, bytes: &[u8], source: &str) -> Result<ParsedDocument, String> {
+        let doc = docx_rs::read_docx(bytes)
             .map_err(|e| format!("Failed to parse DOCX: {}", e))?;
 
         let mut all_text = String::new();
@@ -380,12 +490,139 @@ impl DocumentParser {
             "[DocumentParser] DOCX extracted: {} chars, {} words from {}",
             text.len(),
             word_count,
+            source
+        );
+
+        Ok(ParsedDocument {
+            text,
+            metadata: DocumentMetadata {
+                word_count: Some(word_count),
+                ..Default::default()
+            },
+            used_ocr: false,
+            method: ExtractionMethod::NativeText,
+        })
+    }
+
+    /// Title, author, subject and creation date of a DOCX or XLSX file
+    ///
+    /// Unreadable properties only cost the metadata, never the text.
+    fn office_properties(path: &Path) -> DocumentMetadata {
+        formats::ooxml::read_core_properties(path).unwrap_or_else(|e| {
+            tracing::debug!(
+                "[DocumentParser] No core properties for {}: {}",
+                path.display(),
+                e
+            );
+            DocumentMetadata::default()
+        })
+    }
+
+    /// Run one of the native format extractors and finish the result like
+    /// the other formats: clean, check length, truncate, count words
+    fn extract_native(
+        &self,
+        path: &Path,
+        label: &str,
+        extract: fn(&Path) -> Result<Extracted, String>,
+    ) -> Result<ParsedDocument, String> {
+        tracing::debug!("[DocumentParser] Extracting {}: {}", label, path.display());
+
+        Self::finish_native(label, &path.display().to_string(), extract(path)?)
+    }
+
+    /// Clean, check, truncate and count the words of extracted text
+    fn finish_native(
+        label: &str,
+        source: &str,
+        extracted: Extracted,
+    ) -> Result<ParsedDocument, String> {
+        let Extracted { text, metadata } = extracted;
+        let text = Self::clean_text(&text);
+
+        if text.len() < MIN_TEXT_LENGTH {
+            return Err(format!("{} content too short ({} chars)", label, text.len()));
+        }
+
+        let text = Self::truncate_text(&text);
+        let word_count = text.split_whitespace().count() as u32;
+
+        tracing::info!(
+            "[DocumentParser] {} extracted: {} chars, {} words from {}",
+            label,
+            text.len(),
+            word_count,
+            source
+        );
+
+        Ok(ParsedDocument {
+            text,
+            metadata: DocumentMetadata {
+                word_count: Some(word_count),
+                ..metadata
+            },
+            used_ocr: false,
+            method: ExtractionMethod::NativeText,
+        })
+    }
+
+    /// List an archive and extract the text of its documents in memory
+    ///
+    /// The text starts with the member listing so archives are searchable
+    /// by member name, followed by a section per member document. The page
+    /// count is the number of files in the archive.
+    fn extract_archive(&self, path: &Path) -> Result<ParsedDocument, String> {
+        tracing::debug!("[DocumentParser] Extracting archive: {}", path.display());
+
+        let (listing, documents) = archive::list_and_read(
+            path,
+            &|m| Self::parses_in_memory(m.extension().as_deref()),
+            MAX_ARCHIVE_DOCUMENTS,
+        )?;
+        let file_count = listing.files().count();
+
+        let mut text = format!(
+            "Archive with {} files ({} bytes uncompressed){}\n",
+            file_count,
+            listing.total_size(),
+            if listing.truncated { ", listing truncated" } else { "" }
+        );
+        for member in listing.files() {
+            text.push_str(&format!("{} ({} bytes)\n", member.name, member.size));
+        }
+
+        for (member, bytes) in documents {
+            match self.parse_bytes(&member.name, &bytes) {
+                Ok(parsed) => {
+                    text.push_str(&format!("\n=== {} ===\n", member.name));
+                    text.push_str(&parsed.text);
+                    text.push('\n');
+                }
+                Err(e) => tracing::debug!(
+                    "[DocumentParser] Skipping archive member {}: {}",
+                    member.name,
+                    e
+                ),
+            }
+            if text.len() > MAX_TEXT_LENGTH {
+                break;
+            }
+        }
+
+        let text = Self::truncate_text(&text);
+        let word_count = text.split_whitespace().count() as u32;
+
+        tracing::info!(
+            "[DocumentParser] Archive extracted: {} files, {} chars from {}",
+            file_count,
+            text.len(),
             path.display()
         );
 
         Ok(ParsedDocument {
             text,
             metadata: DocumentMetadata {
+                page_count: Some(file_count as u32),
                 word_count: Some(word_count),
                 ..Default::default()
             },
@@ -494,7 +731,11 @@ impl DocumentParser {
                 // PDF
                 "pdf" |
                 // Office documents
-                "docx" | "xlsx" | "xls" |
+                "docx" | "xlsx" | "xls" | "pptx" | "odt" | "ods" | "odp" | "rtf" |
+                // E-books and email
+                "epub" | "eml" | "mbox" | "msg" |
+                // Archives (gz only as .tar.gz)
+                "zip" | "tar" | "tgz" | "gz" | "7z" |
                 // Text formats
                 "txt" | "md" | "html" | "htm" | "xml" | "json" | "yaml" | "yml" |
                 "csv" | "log" | "ini" | "cfg" | "conf" | "toml" | "env" |
@@ -552,6 +793,26 @@ pub fn parse_document(path: &Path) -> Result<ParsedDocument, String> {
     parser.parse(path)
 }
 
+/// Searchable text of an archive for the vector indexes: the member listing
+/// followed by the text of member documents, cut to `max_chars`
+///
+/// Returns None for other files, for unreadable archives, and for TAR/7z
+/// archives larger than `archive::MAX_STREAMED_ARCHIVE_SIZE`.
+pub fn archive_search_text(path: &Path, max_chars: usize) -> Option<String> {
+    let format = archive::ArchiveFormat::detect(path)?;
+    let size = std::fs::metadata(path).ok()?.len();
+    if !format.has_index() && size > archive::MAX_STREAMED_ARCHIVE_SIZE {
+        return None;
+    }
+    let parsed = DocumentParser::new()
+        .extract_archive(path)
+        .inspect_err(|e| {
+            tracing::debug!(path = %path.display(), error = %e, "Archive not indexed")
+        })
+        .ok()?;
+    Some(parsed.text.chars().take(max_chars).collect())
+}
+
 /// Check if a file extension is supported for parsing
 pub fn is_parseable(ext: Option<&str>) -> bool {
     DocumentParser::is_supported(ext)
@@ -584,10 +845,46 @@ mod tests {
         assert!(DocumentParser::is_supported(Some("docx")));
         assert!(DocumentParser::is_supported(Some("xlsx")));
         assert!(DocumentParser::is_supported(Some("txt")));
+        assert!(DocumentParser::is_supported(Some("pptx")));
+        assert!(DocumentParser::is_supported(Some("ODT")));
+        assert!(DocumentParser::is_supported(Some("epub")));
+        assert!(DocumentParser::is_supported(Some("rtf")));
+        assert!(DocumentParser::is_supported(Some("eml")));
+        assert!(DocumentParser::is_supported(Some("msg")));
+        assert!(DocumentParser::is_supported(Some("zip")));
+        assert!(DocumentParser::is_supported(Some("7z")));
         assert!(!DocumentParser::is_supported(Some("exe")));
         assert!(!DocumentParser::is_supported(Some("mp4")));
     }
 
+    #[test]
+    fn test_archive_parsing() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("records.zip");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+        for (name, content) in [
+            ("notes/todo.md", "Renew the passport before the trip in June."),
+            ("photo.jpg", "not really a jpeg"),
+        ] {
+            zip.start_file(name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let parsed = DocumentParser::new().parse(&path).unwrap();
+        assert!(parsed.text.contains("photo.jpg (17 bytes)"));
+        assert!(parsed.text.contains("=== notes/todo.md ===\nRenew the passport"));
+        assert!(!parsed.text.contains("not really"));
+        assert_eq!(parsed.metadata.page_count, Some(2));
+
+        // Vector indexes get the same text, cut short
+        let search_text = archive_search_text(&path, 120).unwrap();
+        assert!(search_text.contains("notes/todo.md"));
+        assert!(search_text.chars().count() <= 120);
+        assert!(archive_search_text(&temp.path().join("notes.txt"), 120).is_none());
+    }
+
     #[test]
     fn test_truncate_text() {
         let long_text = "a ".repeat(300_000);
@@ -5,12 +5,15 @@
 //! 1. Receives a batch of files
 //! 2. Checks cache for existing analyses
 //! 3. **NEW**: Tries text extraction first (PDF, Office docs)
-//! 4. Falls back to Grok Vision for scanned/image docs
+//! 4. Falls back to local OCR and/or Grok Vision for scanned/image docs,
+//!    in the order the folder's `OcrPolicy` gives. Under `LocalOnly` the
+//!    OCR text is classified locally and never sent to an API
 //! 5. Returns summaries in format: "filename | summary | suggested_name"
 
 use super::cache::ContentCache;
-use super::client::GrokClient;
+use super::client::{grok_complete_text, grok_text_provider, GrokClient};
 use super::document_parser::{DocumentParser, ExtractionMethod, ParsedDocument};
+use super::ocr::{self, OcrBackend, OcrEngine, OcrPolicy};
 use super::pdf_renderer::PdfRenderer;
 use super::types::*;
 use super::vision;
@@ -278,6 +281,7 @@ impl ExploreAgent {
                         &filename,
                         &text_preview,
                         ext_str.as_deref(),
+                        AnalysisMethod::TextExtraction,
                         client,
                         cache,
                         batch_id,
@@ -287,23 +291,54 @@ impl ExploreAgent {
             }
         }
 
-        // 3. Fall back to Vision API for scanned/image documents
+        // 3. Fall back to local OCR and/or the Vision API for scanned/image
+        //    documents, in the order the folder's policy gives
         if vision::is_analyzable_extension(ext) {
-            tracing::debug!(
-                "[ExploreAgent {}] Falling back to Vision API for {}",
-                batch_id,
-                filename
-            );
-            return Self::analyze_with_vision_static(
-                path,
-                &filename,
-                ext,
-                client,
-                cache,
-                pdf_renderer,
-                batch_id,
-            )
-            .await;
+            let policy = ocr::policy_for(path);
+            let mut errors = Vec::new();
+
+            for backend in policy.backends() {
+                tracing::debug!(
+                    "[ExploreAgent {}] Trying {:?} for {} ({:?})",
+                    batch_id,
+                    backend,
+                    filename,
+                    policy
+                );
+                let result = match backend {
+                    OcrBackend::Local => {
+                        Self::analyze_with_local_ocr_static(
+                            path,
+                            &filename,
+                            policy == OcrPolicy::LocalOnly,
+                            client,
+                            cache,
+                            pdf_renderer,
+                            batch_id,
+                        )
+                        .await
+                    }
+                    OcrBackend::Vision => {
+                        Self::analyze_with_vision_static(
+                            path,
+                            &filename,
+                            ext,
+                            client,
+                            cache,
+                            pdf_renderer,
+                            batch_id,
+                        )
+                        .await
+                    }
+                };
+                match result {
+                    Ok(analysis) => return Ok(analysis),
+                    Err(e) => errors.push(e),
+                }
+            }
+
+            // Keep every backend's error so auth failures are still recognized
+            return Err(errors.join("; "));
         }
 
         Err(format!(
@@ -313,18 +348,19 @@ impl ExploreAgent {
     }
 
     /// Analyze text content using Grok text API (static version)
+    ///
+    /// `method` records where the text came from.
+    #[allow(clippy::too_many_arguments)]
     async fn analyze_text_content_static(
         path: &Path,
         filename: &str,
         text: &str,
         _ext: Option<&str>,
+        method: AnalysisMethod,
         _client: &Arc<GrokClient>,
         cache: &Arc<ContentCache>,
         batch_id: usize,
     ) -> Result<(DocumentAnalysis, u32), String> {
-        use reqwest::Client;
-        use serde_json::json;
-
         let prompt = format!(
             r#"Analyze this document and extract SPECIFIC information for file organization.
 
@@ -343,52 +379,13 @@ Respond with ONLY this JSON format:
             filename, text
         );
 
-        let api_key = std::env::var("XAI_API_KEY")
-            .or_else(|_| std::env::var("GROK_API_KEY"))
-            .or_else(|_| std::env::var("VITE_XAI_API_KEY"))
-            .map_err(|_| "No Grok API key found")?;
-
-        let http_client = Client::new();
-        let response = http_client
-            .post("https://api.x.ai/v1/chat/completions")
-            .header("Authorization", format!("Bearer {}", api_key))
-            .header("Content-Type", "application/json")
-            .json(&json!({
-                "model": "grok-4-1-fast",
-                "messages": [{"role": "user", "content": prompt}],
-                "max_tokens": 500,
-                "temperature": 0.1
-            }))
-            .send()
-            .await
-            .map_err(|e| format!("API request failed: {}", e))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(format!("Grok API error ({}): {}", status, error_text));
-        }
-
-        #[derive(serde::Deserialize)]
-        struct GrokResponse { choices: Vec<Choice> }
-        #[derive(serde::Deserialize)]
-        struct Choice { message: Message }
-        #[derive(serde::Deserialize)]
-        struct Message { content: String }
-
-        let grok_resp: GrokResponse = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse Grok response: {}", e))?;
-
-        let content = grok_resp
-            .choices
-            .first()
-            .map(|c| c.message.content.clone())
-            .ok_or("No response from Grok")?;
+        let provider = grok_text_provider(None)?;
+        let content =
+            grok_complete_text(provider.as_ref(), "grok-4-1-fast", prompt, 500, 0.1).await?;
 
         // Parse JSON from response
-        let analysis = Self::parse_json_analysis_response(path, filename, &content)?;
+        let mut analysis = Self::parse_json_analysis_response(path, filename, &content)?;
+        analysis.method = method;
 
         // Estimate tokens: ~input/4 + output/4 + overhead
         let estimated_tokens = ((text.len() / 4) + (content.len() / 4) + 100) as u32;
@@ -463,6 +460,58 @@ Respond with ONLY this JSON format:
         })
     }
 
+    /// Analyze a scanned document or image from its local OCR text
+    ///
+    /// With `offline`, the text is classified by `local_text_analysis`
+    /// instead of the Grok text API.
+    #[allow(clippy::too_many_arguments)]
+    async fn analyze_with_local_ocr_static(
+        path: &Path,
+        filename: &str,
+        offline: bool,
+        client: &Arc<GrokClient>,
+        cache: &Arc<ContentCache>,
+        pdf_renderer: &Arc<PdfRenderer>,
+        batch_id: usize,
+    ) -> Result<(DocumentAnalysis, u32), String> {
+        let parsed = OcrEngine::from_settings()
+            .recognize_file(path, pdf_renderer)
+            .await?;
+        let text_preview: String = parsed.text.chars().take(4000).collect();
+
+        tracing::debug!(
+            "[ExploreAgent {}] Local OCR SUCCESS: {} chars from {}",
+            batch_id,
+            text_preview.len(),
+            filename
+        );
+
+        if offline {
+            let analysis = local_text_analysis(path, filename, &text_preview);
+            if let Err(e) = cache.store(path, &analysis, 0) {
+                tracing::warn!(
+                    "[ExploreAgent {}] Failed to cache analysis: {}",
+                    batch_id,
+                    e
+                );
+            }
+            return Ok((analysis, 0));
+        }
+
+        let ext = path.extension().and_then(|e| e.to_str());
+        Self::analyze_text_content_static(
+            path,
+            filename,
+            &text_preview,
+            ext,
+            AnalysisMethod::Ocr,
+            client,
+            cache,
+            batch_id,
+        )
+        .await
+    }
+
     /// Analyze using Vision API (static version)
     async fn analyze_with_vision_static(
         path: &Path,
@@ -645,9 +694,6 @@ Respond with ONLY this JSON format:
         content: &str,
         parsed: &ParsedDocument,
     ) -> Result<DocumentAnalysis, String> {
-        use reqwest::Client;
-        use serde_json::json;
-
         let prompt = format!(
             r#"Analyze this document and extract SPECIFIC information for intelligent file organization.
 
@@ -701,55 +747,9 @@ The key_entities array is CRITICAL for folder organization. Include every specif
             filename, content
         );
 
-        let api_key = std::env::var("XAI_API_KEY")
-            .or_else(|_| std::env::var("GROK_API_KEY"))
-            .or_else(|_| std::env::var("VITE_XAI_API_KEY"))
-            .map_err(|_| "No Grok API key found")?;
-
-        let client = Client::new();
-        let response = client
-            .post("https://api.x.ai/v1/chat/completions")
-            .header("Authorization", format!("Bearer {}", api_key))
-            .header("Content-Type", "application/json")
-            .json(&json!({
-                "model": "grok-4-1-fast",
-                "messages": [{"role": "user", "content": prompt}],
-                "max_tokens": 1000,
-                "temperature": 0.1
-            }))
-            .send()
-            .await
-            .map_err(|e| format!("API request failed: {}", e))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let text = response.text().await.unwrap_or_default();
-            return Err(format!("Grok API error ({}): {}", status, text));
-        }
-
-        #[derive(serde::Deserialize)]
-        struct GrokResponse {
-            choices: Vec<Choice>,
-        }
-        #[derive(serde::Deserialize)]
-        struct Choice {
-            message: Message,
-        }
-        #[derive(serde::Deserialize)]
-        struct Message {
-            content: String,
-        }
-
-        let grok_resp: GrokResponse = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse Grok response: {}", e))?;
-
-        let content = grok_resp
-            .choices
-            .first()
-            .map(|c| c.message.content.clone())
-            .ok_or("No response from Grok")?;
+        let provider = grok_text_provider(None)?;
+        let content =
+            grok_complete_text(provider.as_ref(), "grok-4-1-fast", prompt, 1000, 0.1).await?;
 
         // Parse the JSON response
         #[derive(serde::Deserialize)]
@@ -867,10 +867,65 @@ pub fn create_batches(files: Vec<std::path::PathBuf>, batch_size: usize) -> Vec<
         .collect()
 }
 
+/// Keywords that identify a document type in recognized text, checked in order
+const DOCUMENT_KEYWORDS: &[(&[&str], DocumentType)] = &[
+    (&["invoice", "rechnung", "facture"], DocumentType::Invoice),
+    (&["receipt", "quittung", "reçu"], DocumentType::Receipt),
+    (&["statement of account", "account statement", "kontoauszug"], DocumentType::Statement),
+    (&["agreement", "contract", "vertrag"], DocumentType::Contract),
+    (&["certificate", "zertifikat", "urkunde"], DocumentType::Certificate),
+    (&["curriculum vitae", "resume", "lebenslauf"], DocumentType::Resume),
+    (&["dear ", "sehr geehrte", "sincerely"], DocumentType::Letter),
+];
+
+/// Characters of recognized text kept as the summary of a local analysis
+const LOCAL_SUMMARY_CHARS: usize = 300;
+
+/// Analyze recognized text without an API: the document type comes from
+/// keywords and the summary is the start of the text
+fn local_text_analysis(path: &Path, filename: &str, text: &str) -> DocumentAnalysis {
+    let lower = text.to_lowercase();
+    let document_type = DOCUMENT_KEYWORDS
+        .iter()
+        .find(|(keywords, _)| keywords.iter().any(|k| lower.contains(k)))
+        .map(|(_, document_type)| document_type.clone())
+        .unwrap_or(DocumentType::Unknown);
+    let summary: String = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(LOCAL_SUMMARY_CHARS)
+        .collect();
+
+    DocumentAnalysis {
+        file_path: path.to_string_lossy().to_string(),
+        file_name: filename.to_string(),
+        content_summary: summary,
+        document_type,
+        key_entities: Vec::new(),
+        confidence: 0.4,
+        suggested_name: None,
+        method: AnalysisMethod::Ocr,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_local_text_analysis() {
+        let text = "ACME Corp\n\nINVOICE   No. 42\nTotal due: 120 EUR";
+        let analysis = local_text_analysis(Path::new("/scans/a.png"), "a.png", text);
+        assert_eq!(analysis.document_type, DocumentType::Invoice);
+        assert_eq!(analysis.content_summary, "ACME Corp INVOICE No. 42 Total due: 120 EUR");
+        assert_eq!(analysis.method, AnalysisMethod::Ocr);
+
+        let other = local_text_analysis(Path::new("/scans/b.png"), "b.png", "Holiday photos");
+        assert_eq!(other.document_type, DocumentType::Unknown);
+    }
+
     #[test]
     fn test_create_batches() {
         let files: Vec<_> = (0..25)
@@ -0,0 +1,303 @@
+//! Email: single RFC 822 messages (EML), mailboxes (MBOX) and Outlook
+//! messages (MSG)
+
+use super::{non_empty, Extracted};
+use crate::ai::grok::document_parser::DocumentMetadata;
+use mail_parser::mailbox::mbox::MessageIterator;
+use mail_parser::{Address, Message, MessageParser, MimeHeaders};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// Stop collecting mailbox text past this size; messages are still counted
+const MAX_MBOX_TEXT: usize = 1024 * 1024;
+
+/// Seconds between the FILETIME epoch (1601) and the Unix epoch
+const FILETIME_UNIX_OFFSET: i64 = 11_644_473_600;
+
+/// Extract the headers and body of an EML file
+pub fn extract_eml(path: &Path) -> Result<Extracted, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read email: {}", e))?;
+    extract_eml_bytes(&bytes)
+}
+
+/// Extract an EML message held in memory
+pub fn extract_eml_bytes(bytes: &[u8]) -> Result<Extracted, String> {
+    let message = MessageParser::default()
+        .parse(bytes)
+        .ok_or("Failed to parse email message")?;
+
+    Ok(Extracted {
+        text: message_text(&message),
+        metadata: DocumentMetadata {
+            title: non_empty(message.subject().map(str::to_string)),
+            author: sender(message.from()),
+            creation_date: message.date().map(|d| d.to_rfc3339()),
+            ..Default::default()
+        },
+    })
+}
+
+/// Extract every message of an MBOX file
+///
+/// The page count is the number of messages and the creation date is the
+/// date of the first one.
+pub fn extract_mbox(path: &Path) -> Result<Extracted, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open mailbox: {}", e))?;
+    let parser = MessageParser::default();
+    let mut text = String::new();
+    let mut count = 0u32;
+    let mut first_date = None;
+
+    for entry in MessageIterator::new(BufReader::new(file)) {
+        let entry = entry.map_err(|e| format!("Failed to read mailbox: {}", e))?;
+        count += 1;
+        if text.len() > MAX_MBOX_TEXT {
+            continue;
+        }
+        let Some(message) = parser.parse(entry.contents()) else {
+            continue;
+        };
+        if first_date.is_none() {
+            first_date = message.date().map(|d| d.to_rfc3339());
+        }
+        text.push_str(&format!("\n=== Message {} ===\n", count));
+        text.push_str(&message_text(&message));
+    }
+
+    Ok(Extracted {
+        text,
+        metadata: DocumentMetadata {
+            creation_date: first_date,
+            page_count: Some(count),
+            ..Default::default()
+        },
+    })
+}
+
+/// Extract an Outlook MSG (OLE compound file) message
+///
+/// Reads the subject, sender, recipients and plain-text body property
+/// streams, and the submit time from the property stream.
+pub fn extract_msg(path: &Path) -> Result<Extracted, String> {
+    let mut msg = cfb::open(path).map_err(|e| format!("Failed to open MSG: {}", e))?;
+
+    let subject = msg_string(&mut msg, 0x0037);
+    let sender_name = msg_string(&mut msg, 0x0C1A).or_else(|| msg_string(&mut msg, 0x0042));
+    let sender_email = msg_string(&mut msg, 0x0C1F);
+    let to = msg_string(&mut msg, 0x0E04);
+    let body = msg_string(&mut msg, 0x1000).unwrap_or_default();
+    let sent = msg_submit_time(&mut msg);
+
+    let from = match (&sender_name, &sender_email) {
+        (Some(name), Some(email)) => Some(format!("{} <{}>", name, email)),
+        (name, email) => name.clone().or_else(|| email.clone()),
+    };
+
+    let mut text = String::new();
+    push_header(&mut text, "From", from.as_deref());
+    push_header(&mut text, "To", to.as_deref());
+    push_header(&mut text, "Date", sent.as_deref());
+    push_header(&mut text, "Subject", subject.as_deref());
+    text.push('\n');
+    text.push_str(&body);
+
+    Ok(Extracted {
+        text,
+        metadata: DocumentMetadata {
+            title: subject,
+            author: sender_name.or(sender_email),
+            creation_date: sent,
+            ..Default::default()
+        },
+    })
+}
+
+/// Header summary, body and attachment names of a parsed message
+fn message_text(message: &Message) -> String {
+    let mut text = String::new();
+    push_header(&mut text, "From", addresses(message.from()).as_deref());
+    push_header(&mut text, "To", addresses(message.to()).as_deref());
+    push_header(
+        &mut text,
+        "Date",
+        message.date().map(|d| d.to_rfc3339()).as_deref(),
+    );
+    push_header(&mut text, "Subject", message.subject());
+
+    let attachments: Vec<&str> = message
+        .attachments()
+        .filter_map(|part| part.attachment_name())
+        .collect();
+    if !attachments.is_empty() {
+        push_header(&mut text, "Attachments", Some(&attachments.join(", ")));
+    }
+    text.push('\n');
+
+    // body_text falls back to the HTML part converted to text
+    if let Some(body) = message.body_text(0) {
+        text.push_str(&body);
+        text.push('\n');
+    }
+    text
+}
+
+fn push_header(text: &mut String, name: &str, value: Option<&str>) {
+    if let Some(value) = value.filter(|v| !v.trim().is_empty()) {
+        text.push_str(&format!("{}: {}\n", name, value.trim()));
+    }
+}
+
+/// Display name of the first sender, or its address
+fn sender(address: Option<&Address>) -> Option<String> {
+    let first = address?.first()?;
+    non_empty(first.name().or(first.address()).map(str::to_string))
+}
+
+/// "Name <address>" list of every address in a header
+fn addresses(address: Option<&Address>) -> Option<String> {
+    let list: Vec<String> = address?
+        .iter()
+        .map(|addr| match (addr.name(), addr.address()) {
+            (Some(name), Some(email)) => format!("{} <{}>", name, email),
+            (name, email) => name.or(email).unwrap_or_default().to_string(),
+        })
+        .filter(|s| !s.is_empty())
+        .collect();
+    (!list.is_empty()).then(|| list.join(", "))
+}
+
+fn read_stream(msg: &mut cfb::CompoundFile<File>, name: &str) -> Option<Vec<u8>> {
+    let mut stream = msg.open_stream(name).ok()?;
+    let mut bytes = Vec::new();
+    stream
+        .by_ref()
+        .take(super::MAX_ENTRY_BYTES)
+        .read_to_end(&mut bytes)
+        .ok()?;
+    Some(bytes)
+}
+
+/// A string property: UTF-16 (PT_UNICODE) or 8-bit (PT_STRING8) stream
+fn msg_string(msg: &mut cfb::CompoundFile<File>, property: u16) -> Option<String> {
+    if let Some(bytes) = read_stream(msg, &format!("/__substg1.0_{:04X}001F", property)) {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        return non_empty(Some(
+            String::from_utf16_lossy(&units)
+                .trim_end_matches('\0')
+                .to_string(),
+        ));
+    }
+    let bytes = read_stream(msg, &format!("/__substg1.0_{:04X}001E", property))?;
+    non_empty(Some(
+        String::from_utf8_lossy(&bytes)
+            .trim_end_matches('\0')
+            .to_string(),
+    ))
+}
+
+/// PR_CLIENT_SUBMIT_TIME from the top-level property stream
+///
+/// The stream has a 32-byte header followed by 16-byte entries: tag,
+/// flags, then the 8-byte value (a FILETIME for PT_SYSTIME).
+fn msg_submit_time(msg: &mut cfb::CompoundFile<File>) -> Option<String> {
+    const SUBMIT_TIME_TAG: u32 = 0x0039_0040;
+    let bytes = read_stream(msg, "/__properties_version1.0")?;
+    let entry = bytes.get(32..)?.chunks_exact(16).find(|entry| {
+        u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]) == SUBMIT_TIME_TAG
+    })?;
+    let filetime = u64::from_le_bytes(entry[8..16].try_into().ok()?);
+    let seconds = (filetime / 10_000_000) as i64 - FILETIME_UNIX_OFFSET;
+    chrono::DateTime::from_timestamp(seconds, 0).map(|d| d.to_rfc3339())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    const EML: &str = "From: Jo Park <jo@example.com>\r\n\
+To: team@example.com\r\n\
+Subject: Invoice 4411\r\n\
+Date: Tue, 5 Mar 2024 14:02:00 +0000\r\n\
+Content-Type: text/plain\r\n\
+\r\n\
+Please find the invoice for March attached.\r\n";
+
+    #[test]
+    fn test_eml_headers_and_body() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("invoice.eml");
+        std::fs::write(&path, EML).unwrap();
+
+        let extracted = extract_eml(&path).unwrap();
+        assert!(extracted.text.contains("From: Jo Park <jo@example.com>\n"));
+        assert!(extracted.text.contains("Subject: Invoice 4411\n"));
+        assert!(extracted.text.contains("invoice for March"));
+        assert_eq!(extracted.metadata.title.as_deref(), Some("Invoice 4411"));
+        assert_eq!(extracted.metadata.author.as_deref(), Some("Jo Park"));
+        assert!(extracted
+            .metadata
+            .creation_date
+            .as_deref()
+            .is_some_and(|d| d.starts_with("2024-03-05T14:02:00")));
+    }
+
+    #[test]
+    fn test_mbox_counts_messages() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("archive.mbox");
+        let mut mbox = String::new();
+        for _ in 0..2 {
+            mbox.push_str("From jo@example.com Tue Mar  5 14:02:00 2024\n");
+            mbox.push_str(&EML.replace("\r\n", "\n"));
+            mbox.push('\n');
+        }
+        std::fs::write(&path, mbox).unwrap();
+
+        let extracted = extract_mbox(&path).unwrap();
+        assert_eq!(extracted.metadata.page_count, Some(2));
+        assert!(extracted.text.contains("=== Message 2 ==="));
+        assert_eq!(extracted.text.matches("Invoice 4411").count(), 2);
+    }
+
+    #[test]
+    fn test_msg_property_streams() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("note.msg");
+        let mut msg = cfb::create(&path).unwrap();
+        let utf16 = |s: &str| -> Vec<u8> { s.encode_utf16().flat_map(u16::to_le_bytes).collect() };
+        for (name, data) in [
+            ("/__substg1.0_0037001F", utf16("Site visit")),
+            ("/__substg1.0_0C1A001F", utf16("Ari Chen")),
+            ("/__substg1.0_1000001E", b"Meet at the north gate.".to_vec()),
+        ] {
+            msg.create_stream(name).unwrap().write_all(&data).unwrap();
+        }
+        // 2024-01-01T00:00:00Z as a FILETIME
+        let filetime = (1_704_067_200 + FILETIME_UNIX_OFFSET) as u64 * 10_000_000;
+        let mut properties = vec![0u8; 32];
+        properties.extend_from_slice(&0x0039_0040u32.to_le_bytes());
+        properties.extend_from_slice(&0u32.to_le_bytes());
+        properties.extend_from_slice(&filetime.to_le_bytes());
+        msg.create_stream("/__properties_version1.0")
+            .unwrap()
+            .write_all(&properties)
+            .unwrap();
+        msg.flush().unwrap();
+        drop(msg);
+
+        let extracted = extract_msg(&path).unwrap();
+        assert!(extracted.text.contains("Subject: Site visit\n"));
+        assert!(extracted.text.contains("north gate"));
+        assert_eq!(extracted.metadata.author.as_deref(), Some("Ari Chen"));
+        assert_eq!(
+            extracted.metadata.creation_date.as_deref(),
+            Some("2024-01-01T00:00:00+00:00")
+        );
+    }
+}
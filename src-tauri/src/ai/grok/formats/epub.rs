@@ -0,0 +1,194 @@
+//! EPUB e-books
+
+use super::xml::{self, Layout};
+use super::{non_empty, open_zip, zip_text, Extracted};
+use crate::ai::grok::document_parser::DocumentMetadata;
+use std::collections::HashMap;
+use std::path::Path;
+
+const XHTML_LAYOUT: Layout = Layout {
+    blocks: &[
+        "p",
+        "div",
+        "h1",
+        "h2",
+        "h3",
+        "h4",
+        "h5",
+        "h6",
+        "li",
+        "tr",
+        "blockquote",
+        "pre",
+        "section",
+        "article",
+        "dt",
+        "dd",
+        "figcaption",
+    ],
+    cells: &["td", "th"],
+    tabs: &[],
+    line_breaks: &["br", "hr"],
+    skip: &["head", "script", "style", "svg"],
+    text_in: &[],
+};
+
+/// Extract the chapters of an EPUB in reading order
+///
+/// The page count is the number of documents in the spine, i.e. chapters
+/// for most books.
+pub fn extract(path: &Path) -> Result<Extracted, String> {
+    let mut archive = open_zip(path)?;
+
+    let container = zip_text(&mut archive, "META-INF/container.xml")?
+        .ok_or("Not an EPUB file (META-INF/container.xml missing)")?;
+    let package_path = xml::elements(&container, "rootfile")
+        .into_iter()
+        .find_map(|attrs| attrs.get("full-path").cloned())
+        .ok_or("EPUB container lists no package document")?;
+    let package = zip_text(&mut archive, &package_path)?
+        .ok_or_else(|| format!("EPUB package document {} missing", package_path))?;
+
+    // Manifest hrefs are relative to the package document
+    let base = match package_path.rfind('/') {
+        Some(i) => &package_path[..=i],
+        None => "",
+    };
+    let manifest: HashMap<String, String> = xml::elements(&package, "item")
+        .into_iter()
+        .filter_map(|mut attrs| Some((attrs.remove("id")?, attrs.remove("href")?)))
+        .collect();
+    let spine: Vec<String> = xml::elements(&package, "itemref")
+        .into_iter()
+        .filter_map(|mut attrs| manifest.get(&attrs.remove("idref")?).cloned())
+        .collect();
+
+    let mut text = String::new();
+    for href in &spine {
+        let name = resolve_href(base, href);
+        match zip_text(&mut archive, &name)? {
+            Some(chapter) => {
+                text.push_str(&xml::text(&chapter, &XHTML_LAYOUT));
+                text.push('\n');
+            }
+            None => tracing::debug!("[DocumentParser] EPUB chapter missing: {}", name),
+        }
+    }
+
+    let mut fields = xml::fields(&package, &["title", "creator", "subject", "date"]);
+    Ok(Extracted {
+        text,
+        metadata: DocumentMetadata {
+            title: non_empty(fields.remove("title")),
+            author: non_empty(fields.remove("creator")),
+            subject: non_empty(fields.remove("subject")),
+            creation_date: non_empty(fields.remove("date")),
+            page_count: Some(spine.len() as u32),
+            ..Default::default()
+        },
+    })
+}
+
+/// Archive path of a manifest href: percent-decoded, fragment dropped,
+/// `..` resolved against the package folder
+fn resolve_href(base: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or(href);
+    let href = percent_decode(href);
+    let mut parts: Vec<&str> = base.split('/').filter(|p| !p.is_empty()).collect();
+    for part in href.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            _ => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::TempDir;
+    use zip::write::SimpleFileOptions;
+
+    #[test]
+    fn test_epub_spine_order_and_metadata() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("book.epub");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+        let files = [
+            (
+                "META-INF/container.xml",
+                r#"<container><rootfiles><rootfile full-path="OEBPS/content.opf"/></rootfiles></container>"#,
+            ),
+            (
+                "OEBPS/content.opf",
+                r#"<package><metadata><dc:title>Field Notes</dc:title><dc:creator>R. Vale</dc:creator>
+                <dc:date>2019-06-01</dc:date></metadata>
+                <manifest><item id="c2" href="text/two.xhtml"/><item id="c1" href="text/chapter%20one.xhtml"/>
+                <item id="css" href="style.css"/></manifest>
+                <spine><itemref idref="c1"/><itemref idref="c2"/></spine></package>"#,
+            ),
+            (
+                "OEBPS/text/chapter one.xhtml",
+                r#"<html><head><title>ignored</title></head><body><h1>Chapter 1</h1><p>It was &amp; is&nbsp;cold.</p></body></html>"#,
+            ),
+            (
+                "OEBPS/text/two.xhtml",
+                r#"<html><body><h1>Chapter 2</h1><p>Spring.</p></body></html>"#,
+            ),
+        ];
+        for (name, content) in files {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let extracted = extract(&path).unwrap();
+        assert!(extracted.text.starts_with("Chapter 1\nIt was & is cold.\n"));
+        assert!(extracted.text.find("Chapter 1") < extracted.text.find("Chapter 2"));
+        assert!(!extracted.text.contains("ignored"));
+        assert_eq!(extracted.metadata.title.as_deref(), Some("Field Notes"));
+        assert_eq!(extracted.metadata.author.as_deref(), Some("R. Vale"));
+        assert_eq!(
+            extracted.metadata.creation_date.as_deref(),
+            Some("2019-06-01")
+        );
+        assert_eq!(extracted.metadata.page_count, Some(2));
+    }
+
+    #[test]
+    fn test_resolve_href() {
+        assert_eq!(
+            resolve_href("OEBPS/", "text/a.xhtml#s1"),
+            "OEBPS/text/a.xhtml"
+        );
+        assert_eq!(
+            resolve_href("OEBPS/text/", "../b%20c.xhtml"),
+            "OEBPS/b c.xhtml"
+        );
+        assert_eq!(resolve_href("", "a.xhtml"), "a.xhtml");
+    }
+}
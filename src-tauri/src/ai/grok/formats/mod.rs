@@ -0,0 +1,67 @@
+//! Native extractors for office, e-book, RTF and email formats
+//!
+//! - `ooxml`: PPTX slides, plus Office core properties for DOCX/XLSX
+//! - `odf`: OpenDocument text, spreadsheets and presentations
+//! - `epub`: EPUB chapters in spine order
+//! - `rtf`: Rich Text Format
+//! - `email`: EML, MBOX and Outlook MSG
+//!
+//! Each extractor returns the raw text and the metadata the format carries.
+//! `DocumentParser` cleans, checks and truncates the text like it does for
+//! every other format.
+
+pub mod email;
+pub mod epub;
+pub mod odf;
+pub mod ooxml;
+pub mod rtf;
+mod xml;
+
+use super::document_parser::DocumentMetadata;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use zip::ZipArchive;
+
+/// Largest archive member read into memory (64MB uncompressed)
+const MAX_ENTRY_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Text and metadata pulled from a document
+#[derive(Debug, Clone, Default)]
+pub struct Extracted {
+    pub text: String,
+    pub metadata: DocumentMetadata,
+}
+
+type Archive = ZipArchive<BufReader<File>>;
+
+/// Open a ZIP-based document
+fn open_zip(path: &Path) -> Result<Archive, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    ZipArchive::new(BufReader::new(file)).map_err(|e| format!("Failed to read archive: {}", e))
+}
+
+/// Read an archive member as UTF-8 text, or None if it doesn't exist
+///
+/// Members are capped at `MAX_ENTRY_BYTES` so a zip bomb can't exhaust
+/// memory.
+fn zip_text(archive: &mut Archive, name: &str) -> Result<Option<String>, String> {
+    let entry = match archive.by_name(name) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {}: {}", name, e)),
+    };
+    let mut bytes = Vec::new();
+    entry
+        .take(MAX_ENTRY_BYTES)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+    Ok(Some(String::from_utf8_lossy(&bytes).into_owned()))
+}
+
+/// Trimmed, non-empty metadata value
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
@@ -0,0 +1,146 @@
+//! OpenDocument text (ODT), spreadsheets (ODS) and presentations (ODP)
+
+use super::xml::{self, Layout};
+use super::{non_empty, open_zip, zip_text, Extracted};
+use crate::ai::grok::document_parser::DocumentMetadata;
+use std::path::Path;
+
+const CONTENT_LAYOUT: Layout = Layout {
+    blocks: &["p", "h", "table-row", "page"],
+    cells: &["table-cell"],
+    tabs: &["tab"],
+    line_breaks: &["line-break"],
+    // Office settings, scripts and form definitions carry no document text
+    skip: &[
+        "automatic-styles",
+        "font-face-decls",
+        "scripts",
+        "forms",
+        "annotation",
+    ],
+    text_in: &[],
+};
+
+/// Extract the text and metadata of an ODT, ODS or ODP file
+///
+/// The page count is the page statistic for text documents, the number of
+/// sheets for spreadsheets and the number of slides for presentations.
+pub fn extract(path: &Path) -> Result<Extracted, String> {
+    let mut archive = open_zip(path)?;
+    let content = zip_text(&mut archive, "content.xml")?
+        .ok_or("Not an OpenDocument file (content.xml missing)")?;
+    let text = xml::text(&content, &CONTENT_LAYOUT);
+
+    let mut metadata = match zip_text(&mut archive, "meta.xml")? {
+        Some(meta) => read_meta(&meta),
+        None => DocumentMetadata::default(),
+    };
+
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|s| s.to_lowercase());
+    match ext.as_deref() {
+        Some("ods") => {
+            metadata.page_count = Some(xml::elements(&content, "table").len() as u32);
+        }
+        Some("odp") => {
+            metadata.page_count = Some(xml::elements(&content, "page").len() as u32);
+        }
+        _ => {}
+    }
+
+    Ok(Extracted { text, metadata })
+}
+
+/// Metadata from `meta.xml`
+fn read_meta(meta: &str) -> DocumentMetadata {
+    let mut fields = xml::fields(
+        meta,
+        &[
+            "title",
+            "subject",
+            "initial-creator",
+            "creator",
+            "creation-date",
+        ],
+    );
+    let page_count = xml::elements(meta, "document-statistic")
+        .first()
+        .and_then(|stats| stats.get("page-count")?.parse().ok());
+
+    DocumentMetadata {
+        title: non_empty(fields.remove("title")),
+        // The initial creator is the author; dc:creator is whoever saved last
+        author: non_empty(fields.remove("initial-creator"))
+            .or_else(|| non_empty(fields.remove("creator"))),
+        subject: non_empty(fields.remove("subject")),
+        creation_date: non_empty(fields.remove("creation-date")),
+        page_count,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::TempDir;
+    use zip::write::SimpleFileOptions;
+
+    fn write_odf(path: &Path, content: &str, meta: &str) {
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+        for (name, data) in [("content.xml", content), ("meta.xml", meta)] {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(data.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_odt_text_and_meta() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("letter.odt");
+        write_odf(
+            &path,
+            r#"<office:document-content><office:automatic-styles><style:style/></office:automatic-styles>
+            <office:body><office:text><text:h>Lease</text:h>
+            <text:p>Rent is<text:s text:c="2"/>due<text:tab/>monthly.</text:p></office:text></office:body>
+            </office:document-content>"#,
+            r#"<office:document-meta><office:meta><dc:title>Lease agreement</dc:title>
+            <meta:initial-creator>Sam</meta:initial-creator><dc:creator>Editor</dc:creator>
+            <meta:creation-date>2023-05-04T10:00:00</meta:creation-date>
+            <meta:document-statistic meta:page-count="4" meta:word-count="900"/>
+            </office:meta></office:document-meta>"#,
+        );
+
+        let extracted = extract(&path).unwrap();
+        assert!(extracted.text.contains("Lease\n"));
+        assert!(extracted.text.contains("Rent is  due\tmonthly.\n"));
+        assert_eq!(extracted.metadata.title.as_deref(), Some("Lease agreement"));
+        assert_eq!(extracted.metadata.author.as_deref(), Some("Sam"));
+        assert_eq!(
+            extracted.metadata.creation_date.as_deref(),
+            Some("2023-05-04T10:00:00")
+        );
+        assert_eq!(extracted.metadata.page_count, Some(4));
+    }
+
+    #[test]
+    fn test_ods_counts_sheets() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("budget.ods");
+        write_odf(
+            &path,
+            r#"<office:document-content><office:body><office:spreadsheet>
+            <table:table table:name="Q1"><table:table-row><table:table-cell><text:p>Rent</text:p></table:table-cell>
+            <table:table-cell><text:p>1200</text:p></table:table-cell></table:table-row></table:table>
+            <table:table table:name="Q2"/></office:spreadsheet></office:body></office:document-content>"#,
+            "<office:document-meta/>",
+        );
+
+        let extracted = extract(&path).unwrap();
+        assert!(extracted.text.contains("Rent | 1200 | \n"));
+        assert_eq!(extracted.metadata.page_count, Some(2));
+    }
+}
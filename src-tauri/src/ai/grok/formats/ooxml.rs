@@ -0,0 +1,131 @@
+//! Office Open XML: PowerPoint slides and the core properties shared by
+//! DOCX, XLSX and PPTX
+
+use super::xml::{self, Layout};
+use super::{non_empty, open_zip, zip_text, Archive, Extracted};
+use crate::ai::grok::document_parser::DocumentMetadata;
+use std::path::Path;
+
+/// Slide and notes text: paragraphs on their own lines, text only from
+/// `<a:t>` runs so shape properties never leak in
+const DRAWING_LAYOUT: Layout = Layout {
+    blocks: &["p"],
+    cells: &["tc"],
+    tabs: &["tab"],
+    line_breaks: &["br"],
+    skip: &[],
+    text_in: &["t"],
+};
+
+/// Extract the text of every slide (and its speaker notes) from a PPTX
+pub fn extract_pptx(path: &Path) -> Result<Extracted, String> {
+    let mut archive = open_zip(path)?;
+
+    // ppt/slides/slide12.xml sorts before slide2.xml by name, so sort by number
+    let mut slides: Vec<(u32, String)> = archive
+        .file_names()
+        .filter_map(|name| {
+            let number = name
+                .strip_prefix("ppt/slides/slide")?
+                .strip_suffix(".xml")?
+                .parse()
+                .ok()?;
+            Some((number, name.to_string()))
+        })
+        .collect();
+    slides.sort();
+
+    let mut text = String::new();
+    for (number, name) in &slides {
+        let Some(slide) = zip_text(&mut archive, name)? else {
+            continue;
+        };
+        text.push_str(&format!("\n=== Slide {} ===\n", number));
+        text.push_str(&xml::text(&slide, &DRAWING_LAYOUT));
+
+        let notes_name = format!("ppt/notesSlides/notesSlide{}.xml", number);
+        if let Some(notes) = zip_text(&mut archive, &notes_name)? {
+            let notes = xml::text(&notes, &DRAWING_LAYOUT);
+            if !notes.trim().is_empty() {
+                text.push_str("Notes:\n");
+                text.push_str(&notes);
+            }
+        }
+    }
+
+    let mut metadata = core_properties(&mut archive)?;
+    metadata.page_count = Some(slides.len() as u32);
+    Ok(Extracted { text, metadata })
+}
+
+/// Title, author, subject and creation date from `docProps/core.xml`
+///
+/// Missing properties are left as None; a document without the part gets
+/// empty metadata.
+pub fn core_properties(archive: &mut Archive) -> Result<DocumentMetadata, String> {
+    let Some(core) = zip_text(archive, "docProps/core.xml")? else {
+        return Ok(DocumentMetadata::default());
+    };
+    let mut fields = xml::fields(&core, &["title", "creator", "subject", "created"]);
+    Ok(DocumentMetadata {
+        title: non_empty(fields.remove("title")),
+        author: non_empty(fields.remove("creator")),
+        subject: non_empty(fields.remove("subject")),
+        creation_date: non_empty(fields.remove("created")),
+        ..Default::default()
+    })
+}
+
+/// Core properties of any OOXML file (DOCX, XLSX, PPTX)
+pub fn read_core_properties(path: &Path) -> Result<DocumentMetadata, String> {
+    core_properties(&mut open_zip(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::TempDir;
+    use zip::write::SimpleFileOptions;
+
+    #[test]
+    fn test_pptx_slides_in_order_with_metadata() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("deck.pptx");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+        let slide = |text: &str| {
+            format!(
+                r#"<p:sld><p:cSld><p:spTree><p:sp><p:txBody><a:p><a:r><a:rPr lang="en"/><a:t>{}</a:t></a:r></a:p></p:txBody></p:sp></p:spTree></p:cSld></p:sld>"#,
+                text
+            )
+        };
+        for (name, content) in [
+            ("ppt/slides/slide10.xml", slide("Closing")),
+            ("ppt/slides/slide2.xml", slide("Roadmap")),
+            ("ppt/slides/slide1.xml", slide("Kickoff")),
+            (
+                "docProps/core.xml",
+                r#"<cp:coreProperties><dc:title>Kickoff deck</dc:title><dc:creator>Dana</dc:creator><dcterms:created>2024-03-01T09:00:00Z</dcterms:created></cp:coreProperties>"#
+                    .to_string(),
+            ),
+        ] {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let extracted = extract_pptx(&path).unwrap();
+        let kickoff = extracted.text.find("Kickoff").unwrap();
+        let roadmap = extracted.text.find("Roadmap").unwrap();
+        let closing = extracted.text.find("Closing").unwrap();
+        assert!(kickoff < roadmap && roadmap < closing);
+        assert!(!extracted.text.contains("lang"));
+        assert_eq!(extracted.metadata.page_count, Some(3));
+        assert_eq!(extracted.metadata.title.as_deref(), Some("Kickoff deck"));
+        assert_eq!(extracted.metadata.author.as_deref(), Some("Dana"));
+        assert_eq!(
+            extracted.metadata.creation_date.as_deref(),
+            Some("2024-03-01T09:00:00Z")
+        );
+    }
+}
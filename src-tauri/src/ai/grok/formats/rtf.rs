@@ -0,0 +1,373 @@
+//! Rich Text Format
+//!
+//! A small tokenizer rather than a full RTF reader: it follows groups and
+//! destinations well enough to drop font tables, pictures and other
+//! non-text groups, decodes `\'hh` and `\uN` characters, and reads the
+//! `\info` group for metadata. Code pages other than Windows-1252 are not
+//! decoded.
+
+use super::{non_empty, Extracted};
+use crate::ai::grok::document_parser::DocumentMetadata;
+use std::path::Path;
+
+/// Destinations whose content is never document text
+const SKIPPED_DESTINATIONS: &[&str] = &[
+    "fonttbl",
+    "colortbl",
+    "stylesheet",
+    "listtable",
+    "listoverridetable",
+    "revtbl",
+    "rsidtbl",
+    "filetbl",
+    "generator",
+    "xmlnstbl",
+    "themedata",
+    "colorschememapping",
+    "datastore",
+    "latentstyles",
+    "pict",
+    "objdata",
+    "fldinst",
+    "header",
+    "headerl",
+    "headerr",
+    "headerf",
+    "footer",
+    "footerl",
+    "footerr",
+    "footerf",
+    "private",
+];
+
+/// Where text in the current group goes
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Destination {
+    Body,
+    Skip,
+    Info,
+    Title,
+    Author,
+    Subject,
+    Created,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Group {
+    destination: Destination,
+    /// Fallback characters that follow each `\uN` (`\ucN`)
+    unicode_skip: usize,
+}
+
+#[derive(Default)]
+struct Output {
+    text: String,
+    title: String,
+    author: String,
+    subject: String,
+    /// Creation time as year, month, day, hour, minute
+    created: [i32; 5],
+    pages: Option<u32>,
+}
+
+/// Extract the text and `\info` metadata of an RTF file
+pub fn extract(path: &Path) -> Result<Extracted, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read RTF: {}", e))?;
+    extract_bytes(&bytes)
+}
+
+/// Extract an RTF document held in memory
+pub fn extract_bytes(bytes: &[u8]) -> Result<Extracted, String> {
+    if !bytes.starts_with(b"{\\rtf") {
+        return Err("Not an RTF file (missing {\\rtf header)".to_string());
+    }
+    Ok(parse(bytes))
+}
+
+fn parse(bytes: &[u8]) -> Extracted {
+    let mut out = Output::default();
+    let mut stack: Vec<Group> = Vec::new();
+    let mut group = Group {
+        destination: Destination::Body,
+        unicode_skip: 1,
+    };
+    // The first control word of a group may name its destination
+    let mut group_start = false;
+    // Fallback characters still to drop after a \uN
+    let mut pending_skip = 0usize;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let byte = bytes[i];
+        i += 1;
+        match byte {
+            b'{' => {
+                stack.push(group);
+                group_start = true;
+                pending_skip = 0;
+            }
+            b'}' => {
+                if let Some(outer) = stack.pop() {
+                    group = outer;
+                }
+                group_start = false;
+                pending_skip = 0;
+            }
+            b'\r' | b'\n' => {}
+            b'\\' => {
+                let Some(&next) = bytes.get(i) else { break };
+                if next.is_ascii_alphabetic() {
+                    let (word, param, end) = control_word(bytes, i);
+                    i = end;
+                    let starts_group = std::mem::replace(&mut group_start, false);
+                    if starts_group {
+                        if let Some(destination) = destination(word, group.destination) {
+                            group.destination = destination;
+                            continue;
+                        }
+                    }
+                    i = control(
+                        word,
+                        param,
+                        &mut group,
+                        &mut pending_skip,
+                        &mut out,
+                        bytes,
+                        i,
+                    );
+                    continue;
+                }
+
+                i += 1;
+                let starts_group = std::mem::replace(&mut group_start, false);
+                match next {
+                    // \* marks a destination this reader may not know
+                    b'*' if starts_group => group.destination = Destination::Skip,
+                    b'\'' => {
+                        let hex = bytes
+                            .get(i..i + 2)
+                            .and_then(|h| std::str::from_utf8(h).ok());
+                        if let Some(value) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                            i += 2;
+                            emit(&mut out, &group, &mut pending_skip, cp1252(value));
+                        }
+                    }
+                    b'\\' | b'{' | b'}' => emit(&mut out, &group, &mut pending_skip, next as char),
+                    b'~' => emit(&mut out, &group, &mut pending_skip, ' '),
+                    b'_' => emit(&mut out, &group, &mut pending_skip, '-'),
+                    b'\r' | b'\n' => emit(&mut out, &group, &mut pending_skip, '\n'),
+                    _ => {}
+                }
+            }
+            _ => {
+                group_start = false;
+                emit(&mut out, &group, &mut pending_skip, cp1252(byte));
+            }
+        }
+    }
+
+    let created = match out.created {
+        [year, month, day, hour, minute] if year > 0 => Some(format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:00",
+            year,
+            month.max(1),
+            day.max(1),
+            hour,
+            minute
+        )),
+        _ => None,
+    };
+
+    Extracted {
+        text: out.text,
+        metadata: DocumentMetadata {
+            title: non_empty(Some(out.title)),
+            author: non_empty(Some(out.author)),
+            subject: non_empty(Some(out.subject)),
+            creation_date: created,
+            page_count: out.pages,
+            ..Default::default()
+        },
+    }
+}
+
+/// Read a control word and its optional numeric parameter starting at
+/// `start`; returns the word, the parameter and the index after it
+fn control_word(bytes: &[u8], start: usize) -> (&str, Option<i32>, usize) {
+    let mut i = start;
+    while i < bytes.len() && bytes[i].is_ascii_alphabetic() {
+        i += 1;
+    }
+    let word = std::str::from_utf8(&bytes[start..i]).unwrap_or("");
+
+    let param_start = i;
+    if i < bytes.len() && bytes[i] == b'-' {
+        i += 1;
+    }
+    while i < bytes.len() && bytes[i].is_ascii_digit() {
+        i += 1;
+    }
+    let param = std::str::from_utf8(&bytes[param_start..i])
+        .ok()
+        .and_then(|p| p.parse().ok());
+
+    // A single space delimits the control word and is not text
+    if i < bytes.len() && bytes[i] == b' ' {
+        i += 1;
+    }
+    (word, param, i)
+}
+
+/// The destination a group takes when it opens with `word`, if any
+fn destination(word: &str, current: Destination) -> Option<Destination> {
+    if current == Destination::Skip {
+        return None;
+    }
+    if current == Destination::Info {
+        return Some(match word {
+            "title" => Destination::Title,
+            "author" => Destination::Author,
+            "subject" => Destination::Subject,
+            "creatim" => Destination::Created,
+            // Keywords, comments, revision times and the like
+            _ => Destination::Skip,
+        });
+    }
+    match word {
+        "info" => Some(Destination::Info),
+        w if SKIPPED_DESTINATIONS.contains(&w) => Some(Destination::Skip),
+        _ => None,
+    }
+}
+
+/// Apply a formatting or character control word; returns the index to
+/// continue reading at
+fn control(
+    word: &str,
+    param: Option<i32>,
+    group: &mut Group,
+    pending_skip: &mut usize,
+    out: &mut Output,
+    bytes: &[u8],
+    i: usize,
+) -> usize {
+    let special = match word {
+        "par" | "line" | "sect" | "page" | "row" => Some('\n'),
+        "tab" => Some('\t'),
+        "emdash" => Some('\u{2014}'),
+        "endash" => Some('\u{2013}'),
+        "bullet" => Some('\u{2022}'),
+        "lquote" => Some('\u{2018}'),
+        "rquote" => Some('\u{2019}'),
+        "ldblquote" => Some('\u{201C}'),
+        "rdblquote" => Some('\u{201D}'),
+        _ => None,
+    };
+    if let Some(c) = special {
+        *pending_skip = 0;
+        emit(out, group, pending_skip, c);
+        return i;
+    }
+
+    match word {
+        "cell" => {
+            *pending_skip = 0;
+            for c in " | ".chars() {
+                emit(out, group, pending_skip, c);
+            }
+        }
+        "u" => {
+            if let Some(code) = param {
+                // Code points above 32767 are written as negative numbers
+                let code = if code < 0 { code + 65536 } else { code } as u32;
+                let c = char::from_u32(code).unwrap_or('\u{FFFD}');
+                *pending_skip = 0;
+                emit(out, group, pending_skip, c);
+                *pending_skip = group.unicode_skip;
+            }
+        }
+        "uc" => group.unicode_skip = param.unwrap_or(1).max(0) as usize,
+        "bin" => {
+            // Raw binary data: skip it without tokenizing
+            let len = param.unwrap_or(0).max(0) as usize;
+            return (i + len).min(bytes.len());
+        }
+        "nofpages" => out.pages = param.and_then(|p| u32::try_from(p).ok()),
+        "yr" | "mo" | "dy" | "hr" | "min" if group.destination == Destination::Created => {
+            let slot = ["yr", "mo", "dy", "hr", "min"]
+                .iter()
+                .position(|w| *w == word)
+                .unwrap_or(0);
+            out.created[slot] = param.unwrap_or(0);
+        }
+        _ => {}
+    }
+    i
+}
+
+/// Append a character to the current destination, dropping \uN fallbacks
+fn emit(out: &mut Output, group: &Group, pending_skip: &mut usize, c: char) {
+    if *pending_skip > 0 {
+        *pending_skip -= 1;
+        return;
+    }
+    match group.destination {
+        Destination::Body => out.text.push(c),
+        Destination::Title => out.title.push(c),
+        Destination::Author => out.author.push(c),
+        Destination::Subject => out.subject.push(c),
+        Destination::Skip | Destination::Info | Destination::Created => {}
+    }
+}
+
+/// Windows-1252 byte to char: Latin-1 apart from the 0x80-0x9F block
+fn cp1252(byte: u8) -> char {
+    match byte {
+        0x80 => '\u{20AC}',
+        0x85 => '\u{2026}',
+        0x91 => '\u{2018}',
+        0x92 => '\u{2019}',
+        0x93 => '\u{201C}',
+        0x94 => '\u{201D}',
+        0x95 => '\u{2022}',
+        0x96 => '\u{2013}',
+        0x97 => '\u{2014}',
+        0x99 => '\u{2122}',
+        _ => byte as char,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rtf_text_and_info() {
+        let rtf = br"{\rtf1\ansi\ansicpg1252{\fonttbl{\f0 Times New Roman;}}{\colortbl;\red0\green0\blue0;}
+{\*\generator Writer 1.0;}{\info{\title Lease renewal}{\author Kim Lee}{\keywords rent}
+{\creatim\yr2022\mo7\dy14\hr9\min30}\nofpages3}
+\pard\f0 Caf\'e9 terms\par
+Price:\tab 5\u8364?\par
+{\header Page header}Braces \{ and \} stay.\par}";
+
+        let extracted = parse(rtf);
+        assert_eq!(
+            extracted.text,
+            "Caf\u{e9} terms\nPrice:\t5\u{20AC}\nBraces { and } stay.\n"
+        );
+        assert_eq!(extracted.metadata.title.as_deref(), Some("Lease renewal"));
+        assert_eq!(extracted.metadata.author.as_deref(), Some("Kim Lee"));
+        assert_eq!(
+            extracted.metadata.creation_date.as_deref(),
+            Some("2022-07-14T09:30:00")
+        );
+        assert_eq!(extracted.metadata.page_count, Some(3));
+    }
+
+    #[test]
+    fn test_rejects_non_rtf() {
+        let temp = tempfile::NamedTempFile::with_suffix(".rtf").unwrap();
+        std::fs::write(temp.path(), "plain text").unwrap();
+        assert!(extract(temp.path()).is_err());
+    }
+}
@@ -0,0 +1,251 @@
+//! Lenient XML text extraction shared by the ZIP-based formats
+
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::{BytesRef, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+
+/// How element names map onto plain text layout, by local name
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Layout<'a> {
+    /// Elements followed by a line break (paragraphs, headings, rows)
+    pub blocks: &'a [&'a str],
+    /// Elements followed by a cell separator
+    pub cells: &'a [&'a str],
+    /// Elements that stand for a tab
+    pub tabs: &'a [&'a str],
+    /// Elements that stand for a line break
+    pub line_breaks: &'a [&'a str],
+    /// Elements whose content is not text (scripts, styles, metadata)
+    pub skip: &'a [&'a str],
+    /// Only keep text inside these elements; empty keeps all text
+    pub text_in: &'a [&'a str],
+}
+
+/// Plain text of an XML document laid out by `layout`
+///
+/// Malformed documents yield whatever text was read before the error.
+pub fn text(xml: &str, layout: &Layout) -> String {
+    let mut reader = lenient_reader(xml);
+    let decoder = reader.decoder();
+    let mut out = String::new();
+    let mut skip_depth = 0usize;
+    let mut text_depth = 0usize;
+    let mut cell_depth = 0usize;
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let name = local_name(e.local_name().as_ref());
+                if skip_depth > 0 || layout.skip.contains(&name.as_str()) {
+                    skip_depth += 1;
+                } else if layout.text_in.contains(&name.as_str()) {
+                    text_depth += 1;
+                } else if layout.cells.contains(&name.as_str()) {
+                    cell_depth += 1;
+                }
+            }
+            Ok(Event::Empty(e)) if skip_depth == 0 => {
+                let name = local_name(e.local_name().as_ref());
+                if layout.tabs.contains(&name.as_str()) {
+                    out.push('\t');
+                } else if layout.line_breaks.contains(&name.as_str()) {
+                    out.push('\n');
+                } else if name == "s" {
+                    // ODF run of spaces: <text:s text:c="3"/>
+                    let count = e
+                        .try_get_attribute("text:c")
+                        .ok()
+                        .flatten()
+                        .and_then(|a| {
+                            a.decode_and_unescape_value(decoder)
+                                .ok()?
+                                .parse::<usize>()
+                                .ok()
+                        })
+                        .unwrap_or(1);
+                    out.push_str(&" ".repeat(count.min(64)));
+                }
+            }
+            Ok(Event::End(e)) => {
+                let name = local_name(e.local_name().as_ref());
+                if skip_depth > 0 {
+                    skip_depth -= 1;
+                    continue;
+                }
+                if layout.text_in.contains(&name.as_str()) {
+                    text_depth = text_depth.saturating_sub(1);
+                }
+                if layout.blocks.contains(&name.as_str()) {
+                    // Paragraphs inside a table cell stay on the row's line
+                    out.push(if cell_depth > 0 { ' ' } else { '\n' });
+                } else if layout.cells.contains(&name.as_str()) {
+                    cell_depth = cell_depth.saturating_sub(1);
+                    out.truncate(out.trim_end_matches(' ').len());
+                    out.push_str(" | ");
+                }
+            }
+            Ok(Event::Text(t)) if keeps_text(layout, skip_depth, text_depth) => {
+                let Ok(decoded) = t.decode() else { continue };
+                if decoded.trim().is_empty() && decoded.contains('\n') {
+                    // Indentation between elements separates words at most
+                    if !out.ends_with(char::is_whitespace) && !out.is_empty() {
+                        out.push(' ');
+                    }
+                } else {
+                    out.push_str(&decoded);
+                }
+            }
+            Ok(Event::CData(t)) if keeps_text(layout, skip_depth, text_depth) => {
+                if let Ok(decoded) = t.decode() {
+                    out.push_str(&decoded);
+                }
+            }
+            Ok(Event::GeneralRef(r)) if keeps_text(layout, skip_depth, text_depth) => {
+                if let Some(resolved) = resolve_entity(&r) {
+                    out.push_str(&resolved);
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+
+    out
+}
+
+/// Text of the first element with each of the given local names
+///
+/// Used for flat metadata documents such as `docProps/core.xml`, ODF
+/// `meta.xml` and the EPUB package document.
+pub fn fields(xml: &str, names: &[&str]) -> HashMap<String, String> {
+    let mut reader = lenient_reader(xml);
+    let mut found: HashMap<String, String> = HashMap::new();
+    let mut current: Option<String> = None;
+    let mut value = String::new();
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let name = local_name(e.local_name().as_ref());
+                if current.is_none() && names.contains(&name.as_str()) && !found.contains_key(&name)
+                {
+                    current = Some(name);
+                    value.clear();
+                }
+            }
+            Ok(Event::End(e)) => {
+                let name = local_name(e.local_name().as_ref());
+                if current.as_deref() == Some(name.as_str()) {
+                    found.insert(name, value.trim().to_string());
+                    current = None;
+                }
+            }
+            Ok(Event::Text(t)) if current.is_some() => {
+                if let Ok(decoded) = t.decode() {
+                    value.push_str(&decoded);
+                }
+            }
+            Ok(Event::GeneralRef(r)) if current.is_some() => {
+                if let Some(resolved) = resolve_entity(&r) {
+                    value.push_str(&resolved);
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+
+    found
+}
+
+/// Attributes of every element with the given local name, keyed by
+/// attribute local name
+pub fn elements(xml: &str, element: &str) -> Vec<HashMap<String, String>> {
+    let mut reader = lenient_reader(xml);
+    let decoder = reader.decoder();
+    let mut found = Vec::new();
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e))
+                if local_name(e.local_name().as_ref()) == element =>
+            {
+                let attributes = e
+                    .attributes()
+                    .flatten()
+                    .filter_map(|a| {
+                        let key = local_name(a.key.local_name().as_ref());
+                        let value = a.decode_and_unescape_value(decoder).ok()?.into_owned();
+                        Some((key, value))
+                    })
+                    .collect();
+                found.push(attributes);
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+
+    found
+}
+
+fn keeps_text(layout: &Layout, skip_depth: usize, text_depth: usize) -> bool {
+    skip_depth == 0 && (layout.text_in.is_empty() || text_depth > 0)
+}
+
+fn lenient_reader(xml: &str) -> Reader<&[u8]> {
+    let mut reader = Reader::from_str(xml);
+    let config = reader.config_mut();
+    config.check_end_names = false;
+    config.allow_dangling_amp = true;
+    reader
+}
+
+fn local_name(name: &[u8]) -> String {
+    String::from_utf8_lossy(name).to_lowercase()
+}
+
+/// Character references, the XML entities and the one HTML entity that
+/// shows up everywhere in XHTML
+fn resolve_entity(reference: &BytesRef) -> Option<String> {
+    if let Ok(Some(c)) = reference.resolve_char_ref() {
+        return Some(c.to_string());
+    }
+    let name = reference.decode().ok()?;
+    match name.as_ref() {
+        "nbsp" => Some(" ".to_string()),
+        other => resolve_predefined_entity(other).map(str::to_string),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_layout_and_entities() {
+        let xml = r#"<doc><head><style>p { }</style></head>
+            <p>Fish &amp; chips<br/>&#169; 2024</p><row><c>a</c><c>b</c></row></doc>"#;
+        let layout = Layout {
+            blocks: &["p", "row"],
+            cells: &["c"],
+            line_breaks: &["br"],
+            skip: &["head"],
+            ..Default::default()
+        };
+        let text = text(xml, &layout);
+        assert!(text.contains("Fish & chips\n© 2024\n"), "{:?}", text);
+        assert!(text.contains("a | b | \n"));
+        assert!(!text.contains("p { }"));
+    }
+
+    #[test]
+    fn test_fields_take_first_match_by_local_name() {
+        let xml = r#"<cp:coreProperties xmlns:dc="x"><dc:title>Q3 Report</dc:title>
+            <dc:creator>Ana</dc:creator><dc:creator>Bo</dc:creator></cp:coreProperties>"#;
+        let found = fields(xml, &["title", "creator"]);
+        assert_eq!(found["title"], "Q3 Report");
+        assert_eq!(found["creator"], "Ana");
+    }
+}
@@ -4,7 +4,8 @@
 //!
 //! ## Architecture
 //! 1. Scan ALL files (PDFs, images, Office docs, text)
-//! 2. Extract text from documents (pure Rust)
+//! 2. Extract text from documents (pure Rust); scans and images go to local
+//!    OCR and/or Grok Vision, in the order the folder's `OcrPolicy` gives
 //! 3. OpenAI GPT-5-nano workers analyze in parallel (5 files/batch, 2-20 workers)
 //! 4. Grok grok-4-1-fast summarizes outputs (temp=0.1)
 //! 5. Grok orchestrator creates folder structure + assignments
@@ -282,29 +283,33 @@ impl GrokOrganizer {
                         let ext_str = ext.map(|s| s.to_string());
                         let parsed_result = tokio::task::spawn_blocking(move || {
                             let parser = DocumentParser::new();
-                            parser.parse(&path_clone)
+                            // Title/author/page lines go ahead of the text
+                            parser.parse(&path_clone).map(|parsed| {
+                                let preview = parser.get_analysis_preview(&parsed, 2000);
+                                (parsed.text.len(), preview)
+                            })
                         })
                         .await
                         .ok()?;
 
                         match parsed_result {
-                            Ok(parsed) if parsed.text.len() >= 100 => {
+                            Ok((text_len, preview)) if text_len >= 100 => {
                                 return Some(ExtractionResult::Extracted(FileContent {
                                     path: path.clone(),
                                     filename,
-                                    content: parsed.text.chars().take(2000).collect(),
+                                    content: preview,
                                     extension: ext_str.unwrap_or_default(),
                                 }));
                             }
                             _ => {
-                                // Text extraction failed, try Vision API
+                                // Text extraction failed, try OCR / Vision API
                                 if vision::is_analyzable_extension(ext) {
                                     return Some(ExtractionResult::NeedsVision(path));
                                 }
                             }
                         }
                     } else if vision::is_analyzable_extension(ext) {
-                        // Image or scanned PDF - use Vision API
+                        // Image or scanned PDF - use OCR / Vision API
                         return Some(ExtractionResult::NeedsVision(path));
                     }
 
@@ -372,7 +377,7 @@ impl GrokOrganizer {
         let cache_hits = all_file_paths.len() - file_contents.len() - vision_files.len();
 
         tracing::info!(
-            "[GrokOrganizer] Extracted text from {} files, {} need Vision API, {} cached",
+            "[GrokOrganizer] Extracted text from {} files, {} need OCR or Vision API, {} cached",
             file_contents.len(),
             vision_files.len(),
             cache_hits
@@ -474,14 +479,18 @@ impl GrokOrganizer {
             tracing::info!("[GrokOrganizer] No text content to analyze (all cached or vision-only)");
         }
 
-        // 6. Process Vision files with existing Grok pipeline
+        // 6. Process scans and images with the explore agents (local OCR and/or
+        //    Vision API, per folder OCR policy)
         if !vision_files.is_empty() {
             progress_callback(AnalysisProgress {
                 phase: AnalysisPhase::AnalyzingContent,
                 current: 0,
                 total: vision_files.len(),
                 current_file: None,
-                message: format!("Analyzing {} image files with Vision API...", vision_files.len()),
+                message: format!(
+                    "Analyzing {} scanned and image files (OCR / Vision API)...",
+                    vision_files.len()
+                ),
             });
 
             let batches = create_batches(vision_files.clone(), self.config.batch_size);
@@ -870,4 +879,67 @@ mod tests {
         // Note: This test requires a valid API key to fully work
         // For unit testing, we just verify the scan logic
     }
+
+    #[tokio::test]
+    async fn test_grok_only_pipeline_replays_recorded_session() {
+        use crate::ai::cassette::{fixture_path, install, Cassette};
+
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().join("files");
+        std::fs::create_dir(&root).unwrap();
+        image::RgbImage::new(16, 16)
+            .save(root.join("receipt-scan.png"))
+            .unwrap();
+        std::fs::write(root.join("todo.txt"), "milk, eggs, coffee filters").unwrap();
+
+        // Built by hand so no OpenAI key from the environment switches the pipeline
+        let config = GrokConfig {
+            api_key: "unused".to_string(),
+            ..Default::default()
+        };
+        let cache = Arc::new(ContentCache::open(&temp.path().join("cache")).unwrap());
+        let organizer = GrokOrganizer {
+            client: Arc::new(GrokClient::new(config.clone()).unwrap()),
+            cache: Arc::clone(&cache),
+            pdf_renderer: Arc::new(PdfRenderer::new()),
+            config,
+            openai_api_key: None,
+            grok_api_key: "unused".to_string(),
+        };
+
+        // The image goes to Vision (a blank scan has no text for local OCR),
+        // then the orchestrator plans both files
+        let guard = install(
+            Cassette::replay(fixture_path("grok_organize.json"))
+                .unwrap()
+                .with_placeholder("ROOT", root.to_string_lossy()),
+        )
+        .await;
+
+        let plan = organizer
+            .organize(&root, "Organize my receipts", |_| {})
+            .await
+            .unwrap();
+
+        assert_eq!(guard.cassette().remaining(), 0);
+        assert_eq!(plan.description, "Receipts by merchant, notes kept together");
+        assert_eq!(plan.folder_structure.len(), 2);
+
+        let receipt = root.join("receipt-scan.png");
+        let assignment = plan
+            .assignments
+            .iter()
+            .find(|a| a.file_path == receipt.to_string_lossy())
+            .unwrap();
+        assert_eq!(assignment.destination_folder, "Receipts/Blue-Bottle-Coffee");
+        assert_eq!(
+            assignment.get_sanitized_new_name(),
+            "Blue-Bottle-Coffee-Receipt-2024-03-02.png"
+        );
+
+        // The Vision analysis was cached in the temp folder
+        let cached = cache.get_cached(&receipt).unwrap().unwrap();
+        assert_eq!(cached.document_type, DocumentType::Receipt);
+        assert!(cached.key_entities.contains(&"Blue Bottle Coffee".to_string()));
+    }
 }
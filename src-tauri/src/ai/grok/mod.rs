@@ -6,6 +6,7 @@
 //! ┌─────────────────────────────────────────────────────────────────┐
 //! │  1. SCAN: Identify all files (PDFs, images, Office docs, text) │
 //! │  2. EXTRACT: Pure Rust text extraction (pdf-extract, calamine) │
+//! │     Scans: local OCR and/or Grok Vision, per folder policy     │
 //! │  3. OPENAI WORKERS: GPT-5-nano (2-20 workers, 5 files/batch)   │
 //! │  4. GROK SUMMARIZER: grok-4-1-fast (temp=0.1)                  │
 //! │  5. GROK ORCHESTRATOR: Creates folder structure + assignments  │
@@ -16,6 +17,8 @@
 mod cache;
 mod client;
 mod explore_agent;
+mod formats;
+pub mod ocr;
 pub mod openai_worker;
 mod orchestrator;
 mod pdf_renderer;
@@ -28,6 +31,7 @@ pub mod integration;
 pub mod types;
 
 // Public API - used by commands/grok.rs and commands/ai.rs
+pub use cache::ContentCache;
 pub use integration::{GrokOrganizer, ScanResult};
 #[allow(unused_imports)]
 pub use openai_worker::FileAnalysis;
@@ -0,0 +1,552 @@
+//! Local OCR Module
+//!
+//! Offline text recognition for scanned PDFs and images, so they can be
+//! analyzed without a paid Vision API call. Recognition uses Tesseract through
+//! the optional `ocr` feature, which links libtesseract and leptonica. Release
+//! builds for Linux and Windows enable it; other builds report local OCR
+//! unavailable and documents go to the Vision API as before.
+//!
+//! Traineddata for the configured languages is not shipped with the app. It
+//! is found in Tesseract's default location or under `TESSDATA_PREFIX`
+//! (e.g. the `tesseract-ocr-eng` package next to the AppImage); without it,
+//! recognition fails and the policy falls back to the next backend.
+//!
+//! PDF pages are recognized from `PdfRenderer` renders; images are
+//! recognized directly.
+//!
+//! ## Policy
+//! `OcrPolicy` decides which backends run for a document and in what order.
+//! The default policy applies everywhere and folders can override it; the
+//! nearest configured ancestor of a file wins. Settings are stored at
+//! `~/.config/sentinel/ocr_policy.json`.
+
+use super::document_parser::ParsedDocument;
+use super::pdf_renderer::{PdfRenderer, RENDER_DPI};
+use super::vision;
+use crate::wal::io::atomic_write;
+use image::GrayImage;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// Settings filename inside the sentinel config directory
+const SETTINGS_FILENAME: &str = "ocr_policy.json";
+
+/// Tesseract language used when none is configured
+pub const DEFAULT_LANGUAGE: &str = "eng";
+
+/// PDF pages rendered and recognized per document
+const MAX_OCR_PAGES: usize = 3;
+
+/// Resolution assumed for image files (typical scanner output)
+const IMAGE_DPI: i32 = 300;
+
+/// Mean Tesseract word confidence (0-100) below which a page is discarded
+#[cfg(feature = "ocr")]
+const MIN_CONFIDENCE: i32 = 40;
+
+/// Minimum recognized text for OCR to count as successful
+const MIN_OCR_TEXT_LENGTH: usize = 50;
+
+/// Saved settings, loaded on first use
+static SETTINGS: Lazy<RwLock<OcrSettings>> = Lazy::new(|| RwLock::new(OcrSettings::load()));
+
+/// Where the text of a scanned document comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OcrBackend {
+    /// Local OCR (free, offline)
+    Local,
+    /// Grok Vision API (paid)
+    Vision,
+}
+
+/// Fallback order for scanned documents and images
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OcrPolicy {
+    /// Local OCR, then the Vision API if it finds no usable text
+    #[default]
+    LocalFirst,
+    /// Vision API, then local OCR if the call fails (e.g. no API key)
+    VisionFirst,
+    /// Local OCR only; documents and their recognized text never go to an
+    /// API, and the text is classified locally
+    LocalOnly,
+    /// Vision API only
+    VisionOnly,
+}
+
+impl OcrPolicy {
+    /// Backends to try, in order
+    pub fn backends(&self) -> &'static [OcrBackend] {
+        match self {
+            OcrPolicy::LocalFirst => &[OcrBackend::Local, OcrBackend::Vision],
+            OcrPolicy::VisionFirst => &[OcrBackend::Vision, OcrBackend::Local],
+            OcrPolicy::LocalOnly => &[OcrBackend::Local],
+            OcrPolicy::VisionOnly => &[OcrBackend::Vision],
+        }
+    }
+}
+
+/// Saved OCR policies
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrSettings {
+    /// Policy for files outside any configured folder
+    #[serde(default)]
+    pub default_policy: OcrPolicy,
+    /// Per-folder overrides, keyed by absolute folder path
+    #[serde(default)]
+    pub folders: BTreeMap<PathBuf, OcrPolicy>,
+    /// Tesseract languages, e.g. "eng" or "eng+deu" (defaults to `DEFAULT_LANGUAGE`)
+    #[serde(default)]
+    pub language: Option<String>,
+}
+
+impl OcrSettings {
+    fn default_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("sentinel")
+            .join(SETTINGS_FILENAME)
+    }
+
+    /// Load saved settings, falling back to defaults
+    fn load() -> Self {
+        match Self::load_from(&Self::default_path()) {
+            Ok(settings) => settings,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to load OCR settings");
+                Self::default()
+            }
+        }
+    }
+
+    fn load_from(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read OCR settings: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse OCR settings: {}", e))
+    }
+
+    fn save_to(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create config directory: {}", e))?;
+        }
+        let json = serde_json::to_vec_pretty(self)
+            .map_err(|e| format!("Failed to serialize OCR settings: {}", e))?;
+        atomic_write(path, &json)?;
+        Ok(())
+    }
+
+    /// Check that folder keys are absolute and the language is well-formed
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(folder) = self.folders.keys().find(|f| !f.is_absolute()) {
+            return Err(format!(
+                "OCR policy folders must be absolute paths (got '{}')",
+                folder.display()
+            ));
+        }
+        if let Some(language) = &self.language {
+            let valid = !language.is_empty()
+                && language.split('+').all(|l| {
+                    !l.is_empty() && l.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                });
+            if !valid {
+                return Err(format!(
+                    "OCR language must be Tesseract language codes joined by '+' (got '{}')",
+                    language
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Policy for a file: the nearest configured ancestor folder, else the default
+    ///
+    /// Existing paths are canonicalized first so they match the canonical
+    /// folder keys through symlinks and `..` components.
+    pub fn policy_for(&self, path: &Path) -> OcrPolicy {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.folders
+            .iter()
+            .filter(|(folder, _)| path.starts_with(folder))
+            .max_by_key(|(folder, _)| folder.components().count())
+            .map(|(_, policy)| *policy)
+            .unwrap_or(self.default_policy)
+    }
+
+    /// Set or clear (`None`) the override for a folder
+    ///
+    /// Existing folders are stored canonicalized.
+    pub fn set_folder_policy(&mut self, folder: PathBuf, policy: Option<OcrPolicy>) {
+        let canonical = folder.canonicalize().unwrap_or_else(|_| folder.clone());
+        match policy {
+            Some(policy) => {
+                self.folders.remove(&folder);
+                self.folders.insert(canonical, policy);
+            }
+            None => {
+                self.folders.remove(&folder);
+                self.folders.remove(&canonical);
+            }
+        }
+    }
+
+    /// Configured Tesseract languages
+    pub fn language(&self) -> &str {
+        self.language
+            .as_deref()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .unwrap_or(DEFAULT_LANGUAGE)
+    }
+}
+
+/// Current OCR settings
+pub fn current_settings() -> OcrSettings {
+    SETTINGS.read().map(|s| s.clone()).unwrap_or_default()
+}
+
+/// Validate, persist and activate new settings
+pub fn save_settings(mut settings: OcrSettings) -> Result<(), String> {
+    settings.validate()?;
+    for (folder, policy) in std::mem::take(&mut settings.folders) {
+        settings.set_folder_policy(folder, Some(policy));
+    }
+    settings.save_to(&OcrSettings::default_path())?;
+    let mut current = SETTINGS
+        .write()
+        .map_err(|_| "OCR settings lock poisoned".to_string())?;
+    *current = settings;
+    Ok(())
+}
+
+/// Policy that applies to a file under the current settings
+pub fn policy_for(path: &Path) -> OcrPolicy {
+    current_settings().policy_for(path)
+}
+
+/// Local OCR engine
+#[derive(Debug, Clone)]
+pub struct OcrEngine {
+    /// Tesseract languages, e.g. "eng+deu" (only read with the `ocr` feature)
+    #[cfg_attr(not(feature = "ocr"), allow(dead_code))]
+    language: String,
+}
+
+impl OcrEngine {
+    /// Create an engine for the given Tesseract languages
+    pub fn new(language: &str) -> Self {
+        Self {
+            language: language.to_string(),
+        }
+    }
+
+    /// Create an engine with the configured languages
+    pub fn from_settings() -> Self {
+        Self::new(current_settings().language())
+    }
+
+    /// Whether this build includes local OCR
+    pub fn is_available() -> bool {
+        cfg!(feature = "ocr")
+    }
+
+    /// Check if a file type can be OCR'd locally (PDFs and images)
+    pub fn can_recognize(ext: Option<&str>) -> bool {
+        ext.is_some_and(|e| e.eq_ignore_ascii_case("pdf")) || vision::is_image_extension(ext)
+    }
+
+    /// OCR a scanned PDF or an image
+    ///
+    /// PDFs are rendered page by page (up to `MAX_OCR_PAGES`) with the given
+    /// renderer. Fails if local OCR is unavailable or finds too little text,
+    /// so callers can fall back to the Vision API.
+    pub async fn recognize_file(
+        &self,
+        path: &Path,
+        renderer: &PdfRenderer,
+    ) -> Result<ParsedDocument, String> {
+        if !Self::is_available() {
+            return Err("Local OCR not available (built without the `ocr` feature)".to_string());
+        }
+
+        let ext = path.extension().and_then(|e| e.to_str());
+        if !Self::can_recognize(ext) {
+            return Err(format!(
+                "Local OCR does not support {:?} files",
+                ext.unwrap_or("unknown")
+            ));
+        }
+
+        let (images, ppi, page_count) = if ext.is_some_and(|e| e.eq_ignore_ascii_case("pdf")) {
+            let page_count = renderer.page_count(path).await?;
+            let mut pages = Vec::new();
+            for index in 0..page_count.min(MAX_OCR_PAGES) {
+                pages.push(renderer.render_page(path, index).await?);
+            }
+            (pages, RENDER_DPI as i32, Some(page_count as u32))
+        } else {
+            let bytes = tokio::fs::read(path)
+                .await
+                .map_err(|e| format!("Failed to read image: {}", e))?;
+            (vec![bytes], IMAGE_DPI, None)
+        };
+
+        let engine = self.clone();
+        let source = path.display().to_string();
+        tokio::task::spawn_blocking(move || {
+            let mut text = String::new();
+            for (index, image) in images.iter().enumerate() {
+                match engine.recognize_image(image, ppi) {
+                    Ok(page) => {
+                        text.push_str(&page);
+                        text.push('\n');
+                    }
+                    Err(e) => tracing::debug!(
+                        "[OCR] Page {} of {} not recognized: {}",
+                        index + 1,
+                        source,
+                        e
+                    ),
+                }
+            }
+
+            let parsed = ParsedDocument::from_ocr(&text, page_count);
+            if parsed.text.len() < MIN_OCR_TEXT_LENGTH {
+                return Err(format!(
+                    "Local OCR found too little text ({} chars)",
+                    parsed.text.len()
+                ));
+            }
+            tracing::info!(
+                "[OCR] Recognized {} chars from {}",
+                parsed.text.len(),
+                source
+            );
+            Ok(parsed)
+        })
+        .await
+        .map_err(|e| format!("OCR task failed: {}", e))?
+    }
+
+    /// Recognize the text of an encoded image (blocking)
+    ///
+    /// `ppi` is the resolution the image was scanned or rendered at.
+    pub fn recognize_image(&self, bytes: &[u8], ppi: i32) -> Result<String, String> {
+        let image = image::load_from_memory(bytes)
+            .map_err(|e| format!("Failed to load image: {}", e))?
+            .to_luma8();
+        self.recognize_gray(&image, ppi)
+    }
+
+    #[cfg(feature = "ocr")]
+    fn recognize_gray(&self, image: &GrayImage, ppi: i32) -> Result<String, String> {
+        let (width, height) = image.dimensions();
+        let mut tesseract = tesseract::Tesseract::new(None, Some(&self.language))
+            .map_err(|e| format!("Failed to start Tesseract ({}): {}", self.language, e))?
+            .set_frame(image.as_raw(), width as i32, height as i32, 1, width as i32)
+            .map_err(|e| format!("Failed to pass image to Tesseract: {}", e))?
+            .set_source_resolution(ppi)
+            .recognize()
+            .map_err(|e| format!("Tesseract recognition failed: {}", e))?;
+
+        let confidence = tesseract.mean_text_conf();
+        if confidence < MIN_CONFIDENCE {
+            return Err(format!("Low OCR confidence ({})", confidence));
+        }
+        tesseract
+            .get_text()
+            .map_err(|e| format!("Failed to read Tesseract text: {}", e))
+    }
+
+    #[cfg(not(feature = "ocr"))]
+    fn recognize_gray(&self, _image: &GrayImage, _ppi: i32) -> Result<String, String> {
+        Err("Local OCR not available (built without the `ocr` feature)".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_policy_order() {
+        assert_eq!(
+            OcrPolicy::default().backends(),
+            [OcrBackend::Local, OcrBackend::Vision]
+        );
+        assert_eq!(
+            OcrPolicy::VisionFirst.backends(),
+            [OcrBackend::Vision, OcrBackend::Local]
+        );
+        assert_eq!(OcrPolicy::LocalOnly.backends(), [OcrBackend::Local]);
+        assert_eq!(OcrPolicy::VisionOnly.backends(), [OcrBackend::Vision]);
+    }
+
+    #[test]
+    fn test_nearest_folder_policy_wins() {
+        let mut settings = OcrSettings::default();
+        settings.set_folder_policy(PathBuf::from("/home/sam/Scans"), Some(OcrPolicy::LocalOnly));
+        settings.set_folder_policy(
+            PathBuf::from("/home/sam/Scans/receipts"),
+            Some(OcrPolicy::VisionFirst),
+        );
+
+        let policy = |s: &OcrSettings, p: &str| s.policy_for(Path::new(p));
+        assert_eq!(
+            policy(&settings, "/home/sam/Scans/a.pdf"),
+            OcrPolicy::LocalOnly
+        );
+        assert_eq!(
+            policy(&settings, "/home/sam/Scans/receipts/b.jpg"),
+            OcrPolicy::VisionFirst
+        );
+        // Path components, not string prefixes
+        assert_eq!(
+            policy(&settings, "/home/sam/Scans2/c.pdf"),
+            OcrPolicy::LocalFirst
+        );
+
+        settings.set_folder_policy(PathBuf::from("/home/sam/Scans/receipts"), None);
+        assert_eq!(
+            policy(&settings, "/home/sam/Scans/receipts/b.jpg"),
+            OcrPolicy::LocalOnly
+        );
+    }
+
+    #[test]
+    fn test_settings_round_trip_and_validation() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("sentinel").join(SETTINGS_FILENAME);
+        assert_eq!(
+            OcrSettings::load_from(&path).unwrap(),
+            OcrSettings::default()
+        );
+
+        let mut settings = OcrSettings {
+            default_policy: OcrPolicy::VisionOnly,
+            language: Some("eng+deu".to_string()),
+            ..Default::default()
+        };
+        settings.set_folder_policy(temp.path().join("scans"), Some(OcrPolicy::LocalOnly));
+        settings.validate().unwrap();
+        settings.save_to(&path).unwrap();
+        assert_eq!(OcrSettings::load_from(&path).unwrap(), settings);
+        assert_eq!(settings.language(), "eng+deu");
+
+        settings.language = Some("eng; rm".to_string());
+        assert!(settings.validate().is_err());
+        settings.language = None;
+        assert_eq!(settings.language(), DEFAULT_LANGUAGE);
+        settings.set_folder_policy(PathBuf::from("relative"), Some(OcrPolicy::LocalOnly));
+        assert!(settings.validate().is_err());
+    }
+
+    #[cfg(not(feature = "ocr"))]
+    #[tokio::test]
+    async fn test_unavailable_without_feature() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("scan.png");
+        image::GrayImage::new(8, 8).save(&path).unwrap();
+
+        assert!(!OcrEngine::is_available());
+        assert!(OcrEngine::can_recognize(Some("PDF")));
+        assert!(!OcrEngine::can_recognize(Some("docx")));
+        let result = OcrEngine::new(DEFAULT_LANGUAGE)
+            .recognize_file(&path, &PdfRenderer::new())
+            .await;
+        assert!(result.unwrap_err().contains("ocr"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_folder_policy_matches_through_symlinks() {
+        let temp = TempDir::new().unwrap();
+        let scans = temp.path().join("scans");
+        fs::create_dir(&scans).unwrap();
+        fs::write(scans.join("a.pdf"), b"%PDF").unwrap();
+        let link = temp.path().join("link");
+        std::os::unix::fs::symlink(&scans, &link).unwrap();
+
+        let mut settings = OcrSettings::default();
+        settings.set_folder_policy(link.clone(), Some(OcrPolicy::LocalOnly));
+        assert!(settings.folders.contains_key(&scans.canonicalize().unwrap()));
+        assert_eq!(settings.policy_for(&scans.join("a.pdf")), OcrPolicy::LocalOnly);
+        assert_eq!(
+            settings.policy_for(&temp.path().join("scans/../link/a.pdf")),
+            OcrPolicy::LocalOnly
+        );
+
+        settings.set_folder_policy(link, None);
+        assert!(settings.folders.is_empty());
+    }
+
+    /// Render lines of text to a PNG with a system font
+    #[cfg(feature = "ocr")]
+    fn render_text_png(lines: &[&str]) -> Vec<u8> {
+        use resvg::tiny_skia::{Pixmap, Transform};
+        use resvg::usvg::{Options, Tree};
+
+        let text: String = lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| format!(r#"<text x="40" y="{}">{}</text>"#, 80 + i * 60, line))
+            .collect();
+        let height = 80 + lines.len() * 60;
+        let svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="1400" height="{height}">
+                 <rect width="100%" height="100%" fill="white"/>
+                 <g font-family="DejaVu Sans, Liberation Sans, Arial, Helvetica, sans-serif"
+                    font-size="40" fill="black">{text}</g>
+               </svg>"#
+        );
+
+        let mut options = Options::default();
+        options.fontdb_mut().load_system_fonts();
+        let tree = Tree::from_str(&svg, &options).unwrap();
+        let mut pixmap = Pixmap::new(1400, height as u32).unwrap();
+        resvg::render(&tree, Transform::default(), &mut pixmap.as_mut());
+        pixmap.encode_png().unwrap()
+    }
+
+    #[cfg(feature = "ocr")]
+    #[tokio::test]
+    async fn test_tesseract_recognizes_rendered_text() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("scan.png");
+        fs::write(
+            &path,
+            render_text_png(&[
+                "Invoice number 4821 from Northwind Traders",
+                "Total amount due within thirty days",
+            ]),
+        )
+        .unwrap();
+
+        assert!(OcrEngine::is_available());
+        let parsed = OcrEngine::new(DEFAULT_LANGUAGE)
+            .recognize_file(&path, &PdfRenderer::new())
+            .await
+            .unwrap();
+        assert!(parsed.text.contains("Invoice"), "got {:?}", parsed.text);
+        assert!(parsed.text.contains("Northwind"), "got {:?}", parsed.text);
+
+        // A blank page has no words to be confident about
+        let blank = temp.path().join("blank.png");
+        image::GrayImage::from_pixel(400, 200, image::Luma([255])).save(&blank).unwrap();
+        assert!(OcrEngine::new(DEFAULT_LANGUAGE)
+            .recognize_file(&blank, &PdfRenderer::new())
+            .await
+            .is_err());
+    }
+}
@@ -4,7 +4,10 @@
 //! Each worker analyzes a batch of files (5 per batch) and returns
 //! structured analysis including suggested filenames.
 
-use reqwest::Client;
+use crate::ai::provider::settings::current_settings;
+use crate::ai::provider::{
+    self, ChatMessage, ChatRequest, LlmProvider, OpenAICompatProvider, ProviderKind,
+};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -88,25 +91,26 @@ pub async fn run_parallel_workers_with_progress(
     use futures::stream::{FuturesUnordered, StreamExt};
 
     let semaphore = Arc::new(Semaphore::new(max_concurrent));
-    let api_key = Arc::new(api_key);
     let total_batches = batches.len();
 
-    // Create SHARED HTTP client (reuses connections, avoids TLS overhead)
-    let shared_client = Arc::new(
-        Client::builder()
-            .timeout(Duration::from_secs(60))
-            .pool_max_idle_per_host(max_concurrent)
-            .build()
-            .expect("Failed to create HTTP client")
-    );
+    // SHARED provider (pooled HTTP client, avoids TLS overhead); the local
+    // model replaces OpenAI when one is selected in settings
+    let shared_provider: Arc<dyn LlmProvider> =
+        if current_settings().provider == ProviderKind::Local {
+            match provider::active_provider() {
+                Ok(provider) => provider,
+                Err(e) => return vec![Err(e); total_batches],
+            }
+        } else {
+            Arc::new(OpenAICompatProvider::openai(api_key))
+        };
 
     // Use FuturesUnordered to process results as they complete
     let mut futures = FuturesUnordered::new();
 
     for (batch_id, batch) in batches.into_iter().enumerate() {
         let sem = Arc::clone(&semaphore);
-        let key = Arc::clone(&api_key);
-        let client = Arc::clone(&shared_client);
+        let provider = Arc::clone(&shared_provider);
         let file_count = batch.len();
 
         futures.push(tokio::spawn(async move {
@@ -115,7 +119,7 @@ pub async fn run_parallel_workers_with_progress(
 
             eprintln!("[OpenAI Worker {}] Processing batch of {} files", batch_id, file_count);
 
-            let result = analyze_batch_with_client(&client, &key, batch).await;
+            let result = analyze_batch_with_provider(provider.as_ref(), batch).await;
 
             match &result {
                 Ok(analyses) => {
@@ -166,10 +170,9 @@ pub async fn run_parallel_workers_with_progress(
     results.into_iter().map(|r| r.unwrap_or_else(|| Err("Missing result".to_string()))).collect()
 }
 
-/// Analyze a batch using a shared HTTP client (avoids creating new clients per batch)
-async fn analyze_batch_with_client(
-    client: &Client,
-    api_key: &str,
+/// Analyze a batch using a shared provider (avoids creating new clients per batch)
+async fn analyze_batch_with_provider(
+    provider: &dyn LlmProvider,
     files: Vec<FileContent>,
 ) -> Result<Vec<FileAnalysis>, String> {
     if files.is_empty() {
@@ -244,67 +247,30 @@ Return ONLY the JSON array, no other text."#,
     // Call OpenAI API using shared client with timeout
     eprintln!("[OpenAI] Sending request for {} files to model {}", files.len(), model);
 
-    let request_future = client
-        .post("https://api.openai.com/v1/chat/completions")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .json(&serde_json::json!({
-            "model": model,
-            "messages": [
-                {"role": "system", "content": "You are a document analysis assistant. Respond only with valid JSON arrays."},
-                {"role": "user", "content": prompt}
-            ],
-            "max_completion_tokens": 25000
-        }))
-        .send();
-
-    // Wrap in timeout to prevent indefinite hang
-    let response = match tokio::time::timeout(Duration::from_secs(90), request_future).await {
+    let request = ChatRequest::new(model, 25000)
+        .with_system("You are a document analysis assistant. Respond only with valid JSON arrays.")
+        .with_messages(vec![ChatMessage::user(prompt)]);
+
+    // Wrap in timeout to prevent indefinite hang (local models get longer)
+    let timeout_secs = if provider.kind() == ProviderKind::Local { 600 } else { 90 };
+    let response = match tokio::time::timeout(
+        Duration::from_secs(timeout_secs),
+        provider.complete(&request),
+    )
+    .await
+    {
         Ok(Ok(resp)) => resp,
         Ok(Err(e)) => {
             eprintln!("[OpenAI] Request error: {}", e);
             return Err(format!("OpenAI API request failed: {}", e));
         }
         Err(_) => {
-            eprintln!("[OpenAI] Request TIMEOUT after 90 seconds");
-            return Err("OpenAI API request timed out after 90 seconds".to_string());
+            eprintln!("[OpenAI] Request TIMEOUT after {} seconds", timeout_secs);
+            return Err(format!("OpenAI API request timed out after {} seconds", timeout_secs));
         }
     };
 
-    eprintln!("[OpenAI] Got response with status: {}", response.status());
-
-    if !response.status().is_success() {
-        let status = response.status();
-        let text = response.text().await.unwrap_or_default();
-        eprintln!("[OpenAI] API error: {} - {}", status, text);
-        return Err(format!("OpenAI API error ({}): {}", status, text));
-    }
-
-    #[derive(serde::Deserialize, Debug)]
-    struct OpenAIResponse {
-        choices: Vec<Choice>,
-    }
-    #[derive(serde::Deserialize, Debug)]
-    struct Choice {
-        message: Message,
-        #[allow(dead_code)] // Required for JSON deserialization but value not used
-        finish_reason: Option<String>,
-    }
-    #[derive(serde::Deserialize, Debug)]
-    struct Message {
-        content: Option<String>,
-    }
-
-    let response_text = response.text().await
-        .map_err(|e| format!("Failed to read OpenAI response: {}", e))?;
-
-    let api_response: OpenAIResponse = serde_json::from_str(&response_text)
-        .map_err(|e| format!("Failed to parse OpenAI response: {}", e))?;
-
-    let content = api_response.choices.first()
-        .and_then(|c| c.message.content.clone())
-        .ok_or_else(|| "No response from OpenAI".to_string())?;
-
+    let content = response.text();
     if content.trim().is_empty() {
         return Err("OpenAI returned empty content".to_string());
     }
@@ -13,7 +13,7 @@
 //! - File assignments (file → folder mapping)
 //! - Suggested renames
 
-use super::client::GrokClient;
+use super::client::{grok_complete_text, grok_text_provider, GrokClient};
 use super::types::*;
 use super::utils::extract_json_object;
 use serde::Deserialize;
@@ -357,64 +357,18 @@ Output ONLY valid JSON. No markdown, no explanation, no code blocks."#,
 
     /// Send a text-only request to Grok
     async fn send_text_request(&self, prompt: &str) -> Result<String, String> {
-        use reqwest::Client;
-        use serde_json::json;
-
-        let client = Client::new();
-
-        let request_body = json!({
-            "model": "grok-4-1-fast",
-            "messages": [{
-                "role": "user",
-                "content": prompt
-            }],
-            "max_tokens": 16000,  // Large output for complex hierarchical structures
-            "temperature": 0.3   // Slightly higher for more creative folder naming
-        });
-
-        // Get API key from environment (dotenvy loads .env at startup)
-        let api_key = std::env::var("XAI_API_KEY")
-            .or_else(|_| std::env::var("GROK_API_KEY"))
-            .or_else(|_| std::env::var("VITE_XAI_API_KEY"))
-            .map_err(|_| "No Grok API key found (XAI_API_KEY, GROK_API_KEY, or VITE_XAI_API_KEY)")?;
-
-        let response = client
-            .post("https://api.x.ai/v1/chat/completions")
-            .header("Authorization", format!("Bearer {}", api_key))
-            .header("Content-Type", "application/json")
-            .json(&request_body)
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let text = response.text().await.unwrap_or_default();
-            return Err(format!("API error ({}): {}", status, text));
-        }
-
-        #[derive(Deserialize)]
-        struct Response {
-            choices: Vec<Choice>,
-        }
-        #[derive(Deserialize)]
-        struct Choice {
-            message: Message,
-        }
-        #[derive(Deserialize)]
-        struct Message {
-            content: String,
-        }
-
-        let resp: Response = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
-
-        resp.choices
-            .first()
-            .map(|c| c.message.content.clone())
-            .ok_or_else(|| "No response content".to_string())
+        let provider = grok_text_provider(None)?;
+
+        // Large output for complex hierarchical structures; slightly higher
+        // temperature for more creative folder naming
+        grok_complete_text(
+            provider.as_ref(),
+            "grok-4-1-fast",
+            prompt.to_string(),
+            16000,
+            0.3,
+        )
+        .await
     }
 
     /// Parse the plan response from Grok
@@ -1,7 +1,8 @@
 //! PDF Rendering Module
 //!
-//! Converts PDF pages to images for Grok Vision analysis.
-//! Uses a fallback chain: pdfium -> image extraction -> skip
+//! Converts PDF pages to images for Grok Vision analysis and local OCR.
+//! Uses pdfium when the `pdfium` feature is enabled, otherwise the built-in
+//! renderer in `crate::pdf`, which needs no native library.
 //!
 //! Note: pdfium-render requires the pdfium library to be installed.
 //! On macOS: brew install pdfium
@@ -9,14 +10,16 @@
 //! On Windows: Download from https://github.com/nickelc/pdfium-binaries
 
 #[cfg(not(feature = "pdfium"))]
-use image::{DynamicImage, RgbaImage};
+use crate::pdf::{PdfDocument, RenderOptions};
+#[cfg(not(feature = "pdfium"))]
+use image::DynamicImage;
 use image::ImageFormat;
 use std::io::Cursor;
 use std::path::Path;
 
 /// Target DPI for PDF rendering (150 = good balance of quality and size)
 #[allow(dead_code)]
-const RENDER_DPI: f32 = 150.0;
+pub(super) const RENDER_DPI: f32 = 150.0;
 
 /// Maximum page dimension in pixels
 #[allow(dead_code)]
@@ -38,7 +41,7 @@ impl PdfRenderer {
         if pdfium_available {
             tracing::info!("[PdfRenderer] Using pdfium backend");
         } else {
-            tracing::warn!("[PdfRenderer] pdfium not available, using fallback");
+            tracing::info!("[PdfRenderer] pdfium not available, using built-in renderer");
         }
 
         Self { pdfium_available }
@@ -84,7 +87,7 @@ impl PdfRenderer {
         }
         #[cfg(not(feature = "pdfium"))]
         {
-            Self::render_fallback(path, page_index)
+            Self::render_builtin(path, page_index)
         }
     }
 
@@ -131,81 +134,24 @@ impl PdfRenderer {
         Ok(buffer)
     }
 
-    /// Fallback rendering using pdf-extract for text overlay on blank image
+    /// Render using the built-in renderer
     #[cfg(not(feature = "pdfium"))]
-    fn render_fallback(path: &Path, _page_index: usize) -> Result<Vec<u8>, String> {
-        // Create a placeholder image with text indicating PDF content
-        // This is a degraded mode when pdfium is not available
-
-        let width = 800u32;
-        let height = 600u32;
-        let mut img = RgbaImage::new(width, height);
-
-        // Fill with light gray background
-        for pixel in img.pixels_mut() {
-            *pixel = image::Rgba([245, 245, 245, 255]);
-        }
-
-        // Try to extract text from PDF
-        let text = Self::extract_pdf_text(path).unwrap_or_else(|_| {
-            format!("PDF: {}", path.file_name().unwrap_or_default().to_string_lossy())
-        });
-
-        // For now, just return the placeholder
-        // In production, you'd want to render text onto the image
-        tracing::warn!(
-            "[PdfRenderer] Using fallback for {}, extracted {} chars of text",
-            path.display(),
-            text.len()
-        );
+    fn render_builtin(path: &Path, page_index: usize) -> Result<Vec<u8>, String> {
+        let options = RenderOptions {
+            dpi: RENDER_DPI,
+            max_dimension: MAX_PAGE_DIMENSION,
+        };
+        let image = PdfDocument::open(path)?.render_page(page_index, &options)?;
 
-        let dynamic_image = DynamicImage::ImageRgba8(img);
+        // JPEG has no alpha channel
+        let rgb = DynamicImage::ImageRgba8(image).to_rgb8();
         let mut buffer = Vec::new();
-        dynamic_image
-            .write_to(&mut Cursor::new(&mut buffer), ImageFormat::Jpeg)
+        rgb.write_to(&mut Cursor::new(&mut buffer), ImageFormat::Jpeg)
             .map_err(|e| format!("Failed to encode image: {}", e))?;
 
         Ok(buffer)
     }
 
-    /// Extract text from PDF as fallback
-    #[cfg(not(feature = "pdfium"))]
-    fn extract_pdf_text(path: &Path) -> Result<String, String> {
-        // Read raw PDF and look for text streams
-        // This is a very basic implementation
-        let content = std::fs::read(path)
-            .map_err(|e| format!("Failed to read PDF: {}", e))?;
-
-        // Look for text in PDF content (very basic extraction)
-        let content_str = String::from_utf8_lossy(&content);
-
-        // Extract text between BT and ET markers (PDF text blocks)
-        let mut text = String::new();
-        let mut in_text_block = false;
-
-        for line in content_str.lines() {
-            if line.contains("BT") {
-                in_text_block = true;
-            } else if line.contains("ET") {
-                in_text_block = false;
-            } else if in_text_block {
-                // Try to extract text from Tj or TJ operators
-                if let Some(start) = line.find('(') {
-                    if let Some(end) = line.rfind(')') {
-                        text.push_str(&line[start + 1..end]);
-                        text.push(' ');
-                    }
-                }
-            }
-        }
-
-        if text.is_empty() {
-            Err("No text found in PDF".to_string())
-        } else {
-            Ok(text)
-        }
-    }
-
     /// Get the number of pages in a PDF
     #[allow(dead_code)]
     pub async fn page_count(&self, path: &Path) -> Result<usize, String> {
@@ -234,13 +180,7 @@ impl PdfRenderer {
         }
         #[cfg(not(feature = "pdfium"))]
         {
-            // Estimate page count from file size (rough heuristic)
-            let size = std::fs::metadata(path)
-                .map_err(|e| format!("Failed to get file size: {}", e))?
-                .len();
-
-            // Rough estimate: ~50KB per page for average PDF
-            Ok((size / 50_000).max(1) as usize)
+            Ok(PdfDocument::open(path)?.page_count())
         }
     }
 
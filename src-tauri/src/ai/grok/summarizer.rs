@@ -6,14 +6,12 @@
 
 use super::openai_worker::FileAnalysis;
 use super::types::{AnalysisMethod, DocumentAnalysis, DocumentType};
+use super::client::{grok_complete_text, grok_text_provider};
 use super::utils::extract_json_array;
-use reqwest::Client;
 use serde::Deserialize;
-use std::time::Duration;
 
 /// Grok summarizer for consistent output formatting
 pub struct GrokSummarizer {
-    client: Client,
     api_key: String,
     model: String,
 }
@@ -22,10 +20,6 @@ impl GrokSummarizer {
     /// Create a new Grok summarizer
     pub fn new(api_key: String) -> Self {
         Self {
-            client: Client::builder()
-                .timeout(Duration::from_secs(120))
-                .build()
-                .expect("Failed to create HTTP client"),
             api_key,
             model: "grok-4-1-fast".to_string(),
         }
@@ -82,56 +76,16 @@ Return ONLY the JSON array, no other text."#,
             input_json
         );
 
-        let response = self
-            .client
-            .post("https://api.x.ai/v1/chat/completions")
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&serde_json::json!({
-                "model": self.model,
-                "messages": [{"role": "user", "content": prompt}],
-                "max_tokens": 8000,
-                "temperature": 0.1
-            }))
-            .send()
-            .await
-            .map_err(|e| format!("Grok API request failed: {}", e))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let text = response.text().await.unwrap_or_default();
-            // Fallback to direct conversion if API fails
-            tracing::warn!(
-                "[GrokSummarizer] API error ({}): {} - using direct conversion",
-                status,
-                text
-            );
-            return Ok(self.direct_convert(analyses));
-        }
-
-        #[derive(Deserialize)]
-        struct GrokResponse {
-            choices: Vec<Choice>,
-        }
-        #[derive(Deserialize)]
-        struct Choice {
-            message: Message,
-        }
-        #[derive(Deserialize)]
-        struct Message {
-            content: String,
-        }
-
-        let grok_resp: GrokResponse = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse Grok response: {}", e))?;
-
-        let content = grok_resp
-            .choices
-            .first()
-            .map(|c| c.message.content.clone())
-            .ok_or("No response from Grok")?;
+        let provider = grok_text_provider(Some(&self.api_key))?;
+        let content =
+            match grok_complete_text(provider.as_ref(), &self.model, prompt, 8000, 0.1).await {
+                Ok(content) => content,
+                Err(e) => {
+                    // Fallback to direct conversion if API fails
+                    tracing::warn!("[GrokSummarizer] {} - using direct conversion", e);
+                    return Ok(self.direct_convert(analyses));
+                }
+            };
 
         // Parse the formatted output
         let json_str = extract_json_array(&content).map_err(|e| {
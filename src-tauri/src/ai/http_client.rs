@@ -8,9 +8,16 @@
 //! - TLS session resumption (avoids 1-2 RTT handshake per request)
 //! - Connection pooling (reuses existing TCP connections)
 //! - Single client initialization (avoids repeated builder overhead)
+//!
+//! Agent traffic goes through [`send`], which serves requests from the
+//! active HTTP cassette when one is installed (see `ai::cassette`, only
+//! compiled into tests and builds with the `cassette` feature).
 
+#[cfg(any(test, feature = "cassette"))]
+use super::cassette;
 use once_cell::sync::Lazy;
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder, Response};
+use std::fmt;
 use std::time::Duration;
 
 /// Global HTTP client for Anthropic API calls
@@ -34,7 +41,6 @@ pub static ANTHROPIC_CLIENT: Lazy<Client> = Lazy::new(|| {
 /// Global HTTP client for OpenAI API calls
 ///
 /// Similar configuration to Anthropic client, tuned for OpenAI's API patterns.
-#[allow(dead_code)]
 pub static OPENAI_CLIENT: Lazy<Client> = Lazy::new(|| {
     Client::builder()
         .timeout(Duration::from_secs(90))
@@ -49,7 +55,6 @@ pub static OPENAI_CLIENT: Lazy<Client> = Lazy::new(|| {
 /// Global HTTP client for short API validation requests
 ///
 /// Shorter timeout optimized for quick operations like API key validation.
-#[allow(dead_code)]
 pub static VALIDATION_CLIENT: Lazy<Client> = Lazy::new(|| {
     Client::builder()
         .timeout(Duration::from_secs(30))
@@ -59,6 +64,20 @@ pub static VALIDATION_CLIENT: Lazy<Client> = Lazy::new(|| {
         .expect("Failed to create validation HTTP client")
 });
 
+/// Global HTTP client for self-hosted OpenAI-compatible servers
+///
+/// Local models (llama.cpp, Ollama, vLLM) can take minutes to produce a long
+/// response on consumer hardware, so the timeout is much longer.
+pub static LOCAL_CLIENT: Lazy<Client> = Lazy::new(|| {
+    Client::builder()
+        .timeout(Duration::from_secs(600))
+        .pool_max_idle_per_host(8)
+        .pool_idle_timeout(Duration::from_secs(90))
+        .tcp_nodelay(true)
+        .build()
+        .expect("Failed to create local model HTTP client")
+});
+
 /// Get the global Anthropic HTTP client
 ///
 /// This function returns a reference to the lazy-initialized client.
@@ -70,18 +89,63 @@ pub fn anthropic_client() -> &'static Client {
 
 /// Get the global OpenAI HTTP client
 #[inline]
-#[allow(dead_code)]
 pub fn openai_client() -> &'static Client {
     &OPENAI_CLIENT
 }
 
 /// Get the global validation HTTP client
 #[inline]
-#[allow(dead_code)]
 pub fn validation_client() -> &'static Client {
     &VALIDATION_CLIENT
 }
 
+/// Get the global HTTP client for local model servers
+#[inline]
+pub fn local_client() -> &'static Client {
+    &LOCAL_CLIENT
+}
+
+/// Error from [`send`]
+#[derive(Debug)]
+pub enum SendError {
+    /// The request failed on the network
+    Http(reqwest::Error),
+    /// The active cassette has no matching response, or could not be written
+    #[cfg(any(test, feature = "cassette"))]
+    Cassette(String),
+}
+
+impl SendError {
+    /// Whether the server could not be reached at all
+    pub fn is_connect(&self) -> bool {
+        matches!(self, SendError::Http(e) if e.is_connect())
+    }
+}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendError::Http(e) => write!(f, "{}", e),
+            #[cfg(any(test, feature = "cassette"))]
+            SendError::Cassette(message) => write!(f, "Cassette: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for SendError {}
+
+/// Send a request, recording or replaying it if a cassette is active
+pub async fn send(builder: RequestBuilder) -> Result<Response, SendError> {
+    #[cfg(any(test, feature = "cassette"))]
+    if let Some(cassette) = cassette::active() {
+        let (client, request) = builder.build_split();
+        return cassette
+            .send(&client, request.map_err(SendError::Http)?)
+            .await;
+    }
+    builder.send().await.map_err(SendError::Http)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -92,6 +156,7 @@ mod tests {
         let _ = anthropic_client();
         let _ = openai_client();
         let _ = validation_client();
+        let _ = local_client();
     }
 
     #[test]
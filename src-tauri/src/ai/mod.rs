@@ -1,9 +1,12 @@
+#[cfg(any(test, feature = "cassette"))]
+pub mod cassette;
 pub mod chat;
 pub mod client;
 pub mod credentials;
 pub mod grok;
 pub mod http_client;
 pub mod prompts;
+pub mod provider;
 pub mod rules;
 pub mod tools;
 pub mod v2;
@@ -0,0 +1,562 @@
+//! Anthropic Messages API backend
+
+use super::settings::api_key;
+use super::sse::SseDecoder;
+use super::{
+    check_vision, ChatRequest, ChatResponse, ContentPart, LlmProvider, ProviderError, ProviderKind,
+    Role, StopReason, StreamEvent, Usage,
+};
+use crate::ai::http_client::{anthropic_client, send};
+use async_trait::async_trait;
+use futures::StreamExt;
+use reqwest::Client;
+use serde_json::{json, Value};
+
+const ANTHROPIC_API_URL: &str = "https://api.anthropic.com/v1/messages";
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Maximum streamed block sizes, to bound memory on malformed responses
+const MAX_TEXT_BLOCK_SIZE: usize = 500_000;
+const MAX_THINKING_BLOCK_SIZE: usize = 1_000_000;
+const MAX_TOOL_INPUT_SIZE: usize = 100_000;
+
+/// Claude via the Anthropic Messages API
+pub struct AnthropicProvider {
+    api_key: String,
+    api_url: String,
+    client: &'static Client,
+}
+
+impl AnthropicProvider {
+    /// Create a provider with an explicit API key
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            api_url: ANTHROPIC_API_URL.to_string(),
+            client: anthropic_client(),
+        }
+    }
+
+    /// Send requests through `client` instead of the shared Anthropic client
+    pub fn with_client(mut self, client: &'static Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Create a provider using the stored Anthropic API key
+    pub fn from_credentials() -> Result<Self, String> {
+        Ok(Self::new(api_key("anthropic")?))
+    }
+
+    /// Build the JSON request body
+    fn request_body(&self, request: &ChatRequest, stream: bool) -> Value {
+        let messages: Vec<Value> = request
+            .messages
+            .iter()
+            .map(|m| {
+                json!({
+                    "role": match m.role {
+                        Role::User => "user",
+                        Role::Assistant => "assistant",
+                    },
+                    "content": m.content.iter().map(content_to_json).collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+
+        let mut body = json!({
+            "model": self.resolve_model(&request.model),
+            "max_tokens": request.max_tokens,
+            "messages": messages,
+        });
+        if let Some(system) = &request.system {
+            body["system"] = json!(system);
+        }
+        if !request.tools.is_empty() {
+            body["tools"] = json!(request.tools);
+        }
+        if let Some(temperature) = request.temperature {
+            body["temperature"] = json!(temperature);
+        }
+        if let Some(budget) = request.thinking_budget {
+            body["thinking"] = json!({ "type": "enabled", "budget_tokens": budget });
+        }
+        if stream {
+            body["stream"] = json!(true);
+        }
+        body
+    }
+
+    async fn send(&self, body: &Value) -> Result<reqwest::Response, ProviderError> {
+        let response = send(
+            self.client
+                .post(&self.api_url)
+                .header("x-api-key", &self.api_key)
+                .header("anthropic-version", ANTHROPIC_VERSION)
+                .header("content-type", "application/json")
+                .json(body),
+        )
+        .await
+        .map_err(ProviderError::network)?;
+
+        if !response.status().is_success() {
+            return Err(ProviderError::from_response("Anthropic", response).await);
+        }
+        Ok(response)
+    }
+}
+
+/// Convert a neutral content part to an Anthropic content block
+fn content_to_json(part: &ContentPart) -> Value {
+    match part {
+        ContentPart::Text { text, cache } => {
+            let mut block = json!({ "type": "text", "text": text });
+            if *cache {
+                block["cache_control"] = json!({ "type": "ephemeral" });
+            }
+            block
+        }
+        ContentPart::Image { mime_type, data } => json!({
+            "type": "image",
+            "source": { "type": "base64", "media_type": mime_type, "data": data },
+        }),
+        ContentPart::ToolUse { id, name, input } => json!({
+            "type": "tool_use",
+            "id": id,
+            "name": name,
+            "input": input,
+        }),
+        ContentPart::ToolResult {
+            tool_use_id,
+            content,
+            is_error,
+        } => {
+            let mut block = json!({
+                "type": "tool_result",
+                "tool_use_id": tool_use_id,
+                "content": content,
+            });
+            if *is_error {
+                block["is_error"] = json!(true);
+            }
+            block
+        }
+        ContentPart::Thinking {
+            thinking,
+            signature,
+        } => json!({
+            "type": "thinking",
+            "thinking": thinking,
+            "signature": signature,
+        }),
+    }
+}
+
+/// Append a streamed chunk unless the block would exceed `max` bytes
+fn push_bounded(block: &mut String, chunk: &str, max: usize, what: &str) {
+    if block.len() + chunk.len() <= max {
+        block.push_str(chunk);
+    } else {
+        tracing::warn!(max_size = max, block = what, "Streamed block truncated");
+    }
+}
+
+fn parse_stop_reason(reason: Option<&str>) -> StopReason {
+    match reason {
+        Some("end_turn") | Some("stop_sequence") | None => StopReason::EndTurn,
+        Some("tool_use") => StopReason::ToolUse,
+        Some("max_tokens") => StopReason::MaxTokens,
+        Some(other) => StopReason::Other(other.to_string()),
+    }
+}
+
+fn parse_usage(usage: &Value, into: &mut Usage) {
+    let field = |name: &str| usage.get(name).and_then(Value::as_u64);
+    if let Some(v) = field("input_tokens") {
+        into.input_tokens = v;
+    }
+    if let Some(v) = field("output_tokens") {
+        into.output_tokens = v;
+    }
+    if let Some(v) = field("cache_creation_input_tokens") {
+        into.cache_creation_input_tokens = v;
+    }
+    if let Some(v) = field("cache_read_input_tokens") {
+        into.cache_read_input_tokens = v;
+    }
+}
+
+/// Parse a non-streaming Messages API response body
+fn parse_response(body: &Value) -> Vec<ContentPart> {
+    body.get("content")
+        .and_then(Value::as_array)
+        .map(|blocks| {
+            blocks
+                .iter()
+                .filter_map(|block| match block.get("type").and_then(Value::as_str) {
+                    Some("text") => Some(ContentPart::text(
+                        block
+                            .get("text")
+                            .and_then(Value::as_str)
+                            .unwrap_or_default(),
+                    )),
+                    Some("tool_use") => Some(ContentPart::tool_use(
+                        block.get("id").and_then(Value::as_str).unwrap_or_default(),
+                        block
+                            .get("name")
+                            .and_then(Value::as_str)
+                            .unwrap_or_default(),
+                        block.get("input").cloned().unwrap_or_else(|| json!({})),
+                    )),
+                    Some("thinking") => Some(ContentPart::Thinking {
+                        thinking: block
+                            .get("thinking")
+                            .and_then(Value::as_str)
+                            .unwrap_or_default()
+                            .to_string(),
+                        signature: block
+                            .get("signature")
+                            .and_then(Value::as_str)
+                            .unwrap_or_default()
+                            .to_string(),
+                    }),
+                    // Redacted thinking and other block types are not surfaced
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// A content block being assembled from stream deltas
+enum PartialBlock {
+    Text(String),
+    Thinking {
+        thinking: String,
+        signature: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        input_json: String,
+    },
+    Ignored,
+}
+
+#[async_trait]
+impl LlmProvider for AnthropicProvider {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::Anthropic
+    }
+
+    fn supports_vision(&self) -> bool {
+        true
+    }
+
+    fn resolve_model(&self, model: &str) -> String {
+        model.to_string()
+    }
+
+    async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, ProviderError> {
+        check_vision(self, request)?;
+        let response = self.send(&self.request_body(request, false)).await?;
+        let headers = response.headers().clone();
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| ProviderError::new(format!("Failed to parse response: {}", e)))?;
+
+        let mut usage = Usage::default();
+        if let Some(u) = body.get("usage") {
+            parse_usage(u, &mut usage);
+        }
+
+        Ok(ChatResponse {
+            content: parse_response(&body),
+            stop_reason: parse_stop_reason(body.get("stop_reason").and_then(Value::as_str)),
+            usage,
+            headers,
+        })
+    }
+
+    async fn stream(
+        &self,
+        request: &ChatRequest,
+        on_event: &(dyn Fn(StreamEvent) + Send + Sync),
+    ) -> Result<ChatResponse, ProviderError> {
+        check_vision(self, request)?;
+        let response = self.send(&self.request_body(request, true)).await?;
+        let headers = response.headers().clone();
+
+        let mut decoder = SseDecoder::new();
+        let mut blocks: Vec<PartialBlock> = Vec::new();
+        let mut usage = Usage::default();
+        let mut stop_reason = None;
+        let mut stream = response.bytes_stream();
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| ProviderError::new(format!("Stream error: {}", e)))?;
+            for data in decoder.push(&chunk).map_err(ProviderError::new)? {
+                let Ok(event) = serde_json::from_str::<Value>(&data) else {
+                    continue;
+                };
+                match event.get("type").and_then(Value::as_str) {
+                    Some("message_start") => {
+                        if let Some(u) = event.pointer("/message/usage") {
+                            parse_usage(u, &mut usage);
+                        }
+                    }
+                    Some("content_block_start") => {
+                        let block = &event["content_block"];
+                        blocks.push(match block.get("type").and_then(Value::as_str) {
+                            Some("text") => PartialBlock::Text(String::new()),
+                            Some("thinking") => {
+                                on_event(StreamEvent::ThinkingStart);
+                                PartialBlock::Thinking {
+                                    thinking: String::new(),
+                                    signature: String::new(),
+                                }
+                            }
+                            Some("tool_use") => {
+                                let id = block["id"].as_str().unwrap_or_default().to_string();
+                                let name = block["name"].as_str().unwrap_or_default().to_string();
+                                on_event(StreamEvent::ToolUseStart {
+                                    id: id.clone(),
+                                    name: name.clone(),
+                                });
+                                PartialBlock::ToolUse {
+                                    id,
+                                    name,
+                                    input_json: String::new(),
+                                }
+                            }
+                            _ => PartialBlock::Ignored,
+                        });
+                    }
+                    Some("content_block_delta") => {
+                        let delta = &event["delta"];
+                        let current = blocks.last_mut();
+                        match (delta.get("type").and_then(Value::as_str), current) {
+                            (Some("text_delta"), Some(PartialBlock::Text(text))) => {
+                                let chunk = delta["text"].as_str().unwrap_or_default();
+                                push_bounded(text, chunk, MAX_TEXT_BLOCK_SIZE, "text");
+                                on_event(StreamEvent::TextDelta(chunk.to_string()));
+                            }
+                            (
+                                Some("input_json_delta"),
+                                Some(PartialBlock::ToolUse { input_json, .. }),
+                            ) => {
+                                let chunk = delta["partial_json"].as_str().unwrap_or_default();
+                                push_bounded(input_json, chunk, MAX_TOOL_INPUT_SIZE, "tool_input");
+                            }
+                            (Some("thinking_delta"), current) => {
+                                let chunk = delta["thinking"].as_str().unwrap_or_default();
+                                if let Some(PartialBlock::Thinking { thinking, .. }) = current {
+                                    push_bounded(
+                                        thinking,
+                                        chunk,
+                                        MAX_THINKING_BLOCK_SIZE,
+                                        "thinking",
+                                    );
+                                }
+                                on_event(StreamEvent::ThinkingDelta(chunk.to_string()));
+                            }
+                            (
+                                Some("signature_delta"),
+                                Some(PartialBlock::Thinking { signature, .. }),
+                            ) => {
+                                signature.push_str(delta["signature"].as_str().unwrap_or_default());
+                            }
+                            _ => {}
+                        }
+                    }
+                    Some("content_block_stop") => {
+                        if let Some(PartialBlock::Thinking { .. }) = blocks.last() {
+                            on_event(StreamEvent::ThinkingStop);
+                        }
+                    }
+                    Some("message_delta") => {
+                        if let Some(reason) =
+                            event.pointer("/delta/stop_reason").and_then(Value::as_str)
+                        {
+                            stop_reason = Some(reason.to_string());
+                        }
+                        if let Some(u) = event.get("usage") {
+                            parse_usage(u, &mut usage);
+                        }
+                    }
+                    Some("error") => {
+                        let message = event
+                            .pointer("/error/message")
+                            .and_then(Value::as_str)
+                            .unwrap_or("unknown stream error");
+                        return Err(ProviderError::new(format!(
+                            "Anthropic stream error: {}",
+                            message
+                        )));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let content = blocks
+            .into_iter()
+            .filter_map(|block| match block {
+                PartialBlock::Text(text) => Some(ContentPart::text(text)),
+                PartialBlock::Thinking {
+                    thinking,
+                    signature,
+                } => Some(ContentPart::Thinking {
+                    thinking,
+                    signature,
+                }),
+                PartialBlock::ToolUse {
+                    id,
+                    name,
+                    input_json,
+                } => {
+                    let input = if input_json.trim().is_empty() {
+                        json!({})
+                    } else {
+                        serde_json::from_str(&input_json).unwrap_or_else(|_| json!({}))
+                    };
+                    Some(ContentPart::tool_use(id, name, input))
+                }
+                PartialBlock::Ignored => None,
+            })
+            .collect();
+
+        Ok(ChatResponse {
+            content,
+            stop_reason: parse_stop_reason(stop_reason.as_deref()),
+            usage,
+            headers,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::provider::ChatMessage;
+
+    #[test]
+    fn test_request_body_blocks() {
+        let provider = AnthropicProvider::new("key");
+        let request = ChatRequest::new("claude-sonnet-4-5", 100)
+            .with_system("sys")
+            .with_messages(vec![
+                ChatMessage::user_parts(vec![
+                    ContentPart::cached_text("tree"),
+                    ContentPart::image("image/png", "AAAA"),
+                ]),
+                ChatMessage::assistant_parts(vec![ContentPart::tool_use(
+                    "t1",
+                    "grep",
+                    json!({ "pattern": "x" }),
+                )]),
+                ChatMessage::user_parts(vec![ContentPart::tool_result("t1", "none", true)]),
+            ]);
+
+        let body = provider.request_body(&request, false);
+        assert_eq!(body["system"], "sys");
+        assert_eq!(
+            body["messages"][0]["content"][0]["cache_control"]["type"],
+            "ephemeral"
+        );
+        assert_eq!(
+            body["messages"][0]["content"][1]["source"]["media_type"],
+            "image/png"
+        );
+        assert_eq!(body["messages"][1]["content"][0]["type"], "tool_use");
+        assert_eq!(body["messages"][2]["content"][0]["is_error"], true);
+        assert!(body.get("tools").is_none());
+        assert!(body.get("stream").is_none());
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let body = json!({
+            "content": [
+                { "type": "text", "text": "Looking" },
+                { "type": "tool_use", "id": "t1", "name": "grep", "input": { "pattern": "x" } }
+            ],
+            "stop_reason": "tool_use"
+        });
+        let content = parse_response(&body);
+        assert_eq!(content.len(), 2);
+        assert_eq!(
+            parse_stop_reason(body["stop_reason"].as_str()),
+            StopReason::ToolUse
+        );
+    }
+
+    #[test]
+    fn test_thinking_round_trip() {
+        let provider = AnthropicProvider::new("key");
+        let request = ChatRequest::new("claude-sonnet-4-5", 16000)
+            .with_thinking(10000)
+            .with_messages(vec![ChatMessage::assistant_parts(vec![ContentPart::Thinking {
+                thinking: "Check the folder first".to_string(),
+                signature: "sig".to_string(),
+            }])]);
+
+        let body = provider.request_body(&request, true);
+        assert_eq!(body["thinking"]["budget_tokens"], 10000);
+        assert_eq!(body["messages"][0]["content"][0]["type"], "thinking");
+        assert_eq!(body["messages"][0]["content"][0]["signature"], "sig");
+
+        let content = parse_response(&json!({
+            "content": [{ "type": "thinking", "thinking": "Hmm", "signature": "s2" }]
+        }));
+        assert_eq!(
+            content,
+            vec![ContentPart::Thinking {
+                thinking: "Hmm".to_string(),
+                signature: "s2".to_string(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_replayed_from_cassette() {
+        use crate::ai::cassette::{fixture_path, install, Cassette};
+        use std::sync::Mutex;
+
+        let _guard = install(
+            Cassette::replay(fixture_path("anthropic_stream_tool_use.json"))
+                .unwrap()
+                .with_placeholder("ROOT", "/Users/me/Downloads"),
+        )
+        .await;
+
+        let provider = AnthropicProvider::new("unused");
+        let request = ChatRequest::new("claude-sonnet-4-5", 1024)
+            .with_messages(vec![ChatMessage::user("What is in Downloads?")]);
+        let deltas = Mutex::new(String::new());
+        let response = provider
+            .stream(&request, &|event| {
+                if let StreamEvent::TextDelta(text) = event {
+                    deltas.lock().unwrap().push_str(&text);
+                }
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *deltas.lock().unwrap(),
+            "Let me look in /Users/me/Downloads."
+        );
+        assert_eq!(response.stop_reason, StopReason::ToolUse);
+        assert_eq!(response.usage.input_tokens, 512);
+        assert_eq!(response.usage.output_tokens, 38);
+        match &response.content[1] {
+            ContentPart::ToolUse { name, input, .. } => {
+                assert_eq!(name, "list_directory");
+                assert_eq!(input["path"], "/Users/me/Downloads");
+            }
+            other => panic!("expected tool use, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,580 @@
+//! LLM Provider Abstraction
+//!
+//! One interface for every model backend the app talks to. Callers build a
+//! provider-neutral `ChatRequest` (system prompt, messages with text, image,
+//! tool-use and tool-result parts, tool definitions) and get back a
+//! `ChatResponse`, either in one shot (`complete`) or incrementally
+//! (`stream`).
+//!
+//! ## Backends
+//! - `anthropic` - Anthropic Messages API (Claude)
+//! - `openai_compat` - OpenAI Chat Completions wire format, used for OpenAI,
+//!   xAI and any local server that speaks it (llama.cpp, Ollama, vLLM)
+//!
+//! The backend used for app-wide requests is chosen by `LlmSettings`
+//! (see `settings`), so the whole pipeline can run against a local model.
+
+pub mod anthropic;
+pub mod openai_compat;
+pub mod settings;
+mod sse;
+
+pub use anthropic::AnthropicProvider;
+pub use openai_compat::OpenAICompatProvider;
+pub use settings::{active_provider, provider_for, LlmSettings, ProviderKind};
+
+use crate::ai::http_client::SendError;
+use crate::ai::tools::ToolDefinition;
+use async_trait::async_trait;
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::time::Duration;
+
+/// Speaker of a chat message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    User,
+    Assistant,
+}
+
+/// A single piece of message content
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    /// Plain text. `cache` marks large, repeated context for prompt caching
+    /// on backends that support it (ignored elsewhere).
+    Text {
+        text: String,
+        #[serde(default)]
+        cache: bool,
+    },
+    /// Base64-encoded image
+    Image { mime_type: String, data: String },
+    /// A tool call made by the assistant
+    ToolUse {
+        id: String,
+        name: String,
+        input: Value,
+    },
+    /// The result of a tool call, sent back by the user side
+    ToolResult {
+        tool_use_id: String,
+        content: String,
+        #[serde(default)]
+        is_error: bool,
+    },
+    /// Extended thinking from the assistant. Anthropic requires it to be sent
+    /// back unchanged (with its signature) in later turns; other backends
+    /// drop it.
+    Thinking { thinking: String, signature: String },
+}
+
+impl ContentPart {
+    /// Create a text part
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::Text {
+            text: text.into(),
+            cache: false,
+        }
+    }
+
+    /// Create a text part marked for prompt caching
+    pub fn cached_text(text: impl Into<String>) -> Self {
+        Self::Text {
+            text: text.into(),
+            cache: true,
+        }
+    }
+
+    /// Create a base64 image part
+    pub fn image(mime_type: impl Into<String>, data: impl Into<String>) -> Self {
+        Self::Image {
+            mime_type: mime_type.into(),
+            data: data.into(),
+        }
+    }
+
+    /// Create a tool call part
+    pub fn tool_use(id: impl Into<String>, name: impl Into<String>, input: Value) -> Self {
+        Self::ToolUse {
+            id: id.into(),
+            name: name.into(),
+            input,
+        }
+    }
+
+    /// Create a tool result part
+    pub fn tool_result(
+        tool_use_id: impl Into<String>,
+        content: impl Into<String>,
+        is_error: bool,
+    ) -> Self {
+        Self::ToolResult {
+            tool_use_id: tool_use_id.into(),
+            content: content.into(),
+            is_error,
+        }
+    }
+}
+
+/// A message in the conversation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: Role,
+    pub content: Vec<ContentPart>,
+}
+
+impl ChatMessage {
+    /// A user message with a single text part
+    pub fn user(text: impl Into<String>) -> Self {
+        Self {
+            role: Role::User,
+            content: vec![ContentPart::text(text)],
+        }
+    }
+
+    /// A user message with arbitrary parts
+    pub fn user_parts(content: Vec<ContentPart>) -> Self {
+        Self {
+            role: Role::User,
+            content,
+        }
+    }
+
+    /// An assistant message with a single text part
+    pub fn assistant(text: impl Into<String>) -> Self {
+        Self {
+            role: Role::Assistant,
+            content: vec![ContentPart::text(text)],
+        }
+    }
+
+    /// An assistant message with arbitrary parts
+    pub fn assistant_parts(content: Vec<ContentPart>) -> Self {
+        Self {
+            role: Role::Assistant,
+            content,
+        }
+    }
+}
+
+/// A provider-neutral chat request
+#[derive(Debug, Clone)]
+pub struct ChatRequest {
+    /// Model ID. Claude model IDs are mapped to the provider's configured
+    /// models by `LlmProvider::resolve_model`.
+    pub model: String,
+    pub system: Option<String>,
+    pub messages: Vec<ChatMessage>,
+    pub tools: Vec<ToolDefinition>,
+    pub max_tokens: u32,
+    pub temperature: Option<f32>,
+    /// Extended thinking budget in tokens (must be below `max_tokens`).
+    /// Ignored by backends without extended thinking.
+    pub thinking_budget: Option<u32>,
+}
+
+impl ChatRequest {
+    /// Create a request with no system prompt, messages or tools
+    pub fn new(model: impl Into<String>, max_tokens: u32) -> Self {
+        Self {
+            model: model.into(),
+            system: None,
+            messages: Vec::new(),
+            tools: Vec::new(),
+            max_tokens,
+            temperature: None,
+            thinking_budget: None,
+        }
+    }
+
+    /// Set the system prompt
+    pub fn with_system(mut self, system: impl Into<String>) -> Self {
+        self.system = Some(system.into());
+        self
+    }
+
+    /// Set the conversation messages
+    pub fn with_messages(mut self, messages: Vec<ChatMessage>) -> Self {
+        self.messages = messages;
+        self
+    }
+
+    /// Set the tools the model may call
+    pub fn with_tools(mut self, tools: Vec<ToolDefinition>) -> Self {
+        self.tools = tools;
+        self
+    }
+
+    /// Set the sampling temperature
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// Enable extended thinking with a token budget
+    pub fn with_thinking(mut self, budget_tokens: u32) -> Self {
+        self.thinking_budget = Some(budget_tokens);
+        self
+    }
+
+    /// Whether any message carries an image
+    pub fn has_images(&self) -> bool {
+        self.messages
+            .iter()
+            .flat_map(|m| m.content.iter())
+            .any(|p| matches!(p, ContentPart::Image { .. }))
+    }
+}
+
+/// Why the model stopped generating
+#[derive(Debug, Clone, PartialEq)]
+pub enum StopReason {
+    /// Natural end of the turn
+    EndTurn,
+    /// The model wants tool results
+    ToolUse,
+    /// Output hit `max_tokens`
+    MaxTokens,
+    /// Anything else, as reported by the backend
+    Other(String),
+}
+
+/// Token usage for a single request
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Usage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    pub cache_read_input_tokens: u64,
+}
+
+/// A complete model response
+#[derive(Debug, Clone)]
+pub struct ChatResponse {
+    pub content: Vec<ContentPart>,
+    pub stop_reason: StopReason,
+    pub usage: Usage,
+    /// Response headers (used for header-based rate limiting)
+    pub headers: HeaderMap,
+}
+
+impl ChatResponse {
+    /// Concatenated text of all text parts
+    pub fn text(&self) -> String {
+        self.content
+            .iter()
+            .filter_map(|p| match p {
+                ContentPart::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("")
+    }
+
+    /// Whether the response contains tool calls
+    pub fn has_tool_use(&self) -> bool {
+        self.content
+            .iter()
+            .any(|p| matches!(p, ContentPart::ToolUse { .. }))
+    }
+}
+
+/// Incremental output delivered while streaming
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEvent {
+    /// A chunk of response text
+    TextDelta(String),
+    /// A reasoning block started (backends with extended thinking)
+    ThinkingStart,
+    /// A chunk of reasoning text (backends with visible thinking)
+    ThinkingDelta(String),
+    /// The current reasoning block finished
+    ThinkingStop,
+    /// The model started a tool call (input arrives with the final response)
+    ToolUseStart { id: String, name: String },
+}
+
+/// Error from a provider request
+#[derive(Debug, Clone)]
+pub struct ProviderError {
+    pub message: String,
+    /// HTTP status, if the backend answered
+    pub status: Option<u16>,
+    /// Server-requested delay before retrying
+    pub retry_after: Option<Duration>,
+}
+
+impl ProviderError {
+    /// An error without an HTTP status (configuration, parsing, ...)
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            status: None,
+            retry_after: None,
+        }
+    }
+
+    /// A transport failure (connection refused, timeout, TLS, ...)
+    ///
+    /// Cassette misses are reported as-is so they are not retried.
+    pub fn network(error: SendError) -> Self {
+        match error {
+            SendError::Http(e) => Self::new(format!("HTTP request failed: {}", e)),
+            #[cfg(any(test, feature = "cassette"))]
+            SendError::Cassette(message) => Self::new(format!("Cassette: {}", message)),
+        }
+    }
+
+    /// Build an error from a non-success HTTP response
+    pub async fn from_response(provider: &str, response: reqwest::Response) -> Self {
+        let status = response.status().as_u16();
+        let retry_after = response
+            .headers()
+            .get("retry-after")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|s| s.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+        let body = response.text().await.unwrap_or_default();
+
+        // Both wire formats nest the message under `error.message`
+        let detail = serde_json::from_str::<Value>(&body)
+            .ok()
+            .and_then(|v| {
+                v.get("error")
+                    .and_then(|e| e.get("message").or(Some(e)))
+                    .and_then(|m| m.as_str().map(String::from))
+            })
+            .unwrap_or(body);
+
+        tracing::warn!(provider, status, error = %detail, "LLM API error");
+
+        Self {
+            message: format!("{} API error ({}): {}", provider, status, detail),
+            status: Some(status),
+            retry_after,
+        }
+    }
+
+    /// Whether the request may succeed if retried
+    pub fn is_retryable(&self) -> bool {
+        match self.status {
+            // Rate limited, overloaded or server error
+            Some(status) => status == 429 || status == 529 || (500..=599).contains(&status),
+            // Transport failures are usually transient
+            None => self.message.starts_with("HTTP request failed"),
+        }
+    }
+}
+
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ProviderError {}
+
+impl From<ProviderError> for String {
+    fn from(e: ProviderError) -> Self {
+        e.message
+    }
+}
+
+/// A model backend
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    /// Which backend this is
+    fn kind(&self) -> ProviderKind;
+
+    /// Whether image parts are accepted
+    fn supports_vision(&self) -> bool;
+
+    /// Map a requested model ID to the one this backend should use.
+    ///
+    /// Existing code asks for Claude models by tier (Haiku for cheap
+    /// exploration, Sonnet for planning); non-Anthropic backends translate
+    /// those to their configured fast/smart models.
+    fn resolve_model(&self, model: &str) -> String;
+
+    /// Send a request and wait for the full response
+    async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, ProviderError>;
+
+    /// Send a request, reporting output as it arrives, and return the full
+    /// response once the stream ends
+    async fn stream(
+        &self,
+        request: &ChatRequest,
+        on_event: &(dyn Fn(StreamEvent) + Send + Sync),
+    ) -> Result<ChatResponse, ProviderError>;
+}
+
+/// Whether a model ID names a fast/cheap tier model
+pub fn is_fast_model(model: &str) -> bool {
+    let model = model.to_lowercase();
+    model.contains("haiku") || model.contains("mini") || model.contains("fast")
+}
+
+/// Reject image requests to backends without vision support
+pub fn check_vision(
+    provider: &dyn LlmProvider,
+    request: &ChatRequest,
+) -> Result<(), ProviderError> {
+    if request.has_images() && !provider.supports_vision() {
+        return Err(ProviderError::new(format!(
+            "The {} provider is not configured for image input",
+            provider.kind().as_str()
+        )));
+    }
+    Ok(())
+}
+
+/// Call `complete`, retrying rate limits and transient failures with
+/// exponential backoff (honouring `retry-after` when present).
+///
+/// `on_retry` is called with the attempt number and delay before each retry.
+pub async fn complete_with_retry(
+    provider: &dyn LlmProvider,
+    request: &ChatRequest,
+    max_retries: u32,
+    initial_delay: Duration,
+    on_retry: impl Fn(u32, Duration),
+) -> Result<ChatResponse, ProviderError> {
+    let mut delay = initial_delay;
+    let mut attempt = 0;
+
+    loop {
+        match provider.complete(request).await {
+            Ok(response) => return Ok(response),
+            Err(e) if e.is_retryable() && attempt < max_retries => {
+                attempt += 1;
+                let wait = e.retry_after.unwrap_or(delay);
+                tracing::warn!(
+                    attempt,
+                    max_retries,
+                    delay_ms = wait.as_millis() as u64,
+                    error = %e,
+                    "Retrying LLM request"
+                );
+                on_retry(attempt, wait);
+                tokio::time::sleep(wait).await;
+                delay *= 2;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Provider that fails with a fixed status a number of times, then succeeds
+    struct FlakyProvider {
+        failures: AtomicU32,
+        status: u16,
+    }
+
+    #[async_trait]
+    impl LlmProvider for FlakyProvider {
+        fn kind(&self) -> ProviderKind {
+            ProviderKind::Local
+        }
+
+        fn supports_vision(&self) -> bool {
+            false
+        }
+
+        fn resolve_model(&self, model: &str) -> String {
+            model.to_string()
+        }
+
+        async fn complete(&self, _request: &ChatRequest) -> Result<ChatResponse, ProviderError> {
+            if self.failures.load(Ordering::SeqCst) > 0 {
+                self.failures.fetch_sub(1, Ordering::SeqCst);
+                return Err(ProviderError {
+                    message: "failed".to_string(),
+                    status: Some(self.status),
+                    retry_after: Some(Duration::from_millis(1)),
+                });
+            }
+            Ok(ChatResponse {
+                content: vec![ContentPart::text("ok")],
+                stop_reason: StopReason::EndTurn,
+                usage: Usage::default(),
+                headers: HeaderMap::new(),
+            })
+        }
+
+        async fn stream(
+            &self,
+            request: &ChatRequest,
+            _on_event: &(dyn Fn(StreamEvent) + Send + Sync),
+        ) -> Result<ChatResponse, ProviderError> {
+            self.complete(request).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_recovers_from_rate_limit() {
+        let provider = FlakyProvider {
+            failures: AtomicU32::new(2),
+            status: 429,
+        };
+        let retries = AtomicU32::new(0);
+        let response = complete_with_retry(
+            &provider,
+            &ChatRequest::new("m", 10),
+            3,
+            Duration::from_millis(1),
+            |_, _| {
+                retries.fetch_add(1, Ordering::SeqCst);
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.text(), "ok");
+        assert_eq!(retries.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_does_not_retry_client_errors() {
+        let provider = FlakyProvider {
+            failures: AtomicU32::new(1),
+            status: 400,
+        };
+        let result = complete_with_retry(
+            &provider,
+            &ChatRequest::new("m", 10),
+            3,
+            Duration::from_millis(1),
+            |_, _| {},
+        )
+        .await;
+        assert_eq!(result.unwrap_err().status, Some(400));
+    }
+
+    #[test]
+    fn test_vision_check() {
+        let provider = FlakyProvider {
+            failures: AtomicU32::new(0),
+            status: 200,
+        };
+        let request = ChatRequest::new("m", 10).with_messages(vec![ChatMessage::user_parts(vec![
+            ContentPart::image("image/png", "AAAA"),
+        ])]);
+        assert!(check_vision(&provider, &request).is_err());
+        assert!(check_vision(&provider, &ChatRequest::new("m", 10)).is_ok());
+    }
+
+    #[test]
+    fn test_fast_model_detection() {
+        assert!(is_fast_model("claude-3-5-haiku-latest"));
+        assert!(is_fast_model("gpt-4o-mini"));
+        assert!(!is_fast_model("claude-sonnet-4-5"));
+    }
+}
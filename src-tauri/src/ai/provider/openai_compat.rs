@@ -0,0 +1,576 @@
+//! OpenAI Chat Completions compatible backend
+//!
+//! Speaks the `/chat/completions` wire format, which OpenAI, xAI and most
+//! self-hosted servers (llama.cpp `llama-server`, Ollama, vLLM, LM Studio)
+//! implement. Differences between them are limited to the token limit field,
+//! stream usage reporting and whether an API key is required.
+
+use super::sse::SseDecoder;
+use super::{
+    check_vision, is_fast_model, ChatRequest, ChatResponse, ContentPart, LlmProvider,
+    ProviderError, ProviderKind, Role, StopReason, StreamEvent, Usage,
+};
+use crate::ai::chat::tool_conversion::{
+    parse_openai_tool_call, tool_result_to_openai_message, tools_to_openai_format,
+};
+use crate::ai::http_client::{local_client, openai_client, send};
+use async_trait::async_trait;
+use futures::StreamExt;
+use reqwest::Client;
+use serde_json::{json, Value};
+
+/// Default OpenAI API base URL
+pub const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// Default xAI API base URL
+pub const XAI_BASE_URL: &str = "https://api.x.ai/v1";
+
+/// OpenAI-compatible chat completions backend
+pub struct OpenAICompatProvider {
+    kind: ProviderKind,
+    base_url: String,
+    api_key: Option<String>,
+    fast_model: String,
+    smart_model: String,
+    vision: bool,
+}
+
+impl OpenAICompatProvider {
+    /// Create a provider for an arbitrary OpenAI-compatible endpoint.
+    ///
+    /// `base_url` is the API root (e.g. `http://localhost:11434/v1`);
+    /// `/chat/completions` is appended. Claude model requests are mapped to
+    /// `fast_model` / `smart_model`.
+    pub fn new(
+        kind: ProviderKind,
+        base_url: impl Into<String>,
+        api_key: Option<String>,
+        fast_model: impl Into<String>,
+        smart_model: impl Into<String>,
+        vision: bool,
+    ) -> Self {
+        Self {
+            kind,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            api_key: api_key.filter(|k| !k.is_empty()),
+            fast_model: fast_model.into(),
+            smart_model: smart_model.into(),
+            vision,
+        }
+    }
+
+    /// OpenAI with its default models
+    pub fn openai(api_key: impl Into<String>) -> Self {
+        Self::new(
+            ProviderKind::OpenAI,
+            OPENAI_BASE_URL,
+            Some(api_key.into()),
+            "gpt-5-mini",
+            "gpt-5.1",
+            true,
+        )
+    }
+
+    /// xAI (Grok) with its default models
+    pub fn xai(api_key: impl Into<String>) -> Self {
+        Self::new(
+            ProviderKind::Xai,
+            XAI_BASE_URL,
+            Some(api_key.into()),
+            "grok-4-1-fast",
+            "grok-4-1-fast",
+            true,
+        )
+    }
+
+    fn endpoint(&self) -> String {
+        format!("{}/chat/completions", self.base_url)
+    }
+
+    fn client(&self) -> &'static Client {
+        match self.kind {
+            ProviderKind::Local => local_client(),
+            _ => openai_client(),
+        }
+    }
+
+    /// Build the JSON request body
+    fn request_body(&self, request: &ChatRequest, stream: bool) -> Value {
+        let mut messages = Vec::new();
+        if let Some(system) = &request.system {
+            messages.push(json!({ "role": "system", "content": system }));
+        }
+        for message in &request.messages {
+            messages.extend(message_to_json(message.role, &message.content));
+        }
+
+        let mut body = json!({
+            "model": self.resolve_model(&request.model),
+            "messages": messages,
+        });
+
+        // OpenAI's newer models reject `max_tokens`; everyone else expects it
+        let limit_field = match self.kind {
+            ProviderKind::OpenAI => "max_completion_tokens",
+            _ => "max_tokens",
+        };
+        body[limit_field] = json!(request.max_tokens);
+
+        if !request.tools.is_empty() {
+            let tools: Vec<Value> = request.tools.iter().map(|t| json!(t)).collect();
+            body["tools"] = json!(tools_to_openai_format(&tools));
+        }
+        if let Some(temperature) = request.temperature {
+            body["temperature"] = json!(temperature);
+        }
+        if stream {
+            body["stream"] = json!(true);
+            // Local servers vary in support for stream_options; skip it there
+            if self.kind != ProviderKind::Local {
+                body["stream_options"] = json!({ "include_usage": true });
+            }
+        }
+        body
+    }
+
+    async fn send(&self, body: &Value) -> Result<reqwest::Response, ProviderError> {
+        let mut builder = self
+            .client()
+            .post(self.endpoint())
+            .header("Content-Type", "application/json")
+            .json(body);
+        if let Some(key) = &self.api_key {
+            builder = builder.header("Authorization", format!("Bearer {}", key));
+        }
+
+        let response = send(builder).await.map_err(|e| {
+            if self.kind == ProviderKind::Local && e.is_connect() {
+                ProviderError::new(format!(
+                    "HTTP request failed: could not connect to local model server at {} ({})",
+                    self.base_url, e
+                ))
+            } else {
+                ProviderError::network(e)
+            }
+        })?;
+
+        if !response.status().is_success() {
+            return Err(ProviderError::from_response(self.kind.as_str(), response).await);
+        }
+        Ok(response)
+    }
+}
+
+/// Convert one neutral message into one or more chat completion messages.
+///
+/// Tool results become separate `tool` role messages; any remaining user
+/// content follows them as a regular user message.
+fn message_to_json(role: Role, content: &[ContentPart]) -> Vec<Value> {
+    let mut out = Vec::new();
+
+    match role {
+        Role::Assistant => {
+            let text: String = content
+                .iter()
+                .filter_map(|p| match p {
+                    ContentPart::Text { text, .. } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect();
+            let tool_calls: Vec<Value> = content
+                .iter()
+                .filter_map(|p| match p {
+                    ContentPart::ToolUse { id, name, input } => Some(json!({
+                        "id": id,
+                        "type": "function",
+                        "function": { "name": name, "arguments": input.to_string() },
+                    })),
+                    _ => None,
+                })
+                .collect();
+
+            let mut message = json!({
+                "role": "assistant",
+                "content": if text.is_empty() { Value::Null } else { json!(text) },
+            });
+            if !tool_calls.is_empty() {
+                message["tool_calls"] = json!(tool_calls);
+            }
+            out.push(message);
+        }
+        Role::User => {
+            let mut parts = Vec::new();
+            let mut has_image = false;
+            for part in content {
+                match part {
+                    ContentPart::ToolResult {
+                        tool_use_id,
+                        content,
+                        is_error,
+                    } => {
+                        out.push(tool_result_to_openai_message(
+                            tool_use_id,
+                            content,
+                            *is_error,
+                        ));
+                    }
+                    ContentPart::Text { text, .. } => {
+                        parts.push(json!({ "type": "text", "text": text }));
+                    }
+                    ContentPart::Image { mime_type, data } => {
+                        has_image = true;
+                        parts.push(json!({
+                            "type": "image_url",
+                            "image_url": { "url": format!("data:{};base64,{}", mime_type, data) },
+                        }));
+                    }
+                    ContentPart::ToolUse { .. } | ContentPart::Thinking { .. } => {}
+                }
+            }
+
+            if !parts.is_empty() {
+                // Plain string content is the most widely supported form
+                let content = if has_image {
+                    json!(parts)
+                } else {
+                    json!(parts
+                        .iter()
+                        .filter_map(|p| p["text"].as_str())
+                        .collect::<Vec<_>>()
+                        .join("\n"))
+                };
+                out.push(json!({ "role": "user", "content": content }));
+            }
+        }
+    }
+
+    out
+}
+
+fn parse_finish_reason(reason: Option<&str>, has_tool_calls: bool) -> StopReason {
+    match reason {
+        Some("tool_calls") | Some("function_call") => StopReason::ToolUse,
+        // Some servers report "stop" even when returning tool calls
+        _ if has_tool_calls => StopReason::ToolUse,
+        Some("stop") | None => StopReason::EndTurn,
+        Some("length") => StopReason::MaxTokens,
+        Some(other) => StopReason::Other(other.to_string()),
+    }
+}
+
+fn parse_usage(usage: &Value) -> Usage {
+    let field = |pointer: &str| usage.pointer(pointer).and_then(Value::as_u64).unwrap_or(0);
+    Usage {
+        input_tokens: field("/prompt_tokens"),
+        output_tokens: field("/completion_tokens"),
+        cache_creation_input_tokens: 0,
+        cache_read_input_tokens: field("/prompt_tokens_details/cached_tokens"),
+    }
+}
+
+/// Parse a non-streaming chat completion response body
+fn parse_response(body: &Value) -> (Vec<ContentPart>, StopReason) {
+    let choice = &body["choices"][0];
+    let message = &choice["message"];
+
+    let mut content = Vec::new();
+    if let Some(text) = message["content"].as_str().filter(|t| !t.is_empty()) {
+        content.push(ContentPart::text(text));
+    }
+    if let Some(calls) = message["tool_calls"].as_array() {
+        for call in calls {
+            let (id, name, input) = parse_openai_tool_call(call);
+            content.push(ContentPart::tool_use(id, name, input));
+        }
+    }
+
+    let has_tool_calls = content
+        .iter()
+        .any(|p| matches!(p, ContentPart::ToolUse { .. }));
+    let stop_reason = parse_finish_reason(choice["finish_reason"].as_str(), has_tool_calls);
+    (content, stop_reason)
+}
+
+/// A tool call being assembled from stream deltas
+#[derive(Default)]
+struct PartialToolCall {
+    id: String,
+    name: String,
+    arguments: String,
+}
+
+#[async_trait]
+impl LlmProvider for OpenAICompatProvider {
+    fn kind(&self) -> ProviderKind {
+        self.kind
+    }
+
+    fn supports_vision(&self) -> bool {
+        self.vision
+    }
+
+    fn resolve_model(&self, model: &str) -> String {
+        // Claude tiers map everywhere; a local server only knows its own models
+        let configured = model == self.fast_model || model == self.smart_model;
+        let foreign = model.is_empty()
+            || model.starts_with("claude")
+            || (self.kind == ProviderKind::Local && !configured);
+        if foreign {
+            if is_fast_model(model) {
+                self.fast_model.clone()
+            } else {
+                self.smart_model.clone()
+            }
+        } else {
+            model.to_string()
+        }
+    }
+
+    async fn complete(&self, request: &ChatRequest) -> Result<ChatResponse, ProviderError> {
+        check_vision(self, request)?;
+        let response = self.send(&self.request_body(request, false)).await?;
+        let headers = response.headers().clone();
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| ProviderError::new(format!("Failed to parse response: {}", e)))?;
+
+        let (content, stop_reason) = parse_response(&body);
+        Ok(ChatResponse {
+            content,
+            stop_reason,
+            usage: parse_usage(&body["usage"]),
+            headers,
+        })
+    }
+
+    async fn stream(
+        &self,
+        request: &ChatRequest,
+        on_event: &(dyn Fn(StreamEvent) + Send + Sync),
+    ) -> Result<ChatResponse, ProviderError> {
+        check_vision(self, request)?;
+        let response = self.send(&self.request_body(request, true)).await?;
+        let headers = response.headers().clone();
+
+        let mut decoder = SseDecoder::new();
+        let mut text = String::new();
+        let mut tool_calls: Vec<PartialToolCall> = Vec::new();
+        let mut finish_reason: Option<String> = None;
+        let mut usage = Usage::default();
+        let mut stream = response.bytes_stream();
+
+        'outer: while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| ProviderError::new(format!("Stream error: {}", e)))?;
+            for data in decoder.push(&chunk).map_err(ProviderError::new)? {
+                if data == "[DONE]" {
+                    break 'outer;
+                }
+                let Ok(event) = serde_json::from_str::<Value>(&data) else {
+                    continue;
+                };
+                if let Some(message) = event.pointer("/error/message").and_then(Value::as_str) {
+                    return Err(ProviderError::new(format!(
+                        "{} stream error: {}",
+                        self.kind.as_str(),
+                        message
+                    )));
+                }
+                if event["usage"].is_object() {
+                    usage = parse_usage(&event["usage"]);
+                }
+
+                let choice = &event["choices"][0];
+                if let Some(reason) = choice["finish_reason"].as_str() {
+                    finish_reason = Some(reason.to_string());
+                }
+                let delta = &choice["delta"];
+
+                if let Some(chunk) = delta["content"].as_str().filter(|c| !c.is_empty()) {
+                    text.push_str(chunk);
+                    on_event(StreamEvent::TextDelta(chunk.to_string()));
+                }
+                // Reasoning models served by xAI, vLLM and llama.cpp
+                if let Some(chunk) = delta["reasoning_content"]
+                    .as_str()
+                    .filter(|c| !c.is_empty())
+                {
+                    on_event(StreamEvent::ThinkingDelta(chunk.to_string()));
+                }
+
+                if let Some(calls) = delta["tool_calls"].as_array() {
+                    for call in calls {
+                        let index = call["index"].as_u64().unwrap_or(0) as usize;
+                        while tool_calls.len() <= index {
+                            tool_calls.push(PartialToolCall::default());
+                        }
+                        let entry = &mut tool_calls[index];
+                        if let Some(id) = call["id"].as_str() {
+                            entry.id = id.to_string();
+                        }
+                        if let Some(name) = call.pointer("/function/name").and_then(Value::as_str) {
+                            if entry.name.is_empty() {
+                                entry.name = name.to_string();
+                                on_event(StreamEvent::ToolUseStart {
+                                    id: entry.id.clone(),
+                                    name: entry.name.clone(),
+                                });
+                            }
+                        }
+                        if let Some(args) =
+                            call.pointer("/function/arguments").and_then(Value::as_str)
+                        {
+                            entry.arguments.push_str(args);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut content = Vec::new();
+        if !text.is_empty() {
+            content.push(ContentPart::text(text));
+        }
+        for (index, call) in tool_calls.into_iter().enumerate() {
+            let input = if call.arguments.trim().is_empty() {
+                json!({})
+            } else {
+                serde_json::from_str(&call.arguments).unwrap_or_else(|_| json!({}))
+            };
+            // Some local servers omit tool call IDs
+            let id = if call.id.is_empty() {
+                format!("call_{}", index)
+            } else {
+                call.id
+            };
+            content.push(ContentPart::tool_use(id, call.name, input));
+        }
+
+        let has_tool_calls = content
+            .iter()
+            .any(|p| matches!(p, ContentPart::ToolUse { .. }));
+        Ok(ChatResponse {
+            content,
+            stop_reason: parse_finish_reason(finish_reason.as_deref(), has_tool_calls),
+            usage,
+            headers,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::provider::ChatMessage;
+    use crate::ai::tools::ToolDefinition;
+
+    fn local() -> OpenAICompatProvider {
+        OpenAICompatProvider::new(
+            ProviderKind::Local,
+            "http://localhost:11434/v1/",
+            None,
+            "qwen3:4b",
+            "qwen3:32b",
+            false,
+        )
+    }
+
+    #[test]
+    fn test_model_resolution() {
+        let provider = local();
+        assert_eq!(
+            provider.resolve_model("claude-3-5-haiku-latest"),
+            "qwen3:4b"
+        );
+        assert_eq!(provider.resolve_model("claude-sonnet-4-5"), "qwen3:32b");
+        assert_eq!(provider.resolve_model("qwen3:32b"), "qwen3:32b");
+        assert_eq!(provider.resolve_model("grok-4-1-fast"), "qwen3:4b");
+        assert_eq!(
+            OpenAICompatProvider::openai("key").resolve_model("gpt-4o"),
+            "gpt-4o"
+        );
+        assert_eq!(
+            provider.endpoint(),
+            "http://localhost:11434/v1/chat/completions"
+        );
+    }
+
+    #[test]
+    fn test_request_body_tool_round_trip() {
+        let provider = local();
+        let request = ChatRequest::new("claude-sonnet-4-5", 256)
+            .with_system("sys")
+            .with_tools(vec![ToolDefinition {
+                name: "grep".to_string(),
+                description: "Search".to_string(),
+                input_schema: json!({ "type": "object" }),
+            }])
+            .with_messages(vec![
+                ChatMessage::user("find x"),
+                ChatMessage::assistant_parts(vec![ContentPart::tool_use(
+                    "c1",
+                    "grep",
+                    json!({ "pattern": "x" }),
+                )]),
+                ChatMessage::user_parts(vec![
+                    ContentPart::tool_result("c1", "no matches", false),
+                    ContentPart::text("keep going"),
+                ]),
+            ]);
+
+        let body = provider.request_body(&request, true);
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages[0]["role"], "system");
+        assert_eq!(messages[1]["content"], "find x");
+        assert_eq!(messages[2]["content"], Value::Null);
+        assert_eq!(
+            messages[2]["tool_calls"][0]["function"]["arguments"],
+            "{\"pattern\":\"x\"}"
+        );
+        assert_eq!(messages[3]["role"], "tool");
+        assert_eq!(messages[3]["tool_call_id"], "c1");
+        assert_eq!(messages[4]["content"], "keep going");
+        assert_eq!(body["max_tokens"], 256);
+        assert_eq!(body["tools"][0]["function"]["name"], "grep");
+        assert!(body.get("stream_options").is_none());
+    }
+
+    #[test]
+    fn test_openai_uses_completion_token_field_and_images() {
+        let provider = OpenAICompatProvider::openai("key");
+        let request =
+            ChatRequest::new("gpt-5.1", 64).with_messages(vec![ChatMessage::user_parts(vec![
+                ContentPart::text("what is this"),
+                ContentPart::image("image/png", "AAAA"),
+            ])]);
+        let body = provider.request_body(&request, false);
+        assert_eq!(body["max_completion_tokens"], 64);
+        assert_eq!(
+            body["messages"][0]["content"][1]["image_url"]["url"],
+            "data:image/png;base64,AAAA"
+        );
+    }
+
+    #[test]
+    fn test_parse_response_with_tool_calls() {
+        let body = json!({
+            "choices": [{
+                "message": {
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "c1",
+                        "type": "function",
+                        "function": { "name": "grep", "arguments": "{\"pattern\":\"x\"}" }
+                    }]
+                },
+                "finish_reason": "stop"
+            }]
+        });
+        let (content, stop_reason) = parse_response(&body);
+        assert_eq!(stop_reason, StopReason::ToolUse);
+        assert_eq!(
+            content[0],
+            ContentPart::tool_use("c1", "grep", json!({ "pattern": "x" }))
+        );
+    }
+}
@@ -0,0 +1,371 @@
+//! Provider selection and persistence.
+//!
+//! The active backend is stored at `~/.config/sentinel/llm_provider.json`.
+//! For headless runs (CLI, CI) the following environment variables override
+//! the saved settings:
+//!
+//! - `SENTINEL_LLM_PROVIDER` - `anthropic`, `openai`, `xai` or `local`
+//! - `SENTINEL_LLM_BASE_URL` - API root of an OpenAI-compatible server
+//! - `SENTINEL_LLM_MODEL` - model used for both fast and smart requests
+//! - `SENTINEL_LLM_API_KEY` - bearer token for the local server, if any
+
+use super::{AnthropicProvider, LlmProvider, OpenAICompatProvider};
+use crate::ai::credentials::CredentialManager;
+use crate::wal::io::atomic_write;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+/// Settings filename inside the sentinel config directory
+const SETTINGS_FILENAME: &str = "llm_provider.json";
+
+/// Default base URL for the local provider (Ollama's OpenAI endpoint)
+pub const DEFAULT_LOCAL_BASE_URL: &str = "http://localhost:11434/v1";
+
+/// Saved settings, loaded on first use
+static SETTINGS: Lazy<RwLock<LlmSettings>> = Lazy::new(|| RwLock::new(LlmSettings::load()));
+
+/// Model backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderKind {
+    #[default]
+    Anthropic,
+    #[serde(rename = "openai")]
+    OpenAI,
+    Xai,
+    /// Self-hosted OpenAI-compatible server (llama.cpp, Ollama, vLLM)
+    Local,
+}
+
+impl ProviderKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProviderKind::Anthropic => "anthropic",
+            ProviderKind::OpenAI => "openai",
+            ProviderKind::Xai => "xai",
+            ProviderKind::Local => "local",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "anthropic" | "claude" => Some(ProviderKind::Anthropic),
+            "openai" => Some(ProviderKind::OpenAI),
+            "xai" | "grok" => Some(ProviderKind::Xai),
+            "local" => Some(ProviderKind::Local),
+            _ => None,
+        }
+    }
+}
+
+/// User-selected LLM backend
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LlmSettings {
+    /// Backend used for app-wide requests
+    #[serde(default)]
+    pub provider: ProviderKind,
+    /// API root for the local provider (defaults to `DEFAULT_LOCAL_BASE_URL`)
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Model used where the app asks for a fast model (Haiku tier)
+    #[serde(default)]
+    pub fast_model: Option<String>,
+    /// Model used where the app asks for a capable model (Sonnet tier)
+    #[serde(default)]
+    pub smart_model: Option<String>,
+    /// Whether the local model accepts images
+    #[serde(default)]
+    pub vision: bool,
+}
+
+impl Default for LlmSettings {
+    fn default() -> Self {
+        Self {
+            provider: ProviderKind::Anthropic,
+            base_url: None,
+            fast_model: None,
+            smart_model: None,
+            vision: false,
+        }
+    }
+}
+
+impl LlmSettings {
+    fn default_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("sentinel")
+            .join(SETTINGS_FILENAME)
+    }
+
+    /// Load saved settings, falling back to defaults
+    fn load() -> Self {
+        match Self::load_from(&Self::default_path()) {
+            Ok(settings) => settings,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to load LLM provider settings");
+                Self::default()
+            }
+        }
+    }
+
+    fn load_from(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read LLM settings: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse LLM settings: {}", e))
+    }
+
+    fn save_to(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create config directory: {}", e))?;
+        }
+        let json = serde_json::to_vec_pretty(self)
+            .map_err(|e| format!("Failed to serialize LLM settings: {}", e))?;
+        atomic_write(path, &json)?;
+        Ok(())
+    }
+
+    /// Check that the settings describe a usable backend
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(url) = &self.base_url {
+            let url = url.trim();
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                return Err(format!(
+                    "Base URL must start with http:// or https:// (got '{}')",
+                    url
+                ));
+            }
+        }
+        if self.provider == ProviderKind::Local
+            && self.fast_model.as_deref().unwrap_or("").trim().is_empty()
+            && self.smart_model.as_deref().unwrap_or("").trim().is_empty()
+        {
+            return Err("The local provider needs at least one model name".to_string());
+        }
+        Ok(())
+    }
+
+    /// Apply `SENTINEL_LLM_*` environment overrides
+    fn with_env_overrides(mut self) -> Self {
+        if let Ok(name) = std::env::var("SENTINEL_LLM_PROVIDER") {
+            match ProviderKind::from_name(&name) {
+                Some(kind) => self.provider = kind,
+                None => tracing::warn!(provider = %name, "Ignoring unknown SENTINEL_LLM_PROVIDER"),
+            }
+        }
+        if let Ok(url) = std::env::var("SENTINEL_LLM_BASE_URL") {
+            self.base_url = Some(url);
+            // A base URL alone implies a local server
+            if std::env::var("SENTINEL_LLM_PROVIDER").is_err() {
+                self.provider = ProviderKind::Local;
+            }
+        }
+        if let Ok(model) = std::env::var("SENTINEL_LLM_MODEL") {
+            self.fast_model = Some(model.clone());
+            self.smart_model = Some(model);
+        }
+        self
+    }
+
+    /// Fast and smart model names, each falling back to the other
+    fn models(&self, default_fast: &str, default_smart: &str) -> (String, String) {
+        let non_empty = |m: &Option<String>| {
+            m.as_ref()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+        let fast = non_empty(&self.fast_model);
+        let smart = non_empty(&self.smart_model);
+        (
+            fast.clone()
+                .or_else(|| smart.clone())
+                .unwrap_or_else(|| default_fast.to_string()),
+            smart.or(fast).unwrap_or_else(|| default_smart.to_string()),
+        )
+    }
+}
+
+/// Current settings (saved settings with environment overrides applied)
+pub fn current_settings() -> LlmSettings {
+    let saved = SETTINGS.read().map(|s| s.clone()).unwrap_or_default();
+    saved.with_env_overrides()
+}
+
+/// Saved settings, without environment overrides
+pub fn saved_settings() -> LlmSettings {
+    SETTINGS.read().map(|s| s.clone()).unwrap_or_default()
+}
+
+/// Validate, persist and activate new settings
+pub fn save_settings(settings: LlmSettings) -> Result<(), String> {
+    settings.validate()?;
+    settings.save_to(&LlmSettings::default_path())?;
+    let mut current = SETTINGS
+        .write()
+        .map_err(|_| "LLM settings lock poisoned".to_string())?;
+    *current = settings;
+    Ok(())
+}
+
+/// Stored API key for `provider`
+#[cfg(not(any(test, feature = "cassette")))]
+pub(crate) fn api_key(provider: &str) -> Result<String, String> {
+    CredentialManager::get_api_key(provider)
+}
+
+/// Stored API key, or a placeholder while replaying an HTTP cassette
+/// (replayed requests never reach the provider)
+#[cfg(any(test, feature = "cassette"))]
+pub(crate) fn api_key(provider: &str) -> Result<String, String> {
+    CredentialManager::get_api_key(provider).or_else(|e| {
+        if crate::ai::cassette::is_replaying() {
+            Ok("cassette-replay".to_string())
+        } else {
+            Err(e)
+        }
+    })
+}
+
+/// xAI key from the environment or the keychain
+fn xai_api_key() -> Result<String, String> {
+    std::env::var("XAI_API_KEY")
+        .or_else(|_| std::env::var("GROK_API_KEY"))
+        .or_else(|_| std::env::var("VITE_XAI_API_KEY"))
+        .or_else(|_| api_key("xai"))
+        .map_err(|_| {
+            "No Grok API key found. Set XAI_API_KEY in .env or configure in settings.".to_string()
+        })
+}
+
+fn build_provider(
+    settings: &LlmSettings,
+    kind: ProviderKind,
+) -> Result<Arc<dyn LlmProvider>, String> {
+    let provider: Arc<dyn LlmProvider> = match kind {
+        ProviderKind::Anthropic => Arc::new(AnthropicProvider::from_credentials()?),
+        ProviderKind::OpenAI => Arc::new(OpenAICompatProvider::openai(api_key("openai")?)),
+        ProviderKind::Xai => Arc::new(OpenAICompatProvider::xai(xai_api_key()?)),
+        ProviderKind::Local => {
+            let (fast, smart) = settings.models("", "");
+            let base_url = settings
+                .base_url
+                .clone()
+                .filter(|u| !u.trim().is_empty())
+                .unwrap_or_else(|| DEFAULT_LOCAL_BASE_URL.to_string());
+            let api_key = std::env::var("SENTINEL_LLM_API_KEY")
+                .ok()
+                .or_else(|| CredentialManager::get_api_key("local").ok());
+            Arc::new(OpenAICompatProvider::new(
+                ProviderKind::Local,
+                base_url.trim(),
+                api_key,
+                fast,
+                smart,
+                settings.vision,
+            ))
+        }
+    };
+    Ok(provider)
+}
+
+/// The provider selected in settings, used for all app-wide requests
+pub fn active_provider() -> Result<Arc<dyn LlmProvider>, String> {
+    let settings = current_settings();
+    build_provider(&settings, settings.provider)
+}
+
+/// A specific provider, regardless of the active selection.
+///
+/// Used when the user explicitly picks a cloud model (e.g. a `gpt-` model
+/// in chat).
+pub fn provider_for(kind: ProviderKind) -> Result<Arc<dyn LlmProvider>, String> {
+    build_provider(&current_settings(), kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_settings_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("sentinel").join(SETTINGS_FILENAME);
+
+        let settings = LlmSettings {
+            provider: ProviderKind::Local,
+            base_url: Some("http://127.0.0.1:8080/v1".to_string()),
+            fast_model: Some("qwen3:4b".to_string()),
+            smart_model: None,
+            vision: true,
+        };
+        settings.save_to(&path).unwrap();
+        assert_eq!(LlmSettings::load_from(&path).unwrap(), settings);
+
+        let json = fs::read_to_string(&path).unwrap();
+        assert!(json.contains("\"provider\": \"local\""));
+        assert!(json.contains("baseUrl"));
+    }
+
+    #[test]
+    fn test_missing_file_uses_defaults() {
+        let dir = TempDir::new().unwrap();
+        let settings = LlmSettings::load_from(&dir.path().join("missing.json")).unwrap();
+        assert_eq!(settings.provider, ProviderKind::Anthropic);
+    }
+
+    #[test]
+    fn test_validation() {
+        let mut settings = LlmSettings {
+            provider: ProviderKind::Local,
+            base_url: Some("localhost:11434".to_string()),
+            fast_model: Some("llama3.1:8b".to_string()),
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err());
+
+        settings.base_url = Some("http://localhost:11434/v1".to_string());
+        assert!(settings.validate().is_ok());
+
+        settings.fast_model = None;
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn test_models_fall_back_to_each_other() {
+        let settings = LlmSettings {
+            smart_model: Some("llama3.3:70b".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            settings.models("a", "b"),
+            ("llama3.3:70b".to_string(), "llama3.3:70b".to_string())
+        );
+        assert_eq!(
+            LlmSettings::default().models("a", "b"),
+            ("a".to_string(), "b".to_string())
+        );
+    }
+
+    #[test]
+    fn test_provider_kind_names() {
+        assert_eq!(ProviderKind::from_name("Grok"), Some(ProviderKind::Xai));
+        assert_eq!(
+            ProviderKind::from_name("openai"),
+            Some(ProviderKind::OpenAI)
+        );
+        assert_eq!(
+            serde_json::to_string(&ProviderKind::OpenAI).unwrap(),
+            "\"openai\""
+        );
+        assert_eq!(ProviderKind::from_name("bedrock"), None);
+    }
+}
@@ -0,0 +1,61 @@
+//! Minimal Server-Sent Events decoder shared by the streaming backends.
+//!
+//! Buffers raw bytes (so multi-byte UTF-8 split across chunks is decoded
+//! correctly) and yields the payload of each complete `data:` line.
+
+/// Maximum buffered bytes without a newline before the stream is rejected
+const MAX_LINE_BYTES: usize = 4 * 1024 * 1024;
+
+/// Incremental SSE line decoder
+#[derive(Default)]
+pub(crate) struct SseDecoder {
+    buffer: Vec<u8>,
+}
+
+impl SseDecoder {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a chunk and return the `data:` payloads of all completed lines
+    pub(crate) fn push(&mut self, chunk: &[u8]) -> Result<Vec<String>, String> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut payloads = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\r', '\n']);
+            if let Some(data) = line.strip_prefix("data:") {
+                payloads.push(data.trim_start().to_string());
+            }
+        }
+
+        if self.buffer.len() > MAX_LINE_BYTES {
+            return Err("Stream line exceeded maximum size".to_string());
+        }
+
+        Ok(payloads)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_lines_and_utf8() {
+        let mut decoder = SseDecoder::new();
+        let bytes = "event: x\ndata: {\"t\":\"é\"}\r\n\ndata: [DONE]\n".as_bytes();
+        // Split inside the two-byte 'é'
+        let split = bytes.iter().position(|&b| b == 0xC3).unwrap() + 1;
+
+        let first = decoder.push(&bytes[..split]).unwrap();
+        assert!(first.is_empty());
+        let rest = decoder.push(&bytes[split..]).unwrap();
+        assert_eq!(
+            rest,
+            vec!["{\"t\":\"é\"}".to_string(), "[DONE]".to_string()]
+        );
+    }
+}
@@ -19,6 +19,8 @@ pub enum Expression {
     Comparison(Comparison),
     /// Function call: file.function(args) or file.field.function(args)
     FunctionCall(FunctionCall),
+    /// Comparison between computed operands: file.modifiedAt < now() - 90d
+    OperandComparison(OperandComparison),
     /// Boolean literal: true or false
     Literal(bool),
 }
@@ -34,6 +36,55 @@ pub struct Comparison {
     pub value: Value,
 }
 
+/// Comparison where either side may be a field, a value function or
+/// arithmetic, e.g. `file.size / 1MB > 10` or
+/// `file.modifiedAt.month == file.createdAt.month`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OperandComparison {
+    pub left: Operand,
+    pub op: ComparisonOp,
+    pub right: Operand,
+}
+
+/// A numeric or literal operand in a computed comparison.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Operand {
+    /// File field: file.size, file.modifiedAt.year
+    Field(Field),
+    /// Literal value: 10, 5MB, 90d, '2024-01-01'
+    Literal(Value),
+    /// Value function: now(), date('2024-01-01')
+    Call(FunctionName, Vec<Value>),
+    /// Arithmetic: left op right
+    Arithmetic(Box<Operand>, ArithmeticOp, Box<Operand>),
+}
+
+/// Arithmetic operators on numeric operands.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ArithmeticOp {
+    /// Addition: +
+    Add,
+    /// Subtraction: -
+    Sub,
+    /// Multiplication: *
+    Mul,
+    /// Division: /
+    Div,
+}
+
+impl ArithmeticOp {
+    /// Apply the operator, failing on division by zero
+    pub fn apply(&self, left: f64, right: f64) -> Option<f64> {
+        match self {
+            ArithmeticOp::Add => Some(left + right),
+            ArithmeticOp::Sub => Some(left - right),
+            ArithmeticOp::Mul => Some(left * right),
+            ArithmeticOp::Div if right == 0.0 => None,
+            ArithmeticOp::Div => Some(left / right),
+        }
+    }
+}
+
 /// Supported comparison operators.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ComparisonOp {
@@ -53,6 +104,8 @@ pub enum ComparisonOp {
     In,
     /// Value matches regex pattern: MATCHES 'pattern'
     Matches,
+    /// Text or list contains a value: CONTAINS 'text'
+    Contains,
 }
 
 impl ComparisonOp {
@@ -89,12 +142,55 @@ pub enum Field {
     FileMimeType,
     /// Whether file is hidden: file.isHidden
     FileIsHidden,
+    /// Part of the modified timestamp in local time: file.modifiedAt.year
+    FileModifiedPart(DatePart),
+    /// Part of the created timestamp in local time: file.createdAt.month
+    FileCreatedPart(DatePart),
+    /// Extracted document text: file.content
+    FileContent,
+    /// Cached analysis summary: file.summary
+    FileSummary,
+    /// Cached document classification (invoice, contract, ...): file.docType
+    FileDocType,
+    /// Cached key entities (people, companies, amounts): file.entities
+    FileEntities,
+    /// Page count of parsed documents: file.pageCount
+    FilePageCount,
+    /// Title from document metadata (or email subject): file.title
+    FileTitle,
+    /// Author from document metadata (or email sender): file.author
+    FileAuthor,
+    /// Names of the files inside a ZIP/TAR/7z archive: file.archive
+    FileArchive,
+    /// Photo capture time from EXIF/XMP (unix ms): file.exif.takenAt
+    ExifTakenAt,
+    /// Part of the capture time in local time: file.exif.takenAt.year
+    ExifTakenPart(DatePart),
+    /// Camera make and model: file.exif.camera
+    ExifCamera,
+    /// Lens model: file.exif.lens
+    ExifLens,
+    /// Image width in pixels: file.exif.width
+    ExifWidth,
+    /// Image height in pixels: file.exif.height
+    ExifHeight,
+    /// GPS latitude in decimal degrees: file.exif.latitude
+    ExifLatitude,
+    /// GPS longitude in decimal degrees: file.exif.longitude
+    ExifLongitude,
 }
 
 impl Field {
     /// Parse field from string identifier.
-    /// Supports both camelCase and snake_case variants.
+    /// Supports both camelCase and snake_case variants, and date parts
+    /// such as `modifiedAt.year`. Photo fields are prefixed with `exif.`.
     pub fn from_str(s: &str) -> Option<Self> {
+        if s.get(..5).is_some_and(|p| p.eq_ignore_ascii_case("exif.")) {
+            return Field::exif_from_str(&s[5..]);
+        }
+        if let Some((base, part)) = s.split_once('.') {
+            return Field::from_str(base)?.with_date_part(DatePart::from_str(part)?);
+        }
         match s.to_lowercase().as_str() {
             "name" | "filename" => Some(Field::FileName),
             "ext" | "extension" => Some(Field::FileExt),
@@ -104,6 +200,32 @@ impl Field {
             "createdat" | "created_at" | "created" | "ctime" => Some(Field::FileCreatedAt),
             "mimetype" | "mime_type" | "mime" => Some(Field::FileMimeType),
             "ishidden" | "is_hidden" | "hidden" => Some(Field::FileIsHidden),
+            "content" | "text" => Some(Field::FileContent),
+            "summary" => Some(Field::FileSummary),
+            "doctype" | "doc_type" | "documenttype" | "document_type" => Some(Field::FileDocType),
+            "entities" | "key_entities" => Some(Field::FileEntities),
+            "pagecount" | "page_count" | "pages" => Some(Field::FilePageCount),
+            "title" | "doctitle" | "doc_title" => Some(Field::FileTitle),
+            "author" | "creator" => Some(Field::FileAuthor),
+            "archive" | "archive_members" | "archivemembers" => Some(Field::FileArchive),
+            _ => None,
+        }
+    }
+
+    fn exif_from_str(s: &str) -> Option<Self> {
+        if let Some((base, part)) = s.split_once('.') {
+            return Field::exif_from_str(base)?.with_date_part(DatePart::from_str(part)?);
+        }
+        match s.to_lowercase().as_str() {
+            "takenat" | "taken_at" | "taken" | "datetaken" | "date_taken" => {
+                Some(Field::ExifTakenAt)
+            }
+            "camera" | "model" => Some(Field::ExifCamera),
+            "lens" => Some(Field::ExifLens),
+            "width" => Some(Field::ExifWidth),
+            "height" => Some(Field::ExifHeight),
+            "latitude" | "lat" => Some(Field::ExifLatitude),
+            "longitude" | "lon" | "lng" => Some(Field::ExifLongitude),
             _ => None,
         }
     }
@@ -119,8 +241,141 @@ impl Field {
             Field::FileCreatedAt => "createdAt",
             Field::FileMimeType => "mimeType",
             Field::FileIsHidden => "isHidden",
+            Field::FileModifiedPart(part) => match part {
+                DatePart::Year => "modifiedAt.year",
+                DatePart::Month => "modifiedAt.month",
+                DatePart::Day => "modifiedAt.day",
+                DatePart::Weekday => "modifiedAt.weekday",
+                DatePart::Hour => "modifiedAt.hour",
+            },
+            Field::FileCreatedPart(part) => match part {
+                DatePart::Year => "createdAt.year",
+                DatePart::Month => "createdAt.month",
+                DatePart::Day => "createdAt.day",
+                DatePart::Weekday => "createdAt.weekday",
+                DatePart::Hour => "createdAt.hour",
+            },
+            Field::FileContent => "content",
+            Field::FileSummary => "summary",
+            Field::FileDocType => "docType",
+            Field::FileEntities => "entities",
+            Field::FilePageCount => "pageCount",
+            Field::FileTitle => "title",
+            Field::FileAuthor => "author",
+            Field::FileArchive => "archive",
+            Field::ExifTakenAt => "exif.takenAt",
+            Field::ExifTakenPart(part) => match part {
+                DatePart::Year => "exif.takenAt.year",
+                DatePart::Month => "exif.takenAt.month",
+                DatePart::Day => "exif.takenAt.day",
+                DatePart::Weekday => "exif.takenAt.weekday",
+                DatePart::Hour => "exif.takenAt.hour",
+            },
+            Field::ExifCamera => "exif.camera",
+            Field::ExifLens => "exif.lens",
+            Field::ExifWidth => "exif.width",
+            Field::ExifHeight => "exif.height",
+            Field::ExifLatitude => "exif.latitude",
+            Field::ExifLongitude => "exif.longitude",
         }
     }
+
+    /// Whether the field is read from document content rather than
+    /// filesystem metadata
+    pub fn is_content(&self) -> bool {
+        matches!(
+            self,
+            Field::FileContent
+                | Field::FileSummary
+                | Field::FileDocType
+                | Field::FileEntities
+                | Field::FilePageCount
+                | Field::FileTitle
+                | Field::FileAuthor
+                | Field::FileArchive
+        )
+    }
+
+    /// Whether the field is read from photo metadata (EXIF/XMP)
+    pub fn is_photo(&self) -> bool {
+        matches!(
+            self,
+            Field::ExifTakenAt
+                | Field::ExifTakenPart(_)
+                | Field::ExifCamera
+                | Field::ExifLens
+                | Field::ExifWidth
+                | Field::ExifHeight
+                | Field::ExifLatitude
+                | Field::ExifLongitude
+        )
+    }
+
+    /// Whether the field holds a timestamp (unix ms)
+    pub fn is_timestamp(&self) -> bool {
+        matches!(
+            self,
+            Field::FileModifiedAt | Field::FileCreatedAt | Field::ExifTakenAt
+        )
+    }
+
+    /// The given part of a timestamp field, or None for other fields
+    pub fn with_date_part(&self, part: DatePart) -> Option<Self> {
+        match self {
+            Field::FileModifiedAt => Some(Field::FileModifiedPart(part)),
+            Field::FileCreatedAt => Some(Field::FileCreatedPart(part)),
+            Field::ExifTakenAt => Some(Field::ExifTakenPart(part)),
+            _ => None,
+        }
+    }
+}
+
+/// Calendar component of a timestamp, evaluated in local time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DatePart {
+    /// Four-digit year
+    Year,
+    /// Month, 1 (January) to 12
+    Month,
+    /// Day of month, 1 to 31
+    Day,
+    /// ISO weekday, 1 (Monday) to 7 (Sunday)
+    Weekday,
+    /// Hour of day, 0 to 23
+    Hour,
+}
+
+impl DatePart {
+    /// Parse date part from string identifier.
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "year" => Some(DatePart::Year),
+            "month" => Some(DatePart::Month),
+            "day" | "dayofmonth" | "day_of_month" => Some(DatePart::Day),
+            "weekday" | "dayofweek" | "day_of_week" => Some(DatePart::Weekday),
+            "hour" => Some(DatePart::Hour),
+            _ => None,
+        }
+    }
+
+    /// Numeric value for a month or weekday name ('march', 'sat', ...)
+    pub fn value_for_name(&self, name: &str) -> Option<f64> {
+        const MONTHS: [&str; 12] = [
+            "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+        ];
+        const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+        let name = name.trim().to_lowercase();
+        let names: &[&str] = match self {
+            DatePart::Month => &MONTHS,
+            DatePart::Weekday => &WEEKDAYS,
+            _ => return None,
+        };
+        names
+            .iter()
+            .position(|n| name.len() >= 3 && name.starts_with(n))
+            .map(|i| (i + 1) as f64)
+    }
 }
 
 /// Function call on a file or field.
@@ -147,6 +402,10 @@ pub enum FunctionName {
     Matches,
     /// Semantic similarity score (0.0-1.0): file.vector_similarity('query')
     VectorSimilarity,
+    /// Current time in unix ms: now()
+    Now,
+    /// Date literal in unix ms: date('2024-01-01')
+    Date,
 }
 
 impl FunctionName {
@@ -160,10 +419,18 @@ impl FunctionName {
             "vector_similarity" | "vectorsimilarity" | "similarity" => {
                 Some(FunctionName::VectorSimilarity)
             }
+            "now" => Some(FunctionName::Now),
+            "date" => Some(FunctionName::Date),
             _ => None,
         }
     }
 
+    /// Whether the function produces a value for comparisons rather than
+    /// a match result
+    pub fn is_value_function(&self) -> bool {
+        matches!(self, FunctionName::Now | FunctionName::Date)
+    }
+
     /// Get the canonical name for this function
     pub fn canonical_name(&self) -> &'static str {
         match self {
@@ -172,6 +439,8 @@ impl FunctionName {
             FunctionName::EndsWith => "endsWith",
             FunctionName::Matches => "matches",
             FunctionName::VectorSimilarity => "vector_similarity",
+            FunctionName::Now => "now",
+            FunctionName::Date => "date",
         }
     }
 }
@@ -189,6 +458,8 @@ pub enum Value {
     Array(Vec<Value>),
     /// Size in bytes with unit: 10KB, 5MB, 1GB
     SizeBytes(u64),
+    /// Time span in milliseconds with unit: 90d, 2w, 12h
+    Duration(i64),
     /// Null/None value
     Null,
 }
@@ -201,6 +472,7 @@ impl Value {
             Value::Number(n) => Some(n.to_string()),
             Value::Boolean(b) => Some(b.to_string()),
             Value::SizeBytes(b) => Some(b.to_string()),
+            Value::Duration(ms) => Some(ms.to_string()),
             Value::Array(_) | Value::Null => None,
         }
     }
@@ -210,6 +482,7 @@ impl Value {
         match self {
             Value::Number(n) => Some(*n),
             Value::SizeBytes(b) => Some(*b as f64),
+            Value::Duration(ms) => Some(*ms as f64),
             Value::String(s) => s.parse().ok(),
             _ => None,
         }
@@ -258,6 +531,26 @@ mod tests {
         assert_eq!(Field::from_str("unknown"), None);
     }
 
+    #[test]
+    fn test_date_part_fields() {
+        assert_eq!(
+            Field::from_str("modifiedAt.year"),
+            Some(Field::FileModifiedPart(DatePart::Year))
+        );
+        assert_eq!(
+            Field::from_str("created_at.weekday"),
+            Some(Field::FileCreatedPart(DatePart::Weekday))
+        );
+        assert_eq!(Field::from_str("size.year"), None);
+        assert_eq!(
+            Field::FileCreatedPart(DatePart::Month).canonical_name(),
+            "createdAt.month"
+        );
+        assert_eq!(DatePart::Weekday.value_for_name("Saturday"), Some(6.0));
+        assert_eq!(DatePart::Month.value_for_name("mar"), Some(3.0));
+        assert_eq!(DatePart::Month.value_for_name("m"), None);
+    }
+
     #[test]
     fn test_function_parsing() {
         assert_eq!(
@@ -0,0 +1,292 @@
+//! Document content for content-aware rule fields.
+//!
+//! Fields such as `file.content`, `file.docType` and `file.entities` are not
+//! part of the filesystem metadata in [`VirtualFile`]. The evaluator asks a
+//! [`ContentSource`] for them only when a rule references one, so rules that
+//! stick to name/ext/size never open a document.
+//!
+//! [`DocumentContentSource`] extracts text with the grok document parser and
+//! reads classifications from the grok content cache. It never calls an LLM:
+//! files that were not analyzed yet simply have no `docType`/`entities`.
+//! Photo fields (`file.exif.*`) are read from EXIF/XMP the same lazy way,
+//! and `file.archive` lists archive members without extracting anything.
+
+use super::evaluator::VirtualFile;
+use crate::ai::grok::document_parser::DocumentParser;
+use crate::ai::grok::ContentCache;
+use crate::archive::{self, ArchiveListing};
+use crate::media::{self, PhotoMetadata};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Text extracted from a document
+#[derive(Debug, Clone, Default)]
+pub struct DocumentContent {
+    /// Extracted text
+    pub text: String,
+    /// Number of pages, when the format has pages
+    pub page_count: Option<u32>,
+    /// Title from the document's metadata
+    pub title: Option<String>,
+    /// Author from the document's metadata
+    pub author: Option<String>,
+}
+
+/// Cached analysis of a document
+#[derive(Debug, Clone, Default)]
+pub struct ContentAnalysis {
+    /// Document classification, e.g. "invoice"
+    pub doc_type: String,
+    /// Key entities (people, companies, dates, amounts)
+    pub entities: Vec<String>,
+    /// Short summary of the content
+    pub summary: String,
+}
+
+/// Lazily resolved document content for rule evaluation.
+/// Implementations should memoize, since one rule is evaluated against many files
+/// and several rules may reference the same file.
+pub trait ContentSource: Send + Sync {
+    /// Extracted text of the file, or None if it cannot be parsed
+    fn document(&self, file: &VirtualFile) -> Option<Arc<DocumentContent>>;
+
+    /// Cached analysis of the file, or None if it was never analyzed
+    fn analysis(&self, file: &VirtualFile) -> Option<Arc<ContentAnalysis>>;
+
+    /// Photo metadata of the file, or None if it is not a photo or has none
+    fn photo(&self, _file: &VirtualFile) -> Option<Arc<PhotoMetadata>> {
+        None
+    }
+
+    /// Member listing of the file, or None if it is not an archive
+    fn archive(&self, _file: &VirtualFile) -> Option<Arc<ArchiveListing>> {
+        None
+    }
+}
+
+/// Simple in-memory content source for testing.
+#[derive(Default)]
+pub struct SimpleContentSource {
+    documents: HashMap<String, Arc<DocumentContent>>,
+    analyses: HashMap<String, Arc<ContentAnalysis>>,
+    photos: HashMap<String, Arc<PhotoMetadata>>,
+    archives: HashMap<String, Arc<ArchiveListing>>,
+}
+
+impl SimpleContentSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the extracted text of a file
+    pub fn add_document(&mut self, path: &str, document: DocumentContent) {
+        self.documents.insert(path.to_string(), Arc::new(document));
+    }
+
+    /// Set the cached analysis of a file
+    pub fn add_analysis(&mut self, path: &str, analysis: ContentAnalysis) {
+        self.analyses.insert(path.to_string(), Arc::new(analysis));
+    }
+
+    /// Set the photo metadata of a file
+    pub fn add_photo(&mut self, path: &str, photo: PhotoMetadata) {
+        self.photos.insert(path.to_string(), Arc::new(photo));
+    }
+
+    /// Set the member listing of an archive
+    pub fn add_archive(&mut self, path: &str, listing: ArchiveListing) {
+        self.archives.insert(path.to_string(), Arc::new(listing));
+    }
+}
+
+impl ContentSource for SimpleContentSource {
+    fn document(&self, file: &VirtualFile) -> Option<Arc<DocumentContent>> {
+        self.documents.get(&file.path).cloned()
+    }
+
+    fn analysis(&self, file: &VirtualFile) -> Option<Arc<ContentAnalysis>> {
+        self.analyses.get(&file.path).cloned()
+    }
+
+    fn photo(&self, file: &VirtualFile) -> Option<Arc<PhotoMetadata>> {
+        self.photos.get(&file.path).cloned()
+    }
+
+    fn archive(&self, file: &VirtualFile) -> Option<Arc<ArchiveListing>> {
+        self.archives.get(&file.path).cloned()
+    }
+}
+
+/// Content source backed by the document parser and the analysis cache.
+pub struct DocumentContentSource {
+    parser: DocumentParser,
+    /// Directory of the content cache database
+    cache_dir: Option<PathBuf>,
+    /// Opened on first use; None if it could not be opened
+    cache: OnceLock<Option<ContentCache>>,
+    documents: Mutex<HashMap<String, Option<Arc<DocumentContent>>>>,
+    analyses: Mutex<HashMap<String, Option<Arc<ContentAnalysis>>>>,
+    photos: Mutex<HashMap<String, Option<Arc<PhotoMetadata>>>>,
+    archives: Mutex<HashMap<String, Option<Arc<ArchiveListing>>>>,
+}
+
+impl DocumentContentSource {
+    /// Create a content source reading analyses from the cache in `cache_dir`.
+    /// Without a cache directory only text fields are available.
+    pub fn new(cache_dir: Option<PathBuf>) -> Self {
+        Self {
+            // Unit struct; DocumentParser::new() only adds an info log per source
+            parser: DocumentParser,
+            cache_dir,
+            cache: OnceLock::new(),
+            documents: Mutex::new(HashMap::new()),
+            analyses: Mutex::new(HashMap::new()),
+            photos: Mutex::new(HashMap::new()),
+            archives: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Content source using the analysis cache shared with the Grok organizer
+    pub fn open_default() -> Self {
+        Self::new(ContentCache::default_dir())
+    }
+
+    fn cache(&self) -> Option<&ContentCache> {
+        self.cache
+            .get_or_init(|| {
+                let dir = self.cache_dir.as_ref()?;
+                match ContentCache::open(dir) {
+                    Ok(cache) => Some(cache),
+                    Err(e) => {
+                        tracing::warn!(error = %e, "Content cache unavailable for rule evaluation");
+                        None
+                    }
+                }
+            })
+            .as_ref()
+    }
+
+    fn load_document(&self, path: &Path) -> Option<DocumentContent> {
+        let ext = path.extension().and_then(|e| e.to_str());
+        if !DocumentParser::is_supported(ext) {
+            return None;
+        }
+        match self.parser.parse(path) {
+            Ok(parsed) => Some(DocumentContent {
+                text: parsed.text,
+                page_count: parsed.metadata.page_count,
+                title: parsed.metadata.title,
+                author: parsed.metadata.author,
+            }),
+            Err(e) => {
+                tracing::debug!(path = %path.display(), error = %e, "No content for rule evaluation");
+                None
+            }
+        }
+    }
+
+    fn load_archive(&self, path: &Path) -> Option<ArchiveListing> {
+        if !archive::is_archive(path) {
+            return None;
+        }
+        archive::list(path)
+            .inspect_err(|e| {
+                tracing::debug!(path = %path.display(), error = %e, "No archive listing for rule evaluation")
+            })
+            .ok()
+    }
+
+    fn load_analysis(&self, path: &Path) -> Option<ContentAnalysis> {
+        let analysis = self.cache()?.get_cached(path).ok()??;
+        Some(ContentAnalysis {
+            doc_type: analysis.document_type.as_str().to_string(),
+            entities: analysis.key_entities,
+            summary: analysis.content_summary,
+        })
+    }
+}
+
+impl ContentSource for DocumentContentSource {
+    fn document(&self, file: &VirtualFile) -> Option<Arc<DocumentContent>> {
+        if let Some(cached) = self.documents.lock().ok()?.get(&file.path) {
+            return cached.clone();
+        }
+        // Parse outside the lock; a concurrent duplicate parse is harmless
+        let document = self.load_document(Path::new(&file.path)).map(Arc::new);
+        self.documents
+            .lock()
+            .ok()?
+            .insert(file.path.clone(), document.clone());
+        document
+    }
+
+    fn analysis(&self, file: &VirtualFile) -> Option<Arc<ContentAnalysis>> {
+        if let Some(cached) = self.analyses.lock().ok()?.get(&file.path) {
+            return cached.clone();
+        }
+        let analysis = self.load_analysis(Path::new(&file.path)).map(Arc::new);
+        self.analyses
+            .lock()
+            .ok()?
+            .insert(file.path.clone(), analysis.clone());
+        analysis
+    }
+
+    fn photo(&self, file: &VirtualFile) -> Option<Arc<PhotoMetadata>> {
+        if let Some(cached) = self.photos.lock().ok()?.get(&file.path) {
+            return cached.clone();
+        }
+        let photo = media::read_photo_metadata(Path::new(&file.path)).map(Arc::new);
+        self.photos
+            .lock()
+            .ok()?
+            .insert(file.path.clone(), photo.clone());
+        photo
+    }
+
+    fn archive(&self, file: &VirtualFile) -> Option<Arc<ArchiveListing>> {
+        if let Some(cached) = self.archives.lock().ok()?.get(&file.path) {
+            return cached.clone();
+        }
+        let listing = self.load_archive(Path::new(&file.path)).map(Arc::new);
+        self.archives
+            .lock()
+            .ok()?
+            .insert(file.path.clone(), listing.clone());
+        listing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document_source_parses_text_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        std::fs::write(&path, "Invoice from Acme Corp, total due 120 EUR").unwrap();
+        let file = VirtualFile::from_path(&path).unwrap();
+
+        let source = DocumentContentSource::new(None);
+        let document = source.document(&file).unwrap();
+        assert!(document.text.contains("Acme Corp"));
+
+        // Served from memory after the file is gone
+        std::fs::remove_file(&path).unwrap();
+        assert!(source.document(&file).is_some());
+        assert!(source.analysis(&file).is_none());
+    }
+
+    #[test]
+    fn test_unsupported_files_have_no_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("photo.jpg");
+        std::fs::write(&path, [0xFF, 0xD8, 0xFF]).unwrap();
+        let file = VirtualFile::from_path(&path).unwrap();
+
+        let source = DocumentContentSource::new(None);
+        assert!(source.document(&file).is_none());
+    }
+}
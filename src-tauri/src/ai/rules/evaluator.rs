@@ -4,9 +4,13 @@
 //! to determine if they match the rule criteria.
 
 use super::ast::*;
+use super::content::ContentSource;
+use crate::media::PhotoMetadata;
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike};
 use regex::Regex;
 use std::collections::HashMap;
 use std::path::Path;
+use std::sync::Arc;
 
 /// Error type for rule evaluation failures
 #[derive(Debug, Clone)]
@@ -205,15 +209,72 @@ impl VectorIndex for SimpleVectorIndex {
     }
 }
 
+/// Parse a date literal into unix milliseconds.
+///
+/// Accepts RFC 3339 timestamps, `YYYY-MM-DD HH:MM[:SS]` and `YYYY-MM-DD`;
+/// values without an offset are read as local time.
+pub fn parse_date_ms(s: &str) -> Option<i64> {
+    let s = s.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.timestamp_millis());
+    }
+    let naive = ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(s, fmt).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+        })?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|dt| dt.timestamp_millis())
+}
+
+/// Extract a calendar component (local time) from unix milliseconds
+fn date_part(ms: i64, part: DatePart) -> Option<f64> {
+    let dt = Local.timestamp_millis_opt(ms).single()?;
+    let value = match part {
+        DatePart::Year => dt.year() as u32,
+        DatePart::Month => dt.month(),
+        DatePart::Day => dt.day(),
+        DatePart::Weekday => dt.weekday().number_from_monday(),
+        DatePart::Hour => dt.hour(),
+    };
+    Some(value as f64)
+}
+
 /// Rule evaluator that matches files against rule expressions.
 pub struct RuleEvaluator<'a, V: VectorIndex> {
     vector_index: &'a V,
+    /// Reference time for now(), in unix ms
+    now_ms: i64,
+    /// Source for content fields (file.content, file.docType, ...)
+    content: Option<&'a dyn ContentSource>,
 }
 
 impl<'a, V: VectorIndex> RuleEvaluator<'a, V> {
     /// Create a new rule evaluator with the given vector index
     pub fn new(vector_index: &'a V) -> Self {
-        Self { vector_index }
+        Self {
+            vector_index,
+            now_ms: chrono::Utc::now().timestamp_millis(),
+            content: None,
+        }
+    }
+
+    /// Resolve content fields from the given source.
+    /// Without one, content fields are null and never match.
+    pub fn with_content(mut self, content: &'a dyn ContentSource) -> Self {
+        self.content = Some(content);
+        self
+    }
+
+    /// Evaluate now() as the given time instead of the creation time
+    pub fn with_now(mut self, now_ms: i64) -> Self {
+        self.now_ms = now_ms;
+        self
     }
 
     /// Evaluate an expression against a file
@@ -228,6 +289,7 @@ impl<'a, V: VectorIndex> RuleEvaluator<'a, V> {
             Expression::Not(inner) => Ok(!self.evaluate(inner, file)?),
             Expression::Comparison(cmp) => self.evaluate_comparison(cmp, file),
             Expression::FunctionCall(func) => self.evaluate_function(func, file),
+            Expression::OperandComparison(cmp) => self.evaluate_operand_comparison(cmp, file),
             Expression::Literal(b) => Ok(*b),
         }
     }
@@ -239,16 +301,79 @@ impl<'a, V: VectorIndex> RuleEvaluator<'a, V> {
         file: &VirtualFile,
     ) -> Result<bool, RuleError> {
         let field_value = self.get_field_value(&cmp.field, file);
+        let value = normalize_literal(&cmp.field, &cmp.value);
+        self.apply_op(&cmp.op, &field_value, &value)
+    }
+
+    /// Evaluate a comparison between computed operands against a file
+    pub fn evaluate_operand_comparison(
+        &self,
+        cmp: &OperandComparison,
+        file: &VirtualFile,
+    ) -> Result<bool, RuleError> {
+        let mut left = self.evaluate_operand(&cmp.left, file)?;
+        let mut right = self.evaluate_operand(&cmp.right, file)?;
+
+        // Literals are interpreted relative to the field on the other side
+        if let (Operand::Field(field), Operand::Literal(_)) = (&cmp.left, &cmp.right) {
+            right = normalize_literal(field, &right);
+        }
+        if let (Operand::Literal(_), Operand::Field(field)) = (&cmp.left, &cmp.right) {
+            left = normalize_literal(field, &left);
+        }
+
+        self.apply_op(&cmp.op, &left, &right)
+    }
 
-        match cmp.op {
-            ComparisonOp::Eq => self.compare_eq(&field_value, &cmp.value),
-            ComparisonOp::Ne => Ok(!self.compare_eq(&field_value, &cmp.value)?),
-            ComparisonOp::Gt => self.compare_ord(&field_value, &cmp.value, |a, b| a > b),
-            ComparisonOp::Lt => self.compare_ord(&field_value, &cmp.value, |a, b| a < b),
-            ComparisonOp::Gte => self.compare_ord(&field_value, &cmp.value, |a, b| a >= b),
-            ComparisonOp::Lte => self.compare_ord(&field_value, &cmp.value, |a, b| a <= b),
-            ComparisonOp::In => self.compare_in(&field_value, &cmp.value),
-            ComparisonOp::Matches => self.compare_matches(&field_value, &cmp.value),
+    fn apply_op(&self, op: &ComparisonOp, left: &Value, right: &Value) -> Result<bool, RuleError> {
+        match op {
+            ComparisonOp::Eq => self.compare_eq(left, right),
+            ComparisonOp::Ne => Ok(!self.compare_eq(left, right)?),
+            ComparisonOp::Gt => self.compare_ord(left, right, |a, b| a > b),
+            ComparisonOp::Lt => self.compare_ord(left, right, |a, b| a < b),
+            ComparisonOp::Gte => self.compare_ord(left, right, |a, b| a >= b),
+            ComparisonOp::Lte => self.compare_ord(left, right, |a, b| a <= b),
+            ComparisonOp::In => self.compare_in(left, right),
+            ComparisonOp::Matches => self.compare_matches(left, right),
+            ComparisonOp::Contains => self.compare_contains(left, right),
+        }
+    }
+
+    /// Compute the value of an operand for a file.
+    ///
+    /// Arithmetic involving a missing timestamp yields `Value::Null`.
+    pub fn evaluate_operand(&self, operand: &Operand, file: &VirtualFile) -> Result<Value, RuleError> {
+        match operand {
+            Operand::Field(field) => Ok(self.get_field_value(field, file)),
+            Operand::Literal(value) => Ok(value.clone()),
+            Operand::Call(FunctionName::Now, _) => Ok(Value::Number(self.now_ms as f64)),
+            Operand::Call(FunctionName::Date, args) => {
+                let text = args
+                    .first()
+                    .and_then(|v| v.as_string())
+                    .ok_or_else(|| RuleError::new("date requires a date string"))?;
+                parse_date_ms(&text)
+                    .map(|ms| Value::Number(ms as f64))
+                    .ok_or_else(|| RuleError::new(format!("Invalid date: '{}'", text)))
+            }
+            Operand::Call(function, _) => Err(RuleError::new(format!(
+                "{} does not produce a value",
+                function.canonical_name()
+            ))),
+            Operand::Arithmetic(left, op, right) => {
+                let left = self.evaluate_operand(left, file)?;
+                let right = self.evaluate_operand(right, file)?;
+                if left.is_null() || right.is_null() {
+                    return Ok(Value::Null);
+                }
+                let (a, b) = match (numeric_value(&left), numeric_value(&right)) {
+                    (Some(a), Some(b)) => (a, b),
+                    _ => return Err(RuleError::new("Arithmetic requires numeric operands")),
+                };
+                op.apply(a, b)
+                    .map(Value::Number)
+                    .ok_or_else(|| RuleError::new("Division by zero"))
+            }
         }
     }
 
@@ -287,8 +412,7 @@ impl<'a, V: VectorIndex> RuleEvaluator<'a, V> {
                     .and_then(|v| v.as_string())
                     .ok_or_else(|| RuleError::new("contains requires a string argument"))?;
 
-                let target_str = target.as_string().unwrap_or_default();
-                Ok(target_str.to_lowercase().contains(&pattern.to_lowercase()))
+                self.compare_contains(&target, &Value::String(pattern))
             }
 
             FunctionName::StartsWith => {
@@ -349,6 +473,11 @@ impl<'a, V: VectorIndex> RuleEvaluator<'a, V> {
                 // When used with comparison, the caller handles the threshold
                 Ok(score > 0.5)
             }
+
+            FunctionName::Now | FunctionName::Date => Err(RuleError::new(format!(
+                "{}() is a value, compare it: file.modifiedAt < now() - 90d",
+                func.function.canonical_name()
+            ))),
         }
     }
 
@@ -377,9 +506,116 @@ impl<'a, V: VectorIndex> RuleEvaluator<'a, V> {
                 .map(Value::String)
                 .unwrap_or(Value::Null),
             Field::FileIsHidden => Value::Boolean(file.is_hidden),
+            Field::FileModifiedPart(part) => file
+                .modified_at
+                .and_then(|t| date_part(t, *part))
+                .map(Value::Number)
+                .unwrap_or(Value::Null),
+            Field::FileCreatedPart(part) => file
+                .created_at
+                .and_then(|t| date_part(t, *part))
+                .map(Value::Number)
+                .unwrap_or(Value::Null),
+            Field::FileContent => self
+                .content
+                .and_then(|c| c.document(file))
+                .map(|d| Value::String(d.text.clone()))
+                .unwrap_or(Value::Null),
+            Field::FilePageCount => self
+                .content
+                .and_then(|c| c.document(file))
+                .and_then(|d| d.page_count)
+                .map(|n| Value::Number(n as f64))
+                .unwrap_or(Value::Null),
+            Field::FileTitle => self
+                .content
+                .and_then(|c| c.document(file))
+                .and_then(|d| d.title.clone())
+                .map(Value::String)
+                .unwrap_or(Value::Null),
+            Field::FileAuthor => self
+                .content
+                .and_then(|c| c.document(file))
+                .and_then(|d| d.author.clone())
+                .map(Value::String)
+                .unwrap_or(Value::Null),
+            Field::FileSummary => self
+                .content
+                .and_then(|c| c.analysis(file))
+                .map(|a| Value::String(a.summary.clone()))
+                .unwrap_or(Value::Null),
+            Field::FileDocType => self
+                .content
+                .and_then(|c| c.analysis(file))
+                .map(|a| Value::String(a.doc_type.clone()))
+                .unwrap_or(Value::Null),
+            Field::FileEntities => self
+                .content
+                .and_then(|c| c.analysis(file))
+                .map(|a| {
+                    Value::Array(a.entities.iter().cloned().map(Value::String).collect())
+                })
+                .unwrap_or(Value::Null),
+            Field::FileArchive => self
+                .content
+                .and_then(|c| c.archive(file))
+                .map(|listing| {
+                    Value::Array(
+                        listing
+                            .files()
+                            .map(|m| Value::String(m.name.clone()))
+                            .collect(),
+                    )
+                })
+                .unwrap_or(Value::Null),
+            Field::ExifTakenAt => self
+                .photo(file)
+                .and_then(|p| p.taken_at)
+                .map(|t| Value::Number(t as f64))
+                .unwrap_or(Value::Null),
+            Field::ExifTakenPart(part) => self
+                .photo(file)
+                .and_then(|p| p.taken_at)
+                .and_then(|t| date_part(t, *part))
+                .map(Value::Number)
+                .unwrap_or(Value::Null),
+            Field::ExifCamera => self
+                .photo(file)
+                .and_then(|p| p.camera())
+                .map(Value::String)
+                .unwrap_or(Value::Null),
+            Field::ExifLens => self
+                .photo(file)
+                .and_then(|p| p.lens.clone())
+                .map(Value::String)
+                .unwrap_or(Value::Null),
+            Field::ExifWidth => self
+                .photo(file)
+                .and_then(|p| p.width)
+                .map(|n| Value::Number(n as f64))
+                .unwrap_or(Value::Null),
+            Field::ExifHeight => self
+                .photo(file)
+                .and_then(|p| p.height)
+                .map(|n| Value::Number(n as f64))
+                .unwrap_or(Value::Null),
+            Field::ExifLatitude => self
+                .photo(file)
+                .and_then(|p| p.gps)
+                .map(|g| Value::Number(g.latitude))
+                .unwrap_or(Value::Null),
+            Field::ExifLongitude => self
+                .photo(file)
+                .and_then(|p| p.gps)
+                .map(|g| Value::Number(g.longitude))
+                .unwrap_or(Value::Null),
         }
     }
 
+    fn photo(&self, file: &VirtualFile) -> Option<Arc<PhotoMetadata>> {
+        self.content.and_then(|c| c.photo(file))
+    }
+
     // Helper methods for comparisons
 
     fn compare_eq(&self, left: &Value, right: &Value) -> Result<bool, RuleError> {
@@ -391,6 +627,9 @@ impl<'a, V: VectorIndex> RuleEvaluator<'a, V> {
             (Value::Number(a), Value::SizeBytes(b)) => Ok(*a == *b as f64),
             (Value::Boolean(a), Value::Boolean(b)) => Ok(a == b),
             (Value::Null, Value::Null) => Ok(true),
+            (Value::Duration(_), _) | (_, Value::Duration(_)) => {
+                Ok(matches!((left.as_number(), right.as_number()), (Some(a), Some(b)) if a == b))
+            }
             _ => Ok(false),
         }
     }
@@ -444,6 +683,78 @@ impl<'a, V: VectorIndex> RuleEvaluator<'a, V> {
 
         Ok(regex.is_match(&target))
     }
+
+    /// Case-insensitive substring test; on lists, true if any element contains it
+    ///
+    /// On lists a needle with `*` or `?` is a glob matched against whole
+    /// elements, so `file.archive.contains('*.pdf')` finds archived PDFs.
+    fn compare_contains(&self, left: &Value, right: &Value) -> Result<bool, RuleError> {
+        let needle = right
+            .as_string()
+            .ok_or_else(|| RuleError::new("CONTAINS requires a string value"))?
+            .to_lowercase();
+
+        let contains = |value: &Value| {
+            value
+                .as_string()
+                .is_some_and(|s| s.to_lowercase().contains(&needle))
+        };
+        match left {
+            Value::Array(items) if needle.contains(['*', '?']) => {
+                let pattern = needle
+                    .split('*')
+                    .map(|part| {
+                        part.split('?')
+                            .map(regex::escape)
+                            .collect::<Vec<_>>()
+                            .join(".")
+                    })
+                    .collect::<Vec<_>>()
+                    .join(".*");
+                let regex = Regex::new(&format!("(?is)^{}$", pattern)).map_err(|e| {
+                    RuleError::new(format!("Invalid pattern: {}", e))
+                })?;
+                Ok(items
+                    .iter()
+                    .any(|item| item.as_string().is_some_and(|s| regex.is_match(&s))))
+            }
+            Value::Array(items) => Ok(items.iter().any(contains)),
+            Value::Null => Ok(false),
+            other => Ok(contains(other)),
+        }
+    }
+}
+
+/// Numeric value of an arithmetic operand; date strings become unix ms
+fn numeric_value(value: &Value) -> Option<f64> {
+    value.as_number().or_else(|| match value {
+        Value::String(s) => parse_date_ms(s).map(|ms| ms as f64),
+        _ => None,
+    })
+}
+
+/// Interpret a literal in terms of the field it is compared with:
+/// date strings against timestamps become unix ms, and month or weekday
+/// names against date parts become numbers.
+fn normalize_literal(field: &Field, value: &Value) -> Value {
+    match value {
+        Value::Array(items) => {
+            Value::Array(items.iter().map(|v| normalize_literal(field, v)).collect())
+        }
+        Value::String(s) if field.is_timestamp() => parse_date_ms(s)
+            .map(|ms| Value::Number(ms as f64))
+            .unwrap_or_else(|| value.clone()),
+        Value::String(s) => match field {
+            Field::FileModifiedPart(part)
+            | Field::FileCreatedPart(part)
+            | Field::ExifTakenPart(part) => part
+                .value_for_name(s)
+                .map(Value::Number)
+                .unwrap_or_else(|| value.clone()),
+            _ => value.clone(),
+        },
+        _ => value.clone(),
+    }
 }
 
 /// Evaluate a rule against multiple files and return matching ones
@@ -601,4 +912,219 @@ mod tests {
         assert_eq!(matches.len(), 2);
         assert!(matches.iter().all(|f| f.ext.as_deref() == Some("pdf")));
     }
+
+    #[test]
+    fn test_relative_time() {
+        let index = SimpleVectorIndex::new();
+        // Modified 2023-11-14; "now" is 100 days later
+        let day = 24 * 60 * 60 * 1000;
+        let evaluator = RuleEvaluator::new(&index).with_now(1700000000000 + 100 * day);
+        let file = create_test_file("old-report", Some("pdf"), 1024);
+
+        let older_than_90 = RuleParser::parse("file.modifiedAt < now() - 90d").unwrap();
+        assert!(evaluator.evaluate(&older_than_90, &file).unwrap());
+
+        let older_than_120 = RuleParser::parse("file.modifiedAt < now() - 120d").unwrap();
+        assert!(!evaluator.evaluate(&older_than_120, &file).unwrap());
+
+        let age = RuleParser::parse("now() - file.modifiedAt >= 14w").unwrap();
+        assert!(evaluator.evaluate(&age, &file).unwrap());
+    }
+
+    #[test]
+    fn test_date_literals_and_parts() {
+        let index = SimpleVectorIndex::new();
+        let evaluator = RuleEvaluator::new(&index);
+        // Modified 2023-11-14, created 2023-07-22
+        let file = create_test_file("photo", Some("jpg"), 1024);
+
+        for rule in [
+            "file.modifiedAt > '2023-01-01'",
+            "file.modifiedAt < date('2024-01-01')",
+            "file.modifiedAt.year == 2023",
+            "file.modifiedAt.month == 'November'",
+            "file.createdAt.month IN [6, 7, 8]",
+            "file.modifiedAt.year == file.createdAt.year",
+        ] {
+            let expr = RuleParser::parse(rule).unwrap();
+            assert!(evaluator.evaluate(&expr, &file).unwrap(), "{}", rule);
+        }
+
+        let same_month = RuleParser::parse("file.modifiedAt.month == file.createdAt.month").unwrap();
+        assert!(!evaluator.evaluate(&same_month, &file).unwrap());
+    }
+
+    #[test]
+    fn test_arithmetic_on_fields() {
+        let index = SimpleVectorIndex::new();
+        let evaluator = RuleEvaluator::new(&index);
+        let file = create_test_file("video", Some("mp4"), 1024 * 1024 * 30); // 30MB
+
+        let expr = RuleParser::parse("file.size / 1MB > 25").unwrap();
+        assert!(evaluator.evaluate(&expr, &file).unwrap());
+
+        let expr = RuleParser::parse("file.size * 2 < 50MB").unwrap();
+        assert!(!evaluator.evaluate(&expr, &file).unwrap());
+
+        let expr = RuleParser::parse("file.size / 0 > 1").unwrap();
+        assert!(evaluator.evaluate(&expr, &file).is_err());
+    }
+
+    #[test]
+    fn test_content_fields() {
+        use crate::ai::rules::content::{ContentAnalysis, DocumentContent, SimpleContentSource};
+
+        let index = SimpleVectorIndex::new();
+        let invoice = create_test_file("scan-0042", Some("pdf"), 2048);
+        let photo = create_test_file("beach", Some("jpg"), 2048);
+
+        let mut content = SimpleContentSource::new();
+        content.add_document(
+            &invoice.path,
+            DocumentContent {
+                text: "INVOICE #42\nTotal due: 120 EUR".to_string(),
+                page_count: Some(2),
+                title: Some("Invoice 42".to_string()),
+                author: Some("Acme Billing".to_string()),
+            },
+        );
+        content.add_analysis(
+            &invoice.path,
+            ContentAnalysis {
+                doc_type: "invoice".to_string(),
+                entities: vec!["Acme Corp".to_string(), "120 EUR".to_string()],
+                summary: "Invoice from Acme Corp".to_string(),
+            },
+        );
+        let evaluator = RuleEvaluator::new(&index).with_content(&content);
+
+        for rule in [
+            "file.docType == 'invoice'",
+            "file.docType IN ['invoice', 'receipt']",
+            "file.entities CONTAINS 'acme'",
+            "file.entities.contains('Acme')",
+            "file.content.contains('total due')",
+            "file.content CONTAINS 'INVOICE'",
+            "file.summary MATCHES 'Acme'",
+            "file.pageCount <= 2",
+            "file.title.startsWith('Invoice')",
+            "file.author CONTAINS 'acme'",
+        ] {
+            let expr = RuleParser::parse(rule).unwrap();
+            assert!(evaluator.evaluate(&expr, &invoice).unwrap(), "{}", rule);
+            assert!(!evaluator.evaluate(&expr, &photo).unwrap_or(false), "{}", rule);
+        }
+
+        // Without a content source, content fields are null
+        let plain = RuleEvaluator::new(&index);
+        let expr = RuleParser::parse("file.docType == 'invoice'").unwrap();
+        assert!(!plain.evaluate(&expr, &invoice).unwrap());
+    }
+
+    #[test]
+    fn test_archive_field() {
+        use crate::ai::rules::content::SimpleContentSource;
+        use crate::archive::{ArchiveFormat, ArchiveListing, ArchiveMember};
+
+        let index = SimpleVectorIndex::new();
+        let backup = create_test_file("backup", Some("zip"), 4096);
+        let notes = create_test_file("notes", Some("txt"), 2048);
+
+        let member = |name: &str, is_dir: bool| ArchiveMember {
+            name: name.to_string(),
+            size: 100,
+            modified_at: None,
+            is_dir,
+        };
+        let mut content = SimpleContentSource::new();
+        content.add_archive(
+            &backup.path,
+            ArchiveListing {
+                format: ArchiveFormat::Zip,
+                members: vec![
+                    member("scans", true),
+                    member("scans/Invoice-2024.PDF", false),
+                    member("readme.txt", false),
+                ],
+                truncated: false,
+            },
+        );
+        let evaluator = RuleEvaluator::new(&index).with_content(&content);
+
+        for rule in [
+            "file.archive.contains('*.pdf')",
+            "file.archive CONTAINS 'invoice-????.pdf' OR file.archive CONTAINS '*/invoice-*'",
+            "file.archive.contains('readme')",
+        ] {
+            let expr = RuleParser::parse(rule).unwrap();
+            assert!(evaluator.evaluate(&expr, &backup).unwrap(), "{}", rule);
+            assert!(!evaluator.evaluate(&expr, &notes).unwrap_or(false), "{}", rule);
+        }
+
+        // Globs match whole member names, and folders aren't members
+        for rule in ["file.archive.contains('*.pd')", "file.archive.contains('scans/*.txt')"] {
+            let expr = RuleParser::parse(rule).unwrap();
+            assert!(!evaluator.evaluate(&expr, &backup).unwrap(), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn test_photo_fields() {
+        use crate::ai::rules::content::SimpleContentSource;
+        use crate::media::{GpsPosition, PhotoMetadata};
+
+        let index = SimpleVectorIndex::new();
+        let photo = create_test_file("IMG_0042", Some("jpg"), 2048);
+        let document = create_test_file("notes", Some("txt"), 2048);
+
+        let mut content = SimpleContentSource::new();
+        content.add_photo(
+            &photo.path,
+            PhotoMetadata {
+                taken_at: Some(1_689_352_205_000),
+                camera_make: Some("Apple".to_string()),
+                camera_model: Some("iPhone 15 Pro".to_string()),
+                width: Some(4032),
+                height: Some(3024),
+                gps: Some(GpsPosition {
+                    latitude: 48.8566,
+                    longitude: 2.3522,
+                    altitude: None,
+                }),
+                ..Default::default()
+            },
+        );
+        let evaluator = RuleEvaluator::new(&index).with_content(&content);
+
+        for rule in [
+            "file.exif.camera CONTAINS 'iphone'",
+            "file.exif.camera == 'Apple iPhone 15 Pro'",
+            "file.exif.camera.startsWith('Apple')",
+            "file.exif.takenAt > '2023-01-01' AND file.exif.takenAt < date('2024-01-01')",
+            "file.exif.takenAt.year == 2023 AND file.exif.takenAt.month == 'July'",
+            "file.exif.width > file.exif.height",
+            "file.exif.latitude > 48 AND file.exif.longitude < 3",
+        ] {
+            let expr = RuleParser::parse(rule).unwrap();
+            assert!(evaluator.evaluate(&expr, &photo).unwrap(), "{}", rule);
+            assert!(!evaluator.evaluate(&expr, &document).unwrap_or(false), "{}", rule);
+        }
+
+        assert!(RuleParser::parse("file.exif.shutter == 1").is_err());
+        assert!(RuleParser::parse("file.exif.camera.year == 2023").is_err());
+    }
+
+    #[test]
+    fn test_missing_timestamp_never_matches() {
+        let index = SimpleVectorIndex::new();
+        let evaluator = RuleEvaluator::new(&index);
+        let mut file = create_test_file("orphan", Some("txt"), 10);
+        file.modified_at = None;
+
+        let files = vec![file];
+        let expr = RuleParser::parse("file.modifiedAt < now() - 1d").unwrap();
+        assert!(filter_files(&expr, &files, &index).is_empty());
+        let expr = RuleParser::parse("file.modifiedAt.year == 2023").unwrap();
+        assert!(!evaluator.evaluate(&expr, &files[0]).unwrap());
+    }
 }
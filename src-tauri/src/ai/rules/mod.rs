@@ -11,14 +11,24 @@
 //! - `file.name.contains('invoice') AND file.size > 10KB`
 //! - `NOT file.isHidden AND file.modifiedAt > '2024-01-01'`
 //! - `(file.ext == 'jpg' OR file.ext == 'png') AND file.size < 5MB`
+//! - `file.modifiedAt < now() - 90d`
+//! - `file.createdAt.weekday IN ['Sat', 'Sun']`
+//! - `file.size / 1MB > 100`
+//! - `file.docType == 'invoice' AND file.entities CONTAINS 'Acme'`
+//! - `file.content.contains('total due') AND file.pageCount <= 2`
+//! - `file.exif.camera CONTAINS 'iPhone' AND file.exif.takenAt.year == 2023`
 
 #![allow(dead_code)]
 #![allow(unused_imports)]
 
 pub mod ast;
+pub mod content;
 pub mod evaluator;
 pub mod parser;
+pub mod template;
 
 pub use ast::*;
+pub use content::*;
 pub use evaluator::*;
 pub use parser::*;
+pub use template::*;
@@ -7,6 +7,11 @@
 //! - `file.name.contains('invoice') AND file.size > 10KB`
 //! - `NOT file.isHidden AND file.modifiedAt > '2024-01-01'`
 //! - `(file.ext == 'jpg' OR file.ext == 'png') AND file.size < 5MB`
+//! - `file.modifiedAt < now() - 90d`
+//! - `file.modifiedAt.month == file.createdAt.month`
+//! - `file.docType == 'invoice' AND file.entities CONTAINS 'Acme'`
+//!
+//! `ParseError::position` is a character offset into the input.
 
 use super::ast::*;
 use std::iter::Peekable;
@@ -44,6 +49,7 @@ pub enum Token {
     String(String),
     Number(f64),
     SizeBytes(u64),
+    Duration(i64),
 
     // Keywords
     And,
@@ -61,6 +67,10 @@ pub enum Token {
     Lt,         // <
     Gte,        // >=
     Lte,        // <=
+    Plus,       // +
+    Minus,      // -
+    Star,       // *
+    Slash,      // /
 
     // Punctuation
     Dot,        // .
@@ -170,7 +180,7 @@ impl<'a> Lexer<'a> {
             }
         }
 
-        // Check for size suffix (KB, MB, GB, TB)
+        // Check for size suffix (KB, MB, GB, TB) or duration suffix (h, d, w, mo, y)
         if let Some(&c) = self.peek() {
             if c.is_ascii_alphabetic() {
                 let suffix_start = self.position;
@@ -192,14 +202,32 @@ impl<'a> Lexer<'a> {
                     _ => None,
                 };
 
+                const SECOND: i64 = 1000;
+                const DAY: i64 = 24 * 60 * 60 * SECOND;
+                let duration = match suffix.to_uppercase().as_str() {
+                    "S" | "SEC" | "SECS" | "SECONDS" => Some(SECOND),
+                    "MIN" | "MINS" | "MINUTES" => Some(60 * SECOND),
+                    "H" | "HR" | "HOURS" => Some(60 * 60 * SECOND),
+                    "D" | "DAY" | "DAYS" => Some(DAY),
+                    "W" | "WK" | "WEEKS" => Some(7 * DAY),
+                    "MO" | "MONTH" | "MONTHS" => Some(30 * DAY),
+                    "Y" | "YR" | "YEAR" | "YEARS" => Some(365 * DAY),
+                    _ => None,
+                };
+
                 if let Some(mult) = multiplier {
                     let base: f64 = s.parse().map_err(|_| {
                         ParseError::new("Invalid number format", start)
                     })?;
                     return Ok(Token::SizeBytes((base * mult as f64) as u64));
+                } else if let Some(unit) = duration {
+                    let base: f64 = s.parse().map_err(|_| {
+                        ParseError::new("Invalid number format", start)
+                    })?;
+                    return Ok(Token::Duration((base * unit as f64) as i64));
                 } else {
                     return Err(ParseError::new(
-                        format!("Unknown size suffix: {}", suffix),
+                        format!("Unknown size or duration suffix: {}", suffix),
                         suffix_start,
                     ));
                 }
@@ -302,6 +330,10 @@ impl<'a> Lexer<'a> {
                 ')' => Ok(Token::RParen),
                 '[' => Ok(Token::LBracket),
                 ']' => Ok(Token::RBracket),
+                '+' => Ok(Token::Plus),
+                '-' => Ok(Token::Minus),
+                '*' => Ok(Token::Star),
+                '/' => Ok(Token::Slash),
 
                 // Operators using symbols
                 '&' => {
@@ -341,11 +373,29 @@ impl<'a> Lexer<'a> {
         }
         Ok(tokens)
     }
+
+    /// Tokenize, pairing each token with its character offset in the input
+    pub fn tokenize_with_offsets(&mut self) -> Result<Vec<(Token, usize)>, ParseError> {
+        let mut tokens = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let offset = self.position;
+            let token = self.next_token()?;
+            let done = token == Token::Eof;
+            tokens.push((token, offset));
+            if done {
+                break;
+            }
+        }
+        Ok(tokens)
+    }
 }
 
 /// Recursive descent parser for rule expressions
 pub struct RuleParser {
     tokens: Vec<Token>,
+    /// Character offset of each token, for error positions
+    offsets: Vec<usize>,
     position: usize,
 }
 
@@ -353,16 +403,20 @@ impl RuleParser {
     /// Parse a rule expression string into an AST
     pub fn parse(input: &str) -> Result<Expression, ParseError> {
         let mut lexer = Lexer::new(input);
-        let tokens = lexer.tokenize()?;
+        let (tokens, offsets) = lexer.tokenize_with_offsets()?.into_iter().unzip();
 
-        let mut parser = Self { tokens, position: 0 };
+        let mut parser = Self {
+            tokens,
+            offsets,
+            position: 0,
+        };
         let expr = parser.parse_expression()?;
 
         // Ensure we consumed all tokens
         if !parser.is_at_end() {
             return Err(ParseError::new(
                 format!("Unexpected token: {:?}", parser.current()),
-                parser.position,
+                parser.offset(),
             ));
         }
 
@@ -373,6 +427,28 @@ impl RuleParser {
         self.tokens.get(self.position).unwrap_or(&Token::Eof)
     }
 
+    /// Token `n` positions ahead of the current one
+    fn peek_ahead(&self, n: usize) -> &Token {
+        self.tokens.get(self.position + n).unwrap_or(&Token::Eof)
+    }
+
+    /// Character offset of the current token
+    fn offset(&self) -> usize {
+        self.offsets
+            .get(self.position)
+            .or(self.offsets.last())
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Character offset of the previous token
+    fn previous_offset(&self) -> usize {
+        self.offsets
+            .get(self.position.saturating_sub(1))
+            .copied()
+            .unwrap_or(0)
+    }
+
     fn advance(&mut self) -> &Token {
         if !self.is_at_end() {
             self.position += 1;
@@ -394,7 +470,7 @@ impl RuleParser {
         } else {
             Err(ParseError::new(
                 format!("{}, got {:?}", message, self.current()),
-                self.position,
+                self.offset(),
             ))
         }
     }
@@ -466,17 +542,21 @@ impl RuleParser {
             Token::Identifier(name) => {
                 if name.to_lowercase() == "file" {
                     self.parse_file_expression()
+                } else if FunctionName::from_str(&name).is_some_and(|f| f.is_value_function()) {
+                    // Computed comparison starting with a value: now() - file.modifiedAt > 30d
+                    let left = self.parse_arithmetic()?;
+                    self.finish_operand_comparison(left)
                 } else {
                     Err(ParseError::new(
                         format!("Expected 'file', got '{}'", name),
-                        self.position,
+                        self.offset(),
                     ))
                 }
             }
 
             _ => Err(ParseError::new(
                 format!("Unexpected token: {:?}", self.current()),
-                self.position,
+                self.offset(),
             )),
         }
     }
@@ -495,13 +575,24 @@ impl RuleParser {
             _ => {
                 return Err(ParseError::new(
                     "Expected field name after 'file.'",
-                    self.position,
+                    self.offset(),
                 ));
             }
         };
 
         // Check if this is a direct function call on file (e.g., file.vector_similarity)
         if let Some(func_name) = FunctionName::from_str(&name) {
+            if func_name.is_value_function() {
+                return Err(ParseError::new(
+                    format!(
+                        "'{}' is not a file function; use {}() in a comparison",
+                        name,
+                        func_name.canonical_name()
+                    ),
+                    self.previous_offset(),
+                ));
+            }
+
             // This is a function call: file.function(args)
             self.consume(&Token::LParen, "Expected '(' for function call")?;
             let args = self.parse_function_args()?;
@@ -543,9 +634,7 @@ impl RuleParser {
         }
 
         // This should be a field reference
-        let field = Field::from_str(&name).ok_or_else(|| {
-            ParseError::new(format!("Unknown field: '{}'", name), self.position - 1)
-        })?;
+        let field = self.parse_field(name)?;
 
         // Check for method chain: file.field.function()
         if matches!(self.current(), Token::Dot) {
@@ -558,13 +647,13 @@ impl RuleParser {
                 _ => {
                     return Err(ParseError::new(
                         "Expected function name after field",
-                        self.position,
+                        self.offset(),
                     ));
                 }
             };
 
             let function = FunctionName::from_str(&func_name).ok_or_else(|| {
-                ParseError::new(format!("Unknown function: '{}'", func_name), self.position - 1)
+                ParseError::new(format!("Unknown function: '{}'", func_name), self.previous_offset())
             })?;
 
             self.consume(&Token::LParen, "Expected '(' for function call")?;
@@ -578,10 +667,24 @@ impl RuleParser {
             }));
         }
 
+        // Arithmetic on the field: file.size / 1MB > 10
+        if self.arithmetic_op().is_some() {
+            let left = self.parse_arithmetic_from(Operand::Field(field))?;
+            return self.finish_operand_comparison(left);
+        }
+
         // Check for comparison operator
         if let Some(op) = self.try_parse_comparison_op() {
-            let value = self.parse_value()?;
-            return Ok(Expression::Comparison(Comparison { field, op, value }));
+            let right = self.parse_arithmetic()?;
+            return Ok(match right {
+                // Plain literal: keep the simple comparison form
+                Operand::Literal(value) => Expression::Comparison(Comparison { field, op, value }),
+                right => Expression::OperandComparison(OperandComparison {
+                    left: Operand::Field(field),
+                    op,
+                    right,
+                }),
+            });
         }
 
         // Check for IN operator
@@ -606,6 +709,18 @@ impl RuleParser {
             }));
         }
 
+        // Check for CONTAINS operator. Not a keyword, so that
+        // file.name.contains('x') keeps lexing as a method call
+        if matches!(self.current(), Token::Identifier(n) if n.eq_ignore_ascii_case("contains")) {
+            self.advance();
+            let value = self.parse_value()?;
+            return Ok(Expression::Comparison(Comparison {
+                field,
+                op: ComparisonOp::Contains,
+                value,
+            }));
+        }
+
         // For boolean fields, no operator means checking if true
         if matches!(field, Field::FileIsHidden) {
             return Ok(Expression::Comparison(Comparison {
@@ -616,11 +731,178 @@ impl RuleParser {
         }
 
         Err(ParseError::new(
-            "Expected comparison operator, IN, or MATCHES",
-            self.position,
+            "Expected comparison operator, IN, MATCHES, or CONTAINS",
+            self.offset(),
         ))
     }
 
+    /// Resolve a field name after 'file.', including the photo fields in
+    /// the exif namespace (file.exif.camera) and an optional date part
+    fn parse_field(&mut self, mut name: String) -> Result<Field, ParseError> {
+        if name.eq_ignore_ascii_case("exif") {
+            self.consume(&Token::Dot, "Expected '.' after 'file.exif'")?;
+            match self.current().clone() {
+                Token::Identifier(n) => {
+                    self.advance();
+                    name = format!("exif.{}", n);
+                }
+                _ => {
+                    return Err(ParseError::new(
+                        "Expected photo field name after 'file.exif.'",
+                        self.offset(),
+                    ));
+                }
+            }
+        }
+        let field = Field::from_str(&name).ok_or_else(|| {
+            ParseError::new(format!("Unknown field: '{}'", name), self.previous_offset())
+        })?;
+        self.parse_date_part(field)
+    }
+
+    /// Parse an optional date part after a timestamp field: file.modifiedAt.year
+    fn parse_date_part(&mut self, field: Field) -> Result<Field, ParseError> {
+        let part = match (self.current(), self.peek_ahead(1), self.peek_ahead(2)) {
+            // A following '(' means a method call such as file.name.contains()
+            (Token::Dot, Token::Identifier(name), next) if !matches!(next, Token::LParen) => {
+                DatePart::from_str(name)
+            }
+            _ => None,
+        };
+        let Some(part) = part else {
+            return Ok(field);
+        };
+
+        self.advance(); // consume '.'
+        self.advance(); // consume part name
+        field.with_date_part(part).ok_or_else(|| {
+            ParseError::new(
+                format!(
+                    "Date parts are only available on modifiedAt, createdAt and exif.takenAt, not '{}'",
+                    field.canonical_name()
+                ),
+                self.previous_offset(),
+            )
+        })
+    }
+
+    /// Parse `op right` after a computed left operand
+    fn finish_operand_comparison(&mut self, left: Operand) -> Result<Expression, ParseError> {
+        let op = self.try_parse_comparison_op().ok_or_else(|| {
+            ParseError::new(
+                "Expected comparison operator after arithmetic expression",
+                self.offset(),
+            )
+        })?;
+        let right = self.parse_arithmetic()?;
+        Ok(Expression::OperandComparison(OperandComparison { left, op, right }))
+    }
+
+    fn arithmetic_op(&self) -> Option<ArithmeticOp> {
+        match self.current() {
+            Token::Plus => Some(ArithmeticOp::Add),
+            Token::Minus => Some(ArithmeticOp::Sub),
+            Token::Star => Some(ArithmeticOp::Mul),
+            Token::Slash => Some(ArithmeticOp::Div),
+            _ => None,
+        }
+    }
+
+    /// Parse arithmetic: `+`/`-` bind looser than `*`/`/`
+    fn parse_arithmetic(&mut self) -> Result<Operand, ParseError> {
+        let first = self.parse_operand_atom()?;
+        self.parse_arithmetic_from(first)
+    }
+
+    /// Continue an arithmetic expression whose first atom is already parsed
+    fn parse_arithmetic_from(&mut self, first: Operand) -> Result<Operand, ParseError> {
+        let mut left = self.parse_term_from(first)?;
+        while let Some(op @ (ArithmeticOp::Add | ArithmeticOp::Sub)) = self.arithmetic_op() {
+            self.advance();
+            let atom = self.parse_operand_atom()?;
+            let right = self.parse_term_from(atom)?;
+            left = Operand::Arithmetic(Box::new(left), op, Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_term_from(&mut self, first: Operand) -> Result<Operand, ParseError> {
+        let mut left = first;
+        while let Some(op @ (ArithmeticOp::Mul | ArithmeticOp::Div)) = self.arithmetic_op() {
+            self.advance();
+            let right = self.parse_operand_atom()?;
+            left = Operand::Arithmetic(Box::new(left), op, Box::new(right));
+        }
+        Ok(left)
+    }
+
+    /// Parse a single operand: literal, file field, value function or
+    /// parenthesized arithmetic
+    fn parse_operand_atom(&mut self) -> Result<Operand, ParseError> {
+        match self.current().clone() {
+            Token::Identifier(name) if name.eq_ignore_ascii_case("file") => {
+                self.advance();
+                self.consume(&Token::Dot, "Expected '.' after 'file'")?;
+                let name = match self.current().clone() {
+                    Token::Identifier(n) => {
+                        self.advance();
+                        n
+                    }
+                    _ => {
+                        return Err(ParseError::new(
+                            "Expected field name after 'file.'",
+                            self.offset(),
+                        ));
+                    }
+                };
+                Ok(Operand::Field(self.parse_field(name)?))
+            }
+            Token::Identifier(name) => {
+                let function = FunctionName::from_str(&name)
+                    .filter(FunctionName::is_value_function)
+                    .ok_or_else(|| {
+                        ParseError::new(
+                            format!("Expected value, got {:?}", self.current()),
+                            self.offset(),
+                        )
+                    })?;
+                self.advance();
+                self.consume(&Token::LParen, "Expected '(' for function call")?;
+                let args = self.parse_function_args()?;
+                self.consume(&Token::RParen, "Expected ')'")?;
+
+                let arity_error = match function {
+                    FunctionName::Now if !args.is_empty() => Some("now() takes no arguments"),
+                    FunctionName::Date if !matches!(args.as_slice(), [Value::String(_)]) => {
+                        Some("date() takes one date string, e.g. date('2024-01-31')")
+                    }
+                    _ => None,
+                };
+                if let Some(message) = arity_error {
+                    return Err(ParseError::new(message, self.previous_offset()));
+                }
+                Ok(Operand::Call(function, args))
+            }
+            Token::LParen => {
+                self.advance();
+                let inner = self.parse_arithmetic()?;
+                self.consume(&Token::RParen, "Expected ')'")?;
+                Ok(inner)
+            }
+            Token::Minus => {
+                // Negative operand: -1
+                self.advance();
+                let operand = self.parse_operand_atom()?;
+                Ok(Operand::Arithmetic(
+                    Box::new(Operand::Literal(Value::Number(0.0))),
+                    ArithmeticOp::Sub,
+                    Box::new(operand),
+                ))
+            }
+            _ => Ok(Operand::Literal(self.parse_value()?)),
+        }
+    }
+
     fn try_parse_comparison_op(&mut self) -> Option<ComparisonOp> {
         let op = match self.current() {
             Token::Eq => Some(ComparisonOp::Eq),
@@ -669,6 +951,10 @@ impl RuleParser {
                 self.advance();
                 Ok(Value::SizeBytes(b))
             }
+            Token::Duration(ms) => {
+                self.advance();
+                Ok(Value::Duration(ms))
+            }
             Token::True => {
                 self.advance();
                 Ok(Value::Boolean(true))
@@ -694,7 +980,7 @@ impl RuleParser {
             }
             _ => Err(ParseError::new(
                 format!("Expected value, got {:?}", self.current()),
-                self.position,
+                self.offset(),
             )),
         }
     }
@@ -839,4 +1125,101 @@ mod tests {
         let expr = RuleParser::parse("file.ext !\t= 'doc'").unwrap();
         assert!(matches!(expr, Expression::Comparison(_)));
     }
+
+    #[test]
+    fn test_contains_operator() {
+        let expr = RuleParser::parse("file.entities CONTAINS 'Acme'").unwrap();
+        assert_eq!(
+            expr,
+            Expression::Comparison(Comparison {
+                field: Field::FileEntities,
+                op: ComparisonOp::Contains,
+                value: Value::String("Acme".to_string()),
+            })
+        );
+
+        // Method form still parses as a function call
+        let expr = RuleParser::parse("file.content.contains('total due')").unwrap();
+        assert!(matches!(
+            expr,
+            Expression::FunctionCall(FunctionCall { ref receiver, function: FunctionName::Contains, .. })
+                if receiver == "file.content"
+        ));
+    }
+
+    #[test]
+    fn test_relative_time() {
+        let expr = RuleParser::parse("file.modifiedAt < now() - 90d").unwrap();
+        match expr {
+            Expression::OperandComparison(cmp) => {
+                assert_eq!(cmp.left, Operand::Field(Field::FileModifiedAt));
+                assert_eq!(cmp.op, ComparisonOp::Lt);
+                assert_eq!(
+                    cmp.right,
+                    Operand::Arithmetic(
+                        Box::new(Operand::Call(FunctionName::Now, vec![])),
+                        ArithmeticOp::Sub,
+                        Box::new(Operand::Literal(Value::Duration(90 * 24 * 60 * 60 * 1000))),
+                    )
+                );
+            }
+            _ => panic!("Expected operand comparison"),
+        }
+    }
+
+    #[test]
+    fn test_date_parts() {
+        let expr = RuleParser::parse("file.modifiedAt.year == 2024").unwrap();
+        match expr {
+            Expression::Comparison(cmp) => {
+                assert_eq!(cmp.field, Field::FileModifiedPart(DatePart::Year));
+                assert_eq!(cmp.value, Value::Number(2024.0));
+            }
+            _ => panic!("Expected comparison"),
+        }
+
+        let expr = RuleParser::parse("file.modifiedAt.month == file.createdAt.month").unwrap();
+        match expr {
+            Expression::OperandComparison(cmp) => {
+                assert_eq!(cmp.left, Operand::Field(Field::FileModifiedPart(DatePart::Month)));
+                assert_eq!(cmp.right, Operand::Field(Field::FileCreatedPart(DatePart::Month)));
+            }
+            _ => panic!("Expected operand comparison"),
+        }
+
+        // Method calls still parse after a field
+        assert!(matches!(
+            RuleParser::parse("file.name.contains('day')").unwrap(),
+            Expression::FunctionCall(_)
+        ));
+    }
+
+    #[test]
+    fn test_arithmetic_precedence() {
+        let expr = RuleParser::parse("file.size / 1MB + 1 > 10").unwrap();
+        match expr {
+            Expression::OperandComparison(cmp) => match cmp.left {
+                Operand::Arithmetic(left, ArithmeticOp::Add, _) => {
+                    assert!(matches!(*left, Operand::Arithmetic(_, ArithmeticOp::Div, _)));
+                }
+                other => panic!("Expected addition at the top, got {:?}", other),
+            },
+            _ => panic!("Expected operand comparison"),
+        }
+    }
+
+    #[test]
+    fn test_error_positions_are_character_offsets() {
+        let err = RuleParser::parse("file.ext == 'pdf' AND file.foo == 1").unwrap_err();
+        assert_eq!(err.position, 27);
+
+        let err = RuleParser::parse("file.size.year > 1").unwrap_err();
+        assert_eq!(err.position, 10);
+
+        let err = RuleParser::parse("file.modifiedAt < now() -").unwrap_err();
+        assert_eq!(err.position, 25);
+
+        let err = RuleParser::parse("file.modifiedAt < date(5)").unwrap_err();
+        assert!(err.message.contains("date()"));
+    }
 }
@@ -0,0 +1,558 @@
+//! Rename templates for rule-based and batch renames.
+//!
+//! A template is literal text with `{...}` placeholders:
+//! - any rule field: `{name}`, `{ext}`, `{size}`, `{docType}`, `{exif.camera}`
+//! - dates with a strftime format: `{modifiedAt:%Y-%m}`, `{exif.takenAt:%Y}`;
+//!   without a format dates render as `YYYY-MM-DD`, and `{date}` is the
+//!   modified date; dates are rendered in UTC
+//! - `{n}`, the position of the file among those renamed by the same
+//!   template, zero padded with `{n:03}`
+//! - regex capture groups of the rule's `file.name MATCHES` pattern:
+//!   `{1}`, `{match.year}`
+//! - transforms after `|`: `lower`, `upper`, `title`, `slug`, `snake`,
+//!   `trim`, `max:N` (truncate) and `default:TEXT` (for empty values)
+//!
+//! Example: `{exif.takenAt:%Y-%m-%d}_{exif.camera|slug|default:unknown}_{n:03}.{ext}`
+//!
+//! `{{` and `}}` are literal braces. Templates are parsed before any file
+//! is renamed, so typos are reported once instead of per file.
+
+use super::ast::{ComparisonOp, Expression, Field, FunctionName, Value};
+use super::parser::ParseError;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{TimeZone, Utc};
+use regex::Regex;
+use std::collections::HashMap;
+
+/// Longest file name most filesystems accept, in bytes
+const MAX_NAME_BYTES: usize = 255;
+
+/// A parsed rename template
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenameTemplate {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Placeholder {
+    source: Source,
+    format: Format,
+    transforms: Vec<Transform>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Source {
+    Field(Field),
+    Counter,
+    Capture(usize),
+    NamedCapture(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Format {
+    Text,
+    /// strftime format, in UTC
+    Date(String),
+    /// Minimum width, padded with zeros
+    Number(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Transform {
+    Lower,
+    Upper,
+    Title,
+    Slug,
+    Snake,
+    Trim,
+    Max(usize),
+    Default(String),
+}
+
+/// Per-file inputs to a template that are not file fields
+#[derive(Debug, Clone, Default)]
+pub struct RenameContext {
+    /// Value of `{n}`, starting at 1
+    pub counter: u64,
+    captures: Vec<Option<String>>,
+    named_captures: HashMap<String, String>,
+}
+
+impl RenameContext {
+    pub fn new(counter: u64) -> Self {
+        Self {
+            counter,
+            ..Default::default()
+        }
+    }
+
+    /// Capture groups of `pattern` in `text`, for `{1}` and `{match.name}`
+    pub fn with_captures(mut self, pattern: &Regex, text: &str) -> Self {
+        if let Some(captures) = pattern.captures(text) {
+            self.captures = captures
+                .iter()
+                .map(|m| m.map(|m| m.as_str().to_string()))
+                .collect();
+            self.named_captures = pattern
+                .capture_names()
+                .flatten()
+                .filter_map(|name| Some((name.to_string(), captures.name(name)?.as_str().to_string())))
+                .collect();
+        }
+        self
+    }
+}
+
+impl RenameTemplate {
+    /// Parse and validate a template
+    pub fn parse(template: &str) -> Result<Self, ParseError> {
+        if template.trim().is_empty() {
+            return Err(ParseError::new("Rename template is empty", 0));
+        }
+
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.char_indices().peekable();
+        while let Some((pos, c)) = chars.next() {
+            match c {
+                '{' if chars.peek().map(|(_, c)| *c) == Some('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek().map(|(_, c)| *c) == Some('}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let end = template[pos..]
+                        .find('}')
+                        .map(|i| pos + i)
+                        .ok_or_else(|| ParseError::new("Unclosed '{'", pos))?;
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    let placeholder = parse_placeholder(&template[pos + 1..end], pos + 1)?;
+                    segments.push(Segment::Placeholder(placeholder));
+                    while chars.peek().is_some_and(|(i, _)| *i <= end) {
+                        chars.next();
+                    }
+                }
+                '}' => return Err(ParseError::new("Unmatched '}' (use '}}' for a brace)", pos)),
+                '/' | '\\' => {
+                    return Err(ParseError::new(
+                        "Rename templates cannot contain path separators",
+                        pos,
+                    ))
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Self { segments })
+    }
+
+    fn placeholders(&self) -> impl Iterator<Item = &Placeholder> {
+        self.segments.iter().filter_map(|s| match s {
+            Segment::Placeholder(p) => Some(p),
+            Segment::Literal(_) => None,
+        })
+    }
+
+    /// Whether the template uses regex capture groups
+    pub fn uses_captures(&self) -> bool {
+        self.placeholders()
+            .any(|p| matches!(p.source, Source::Capture(_) | Source::NamedCapture(_)))
+    }
+
+    /// Check the capture groups used by the template exist in `pattern`
+    pub fn validate_captures(&self, pattern: Option<&Regex>) -> Result<(), String> {
+        if !self.uses_captures() {
+            return Ok(());
+        }
+        let pattern = pattern.ok_or_else(|| {
+            "Capture groups like {1} need a file.name MATCHES pattern in the rule".to_string()
+        })?;
+        for placeholder in self.placeholders() {
+            match &placeholder.source {
+                Source::Capture(i) if *i >= pattern.captures_len() => {
+                    return Err(format!(
+                        "Pattern '{}' has no capture group {}",
+                        pattern.as_str(),
+                        i
+                    ));
+                }
+                Source::NamedCapture(name)
+                    if !pattern.capture_names().flatten().any(|n| n == name) =>
+                {
+                    return Err(format!(
+                        "Pattern '{}' has no capture group named '{}'",
+                        pattern.as_str(),
+                        name
+                    ));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Render the file name for one file
+    ///
+    /// `field` resolves rule fields, typically
+    /// [`RuleEvaluator::get_field_value`](super::RuleEvaluator::get_field_value).
+    /// Missing values render as empty text. Fails if the result is not a
+    /// usable file name.
+    pub fn render(
+        &self,
+        context: &RenameContext,
+        field: impl Fn(&Field) -> Value,
+    ) -> Result<String, String> {
+        let mut name = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => name.push_str(text),
+                Segment::Placeholder(placeholder) => {
+                    let value = match &placeholder.source {
+                        Source::Field(f) => format_value(&field(f), &placeholder.format),
+                        Source::Counter => {
+                            format_value(&Value::Number(context.counter as f64), &placeholder.format)
+                        }
+                        Source::Capture(i) => context.captures.get(*i).cloned().flatten().unwrap_or_default(),
+                        Source::NamedCapture(n) => {
+                            context.named_captures.get(n).cloned().unwrap_or_default()
+                        }
+                    };
+                    let value = placeholder
+                        .transforms
+                        .iter()
+                        .fold(value, |value, transform| transform.apply(value));
+                    name.push_str(&sanitize(&value));
+                }
+            }
+        }
+
+        // "{name}.{ext}" for a file without extension leaves a trailing dot
+        let name = name.trim().trim_end_matches('.').trim_end().to_string();
+        if name.is_empty() || name == "." || name == ".." {
+            return Err(format!("Template renders to an invalid file name '{}'", name));
+        }
+        if name.len() > MAX_NAME_BYTES {
+            return Err(format!("Template renders to a name longer than {} bytes", MAX_NAME_BYTES));
+        }
+        Ok(name)
+    }
+}
+
+/// The regex of the first `file.name MATCHES` test in a rule condition,
+/// whose capture groups a rename template may use
+pub fn capture_pattern(expr: &Expression) -> Option<&str> {
+    match expr {
+        Expression::And(left, right) | Expression::Or(left, right) => {
+            capture_pattern(left).or_else(|| capture_pattern(right))
+        }
+        Expression::Comparison(cmp)
+            if cmp.field == Field::FileName && cmp.op == ComparisonOp::Matches =>
+        {
+            match &cmp.value {
+                Value::String(pattern) => Some(pattern),
+                _ => None,
+            }
+        }
+        Expression::FunctionCall(call)
+            if call.receiver == "file.name" && call.function == FunctionName::Matches =>
+        {
+            match call.args.first() {
+                Some(Value::String(pattern)) => Some(pattern),
+                _ => None,
+            }
+        }
+        // A negated match has no groups to capture
+        _ => None,
+    }
+}
+
+fn parse_placeholder(inner: &str, offset: usize) -> Result<Placeholder, ParseError> {
+    let mut parts = inner.split('|');
+    let head = parts.next().unwrap_or_default();
+    let (name, format) = match head.split_once(':') {
+        Some((name, format)) => (name.trim(), Some(format)),
+        None => (head.trim(), None),
+    };
+    if name.is_empty() {
+        return Err(ParseError::new("Empty placeholder", offset));
+    }
+
+    let source = if name.eq_ignore_ascii_case("n") || name.eq_ignore_ascii_case("counter") {
+        Source::Counter
+    } else if name.chars().all(|c| c.is_ascii_digit()) {
+        Source::Capture(
+            name.parse()
+                .map_err(|_| ParseError::new("Capture group number too large", offset))?,
+        )
+    } else if let Some(group) = name.strip_prefix("match.") {
+        Source::NamedCapture(group.to_string())
+    } else {
+        let field = match name.to_lowercase().as_str() {
+            // Short names kept from the original rename patterns
+            "date" => Some(Field::FileModifiedAt),
+            "taken" => Some(Field::ExifTakenAt),
+            "camera" => Some(Field::ExifCamera),
+            "lens" => Some(Field::ExifLens),
+            _ => Field::from_str(name),
+        };
+        Source::Field(
+            field.ok_or_else(|| ParseError::new(format!("Unknown placeholder '{}'", name), offset))?,
+        )
+    };
+
+    let format = parse_format(&source, format, offset)?;
+    let transforms = parts
+        .map(|t| Transform::parse(t, offset))
+        .collect::<Result<_, _>>()?;
+    Ok(Placeholder {
+        source,
+        format,
+        transforms,
+    })
+}
+
+fn parse_format(source: &Source, format: Option<&str>, offset: usize) -> Result<Format, ParseError> {
+    let is_number = match source {
+        Source::Counter => true,
+        Source::Field(field) if field.is_timestamp() => {
+            let format = format.unwrap_or("%Y-%m-%d");
+            if format.is_empty()
+                || StrftimeItems::new(format).any(|item| matches!(item, Item::Error))
+            {
+                return Err(ParseError::new(
+                    format!("Invalid date format '{}'", format),
+                    offset,
+                ));
+            }
+            return Ok(Format::Date(format.to_string()));
+        }
+        Source::Field(field) => matches!(
+            field,
+            Field::FileSize
+                | Field::FilePageCount
+                | Field::FileModifiedPart(_)
+                | Field::FileCreatedPart(_)
+                | Field::ExifTakenPart(_)
+                | Field::ExifWidth
+                | Field::ExifHeight
+        ),
+        Source::Capture(_) | Source::NamedCapture(_) => false,
+    };
+
+    match format {
+        None => Ok(if is_number { Format::Number(0) } else { Format::Text }),
+        Some(width) if is_number => width
+            .parse::<usize>()
+            .ok()
+            .filter(|w| *w <= 20)
+            .map(Format::Number)
+            .ok_or_else(|| {
+                ParseError::new(
+                    format!("Invalid number format '{}', expected a width like 03", width),
+                    offset,
+                )
+            }),
+        Some(_) => Err(ParseError::new(
+            "Only numbers and dates take a format; use a transform such as |lower",
+            offset,
+        )),
+    }
+}
+
+impl Transform {
+    fn parse(text: &str, offset: usize) -> Result<Self, ParseError> {
+        let (name, arg) = match text.split_once(':') {
+            Some((name, arg)) => (name.trim(), Some(arg)),
+            None => (text.trim(), None),
+        };
+        let transform = match (name.to_lowercase().as_str(), arg) {
+            ("lower", None) => Transform::Lower,
+            ("upper", None) => Transform::Upper,
+            ("title", None) => Transform::Title,
+            ("slug", None) => Transform::Slug,
+            ("snake", None) => Transform::Snake,
+            ("trim", None) => Transform::Trim,
+            ("max", Some(n)) => Transform::Max(
+                n.trim()
+                    .parse()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| ParseError::new("max takes a positive length, e.g. |max:40", offset))?,
+            ),
+            ("default", Some(text)) => Transform::Default(text.to_string()),
+            _ => {
+                return Err(ParseError::new(
+                    format!("Unknown transform '{}'", text.trim()),
+                    offset,
+                ))
+            }
+        };
+        Ok(transform)
+    }
+
+    fn apply(&self, value: String) -> String {
+        match self {
+            Transform::Lower => value.to_lowercase(),
+            Transform::Upper => value.to_uppercase(),
+            Transform::Title => title_case(&value),
+            Transform::Slug => separate_words(&value, '-'),
+            Transform::Snake => separate_words(&value, '_'),
+            Transform::Trim => value.trim().to_string(),
+            Transform::Max(n) => value.chars().take(*n).collect(),
+            Transform::Default(text) if value.is_empty() => text.clone(),
+            Transform::Default(_) => value,
+        }
+    }
+}
+
+fn format_value(value: &Value, format: &Format) -> String {
+    match (value, format) {
+        (Value::Number(ms), Format::Date(format)) => Utc
+            .timestamp_millis_opt(*ms as i64)
+            .single()
+            .map(|dt| dt.format(format).to_string())
+            .unwrap_or_default(),
+        (Value::Number(n), Format::Number(width)) if n.fract() == 0.0 => {
+            format!("{:0width$}", *n as i64, width = *width)
+        }
+        (Value::SizeBytes(n), Format::Number(width)) => format!("{:0width$}", n, width = *width),
+        (Value::Array(items), _) => items
+            .iter()
+            .filter_map(Value::as_string)
+            .collect::<Vec<_>>()
+            .join("-"),
+        (value, _) => value.as_string().unwrap_or_default(),
+    }
+}
+
+/// Lowercase words joined by `separator`: "Tax Return (2024)" -> "tax-return-2024"
+fn separate_words(value: &str, separator: char) -> String {
+    value
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(&separator.to_string())
+}
+
+fn title_case(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut word_start = true;
+    for c in value.chars() {
+        if word_start {
+            result.extend(c.to_uppercase());
+        } else {
+            result.extend(c.to_lowercase());
+        }
+        word_start = !c.is_alphanumeric();
+    }
+    result
+}
+
+/// Substituted values must not add path separators or control characters
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| if c == '/' || c == '\\' || c.is_control() { '-' } else { c })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::rules::RuleParser;
+
+    fn fields(field: &Field) -> Value {
+        match field {
+            Field::FileName => Value::String("IMG_20230714_Beach Trip".to_string()),
+            Field::FileExt => Value::String("jpg".to_string()),
+            Field::FileModifiedAt => Value::Number(1_689_352_205_000.0),
+            Field::FileSize => Value::SizeBytes(2048),
+            Field::ExifCamera => Value::String("Canon EOS R6".to_string()),
+            Field::FileEntities => Value::Array(vec![
+                Value::String("Acme".to_string()),
+                Value::String("Bob".to_string()),
+            ]),
+            _ => Value::Null,
+        }
+    }
+
+    fn render(template: &str, counter: u64) -> String {
+        RenameTemplate::parse(template)
+            .unwrap()
+            .render(&RenameContext::new(counter), fields)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_fields_counters_and_dates() {
+        assert_eq!(render("{name}.{ext}", 1), "IMG_20230714_Beach Trip.jpg");
+        assert_eq!(render("photo-{n:03}.{ext}", 7), "photo-007.jpg");
+        assert_eq!(render("{n}_{size:06}", 12), "12_002048");
+        assert_eq!(render("{date:%Y-%m}_{camera|slug}", 1), "2023-07_canon-eos-r6");
+        assert_eq!(render("{entities}.txt", 1), "Acme-Bob.txt");
+        // No extension: no trailing dot
+        assert_eq!(render("{name|slug}.{pageCount}", 1), "img-20230714-beach-trip");
+    }
+
+    #[test]
+    fn test_transforms_and_escapes() {
+        assert_eq!(render("{name|snake|upper}", 1), "IMG_20230714_BEACH_TRIP");
+        assert_eq!(render("{name|lower|title}", 1), "Img_20230714_Beach Trip");
+        assert_eq!(render("{name|max:3}", 1), "IMG");
+        assert_eq!(render("{exif.lens|default:no-lens} {{x}}", 1), "no-lens {x}");
+    }
+
+    #[test]
+    fn test_capture_groups() {
+        let rule = RuleParser::parse("file.name MATCHES 'IMG_([0-9]{4})(?P<month>[0-9]{2})'").unwrap();
+        let pattern = Regex::new(capture_pattern(&rule).unwrap()).unwrap();
+
+        let template = RenameTemplate::parse("{1}-{match.month}.{ext}").unwrap();
+        template.validate_captures(Some(&pattern)).unwrap();
+        let context = RenameContext::new(1).with_captures(&pattern, "IMG_20230714_Beach Trip");
+        assert_eq!(template.render(&context, fields).unwrap(), "2023-07.jpg");
+
+        let unknown = RenameTemplate::parse("{2}-{match.day}").unwrap();
+        assert!(unknown.validate_captures(Some(&pattern)).is_err());
+        assert!(template.validate_captures(None).is_err());
+        let negated = RuleParser::parse("NOT file.name MATCHES 'x(.)'").unwrap();
+        assert_eq!(capture_pattern(&negated), None);
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        for template in [
+            "",
+            "{nope}",
+            "{name:%Y}",
+            "{modifiedAt:%Q}",
+            "{n:abc}",
+            "{name|shout}",
+            "{name|max:0}",
+            "archive/{name}",
+            "{name",
+            "name}",
+        ] {
+            assert!(RenameTemplate::parse(template).is_err(), "{}", template);
+        }
+
+        // Parses, but a photo without a lens renders to nothing
+        let template = RenameTemplate::parse("{exif.lens}").unwrap();
+        assert!(template.render(&RenameContext::new(1), fields).is_err());
+    }
+}
@@ -13,13 +13,16 @@
 //! - **LocalVectorIndex**: Real semantic search via fastembed
 //!
 //! This module implements the main agent loop that:
-//! 1. Builds a ShadowVFS from the target folder
+//! 1. Builds a AgentVFS from the target folder
 //! 2. Checks file count to decide between full tree or sampling mode
 //! 3. Runs the coverage loop with Claude using V2 tools
 //! 4. Returns the finalized OrganizePlan
 
 use crate::ai::client::{CacheControl, ClaudeModel};
-use crate::ai::credentials::CredentialManager;
+use crate::ai::provider::{
+    active_provider, complete_with_retry, ChatMessage, ChatRequest, ChatResponse, ContentPart,
+    LlmProvider, Role, StopReason,
+};
 use crate::jobs::OrganizePlan;
 
 use super::analytics::DigestGenerator;
@@ -33,16 +36,13 @@ use super::prompts::{
 use super::rate_limiter::RateLimitManager;
 use super::sampling::{self, should_use_sampling};
 use super::tools::{execute_v2_tool, get_v2_organize_tools, V2ToolResult};
-use super::vfs::ShadowVFS;
+use super::vfs::AgentVFS;
 
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
+use std::sync::Arc;
 use std::time::Duration;
 
-const ANTHROPIC_API_URL: &str = "https://api.anthropic.com/v1/messages";
-const ANTHROPIC_VERSION: &str = "2023-06-01";
-
 /// Maximum retries for rate limit errors
 const MAX_RETRIES: u32 = 3;
 
@@ -159,15 +159,83 @@ struct ToolApiResponse {
     stop_reason: String,
 }
 
-/// API error response
-#[derive(Deserialize)]
-struct ApiError {
-    error: ApiErrorDetail,
+impl ToolMessageContent {
+    fn to_part(&self) -> ContentPart {
+        match self {
+            Self::Text {
+                text,
+                cache_control,
+                ..
+            } => ContentPart::Text {
+                text: text.clone(),
+                cache: cache_control.is_some(),
+            },
+            Self::ToolUse {
+                id, name, input, ..
+            } => ContentPart::tool_use(id.clone(), name.clone(), input.clone()),
+            Self::ToolResult {
+                tool_use_id,
+                content,
+                is_error,
+                ..
+            } => ContentPart::tool_result(
+                tool_use_id.clone(),
+                content.clone(),
+                is_error.unwrap_or(false),
+            ),
+        }
+    }
 }
 
-#[derive(Deserialize)]
-struct ApiErrorDetail {
-    message: String,
+impl ToolApiRequest {
+    /// Convert to a provider-neutral request
+    fn to_chat_request(&self) -> ChatRequest {
+        let messages = self
+            .messages
+            .iter()
+            .map(|m| ChatMessage {
+                role: if m.role == "assistant" {
+                    Role::Assistant
+                } else {
+                    Role::User
+                },
+                content: m.content.iter().map(ToolMessageContent::to_part).collect(),
+            })
+            .collect();
+
+        ChatRequest::new(self.model.clone(), self.max_tokens)
+            .with_system(self.system.clone())
+            .with_messages(messages)
+            .with_tools(self.tools.clone().unwrap_or_default())
+    }
+}
+
+impl From<ChatResponse> for ToolApiResponse {
+    fn from(response: ChatResponse) -> Self {
+        let content = response
+            .content
+            .into_iter()
+            .filter_map(|part| match part {
+                ContentPart::Text { text, .. } => Some(ContentBlockResponse::Text { text }),
+                ContentPart::ToolUse { id, name, input } => {
+                    Some(ContentBlockResponse::ToolUse { id, name, input })
+                }
+                _ => None,
+            })
+            .collect();
+
+        let stop_reason = match response.stop_reason {
+            StopReason::EndTurn => "end_turn".to_string(),
+            StopReason::ToolUse => "tool_use".to_string(),
+            StopReason::MaxTokens => "max_tokens".to_string(),
+            StopReason::Other(reason) => reason,
+        };
+
+        Self {
+            content,
+            stop_reason,
+        }
+    }
 }
 
 /// Event types emitted during the agent loop
@@ -240,7 +308,7 @@ pub struct ProgressEvent {
 /// - **Progress emitter**: Optional callback to emit analysis-progress events for UI progress bar
 ///
 /// This function:
-/// 1. Builds a ShadowVFS from the target folder
+/// 1. Builds a AgentVFS from the target folder
 /// 2. Checks file count to decide between full tree or sampling mode
 /// 3. Runs the coverage loop with Claude using V2 tools
 /// 4. Returns the final OrganizePlan
@@ -254,13 +322,13 @@ where
     F: Fn(&str, &str, Option<Vec<ExpandableDetail>>),
     P: Fn(ProgressEvent),
 {
-    // 1. Build ShadowVFS from target folder
+    // 1. Build AgentVFS from target folder
     event_emitter("indexing", "Scanning folder structure...", Some(vec![
         ExpandableDetail { label: "Path".to_string(), value: target_folder.to_string_lossy().to_string() },
     ]));
     eprintln!("[V4AgentLoop] Building VFS for: {}", target_folder.display());
 
-    let mut vfs = ShadowVFS::new(target_folder).map_err(|e| {
+    let mut vfs = AgentVFS::new(target_folder).map_err(|e| {
         format!("Failed to scan folder: {}", e)
     })?;
 
@@ -379,11 +447,7 @@ where
 
     // 5. Initialize conversation
     let tools = get_v2_organize_tools();
-    let client = Client::builder()
-        .timeout(Duration::from_secs(120))
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
-    let api_key = CredentialManager::get_api_key("anthropic")?;
+    let provider = active_provider()?;
 
     // V3: Initialize rate limiter for header-based dynamic delays
     let mut rate_limiter = RateLimitManager::new();
@@ -460,71 +524,18 @@ where
         };
 
         // Send request with exponential backoff for rate limits
-        let mut retry_delay = Duration::from_secs(5);
-        let mut last_error = String::new();
-        let mut response_result = None;
-
-        for retry in 0..=MAX_RETRIES {
-            if retry > 0 {
-                eprintln!("[V3AgentLoop] Rate limited, retrying in {:?} (attempt {}/{})", retry_delay, retry, MAX_RETRIES);
-                event_emitter("thinking", &format!("Rate limited, waiting {:?}...", retry_delay), Some(vec![
+        let api_response = send_api_request_with_retry(
+            provider.as_ref(),
+            &request,
+            &mut rate_limiter,
+            |retry, delay| {
+                event_emitter("thinking", &format!("Rate limited, waiting {:?}...", delay), Some(vec![
                     ExpandableDetail { label: "Retry".to_string(), value: format!("{}/{}", retry, MAX_RETRIES) },
-                    ExpandableDetail { label: "Delay".to_string(), value: format!("{:?}", retry_delay) },
+                    ExpandableDetail { label: "Delay".to_string(), value: format!("{:?}", delay) },
                 ]));
-                tokio::time::sleep(retry_delay).await;
-                retry_delay *= 2; // Exponential backoff
-            }
-
-            let resp = client
-                .post(ANTHROPIC_API_URL)
-                .header("x-api-key", &api_key)
-                .header("anthropic-version", ANTHROPIC_VERSION)
-                .header("content-type", "application/json")
-                .json(&request)
-                .send()
-                .await;
-
-            match resp {
-                Ok(r) if r.status() == 429 => {
-                    // Rate limited - get retry-after header if available
-                    if let Some(retry_after) = r.headers().get("retry-after") {
-                        if let Ok(secs) = retry_after.to_str().unwrap_or("5").parse::<u64>() {
-                            retry_delay = Duration::from_secs(secs);
-                        }
-                    }
-                    last_error = "Rate limit exceeded".to_string();
-                    continue;
-                }
-                Ok(r) => {
-                    response_result = Some(r);
-                    break;
-                }
-                Err(e) => {
-                    last_error = format!("Request failed: {}", e);
-                    continue;
-                }
-            }
-        }
-
-        let response = response_result.ok_or_else(|| format!("Max retries exceeded: {}", last_error))?;
-
-        // V3: Update rate limiter from response headers before consuming response
-        rate_limiter.update_from_response(&response);
-
-        let status = response.status();
-
-        if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            if let Ok(api_error) = serde_json::from_str::<ApiError>(&error_text) {
-                return Err(format!("API error: {}", api_error.error.message));
-            }
-            return Err(format!("API error ({}): {}", status, error_text));
-        }
-
-        let api_response: ToolApiResponse = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+            },
+        )
+        .await?;
 
         eprintln!("[V3AgentLoop] stop_reason: {}", api_response.stop_reason);
 
@@ -676,18 +687,7 @@ where
                 let plan = OrganizePlan {
                     plan_id: format!("plan-{}", chrono::Utc::now().timestamp_millis()),
                     description: "Auto-generated organization plan".to_string(),
-                    operations: vfs
-                        .operations()
-                        .iter()
-                        .map(|op| crate::jobs::OrganizeOperation {
-                            op_id: op.op_id.clone(),
-                            op_type: op.op_type.to_string(),
-                            source: op.source.clone(),
-                            destination: op.destination.clone(),
-                            path: op.path.clone(),
-                            new_name: op.new_name.clone(),
-                        })
-                        .collect(),
+                    operations: vfs.organize_operations(),
                     // organization_root is the target folder - all organization stays within it
                     target_folder: vfs.organization_root().to_string_lossy().to_string(),
                     simplification_recommended: None,
@@ -742,7 +742,7 @@ async fn run_v4_sampled_loop<F, P>(
     target_folder: &Path,
     user_request: &str,
     event_emitter: &F,
-    vfs: &mut ShadowVFS,
+    vfs: &mut AgentVFS,
     progress_emitter: Option<&P>,
 ) -> Result<OrganizePlan, String>
 where
@@ -758,13 +758,9 @@ where
 
     eprintln!("[V4SampledLoop] Starting Map-Reduce flow for {} files", file_count);
 
-    // Initialize HTTP client and rate limiter
+    // Initialize LLM provider and rate limiter
     let tools = get_v2_organize_tools();
-    let client = Client::builder()
-        .timeout(Duration::from_secs(180)) // Longer timeout for large folders
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
-    let api_key = CredentialManager::get_api_key("anthropic")?;
+    let provider = active_provider()?;
     let mut rate_limiter = RateLimitManager::new();
 
     // V4 uses fewer iterations but with higher coverage per iteration
@@ -824,7 +820,7 @@ where
             sampling::generate_sample(&all_files, 0)
         } else {
             // Subsequent iterations: sample only unmatched files (janitor pass)
-            sampling::generate_unmatched_sample(&all_files, vfs.matched_paths())
+            sampling::generate_unmatched_sample(&all_files, &vfs.matched_paths())
         };
 
         // Build context
@@ -867,17 +863,10 @@ where
             tools: Some(tools.clone()),
         };
 
-        // Make API call with retry logic
-        let response = send_api_request_with_retry(&client, &api_key, &request, &mut rate_limiter).await?;
-
-        // Update rate limiter
-        rate_limiter.update_from_response(&response);
-
-        // Process response
-        let api_response: ToolApiResponse = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        // Make API call with retry logic (also updates the rate limiter)
+        let api_response =
+            send_api_request_with_retry(provider.as_ref(), &request, &mut rate_limiter, |_, _| {})
+                .await?;
 
         eprintln!("[V4SampledLoop] stop_reason: {}", api_response.stop_reason);
 
@@ -971,18 +960,7 @@ where
                 coverage * 100.0,
                 vfs.organized_count()
             ),
-            operations: vfs
-                .operations()
-                .iter()
-                .map(|op| crate::jobs::OrganizeOperation {
-                    op_id: op.op_id.clone(),
-                    op_type: op.op_type.to_string(),
-                    source: op.source.clone(),
-                    destination: op.destination.clone(),
-                    path: op.path.clone(),
-                    new_name: op.new_name.clone(),
-                })
-                .collect(),
+            operations: vfs.organize_operations(),
             // organization_root is the target folder - all organization stays within it
             target_folder: vfs.organization_root().to_string_lossy().to_string(),
             simplification_recommended: None,
@@ -1005,57 +983,32 @@ where
 }
 
 /// Helper function to send API request with retry logic
+///
+/// Goes through the active LLM provider, retries rate limits and transient
+/// failures with exponential backoff, and feeds response headers to the
+/// rate limiter. `on_retry` receives the attempt number and delay.
 async fn send_api_request_with_retry(
-    client: &Client,
-    api_key: &str,
+    provider: &dyn LlmProvider,
     request: &ToolApiRequest,
-    _rate_limiter: &mut RateLimitManager,
-) -> Result<reqwest::Response, String> {
-    let mut retry_delay = Duration::from_secs(5);
-
-    for retry in 0..=MAX_RETRIES {
-        if retry > 0 {
-            eprintln!("[V4SampledLoop] Retrying in {:?} (attempt {}/{})", retry_delay, retry, MAX_RETRIES);
-            tokio::time::sleep(retry_delay).await;
-            retry_delay *= 2;
-        }
-
-        let resp = client
-            .post(ANTHROPIC_API_URL)
-            .header("x-api-key", api_key)
-            .header("anthropic-version", ANTHROPIC_VERSION)
-            .header("content-type", "application/json")
-            .json(request)
-            .send()
-            .await;
-
-        match resp {
-            Ok(r) if r.status() == 429 => {
-                if let Some(retry_after) = r.headers().get("retry-after") {
-                    if let Ok(secs) = retry_after.to_str().unwrap_or("5").parse::<u64>() {
-                        retry_delay = Duration::from_secs(secs);
-                    }
-                }
-                continue;
-            }
-            Ok(r) if r.status().is_success() => {
-                return Ok(r);
-            }
-            Ok(r) => {
-                let status = r.status();
-                let error_text = r.text().await.unwrap_or_default();
-                return Err(format!("API error ({}): {}", status, error_text));
-            }
-            Err(e) => {
-                if retry == MAX_RETRIES {
-                    return Err(format!("Request failed after {} retries: {}", MAX_RETRIES, e));
-                }
-                continue;
-            }
-        }
-    }
-
-    Err("Max retries exceeded".to_string())
+    rate_limiter: &mut RateLimitManager,
+    on_retry: impl Fn(u32, Duration),
+) -> Result<ToolApiResponse, String> {
+    let response = complete_with_retry(
+        provider,
+        &request.to_chat_request(),
+        MAX_RETRIES,
+        Duration::from_secs(5),
+        |retry, delay| {
+            eprintln!("[AgentLoop] Retrying in {:?} (attempt {}/{})", delay, retry, MAX_RETRIES);
+            on_retry(retry, delay);
+        },
+    )
+    .await
+    .map_err(|e| format!("API error: {}", e))?;
+
+    rate_limiter.update_from_headers(&response.headers);
+
+    Ok(response.into())
 }
 
 /// V5 Hologram loop for pattern-heavy large folders
@@ -1081,7 +1034,7 @@ async fn run_v5_hologram_loop<F, P>(
     target_folder: &Path,
     user_request: &str,
     event_emitter: &F,
-    vfs: &mut ShadowVFS,
+    vfs: &mut AgentVFS,
     progress_emitter: Option<&P>,
 ) -> Result<OrganizePlan, String>
 where
@@ -1125,13 +1078,9 @@ where
         hologram.stats.outlier_count
     );
 
-    // Initialize HTTP client and rate limiter
+    // Initialize LLM provider and rate limiter
     let tools = get_v2_organize_tools();
-    let client = Client::builder()
-        .timeout(Duration::from_secs(180))
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
-    let api_key = CredentialManager::get_api_key("anthropic")?;
+    let provider = active_provider()?;
     let mut rate_limiter = RateLimitManager::new();
 
     // V5 uses fewer iterations since patterns are pre-computed
@@ -1209,17 +1158,10 @@ where
             tools: Some(tools.clone()),
         };
 
-        // Make API call with retry logic
-        let response = send_api_request_with_retry(&client, &api_key, &request, &mut rate_limiter).await?;
-
-        // Update rate limiter
-        rate_limiter.update_from_response(&response);
-
-        // Process response
-        let api_response: ToolApiResponse = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        // Make API call with retry logic (also updates the rate limiter)
+        let api_response =
+            send_api_request_with_retry(provider.as_ref(), &request, &mut rate_limiter, |_, _| {})
+                .await?;
 
         eprintln!("[V5HologramLoop] stop_reason: {}", api_response.stop_reason);
 
@@ -1335,18 +1277,7 @@ where
                 coverage * 100.0,
                 hologram.stats.pattern_count
             ),
-            operations: vfs
-                .operations()
-                .iter()
-                .map(|op| crate::jobs::OrganizeOperation {
-                    op_id: op.op_id.clone(),
-                    op_type: op.op_type.to_string(),
-                    source: op.source.clone(),
-                    destination: op.destination.clone(),
-                    path: op.path.clone(),
-                    new_name: op.new_name.clone(),
-                })
-                .collect(),
+            operations: vfs.organize_operations(),
             // organization_root is the target folder - all organization stays within it
             target_folder: vfs.organization_root().to_string_lossy().to_string(),
             simplification_recommended: None,
@@ -1414,7 +1345,7 @@ async fn run_v4_sampled_loop_with_blueprint<F, P>(
     target_folder: &Path,
     user_request: &str,
     event_emitter: &F,
-    vfs: &mut ShadowVFS,
+    vfs: &mut AgentVFS,
     blueprint: &Blueprint,
     progress_emitter: Option<&P>,
 ) -> Result<OrganizePlan, String>
@@ -1442,7 +1373,7 @@ async fn run_v5_hologram_loop_with_blueprint<F, P>(
     target_folder: &Path,
     user_request: &str,
     event_emitter: &F,
-    vfs: &mut ShadowVFS,
+    vfs: &mut AgentVFS,
     blueprint: &Blueprint,
     progress_emitter: Option<&P>,
 ) -> Result<OrganizePlan, String>
@@ -1496,17 +1427,35 @@ where
     F: Fn(&str, &str, Option<Vec<ExpandableDetail>>),
     P: Fn(ProgressEvent),
 {
-    use super::prompts::{build_hybrid_context, V6_HYBRID_SYSTEM_PROMPT};
-
-    // 1. Build ShadowVFS from target folder
+    // 1. Build AgentVFS from target folder
     event_emitter("indexing", "Building virtual filesystem...", Some(vec![
         ExpandableDetail { label: "Path".to_string(), value: target_folder.to_string_lossy().to_string() },
     ]));
 
-    let mut vfs = ShadowVFS::new(target_folder).map_err(|e| {
+    let vfs = AgentVFS::new(target_folder).map_err(|e| {
         format!("Failed to scan folder: {}", e)
     })?;
+    let provider = active_provider()?;
+
+    run_v6_hybrid_with(vfs, provider, user_request, analyses, event_emitter, progress_emitter).await
+}
 
+/// V6 hybrid loop over an already built AgentVFS, sending requests to `provider`
+async fn run_v6_hybrid_with<F, P>(
+    mut vfs: AgentVFS,
+    provider: Arc<dyn LlmProvider>,
+    user_request: &str,
+    analyses: Vec<crate::ai::grok::FileAnalysis>,
+    event_emitter: F,
+    progress_emitter: Option<P>,
+) -> Result<OrganizePlan, String>
+where
+    F: Fn(&str, &str, Option<Vec<ExpandableDetail>>),
+    P: Fn(ProgressEvent),
+{
+    use super::prompts::{build_hybrid_context, V6_HYBRID_SYSTEM_PROMPT};
+
+    let target_folder = vfs.root().to_path_buf();
     let file_count = vfs.file_count();
 
     // Emit progress
@@ -1545,13 +1494,8 @@ where
         context.len()
     );
 
-    // 3. Initialize HTTP client and tools
+    // 3. Initialize tools
     let tools = get_v2_organize_tools();
-    let client = Client::builder()
-        .timeout(Duration::from_secs(180))
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
-    let api_key = CredentialManager::get_api_key("anthropic")?;
     let mut rate_limiter = RateLimitManager::new();
 
     // V6 uses fewer iterations since analyses are pre-computed
@@ -1615,17 +1559,10 @@ where
             tools: Some(tools.clone()),
         };
 
-        // Make API call with retry logic
-        let response = send_api_request_with_retry(&client, &api_key, &request, &mut rate_limiter).await?;
-
-        // Update rate limiter
-        rate_limiter.update_from_response(&response);
-
-        // Process response
-        let api_response: ToolApiResponse = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        // Make API call with retry logic (also updates the rate limiter)
+        let api_response =
+            send_api_request_with_retry(provider.as_ref(), &request, &mut rate_limiter, |_, _| {})
+                .await?;
 
         eprintln!("[V6HybridLoop] stop_reason: {}", api_response.stop_reason);
 
@@ -1727,18 +1664,7 @@ where
                 coverage * 100.0,
                 analyses.len()
             ),
-            operations: vfs
-                .operations()
-                .iter()
-                .map(|op| crate::jobs::OrganizeOperation {
-                    op_id: op.op_id.clone(),
-                    op_type: op.op_type.to_string(),
-                    source: op.source.clone(),
-                    destination: op.destination.clone(),
-                    path: op.path.clone(),
-                    new_name: op.new_name.clone(),
-                })
-                .collect(),
+            operations: vfs.organize_operations(),
             target_folder: vfs.organization_root().to_string_lossy().to_string(),
             simplification_recommended: None,
         };
@@ -1826,7 +1752,7 @@ where
 
     // Build VFS
     event_emitter("indexing", "Scanning folder structure...", None);
-    let mut vfs = ShadowVFS::new(target_folder).map_err(|e| {
+    let mut vfs = AgentVFS::new(target_folder).map_err(|e| {
         format!("Failed to scan folder: {}", e)
     })?;
 
@@ -1870,17 +1796,9 @@ Simplify this folder structure by flattening deep nesting and consolidating spar
         ExpandableDetail { label: "Directories".to_string(), value: dir_count.to_string() },
     ]));
 
-    // Get API key
-    let api_key = crate::ai::CredentialManager::get_api_key("anthropic")
-        .map_err(|_| "Anthropic API key not configured".to_string())?;
-
-    // Initialize client
-    let client = Client::builder()
-        .timeout(Duration::from_secs(120))
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let provider = active_provider()?;
 
-    let rate_limiter = RateLimitManager::new();
+    let mut rate_limiter = RateLimitManager::new();
     let tools = get_v2_organize_tools();
 
     // Build messages
@@ -1908,25 +1826,9 @@ Simplify this folder structure by flattening deep nesting and consolidating spar
         };
 
         // Send request
-        let response = client
-            .post(ANTHROPIC_API_URL)
-            .header("x-api-key", &api_key)
-            .header("anthropic-version", ANTHROPIC_VERSION)
-            .header("content-type", "application/json")
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
-
-        if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(format!("API error: {}", error_text));
-        }
-
-        let api_response: ToolApiResponse = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        let api_response =
+            send_api_request_with_retry(provider.as_ref(), &request, &mut rate_limiter, |_, _| {})
+                .await?;
 
         // Collect assistant content for the conversation
         let mut assistant_content = Vec::new();
@@ -2027,18 +1929,7 @@ Simplify this folder structure by flattening deep nesting and consolidating spar
                 "Folder structure simplification: {} operations",
                 vfs.operations().len()
             ),
-            operations: vfs
-                .operations()
-                .iter()
-                .map(|op| crate::jobs::OrganizeOperation {
-                    op_id: op.op_id.clone(),
-                    op_type: op.op_type.to_string(),
-                    source: op.source.clone(),
-                    destination: op.destination.clone(),
-                    path: op.path.clone(),
-                    new_name: op.new_name.clone(),
-                })
-                .collect(),
+            operations: vfs.organize_operations(),
             target_folder: vfs.organization_root().to_string_lossy().to_string(),
             simplification_recommended: None,
         };
@@ -2079,4 +1970,135 @@ mod tests {
         let result = ToolMessageContent::tool_result("123", "success", false);
         assert!(matches!(result, ToolMessageContent::ToolResult { .. }));
     }
+
+    /// Embeds texts by hashing their words, so tests never load a model
+    struct HashEmbedder;
+
+    impl crate::ai::v2::TextEmbedder for HashEmbedder {
+        fn embed(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>, String> {
+            Ok(texts
+                .iter()
+                .map(|text| {
+                    let mut embedding = vec![0.0; 16];
+                    for word in text.split_whitespace() {
+                        let hash = word
+                            .bytes()
+                            .fold(0usize, |h, b| h.wrapping_mul(31).wrapping_add(b as usize));
+                        embedding[hash % 16] += 1.0;
+                    }
+                    embedding
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_v6_hybrid_replays_recorded_session() {
+        use crate::ai::cassette::{fixture_path, install, Cassette};
+        use crate::ai::grok::FileAnalysis;
+        use crate::ai::provider::AnthropicProvider;
+        use crate::ai::rules::DocumentContentSource;
+        use crate::ai::v2::{LocalVectorConfig, LocalVectorIndex};
+        use crate::execution::{journal_from_plan, ExecutionEngine};
+        use crate::vector::EmbeddingStore;
+        use crate::wal::journal::WALManager;
+
+        // Files, caches and the WAL all live in the temp folder
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().join("files");
+        std::fs::create_dir(&root).unwrap();
+        let names = [
+            "invoice-acme-2024.pdf",
+            "lease-agreement.pdf",
+            "beach.jpg",
+            "receipt-scan.png",
+            "notes.txt",
+        ];
+        for name in names {
+            std::fs::write(root.join(name), name).unwrap();
+        }
+        let analyses = names
+            .iter()
+            .map(|name| FileAnalysis {
+                file_path: root.join(name).to_string_lossy().to_string(),
+                old_name: name.to_string(),
+                new_name: name.to_string(),
+                summary: String::new(),
+                entities: Vec::new(),
+                doc_type: "other".to_string(),
+            })
+            .collect();
+
+        let store = EmbeddingStore::open(&temp.path().join("embeddings"), "hash").unwrap();
+        let index = LocalVectorIndex::with_embedder(
+            LocalVectorConfig::default(),
+            Arc::new(HashEmbedder),
+        )
+        .with_store(store);
+        let content = DocumentContentSource::new(Some(temp.path().join("content")));
+        let vfs = AgentVFS::with_index(&root, index, content).unwrap();
+
+        let guard = install(
+            Cassette::replay(fixture_path("v6_hybrid_organize.json"))
+                .unwrap()
+                .with_placeholder("ROOT", root.to_string_lossy()),
+        )
+        .await;
+
+        let plan = run_v6_hybrid_with(
+            vfs,
+            Arc::new(AnthropicProvider::new("unused")),
+            "Sort these by type",
+            analyses,
+            |_, _, _| {},
+            None::<fn(ProgressEvent)>,
+        )
+        .await
+        .unwrap();
+
+        // Both recorded iterations were used; the plan came from commit_plan
+        assert_eq!(guard.cassette().remaining(), 0);
+        assert_eq!(
+            plan.description,
+            "Group documents, images and notes into type folders"
+        );
+
+        // Run the plan through the WAL and check the folder it leaves behind
+        let journal = journal_from_plan(&plan).unwrap();
+        let manager = WALManager::with_dir(temp.path().join("wal"));
+        manager.save_journal(&journal).unwrap();
+        let result = ExecutionEngine::with_manager(manager)
+            .execute_journal(&plan.plan_id)
+            .await
+            .unwrap();
+        assert!(result.success, "{:?}", result.errors);
+
+        let mut tree: Vec<String> = walkdir::WalkDir::new(&root)
+            .min_depth(1)
+            .into_iter()
+            .map(|entry| {
+                let entry = entry.unwrap();
+                let path = entry.path().strip_prefix(&root).unwrap();
+                path.to_string_lossy().replace('\\', "/")
+            })
+            .collect();
+        tree.sort();
+        assert_eq!(
+            tree,
+            [
+                "Documents",
+                "Documents/invoice-acme-2024.pdf",
+                "Documents/lease-agreement.pdf",
+                "Images",
+                "Images/beach.jpg",
+                "Images/receipt-scan.png",
+                "Notes",
+                "Notes/notes.txt",
+            ]
+        );
+        assert_eq!(
+            std::fs::read_to_string(root.join("Notes/notes.txt")).unwrap(),
+            "notes.txt"
+        );
+    }
 }
@@ -15,22 +15,17 @@
 //! The Blueprint is then used by the Builder to slot files efficiently.
 
 use crate::ai::client::ClaudeModel;
-use crate::ai::credentials::CredentialManager;
+use crate::ai::provider::{active_provider, complete_with_retry, ChatMessage, ChatRequest};
 use super::agent_loop::ExpandableDetail;
-use super::rate_limiter::RateLimitManager;
 use super::sampling;
-use super::vfs::ShadowVFS;
+use super::vfs::AgentVFS;
 
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::fs::File;
 use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 
-const ANTHROPIC_API_URL: &str = "https://api.anthropic.com/v1/messages";
-const ANTHROPIC_VERSION: &str = "2023-06-01";
-
 /// Maximum file header size to read (1KB)
 const MAX_HEADER_SIZE: usize = 1024;
 
@@ -105,53 +100,12 @@ pub struct FolderStats {
     pub date_range: Option<(String, String)>,
 }
 
-/// API request structure
-#[derive(Serialize)]
-struct ArchitectApiRequest {
-    model: String,
-    max_tokens: u32,
-    system: String,
-    messages: Vec<Message>,
-}
-
-#[derive(Serialize)]
-struct Message {
-    role: String,
-    content: String,
-}
-
-/// API response structure
-#[derive(Deserialize)]
-struct ApiResponse {
-    content: Vec<ContentBlock>,
-    #[allow(dead_code)]
-    stop_reason: String,
-}
-
-#[derive(Deserialize)]
-#[serde(tag = "type")]
-enum ContentBlock {
-    #[serde(rename = "text")]
-    Text { text: String },
-}
-
-/// API error structure
-#[derive(Deserialize)]
-struct ApiError {
-    error: ApiErrorDetail,
-}
-
-#[derive(Deserialize)]
-struct ApiErrorDetail {
-    message: String,
-}
-
 /// Run the Architect phase to generate a Blueprint.
 ///
 /// # Arguments
 /// * `target_folder` - Path to the folder being organized
 /// * `user_instruction` - User's organization request
-/// * `vfs` - ShadowVFS for file access
+/// * `vfs` - AgentVFS for file access
 /// * `event_emitter` - Callback for UI progress events
 ///
 /// # Returns
@@ -159,7 +113,7 @@ struct ApiErrorDetail {
 pub async fn run_architect<F>(
     target_folder: &Path,
     user_instruction: &str,
-    vfs: &ShadowVFS,
+    vfs: &AgentVFS,
     event_emitter: F,
 ) -> Result<Blueprint, String>
 where
@@ -222,7 +176,7 @@ where
 /// Build context for the Architect from VFS
 fn build_architect_context(
     target_folder: &Path,
-    vfs: &ShadowVFS,
+    vfs: &AgentVFS,
 ) -> Result<(Vec<FileSample>, FolderStats), String> {
     let all_files = vfs.all_files_vec();
 
@@ -342,107 +296,34 @@ async fn call_architect_llm(
     file_samples: &[FileSample],
     folder_stats: &FolderStats,
 ) -> Result<Blueprint, String> {
-    // Get API key
-    let api_key = CredentialManager::get_api_key("anthropic")?;
-
-    let client = Client::builder()
-        .timeout(Duration::from_secs(120))
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
-    let mut rate_limiter = RateLimitManager::new();
+    let provider = active_provider()?;
 
     // Build the prompt
     let prompt = build_architect_prompt(user_instruction, file_samples, folder_stats);
 
     eprintln!("[Architect] Prompt length: {} chars", prompt.len());
 
-    let request = ArchitectApiRequest {
-        model: ClaudeModel::Sonnet.as_str().to_string(),
-        max_tokens: 4096,
-        system: ARCHITECT_SYSTEM_PROMPT.to_string(),
-        messages: vec![Message {
-            role: "user".to_string(),
-            content: prompt,
-        }],
-    };
+    let request = ChatRequest::new(ClaudeModel::Sonnet.as_str(), 4096)
+        .with_system(ARCHITECT_SYSTEM_PROMPT)
+        .with_messages(vec![ChatMessage::user(prompt)]);
 
     // Send request with retries
-    let mut retry_delay = Duration::from_secs(5);
-    let mut last_error = String::new();
-    let mut response_result = None;
-
-    for retry in 0..=MAX_RETRIES {
-        if retry > 0 {
+    let response = complete_with_retry(
+        provider.as_ref(),
+        &request,
+        MAX_RETRIES,
+        Duration::from_secs(5),
+        |retry, delay| {
             eprintln!(
                 "[Architect] Rate limited, retrying in {:?} (attempt {}/{})",
-                retry_delay, retry, MAX_RETRIES
+                delay, retry, MAX_RETRIES
             );
-            tokio::time::sleep(retry_delay).await;
-            retry_delay *= 2;
-        }
+        },
+    )
+    .await
+    .map_err(|e| format!("API error: {}", e))?;
 
-        // Apply rate limit delay if needed
-        let delay = rate_limiter.get_delay();
-        if delay > Duration::ZERO {
-            tokio::time::sleep(delay).await;
-        }
-
-        let resp = client
-            .post(ANTHROPIC_API_URL)
-            .header("x-api-key", &api_key)
-            .header("anthropic-version", ANTHROPIC_VERSION)
-            .header("content-type", "application/json")
-            .json(&request)
-            .send()
-            .await;
-
-        match resp {
-            Ok(r) if r.status() == 429 => {
-                if let Some(retry_after) = r.headers().get("retry-after") {
-                    if let Ok(secs) = retry_after.to_str().unwrap_or("5").parse::<u64>() {
-                        retry_delay = Duration::from_secs(secs);
-                    }
-                }
-                last_error = "Rate limit exceeded".to_string();
-                continue;
-            }
-            Ok(r) => {
-                rate_limiter.update_from_response(&r);
-                response_result = Some(r);
-                break;
-            }
-            Err(e) => {
-                last_error = format!("Request failed: {}", e);
-                continue;
-            }
-        }
-    }
-
-    let response = response_result.ok_or_else(|| format!("Max retries exceeded: {}", last_error))?;
-
-    let status = response.status();
-    if !status.is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        if let Ok(api_error) = serde_json::from_str::<ApiError>(&error_text) {
-            return Err(format!("API error: {}", api_error.error.message));
-        }
-        return Err(format!("API error ({}): {}", status, error_text));
-    }
-
-    let api_response: ApiResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
-
-    // Extract text content
-    let text = api_response
-        .content
-        .iter()
-        .filter_map(|block| match block {
-            ContentBlock::Text { text } => Some(text.as_str()),
-        })
-        .collect::<Vec<_>>()
-        .join("");
+    let text = response.text();
 
     // Parse JSON from response (handle markdown code blocks)
     let json_str = extract_json_from_response(&text)?;
@@ -545,7 +426,7 @@ fn build_architect_prompt(
 /// This prepares the Blueprint for the Builder phase.
 pub fn embed_blueprint(
     blueprint: &Blueprint,
-    vfs: &ShadowVFS,
+    vfs: &AgentVFS,
 ) -> Result<Blueprint, String> {
     let mut embedded = blueprint.clone();
     let index = vfs.vector_index();
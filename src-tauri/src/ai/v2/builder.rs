@@ -10,19 +10,14 @@
 //! matching for the majority of files.
 
 use crate::ai::client::ClaudeModel;
-use crate::ai::credentials::CredentialManager;
+use crate::ai::provider::{active_provider, complete_with_retry, ChatMessage, ChatRequest};
 use crate::ai::rules::VirtualFile;
 
 use super::agent_loop::ExpandableDetail;
 use super::architect::Blueprint;
 use super::local_vector_index::LocalVectorIndex;
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
-const ANTHROPIC_API_URL: &str = "https://api.anthropic.com/v1/messages";
-const ANTHROPIC_VERSION: &str = "2023-06-01";
-
 /// Confidence threshold for automatic Tier 1 slotting
 const TIER1_THRESHOLD: f32 = 0.85;
 
@@ -220,84 +215,27 @@ async fn call_haiku_for_disambiguation(
     files: &[(String, String, Vec<(String, f32)>)],
     blueprint: &Blueprint,
 ) -> Result<Vec<(String, String)>, String> {
-    // Get API key
-    let api_key = CredentialManager::get_api_key("anthropic")?;
-
-    let client = Client::builder()
-        .timeout(Duration::from_secs(60)) // Shorter timeout for Haiku disambiguation
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let provider = active_provider()?;
 
     // Build context with file details and candidate folders
     let prompt = build_disambiguation_prompt(files, blueprint);
 
-    let request = HaikuApiRequest {
-        model: ClaudeModel::Haiku.as_str().to_string(),
-        max_tokens: 1024,
-        system: HAIKU_DISAMBIGUATION_PROMPT.to_string(),
-        messages: vec![HaikuMessage {
-            role: "user".to_string(),
-            content: prompt,
-        }],
-    };
+    let request = ChatRequest::new(ClaudeModel::Haiku.as_str(), 1024)
+        .with_system(HAIKU_DISAMBIGUATION_PROMPT)
+        .with_messages(vec![ChatMessage::user(prompt)]);
 
     // Send request with retries
-    let mut retry_delay = Duration::from_secs(2);
-    let mut last_error = String::new();
-    let mut response_result = None;
-
-    for retry in 0..=MAX_RETRIES {
-        if retry > 0 {
-            tokio::time::sleep(retry_delay).await;
-            retry_delay *= 2;
-        }
-
-        let resp = client
-            .post(ANTHROPIC_API_URL)
-            .header("x-api-key", &api_key)
-            .header("anthropic-version", ANTHROPIC_VERSION)
-            .header("content-type", "application/json")
-            .json(&request)
-            .send()
-            .await;
-
-        match resp {
-            Ok(r) if r.status() == 429 => {
-                last_error = "Rate limit exceeded".to_string();
-                continue;
-            }
-            Ok(r) => {
-                response_result = Some(r);
-                break;
-            }
-            Err(e) => {
-                last_error = format!("Request failed: {}", e);
-                continue;
-            }
-        }
-    }
-
-    let response = response_result.ok_or_else(|| format!("Max retries exceeded: {}", last_error))?;
-
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("API error: {}", error_text));
-    }
-
-    let api_response: HaikuApiResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
-
-    // Extract text content
-    let text = api_response
-        .content
-        .iter()
-        .filter_map(|block| match block {
-            HaikuContentBlock::Text { text } => Some(text.as_str()),
-        })
-        .collect::<Vec<_>>()
-        .join("");
+    let response = complete_with_retry(
+        provider.as_ref(),
+        &request,
+        MAX_RETRIES,
+        Duration::from_secs(2),
+        |_, _| {},
+    )
+    .await
+    .map_err(|e| format!("API error: {}", e))?;
+
+    let text = response.text();
 
     // Parse response
     parse_disambiguation_response(&text, files)
@@ -399,34 +337,6 @@ fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     }
 }
 
-/// Haiku API request
-#[derive(Serialize)]
-struct HaikuApiRequest {
-    model: String,
-    max_tokens: u32,
-    system: String,
-    messages: Vec<HaikuMessage>,
-}
-
-#[derive(Serialize)]
-struct HaikuMessage {
-    role: String,
-    content: String,
-}
-
-/// Haiku API response
-#[derive(Deserialize)]
-struct HaikuApiResponse {
-    content: Vec<HaikuContentBlock>,
-}
-
-#[derive(Deserialize)]
-#[serde(tag = "type")]
-enum HaikuContentBlock {
-    #[serde(rename = "text")]
-    Text { text: String },
-}
-
 /// System prompt for Haiku disambiguation
 const HAIKU_DISAMBIGUATION_PROMPT: &str = r#"You are a file categorization assistant. Given a list of files and their candidate folders, choose the single best folder for each file.
 
@@ -4,6 +4,8 @@
 //! - Fast search (<10ms per query after initialization)
 //! - Memory-efficient storage with pre-computed embeddings
 //! - Batch indexing during VFS creation
+//! - Optional persistent store, so unchanged file names are never re-embedded
+//! - HNSW nearest-neighbour search once the index grows past exact-scan size
 //!
 //! Uses the AllMiniLM-L6-V2 model (384 dimensions) via fastembed.
 //!
@@ -11,6 +13,7 @@
 //! compatibility with the rule evaluation system.
 
 use crate::ai::rules::{RuleError, VectorIndex};
+use crate::vector::{AnnIndex, EmbeddingStore, VectorModelType};
 use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -45,17 +48,36 @@ pub struct IndexedDocument {
     pub embedding: Vec<f32>,
 }
 
+/// Text embedding model behind the index
+///
+/// Implemented by the fastembed model; tests substitute a small
+/// deterministic embedder so no model has to be downloaded.
+pub trait TextEmbedder: Send + Sync {
+    /// Embed each text, in order
+    fn embed(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>, String>;
+}
+
+impl TextEmbedder for TextEmbedding {
+    fn embed(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>, String> {
+        TextEmbedding::embed(self, texts, None).map_err(|e| e.to_string())
+    }
+}
+
 /// Local vector index using fastembed for semantic file search
 ///
 /// This provides real semantic search capabilities using local embeddings,
 /// without requiring any external API calls.
 pub struct LocalVectorIndex {
-    /// Fastembed model instance (shared across queries)
-    model: Arc<TextEmbedding>,
+    /// Embedding model (shared across queries)
+    model: Arc<dyn TextEmbedder>,
     /// Indexed documents by path
     documents: HashMap<PathBuf, IndexedDocument>,
+    /// Nearest-neighbour index over the document embeddings
+    ann: AnnIndex<PathBuf>,
     /// Configuration
     config: LocalVectorConfig,
+    /// Persistent embeddings keyed by text
+    store: Option<EmbeddingStore>,
 }
 
 impl LocalVectorIndex {
@@ -74,11 +96,29 @@ impl LocalVectorIndex {
 
         eprintln!("[LocalVectorIndex] Model initialized successfully");
 
-        Ok(Self {
-            model: Arc::new(model),
+        Ok(Self::with_embedder(config, Arc::new(model)))
+    }
+
+    /// Create an index over a given embedding model
+    pub fn with_embedder(config: LocalVectorConfig, model: Arc<dyn TextEmbedder>) -> Self {
+        Self {
+            model,
             documents: HashMap::new(),
+            ann: AnnIndex::new(),
             config,
-        })
+            store: None,
+        }
+    }
+
+    /// Reuse and persist embeddings through the given store
+    pub fn with_store(mut self, store: EmbeddingStore) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Open the shared on-disk store for this index's model
+    pub fn open_default_store() -> Result<EmbeddingStore, String> {
+        EmbeddingStore::open_default(VectorModelType::AllMiniLmL6V2.id())
     }
 
     /// Create with default configuration
@@ -99,6 +139,26 @@ impl LocalVectorIndex {
             return Ok(0);
         }
 
+        // Embeddings already in the store only need to be loaded
+        let mut files_to_embed = Vec::with_capacity(files.len());
+        for (path, text) in files {
+            let stored = match &self.store {
+                Some(store) => store.lookup_text(&text).unwrap_or_else(|e| {
+                    eprintln!("[LocalVectorIndex] Warning: Store lookup failed: {}", e);
+                    None
+                }),
+                None => None,
+            };
+            match stored {
+                Some(embedding) => self.insert_document(path, text, embedding),
+                None => files_to_embed.push((path, text)),
+            }
+        }
+        let files = files_to_embed;
+        if files.is_empty() {
+            return Ok(self.documents.len());
+        }
+
         eprintln!(
             "[LocalVectorIndex] Indexing {} files...",
             files.len()
@@ -109,7 +169,7 @@ impl LocalVectorIndex {
         // Generate embeddings in batch (much faster than one-by-one)
         let embeddings = self
             .model
-            .embed(texts, None)
+            .embed(texts)
             .map_err(|e| format!("Batch embedding failed: {}", e))?;
 
         if embeddings.len() != files.len() {
@@ -120,15 +180,19 @@ impl LocalVectorIndex {
             ));
         }
 
+        if let Some(store) = &self.store {
+            let entries: Vec<_> = files
+                .iter()
+                .zip(&embeddings)
+                .map(|((_, text), embedding)| (None, text.as_str(), embedding.as_slice()))
+                .collect();
+            if let Err(e) = store.put_many(&entries) {
+                eprintln!("[LocalVectorIndex] Warning: Failed to persist embeddings: {}", e);
+            }
+        }
+
         for ((path, text), embedding) in files.into_iter().zip(embeddings) {
-            self.documents.insert(
-                path.clone(),
-                IndexedDocument {
-                    path,
-                    text,
-                    embedding,
-                },
-            );
+            self.insert_document(path, text, embedding);
         }
 
         eprintln!(
@@ -139,6 +203,19 @@ impl LocalVectorIndex {
         Ok(self.documents.len())
     }
 
+    /// Add or replace a document, keeping the nearest-neighbour index in sync
+    fn insert_document(&mut self, path: PathBuf, text: String, embedding: Vec<f32>) {
+        self.ann.insert(path.clone(), &embedding);
+        self.documents.insert(
+            path.clone(),
+            IndexedDocument {
+                path,
+                text,
+                embedding,
+            },
+        );
+    }
+
     /// Search for files matching a semantic query
     ///
     /// # Arguments
@@ -158,7 +235,7 @@ impl LocalVectorIndex {
         // Generate query embedding
         let query_embeddings = self
             .model
-            .embed(vec![query], None)
+            .embed(vec![query])
             .map_err(|e| format!("Query embedding failed: {}", e))?;
 
         let query_embedding = query_embeddings
@@ -166,20 +243,9 @@ impl LocalVectorIndex {
             .next()
             .ok_or("No query embedding generated")?;
 
-        // Compute similarities against all documents
-        let mut results: Vec<(PathBuf, f32)> = self
-            .documents
-            .iter()
-            .map(|(path, doc)| {
-                let score = cosine_similarity(&query_embedding, &doc.embedding);
-                (path.clone(), score)
-            })
-            .filter(|(_, score)| *score >= self.config.similarity_threshold)
-            .collect();
-
-        // Sort by score descending
-        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-        results.truncate(self.config.max_results);
+        // Top results by score descending
+        let mut results = self.ann.search(&query_embedding, self.config.max_results);
+        results.retain(|(_, score)| *score >= self.config.similarity_threshold);
 
         Ok(results)
     }
@@ -201,7 +267,7 @@ impl LocalVectorIndex {
 
         let query_embeddings = self
             .model
-            .embed(vec![query], None)
+            .embed(vec![query])
             .map_err(|e| format!("Query embedding failed: {}", e))?;
 
         let query_embedding = query_embeddings
@@ -250,13 +316,13 @@ impl LocalVectorIndex {
         }
 
         self.model
-            .embed(texts.to_vec(), None)
+            .embed(texts.to_vec())
             .map_err(|e| format!("Batch embedding failed: {}", e))
     }
 
     /// Get the embedding model for direct access
-    pub fn model(&self) -> &TextEmbedding {
-        &self.model
+    pub fn model(&self) -> &dyn TextEmbedder {
+        self.model.as_ref()
     }
 }
 
@@ -274,7 +340,7 @@ impl VectorIndex for LocalVectorIndex {
         // Generate query embedding
         let query_embeddings = self
             .model
-            .embed(vec![query], None)
+            .embed(vec![query])
             .map_err(|e| RuleError::new(format!("Query embedding failed: {}", e)))?;
 
         let query_embedding = query_embeddings
@@ -41,6 +41,6 @@ pub use architect::{Blueprint, BlueprintFolder};
 #[allow(unused_imports)]
 pub use builder::{BatchMatchResult, MatchResult};
 #[allow(unused_imports)]
-pub use local_vector_index::{LocalVectorConfig, LocalVectorIndex};
+pub use local_vector_index::{LocalVectorConfig, LocalVectorIndex, TextEmbedder};
 #[allow(unused_imports)]
 pub use rate_limiter::{RateLimitManager, RateLimitState};
@@ -41,12 +41,30 @@ Rules match files using a simple expression language:
 - `file.createdAt` - Created timestamp
 - `file.mimeType` - MIME type
 - `file.isHidden` - Whether hidden (starts with .)
+- `file.modifiedAt.year`, `.month`, `.day`, `.weekday`, `.hour` - Date parts
+  (also on `createdAt`; weekday is 1=Monday..7=Sunday; months/weekdays may be names)
+- `file.content` - Extracted document text (PDF, Office, text files)
+- `file.pageCount` - Number of pages of a parsed document
+- `file.title`, `file.author` - Document metadata (email subject and sender for mail)
+- `file.archive` - Names of the files inside a ZIP/TAR/7z archive, e.g.
+  `file.archive.contains('*.pdf')` (`*`/`?` match whole names; empty for non-archives)
+- `file.docType` - Cached classification: 'invoice', 'contract', 'receipt', ...
+- `file.entities` - Cached key entities (people, companies, amounts)
+- `file.summary` - Cached content summary
+  (docType/entities/summary are empty for files that were never analyzed)
+- `file.exif.takenAt` - Photo capture time from EXIF/XMP (also `.year`, `.month`, ...)
+- `file.exif.camera`, `file.exif.lens` - Camera (make and model) and lens
+- `file.exif.width`, `file.exif.height` - Photo dimensions in pixels
+- `file.exif.latitude`, `file.exif.longitude` - GPS position in decimal degrees
+  (exif fields are empty for files without photo metadata)
 
 ### Operators
 - `==`, `!=` - Equality
 - `>`, `<`, `>=`, `<=` - Comparison
 - `IN` - Check if value in array
 - `MATCHES` - Regex match
+- `CONTAINS` - Case-insensitive text contains, or any list element contains
+- `+`, `-`, `*`, `/` - Arithmetic on numbers, sizes, dates and durations
 
 ### Functions
 - `file.name.contains('text')` - String contains
@@ -54,6 +72,8 @@ Rules match files using a simple expression language:
 - `file.name.endsWith('suffix')` - String ends with
 - `file.name.matches('pattern')` - Regex match
 - `file.vector_similarity('query')` - Semantic similarity (0-1)
+- `now()` - Current time, e.g. `file.modifiedAt < now() - 90d`
+- `date('2024-01-31')` - A specific date
 
 ### Boolean Logic
 - `AND`, `&&` - Logical AND
@@ -61,9 +81,20 @@ Rules match files using a simple expression language:
 - `NOT` - Logical NOT
 - `(...)` - Grouping
 
+### Rename Templates (thenRenameTo)
+- `{name}`, `{ext}` and any field above: `{docType}`, `{exif.camera}`
+- `{n}` - Counter per rule, zero padded with `{n:03}`
+- `{modifiedAt:%Y-%m}`, `{exif.takenAt:%Y-%m-%d}` - Dates with a strftime format
+- `{1}`, `{match.year}` - Capture groups of the rule's `file.name MATCHES` pattern
+- `{name|slug}` - Transforms: `lower`, `upper`, `title`, `slug`, `snake`, `trim`, `max:N`, `default:TEXT`
+- Example: `{exif.takenAt:%Y-%m-%d}_{exif.camera|slug|default:unknown}_{n:03}.{ext}`
+
 ### Size Literals
 - `10KB`, `5MB`, `1GB` - Size with units
 
+### Duration Literals
+- `30min`, `12h`, `90d`, `2w`, `6mo`, `1y` - Time spans (mo = 30 days, y = 365 days)
+
 ### Examples
 ```
 file.ext == 'pdf'
@@ -72,6 +103,11 @@ file.name.contains('invoice') AND file.size > 10KB
 NOT file.isHidden AND file.ext == 'txt'
 (file.ext == 'jpg' OR file.ext == 'png') AND file.size < 5MB
 file.vector_similarity('tax document') > 0.7
+file.modifiedAt < now() - 90d
+file.createdAt.year == 2023 AND file.createdAt.month IN ['Jun', 'Jul', 'Aug']
+file.size / 1MB > 100
+file.docType == 'invoice' AND file.entities CONTAINS 'Acme'
+file.content.contains('total due') AND file.pageCount <= 2
 ```
 
 ## COMMON MISTAKES TO AVOID
@@ -105,8 +141,12 @@ file.name.has('test')             # Should be: file.name.contains('test')
 file.name.start('test')           # Should be: file.name.startsWith('test')
 ```
 
-Valid fields: `name`, `ext`, `size`, `path`, `modifiedAt`, `createdAt`, `mimeType`, `isHidden`
-Valid functions (on file.name only): `contains()`, `startsWith()`, `endsWith()`, `matches()`
+# WRONG: Date parts on non-date fields
+file.size.year == 2024            # Only modifiedAt and createdAt have date parts
+
+Valid fields: `name`, `ext`, `size`, `path`, `modifiedAt`, `createdAt`, `mimeType`, `isHidden`,
+`content`, `pageCount`, `title`, `author`, `archive`, `docType`, `entities`, `summary`
+Valid functions (on file.name and text fields): `contains()`, `startsWith()`, `endsWith()`, `matches()`
 
 ## WORKFLOW
 
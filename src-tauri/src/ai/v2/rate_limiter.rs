@@ -6,9 +6,14 @@
 //! - `anthropic-ratelimit-tokens-remaining`
 //! - `anthropic-ratelimit-tokens-reset`
 //!
+//! OpenAI-compatible backends report remaining quota as
+//! `x-ratelimit-remaining-requests` / `x-ratelimit-remaining-tokens`; local
+//! servers send neither, so the minimum delay applies.
+//!
 //! This replaces the fixed MIN_REQUEST_DELAY_MS approach with intelligent
 //! backoff that maximizes throughput while avoiding rate limits.
 
+use reqwest::header::HeaderMap;
 use reqwest::Response;
 use std::time::{Duration, Instant};
 
@@ -88,21 +93,32 @@ impl RateLimitManager {
     ///
     /// Call this after every API response to keep the rate limiter informed
     pub fn update_from_response(&mut self, response: &Response) {
-        let headers = response.headers();
+        self.update_from_headers(response.headers());
+    }
 
-        self.state = RateLimitState {
-            requests_remaining: headers
-                .get("anthropic-ratelimit-requests-remaining")
+    /// Update state from response headers already captured by a provider
+    pub fn update_from_headers(&mut self, headers: &HeaderMap) {
+        let remaining = |anthropic: &str, openai: &str| -> Option<u32> {
+            headers
+                .get(anthropic)
+                .or_else(|| headers.get(openai))
                 .and_then(|v| v.to_str().ok())
-                .and_then(|s| s.parse().ok()),
+                .and_then(|s| s.parse().ok())
+        };
+
+        self.state = RateLimitState {
+            requests_remaining: remaining(
+                "anthropic-ratelimit-requests-remaining",
+                "x-ratelimit-remaining-requests",
+            ),
             requests_reset: headers
                 .get("anthropic-ratelimit-requests-reset")
                 .and_then(|v| v.to_str().ok())
                 .and_then(parse_reset_timestamp),
-            tokens_remaining: headers
-                .get("anthropic-ratelimit-tokens-remaining")
-                .and_then(|v| v.to_str().ok())
-                .and_then(|s| s.parse().ok()),
+            tokens_remaining: remaining(
+                "anthropic-ratelimit-tokens-remaining",
+                "x-ratelimit-remaining-tokens",
+            ),
             tokens_reset: headers
                 .get("anthropic-ratelimit-tokens-reset")
                 .and_then(|v| v.to_str().ok())
@@ -315,4 +331,17 @@ mod tests {
         assert!(limiter.state.requests_remaining.is_none());
         assert!(!limiter.has_header_info);
     }
+
+    #[test]
+    fn test_update_from_openai_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining-requests", "3".parse().unwrap());
+        headers.insert("x-ratelimit-remaining-tokens", "12000".parse().unwrap());
+
+        let mut limiter = RateLimitManager::new();
+        limiter.update_from_headers(&headers);
+
+        assert_eq!(limiter.state().requests_remaining, Some(3));
+        assert_eq!(limiter.state().tokens_remaining, Some(12000));
+    }
 }
@@ -10,7 +10,7 @@ use crate::ai::tools::ToolDefinition;
 use crate::jobs::OrganizePlan;
 use crate::utils::format_size;
 
-use super::vfs::{OperationType, OrganizationRule, ShadowVFS};
+use super::vfs::{AgentVFS, OperationType, OrganizationRule};
 use serde_json::json;
 
 /// Get V2 tool definitions for the agent
@@ -44,7 +44,10 @@ pub fn get_v2_organize_tools() -> Vec<ToolDefinition> {
                                 "name": { "type": "string" },
                                 "if": { "type": "string" },
                                 "thenMoveTo": { "type": "string" },
-                                "thenRenameTo": { "type": "string" },
+                                "thenRenameTo": {
+                                    "type": "string",
+                                    "description": "Rename template, e.g. '{exif.takenAt:%Y-%m-%d}_{n:03}.{ext}'"
+                                },
                                 "priority": { "type": "integer" }
                             },
                             "required": ["name", "if"]
@@ -119,7 +122,7 @@ pub enum V2ToolResult {
 pub fn execute_v2_tool(
     name: &str,
     input: &serde_json::Value,
-    vfs: &mut ShadowVFS,
+    vfs: &mut AgentVFS,
 ) -> V2ToolResult {
     match name {
         "query_semantic_index" => execute_query_semantic(input, vfs),
@@ -131,7 +134,7 @@ pub fn execute_v2_tool(
     }
 }
 
-fn execute_query_semantic(input: &serde_json::Value, vfs: &ShadowVFS) -> V2ToolResult {
+fn execute_query_semantic(input: &serde_json::Value, vfs: &AgentVFS) -> V2ToolResult {
     let query = match input.get("query").and_then(|v| v.as_str()) {
         Some(q) => q,
         None => return V2ToolResult::Error("Missing 'query' parameter".to_string()),
@@ -193,7 +196,7 @@ fn execute_query_semantic(input: &serde_json::Value, vfs: &ShadowVFS) -> V2ToolR
     V2ToolResult::Continue(output)
 }
 
-fn execute_apply_rules(input: &serde_json::Value, vfs: &mut ShadowVFS) -> V2ToolResult {
+fn execute_apply_rules(input: &serde_json::Value, vfs: &mut AgentVFS) -> V2ToolResult {
     // Debug: log the full input structure
     eprintln!("[V2Tool] apply_organization_rules input: {}", serde_json::to_string_pretty(input).unwrap_or_default());
 
@@ -271,7 +274,7 @@ fn execute_apply_rules(input: &serde_json::Value, vfs: &mut ShadowVFS) -> V2Tool
     }
 }
 
-fn execute_preview(input: &serde_json::Value, vfs: &ShadowVFS) -> V2ToolResult {
+fn execute_preview(input: &serde_json::Value, vfs: &AgentVFS) -> V2ToolResult {
     let group_by = input
         .get("group_by")
         .and_then(|v| v.as_str())
@@ -300,6 +303,19 @@ fn execute_preview(input: &serde_json::Value, vfs: &ShadowVFS) -> V2ToolResult {
         output.push_str(&format!("Unchanged files: {}\n", preview.unchanged_files));
     }
 
+    if !preview.collisions.is_empty() {
+        output.push_str(&format!(
+            "Name collisions: {} (suffixed to stay unique)\n",
+            preview.collisions.len()
+        ));
+        for collision in preview.collisions.iter().take(5) {
+            output.push_str(&format!(
+                "  - {} -> {}\n",
+                collision.requested, collision.resolved
+            ));
+        }
+    }
+
     output.push('\n');
 
     // Sort groups for consistent output
@@ -379,7 +395,7 @@ fn execute_preview(input: &serde_json::Value, vfs: &ShadowVFS) -> V2ToolResult {
     V2ToolResult::Continue(output)
 }
 
-fn execute_commit(input: &serde_json::Value, vfs: &ShadowVFS) -> V2ToolResult {
+fn execute_commit(input: &serde_json::Value, vfs: &AgentVFS) -> V2ToolResult {
     let description = match input.get("description").and_then(|v| v.as_str()) {
         Some(d) => d,
         None => return V2ToolResult::Error("Missing 'description' parameter".to_string()),
@@ -420,17 +436,7 @@ fn execute_commit(input: &serde_json::Value, vfs: &ShadowVFS) -> V2ToolResult {
     }
 
     // Convert to OrganizeOperation format
-    let organize_ops: Vec<crate::jobs::OrganizeOperation> = operations
-        .iter()
-        .map(|op| crate::jobs::OrganizeOperation {
-            op_id: op.op_id.clone(),
-            op_type: op.op_type.to_string(),
-            source: op.source.clone(),
-            destination: op.destination.clone(),
-            path: op.path.clone(),
-            new_name: op.new_name.clone(),
-        })
-        .collect();
+    let organize_ops = vfs.organize_operations();
 
     let plan = OrganizePlan {
         plan_id: format!("plan-{}", chrono::Utc::now().timestamp_millis()),
@@ -456,7 +462,7 @@ fn execute_commit(input: &serde_json::Value, vfs: &ShadowVFS) -> V2ToolResult {
 /// V5: Execute inspect_pattern_sample tool
 ///
 /// Returns sample files matching a regex pattern for detailed inspection.
-fn execute_inspect_pattern_sample(input: &serde_json::Value, vfs: &ShadowVFS) -> V2ToolResult {
+fn execute_inspect_pattern_sample(input: &serde_json::Value, vfs: &AgentVFS) -> V2ToolResult {
     let pattern = match input.get("pattern_regex").and_then(|v| v.as_str()) {
         Some(p) => p,
         None => return V2ToolResult::Error("Missing 'pattern_regex' parameter".to_string()),
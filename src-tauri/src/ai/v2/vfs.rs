@@ -1,22 +1,37 @@
-//! Shadow Virtual File System for planning file operations.
+//! Agent view of the folder being organized.
 //!
-//! The ShadowVFS maintains a virtual representation of the file system that
-//! the agent can modify without touching the real filesystem. This allows:
+//! The AgentVFS gives the agent rule evaluation, semantic search and a
+//! compressed tree over the target folder. Planning itself happens on the
+//! shared [`ShadowVFS`](crate::vfs::ShadowVFS): every operation the agent
+//! emits is staged there, so collisions and coverage are decided by the same
+//! logic that `vfs_validate_plan` uses when the committed plan is replayed.
+//! This allows:
 //! - Safe preview of planned operations
 //! - Conflict detection before execution
 //! - Rule-based bulk operations
 
-use crate::ai::rules::{RuleEvaluator, VirtualFile, VectorIndex};
+use crate::ai::rules::{
+    capture_pattern, DocumentContentSource, RenameContext, RenameTemplate, RuleEvaluator,
+    VirtualFile, VectorIndex,
+};
+use crate::ai::grok::document_parser::archive_search_text;
+use crate::execution::simulated_operation;
+use crate::jobs::OrganizeOperation;
 use crate::security::PathValidator;
 use crate::utils::format_size;
+use crate::vfs::{simulate_operation, FileNode, ShadowVFS, VFSNodeType};
 use super::local_vector_index::{LocalVectorConfig, LocalVectorIndex};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
 /// Maximum number of operations allowed to prevent memory exhaustion with large folders
 const MAX_OPERATIONS: usize = 5000;
 
+/// Characters of an archive's listing and member text added to its vector
+/// index text
+const MAX_INDEXED_ARCHIVE_TEXT: usize = 2000;
+
 /// A planned file operation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -38,6 +53,20 @@ pub struct PlannedOperation {
     pub rule_name: Option<String>,
 }
 
+impl PlannedOperation {
+    /// Convert to the plan format used by validation and execution
+    pub fn to_organize_operation(&self) -> OrganizeOperation {
+        OrganizeOperation {
+            op_id: self.op_id.clone(),
+            op_type: self.op_type.to_string(),
+            source: self.source.clone(),
+            destination: self.destination.clone(),
+            path: self.path.clone(),
+            new_name: self.new_name.clone(),
+        }
+    }
+}
+
 /// Types of file operations
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -89,40 +118,34 @@ pub struct ApplyRulesResult {
     pub rules_applied: usize,
 }
 
-/// Shadow Virtual File System for planning operations
-pub struct ShadowVFS {
+/// Agent workspace over the target folder, planning on a [`ShadowVFS`]
+pub struct AgentVFS {
     /// Root path of the target folder (the folder being organized)
     root: PathBuf,
     /// Organization root - where new folders are created (parent of target folder)
     /// This allows organizing `cuero/2025/` to create `cuero/NewFolder/` instead of `cuero/2025/NewFolder/`
     organization_root: PathBuf,
-    /// Virtual files indexed by path
+    /// Virtual files indexed by path, for rule evaluation
     files: HashMap<String, VirtualFile>,
-    /// Planned operations
+    /// Planning model: staged operations, collisions and coverage
+    shadow: ShadowVFS,
+    /// Planned operations, in plan order
     operations: Vec<PlannedOperation>,
     /// Operation ID counter
     op_counter: usize,
     /// Vector index for semantic search (uses fastembed LocalVectorIndex in V3)
     vector_index: LocalVectorIndex,
-    /// V4: Tracks file paths that have been matched by rules (for coverage calculation)
-    matched_files: std::collections::HashSet<String>,
-    /// V5: Tracks destination paths to detect collisions during planning
-    /// Maps destination path -> source path that claimed it
-    destination_registry: HashMap<String, String>,
+    /// Document text and cached analyses for content fields (file.docType, ...)
+    content_source: DocumentContentSource,
+    /// Planned names that were taken and got a counter suffix
+    collisions: Vec<NameCollision>,
 }
 
-impl ShadowVFS {
-    /// Create a new ShadowVFS from a target folder
+impl AgentVFS {
+    /// Create a new AgentVFS from a target folder
     ///
     /// V3: Uses LocalVectorIndex with fastembed for real semantic search
     pub fn new(root: &Path) -> std::io::Result<Self> {
-        let mut files = HashMap::new();
-        let mut file_list = Vec::new();
-
-        // Recursively scan the folder
-        Self::scan_directory(root, &mut files, &mut file_list)?;
-
-        // Build the LocalVectorIndex with batch indexing
         let config = LocalVectorConfig::default();
         let mut vector_index = LocalVectorIndex::new(config).map_err(|e| {
             std::io::Error::new(
@@ -130,18 +153,46 @@ impl ShadowVFS {
                 format!("Failed to create vector index: {}", e),
             )
         })?;
+        match LocalVectorIndex::open_default_store() {
+            Ok(store) => vector_index = vector_index.with_store(store),
+            Err(e) => tracing::warn!(error = %e, "Vector embeddings will not be persisted"),
+        }
+
+        Self::with_index(root, vector_index, DocumentContentSource::open_default())
+    }
+
+    /// Create an AgentVFS that indexes into `vector_index` and reads content
+    /// fields from `content_source`, instead of the shared on-disk defaults
+    pub fn with_index(
+        root: &Path,
+        mut vector_index: LocalVectorIndex,
+        content_source: DocumentContentSource,
+    ) -> std::io::Result<Self> {
+        let mut files = HashMap::new();
+        let mut file_list = Vec::new();
+        let mut shadow = ShadowVFS::new(root.to_path_buf());
+
+        // Recursively scan the folder
+        Self::scan_directory(root, &mut files, &mut file_list, &mut shadow)?;
 
         // Prepare batch data: (path, searchable_text)
-        // searchable_text combines filename and extension for better semantic matching
+        // searchable_text combines filename and extension for better semantic matching,
+        // plus the member names and text of archives so their contents are findable
         let batch_data: Vec<(PathBuf, String)> = file_list
             .iter()
             .filter(|f| !f.is_directory)
             .map(|f| {
-                let text = format!(
+                let mut text = format!(
                     "{} {}",
                     f.name,
                     f.ext.as_deref().unwrap_or("")
                 );
+                if let Some(contents) =
+                    archive_search_text(Path::new(&f.path), MAX_INDEXED_ARCHIVE_TEXT)
+                {
+                    text.push(' ');
+                    text.push_str(&contents);
+                }
                 (PathBuf::from(&f.path), text)
             })
             .collect();
@@ -163,11 +214,12 @@ impl ShadowVFS {
             root: root.to_path_buf(),
             organization_root,
             files,
+            shadow,
             operations: Vec::new(),
             op_counter: 0,
             vector_index,
-            matched_files: std::collections::HashSet::new(),
-            destination_registry: HashMap::new(),
+            content_source,
+            collisions: Vec::new(),
         })
     }
 
@@ -180,25 +232,29 @@ impl ShadowVFS {
         dir: &Path,
         files: &mut HashMap<String, VirtualFile>,
         file_list: &mut Vec<VirtualFile>,
+        shadow: &mut ShadowVFS,
     ) -> std::io::Result<()> {
         for entry in std::fs::read_dir(dir)? {
             let entry = entry?;
             let path = entry.path();
 
             // Security: Skip symlinks to prevent path traversal attacks
-            // and avoid following symlinks to directories outside the root
+            // and avoid following symlinks to directories outside the root.
+            // They still occupy their name, so the planner must not overwrite them.
             if PathValidator::is_symlink(&path) {
                 tracing::debug!(path = %path.display(), "Skipping symlink during VFS scan");
+                shadow.insert_linked(FileNode::new(path, VFSNodeType::Symlink));
                 continue;
             }
 
             if let Ok(vf) = VirtualFile::from_path(&path) {
                 let path_str = path.to_string_lossy().to_string();
+                shadow.insert_linked(file_node(&vf, &path));
                 file_list.push(vf.clone());
                 files.insert(path_str, vf);
 
                 if path.is_dir() {
-                    Self::scan_directory(&path, files, file_list)?;
+                    Self::scan_directory(&path, files, file_list, shadow)?;
                 }
             }
         }
@@ -241,49 +297,29 @@ impl ShadowVFS {
         &self.vector_index
     }
 
+    /// Get the planning model the operations are staged on
+    pub fn shadow(&self) -> &ShadowVFS {
+        &self.shadow
+    }
+
     /// Get planned operations
     pub fn operations(&self) -> &[PlannedOperation] {
         &self.operations
     }
 
-    /// Clear all planned operations
+    /// Get planned operations in the plan format used by validation and execution
+    pub fn organize_operations(&self) -> Vec<OrganizeOperation> {
+        self.operations
+            .iter()
+            .map(PlannedOperation::to_organize_operation)
+            .collect()
+    }
+
+    /// Clear all planned operations (and the coverage they provided)
     pub fn clear_operations(&mut self) {
         self.operations.clear();
-        self.destination_registry.clear();
-    }
-
-    /// Generate a unique destination path by appending a counter suffix
-    /// e.g., file.pdf -> file_1.pdf, file_2.pdf, etc.
-    fn generate_unique_destination(&self, dest_folder: &Path, original_name: &str) -> (PathBuf, String) {
-        let path = Path::new(original_name);
-        let stem = path
-            .file_stem()
-            .map(|s| s.to_string_lossy().to_string())
-            .unwrap_or_else(|| original_name.to_string());
-        let ext = path
-            .extension()
-            .map(|e| format!(".{}", e.to_string_lossy()))
-            .unwrap_or_default();
-
-        let mut counter = 1;
-        loop {
-            let candidate_name = format!("{}_{}{}", stem, counter, ext);
-            let full_path = dest_folder.join(&candidate_name);
-            let path_str = full_path.to_string_lossy().to_string();
-
-            // Check if this destination is already claimed or exists on disk
-            if !self.destination_registry.contains_key(&path_str) && !full_path.exists() {
-                return (full_path, candidate_name);
-            }
-
-            counter += 1;
-            if counter > 1000 {
-                // Safety limit - use UUID suffix
-                let uuid_name = format!("{}_{}{}", stem, uuid::Uuid::new_v4(), ext);
-                let uuid_path = dest_folder.join(&uuid_name);
-                return (uuid_path, uuid_name);
-            }
-        }
+        self.collisions.clear();
+        self.shadow.clear_staged();
     }
 
     // ========== V4 Coverage Tracking Methods ==========
@@ -293,32 +329,32 @@ impl ShadowVFS {
         self.files.values().filter(|f| !f.is_directory).count()
     }
 
-    /// Get count of files that have been matched by rules
+    /// Get count of files covered by the plan
     pub fn organized_count(&self) -> usize {
-        self.matched_files.len()
+        self.shadow.covered_file_count()
     }
 
-    /// Get files that haven't been matched by any rule yet
+    /// Get files the plan does not cover yet
     pub fn get_unmatched_files(&self) -> Vec<VirtualFile> {
         self.files
             .values()
-            .filter(|f| !f.is_directory && !self.matched_files.contains(&f.path))
+            .filter(|f| !f.is_directory && !self.shadow.is_covered(Path::new(&f.path)))
             .cloned()
             .collect()
     }
 
-    /// Get the set of matched file paths (for sampling)
-    pub fn matched_paths(&self) -> &std::collections::HashSet<String> {
-        &self.matched_files
+    /// Get the set of covered file paths (for sampling)
+    pub fn matched_paths(&self) -> HashSet<String> {
+        self.files
+            .values()
+            .filter(|f| !f.is_directory && self.shadow.is_covered(Path::new(&f.path)))
+            .map(|f| f.path.clone())
+            .collect()
     }
 
     /// Calculate current coverage percentage
     pub fn coverage(&self) -> f64 {
-        let total = self.file_count();
-        if total == 0 {
-            return 1.0;
-        }
-        self.matched_files.len() as f64 / total as f64
+        self.shadow.coverage()
     }
 
     /// Check if coverage target (95%) has been reached
@@ -326,11 +362,6 @@ impl ShadowVFS {
         self.coverage() >= 0.95
     }
 
-    /// Reset coverage tracking (but keep operations)
-    pub fn reset_coverage(&mut self) {
-        self.matched_files.clear();
-    }
-
     /// Get all files as a Vec (for sampling)
     pub fn all_files_vec(&self) -> Vec<VirtualFile> {
         self.files
@@ -405,8 +436,7 @@ impl ShadowVFS {
         mode: &str,
     ) -> Result<ApplyRulesResult, String> {
         if mode == "replace" {
-            self.operations.clear();
-            self.destination_registry.clear();
+            self.clear_operations();
         }
 
         // Sort rules by priority (descending)
@@ -419,8 +449,8 @@ impl ShadowVFS {
         let mut parsing_errors: Vec<(String, String)> = Vec::new();
         let mut rules_applied = 0;
 
-        // Collect folders that need to be created
-        let mut folders_to_create: std::collections::HashSet<String> = std::collections::HashSet::new();
+        // Folder creations, staged parents first and placed before the other operations
+        let mut folder_ops: Vec<PlannedOperation> = Vec::new();
 
         for rule in &sorted_rules {
             // Parse the rule condition - collect errors instead of failing
@@ -436,11 +466,36 @@ impl ShadowVFS {
                 }
             };
 
+            // Validate the rename template before touching any file
+            let template = match &rule.then_rename_to {
+                Some(pattern) => match RenameTemplate::parse(pattern) {
+                    Ok(template) => Some(template),
+                    Err(e) => {
+                        parsing_errors.push((
+                            rule.name.clone(),
+                            format!("Invalid rename template '{}': {}", pattern, e),
+                        ));
+                        continue;
+                    }
+                },
+                None => None,
+            };
+            // Capture groups of a file.name MATCHES test feed {1}, {match.x}
+            let name_pattern = capture_pattern(&expr).and_then(|p| regex::Regex::new(p).ok());
+            if let Some(template) = &template {
+                if let Err(e) = template.validate_captures(name_pattern.as_ref()) {
+                    parsing_errors.push((rule.name.clone(), format!("Invalid rename template: {}", e)));
+                    continue;
+                }
+            }
+            let mut counter = 0;
+
             rules_applied += 1;
-            let evaluator = RuleEvaluator::new(&self.vector_index);
+            let evaluator =
+                RuleEvaluator::new(&self.vector_index).with_content(&self.content_source);
 
-            // Find matching files
-            let matching_files: Vec<VirtualFile> = self
+            // Find matching files, in path order so {n} counters are stable
+            let mut matching_files: Vec<VirtualFile> = self
                 .files()
                 .iter()
                 .filter(|f| {
@@ -450,18 +505,23 @@ impl ShadowVFS {
                 .cloned()
                 .cloned()
                 .collect();
+            matching_files.sort_by(|a, b| a.path.cmp(&b.path));
 
             for file in matching_files {
                 processed_files.insert(file.path.clone());
-                // V4: Track matched files for coverage calculation
-                self.matched_files.insert(file.path.clone());
-
-                // Handle move operation
-                if let Some(ref dest_folder) = rule.then_move_to {
-                    // Security: Validate destination path using PathValidator
-                    // Disallow absolute paths - all destinations must be relative to organization_root
-                    // organization_root is the target folder itself, so all organized files stay within it
-                    let dest_path = match PathValidator::validate_destination(
+                let source_path = PathBuf::from(&file.path);
+                // V4: A matched file counts towards coverage even if it stays in place
+                self.shadow.mark_covered(source_path.clone());
+
+                if rule.then_move_to.is_none() && rule.then_rename_to.is_none() {
+                    continue;
+                }
+
+                // Security: Validate destination path using PathValidator
+                // Disallow absolute paths - all destinations must be relative to organization_root
+                // organization_root is the target folder itself, so all organized files stay within it
+                let dest_folder = match &rule.then_move_to {
+                    Some(dest_folder) => match PathValidator::validate_destination(
                         dest_folder,
                         &self.organization_root,
                         false, // Disallow absolute paths in organization rules
@@ -477,81 +537,108 @@ impl ShadowVFS {
                             );
                             continue;
                         }
-                    };
-
-                    // Track folder creation
-                    let dest_str = dest_path.to_string_lossy().to_string();
-                    if !folders_to_create.contains(&dest_str)
-                        && !self.files.contains_key(&dest_str)
-                    {
-                        folders_to_create.insert(dest_str.clone());
+                    },
+                    None => match source_path.parent() {
+                        Some(parent) => parent.to_path_buf(),
+                        None => continue,
+                    },
+                };
+
+                // A move and a rename together become a single move under the new name
+                let file_name = match &template {
+                    Some(template) => {
+                        counter += 1;
+                        let mut context = RenameContext::new(counter);
+                        if let Some(pattern) = &name_pattern {
+                            context = context.with_captures(pattern, &file.name);
+                        }
+                        let fields = RuleEvaluator::new(&self.vector_index)
+                            .with_content(&self.content_source);
+                        let rendered =
+                            template.render(&context, |field| fields.get_field_value(field, &file));
+                        match rendered {
+                            Ok(name) => name,
+                            Err(e) => {
+                                tracing::warn!(
+                                    rule = %rule.name,
+                                    file = %file.path,
+                                    error = %e,
+                                    "Skipping rename with an unusable name"
+                                );
+                                continue;
+                            }
+                        }
                     }
-
-                    // Create move operation with collision detection
-                    let file_name = Path::new(&file.path)
+                    None => source_path
                         .file_name()
                         .map(|n| n.to_string_lossy().to_string())
-                        .unwrap_or_default();
-
-                    // V5: Check if destination is already claimed or exists on disk
-                    let initial_dest = dest_path.join(&file_name);
-                    let initial_dest_str = initial_dest.to_string_lossy().to_string();
-
-                    // Skip if source == destination (file already in correct location)
-                    let source_path = PathBuf::from(&file.path);
-                    if source_path == initial_dest {
-                        continue; // Already at destination, no move needed
-                    }
-
-                    let final_dest = if self.destination_registry.contains_key(&initial_dest_str)
-                        || initial_dest.exists()
-                    {
-                        // Collision detected - generate unique destination
-                        tracing::debug!(
-                            source = %file.path,
-                            destination = %initial_dest_str,
-                            "Collision detected, generating unique name"
+                        .unwrap_or_default(),
+                };
+
+                // V5: Stage on the shadow VFS, which creates missing folders and
+                // resolves collisions with existing files and earlier destinations
+                let placement = match self.shadow.stage_placement(&source_path, &dest_folder, &file_name) {
+                    Ok(Some(placement)) => placement,
+                    Ok(None) => continue, // Already at destination, no move needed
+                    Err(e) => {
+                        tracing::warn!(
+                            rule = %rule.name,
+                            file = %file.path,
+                            error = %e,
+                            "Skipping operation rejected by the VFS"
                         );
-                        let (unique_path, _) = self.generate_unique_destination(&dest_path, &file_name);
-                        unique_path
-                    } else {
-                        initial_dest
-                    };
-
-                    // Register this destination as claimed
-                    let final_dest_str = final_dest.to_string_lossy().to_string();
-                    self.destination_registry
-                        .insert(final_dest_str.clone(), file.path.clone());
+                        continue;
+                    }
+                };
 
+                for folder in placement.created_folders {
                     let op_id = self.next_op_id();
-                    self.operations.push(PlannedOperation {
+                    folder_ops.push(PlannedOperation {
+                        op_id,
+                        op_type: OperationType::CreateFolder,
+                        source: None,
+                        destination: None,
+                        path: Some(folder.to_string_lossy().to_string()),
+                        new_name: None,
+                        rule_name: None,
+                    });
+                }
+
+                let op_id = self.next_op_id();
+                let operation = if rule.then_move_to.is_some() {
+                    PlannedOperation {
                         op_id,
                         op_type: OperationType::Move,
                         source: Some(file.path.clone()),
-                        destination: Some(final_dest_str),
+                        destination: Some(placement.destination.to_string_lossy().to_string()),
                         path: None,
                         new_name: None,
                         rule_name: Some(rule.name.clone()),
-                    });
-                    operations_created += 1;
-                }
-
-                // Handle rename operation
-                if let Some(ref new_name_pattern) = rule.then_rename_to {
-                    let new_name = self.apply_rename_pattern(new_name_pattern, &file);
-
-                    let op_id = self.next_op_id();
-                    self.operations.push(PlannedOperation {
+                    }
+                } else {
+                    PlannedOperation {
                         op_id,
                         op_type: OperationType::Rename,
                         source: None,
                         destination: None,
                         path: Some(file.path.clone()),
-                        new_name: Some(new_name),
+                        new_name: placement
+                            .destination
+                            .file_name()
+                            .map(|n| n.to_string_lossy().to_string()),
                         rule_name: Some(rule.name.clone()),
+                    }
+                };
+                let requested = dest_folder.join(&file_name);
+                if placement.destination != requested {
+                    self.collisions.push(NameCollision {
+                        op_id: operation.op_id.clone(),
+                        requested: requested.to_string_lossy().to_string(),
+                        resolved: placement.destination.to_string_lossy().to_string(),
                     });
-                    operations_created += 1;
                 }
+                self.operations.push(operation);
+                operations_created += 1;
 
                 // Check operation limit to prevent memory exhaustion
                 if self.operations.len() > MAX_OPERATIONS {
@@ -564,27 +651,13 @@ impl ShadowVFS {
             }
         }
 
-        // Add folder creation operations at the beginning
-        let folder_ops: Vec<PlannedOperation> = folders_to_create
-            .into_iter()
-            .map(|path| {
-                self.op_counter += 1;
-                PlannedOperation {
-                    op_id: format!("op-{}", self.op_counter),
-                    op_type: OperationType::CreateFolder,
-                    source: None,
-                    destination: None,
-                    path: Some(path),
-                    new_name: None,
-                    rule_name: None,
-                }
-            })
-            .collect();
-
-        // Prepend folder operations
-        let mut combined_ops = folder_ops;
-        combined_ops.append(&mut self.operations);
-        self.operations = combined_ops;
+        // Folder creations go after those of earlier calls, before any move
+        let insert_at = self
+            .operations
+            .iter()
+            .take_while(|op| op.op_type == OperationType::CreateFolder)
+            .count();
+        self.operations.splice(insert_at..insert_at, folder_ops);
 
         Ok(ApplyRulesResult {
             operations_created,
@@ -593,26 +666,6 @@ impl ShadowVFS {
         })
     }
 
-    /// Apply a rename pattern to a file
-    fn apply_rename_pattern(&self, pattern: &str, file: &VirtualFile) -> String {
-        let mut result = pattern.to_string();
-
-        // Replace placeholders
-        result = result.replace("{name}", &file.name);
-        if let Some(ref ext) = file.ext {
-            result = result.replace("{ext}", ext);
-        }
-        if let Some(modified) = file.modified_at {
-            // Convert to date string
-            let date = chrono::DateTime::from_timestamp_millis(modified)
-                .map(|dt| dt.format("%Y-%m-%d").to_string())
-                .unwrap_or_default();
-            result = result.replace("{date}", &date);
-        }
-
-        result
-    }
-
     /// Preview operations grouped by a field
     pub fn preview_operations(
         &self,
@@ -648,7 +701,8 @@ impl ShadowVFS {
         }
 
         let unchanged_count = if include_unchanged {
-            self.files().len() - self.operations.len()
+            let changed = self.shadow.staged_moves().len() + self.shadow.staged_deletes().len();
+            self.file_count().saturating_sub(changed)
         } else {
             0
         };
@@ -660,21 +714,31 @@ impl ShadowVFS {
                 .collect(),
             total_operations: self.operations.len(),
             unchanged_files: unchanged_count,
+            collisions: self.collisions.clone(),
         }
     }
 
     /// Add a single operation manually
-    pub fn add_operation(&mut self, op_type: OperationType, params: OperationParams) {
-        let op_id = self.next_op_id();
-        self.operations.push(PlannedOperation {
-            op_id,
+    ///
+    /// The operation is staged on the shadow VFS first and rejected if it
+    /// would fail plan validation.
+    pub fn add_operation(&mut self, op_type: OperationType, params: OperationParams) -> Result<(), String> {
+        let operation = PlannedOperation {
+            op_id: format!("op-{}", self.op_counter + 1),
             op_type,
             source: params.source,
             destination: params.destination,
             path: params.path,
             new_name: params.new_name,
             rule_name: params.rule_name,
-        });
+        };
+        let simulated = simulated_operation(&operation.to_organize_operation())
+            .ok_or_else(|| format!("Incomplete {} operation", operation.op_type))?;
+        simulate_operation(&mut self.shadow, simulated).map_err(|e| e.to_string())?;
+
+        self.op_counter += 1;
+        self.operations.push(operation);
+        Ok(())
     }
 
     /// Generate a compressed tree representation for context
@@ -687,7 +751,7 @@ impl ShadowVFS {
         // Use aggressive compression for large folders to fit context limits
         let file_count = self.files.len();
         let config = if file_count > 500 {
-            eprintln!("[ShadowVFS] Large folder detected ({} files), using aggressive compression", file_count);
+            eprintln!("[AgentVFS] Large folder detected ({} files), using aggressive compression", file_count);
             TreeConfig {
                 collapse_threshold: 15,   // Collapse folders with 15+ files
                 max_depth: 4,             // Limit depth to reduce output
@@ -717,7 +781,7 @@ impl ShadowVFS {
                 }
             }
             Err(e) => {
-                eprintln!("[ShadowVFS] TreeCompressor failed: {}, using fallback", e);
+                eprintln!("[AgentVFS] TreeCompressor failed: {}, using fallback", e);
                 self.generate_fallback_tree()
             }
         }
@@ -772,6 +836,20 @@ impl ShadowVFS {
     }
 }
 
+/// Shadow VFS node for a scanned file
+fn file_node(file: &VirtualFile, path: &Path) -> FileNode {
+    let mut node = if file.is_directory {
+        FileNode::directory(path.to_path_buf())
+    } else {
+        FileNode::file(path.to_path_buf())
+    };
+    node.size = file.size;
+    node.mime_type = file.mime_type.clone();
+    node.modified_at = file.modified_at.and_then(chrono::DateTime::from_timestamp_millis);
+    node.created_at = file.created_at.and_then(chrono::DateTime::from_timestamp_millis);
+    node
+}
+
 /// Parameters for manual operation creation
 pub struct OperationParams {
     pub source: Option<String>,
@@ -791,6 +869,22 @@ pub struct OperationPreview {
     pub total_operations: usize,
     /// Number of files that won't be changed
     pub unchanged_files: usize,
+    /// Planned names that were already taken, with the name used instead
+    #[serde(default)]
+    pub collisions: Vec<NameCollision>,
+}
+
+/// A planned destination that was already claimed by an existing file or an
+/// earlier operation, resolved with a counter suffix
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NameCollision {
+    /// Operation that was given the suffixed name
+    pub op_id: String,
+    /// Path the rule asked for
+    pub requested: String,
+    /// Path the operation uses
+    pub resolved: String,
 }
 
 #[cfg(test)]
@@ -799,7 +893,7 @@ mod tests {
     use std::fs;
     use tempfile::tempdir;
 
-    fn create_test_vfs() -> (ShadowVFS, tempfile::TempDir) {
+    fn create_test_vfs() -> (AgentVFS, tempfile::TempDir) {
         let temp = tempdir().unwrap();
 
         // Create test files
@@ -809,7 +903,7 @@ mod tests {
         fs::write(temp.path().join("image2.png"), "fake image").unwrap();
         fs::write(temp.path().join("archive.zip"), "fake archive").unwrap();
 
-        let vfs = ShadowVFS::new(temp.path()).unwrap();
+        let vfs = AgentVFS::new(temp.path()).unwrap();
         (vfs, temp)
     }
 
@@ -843,22 +937,125 @@ mod tests {
         assert!(result.parsing_errors.is_empty()); // No parsing errors
     }
 
+    #[test]
+    fn test_agent_plan_passes_vfs_validation() {
+        let temp = tempdir().unwrap();
+        fs::create_dir(temp.path().join("Documents")).unwrap();
+        fs::write(temp.path().join("Documents").join("doc1.pdf"), "filed").unwrap();
+        fs::write(temp.path().join("doc1.pdf"), "test content").unwrap();
+        fs::write(temp.path().join("image1.jpg"), "fake image").unwrap();
+        fs::write(temp.path().join("archive.zip"), "fake archive").unwrap();
+        let mut vfs = AgentVFS::new(temp.path()).unwrap();
+
+        let rules = vec![
+            OrganizationRule {
+                name: "Move PDFs".to_string(),
+                condition: "file.ext == 'pdf'".to_string(),
+                then_move_to: Some("Documents/2024".to_string()),
+                then_rename_to: None,
+                priority: Some(2),
+            },
+            OrganizationRule {
+                name: "Rename images".to_string(),
+                condition: "file.ext == 'jpg'".to_string(),
+                then_move_to: None,
+                then_rename_to: Some("img-{name}.{ext}".to_string()),
+                priority: Some(1),
+            },
+        ];
+        vfs.apply_rules(&rules, "replace").unwrap();
+
+        // One folder to create, then two PDFs with distinct names and a rename
+        let ops = vfs.operations();
+        assert_eq!(ops.len(), 4);
+        assert_eq!(ops[0].op_type, OperationType::CreateFolder);
+        let destinations: HashSet<_> = ops.iter().filter_map(|op| op.destination.clone()).collect();
+        assert_eq!(destinations.len(), 2);
+
+        // Replaying the committed plan on a fresh VFS agrees with the planner
+        let operations: Vec<_> = vfs
+            .organize_operations()
+            .iter()
+            .filter_map(simulated_operation)
+            .collect();
+        let mut fresh = vfs.shadow().clone();
+        fresh.clear_staged();
+        let replay = crate::vfs::validate_plan(&fresh, operations);
+        assert!(replay.errors.is_empty(), "{:?}", replay.errors);
+        assert_eq!(replay.coverage, vfs.coverage());
+        assert_eq!(vfs.coverage(), 0.75); // archive.zip is unmatched
+    }
+
+    #[test]
+    fn test_rename_templates_and_collisions() {
+        let temp = tempdir().unwrap();
+        fs::write(temp.path().join("IMG_0001.jpg"), "a").unwrap();
+        fs::write(temp.path().join("IMG_0002.jpg"), "b").unwrap();
+        fs::write(temp.path().join("photo-001.jpg"), "existing").unwrap();
+        fs::write(temp.path().join("SCAN_77.pdf"), "c").unwrap();
+        let mut vfs = AgentVFS::new(temp.path()).unwrap();
+
+        let rule = |name: &str, condition: &str, template: &str| OrganizationRule {
+            name: name.to_string(),
+            condition: condition.to_string(),
+            then_move_to: None,
+            then_rename_to: Some(template.to_string()),
+            priority: None,
+        };
+        let rules = vec![
+            rule("Number photos", "file.name MATCHES '^IMG_'", "photo-{n:03}.{ext}"),
+            rule("Scans", "file.name MATCHES '^SCAN_([0-9]+)'", "scan-{1|upper}.{ext}"),
+            rule("Typo", "file.ext == 'txt'", "{nmae}.{ext}"),
+            rule("No groups", "file.ext == 'txt'", "{1}.{ext}"),
+        ];
+        let result = vfs.apply_rules(&rules, "replace").unwrap();
+
+        let errors: Vec<_> = result.parsing_errors.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(errors, vec!["Typo", "No groups"]);
+
+        let mut names: Vec<_> = vfs.operations().iter().filter_map(|op| op.new_name.clone()).collect();
+        names.sort();
+        assert_eq!(names, vec!["photo-001_1.jpg", "photo-002.jpg", "scan-77.pdf"]);
+
+        // photo-001.jpg already exists, so the first photo got a suffix
+        let preview = vfs.preview_operations("rule_name", false);
+        assert_eq!(preview.collisions.len(), 1);
+        assert!(preview.collisions[0].requested.ends_with("photo-001.jpg"));
+        assert!(preview.collisions[0].resolved.ends_with("photo-001_1.jpg"));
+    }
+
     #[test]
     fn test_preview_operations() {
         let (mut vfs, _temp) = create_test_vfs();
 
+        let root = vfs.root().to_path_buf();
         vfs.add_operation(
             OperationType::Move,
             OperationParams {
-                source: Some("/test/file.pdf".to_string()),
-                destination: Some("/test/Documents/file.pdf".to_string()),
+                source: Some(root.join("doc1.pdf").to_string_lossy().to_string()),
+                destination: Some(root.join("renamed.pdf").to_string_lossy().to_string()),
                 path: None,
                 new_name: None,
                 rule_name: Some("test rule".to_string()),
             },
-        );
+        )
+        .unwrap();
 
         let preview = vfs.preview_operations("operation_type", false);
         assert_eq!(preview.total_operations, 1);
+
+        // Operations that would fail validation are rejected
+        let result = vfs.add_operation(
+            OperationType::Move,
+            OperationParams {
+                source: Some("/test/missing.pdf".to_string()),
+                destination: Some("/test/Documents/missing.pdf".to_string()),
+                path: None,
+                new_name: None,
+                rule_name: None,
+            },
+        );
+        assert!(result.is_err());
+        assert_eq!(vfs.operations().len(), 1);
     }
 }
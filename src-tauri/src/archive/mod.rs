@@ -0,0 +1,405 @@
+//! Archive Introspection Module
+//!
+//! Lists and reads archives without extracting them to disk:
+//! - `zip`: ZIP archives (central directory and members)
+//! - `tar`: TAR archives, plain or gzip-compressed
+//! - `sevenz`: 7z archives
+//!
+//! Member contents are only ever read into memory, capped at
+//! `MAX_MEMBER_BYTES`, so indexing an archive never writes files and a zip
+//! bomb cannot exhaust memory. Members are addressed by virtual paths below
+//! the archive, e.g. `invoices.zip/2024/march.pdf`, which is how they show
+//! up as child nodes in the VFS.
+
+mod sevenz;
+mod tar;
+mod zip;
+
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Extensions of files that may be archives (`gz` only as `.tar.gz`)
+pub const ARCHIVE_EXTENSIONS: &[&str] = &["zip", "tar", "tgz", "gz", "7z"];
+
+/// Members listed per archive; the rest are reported as truncated
+pub const MAX_LISTED_MEMBERS: usize = 10_000;
+
+/// Largest member read into memory (uncompressed)
+pub const MAX_MEMBER_BYTES: u64 = 32 * 1024 * 1024;
+
+/// Largest TAR/7z archive indexed automatically. Without a central
+/// directory, listing one decompresses the whole stream.
+pub const MAX_STREAMED_ARCHIVE_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Container format of an archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+    SevenZ,
+}
+
+impl ArchiveFormat {
+    /// Detect the format from the file name
+    pub fn detect(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".7z") {
+            Some(Self::SevenZ)
+        } else {
+            None
+        }
+    }
+
+    /// Whether members are listed from an index (the ZIP central directory)
+    /// instead of by reading through the whole archive
+    pub fn has_index(self) -> bool {
+        matches!(self, Self::Zip)
+    }
+}
+
+/// A file or folder inside an archive
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveMember {
+    /// Path inside the archive, `/`-separated, e.g. "2024/march.pdf"
+    pub name: String,
+    /// Uncompressed size in bytes (0 for folders)
+    pub size: u64,
+    /// Modification time (unix milliseconds)
+    pub modified_at: Option<i64>,
+    pub is_dir: bool,
+}
+
+impl ArchiveMember {
+    /// Last path segment, e.g. "march.pdf"
+    pub fn file_name(&self) -> &str {
+        self.name.rsplit('/').next().unwrap_or(&self.name)
+    }
+
+    /// Lowercase extension of the member, if any
+    pub fn extension(&self) -> Option<String> {
+        Path::new(self.file_name())
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+    }
+}
+
+/// Members of an archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveListing {
+    pub format: ArchiveFormat,
+    /// Members in archive order
+    pub members: Vec<ArchiveMember>,
+    /// The archive has more than `MAX_LISTED_MEMBERS` members
+    pub truncated: bool,
+}
+
+impl ArchiveListing {
+    /// File members (no folders)
+    pub fn files(&self) -> impl Iterator<Item = &ArchiveMember> {
+        self.members.iter().filter(|m| !m.is_dir)
+    }
+
+    /// Total uncompressed size of the listed files
+    pub fn total_size(&self) -> u64 {
+        self.files().map(|m| m.size).sum()
+    }
+}
+
+/// File members read into memory, with their content
+pub type MemberContents = Vec<(ArchiveMember, Vec<u8>)>;
+
+/// Called for each member with a reader over its content; return false to
+/// stop. Content not read by the visitor is skipped.
+type Visitor<'a> = dyn FnMut(&ArchiveMember, &mut dyn Read) -> Result<bool, String> + 'a;
+
+/// Check if a path names a supported archive
+pub fn is_archive(path: &Path) -> bool {
+    ArchiveFormat::detect(path).is_some()
+}
+
+/// List the members of an archive
+pub fn list(path: &Path) -> Result<ArchiveListing, String> {
+    let format = ArchiveFormat::detect(path)
+        .ok_or_else(|| format!("Not a supported archive: {}", path.display()))?;
+    let (members, truncated) = match format {
+        ArchiveFormat::Zip => zip::list(path, MAX_LISTED_MEMBERS)?,
+        ArchiveFormat::Tar => tar::list(path, false, MAX_LISTED_MEMBERS)?,
+        ArchiveFormat::TarGz => tar::list(path, true, MAX_LISTED_MEMBERS)?,
+        ArchiveFormat::SevenZ => sevenz::list(path, MAX_LISTED_MEMBERS)?,
+    };
+    Ok(ArchiveListing {
+        format,
+        members,
+        truncated,
+    })
+}
+
+/// Read the content of up to `limit` file members accepted by `wanted`
+///
+/// Members larger than `MAX_MEMBER_BYTES` are never selected. Everything
+/// stays in memory.
+pub fn read_members(
+    path: &Path,
+    wanted: &dyn Fn(&ArchiveMember) -> bool,
+    limit: usize,
+) -> Result<MemberContents, String> {
+    let format = ArchiveFormat::detect(path)
+        .ok_or_else(|| format!("Not a supported archive: {}", path.display()))?;
+    let mut found = Vec::new();
+    if limit == 0 {
+        return Ok(found);
+    }
+
+    let mut visitor = |member: &ArchiveMember, reader: &mut dyn Read| {
+        if readable(member, wanted) {
+            found.push((member.clone(), read_content(member, reader)?));
+        }
+        Ok(found.len() < limit)
+    };
+    visit(format, path, &mut visitor)?;
+    Ok(found)
+}
+
+/// List an archive and read up to `limit` members accepted by `wanted`
+///
+/// Archives without an index are read through once for both. ZIP members
+/// are listed from the central directory and only wanted ones are inflated.
+pub fn list_and_read(
+    path: &Path,
+    wanted: &dyn Fn(&ArchiveMember) -> bool,
+    limit: usize,
+) -> Result<(ArchiveListing, MemberContents), String> {
+    let format = ArchiveFormat::detect(path)
+        .ok_or_else(|| format!("Not a supported archive: {}", path.display()))?;
+    if format.has_index() {
+        return Ok((list(path)?, read_members(path, wanted, limit)?));
+    }
+
+    let mut members = Vec::new();
+    let mut truncated = false;
+    let mut found = Vec::new();
+    let mut visitor = |member: &ArchiveMember, reader: &mut dyn Read| {
+        if members.len() >= MAX_LISTED_MEMBERS {
+            truncated = true;
+            return Ok(false);
+        }
+        members.push(member.clone());
+        if found.len() < limit && readable(member, wanted) {
+            found.push((member.clone(), read_content(member, reader)?));
+        }
+        Ok(true)
+    };
+    visit(format, path, &mut visitor)?;
+
+    let listing = ArchiveListing {
+        format,
+        members,
+        truncated,
+    };
+    Ok((listing, found))
+}
+
+fn visit(format: ArchiveFormat, path: &Path, visitor: &mut Visitor) -> Result<(), String> {
+    match format {
+        ArchiveFormat::Zip => zip::visit(path, visitor),
+        ArchiveFormat::Tar => tar::visit(path, false, visitor),
+        ArchiveFormat::TarGz => tar::visit(path, true, visitor),
+        ArchiveFormat::SevenZ => sevenz::visit(path, visitor),
+    }
+}
+
+/// File member small enough to read and accepted by `wanted`
+fn readable(member: &ArchiveMember, wanted: &dyn Fn(&ArchiveMember) -> bool) -> bool {
+    !member.is_dir && member.size <= MAX_MEMBER_BYTES && wanted(member)
+}
+
+fn read_content(member: &ArchiveMember, reader: &mut dyn Read) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::with_capacity(member.size as usize);
+    reader
+        .take(MAX_MEMBER_BYTES)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read {}: {}", member.name, e))?;
+    Ok(bytes)
+}
+
+/// Read one member by its name inside the archive
+pub fn read_member(path: &Path, name: &str) -> Result<Vec<u8>, String> {
+    read_members(path, &|m| m.name == name, 1)?
+        .pop()
+        .map(|(_, bytes)| bytes)
+        .ok_or_else(|| format!("{} not found in {}", name, path.display()))
+}
+
+/// Virtual path of a member below its archive
+pub fn member_path(archive: &Path, name: &str) -> PathBuf {
+    let mut path = archive.to_path_buf();
+    for segment in name.split('/') {
+        path.push(segment);
+    }
+    path
+}
+
+/// Split a virtual member path into the archive file and the member name
+///
+/// Returns None if no ancestor of `path` is an existing archive file.
+pub fn split_member_path(path: &Path) -> Option<(PathBuf, String)> {
+    let archive = path
+        .ancestors()
+        .skip(1)
+        .find(|p| is_archive(p) && p.is_file())?;
+    let name = path
+        .strip_prefix(archive)
+        .ok()?
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    (!name.is_empty()).then(|| (archive.to_path_buf(), name))
+}
+
+/// Normalize a stored member name: `/`-separated, no leading slash, no
+/// `.` or `..` segments. Returns None for names that normalize to nothing.
+fn normalize_name(raw: &str) -> Option<String> {
+    let segments: Vec<&str> = raw
+        .split(['/', '\\'])
+        .filter(|s| !s.is_empty() && *s != "." && *s != "..")
+        .collect();
+    (!segments.is_empty()).then(|| segments.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    fn write_zip(path: &Path, files: &[(&str, &str)]) {
+        let mut zip = ::zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+        for (name, content) in files {
+            zip.start_file(*name, ::zip::write::SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    fn write_tar_gz(path: &Path, files: &[(&str, &str)]) {
+        let gz = flate2::write::GzEncoder::new(
+            std::fs::File::create(path).unwrap(),
+            flate2::Compression::default(),
+        );
+        let mut builder = ::tar::Builder::new(gz);
+        for (name, content) in files {
+            let mut header = ::tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mtime(1_700_000_000);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, name, content.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    #[test]
+    fn test_zip_list_and_read() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("invoices.zip");
+        write_zip(
+            &path,
+            &[
+                ("2024/march.txt", "Invoice March"),
+                ("./notes/../readme.md", "hi"),
+            ],
+        );
+
+        let listing = list(&path).unwrap();
+        assert_eq!(listing.format, ArchiveFormat::Zip);
+        let names: Vec<&str> = listing.files().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["2024/march.txt", "notes/readme.md"]);
+        assert_eq!(listing.total_size(), 15);
+        assert!(!listing.truncated);
+
+        let bytes = read_member(&path, "2024/march.txt").unwrap();
+        assert_eq!(bytes, b"Invoice March");
+        assert!(read_member(&path, "missing.txt").is_err());
+    }
+
+    #[test]
+    fn test_tar_gz_list_and_read() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("backup.tar.gz");
+        write_tar_gz(&path, &[("a.txt", "first"), ("docs/b.csv", "x,y")]);
+
+        let listing = list(&path).unwrap();
+        assert_eq!(listing.format, ArchiveFormat::TarGz);
+        assert_eq!(listing.members.len(), 2);
+        assert_eq!(listing.members[1].name, "docs/b.csv");
+        assert_eq!(listing.members[0].modified_at, Some(1_700_000_000_000));
+
+        let csv = read_members(&path, &|m| m.extension().as_deref() == Some("csv"), 10).unwrap();
+        assert_eq!(csv.len(), 1);
+        assert_eq!(csv[0].1, b"x,y");
+
+        // One pass lists every member and reads the wanted ones
+        let (listing, read) = list_and_read(&path, &|m| m.name == "a.txt", 10).unwrap();
+        assert_eq!(listing.members.len(), 2);
+        assert_eq!(read.len(), 1);
+        assert_eq!(read[0].1, b"first");
+    }
+
+    #[test]
+    fn test_sevenz_list_and_read() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("src");
+        std::fs::create_dir_all(source.join("sub")).unwrap();
+        std::fs::write(source.join("one.txt"), "alpha").unwrap();
+        std::fs::write(source.join("sub/two.txt"), "beta").unwrap();
+        let path = temp.path().join("bundle.7z");
+        sevenz_rust::compress_to_path(&source, &path).unwrap();
+
+        let listing = list(&path).unwrap();
+        let mut names: Vec<&str> = listing.files().map(|m| m.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["one.txt", "sub/two.txt"]);
+        assert_eq!(read_member(&path, "sub/two.txt").unwrap(), b"beta");
+    }
+
+    #[test]
+    fn test_member_paths() {
+        let temp = TempDir::new().unwrap();
+        let archive = temp.path().join("a.zip");
+        write_zip(&archive, &[("x/y.txt", "y")]);
+
+        let virtual_path = member_path(&archive, "x/y.txt");
+        assert_eq!(virtual_path, archive.join("x").join("y.txt"));
+        assert_eq!(
+            split_member_path(&virtual_path),
+            Some((archive.clone(), "x/y.txt".to_string()))
+        );
+        assert_eq!(split_member_path(&temp.path().join("plain/file.txt")), None);
+
+        assert_eq!(
+            normalize_name("/../etc//passwd"),
+            Some("etc/passwd".to_string())
+        );
+        assert_eq!(normalize_name("./"), None);
+        assert_eq!(
+            ArchiveFormat::detect(Path::new("x.TGZ")),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert_eq!(ArchiveFormat::detect(Path::new("x.gz")), None);
+    }
+}
@@ -0,0 +1,59 @@
+//! 7z archives
+//!
+//! Listing reads the archive header only. Reading decodes folders in
+//! order, since solid blocks cannot be decoded from the middle.
+
+use super::{normalize_name, ArchiveMember, Visitor};
+use sevenz_rust::{Archive, Password, SevenZArchiveEntry, SevenZReader};
+use std::path::Path;
+
+pub fn list(path: &Path, limit: usize) -> Result<(Vec<ArchiveMember>, bool), String> {
+    let archive = Archive::open(path).map_err(|e| format!("Failed to read 7z archive: {}", e))?;
+    let mut members = Vec::new();
+    for entry in &archive.files {
+        if members.len() >= limit {
+            return Ok((members, true));
+        }
+        members.extend(member(entry));
+    }
+    Ok((members, false))
+}
+
+pub fn visit(path: &Path, visitor: &mut Visitor) -> Result<(), String> {
+    let mut reader = SevenZReader::open(path, Password::empty())
+        .map_err(|e| format!("Failed to read 7z archive: {}", e))?;
+    let mut failure = None;
+
+    reader
+        .for_each_entries(|entry, content| {
+            let keep_going = match member(entry) {
+                Some(member) => visitor(&member, content).unwrap_or_else(|e| {
+                    failure = Some(e);
+                    false
+                }),
+                None => true,
+            };
+            if keep_going {
+                // The next member starts where this one ends in the block
+                std::io::copy(content, &mut std::io::sink())?;
+            }
+            Ok(keep_going)
+        })
+        .map_err(|e| format!("Failed to read 7z archive: {}", e))?;
+
+    failure.map_or(Ok(()), Err)
+}
+
+fn member(entry: &SevenZArchiveEntry) -> Option<ArchiveMember> {
+    if entry.is_anti_item() {
+        return None;
+    }
+    Some(ArchiveMember {
+        name: normalize_name(entry.name())?,
+        size: entry.size(),
+        modified_at: entry
+            .has_last_modified_date
+            .then(|| entry.last_modified_date().to_unix_time() * 1000),
+        is_dir: entry.is_directory(),
+    })
+}
@@ -0,0 +1,67 @@
+//! TAR archives, plain or gzip-compressed
+//!
+//! TAR has no index, so listing reads through the whole stream.
+
+use super::{normalize_name, ArchiveMember, Visitor};
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+fn open(path: &Path, gzip: bool) -> Result<::tar::Archive<Box<dyn Read>>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open archive: {}", e))?;
+    let reader: Box<dyn Read> = if gzip {
+        Box::new(GzDecoder::new(BufReader::new(file)))
+    } else {
+        Box::new(BufReader::new(file))
+    };
+    Ok(::tar::Archive::new(reader))
+}
+
+pub fn list(path: &Path, gzip: bool, limit: usize) -> Result<(Vec<ArchiveMember>, bool), String> {
+    let mut members = Vec::new();
+    let mut truncated = false;
+    let mut collect = |member: &ArchiveMember, _: &mut dyn Read| {
+        if members.len() >= limit {
+            truncated = true;
+            return Ok(false);
+        }
+        members.push(member.clone());
+        Ok(true)
+    };
+    visit(path, gzip, &mut collect)?;
+    Ok((members, truncated))
+}
+
+pub fn visit(path: &Path, gzip: bool, visitor: &mut Visitor) -> Result<(), String> {
+    let mut archive = open(path, gzip)?;
+    let entries = archive
+        .entries()
+        .map_err(|e| format!("Failed to read TAR archive: {}", e))?;
+
+    for entry in entries {
+        let mut entry = entry.map_err(|e| format!("Failed to read TAR entry: {}", e))?;
+        let kind = entry.header().entry_type();
+        // Links, devices and FIFOs have no content worth indexing
+        if !kind.is_file() && !kind.is_dir() {
+            continue;
+        }
+        let Some(name) = entry
+            .path()
+            .ok()
+            .and_then(|p| normalize_name(&p.to_string_lossy()))
+        else {
+            continue;
+        };
+        let member = ArchiveMember {
+            name,
+            size: if kind.is_dir() { 0 } else { entry.size() },
+            modified_at: entry.header().mtime().ok().map(|s| s as i64 * 1000),
+            is_dir: kind.is_dir(),
+        };
+        if !visitor(&member, &mut entry)? {
+            break;
+        }
+    }
+    Ok(())
+}
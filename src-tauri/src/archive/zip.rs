@@ -0,0 +1,75 @@
+//! ZIP archives
+
+use super::{normalize_name, ArchiveMember, Visitor};
+use ::zip::read::ZipFile;
+use ::zip::ZipArchive;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+fn open(path: &Path) -> Result<ZipArchive<BufReader<File>>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open archive: {}", e))?;
+    ZipArchive::new(BufReader::new(file)).map_err(|e| format!("Failed to read ZIP archive: {}", e))
+}
+
+/// List members from the central directory without decompressing
+pub fn list(path: &Path, limit: usize) -> Result<(Vec<ArchiveMember>, bool), String> {
+    let mut archive = open(path)?;
+    let mut members = Vec::new();
+    for index in 0..archive.len() {
+        if members.len() >= limit {
+            return Ok((members, true));
+        }
+        let entry = archive
+            .by_index_raw(index)
+            .map_err(|e| format!("Failed to read ZIP entry: {}", e))?;
+        members.extend(member(&entry));
+    }
+    Ok((members, false))
+}
+
+pub fn visit(path: &Path, visitor: &mut Visitor) -> Result<(), String> {
+    let mut archive = open(path)?;
+    for index in 0..archive.len() {
+        let mut entry = match archive.by_index(index) {
+            Ok(entry) => entry,
+            // Encrypted members and unsupported compression methods
+            Err(e) => {
+                tracing::debug!(archive = %path.display(), index, error = %e, "Skipping ZIP entry");
+                continue;
+            }
+        };
+        let Some(member) = member(&entry) else {
+            continue;
+        };
+        if !visitor(&member, &mut entry)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn member<R: Read>(entry: &ZipFile<'_, R>) -> Option<ArchiveMember> {
+    let is_dir = entry.is_dir();
+    Some(ArchiveMember {
+        name: normalize_name(entry.name())?,
+        size: if is_dir { 0 } else { entry.size() },
+        modified_at: entry.last_modified().and_then(dos_time_ms),
+        is_dir,
+    })
+}
+
+/// ZIP stores MS-DOS local time without a zone; it is read as UTC
+fn dos_time_ms(time: ::zip::DateTime) -> Option<i64> {
+    let date = chrono::NaiveDate::from_ymd_opt(
+        time.year() as i32,
+        time.month() as u32,
+        time.day() as u32,
+    )?;
+    let date_time = date.and_hms_opt(
+        time.hour() as u32,
+        time.minute() as u32,
+        time.second() as u32,
+    )?;
+    Some(date_time.and_utc().timestamp_millis())
+}
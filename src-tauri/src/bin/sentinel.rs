@@ -0,0 +1,9 @@
+//! `sentinel` - headless command-line entry point
+//!
+//! Runs organize plans, undo preflight and WAL recovery without the app.
+//! See `tauri_app_lib::cli` for the commands.
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    std::process::exit(tauri_app_lib::cli::run(args));
+}
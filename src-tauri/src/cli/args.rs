@@ -0,0 +1,362 @@
+//! Command-line argument parsing for the `sentinel` binary.
+//!
+//! Deliberately small and dependency-free: a handful of subcommands with
+//! positional arguments and a few `--flag value` options.
+
+use crate::execution::ConflictPolicy;
+use crate::history::ConflictResolution;
+use std::path::PathBuf;
+
+/// Usage text printed by `sentinel help` and on argument errors
+pub const USAGE: &str = "\
+Usage: sentinel [--json] <command> [options]
+
+Commands:
+  scan <folder> [--depth N]            Scan a folder and print statistics
+  plan <folder> <instruction>          Generate an organize plan with AI (needs XAI_API_KEY)
+  simulate <plan.json>                 Validate a plan against a fresh scan of its target folder
+  execute <plan.json> [options]        Simulate, then execute a plan and record it in history
+      --conflict <fail|skip|auto_rename>   Destination conflict policy (default: auto_rename)
+      --instruction <text>                 Instruction recorded in history (default: \"Organize folder\")
+      --no-simulate                        Skip the simulation step
+  history <folder>                     List organization sessions recorded for a folder
+  undo-preflight <folder> <session-id> Check whether undoing back to a session is safe
+  undo <folder> <session-id> [options] Undo a folder back to a session
+      --resolution <abort|skip|force|backup>  How to handle conflicts (default: abort)
+  recover status                       Show journals left behind by interrupted runs
+  recover resume <job-id>              Finish the pending operations of a journal
+  recover rollback <job-id>            Undo the completed operations of a journal
+  recover discard <job-id>             Delete a journal without touching files
+  help                                 Show this message
+
+Global options:
+  --json                               Print machine-readable JSON instead of text
+
+Plan files are organize plans in the app's JSON format; use `-` to read from stdin.
+`sentinel --json plan ...` prints a plan in that format.
+";
+
+/// Parsed command line
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliArgs {
+    /// Emit JSON instead of human-readable text
+    pub json: bool,
+    /// Subcommand to run
+    pub command: CliCommand,
+}
+
+/// A `sentinel` subcommand
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliCommand {
+    Scan {
+        folder: PathBuf,
+        max_depth: usize,
+    },
+    Plan {
+        folder: PathBuf,
+        instruction: String,
+    },
+    Simulate {
+        plan: PathBuf,
+    },
+    Execute {
+        plan: PathBuf,
+        conflict_policy: ConflictPolicy,
+        instruction: String,
+        simulate: bool,
+    },
+    History {
+        folder: String,
+    },
+    UndoPreflight {
+        folder: String,
+        session_id: String,
+    },
+    Undo {
+        folder: String,
+        session_id: String,
+        resolution: ConflictResolution,
+    },
+    Recover(RecoverAction),
+    Help,
+}
+
+/// Action for `sentinel recover`
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecoverAction {
+    Status,
+    Resume { job_id: String },
+    Rollback { job_id: String },
+    Discard { job_id: String },
+}
+
+/// Cursor over the remaining arguments of a subcommand
+struct ArgCursor<'a> {
+    command: &'a str,
+    args: std::slice::Iter<'a, String>,
+}
+
+impl<'a> ArgCursor<'a> {
+    fn next_arg(&mut self) -> Option<&'a str> {
+        self.args.next().map(String::as_str)
+    }
+
+    /// Next positional argument, erroring with `name` if it is missing
+    fn required(&mut self, name: &str) -> Result<&'a str, String> {
+        match self.next_arg() {
+            Some(arg) if !arg.starts_with("--") => Ok(arg),
+            Some(arg) => Err(format!("{}: unexpected option '{}'", self.command, arg)),
+            None => Err(format!("{}: missing <{}>", self.command, name)),
+        }
+    }
+
+    /// Value following an option
+    fn value(&mut self, option: &str) -> Result<&'a str, String> {
+        self.next_arg()
+            .ok_or_else(|| format!("{}: {} requires a value", self.command, option))
+    }
+
+    fn unexpected(&self, arg: &str) -> String {
+        format!("{}: unexpected argument '{}'", self.command, arg)
+    }
+}
+
+/// Parse arguments (excluding the program name)
+pub fn parse_args(args: &[String]) -> Result<CliArgs, String> {
+    // --json may appear anywhere; everything else is positional or per-command
+    let json = args.iter().any(|a| a == "--json");
+    let rest: Vec<String> = args.iter().filter(|a| *a != "--json").cloned().collect();
+
+    let Some((command, tail)) = rest.split_first() else {
+        return Ok(CliArgs {
+            json,
+            command: CliCommand::Help,
+        });
+    };
+
+    let mut cursor = ArgCursor {
+        command: command.as_str(),
+        args: tail.iter(),
+    };
+
+    let command = match command.as_str() {
+        "scan" => {
+            let folder = PathBuf::from(cursor.required("folder")?);
+            let mut max_depth = 0;
+            while let Some(arg) = cursor.next_arg() {
+                match arg {
+                    "--depth" => {
+                        let value = cursor.value(arg)?;
+                        max_depth = value
+                            .parse()
+                            .map_err(|_| format!("scan: invalid depth '{}'", value))?;
+                    }
+                    other => return Err(cursor.unexpected(other)),
+                }
+            }
+            CliCommand::Scan { folder, max_depth }
+        }
+        "plan" => {
+            let folder = PathBuf::from(cursor.required("folder")?);
+            let instruction = cursor.required("instruction")?.to_string();
+            if let Some(arg) = cursor.next_arg() {
+                return Err(cursor.unexpected(arg));
+            }
+            CliCommand::Plan {
+                folder,
+                instruction,
+            }
+        }
+        "simulate" => {
+            let plan = PathBuf::from(cursor.required("plan.json")?);
+            if let Some(arg) = cursor.next_arg() {
+                return Err(cursor.unexpected(arg));
+            }
+            CliCommand::Simulate { plan }
+        }
+        "execute" => {
+            let plan = PathBuf::from(cursor.required("plan.json")?);
+            let mut conflict_policy = ConflictPolicy::AutoRename;
+            let mut instruction = "Organize folder".to_string();
+            let mut simulate = true;
+            while let Some(arg) = cursor.next_arg() {
+                match arg {
+                    "--conflict" => {
+                        let value = cursor.value(arg)?;
+                        conflict_policy = ConflictPolicy::from_name(value).ok_or_else(|| {
+                            format!("execute: unknown conflict policy '{}'", value)
+                        })?;
+                    }
+                    "--instruction" => instruction = cursor.value(arg)?.to_string(),
+                    "--no-simulate" => simulate = false,
+                    other => return Err(cursor.unexpected(other)),
+                }
+            }
+            CliCommand::Execute {
+                plan,
+                conflict_policy,
+                instruction,
+                simulate,
+            }
+        }
+        "history" => {
+            let folder = cursor.required("folder")?.to_string();
+            if let Some(arg) = cursor.next_arg() {
+                return Err(cursor.unexpected(arg));
+            }
+            CliCommand::History { folder }
+        }
+        "undo-preflight" => {
+            let folder = cursor.required("folder")?.to_string();
+            let session_id = cursor.required("session-id")?.to_string();
+            if let Some(arg) = cursor.next_arg() {
+                return Err(cursor.unexpected(arg));
+            }
+            CliCommand::UndoPreflight { folder, session_id }
+        }
+        "undo" => {
+            let folder = cursor.required("folder")?.to_string();
+            let session_id = cursor.required("session-id")?.to_string();
+            let mut resolution = ConflictResolution::Abort;
+            while let Some(arg) = cursor.next_arg() {
+                match arg {
+                    "--resolution" => {
+                        let value = cursor.value(arg)?;
+                        resolution = ConflictResolution::from_str(value)
+                            .ok_or_else(|| format!("undo: unknown resolution '{}'", value))?;
+                    }
+                    other => return Err(cursor.unexpected(other)),
+                }
+            }
+            CliCommand::Undo {
+                folder,
+                session_id,
+                resolution,
+            }
+        }
+        "recover" => {
+            let action = match cursor.next_arg().unwrap_or("status") {
+                "status" => RecoverAction::Status,
+                "resume" => RecoverAction::Resume {
+                    job_id: cursor.required("job-id")?.to_string(),
+                },
+                "rollback" => RecoverAction::Rollback {
+                    job_id: cursor.required("job-id")?.to_string(),
+                },
+                "discard" => RecoverAction::Discard {
+                    job_id: cursor.required("job-id")?.to_string(),
+                },
+                other => return Err(format!("recover: unknown action '{}'", other)),
+            };
+            if let Some(arg) = cursor.next_arg() {
+                return Err(cursor.unexpected(arg));
+            }
+            CliCommand::Recover(action)
+        }
+        "help" | "--help" | "-h" => CliCommand::Help,
+        other => return Err(format!("Unknown command '{}'", other)),
+    };
+
+    Ok(CliArgs { json, command })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CliArgs, String> {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        parse_args(&args)
+    }
+
+    #[test]
+    fn test_no_arguments_shows_help() {
+        assert_eq!(parse(&[]).unwrap().command, CliCommand::Help);
+    }
+
+    #[test]
+    fn test_json_flag_anywhere() {
+        let args = parse(&["scan", "/tmp", "--json", "--depth", "2"]).unwrap();
+        assert!(args.json);
+        assert_eq!(
+            args.command,
+            CliCommand::Scan {
+                folder: PathBuf::from("/tmp"),
+                max_depth: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_execute_options() {
+        let args = parse(&[
+            "execute",
+            "plan.json",
+            "--conflict",
+            "skip",
+            "--instruction",
+            "Sort invoices",
+            "--no-simulate",
+        ])
+        .unwrap();
+        assert_eq!(
+            args.command,
+            CliCommand::Execute {
+                plan: PathBuf::from("plan.json"),
+                conflict_policy: ConflictPolicy::Skip,
+                instruction: "Sort invoices".to_string(),
+                simulate: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_execute_rejects_unknown_policy() {
+        let err = parse(&["execute", "plan.json", "--conflict", "overwrite"]).unwrap_err();
+        assert!(err.contains("overwrite"));
+    }
+
+    #[test]
+    fn test_plan_and_undo() {
+        assert_eq!(
+            parse(&["plan", "/tmp/inbox", "Sort by year"]).unwrap().command,
+            CliCommand::Plan {
+                folder: PathBuf::from("/tmp/inbox"),
+                instruction: "Sort by year".to_string(),
+            }
+        );
+        assert_eq!(
+            parse(&["undo", "/tmp/inbox", "s1", "--resolution", "skip"])
+                .unwrap()
+                .command,
+            CliCommand::Undo {
+                folder: "/tmp/inbox".to_string(),
+                session_id: "s1".to_string(),
+                resolution: ConflictResolution::Skip,
+            }
+        );
+        assert!(parse(&["plan", "/tmp/inbox"]).is_err());
+        assert!(parse(&["undo", "/tmp/inbox", "s1", "--resolution", "merge"]).is_err());
+    }
+
+    #[test]
+    fn test_recover_actions() {
+        assert_eq!(
+            parse(&["recover"]).unwrap().command,
+            CliCommand::Recover(RecoverAction::Status)
+        );
+        assert_eq!(
+            parse(&["recover", "rollback", "job-1"]).unwrap().command,
+            CliCommand::Recover(RecoverAction::Rollback {
+                job_id: "job-1".to_string()
+            })
+        );
+        assert!(parse(&["recover", "resume"]).is_err());
+    }
+
+    #[test]
+    fn test_missing_positional() {
+        let err = parse(&["undo-preflight", "/tmp"]).unwrap_err();
+        assert!(err.contains("session-id"));
+    }
+}
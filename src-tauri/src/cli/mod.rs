@@ -0,0 +1,690 @@
+//! Headless command-line interface
+//!
+//! Drives the same scan → plan → simulate → execute → history pipeline as the
+//! app, plus undo and WAL recovery, without creating a window. Used by
+//! the `sentinel` binary for scripting, CI fixtures and servers.
+//!
+//! Every command prints either human-readable text or, with `--json`, a
+//! single JSON document on stdout. Diagnostics and progress go to stderr.
+//!
+//! ## Exit Codes
+//! - `0` - success
+//! - `1` - the command ran but reported a problem (invalid plan, failed
+//!   operations, unsafe undo, ...)
+//! - `2` - usage error or the command could not run at all
+
+pub mod args;
+
+pub use args::*;
+
+use crate::ai::grok::{ContentCache, GrokOrganizer};
+use crate::commands::grok::{convert_to_frontend_plan, get_grok_api_key};
+use crate::commands::history::undo_to_session;
+use crate::execution::{
+    journal_from_plan, simulated_operations_from_plan, ExecutionConfig, ExecutionEngine,
+    ExecutionResult, ProgressCallback,
+};
+use crate::history::{
+    preflight_undo, refresh_folder_snapshot, save_plan_history, ConflictResolution,
+    HistoryStore, SessionSummary,
+};
+use crate::jobs::OrganizePlan;
+use crate::vfs::{dry_run_plan, DryRunReport, JWalkScanner, ScanStats, ShadowVFS, VFSStats};
+use crate::wal::{self, RecoveryInfo, WALManager};
+use serde::Serialize;
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing_subscriber::EnvFilter;
+
+/// Exit code for a successful run
+pub const EXIT_OK: i32 = 0;
+
+/// Exit code when the command ran but reported a problem
+pub const EXIT_FAILURE: i32 = 1;
+
+/// Exit code for usage errors and commands that could not run
+pub const EXIT_USAGE: i32 = 2;
+
+/// Result of `sentinel scan`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanReport {
+    pub folder: String,
+    pub scan: ScanStats,
+    pub vfs: VFSStats,
+}
+
+/// Result of `sentinel simulate` (and the simulation step of `execute`)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationReport {
+    pub plan_id: String,
+    pub plan_hash: String,
+    pub target_folder: String,
+    pub operations: usize,
+    pub valid: bool,
+    pub errors: Vec<String>,
+    /// Fraction of files the plan accounts for
+    pub coverage: f64,
+    /// Per-operation classification, bytes to copy and time estimate
+    pub impact: DryRunReport,
+}
+
+/// Result of `sentinel execute`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecuteReport {
+    pub plan_id: String,
+    pub simulation: Option<SimulationReport>,
+    pub execution: Option<ExecutionResult>,
+    /// Whether the run was saved to history (and can be undone)
+    pub history_recorded: bool,
+    /// Journal left behind for `sentinel recover` when execution did not finish cleanly
+    pub journal_kept: bool,
+}
+
+/// Result of `sentinel recover status`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryStatus {
+    /// The interrupted journal recovery would act on first
+    pub interrupted: Option<RecoveryInfo>,
+    /// All journals present in the WAL directory
+    pub journals: Vec<String>,
+}
+
+/// Outcome of a command: what to print and how to exit
+struct Outcome {
+    json: serde_json::Value,
+    text: String,
+    exit_code: i32,
+}
+
+impl Outcome {
+    fn new<T: Serialize>(value: &T, text: String, ok: bool) -> Result<Self, String> {
+        Ok(Self {
+            json: serde_json::to_value(value)
+                .map_err(|e| format!("Failed to serialize output: {}", e))?,
+            text,
+            exit_code: if ok { EXIT_OK } else { EXIT_FAILURE },
+        })
+    }
+}
+
+/// Run the CLI with the given arguments (excluding the program name) and
+/// return the process exit code.
+pub fn run(args: Vec<String>) -> i32 {
+    // Load .env the same way the app does so API keys and overrides match
+    if dotenvy::dotenv().is_err() {
+        let _ = dotenvy::from_path("../.env");
+    }
+
+    // Logs go to stderr so stdout stays parseable
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+        )
+        .with_writer(std::io::stderr)
+        .try_init();
+
+    let cli = match parse_args(&args) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return EXIT_USAGE;
+        }
+    };
+
+    if cli.command == CliCommand::Help {
+        print!("{}", USAGE);
+        return EXIT_OK;
+    }
+
+    let runtime = match tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("error: Failed to start async runtime: {}", e);
+            return EXIT_USAGE;
+        }
+    };
+
+    let json = cli.json;
+    match runtime.block_on(run_command(cli.command, json)) {
+        Ok(outcome) => {
+            if json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&outcome.json).unwrap_or_default()
+                );
+            } else {
+                print!("{}", outcome.text);
+            }
+            outcome.exit_code
+        }
+        Err(e) => {
+            if json {
+                println!("{}", serde_json::json!({ "error": e }));
+            } else {
+                eprintln!("error: {}", e);
+            }
+            EXIT_USAGE
+        }
+    }
+}
+
+async fn run_command(command: CliCommand, json: bool) -> Result<Outcome, String> {
+    match command {
+        CliCommand::Scan { folder, max_depth } => scan(&folder, max_depth).await,
+        CliCommand::Plan {
+            folder,
+            instruction,
+        } => plan(&folder, &instruction, !json).await,
+        CliCommand::Simulate { plan } => {
+            let plan = load_plan(&plan)?;
+            let report = simulate(&plan).await?;
+            let text = simulation_text(&report);
+            Outcome::new(&report, text, report.valid)
+        }
+        CliCommand::Execute {
+            plan,
+            conflict_policy,
+            instruction,
+            simulate: run_simulation,
+        } => {
+            let plan = load_plan(&plan)?;
+            let config = ExecutionConfig {
+                on_destination_exists: conflict_policy,
+            };
+            execute(&plan, config, &instruction, run_simulation, !json).await
+        }
+        CliCommand::History { folder } => history(&folder),
+        CliCommand::UndoPreflight { folder, session_id } => {
+            tokio::task::spawn_blocking(move || undo_preflight(&folder, &session_id))
+                .await
+                .map_err(|e| format!("Task failed: {}", e))?
+        }
+        CliCommand::Undo {
+            folder,
+            session_id,
+            resolution,
+        } => tokio::task::spawn_blocking(move || undo(&folder, &session_id, &resolution, !json))
+            .await
+            .map_err(|e| format!("Task failed: {}", e))?,
+        CliCommand::Recover(action) => tokio::task::spawn_blocking(move || recover(action))
+            .await
+            .map_err(|e| format!("Task failed: {}", e))?,
+        CliCommand::Help => Outcome::new(&USAGE, USAGE.to_string(), true),
+    }
+}
+
+/// Read an organize plan from a file, or stdin for `-`
+fn load_plan(path: &Path) -> Result<OrganizePlan, String> {
+    let content = if path == Path::new("-") {
+        let mut buf = String::new();
+        std::io::stdin()
+            .read_to_string(&mut buf)
+            .map_err(|e| format!("Failed to read plan from stdin: {}", e))?;
+        buf
+    } else {
+        std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read plan {}: {}", path.display(), e))?
+    };
+
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse plan: {}", e))
+}
+
+/// Scan a folder into a fresh VFS
+async fn scan_vfs(folder: &Path, scanner: JWalkScanner) -> Result<(ShadowVFS, ScanStats), String> {
+    if !folder.is_dir() {
+        return Err(format!("Path is not a directory: {}", folder.display()));
+    }
+
+    let root = folder.to_path_buf();
+    let mut vfs = ShadowVFS::new(root.clone());
+    let stats = scanner.scan(&root, &mut vfs).await?;
+    Ok((vfs, stats))
+}
+
+async fn scan(folder: &Path, max_depth: usize) -> Result<Outcome, String> {
+    let scanner = JWalkScanner::new().with_max_depth(max_depth);
+    let (vfs, stats) = scan_vfs(folder, scanner).await?;
+
+    let report = ScanReport {
+        folder: folder.to_string_lossy().to_string(),
+        vfs: vfs.stats(),
+        scan: stats,
+    };
+
+    let text = format!(
+        "Scanned {}\n  files:       {}\n  directories: {}\n  size:        {} bytes\n  errors:      {}\n  duration:    {} ms\n",
+        report.folder,
+        report.scan.total_files,
+        report.scan.total_dirs,
+        report.scan.total_size_bytes,
+        report.scan.errors,
+        report.scan.scan_duration_ms,
+    );
+
+    Outcome::new(&report, text, true)
+}
+
+/// Generate an organize plan with the Grok pipeline, like the app's Changes panel
+async fn plan(folder: &Path, instruction: &str, show_progress: bool) -> Result<Outcome, String> {
+    if !folder.is_dir() {
+        return Err(format!("Path is not a directory: {}", folder.display()));
+    }
+
+    let cache_dir = ContentCache::default_dir().ok_or("Could not determine the cache directory")?;
+    let organizer = GrokOrganizer::new(get_grok_api_key()?, &cache_dir)?;
+
+    let show_progress = show_progress && std::io::stderr().is_terminal();
+    let organization = organizer
+        .organize(folder, instruction, move |progress| {
+            if show_progress {
+                eprint!("\r\x1b[K{}", progress.message);
+            }
+        })
+        .await?;
+    if show_progress {
+        eprintln!();
+    }
+
+    let plan = convert_to_frontend_plan(organization, &folder.to_string_lossy());
+
+    let mut text = format!(
+        "Plan {} ({} operations in {})\n  {}\n",
+        plan.plan_id,
+        plan.operations.len(),
+        plan.target_folder,
+        plan.description
+    );
+    for op in &plan.operations {
+        let detail = match (&op.source, &op.destination, &op.path, &op.new_name) {
+            (Some(source), Some(destination), _, _) => format!("{} -> {}", source, destination),
+            (_, _, Some(path), Some(new_name)) => format!("{} -> {}", path, new_name),
+            (_, _, Some(path), None) => path.clone(),
+            _ => String::new(),
+        };
+        text.push_str(&format!("  {:<13} {}\n", op.op_type, detail));
+    }
+
+    // The JSON form is the plan itself, ready for `simulate` and `execute`
+    Outcome::new(&plan, text, true)
+}
+
+/// Validate a plan against a fresh scan of its target folder
+async fn simulate(plan: &OrganizePlan) -> Result<SimulationReport, String> {
+    let target = PathBuf::from(&plan.target_folder);
+    let scanner = JWalkScanner::new().with_extract_previews(false);
+    let (vfs, _) = scan_vfs(&target, scanner).await?;
+
+    let operations = simulated_operations_from_plan(plan);
+    let operation_count = operations.len();
+    let impact = dry_run_plan(&vfs, &target, operations);
+
+    let errors = impact
+        .operations
+        .iter()
+        .filter_map(|op| op.error.clone())
+        .chain(impact.errors.iter().cloned())
+        .chain(
+            impact
+                .devices
+                .iter()
+                .filter(|d| !d.sufficient)
+                .map(|d| {
+                    format!(
+                        "Not enough space on {}: {} bytes needed, {} available",
+                        d.path,
+                        d.bytes_required,
+                        d.bytes_available.unwrap_or(0)
+                    )
+                }),
+        )
+        .collect();
+
+    Ok(SimulationReport {
+        plan_id: plan.plan_id.clone(),
+        plan_hash: plan.compute_hash(),
+        target_folder: plan.target_folder.clone(),
+        operations: operation_count,
+        valid: impact.valid,
+        errors,
+        coverage: impact.coverage,
+        impact,
+    })
+}
+
+fn simulation_text(report: &SimulationReport) -> String {
+    let mut text = format!(
+        "Plan {} ({} operations in {})\n",
+        report.plan_id, report.operations, report.target_folder
+    );
+    text.push_str(&format!(
+        "  {} rename(s), {} cross-device move(s) copying {} bytes, about {} ms\n",
+        report.impact.renames,
+        report.impact.cross_device_moves,
+        report.impact.bytes_to_copy,
+        report.impact.estimated_duration_ms
+    ));
+    if report.valid {
+        text.push_str("Simulation passed\n");
+    } else {
+        text.push_str(&format!(
+            "Simulation found {} problem(s):\n",
+            report.errors.len()
+        ));
+        for error in &report.errors {
+            text.push_str(&format!("  - {}\n", error));
+        }
+    }
+    text
+}
+
+async fn execute(
+    plan: &OrganizePlan,
+    config: ExecutionConfig,
+    instruction: &str,
+    run_simulation: bool,
+    show_progress: bool,
+) -> Result<Outcome, String> {
+    let mut report = ExecuteReport {
+        plan_id: plan.plan_id.clone(),
+        simulation: None,
+        execution: None,
+        history_recorded: false,
+        journal_kept: false,
+    };
+    let mut text = String::new();
+
+    if run_simulation {
+        let simulation = simulate(plan).await?;
+        text.push_str(&simulation_text(&simulation));
+        let valid = simulation.valid;
+        report.simulation = Some(simulation);
+        if !valid {
+            text.push_str("Nothing was executed\n");
+            return Outcome::new(&report, text, false);
+        }
+    }
+
+    let journal = journal_from_plan(plan)?;
+    let wal_manager = WALManager::new();
+    wal_manager
+        .save_journal(&journal)
+        .map_err(|e| format!("Failed to save WAL journal: {}", e.message))?;
+
+    let progress_callback: Option<Arc<ProgressCallback>> =
+        if show_progress && std::io::stderr().is_terminal() {
+            Some(Arc::new(Box::new(|completed, total| {
+                eprint!("\rExecuting {}/{}", completed, total);
+                if completed >= total {
+                    eprintln!();
+                }
+            })))
+        } else {
+            None
+        };
+
+    let result = ExecutionEngine::new()
+        .execute_journal_with_config(&plan.plan_id, progress_callback, config)
+        .await?;
+
+    if result.success {
+        let _ = wal_manager.discard_journal(&plan.plan_id);
+        match save_plan_history(plan, &journal, instruction, result.completed_count) {
+            Ok(()) => {
+                report.history_recorded = true;
+                // Wait for the baseline so it is in place before the CLI exits
+                let folder = plan.target_folder.clone();
+                if let Err(e) =
+                    tokio::task::spawn_blocking(move || refresh_folder_snapshot(&folder)).await
+                {
+                    tracing::warn!(error = %e, "Folder snapshot task failed");
+                }
+            }
+            Err(e) => {
+                tracing::warn!(
+                    error = %e,
+                    folder = %plan.target_folder,
+                    "Failed to save organization history (undo will not be available)"
+                );
+            }
+        }
+    } else {
+        report.journal_kept = true;
+    }
+
+    text.push_str(&format!(
+        "Executed: {} completed, {} failed, {} skipped, {} renamed\n",
+        result.completed_count, result.failed_count, result.skipped_count, result.renamed_count
+    ));
+    for error in &result.errors {
+        text.push_str(&format!("  error: {}\n", error));
+    }
+    for reason in &result.skipped {
+        text.push_str(&format!("  skipped: {}\n", reason));
+    }
+    if report.history_recorded {
+        text.push_str(&format!(
+            "Recorded in history as session {}\n",
+            plan.plan_id
+        ));
+    }
+    if report.journal_kept {
+        text.push_str(&format!(
+            "Journal kept for recovery; see `sentinel recover status` (job {})\n",
+            plan.plan_id
+        ));
+    }
+
+    let ok = result.success;
+    report.execution = Some(result);
+    Outcome::new(&report, text, ok)
+}
+
+fn history(folder: &str) -> Result<Outcome, String> {
+    let sessions: Vec<SessionSummary> = HistoryStore::new().get_session_summaries(folder)?;
+
+    let mut text = if sessions.is_empty() {
+        format!("No history for {}\n", folder)
+    } else {
+        format!("{} session(s) for {}\n", sessions.len(), folder)
+    };
+    for session in &sessions {
+        text.push_str(&format!(
+            "  {}  {}  {} files{}  {}\n",
+            session.session_id,
+            session.executed_at.format("%Y-%m-%d %H:%M"),
+            session.files_affected,
+            if session.undone { " (undone)" } else { "" },
+            session.user_instruction,
+        ));
+    }
+
+    Outcome::new(&sessions, text, true)
+}
+
+fn undo_preflight(folder: &str, session_id: &str) -> Result<Outcome, String> {
+    let result = preflight_undo(folder, session_id)?;
+
+    let mut text = format!(
+        "Undo to session {}: {} of {} operation(s) safe, {} conflicted\n",
+        session_id, result.safe_operations, result.total_operations, result.conflicted_operations
+    );
+    for conflict in &result.modified_files {
+        text.push_str(&format!("  modified: {}\n", conflict.path));
+    }
+    for path in &result.missing_files {
+        text.push_str(&format!("  missing:  {}\n", path));
+    }
+    for path in &result.blocking_files {
+        text.push_str(&format!("  blocking: {}\n", path));
+    }
+    text.push_str(if result.can_proceed {
+        "Undo can proceed\n"
+    } else {
+        "Undo cannot proceed without resolving conflicts\n"
+    });
+
+    let ok = result.can_proceed;
+    Outcome::new(&result, text, ok)
+}
+
+fn undo(
+    folder: &str,
+    session_id: &str,
+    resolution: &ConflictResolution,
+    show_progress: bool,
+) -> Result<Outcome, String> {
+    let show_progress = show_progress && std::io::stderr().is_terminal();
+    let result = undo_to_session(folder, session_id, resolution, &|completed, total| {
+        if show_progress {
+            eprint!("\rUndoing {}/{}", completed, total);
+            if completed >= total {
+                eprintln!();
+            }
+        }
+    })?;
+
+    if result.operations_undone > 0 {
+        // Wait for the baseline so it is in place before the CLI exits
+        refresh_folder_snapshot(folder);
+    }
+
+    let mut text = format!(
+        "Undo to session {}: {} undone, {} skipped\n",
+        session_id, result.operations_undone, result.operations_skipped
+    );
+    for error in &result.errors {
+        text.push_str(&format!("  error: {}\n", error));
+    }
+    if !result.success {
+        text.push_str("Journal kept for recovery; see `sentinel recover status`\n");
+    }
+
+    let ok = result.success;
+    Outcome::new(&result, text, ok)
+}
+
+fn recover(action: RecoverAction) -> Result<Outcome, String> {
+    match action {
+        RecoverAction::Status => {
+            let interrupted = wal::check_for_recovery()?;
+            let journals = WALManager::new().list_journals().map_err(|e| e.message)?;
+
+            let mut text = match &interrupted {
+                Some(info) => format!(
+                    "Interrupted job {} in {}\n  started:   {}\n  completed: {}\n  pending:   {}\n  failed:    {}\n",
+                    info.job_id,
+                    info.target_folder,
+                    info.started_at.format("%Y-%m-%d %H:%M:%S"),
+                    info.completed_count,
+                    info.pending_count,
+                    info.failed_count,
+                ),
+                None => "No interrupted jobs\n".to_string(),
+            };
+            if !journals.is_empty() {
+                text.push_str(&format!("Journals: {}\n", journals.join(", ")));
+            }
+
+            let status = RecoveryStatus {
+                interrupted,
+                journals,
+            };
+            Outcome::new(&status, text, true)
+        }
+        RecoverAction::Resume { job_id } => {
+            let result = wal::resume_journal(&job_id)?;
+            let text = recovery_text("Resumed", &job_id, &result);
+            let ok = result.success;
+            Outcome::new(&result, text, ok)
+        }
+        RecoverAction::Rollback { job_id } => {
+            let result = wal::rollback_journal(&job_id)?;
+            let text = recovery_text("Rolled back", &job_id, &result);
+            let ok = result.success;
+            Outcome::new(&result, text, ok)
+        }
+        RecoverAction::Discard { job_id } => {
+            wal::discard_journal(&job_id)?;
+            let text = format!("Discarded journal {}\n", job_id);
+            Outcome::new(&serde_json::json!({ "discarded": job_id }), text, true)
+        }
+    }
+}
+
+fn recovery_text(verb: &str, job_id: &str, result: &wal::RecoveryResult) -> String {
+    let mut text = format!(
+        "{} job {}: {} completed, {} failed\n",
+        verb, job_id, result.completed_count, result.failed_count
+    );
+    for error in &result.errors {
+        text.push_str(&format!("  error: {}\n", error));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::OrganizeOperation;
+    use tempfile::tempdir;
+
+    fn move_plan(root: &Path, source: &str, destination: &str) -> OrganizePlan {
+        OrganizePlan {
+            plan_id: "cli-plan".to_string(),
+            description: "test".to_string(),
+            operations: vec![OrganizeOperation {
+                op_id: "1".to_string(),
+                op_type: "move".to_string(),
+                source: Some(root.join(source).to_string_lossy().to_string()),
+                destination: Some(root.join(destination).to_string_lossy().to_string()),
+                path: None,
+                new_name: None,
+            }],
+            target_folder: root.to_string_lossy().to_string(),
+            simplification_recommended: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_simulate_reports_missing_source() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), b"a").unwrap();
+
+        let ok = simulate(&move_plan(dir.path(), "a.txt", "b.txt"))
+            .await
+            .unwrap();
+        assert!(ok.valid);
+        assert_eq!(ok.operations, 1);
+
+        let bad = simulate(&move_plan(dir.path(), "missing.txt", "b.txt"))
+            .await
+            .unwrap();
+        assert!(!bad.valid);
+        assert_eq!(bad.errors.len(), 1);
+    }
+
+    #[test]
+    fn test_load_plan_from_file() {
+        let dir = tempdir().unwrap();
+        let plan = move_plan(dir.path(), "a.txt", "b.txt");
+        let path = dir.path().join("plan.json");
+        std::fs::write(&path, serde_json::to_vec(&plan).unwrap()).unwrap();
+
+        let loaded = load_plan(&path).unwrap();
+        assert_eq!(loaded.plan_id, "cli-plan");
+        assert_eq!(loaded.operations.len(), 1);
+    }
+
+    #[test]
+    fn test_unknown_command_is_usage_error() {
+        assert_eq!(run(vec!["frobnicate".to_string()]), EXIT_USAGE);
+    }
+}
@@ -1,4 +1,6 @@
-use crate::ai::{run_v6_hybrid_organization, ExpandableDetail, ProgressEvent, AnthropicClient, CredentialManager};
+use crate::ai::{run_v6_hybrid_organization, ExpandableDetail, ProgressEvent, AnthropicClient, ClaudeModel, CredentialManager};
+use crate::ai::provider::settings::{current_settings, save_settings, saved_settings};
+use crate::ai::provider::{active_provider, ChatMessage, ChatRequest, LlmSettings, ProviderKind};
 use crate::billing::{BillingState, LimitCheckResult, LimitDenialReason};
 use crate::jobs::OrganizePlan;
 use std::path::Path;
@@ -79,8 +81,11 @@ pub fn get_configured_providers() -> Vec<ProviderStatus> {
         || std::env::var("VITE_OPENAI_API_KEY").is_ok()
         || CredentialManager::has_api_key("openai");
 
-    eprintln!("[DEBUG] Provider status - anthropic: {}, xai: {}, openai: {}",
-        has_anthropic, has_xai, has_openai);
+    // Local: no key needed, configured once selected in LLM settings
+    let has_local = current_settings().provider == ProviderKind::Local;
+
+    eprintln!("[DEBUG] Provider status - anthropic: {}, xai: {}, openai: {}, local: {}",
+        has_anthropic, has_xai, has_openai, has_local);
 
     vec![
         ProviderStatus {
@@ -95,9 +100,39 @@ pub fn get_configured_providers() -> Vec<ProviderStatus> {
             provider: "openai".to_string(),
             configured: has_openai,
         },
+        ProviderStatus {
+            provider: "local".to_string(),
+            configured: has_local,
+        },
     ]
 }
 
+/// Get the saved LLM provider settings
+#[tauri::command]
+pub fn get_llm_settings() -> LlmSettings {
+    saved_settings()
+}
+
+/// Save LLM provider settings and use them for all subsequent requests
+#[tauri::command]
+pub fn set_llm_settings(settings: LlmSettings) -> Result<(), String> {
+    save_settings(settings)
+}
+
+/// Send a minimal request to the active provider to check it responds
+///
+/// Returns the model's reply, e.g. to confirm a local server is reachable.
+#[tauri::command]
+pub async fn test_llm_provider() -> Result<String, String> {
+    let provider = active_provider()?;
+    let request = ChatRequest::new(ClaudeModel::Haiku.as_str(), 10)
+        .with_system("Say 'ok'")
+        .with_messages(vec![ChatMessage::user("test")]);
+
+    let response = provider.complete(&request).await?;
+    Ok(response.text().trim().to_string())
+}
+
 /// Get rename suggestion for a file
 ///
 /// Requires authentication and checks billing limits before calling AI.
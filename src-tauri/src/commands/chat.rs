@@ -5,7 +5,8 @@
 //! - list_files_for_mention: Get files for @ mention autocomplete
 
 use crate::ai::chat::{
-    run_chat_agent, run_openai_chat_agent, ChatAgentResult, ContextItem, ConversationMessage,
+    chat_provider_for_model, run_chat_agent, run_provider_chat_agent, ChatAgentResult,
+    ContextItem, ConversationMessage,
 };
 use crate::billing::{BillingState, LimitCheckResult};
 use crate::rate_limit::RateLimitState;
@@ -107,11 +108,22 @@ pub async fn chat_stream(
     // Reset abort flag at start of new chat
     abort_flag.0.store(false, std::sync::atomic::Ordering::SeqCst);
 
-    // Determine provider based on model prefix
-    let is_openai_model = request.model.starts_with("gpt-");
+    // Determine provider based on model prefix and the selected LLM backend
+    let chat_provider = match chat_provider_for_model(&request.model) {
+        Ok(provider) => provider,
+        Err(e) => {
+            warn!(error = %e, "No chat provider available");
+            let _ = app.emit("chat:error", serde_json::json!({ "message": e }));
+            return Ok(ChatStreamResponse {
+                success: false,
+                response: None,
+                error: Some(e),
+            });
+        }
+    };
 
-    // GPT models don't support extended thinking - auto-disable
-    let actual_extended_thinking = if is_openai_model {
+    // Only the Anthropic agent supports extended thinking - auto-disable elsewhere
+    let actual_extended_thinking = if chat_provider.is_some() {
         false
     } else {
         request.extended_thinking
@@ -209,9 +221,10 @@ pub async fn chat_stream(
     let abort_flag_arc = Some(Arc::clone(&abort_flag.0));
 
     // Route to appropriate provider based on model
-    let result: Result<ChatAgentResult, String> = if is_openai_model {
-        run_openai_chat_agent(
+    let result: Result<ChatAgentResult, String> = if let Some(provider) = chat_provider {
+        run_provider_chat_agent(
             &app,
+            provider.as_ref(),
             &request.message,
             &request.context_items,
             model_id,
@@ -0,0 +1,69 @@
+//! Duplicate Detection Commands
+//!
+//! Exposes the duplicate finder and deduplication planning to the frontend.
+
+use std::path::PathBuf;
+
+use crate::duplicates::{
+    deduplication_plan, find_duplicates, find_similar_images, similar_images_plan,
+    DuplicateReport, DuplicateScanOptions, KeepPolicy, SimilarImageOptions, SimilarImageReport,
+};
+use crate::jobs::OrganizePlan;
+
+/// Find files with identical content under one or more roots
+#[tauri::command]
+pub async fn find_duplicate_files(
+    roots: Vec<String>,
+    options: Option<DuplicateScanOptions>,
+) -> Result<DuplicateReport, String> {
+    if roots.is_empty() {
+        return Err("At least one folder is required".to_string());
+    }
+    let roots: Vec<PathBuf> = roots.into_iter().map(PathBuf::from).collect();
+    let options = options.unwrap_or_default();
+
+    tokio::task::spawn_blocking(move || find_duplicates(&roots, &options))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))
+}
+
+/// Build a plan that quarantines all but one copy in each duplicate group
+///
+/// The plan's target folder is the common ancestor of the scanned roots;
+/// scan that folder into the VFS before calling `vfs_simulate_plan`.
+#[tauri::command]
+pub fn plan_deduplication(
+    report: DuplicateReport,
+    policy: Option<KeepPolicy>,
+) -> Result<OrganizePlan, String> {
+    deduplication_plan(&report, &policy.unwrap_or_default())
+}
+
+/// Find clusters of visually similar images under one or more roots
+///
+/// Perceptual hashes are cached with thumbnails, so repeat scans only
+/// decode new or modified images.
+#[tauri::command]
+pub async fn scan_similar_images(
+    roots: Vec<String>,
+    options: Option<SimilarImageOptions>,
+) -> Result<SimilarImageReport, String> {
+    if roots.is_empty() {
+        return Err("At least one folder is required".to_string());
+    }
+    let roots: Vec<PathBuf> = roots.into_iter().map(PathBuf::from).collect();
+    let options = options.unwrap_or_default();
+
+    tokio::task::spawn_blocking(move || find_similar_images(&roots, &options))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))
+}
+
+/// Build a plan that quarantines all but one image in each similar cluster
+#[tauri::command]
+pub fn plan_similar_image_cleanup(
+    report: SimilarImageReport,
+    policy: Option<KeepPolicy>,
+) -> Result<OrganizePlan, String> {
+    similar_images_plan(&report, &policy.unwrap_or_default())
+}
@@ -4,10 +4,11 @@
 //! Provides commands for scanning, organizing, and executing file organization plans.
 
 use crate::ai::grok::{
-    DocumentAnalysis, GrokOrganizer, OrganizationPlan,
+    ContentCache, DocumentAnalysis, GrokOrganizer, OrganizationPlan,
     ScanResult, sanitize_filename, sanitize_folder_path,
 };
 use crate::ai::grok::AnalysisPhase;
+use crate::ai::grok::ocr::{self, OcrEngine, OcrPolicy, OcrSettings};
 use crate::execution::executor::{ExecutionEngine, ProgressCallback};
 use crate::jobs::{OrganizeOperation, OrganizePlan};
 use crate::wal::entry::{WALJournal, WALOperationType};
@@ -15,7 +16,7 @@ use crate::wal::journal::WALManager;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tauri::{AppHandle, Emitter, Manager, State};
+use tauri::{AppHandle, Emitter, State};
 use tokio::sync::Mutex;
 
 /// Abort flag for cancelling Grok operations
@@ -54,7 +55,6 @@ impl Default for GrokState {
 pub async fn grok_init(
     api_key: Option<String>,
     state: State<'_, GrokState>,
-    app: AppHandle,
 ) -> Result<(), String> {
     // Get API key from parameter or fallback sources
     let key = match api_key {
@@ -67,11 +67,7 @@ pub async fn grok_init(
     };
 
     // Get cache directory
-    let cache_dir = app
-        .path()
-        .app_cache_dir()
-        .map_err(|e| format!("Failed to get cache dir: {}", e))?
-        .join("grok_cache");
+    let cache_dir = ContentCache::default_dir().ok_or("Failed to get cache dir")?;
 
     let organizer = GrokOrganizer::new(key, &cache_dir)?;
 
@@ -272,7 +268,10 @@ pub async fn grok_generate_plan(
 }
 
 /// Convert Grok's OrganizationPlan to frontend's OrganizePlan format
-fn convert_to_frontend_plan(plan: OrganizationPlan, target_folder: &str) -> OrganizePlan {
+pub(crate) fn convert_to_frontend_plan(
+    plan: OrganizationPlan,
+    target_folder: &str,
+) -> OrganizePlan {
     let mut operations = Vec::new();
     let target_path = PathBuf::from(target_folder);
 
@@ -480,7 +479,7 @@ fn validate_api_key(key: &str) -> Result<(), String> {
 }
 
 /// Get the Grok API key from any available source
-fn get_grok_api_key() -> Result<String, String> {
+pub(crate) fn get_grok_api_key() -> Result<String, String> {
     // Priority: env vars > credential manager
     if let Ok(key) = std::env::var("XAI_API_KEY") {
         validate_api_key(&key)?;
@@ -529,6 +528,37 @@ pub async fn grok_get_api_key() -> Result<Option<String>, String> {
     }
 }
 
+/// Check if this build can OCR scanned documents locally
+#[tauri::command]
+pub fn grok_ocr_available() -> bool {
+    OcrEngine::is_available()
+}
+
+/// Get the OCR policies (default and per-folder)
+#[tauri::command]
+pub fn grok_get_ocr_settings() -> OcrSettings {
+    ocr::current_settings()
+}
+
+/// Save the OCR policies and use them for all subsequent analyses
+#[tauri::command]
+pub fn grok_set_ocr_settings(settings: OcrSettings) -> Result<(), String> {
+    ocr::save_settings(settings)?;
+    tracing::info!("[Grok] OCR settings saved");
+    Ok(())
+}
+
+/// Set the OCR policy of one folder, or clear it (`None`) to inherit
+#[tauri::command]
+pub fn grok_set_folder_ocr_policy(
+    folder: String,
+    policy: Option<OcrPolicy>,
+) -> Result<(), String> {
+    let mut settings = ocr::current_settings();
+    settings.set_folder_policy(PathBuf::from(folder), policy);
+    ocr::save_settings(settings)
+}
+
 /// Execute an OrganizationPlan by converting it to WAL operations
 ///
 /// This converts the Grok plan into executable filesystem operations:
@@ -1,15 +1,19 @@
 //! Tauri commands for organization history and undo operations.
 
+use crate::execution::executor::{ExecutionEngine, ProgressCallback};
 use crate::history::{
-    collect_undo_operations, preflight_undo, ConflictResolution, FolderIndexEntry,
-    HistorySession, HistoryStore, HistorySummary, OperationRecord, SessionSummary,
-    UndoPreflightResult, UndoResult,
+    collect_redo_operations, collect_undo_operations, plan_selective_undo, preflight_redo,
+    preflight_selective_undo, preflight_undo, spawn_folder_snapshot, ConflictResolution,
+    DriftReport, FolderIndexEntry, HistorySession, HistoryStore, HistorySummary,
+    OperationRecord, RedoResult, SelectiveUndoPreflight, SessionSummary, UndoPreflightResult,
+    UndoResult, UndoSelection,
 };
+use crate::quarantine::QuarantineManager;
 use crate::wal::{WALEntry, WALJournal, WALManager, WALOperationType, WALStatus};
 use chrono::Utc;
 use std::collections::HashSet;
 use std::path::PathBuf;
-use std::sync::{LazyLock, Mutex};
+use std::sync::{Arc, LazyLock, Mutex};
 use tauri::{AppHandle, Emitter};
 use uuid::Uuid;
 
@@ -186,14 +190,38 @@ pub async fn history_undo_execute(
     target_session_id: String,
     resolution: String,
 ) -> Result<UndoResult, String> {
-    // Acquire lock to prevent concurrent undo operations on the same folder
-    let _lock_guard = UndoLockGuard::new(&folder_path)?;
-
     let resolution = ConflictResolution::from_str(&resolution)
         .ok_or_else(|| format!("Invalid resolution: {}", resolution))?;
 
+    let result = undo_to_session(
+        &folder_path,
+        &target_session_id,
+        &resolution,
+        &|completed, total| emit_undo_progress(&app_handle, completed, total),
+    )?;
+
+    if result.operations_undone > 0 {
+        refresh_snapshot(&folder_path);
+    }
+
+    Ok(result)
+}
+
+/// Undo a folder back to a session and mark the undone sessions in history
+///
+/// Progress is reported as `(completed, total)`. Shared with the `sentinel
+/// undo` CLI command; callers refresh the folder snapshot themselves.
+pub(crate) fn undo_to_session(
+    folder_path: &str,
+    target_session_id: &str,
+    resolution: &ConflictResolution,
+    on_progress: &dyn Fn(usize, usize),
+) -> Result<UndoResult, String> {
+    // Acquire lock to prevent concurrent undo operations on the same folder
+    let _lock_guard = UndoLockGuard::new(folder_path)?;
+
     // Collect undo operations
-    let undo_ops = collect_undo_operations(&folder_path, &target_session_id)?;
+    let undo_ops = collect_undo_operations(folder_path, target_session_id)?;
 
     if undo_ops.is_empty() {
         return Ok(UndoResult {
@@ -204,11 +232,67 @@ pub async fn history_undo_execute(
         });
     }
 
-    // Convert to WAL operations with path validation
+    let run = run_undo_operations(folder_path, &undo_ops, resolution, on_progress)?;
+
+    // Mark sessions as undone in history
+    if !run.undone.is_empty() {
+        let store = HistoryStore::new();
+        store.mark_sessions_undone(folder_path, target_session_id)?;
+    }
+
+    Ok(run.into_result())
+}
+
+/// Forward undo progress to the frontend
+fn emit_undo_progress(app_handle: &AppHandle, completed: usize, total: usize) {
+    let _ = app_handle.emit(
+        "undo-progress",
+        serde_json::json!({
+            "completed": completed,
+            "total": total,
+        }),
+    );
+}
+
+/// Outcome of running undo operations
+struct UndoRun {
+    /// Indices of the operations that were undone
+    undone: Vec<usize>,
+    operations_skipped: usize,
+    errors: Vec<String>,
+}
+
+impl UndoRun {
+    fn into_result(self) -> UndoResult {
+        UndoResult {
+            success: self.errors.is_empty(),
+            operations_undone: self.undone.len(),
+            operations_skipped: self.operations_skipped,
+            errors: self.errors,
+        }
+    }
+}
+
+/// Run undo operations in order through a WAL journal
+///
+/// Operations with invalid paths are left out. Failures are handled per
+/// `resolution`, and the journal is discarded when nothing failed.
+fn run_undo_operations(
+    folder_path: &str,
+    undo_ops: &[OperationRecord],
+    resolution: &ConflictResolution,
+    on_progress: &dyn Fn(usize, usize),
+) -> Result<UndoRun, String> {
+    // Convert to WAL operations with path validation, remembering which
+    // operation each one came from
     let mut wal_ops: Vec<WALOperationType> = Vec::new();
-    for op in &undo_ops {
-        match operation_record_to_wal(op, &folder_path) {
-            Ok(wal_op) => wal_ops.push(wal_op),
+    let mut origins: Vec<usize> = Vec::new();
+    for (index, op) in undo_ops.iter().enumerate() {
+        match operation_record_to_wal(op, folder_path) {
+            Ok(wal_op) => {
+                wal_ops.push(wal_op);
+                origins.push(index);
+            }
             Err(e) => {
                 tracing::warn!("Skipping invalid undo operation: {}", e);
                 // Skip operations with invalid paths
@@ -218,7 +302,7 @@ pub async fn history_undo_execute(
 
     // Create a new WAL journal for the undo
     let job_id = format!("undo-{}", Utc::now().timestamp_millis());
-    let mut journal = WALJournal::new(job_id.clone(), PathBuf::from(&folder_path));
+    let mut journal = WALJournal::new(job_id.clone(), PathBuf::from(folder_path));
 
     for (i, wal_op) in wal_ops.iter().enumerate() {
         let undo_op = wal_op
@@ -244,7 +328,7 @@ pub async fn history_undo_execute(
     wal_manager.save_journal(&journal)?;
 
     // Execute operations
-    let mut operations_undone = 0;
+    let mut undone = Vec::new();
     let mut operations_skipped = 0;
     let mut errors = Vec::new();
     let total_ops = journal.entries.len();
@@ -253,7 +337,7 @@ pub async fn history_undo_execute(
         // Mark as in progress
         journal.entries[i].status = WALStatus::InProgress;
         journal.entries[i].updated_at = Utc::now();
-        wal_manager.save_journal(&journal)?;
+        wal_manager.record_entry_status(&journal.job_id, &journal.entries[i])?;
 
         // Clone the operation to execute (avoid borrow issues)
         let op_to_execute = journal.entries[i].operation.clone();
@@ -262,16 +346,10 @@ pub async fn history_undo_execute(
         match execute_wal_operation(&op_to_execute) {
             Ok(()) => {
                 journal.entries[i].status = WALStatus::Complete;
-                operations_undone += 1;
+                undone.push(origins[i]);
 
                 // Emit progress event
-                let _ = app_handle.emit(
-                    "undo-progress",
-                    serde_json::json!({
-                        "completed": operations_undone,
-                        "total": total_ops,
-                    }),
-                );
+                on_progress(undone.len(), total_ops);
             }
             Err(e) => {
                 match resolution {
@@ -281,7 +359,7 @@ pub async fn history_undo_execute(
                         journal.entries[i].error = Some(e.clone());
                         journal.entries[i].updated_at = Utc::now();
                         errors.push(e);
-                        wal_manager.save_journal(&journal)?;
+                        wal_manager.record_entry_status(&journal.job_id, &journal.entries[i])?;
                         break;
                     }
                     ConflictResolution::Skip => {
@@ -300,14 +378,8 @@ pub async fn history_undo_execute(
                             operations_skipped += 1;
                         } else {
                             journal.entries[i].status = WALStatus::Complete;
-                            operations_undone += 1;
-                            let _ = app_handle.emit(
-                                "undo-progress",
-                                serde_json::json!({
-                                    "completed": operations_undone,
-                                    "total": total_ops,
-                                }),
-                            );
+                            undone.push(origins[i]);
+                            on_progress(undone.len(), total_ops);
                         }
                     }
                     ConflictResolution::Backup => {
@@ -324,14 +396,8 @@ pub async fn history_undo_execute(
                             operations_skipped += 1;
                         } else {
                             journal.entries[i].status = WALStatus::Complete;
-                            operations_undone += 1;
-                            let _ = app_handle.emit(
-                                "undo-progress",
-                                serde_json::json!({
-                                    "completed": operations_undone,
-                                    "total": total_ops,
-                                }),
-                            );
+                            undone.push(origins[i]);
+                            on_progress(undone.len(), total_ops);
                         }
                     }
                 }
@@ -339,13 +405,7 @@ pub async fn history_undo_execute(
         }
 
         journal.entries[i].updated_at = Utc::now();
-        wal_manager.save_journal(&journal)?;
-    }
-
-    // Mark sessions as undone in history
-    if operations_undone > 0 {
-        let store = HistoryStore::new();
-        store.mark_sessions_undone(&folder_path, &target_session_id)?;
+        wal_manager.record_entry_status(&journal.job_id, &journal.entries[i])?;
     }
 
     // Clean up journal on success
@@ -353,14 +413,177 @@ pub async fn history_undo_execute(
         wal_manager.discard_journal(&job_id)?;
     }
 
-    Ok(UndoResult {
-        success: errors.is_empty(),
-        operations_undone,
+    Ok(UndoRun {
+        undone,
         operations_skipped,
         errors,
     })
 }
 
+/// Perform preflight check before undoing selected operations of a session
+#[tauri::command]
+pub async fn history_selective_undo_preflight(
+    folder_path: String,
+    session_id: String,
+    selection: UndoSelection,
+) -> Result<SelectiveUndoPreflight, String> {
+    // Run in blocking context since it does file I/O
+    tokio::task::spawn_blocking(move || {
+        preflight_selective_undo(&folder_path, &session_id, &selection)
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Undo selected operations of a session
+///
+/// Operations the selection depends on are undone with it. What was undone
+/// is recorded as a new session, which can be undone to re-apply it.
+#[tauri::command]
+pub async fn history_selective_undo_execute(
+    app_handle: AppHandle,
+    folder_path: String,
+    session_id: String,
+    selection: UndoSelection,
+    resolution: String,
+) -> Result<UndoResult, String> {
+    let _lock_guard = UndoLockGuard::new(&folder_path)?;
+
+    let resolution = ConflictResolution::from_str(&resolution)
+        .ok_or_else(|| format!("Invalid resolution: {}", resolution))?;
+
+    let plan = plan_selective_undo(&folder_path, &session_id, &selection)?;
+    let undo_ops: Vec<OperationRecord> = plan
+        .operations
+        .iter()
+        .map(|op| op.undo_operation.clone())
+        .collect();
+
+    let run = run_undo_operations(&folder_path, &undo_ops, &resolution, &|completed, total| {
+        emit_undo_progress(&app_handle, completed, total)
+    })?;
+
+    if !run.undone.is_empty() {
+        let store = HistoryStore::new();
+        store.save_session(&folder_path, plan.to_session(&run.undone, &folder_path))?;
+        refresh_snapshot(&folder_path);
+    }
+
+    Ok(run.into_result())
+}
+
+/// Perform preflight check before redo
+#[tauri::command]
+pub async fn history_redo_preflight(
+    folder_path: String,
+    target_session_id: String,
+) -> Result<UndoPreflightResult, String> {
+    // Run in blocking context since it does file I/O
+    tokio::task::spawn_blocking(move || preflight_redo(&folder_path, &target_session_id))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Redo undone sessions up to a specific session
+///
+/// Replays the recorded operations in their original order through the
+/// execution engine. Sessions only count as applied again once every
+/// operation succeeded; otherwise the journal is kept for recovery.
+#[tauri::command]
+pub async fn history_redo_execute(
+    app_handle: AppHandle,
+    folder_path: String,
+    target_session_id: String,
+) -> Result<RedoResult, String> {
+    // Redo shares the undo lock so the two never interleave on a folder
+    let _lock_guard = UndoLockGuard::new(&folder_path)?;
+
+    let redo_ops = collect_redo_operations(&folder_path, &target_session_id)?;
+
+    // Unlike undo, a partial redo would leave a session half applied
+    let wal_ops = redo_ops
+        .iter()
+        .map(|op| operation_record_to_wal(op, &folder_path))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let job_id = format!("redo-{}", Utc::now().timestamp_millis());
+    let mut journal = WALJournal::new(job_id.clone(), PathBuf::from(&folder_path));
+
+    // Chain the operations so they replay in recorded order
+    let mut previous = None;
+    for wal_op in wal_ops {
+        let id = journal.add_operation_with_deps(wal_op, previous.into_iter().collect())?;
+        previous = Some(id);
+    }
+
+    let wal_manager = WALManager::new();
+    wal_manager.save_journal(&journal)?;
+
+    let progress_handle = app_handle.clone();
+    let progress_callback: Arc<ProgressCallback> =
+        Arc::new(Box::new(move |completed: usize, total: usize| {
+            let _ = progress_handle.emit(
+                "redo-progress",
+                serde_json::json!({
+                    "completed": completed,
+                    "total": total,
+                }),
+            );
+        }));
+
+    let result = ExecutionEngine::new()
+        .execute_journal_with_progress(&job_id, Some(progress_callback))
+        .await?;
+
+    if result.success {
+        HistoryStore::new().mark_sessions_redone(&folder_path, &target_session_id)?;
+        refresh_snapshot(&folder_path);
+        wal_manager.discard_journal(&job_id)?;
+    }
+
+    Ok(RedoResult {
+        success: result.success,
+        operations_redone: result.completed_count,
+        operations_failed: result.failed_count,
+        errors: result.errors,
+    })
+}
+
+/// Report how a folder changed since its last snapshot
+///
+/// Returns None when the folder has no snapshot yet.
+#[tauri::command]
+pub async fn history_folder_drift(folder_path: String) -> Result<Option<DriftReport>, String> {
+    // Run in blocking context since it hashes changed files
+    tokio::task::spawn_blocking(move || {
+        match HistoryStore::new().load_snapshot(&folder_path)? {
+            Some(snapshot) => snapshot.drift().map(Some),
+            None => Ok(None),
+        }
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Take a new snapshot of a folder, accepting its current state as the
+/// baseline for drift reports
+#[tauri::command]
+pub async fn history_snapshot_folder(folder_path: String) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        HistoryStore::new().snapshot_folder(&folder_path).map(|_| ())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Re-snapshot a folder after undo or redo so drift reports don't count
+/// history changes as drift
+///
+/// The snapshot runs in the background so the command returns right away.
+fn refresh_snapshot(folder_path: &str) {
+    spawn_folder_snapshot(folder_path.to_string());
+}
+
 /// Delete history for a folder
 #[tauri::command]
 pub fn history_delete(folder_path: String) -> Result<(), String> {
@@ -410,6 +633,7 @@ fn operation_record_to_wal(
         OperationRecord::Quarantine {
             path,
             quarantine_path,
+            item_id,
         } => {
             let validated_path = validate_undo_path(path, base_folder)?;
             // Quarantine path is in a system directory, so we just validate it's not traversing
@@ -419,6 +643,22 @@ fn operation_record_to_wal(
             Ok(WALOperationType::Quarantine {
                 path: validated_path,
                 quarantine_path: PathBuf::from(quarantine_path),
+                item_id: item_id.clone(),
+            })
+        }
+        OperationRecord::Restore {
+            item_id,
+            path,
+            quarantine_path,
+        } => {
+            let validated_path = validate_undo_path(path, base_folder)?;
+            if quarantine_path.contains("..") {
+                return Err("Quarantine path contains traversal".to_string());
+            }
+            Ok(WALOperationType::Restore {
+                item_id: item_id.clone(),
+                path: validated_path,
+                quarantine_path: PathBuf::from(quarantine_path),
             })
         }
         OperationRecord::Copy {
@@ -510,13 +750,20 @@ fn execute_wal_operation(op: &WALOperationType) -> Result<(), String> {
             }
         }
         WALOperationType::Quarantine {
-            path: _,
-            quarantine_path: _,
-        } => {
+            path,
+            item_id: Some(item_id),
+            ..
+        } => QuarantineManager::new()?
+            .quarantine_with_id(path, item_id)
+            .map(|_| ()),
+        WALOperationType::Quarantine { item_id: None, .. } => {
             // Quarantine undo is handled as a Move in the undo_operation
             // This branch shouldn't be hit directly
             Err("Quarantine undo should be a Move operation".to_string())
         }
+        WALOperationType::Restore { item_id, path, .. } => {
+            QuarantineManager::new()?.restore_to(item_id, path)
+        }
     }
 }
 
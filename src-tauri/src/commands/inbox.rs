@@ -0,0 +1,135 @@
+//! Inbox rule Tauri commands
+//!
+//! CRUD for standing rules attached to watched folders, plus a preview that
+//! shows which files currently in the folder a condition would match.
+
+use std::path::PathBuf;
+use tauri::State;
+
+use crate::ai::rules::{DocumentContentSource, VirtualFile};
+use crate::commands::watcher::validate_watch_path;
+use crate::inbox::{find_matching_rule, InboxAction, InboxHandle, InboxRule};
+
+/// Maximum number of matches returned by a rule preview
+const MAX_PREVIEW_MATCHES: usize = 500;
+
+/// Rule definition sent by the frontend (ID omitted for new rules)
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InboxRuleInput {
+    pub id: Option<String>,
+    pub name: String,
+    pub folder: String,
+    pub condition: String,
+    pub action: InboxAction,
+    pub enabled: Option<bool>,
+    pub priority: Option<i32>,
+}
+
+/// List inbox rules, optionally only those attached to one folder
+#[tauri::command]
+pub fn inbox_list_rules(
+    folder: Option<String>,
+    inbox: State<'_, InboxHandle>,
+) -> Result<Vec<InboxRule>, String> {
+    let store = inbox.read_store();
+    Ok(match folder {
+        Some(folder) => store.rules_for_folder(&folder),
+        None => store.rules().to_vec(),
+    })
+}
+
+/// Create or update an inbox rule
+#[tauri::command]
+pub fn inbox_save_rule(
+    rule: InboxRuleInput,
+    inbox: State<'_, InboxHandle>,
+) -> Result<InboxRule, String> {
+    let folder_path = PathBuf::from(&rule.folder);
+    if !folder_path.is_dir() {
+        return Err(format!("Path is not a directory: {}", rule.folder));
+    }
+
+    // SECURITY: Rules can only be attached to folders that may be watched
+    validate_watch_path(&folder_path)?;
+
+    let mut new_rule = InboxRule::new(rule.name, rule.folder, rule.condition, rule.action);
+    if let Some(id) = rule.id {
+        new_rule.id = id;
+    }
+    if let Some(enabled) = rule.enabled {
+        new_rule.enabled = enabled;
+    }
+    if let Some(priority) = rule.priority {
+        new_rule.priority = priority;
+    }
+
+    let saved = inbox.write_store().upsert(new_rule)?;
+
+    tracing::info!(rule = %saved.name, folder = %saved.folder, "Saved inbox rule");
+
+    Ok(saved)
+}
+
+/// Delete an inbox rule
+#[tauri::command]
+pub fn inbox_delete_rule(rule_id: String, inbox: State<'_, InboxHandle>) -> Result<(), String> {
+    inbox.write_store().remove(&rule_id)
+}
+
+/// Enable or disable an inbox rule
+#[tauri::command]
+pub fn inbox_set_rule_enabled(
+    rule_id: String,
+    enabled: bool,
+    inbox: State<'_, InboxHandle>,
+) -> Result<InboxRule, String> {
+    inbox.write_store().set_enabled(&rule_id, enabled)
+}
+
+/// Preview which files currently in a folder match a condition.
+///
+/// Does not modify anything; useful while the user is writing a rule.
+#[tauri::command]
+pub async fn inbox_preview_rule(folder: String, condition: String) -> Result<Vec<String>, String> {
+    let folder_path = PathBuf::from(&folder);
+    validate_watch_path(&folder_path)?;
+
+    let rule = InboxRule::new(
+        "preview".to_string(),
+        folder.clone(),
+        condition,
+        InboxAction::Quarantine,
+    );
+    rule.parse_condition()?;
+
+    tokio::task::spawn_blocking(move || {
+        let content = DocumentContentSource::open_default();
+        let entries = std::fs::read_dir(&folder_path)
+            .map_err(|e| format!("Failed to read directory: {}", e))?;
+
+        let mut matches = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            // Mirror the watcher: only regular, visible files are candidates
+            if path.is_symlink() || path.is_dir() {
+                continue;
+            }
+            let file = match VirtualFile::from_path(&path) {
+                Ok(file) if !file.is_hidden => file,
+                _ => continue,
+            };
+            if find_matching_rule(std::slice::from_ref(&rule), &file, &content).is_some() {
+                matches.push(file.path);
+                if matches.len() >= MAX_PREVIEW_MATCHES {
+                    break;
+                }
+            }
+        }
+
+        matches.sort();
+        Ok(matches)
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
@@ -1,18 +1,12 @@
 use crate::execution::{
-    ConflictPolicy, ExecutionConfig, ExecutionEngine, ExecutionResult, ProgressCallback,
-    StateSnapshot, StateValidator, ValidationResult,
-};
-use crate::history::{
-    compute_file_checksum, FileChecksum, HistoryOperation, HistorySession, HistoryStore,
-    OperationRecord,
+    journal_from_plan, ConflictPolicy, ExecutionConfig, ExecutionEngine, ExecutionResult,
+    ProgressCallback, StateSnapshot, StateValidator, ValidationResult,
 };
+use crate::history::{save_plan_history, spawn_folder_snapshot};
 use crate::jobs::{JobManager, JobStatus, OrganizeJob, OrganizeOperation, OrganizePlan};
 use crate::security::PathValidator;
-use crate::wal::entry::{WALJournal, WALOperationType};
 use crate::wal::journal::WALManager;
-use chrono::Utc;
-use std::collections::HashMap;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use std::sync::Arc;
 use tauri::{AppHandle, Emitter};
 
@@ -273,13 +267,11 @@ pub async fn execute_plan_parallel(
 
     // Parse conflict policy (default to AutoRename for better UX)
     let policy = match conflict_policy.as_deref() {
-        Some("skip") => ConflictPolicy::Skip,
-        Some("fail") => ConflictPolicy::Fail,
-        Some("auto_rename") | None => ConflictPolicy::AutoRename, // Default to auto-rename
-        Some(other) => {
-            tracing::warn!(policy = %other, "Unknown conflict policy, using auto_rename");
+        None => ConflictPolicy::AutoRename, // Default to auto-rename
+        Some(name) => ConflictPolicy::from_name(name).unwrap_or_else(|| {
+            tracing::warn!(policy = %name, "Unknown conflict policy, using auto_rename");
             ConflictPolicy::AutoRename
-        }
+        }),
     };
 
     let config = ExecutionConfig {
@@ -291,97 +283,7 @@ pub async fn execute_plan_parallel(
     );
 
     // Create a WAL journal from the plan
-    let target_folder = PathBuf::from(&plan.target_folder);
-    let mut journal = WALJournal::new(plan.plan_id.clone(), target_folder.clone());
-
-    // Track folder creation operations for dependencies
-    let mut folder_op_ids: std::collections::HashMap<String, uuid::Uuid> =
-        std::collections::HashMap::new();
-
-    // Convert operations to WAL entries with dependencies
-    for op in &plan.operations {
-        let wal_op = match op.op_type.as_str() {
-            "create_folder" => {
-                let path = op.path.as_ref().ok_or_else(|| {
-                    format!("Operation '{}' (create_folder) missing required field 'path'", op.op_id)
-                })?;
-                WALOperationType::CreateFolder {
-                    path: PathBuf::from(path),
-                }
-            }
-            "move" => {
-                let src = op.source.as_ref().ok_or_else(|| {
-                    format!("Operation '{}' (move) missing required field 'source'", op.op_id)
-                })?;
-                let dst = op.destination.as_ref().ok_or_else(|| {
-                    format!("Operation '{}' (move) missing required field 'destination'", op.op_id)
-                })?;
-                WALOperationType::Move {
-                    source: PathBuf::from(src),
-                    destination: PathBuf::from(dst),
-                }
-            }
-            "rename" => {
-                let path = op.path.as_ref().ok_or_else(|| {
-                    format!("Operation '{}' (rename) missing required field 'path'", op.op_id)
-                })?;
-                let new_name = op.new_name.as_ref().ok_or_else(|| {
-                    format!("Operation '{}' (rename) missing required field 'newName'", op.op_id)
-                })?;
-                WALOperationType::Rename {
-                    path: PathBuf::from(path),
-                    new_name: new_name.clone(),
-                }
-            }
-            "trash" | "quarantine" => {
-                let path = op.path.as_ref().ok_or_else(|| {
-                    format!("Operation '{}' ({}) missing required field 'path'", op.op_id, op.op_type)
-                })?;
-                let quarantine_path = dirs::data_dir()
-                    .unwrap_or_else(|| PathBuf::from("/tmp"))
-                    .join("sentinel")
-                    .join("quarantine")
-                    .join(&op.op_id);
-                WALOperationType::Quarantine {
-                    path: PathBuf::from(path),
-                    quarantine_path,
-                }
-            }
-            unknown_type => {
-                return Err(format!("Operation '{}' has unknown type '{}'", op.op_id, unknown_type));
-            }
-        };
-
-        // Check for dependencies - moves depend on their destination folder being created
-        let mut depends_on = Vec::new();
-        if let WALOperationType::Move { destination, .. } = &wal_op {
-            if let Some(parent) = destination.parent() {
-                let parent_str = parent.to_string_lossy().to_string();
-                if let Some(&folder_op_id) = folder_op_ids.get(&parent_str) {
-                    depends_on.push(folder_op_id);
-                }
-            }
-        }
-
-        // Track folder creation for dependency resolution
-        if let WALOperationType::CreateFolder { ref path } = wal_op {
-            let path_str = path.to_string_lossy().to_string();
-            let op_id = if depends_on.is_empty() {
-                journal.add_operation(wal_op)
-                    .map_err(|e| format!("Failed to add operation: {}", e))?
-            } else {
-                journal.add_operation_with_deps(wal_op, depends_on)
-                    .map_err(|e| format!("Failed to add operation: {}", e))?
-            };
-            folder_op_ids.insert(path_str, op_id);
-        } else if depends_on.is_empty() {
-            journal.add_operation(wal_op)
-                .map_err(|e| format!("Failed to add operation: {}", e))?;
-        } else {
-            journal.add_operation_with_deps(wal_op, depends_on)
-                .map_err(|e| format!("Failed to add operation: {}", e))?;
-        }
-    }
+    let journal = journal_from_plan(&plan)?;
 
     tracing::debug!(
         entries = journal.entries.len(),
@@ -456,7 +358,7 @@ pub async fn execute_plan_parallel(
         }
 
         // V9: Save organization history for multi-level undo
-        if let Err(e) = save_organization_history(
+        if let Err(e) = save_plan_history(
             &plan,
             &journal,
             user_instruction.as_deref().unwrap_or("Organize folder"),
@@ -473,6 +375,7 @@ pub async fn execute_plan_parallel(
                 operations = result.completed_count,
                 "Saved organization history for undo"
             );
+            spawn_folder_snapshot(plan.target_folder.clone());
         }
     }
 
@@ -556,149 +459,3 @@ fn cleanup_empty_directories(path: &std::path::Path) -> Result<usize, String> {
 
     Ok(deleted_count)
 }
-
-/// Save organization history for multi-level undo.
-///
-/// This creates a history session from the completed execution and saves it
-/// to the history store. The session includes all operations with their
-/// inverse operations and checksums for integrity verification.
-fn save_organization_history(
-    plan: &OrganizePlan,
-    journal: &WALJournal,
-    user_instruction: &str,
-    files_affected: usize,
-) -> Result<(), String> {
-    let store = HistoryStore::new();
-
-    // Build history operations from journal entries
-    let mut history_ops = Vec::new();
-
-    for (i, entry) in journal.entries.iter().enumerate() {
-        // Convert WAL operation to history operation record
-        let operation = wal_to_operation_record(&entry.operation)?;
-        let undo_operation = operation.inverse();
-
-        // Compute checksums for source and result files
-        let (source_checksums, result_checksums) = compute_operation_checksums(&entry.operation);
-
-        history_ops.push(HistoryOperation {
-            id: entry.id.to_string(),
-            sequence: i as u32,
-            operation,
-            undo_operation,
-            source_checksums,
-            result_checksums,
-        });
-    }
-
-    // Create the history session
-    let session = HistorySession {
-        session_id: plan.plan_id.clone(),
-        user_instruction: user_instruction.to_string(),
-        plan_description: plan.description.clone(),
-        executed_at: Utc::now(),
-        target_folder: plan.target_folder.clone(),
-        operations: history_ops,
-        files_affected,
-        undone: false,
-    };
-
-    // Save to history store
-    store.save_session(&plan.target_folder, session)?;
-
-    Ok(())
-}
-
-/// Convert WALOperationType to OperationRecord
-fn wal_to_operation_record(wal_op: &WALOperationType) -> Result<OperationRecord, String> {
-    match wal_op {
-        WALOperationType::CreateFolder { path } => Ok(OperationRecord::CreateFolder {
-            path: path.to_string_lossy().to_string(),
-        }),
-        WALOperationType::Move {
-            source,
-            destination,
-        } => Ok(OperationRecord::Move {
-            source: source.to_string_lossy().to_string(),
-            destination: destination.to_string_lossy().to_string(),
-        }),
-        WALOperationType::Rename { path, new_name } => Ok(OperationRecord::Rename {
-            path: path.to_string_lossy().to_string(),
-            new_name: new_name.clone(),
-        }),
-        WALOperationType::Quarantine {
-            path,
-            quarantine_path,
-        } => Ok(OperationRecord::Quarantine {
-            path: path.to_string_lossy().to_string(),
-            quarantine_path: quarantine_path.to_string_lossy().to_string(),
-        }),
-        WALOperationType::Copy {
-            source,
-            destination,
-        } => Ok(OperationRecord::Copy {
-            source: source.to_string_lossy().to_string(),
-            destination: destination.to_string_lossy().to_string(),
-        }),
-        WALOperationType::DeleteFolder { path } => Ok(OperationRecord::DeleteFolder {
-            path: path.to_string_lossy().to_string(),
-        }),
-    }
-}
-
-/// Compute checksums for source and result files of an operation
-fn compute_operation_checksums(
-    wal_op: &WALOperationType,
-) -> (HashMap<String, FileChecksum>, HashMap<String, FileChecksum>) {
-    let source_checksums = HashMap::new();
-    let mut result_checksums = HashMap::new();
-
-    match wal_op {
-        WALOperationType::Move {
-            source: _,
-            destination,
-        } => {
-            // After move, file is at destination (source no longer exists)
-            if let Ok(checksum) = compute_file_checksum(destination) {
-                result_checksums.insert(destination.to_string_lossy().to_string(), checksum);
-            }
-        }
-        WALOperationType::Rename { path, new_name } => {
-            // After rename, file is at new path
-            let parent = path.parent().unwrap_or(Path::new(""));
-            let new_path = parent.join(new_name);
-            if let Ok(checksum) = compute_file_checksum(&new_path) {
-                result_checksums.insert(new_path.to_string_lossy().to_string(), checksum);
-            }
-        }
-        WALOperationType::CreateFolder { path } => {
-            // Folder now exists at path
-            if let Ok(checksum) = compute_file_checksum(path) {
-                result_checksums.insert(path.to_string_lossy().to_string(), checksum);
-            }
-        }
-        WALOperationType::Copy {
-            source: _,
-            destination,
-        } => {
-            // Copy creates file at destination
-            if let Ok(checksum) = compute_file_checksum(destination) {
-                result_checksums.insert(destination.to_string_lossy().to_string(), checksum);
-            }
-        }
-        WALOperationType::Quarantine {
-            path: _,
-            quarantine_path,
-        } => {
-            // File is now at quarantine path
-            if let Ok(checksum) = compute_file_checksum(quarantine_path) {
-                result_checksums.insert(quarantine_path.to_string_lossy().to_string(), checksum);
-            }
-        }
-        WALOperationType::DeleteFolder { path: _ } => {
-            // Nothing to checksum - folder is deleted
-        }
-    }
-
-    (source_checksums, result_checksums)
-}
@@ -1,9 +1,11 @@
 pub mod ai;
 pub mod billing;
 pub mod chat;
+pub mod duplicates;
 pub mod filesystem;
 pub mod grok;
 pub mod history;
+pub mod inbox;
 pub mod jobs;
 pub mod permissions;
 pub mod photos;
@@ -16,9 +18,11 @@ pub mod watcher;
 pub use ai::*;
 pub use billing::*;
 pub use chat::*;
+pub use duplicates::*;
 pub use filesystem::*;
 pub use grok::*;
 pub use history::*;
+pub use inbox::*;
 pub use jobs::*;
 pub use permissions::*;
 pub use photos::*;
@@ -1,8 +1,9 @@
+use crate::media::{self, PhotoMetadata};
 use std::path::Path;
 
 const IMAGE_EXTENSIONS: &[&str] = &[
-    "jpg", "jpeg", "png", "gif", "webp", "heic", "heif", "bmp", "tiff", "tif", "raw", "cr2", "nef",
-    "arw", "svg", "ico",
+    "jpg", "jpeg", "png", "gif", "webp", "heic", "heif", "avif", "bmp", "tiff", "tif", "raw", "cr2",
+    "nef", "arw", "dng", "orf", "rw2", "pef", "srw", "raf", "svg", "ico",
 ];
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -14,6 +15,8 @@ pub struct PhotoEntry {
     pub created_at: Option<i64>,
     pub modified_at: Option<i64>,
     pub extension: Option<String>,
+    /// Capture metadata from EXIF or an XMP sidecar
+    pub exif: Option<PhotoMetadata>,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -48,11 +51,10 @@ pub async fn scan_photos(directories: Vec<String>) -> Result<PhotoScanResult, St
         }
     }
 
-    // Sort by date (newest first)
-    photos.sort_by(|a, b| {
-        let a_date = a.created_at.or(a.modified_at).unwrap_or(0);
-        let b_date = b.created_at.or(b.modified_at).unwrap_or(0);
-        b_date.cmp(&a_date)
+    // Sort by capture date, then filesystem date (newest first)
+    photos.sort_by_key(|p| {
+        let taken_at = p.exif.as_ref().and_then(|e| e.taken_at);
+        std::cmp::Reverse(taken_at.or(p.created_at).or(p.modified_at).unwrap_or(0))
     });
 
     let total_count = photos.len();
@@ -176,5 +178,6 @@ fn create_photo_entry(path: &Path) -> std::io::Result<PhotoEntry> {
         created_at,
         modified_at,
         extension,
+        exif: media::read_photo_metadata(path),
     })
 }
@@ -3,13 +3,20 @@
 //! Provides Tauri commands for initializing and querying the vector index,
 //! as well as generating compressed tree XML for AI context.
 
+use crate::ai::grok::document_parser::archive_search_text;
+use crate::archive;
 use crate::models::FileEntry;
 use crate::tree::{to_xml, TreeCompressor, TreeConfig};
-use crate::vector::{VectorConfig, VectorIndex};
-use std::path::PathBuf;
+use crate::vector::{EmbeddingStore, VectorConfig, VectorIndex};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
 use std::sync::{Arc, RwLock};
 use tauri::State;
 
+/// Characters of an archive's listing and member text used as its preview
+const MAX_ARCHIVE_PREVIEW_CHARS: usize = 2000;
+
 /// Shared state for the vector index
 pub struct VectorState(pub Arc<RwLock<Option<VectorIndex>>>);
 
@@ -19,6 +26,48 @@ impl Default for VectorState {
     }
 }
 
+/// Paths changed on disk, waiting to be synced into the vector index
+///
+/// The folder watcher only enqueues; one background thread reads, embeds and
+/// stores the files in order, so the watcher thread never waits on them.
+pub struct VectorSyncQueue {
+    sender: Sender<PathBuf>,
+}
+
+impl VectorSyncQueue {
+    /// Start the sync thread for the index in `state`
+    pub fn start(state: &VectorState) -> Self {
+        let (sender, receiver) = mpsc::channel::<PathBuf>();
+        let state = VectorState(Arc::clone(&state.0));
+        let spawned = std::thread::Builder::new()
+            .name("vector-sync".to_string())
+            .spawn(move || {
+                while let Ok(path) = receiver.recv() {
+                    // Syncing reads the file's current state, so paths queued
+                    // several times by one burst of events only need one pass
+                    let mut batch = vec![path];
+                    for path in receiver.try_iter() {
+                        if !batch.contains(&path) {
+                            batch.push(path);
+                        }
+                    }
+                    for path in &batch {
+                        sync_vector_index(&state, path);
+                    }
+                }
+            });
+        if let Err(e) = spawned {
+            eprintln!("[VectorCommand] Warning: Vector index will not follow file changes: {}", e);
+        }
+        Self { sender }
+    }
+
+    /// Queue a changed, created or removed path
+    pub fn enqueue(&self, path: PathBuf) {
+        let _ = self.sender.send(path);
+    }
+}
+
 /// Shared state for tree configuration
 pub struct TreeState {
     pub config: RwLock<TreeConfig>,
@@ -50,43 +99,32 @@ pub async fn init_vector_index(
         return Err(format!("Invalid folder path: {}", folder_path));
     }
 
-    // Create the vector index
+    // Create the vector index, reusing embeddings persisted by earlier sessions
     let config = VectorConfig::default();
+    let model = config.model.id();
     let mut index = VectorIndex::new(config)?;
+    match EmbeddingStore::open_default(model) {
+        Ok(store) => index = index.with_store(store),
+        Err(e) => eprintln!("[VectorCommand] Warning: Embeddings will not be persisted: {}", e),
+    }
+    index.set_root(path.clone());
 
     // Collect files to index
     let files = collect_files_recursive(&path, 5)?;
     eprintln!("[VectorCommand] Found {} files to index", files.len());
 
-    if files.is_empty() {
-        // Store empty index
-        let mut state_guard = state.0.write().map_err(|e| e.to_string())?;
-        *state_guard = Some(index);
-        return Ok(0);
-    }
-
-    // Prepare batch for indexing
-    let batch: Vec<(PathBuf, String, Option<String>)> = files
+    let batch: Vec<(PathBuf, String)> = files
         .into_iter()
-        .map(|entry| {
-            let file_path = PathBuf::from(&entry.path);
-            let content_preview = get_content_preview(&file_path);
-            (file_path, entry.name, content_preview)
-        })
+        .map(|entry| (PathBuf::from(&entry.path), entry.name))
         .collect();
+    let seen: HashSet<PathBuf> = batch.iter().map(|(p, _)| p.clone()).collect();
 
-    // Index in batches of 100 for memory efficiency
-    let mut total_indexed = 0;
-    for chunk in batch.chunks(100) {
-        let chunk_vec: Vec<(PathBuf, String, Option<String>)> = chunk.to_vec();
-        match index.index_batch(chunk_vec) {
-            Ok(count) => {
-                total_indexed += count;
-                eprintln!("[VectorCommand] Indexed {} files (total: {})", count, total_indexed);
-            }
-            Err(e) => {
-                eprintln!("[VectorCommand] Warning: Batch indexing failed: {}", e);
-            }
+    let stats = index.index_incremental(batch, get_content_preview)?;
+
+    // Forget files that were deleted since the last session
+    if let Some(store) = index.store() {
+        if let Err(e) = store.prune(&path, &seen) {
+            eprintln!("[VectorCommand] Warning: Failed to prune vector store: {}", e);
         }
     }
 
@@ -94,8 +132,74 @@ pub async fn init_vector_index(
     let mut state_guard = state.0.write().map_err(|e| e.to_string())?;
     *state_guard = Some(index);
 
-    eprintln!("[VectorCommand] Vector index initialized with {} documents", total_indexed);
-    Ok(total_indexed)
+    eprintln!(
+        "[VectorCommand] Vector index initialized with {} documents ({} reused, {} embedded)",
+        stats.total(),
+        stats.reused,
+        stats.embedded
+    );
+    Ok(stats.total())
+}
+
+/// Keep the vector index current after a file changed on disk
+///
+/// Called by `VectorSyncQueue` for paths reported by the folder watcher.
+/// Files outside the indexed folder are ignored; paths that no longer exist
+/// are removed from the index.
+fn sync_vector_index(state: &VectorState, path: &Path) {
+    // Same filter as collect_files_recursive: no hidden files, no symlinks
+    let hidden = path
+        .file_name()
+        .is_some_and(|n| n.to_string_lossy().starts_with('.'));
+    if hidden || path.is_symlink() {
+        return;
+    }
+
+    let in_index = |index: &VectorIndex| index.root().is_some_and(|root| path.starts_with(root));
+
+    // Read and embed the file under the read lock, so searches keep running
+    let prepared = if path.is_file() {
+        let state_guard = match state.0.read() {
+            Ok(guard) => guard,
+            Err(_) => return,
+        };
+        let Some(index) = state_guard.as_ref().filter(|index| in_index(index)) else {
+            return;
+        };
+        match index.prepare_file(path, get_content_preview(path).as_deref()) {
+            Ok(prepared) => Some(prepared),
+            Err(e) => {
+                eprintln!("[VectorCommand] Warning: Failed to update index for {:?}: {}", path, e);
+                return;
+            }
+        }
+    } else if !path.exists() {
+        None
+    } else {
+        return;
+    };
+
+    // Only the insert or removal needs the write lock
+    let mut state_guard = match state.0.write() {
+        Ok(guard) => guard,
+        Err(_) => return,
+    };
+    // The index may have been replaced while embedding
+    let index = match state_guard.as_mut() {
+        Some(index) if in_index(index) => index,
+        _ => return,
+    };
+
+    let result = match prepared {
+        Some(prepared) => {
+            index.insert_prepared(prepared);
+            Ok(())
+        }
+        None => index.remove_file(path),
+    };
+    if let Err(e) = result {
+        eprintln!("[VectorCommand] Warning: Failed to update index for {:?}: {}", path, e);
+    }
 }
 
 /// Search the vector index with a natural language query
@@ -364,8 +468,13 @@ fn collect_files_recursive_inner(
 
 /// Get a content preview for a file (for better semantic matching)
 ///
-/// Currently supports text files; returns None for binary files
-fn get_content_preview(path: &PathBuf) -> Option<String> {
+/// Supports text files, and archives through their member listing and
+/// member text; returns None for other binary files
+fn get_content_preview(path: &Path) -> Option<String> {
+    if archive::is_archive(path) {
+        return archive_search_text(path, MAX_ARCHIVE_PREVIEW_CHARS);
+    }
+
     // Only read text files
     let extension = path.extension()?.to_str()?;
 
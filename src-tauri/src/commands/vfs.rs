@@ -5,12 +5,18 @@
 
 use std::path::PathBuf;
 use std::sync::Arc;
-use tauri::State;
-use tokio::sync::RwLock;
-
+use tauri::{AppHandle, Emitter, State};
+use tokio::sync::{Mutex, RwLock};
+
+use crate::execution::{
+    journal_from_plan, plan_from_staged, simulated_operations_from_plan, ExecutionEngine,
+    ExecutionResult, ProgressCallback,
+};
+use crate::history::{save_plan_history, spawn_folder_snapshot};
 use crate::jobs::OrganizePlan;
 use crate::quarantine::{CleanupStats, QuarantineManager, QuarantinedItem};
-use crate::vfs::{FileNode, JWalkScanner, ScanStats, ShadowVFS, SimulatedOperation};
+use crate::vfs::{DryRunReport, FileNode, JWalkScanner, ScanStats, ShadowVFS};
+use crate::wal::journal::WALManager;
 
 /// Thread-safe VFS state managed by Tauri
 pub type VFSState = Arc<RwLock<Option<ShadowVFS>>>;
@@ -20,6 +26,9 @@ pub fn create_vfs_state() -> VFSState {
     Arc::new(RwLock::new(None))
 }
 
+/// Held while staged changes run, so the same set cannot be executed twice
+static EXECUTING_STAGED: Mutex<()> = Mutex::const_new(());
+
 /// Quarantine state managed by Tauri
 pub type QuarantineState = Arc<RwLock<QuarantineManager>>;
 
@@ -150,75 +159,38 @@ pub struct VfsValidationResult {
     pub plan_hash: String,
     /// Plan ID for reference
     pub plan_id: String,
+    /// Fraction of files the plan accounts for
+    pub coverage: f64,
 }
 
 /// Validate an organize plan on the VFS
 ///
 /// This is the enhanced validation command that returns structured results
 /// including a plan hash for sync validation between frontend and backend.
+/// The VFS itself is left unchanged.
 #[tauri::command]
 pub async fn vfs_validate_plan(
     plan: OrganizePlan,
     vfs_state: State<'_, VFSState>,
 ) -> Result<VfsValidationResult, String> {
-    let mut state = vfs_state.write().await;
+    let state = vfs_state.read().await;
     let vfs = state
-        .as_mut()
+        .as_ref()
         .ok_or("VFS not initialized. Call scan_folder_vfs first.")?;
 
     // Compute plan hash for sync validation
     let plan_hash = plan.compute_hash();
     let plan_id = plan.plan_id.clone();
 
-    let operations: Vec<SimulatedOperation> = plan
-        .operations
-        .iter()
-        .filter_map(|op| {
-            match op.op_type.as_str() {
-                "move" => {
-                    let src = op.source.as_ref()?;
-                    let dest = op.destination.as_ref()?;
-                    Some(SimulatedOperation::Move {
-                        source: src.clone(),
-                        destination: dest.clone(),
-                    })
-                }
-                "create_folder" => {
-                    let path = op.path.as_ref()?;
-                    Some(SimulatedOperation::CreateFolder { path: path.clone() })
-                }
-                "delete" | "trash" => {
-                    let path = op.path.as_ref().or(op.source.as_ref())?;
-                    Some(SimulatedOperation::Delete { path: path.clone() })
-                }
-                "rename" => {
-                    let path = op.path.as_ref()?;
-                    let new_name = op.new_name.as_ref()?;
-                    let path_buf = PathBuf::from(path);
-                    let new_path = path_buf
-                        .parent()
-                        .map(|p| p.join(new_name))
-                        .unwrap_or_else(|| PathBuf::from(new_name));
-                    Some(SimulatedOperation::Move {
-                        source: path.clone(),
-                        destination: new_path.to_string_lossy().to_string(),
-                    })
-                }
-                _ => None,
-            }
-        })
-        .collect();
-
-    let errors = match crate::vfs::simulate_plan(vfs, operations) {
-        Ok(()) => Vec::new(),
-        Err(e) => e,
-    };
+    let operations = simulated_operations_from_plan(&plan);
+    let validation = crate::vfs::validate_plan(vfs, operations);
 
     Ok(VfsValidationResult {
-        valid: errors.is_empty(),
-        errors,
+        valid: validation.errors.is_empty(),
+        errors: validation.errors,
         plan_hash,
         plan_id,
+        coverage: validation.coverage,
     })
 }
 
@@ -237,45 +209,7 @@ pub async fn vfs_simulate_plan(
         .as_mut()
         .ok_or("VFS not initialized. Call scan_folder_vfs first.")?;
 
-    let operations: Vec<SimulatedOperation> = plan
-        .operations
-        .iter()
-        .filter_map(|op| {
-            match op.op_type.as_str() {
-                "move" => {
-                    let src = op.source.as_ref()?;
-                    let dest = op.destination.as_ref()?;
-                    Some(SimulatedOperation::Move {
-                        source: src.clone(),
-                        destination: dest.clone(),
-                    })
-                }
-                "create_folder" => {
-                    let path = op.path.as_ref()?;
-                    Some(SimulatedOperation::CreateFolder { path: path.clone() })
-                }
-                "delete" => {
-                    let path = op.path.as_ref().or(op.source.as_ref())?;
-                    Some(SimulatedOperation::Delete { path: path.clone() })
-                }
-                "rename" => {
-                    // Rename is a move to the same directory with a new name
-                    let path = op.path.as_ref()?;
-                    let new_name = op.new_name.as_ref()?;
-                    let path_buf = PathBuf::from(path);
-                    let new_path = path_buf
-                        .parent()
-                        .map(|p| p.join(new_name))
-                        .unwrap_or_else(|| PathBuf::from(new_name));
-                    Some(SimulatedOperation::Move {
-                        source: path.clone(),
-                        destination: new_path.to_string_lossy().to_string(),
-                    })
-                }
-                _ => None,
-            }
-        })
-        .collect();
+    let operations = simulated_operations_from_plan(&plan);
 
     match crate::vfs::simulate_plan(vfs, operations) {
         Ok(()) => Ok(Vec::new()),
@@ -283,6 +217,27 @@ pub async fn vfs_simulate_plan(
     }
 }
 
+/// Dry-run an organize plan on the VFS
+///
+/// Classifies every operation as a rename or a cross-device copy, sums the
+/// bytes to copy, checks free space on each destination device, estimates
+/// how long execution takes and flags operations whose target path would be
+/// rejected. Neither the VFS nor the filesystem is changed.
+#[tauri::command]
+pub async fn vfs_dry_run_plan(
+    plan: OrganizePlan,
+    vfs_state: State<'_, VFSState>,
+) -> Result<DryRunReport, String> {
+    let state = vfs_state.read().await;
+    let vfs = state
+        .as_ref()
+        .ok_or("VFS not initialized. Call scan_folder_vfs first.")?;
+
+    let operations = simulated_operations_from_plan(&plan);
+    let root = PathBuf::from(&plan.target_folder);
+    Ok(crate::vfs::dry_run_plan(vfs, &root, operations))
+}
+
 /// Stage a move operation in the VFS
 #[tauri::command]
 pub async fn vfs_stage_move(
@@ -348,6 +303,161 @@ pub async fn vfs_apply_staged(
     })
 }
 
+/// Staged changes awaiting the user's approval
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VfsStagedPreview {
+    /// The staged operations as a plan, in execution order
+    pub plan: OrganizePlan,
+    /// Hash of the staged set; pass it to `vfs_execute_staged` to approve
+    pub staged_hash: String,
+}
+
+/// Preview the staged changes (e.g. staged by the chat agent) for approval
+#[tauri::command]
+pub async fn vfs_preview_staged(
+    vfs_state: State<'_, VFSState>,
+) -> Result<VfsStagedPreview, String> {
+    let state = vfs_state.read().await;
+    let vfs = state
+        .as_ref()
+        .ok_or("VFS not initialized. Call scan_folder_vfs first.")?;
+
+    Ok(VfsStagedPreview {
+        plan: plan_from_staged(vfs, "staged".to_string(), "Staged changes".to_string()),
+        staged_hash: vfs.staged_hash(),
+    })
+}
+
+/// Execute the staged changes on disk once the user has approved them
+///
+/// `staged_hash` must match the preview the user approved; if anything was
+/// staged or cleared since, nothing runs. The changes go through the WAL
+/// like any organize plan and are recorded in history for undo. On success
+/// the staged changes are applied to the VFS; otherwise they are cleared,
+/// since the VFS no longer matches the disk.
+///
+/// The VFS lock is released while the operations run, so the UI can keep
+/// reading it. If the staged changes were modified in the meantime, the VFS
+/// is left untouched afterwards.
+#[tauri::command]
+pub async fn vfs_execute_staged(
+    staged_hash: String,
+    user_instruction: Option<String>,
+    app: AppHandle,
+    vfs_state: State<'_, VFSState>,
+) -> Result<ExecutionResult, String> {
+    let _executing = EXECUTING_STAGED
+        .try_lock()
+        .map_err(|_| "Staged changes are already being executed".to_string())?;
+
+    let (plan, journal) = {
+        let state = vfs_state.read().await;
+        let vfs = state
+            .as_ref()
+            .ok_or("VFS not initialized. Call scan_folder_vfs first.")?;
+
+        if !vfs.has_staged_operations() {
+            return Err("No staged changes to execute".to_string());
+        }
+        if vfs.staged_hash() != staged_hash {
+            return Err(
+                "Staged changes were modified since they were approved. Review them again."
+                    .to_string(),
+            );
+        }
+        vfs.validate_staged().map_err(|errors| {
+            errors
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>()
+                .join("; ")
+        })?;
+
+        let plan = plan_from_staged(
+            vfs,
+            format!("staged-{}", uuid::Uuid::new_v4()),
+            "Staged changes".to_string(),
+        );
+        let journal = journal_from_plan(&plan)?;
+        (plan, journal)
+    };
+
+    let wal_manager = WALManager::new();
+    wal_manager
+        .save_journal(&journal)
+        .map_err(|e| format!("Failed to save WAL journal: {}", e.message))?;
+
+    let app_clone = app.clone();
+    let progress_callback: Arc<ProgressCallback> = Arc::new(Box::new(move |completed, total| {
+        let _ = app_clone.emit(
+            "execution-progress",
+            serde_json::json!({
+                "completed": completed,
+                "total": total
+            }),
+        );
+    }));
+
+    let result = ExecutionEngine::new()
+        .execute_journal_with_progress(&plan.plan_id, Some(progress_callback))
+        .await?;
+
+    eprintln!(
+        "[VFS] Staged changes executed: {} completed, {} failed",
+        result.completed_count, result.failed_count
+    );
+
+    if !result.success {
+        // Leave the journal in place so recovery can resume or roll it back
+        if let Some(vfs) = staged_vfs(&mut *vfs_state.write().await, &staged_hash) {
+            vfs.clear_staged();
+        }
+        return Ok(result);
+    }
+
+    let _ = wal_manager.discard_journal(&plan.plan_id);
+
+    match save_plan_history(
+        &plan,
+        &journal,
+        user_instruction.as_deref().unwrap_or("Staged changes"),
+        result.completed_count,
+    ) {
+        Ok(()) => spawn_folder_snapshot(plan.target_folder.clone()),
+        Err(e) => {
+            tracing::warn!(
+                error = %e,
+                folder = %plan.target_folder,
+                "Failed to save staged changes history (undo will not be available)"
+            );
+        }
+    }
+
+    if let Some(vfs) = staged_vfs(&mut *vfs_state.write().await, &staged_hash) {
+        if let Err(errors) = crate::vfs::apply_all_staged(vfs) {
+            tracing::warn!(errors = errors.len(), "Failed to apply executed changes to the VFS");
+            vfs.clear_staged();
+        }
+    }
+
+    Ok(result)
+}
+
+/// The VFS, if it still holds the staged changes identified by `staged_hash`
+fn staged_vfs<'a>(
+    state: &'a mut Option<ShadowVFS>,
+    staged_hash: &str,
+) -> Option<&'a mut ShadowVFS> {
+    match state.as_mut() {
+        Some(vfs) if vfs.staged_hash() == staged_hash => Some(vfs),
+        _ => {
+            tracing::warn!("Staged changes were modified during execution; VFS left as is");
+            None
+        }
+    }
+}
+
 /// Clear all staged changes without applying
 #[tauri::command]
 pub async fn vfs_clear_staged(
@@ -398,22 +508,21 @@ pub async fn quarantine_item(
     let manager = quarantine_state.read().await;
     let path_buf = PathBuf::from(&path);
 
-    let quarantine_path = manager.quarantine(&path_buf)?;
-    Ok(quarantine_path.to_string_lossy().to_string())
+    let item = manager.quarantine(&path_buf)?;
+    Ok(item.path.to_string_lossy().to_string())
 }
 
 /// Restore a quarantined item to its original location
 #[tauri::command]
 pub async fn quarantine_restore(
-    quarantine_path: String,
+    item_id: String,
     original_path: Option<String>,
     quarantine_state: State<'_, QuarantineState>,
 ) -> Result<(), String> {
     let manager = quarantine_state.read().await;
-    let q_path = PathBuf::from(&quarantine_path);
     let orig_path = original_path.map(PathBuf::from);
 
-    manager.restore(&q_path, orig_path)
+    manager.restore(&item_id, orig_path)
 }
 
 /// List all quarantined items
@@ -437,12 +546,11 @@ pub async fn quarantine_cleanup(
 /// Permanently delete a quarantined item
 #[tauri::command]
 pub async fn quarantine_permanent_delete(
-    quarantine_path: String,
+    item_id: String,
     quarantine_state: State<'_, QuarantineState>,
 ) -> Result<(), String> {
     let manager = quarantine_state.read().await;
-    let path = PathBuf::from(&quarantine_path);
-    manager.permanent_delete(&path)
+    manager.permanent_delete(&item_id)
 }
 
 /// Check if a path is currently in quarantine
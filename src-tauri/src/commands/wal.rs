@@ -91,7 +91,6 @@ pub async fn wal_add_operation(
     destination: Option<String>,
     path: Option<String>,
     new_name: Option<String>,
-    quarantine_path: Option<String>,
     depends_on: Option<Vec<String>>,
 ) -> Result<String, String> {
     let manager = WALManager::new();
@@ -126,11 +125,7 @@ pub async fn wal_add_operation(
         }
         "quarantine" => {
             let path = path.ok_or("path is required for quarantine")?;
-            let qpath = quarantine_path.ok_or("quarantine_path is required for quarantine")?;
-            WALOperationType::Quarantine {
-                path: PathBuf::from(path),
-                quarantine_path: PathBuf::from(qpath),
-            }
+            WALOperationType::quarantine(PathBuf::from(path))?
         }
         "copy" => {
             let source = source.ok_or("source is required for copy")?;
@@ -163,7 +158,11 @@ pub async fn wal_add_operation(
         journal.add_operation_with_deps(operation, deps)?
     };
 
-    manager.save_journal(&journal).map_err(|e| e.message)?;
+    // Append just the new entry rather than rewriting the journal
+    let entry = journal
+        .get_entry(entry_id)
+        .ok_or_else(|| format!("Entry not found: {}", entry_id))?;
+    manager.append_entry(&job_id, entry).map_err(|e| e.message)?;
 
     Ok(entry_id.to_string())
 }
@@ -253,11 +252,7 @@ pub async fn wal_execute_operations(
             }
             "quarantine" => {
                 let path = op.get("path").and_then(|v| v.as_str()).ok_or("path required")?;
-                let qpath = op.get("quarantinePath").and_then(|v| v.as_str()).ok_or("quarantinePath required")?;
-                WALOperationType::Quarantine {
-                    path: PathBuf::from(path),
-                    quarantine_path: PathBuf::from(qpath),
-                }
+                WALOperationType::quarantine(PathBuf::from(path))?
             }
             _ => return Err(format!("Unknown operation type: {}", op_type)),
         };
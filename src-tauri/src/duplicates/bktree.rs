@@ -0,0 +1,128 @@
+//! BK-tree over 64-bit hashes
+//!
+//! Finds every hash within a Hamming distance of a query without comparing
+//! against all of them. Children are keyed by their distance to the parent,
+//! so the triangle inequality prunes whole subtrees.
+
+use crate::services::image_hash::hamming_distance;
+use std::collections::HashMap;
+
+#[derive(Debug)]
+struct Node<T> {
+    hash: u64,
+    /// Items sharing exactly this hash
+    items: Vec<T>,
+    children: HashMap<u32, Node<T>>,
+}
+
+/// Metric tree for Hamming-distance lookups
+#[derive(Debug)]
+pub struct BkTree<T> {
+    root: Option<Node<T>>,
+    len: usize,
+}
+
+impl<T> Default for BkTree<T> {
+    fn default() -> Self {
+        Self { root: None, len: 0 }
+    }
+}
+
+impl<T> BkTree<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn insert(&mut self, hash: u64, item: T) {
+        self.len += 1;
+        let mut node = match &mut self.root {
+            Some(root) => root,
+            None => {
+                self.root = Some(Node {
+                    hash,
+                    items: vec![item],
+                    children: HashMap::new(),
+                });
+                return;
+            }
+        };
+
+        loop {
+            let distance = hamming_distance(node.hash, hash);
+            if distance == 0 {
+                node.items.push(item);
+                return;
+            }
+            node = node.children.entry(distance).or_insert_with(|| Node {
+                hash,
+                items: Vec::new(),
+                children: HashMap::new(),
+            });
+        }
+    }
+
+    /// All items within `max_distance` of `hash`, with their distance
+    pub fn find(&self, hash: u64, max_distance: u32) -> Vec<(&T, u32)> {
+        let mut found = Vec::new();
+        let mut stack: Vec<&Node<T>> = self.root.iter().collect();
+
+        while let Some(node) = stack.pop() {
+            let distance = hamming_distance(node.hash, hash);
+            if distance <= max_distance {
+                found.extend(node.items.iter().map(|item| (item, distance)));
+            }
+            let low = distance.saturating_sub(max_distance);
+            let high = distance + max_distance;
+            stack.extend(
+                node.children
+                    .iter()
+                    .filter(|(d, _)| (low..=high).contains(*d))
+                    .map(|(_, child)| child),
+            );
+        }
+
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_matches_linear_scan() {
+        // Deterministic spread of hashes, including exact repeats
+        let hashes: Vec<u64> = (0..500u64)
+            .map(|i| (i % 450).wrapping_mul(0x9E37_79B9_7F4A_7C15).rotate_left((i % 7) as u32))
+            .collect();
+
+        let mut tree = BkTree::new();
+        for (i, &hash) in hashes.iter().enumerate() {
+            tree.insert(hash, i);
+        }
+        assert_eq!(tree.len(), hashes.len());
+
+        for &query in hashes.iter().step_by(37) {
+            for max_distance in [0, 8, 24] {
+                let mut found: Vec<usize> =
+                    tree.find(query, max_distance).into_iter().map(|(i, _)| *i).collect();
+                found.sort_unstable();
+                let expected: Vec<usize> = hashes
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &h)| hamming_distance(h, query) <= max_distance)
+                    .map(|(i, _)| i)
+                    .collect();
+                assert_eq!(found, expected);
+            }
+        }
+    }
+}
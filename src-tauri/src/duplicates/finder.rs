@@ -0,0 +1,364 @@
+//! Duplicate file finder
+//!
+//! Files are narrowed down in three passes so most of them are never read:
+//! 1. Group by size (metadata only)
+//! 2. Group same-size files by a BLAKE3 pre-hash of their first and last 64KB
+//! 3. Group the remaining candidates by full SHA-256
+//!
+//! Both hashing passes run in parallel, and full hashes come from the shared
+//! digest cache when a file hasn't changed since it was last hashed.
+
+use crate::services::hashing::{self, HashService};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+/// Bytes hashed from each end of a file in the partial hash pass
+const PARTIAL_CHUNK: u64 = hashing::QUICK_HASH_CHUNK;
+
+/// Options for a duplicate scan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DuplicateScanOptions {
+    /// Ignore files smaller than this many bytes
+    pub min_size: u64,
+    /// Include dotfiles and files inside dot-folders
+    pub include_hidden: bool,
+}
+
+impl Default for DuplicateScanOptions {
+    fn default() -> Self {
+        Self {
+            // Empty files are all "identical" but waste nothing
+            min_size: 1,
+            include_hidden: false,
+        }
+    }
+}
+
+/// One copy of a duplicated file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateFile {
+    pub path: String,
+    pub size: u64,
+    /// Modification time as unix timestamp
+    pub modified: u64,
+}
+
+/// A set of files with identical content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateGroup {
+    pub sha256: String,
+    /// Size of each copy in bytes
+    pub size: u64,
+    /// Copies, sorted by path
+    pub files: Vec<DuplicateFile>,
+    /// Bytes reclaimed by keeping only one copy
+    pub wasted_bytes: u64,
+}
+
+/// Result of a duplicate scan
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateReport {
+    /// Roots that were scanned (canonicalized)
+    pub roots: Vec<String>,
+    /// Duplicate groups, most wasted bytes first
+    pub groups: Vec<DuplicateGroup>,
+    /// Regular files considered (after hidden/size filtering)
+    pub files_scanned: usize,
+    /// Bytes read while hashing
+    pub bytes_hashed: u64,
+    /// Total bytes reclaimable across all groups
+    pub wasted_bytes: u64,
+    /// Paths that could not be read
+    pub errors: Vec<String>,
+}
+
+impl DuplicateReport {
+    /// Number of files that would be removed by keeping one copy per group
+    pub fn redundant_count(&self) -> usize {
+        self.groups.iter().map(|g| g.files.len() - 1).sum()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Candidate {
+    pub(super) path: PathBuf,
+    pub(super) size: u64,
+    pub(super) modified: u64,
+}
+
+impl Candidate {
+    pub(super) fn into_file(self) -> DuplicateFile {
+        DuplicateFile {
+            path: self.path.to_string_lossy().to_string(),
+            size: self.size,
+            modified: self.modified,
+        }
+    }
+}
+
+/// Find files with identical content under the given roots
+///
+/// Overlapping roots are fine: every file is considered once. Hard links to
+/// the same inode are not reported, since removing one reclaims no space.
+pub fn find_duplicates(roots: &[PathBuf], options: &DuplicateScanOptions) -> DuplicateReport {
+    let mut report = DuplicateReport::default();
+    let candidates = collect_candidates(roots, options, &mut report.roots, &mut report.errors);
+    report.files_scanned = candidates.len();
+
+    let mut by_size: BTreeMap<u64, Vec<Candidate>> = BTreeMap::new();
+    for candidate in candidates {
+        by_size.entry(candidate.size).or_default().push(candidate);
+    }
+
+    for (size, same_size) in by_size {
+        if same_size.len() < 2 {
+            continue;
+        }
+
+        // Small files are cheaper to hash whole than twice
+        let buckets = if size > PARTIAL_CHUNK * 2 {
+            report.bytes_hashed += PARTIAL_CHUNK * 2 * same_size.len() as u64;
+            let paths: Vec<PathBuf> = same_size.iter().map(|c| c.path.clone()).collect();
+            let keys = hashing::quick_hash_many(&paths);
+            group_by_keyed(same_size, keys, &mut report.errors)
+                .into_iter()
+                .map(|(_, group)| group)
+                .collect()
+        } else {
+            vec![same_size]
+        };
+
+        for bucket in buckets {
+            report.bytes_hashed += size * bucket.len() as u64;
+            let paths: Vec<PathBuf> = bucket.iter().map(|c| c.path.clone()).collect();
+            let keys = HashService::global().sha256_many(&paths);
+            let full = group_by_keyed(bucket, keys, &mut report.errors);
+            for (sha256, mut files) in full {
+                files.sort_by(|a, b| a.path.cmp(&b.path));
+                let wasted_bytes = size * (files.len() as u64 - 1);
+                report.groups.push(DuplicateGroup {
+                    sha256,
+                    size,
+                    files: files.into_iter().map(Candidate::into_file).collect(),
+                    wasted_bytes,
+                });
+            }
+        }
+    }
+
+    report.groups.sort_by(|a, b| {
+        b.wasted_bytes
+            .cmp(&a.wasted_bytes)
+            .then_with(|| a.sha256.cmp(&b.sha256))
+    });
+    report.wasted_bytes = report.groups.iter().map(|g| g.wasted_bytes).sum();
+
+    tracing::info!(
+        files = report.files_scanned,
+        groups = report.groups.len(),
+        wasted_bytes = report.wasted_bytes,
+        "Duplicate scan complete"
+    );
+    report
+}
+
+/// Walk every root and collect regular files that pass the filters
+pub(super) fn collect_candidates(
+    roots: &[PathBuf],
+    options: &DuplicateScanOptions,
+    scanned_roots: &mut Vec<String>,
+    errors: &mut Vec<String>,
+) -> Vec<Candidate> {
+    let mut seen_paths: HashSet<PathBuf> = HashSet::new();
+    let mut seen_inodes: HashSet<(u64, u64)> = HashSet::new();
+    let mut candidates = Vec::new();
+
+    for root in roots {
+        let root = match root.canonicalize() {
+            Ok(root) => root,
+            Err(e) => {
+                errors.push(format!("{}: {}", root.display(), e));
+                continue;
+            }
+        };
+        scanned_roots.push(root.to_string_lossy().to_string());
+
+        let walker = WalkDir::new(&root)
+            .follow_links(false)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || options.include_hidden || !is_hidden(e.path()));
+
+        for entry in walker {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    errors.push(e.to_string());
+                    continue;
+                }
+            };
+            if !entry.file_type().is_file() || !seen_paths.insert(entry.path().to_path_buf()) {
+                continue;
+            }
+
+            let metadata = match entry.metadata() {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    errors.push(e.to_string());
+                    continue;
+                }
+            };
+            if metadata.len() < options.min_size {
+                continue;
+            }
+            if let Some(inode) = inode_key(&metadata) {
+                if !seen_inodes.insert(inode) {
+                    continue;
+                }
+            }
+
+            candidates.push(Candidate {
+                path: entry.into_path(),
+                size: metadata.len(),
+                modified: modified_secs(&metadata),
+            });
+        }
+    }
+
+    candidates
+}
+
+/// Split candidates by their keys (one per candidate, in order), keeping
+/// only groups with more than one member
+fn group_by_keyed(
+    candidates: Vec<Candidate>,
+    keys: Vec<Result<String, String>>,
+    errors: &mut Vec<String>,
+) -> Vec<(String, Vec<Candidate>)> {
+    let mut groups: HashMap<String, Vec<Candidate>> = HashMap::new();
+    for (candidate, key) in candidates.into_iter().zip(keys) {
+        match key {
+            Ok(k) => groups.entry(k).or_default().push(candidate),
+            Err(e) => errors.push(e),
+        }
+    }
+    groups.into_iter().filter(|(_, g)| g.len() > 1).collect()
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .map(|n| n.to_string_lossy().starts_with('.'))
+        .unwrap_or(false)
+}
+
+fn modified_secs(metadata: &Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(unix)]
+fn inode_key(metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn inode_key(_metadata: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn write(dir: &Path, name: &str, content: &[u8]) -> PathBuf {
+        let path = dir.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_groups_identical_content() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        write(root, "a.txt", b"same content");
+        write(root, "sub/b.txt", b"same content");
+        write(root, "c.txt", b"diff content");
+        write(root, "empty1.txt", b"");
+        write(root, "empty2.txt", b"");
+        write(root, ".hidden/d.txt", b"same content");
+
+        let report = find_duplicates(&[root.to_path_buf()], &DuplicateScanOptions::default());
+
+        assert_eq!(report.groups.len(), 1);
+        let group = &report.groups[0];
+        assert_eq!(group.files.len(), 2);
+        assert!(group.files[0].path.ends_with("a.txt"));
+        assert!(group.files[1].path.ends_with("b.txt"));
+        assert_eq!(group.wasted_bytes, 12);
+        assert_eq!(report.wasted_bytes, 12);
+        assert_eq!(report.redundant_count(), 1);
+        assert_eq!(report.files_scanned, 3);
+    }
+
+    #[test]
+    fn test_partial_hash_collision_is_resolved_by_full_hash() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+
+        // Same size, same head and tail, different middle
+        let first = vec![7u8; 3 * PARTIAL_CHUNK as usize];
+        let mut second = first.clone();
+        second[PARTIAL_CHUNK as usize + 10] = 0;
+        write(root, "first.bin", &first);
+        write(root, "second.bin", &second);
+        write(root, "copy.bin", &first);
+
+        let report = find_duplicates(&[root.to_path_buf()], &DuplicateScanOptions::default());
+
+        assert_eq!(report.groups.len(), 1);
+        let names: Vec<&str> = report.groups[0]
+            .files
+            .iter()
+            .map(|f| Path::new(&f.path).file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["copy.bin", "first.bin"]);
+        assert_eq!(report.wasted_bytes, 3 * PARTIAL_CHUNK);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_overlapping_roots_and_hard_links() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let original = write(root, "nested/original.txt", b"linked");
+        write(root, "other.txt", b"unique");
+        fs::hard_link(&original, root.join("nested/link.txt")).unwrap();
+
+        let report = find_duplicates(
+            &[root.to_path_buf(), root.join("nested"), root.join("missing")],
+            &DuplicateScanOptions::default(),
+        );
+
+        assert!(report.groups.is_empty());
+        assert_eq!(report.files_scanned, 2);
+        assert_eq!(report.roots.len(), 2);
+        assert_eq!(report.errors.len(), 1);
+    }
+}
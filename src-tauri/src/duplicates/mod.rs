@@ -0,0 +1,22 @@
+//! Duplicate Detection Module
+//!
+//! Finds files with identical (or, for images, visually similar) content
+//! across one or more roots and turns the result into a deduplication plan.
+//!
+//! - `finder`: Size -> partial hash -> full SHA-256 grouping
+//! - `similar`: Near-duplicate images clustered by perceptual hash
+//! - `bktree`: Hamming-distance index used by `similar`
+//! - `plan`: Keep-one policies and quarantine plan generation
+//!
+//! Plans contain only `quarantine` operations, so they run through the
+//! normal simulate/execute pipeline and can be undone from history.
+
+mod bktree;
+mod finder;
+mod plan;
+mod similar;
+
+pub use bktree::BkTree;
+pub use finder::*;
+pub use plan::*;
+pub use similar::*;
@@ -0,0 +1,283 @@
+//! Deduplication plans
+//!
+//! Picks one copy to keep in every duplicate group and quarantines the rest.
+//! The result is an ordinary `OrganizePlan`, so it is validated with
+//! `vfs_simulate_plan` and executed as WAL `Quarantine` operations.
+
+use super::finder::{DuplicateFile, DuplicateReport};
+use crate::jobs::{OrganizeOperation, OrganizePlan};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+
+/// Which copy of a duplicate group to keep
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "policy", rename_all = "snake_case")]
+pub enum KeepPolicy {
+    /// Keep the least recently modified copy
+    #[default]
+    Oldest,
+    /// Keep the most recently modified copy
+    Newest,
+    /// Keep the copy with the shortest path
+    ShortestPath,
+    /// Keep the largest copy (for near-duplicates, usually the best quality)
+    Largest,
+    /// Keep a copy inside `folder`, falling back to the oldest copy
+    PreferredFolder { folder: String },
+}
+
+impl KeepPolicy {
+    /// Choose the copy to keep. Ties are broken by path so the choice is stable.
+    pub fn choose<'a>(&self, files: &'a [DuplicateFile]) -> Option<&'a DuplicateFile> {
+        match self {
+            KeepPolicy::Oldest => files.iter().min_by(|a, b| oldest_first(a, b)),
+            KeepPolicy::Newest => files.iter().min_by(|a, b| oldest_first(b, a)),
+            KeepPolicy::ShortestPath => files
+                .iter()
+                .min_by(|a, b| a.path.len().cmp(&b.path.len()).then_with(|| a.path.cmp(&b.path))),
+            KeepPolicy::Largest => files
+                .iter()
+                .min_by(|a, b| b.size.cmp(&a.size).then_with(|| oldest_first(a, b))),
+            KeepPolicy::PreferredFolder { folder } => files
+                .iter()
+                .filter(|f| Path::new(&f.path).starts_with(folder))
+                .min_by(|a, b| oldest_first(a, b))
+                .or_else(|| KeepPolicy::Oldest.choose(files)),
+        }
+    }
+
+    /// Canonicalize the preferred folder so it matches the canonical paths
+    /// of scanned files through symlinks and `..` components
+    fn canonicalized(&self) -> KeepPolicy {
+        match self {
+            KeepPolicy::PreferredFolder { folder } => KeepPolicy::PreferredFolder {
+                folder: Path::new(folder)
+                    .canonicalize()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or_else(|_| folder.clone()),
+            },
+            policy => policy.clone(),
+        }
+    }
+}
+
+fn oldest_first(a: &DuplicateFile, b: &DuplicateFile) -> Ordering {
+    a.modified.cmp(&b.modified).then_with(|| a.path.cmp(&b.path))
+}
+
+/// Build a plan that quarantines every copy except the one `policy` keeps
+pub fn deduplication_plan(
+    report: &DuplicateReport,
+    policy: &KeepPolicy,
+) -> Result<OrganizePlan, String> {
+    quarantine_plan(
+        report.groups.iter().map(|g| g.files.as_slice()),
+        &report.roots,
+        policy,
+    )
+}
+
+/// Build a plan that keeps one file per group and quarantines the others
+///
+/// `roots` are the scanned folders; their common ancestor becomes the plan's
+/// target folder. Roots that only share a filesystem root (for example on
+/// different volumes) are rejected, since the plan could not be simulated.
+pub fn quarantine_plan<'a>(
+    groups: impl IntoIterator<Item = &'a [DuplicateFile]>,
+    roots: &[String],
+    policy: &KeepPolicy,
+) -> Result<OrganizePlan, String> {
+    let target_folder = common_ancestor(roots)?;
+    let policy = policy.canonicalized();
+    let mut operations = Vec::new();
+    let mut reclaimed = 0u64;
+
+    for files in groups {
+        let Some(keep) = policy.choose(files) else {
+            continue;
+        };
+        for file in files.iter().filter(|f| f.path != keep.path) {
+            reclaimed += file.size;
+            operations.push(OrganizeOperation {
+                op_id: uuid::Uuid::new_v4().to_string(),
+                op_type: "quarantine".to_string(),
+                source: None,
+                destination: None,
+                path: Some(file.path.clone()),
+                new_name: None,
+            });
+        }
+    }
+
+    Ok(OrganizePlan {
+        plan_id: format!("dedup-{}", chrono::Utc::now().timestamp_millis()),
+        description: format!(
+            "Quarantine {} duplicate file(s) to reclaim {} bytes",
+            operations.len(),
+            reclaimed
+        ),
+        operations,
+        target_folder: target_folder.to_string_lossy().to_string(),
+        simplification_recommended: None,
+    })
+}
+
+/// Deepest folder containing every root
+///
+/// Errors when there are no roots, or when several roots only share a
+/// filesystem root (or nothing at all, like `C:\` and `D:\`).
+fn common_ancestor(roots: &[String]) -> Result<PathBuf, String> {
+    let Some((first, rest)) = roots.split_first() else {
+        return Err("No scanned folders to build a plan for".to_string());
+    };
+    let mut ancestor = PathBuf::from(first);
+    for root in rest {
+        while !Path::new(root).starts_with(&ancestor) {
+            if !ancestor.pop() {
+                break;
+            }
+        }
+    }
+    if !rest.is_empty() && ancestor.parent().is_none() {
+        return Err(format!(
+            "The scanned folders ({}) have no common parent folder; \
+             scan and clean up each volume separately",
+            roots.join(", ")
+        ));
+    }
+    Ok(ancestor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::duplicates::DuplicateGroup;
+    use crate::execution::simulated_operations_from_plan;
+    use crate::vfs::{validate_plan, FileNode, ShadowVFS};
+
+    fn file(path: &str, modified: u64) -> DuplicateFile {
+        DuplicateFile {
+            path: path.to_string(),
+            size: 10,
+            modified,
+        }
+    }
+
+    fn report() -> DuplicateReport {
+        let files = vec![
+            file("/root/archive/2020/photo.jpg", 100),
+            file("/root/b.jpg", 300),
+            file("/root/keep/photo copy.jpg", 200),
+        ];
+        DuplicateReport {
+            roots: vec!["/root/archive".to_string(), "/root/keep".to_string()],
+            groups: vec![DuplicateGroup {
+                sha256: "abc".to_string(),
+                size: 10,
+                files,
+                wasted_bytes: 20,
+            }],
+            wasted_bytes: 20,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_keep_policies() {
+        let files = &report().groups[0].files;
+        let kept = |policy: KeepPolicy| policy.choose(files).unwrap().path.clone();
+
+        assert_eq!(kept(KeepPolicy::Oldest), "/root/archive/2020/photo.jpg");
+        assert_eq!(kept(KeepPolicy::Newest), "/root/b.jpg");
+        assert_eq!(kept(KeepPolicy::ShortestPath), "/root/b.jpg");
+        assert_eq!(
+            kept(KeepPolicy::PreferredFolder {
+                folder: "/root/keep".to_string()
+            }),
+            "/root/keep/photo copy.jpg"
+        );
+        // No copy in the preferred folder: fall back to oldest
+        assert_eq!(
+            kept(KeepPolicy::PreferredFolder {
+                folder: "/elsewhere".to_string()
+            }),
+            "/root/archive/2020/photo.jpg"
+        );
+
+        // Same size: oldest wins
+        assert_eq!(kept(KeepPolicy::Largest), "/root/archive/2020/photo.jpg");
+        let mut resized = files.clone();
+        resized[2].size = 40;
+        assert_eq!(
+            KeepPolicy::Largest.choose(&resized).unwrap().path,
+            "/root/keep/photo copy.jpg"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_preferred_folder_is_canonicalized() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        std::fs::create_dir(root.join("keep")).unwrap();
+        std::os::unix::fs::symlink(root.join("keep"), root.join("link")).unwrap();
+        let path = |name: &str| root.join(name).to_string_lossy().to_string();
+        let files = vec![file(&path("old.jpg"), 100), file(&path("keep/new.jpg"), 200)];
+
+        for folder in [path("link"), path("keep/../keep")] {
+            let plan = quarantine_plan(
+                [files.as_slice()],
+                &[root.to_string_lossy().to_string()],
+                &KeepPolicy::PreferredFolder { folder },
+            )
+            .unwrap();
+            assert_eq!(plan.operations.len(), 1);
+            assert_eq!(plan.operations[0].path, Some(path("old.jpg")));
+        }
+    }
+
+    #[test]
+    fn test_plan_quarantines_all_but_one_and_validates() {
+        let report = report();
+        let plan = deduplication_plan(&report, &KeepPolicy::Newest).unwrap();
+
+        assert_eq!(plan.target_folder, "/root");
+        assert_eq!(plan.operations.len(), 2);
+        assert!(plan.operations.iter().all(|op| op.op_type == "quarantine"));
+        let quarantined: Vec<_> = plan
+            .operations
+            .iter()
+            .map(|op| op.path.clone().unwrap())
+            .collect();
+        assert!(!quarantined.contains(&"/root/b.jpg".to_string()));
+
+        let mut vfs = ShadowVFS::new(PathBuf::from("/root"));
+        for folder in ["/root/archive", "/root/archive/2020", "/root/keep"] {
+            vfs.insert_linked(FileNode::directory(PathBuf::from(folder)));
+        }
+        for f in &report.groups[0].files {
+            vfs.insert_linked(FileNode::file(PathBuf::from(&f.path)));
+        }
+
+        let operations = simulated_operations_from_plan(&plan);
+        assert_eq!(operations.len(), 2);
+        let validation = validate_plan(&vfs, operations);
+        assert!(validation.errors.is_empty(), "{:?}", validation.errors);
+    }
+
+    #[test]
+    fn test_roots_without_common_folder_are_rejected() {
+        let mut report = report();
+        report.roots = vec!["/mnt/usb".to_string(), "/home/me".to_string()];
+        let err = deduplication_plan(&report, &KeepPolicy::Oldest).unwrap_err();
+        assert!(err.contains("/mnt/usb"));
+
+        report.roots.clear();
+        assert!(deduplication_plan(&report, &KeepPolicy::Oldest).is_err());
+
+        report.roots = vec!["/mnt/usb/a".to_string(), "/mnt/usb/b".to_string()];
+        let plan = deduplication_plan(&report, &KeepPolicy::Oldest).unwrap();
+        assert_eq!(plan.target_folder, "/mnt/usb");
+    }
+}
@@ -0,0 +1,193 @@
+//! Near-duplicate image detection
+//!
+//! Groups images whose perceptual hashes are within a Hamming distance of
+//! each other: burst shots, resized copies and re-encoded versions of the
+//! same photo. Clusters feed the same quarantine plan as exact duplicates.
+
+use super::bktree::BkTree;
+use super::finder::{collect_candidates, DuplicateFile, DuplicateScanOptions};
+use super::plan::{quarantine_plan, KeepPolicy};
+use crate::jobs::OrganizePlan;
+use crate::services::image_hash::{get_image_hashes, is_hashable, HashAlgorithm};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Options for a near-duplicate image scan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SimilarImageOptions {
+    pub algorithm: HashAlgorithm,
+    /// Largest Hamming distance (out of 64 bits) still considered similar
+    pub max_distance: u32,
+    /// Include dotfiles and files inside dot-folders
+    pub include_hidden: bool,
+}
+
+impl Default for SimilarImageOptions {
+    fn default() -> Self {
+        Self {
+            algorithm: HashAlgorithm::default(),
+            max_distance: 10,
+            include_hidden: false,
+        }
+    }
+}
+
+/// Images that look alike
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageCluster {
+    /// Members, sorted by path
+    pub files: Vec<DuplicateFile>,
+    /// Largest distance between the first-found member and any other
+    pub max_distance: u32,
+}
+
+/// Result of a near-duplicate image scan
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimilarImageReport {
+    /// Roots that were scanned (canonicalized)
+    pub roots: Vec<String>,
+    /// Clusters, largest first
+    pub clusters: Vec<ImageCluster>,
+    /// Images that were hashed
+    pub images_scanned: usize,
+    /// Paths that could not be read or decoded
+    pub errors: Vec<String>,
+}
+
+/// Find clusters of visually similar images under the given roots
+pub fn find_similar_images(roots: &[PathBuf], options: &SimilarImageOptions) -> SimilarImageReport {
+    let mut report = SimilarImageReport::default();
+    let scan_options = DuplicateScanOptions {
+        include_hidden: options.include_hidden,
+        ..Default::default()
+    };
+
+    let mut candidates = collect_candidates(roots, &scan_options, &mut report.roots, &mut report.errors);
+    candidates.retain(|c| is_hashable(&c.path));
+    candidates.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut images = Vec::with_capacity(candidates.len());
+    for candidate in candidates {
+        match get_image_hashes(&candidate.path) {
+            Ok(hashes) => images.push((hashes.get(options.algorithm), candidate.into_file())),
+            Err(e) => report.errors.push(e),
+        }
+    }
+    report.images_scanned = images.len();
+    report.clusters = cluster_images(images, options.max_distance);
+
+    tracing::info!(
+        images = report.images_scanned,
+        clusters = report.clusters.len(),
+        "Similar image scan complete"
+    );
+    report
+}
+
+/// Build a plan that keeps one image per cluster and quarantines the others
+pub fn similar_images_plan(
+    report: &SimilarImageReport,
+    policy: &KeepPolicy,
+) -> Result<OrganizePlan, String> {
+    quarantine_plan(
+        report.clusters.iter().map(|c| c.files.as_slice()),
+        &report.roots,
+        policy,
+    )
+}
+
+/// Cluster hashed images around leaders
+///
+/// Each unassigned image (in input order) claims every unassigned image
+/// within `max_distance` of it. Unlike transitive grouping, a long chain of
+/// slightly different shots never merges two unrelated photos.
+fn cluster_images(images: Vec<(u64, DuplicateFile)>, max_distance: u32) -> Vec<ImageCluster> {
+    let mut tree = BkTree::new();
+    for (i, (hash, _)) in images.iter().enumerate() {
+        tree.insert(*hash, i);
+    }
+
+    let mut assigned = vec![false; images.len()];
+    let mut clusters = Vec::new();
+
+    for leader in 0..images.len() {
+        if assigned[leader] {
+            continue;
+        }
+        let mut members: Vec<(usize, u32)> = tree
+            .find(images[leader].0, max_distance)
+            .into_iter()
+            .filter(|(i, _)| !assigned[**i])
+            .map(|(i, distance)| (*i, distance))
+            .collect();
+        for (i, _) in &members {
+            assigned[*i] = true;
+        }
+        if members.len() < 2 {
+            continue;
+        }
+
+        members.sort_unstable();
+        clusters.push(ImageCluster {
+            max_distance: members.iter().map(|(_, d)| *d).max().unwrap_or(0),
+            files: members.iter().map(|(i, _)| images[*i].1.clone()).collect(),
+        });
+    }
+
+    clusters.sort_by(|a, b| {
+        b.files
+            .len()
+            .cmp(&a.files.len())
+            .then_with(|| a.files[0].path.cmp(&b.files[0].path))
+    });
+    clusters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(path: &str, size: u64) -> DuplicateFile {
+        DuplicateFile {
+            path: path.to_string(),
+            size,
+            modified: 0,
+        }
+    }
+
+    #[test]
+    fn test_cluster_images_around_leaders() {
+        let images = vec![
+            (0b0000, image("/p/burst_1.jpg", 100)),
+            (0b0001, image("/p/burst_2.jpg", 120)),
+            (0b0011, image("/p/burst_3.jpg", 110)),
+            // Within 2 of burst_3 but 4 away from the leader
+            (0b1111, image("/p/drifted.jpg", 90)),
+            (u64::MAX, image("/p/other.jpg", 80)),
+        ];
+
+        let clusters = cluster_images(images, 2);
+
+        assert_eq!(clusters.len(), 1);
+        let paths: Vec<&str> = clusters[0].files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["/p/burst_1.jpg", "/p/burst_2.jpg", "/p/burst_3.jpg"]);
+        assert_eq!(clusters[0].max_distance, 2);
+
+        let report = SimilarImageReport {
+            roots: vec!["/p".to_string()],
+            clusters,
+            ..Default::default()
+        };
+        let plan = similar_images_plan(&report, &KeepPolicy::Largest).unwrap();
+        let quarantined: Vec<_> = plan
+            .operations
+            .iter()
+            .map(|op| op.path.clone().unwrap())
+            .collect();
+        assert_eq!(quarantined, vec!["/p/burst_1.jpg", "/p/burst_3.jpg"]);
+        assert_eq!(plan.target_folder, "/p");
+    }
+}
@@ -3,6 +3,7 @@
 //! Executes WAL operations using the DAG-based dependency graph.
 //! Operations at the same level are executed in parallel using tokio tasks.
 
+use crate::quarantine::{move_path, QuarantineManager};
 use crate::security::{cycle_detection, PathValidator};
 use crate::wal::entry::{WALEntry, WALJournal, WALOperationType, WALStatus};
 use crate::wal::journal::WALManager;
@@ -46,7 +47,7 @@ fn get_affected_directories(operation: &WALOperationType) -> Vec<String> {
                 vec![]
             }
         }
-        WALOperationType::Quarantine { path, .. } => {
+        WALOperationType::Quarantine { path, .. } | WALOperationType::Restore { path, .. } => {
             if let Some(parent) = path.parent() {
                 vec![parent.to_string_lossy().to_string()]
             } else {
@@ -85,7 +86,7 @@ const MAX_CONCURRENT_OPS: usize = 10;
 const PROGRESS_BATCH_SIZE: usize = 5;
 
 /// Policy for handling destination conflicts during execution
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ConflictPolicy {
     /// Return error (current/default behavior)
@@ -97,6 +98,18 @@ pub enum ConflictPolicy {
     AutoRename,
 }
 
+impl ConflictPolicy {
+    /// Parse a policy name (`fail`, `skip`, `auto_rename`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "fail" => Some(Self::Fail),
+            "skip" => Some(Self::Skip),
+            "auto_rename" => Some(Self::AutoRename),
+            _ => None,
+        }
+    }
+}
+
 /// Configuration for execution behavior
 #[derive(Debug, Clone, Default)]
 pub struct ExecutionConfig {
@@ -760,7 +773,11 @@ fn execute_operation_sync_with_config(
             Ok(ExecutionOutcome::Completed)
         }
 
-        WALOperationType::Quarantine { path, quarantine_path } => {
+        WALOperationType::Quarantine {
+            path,
+            quarantine_path,
+            item_id: None,
+        } => {
             execute_operation_sync_with_config(
                 &WALOperationType::Move {
                     source: path.clone(),
@@ -770,6 +787,11 @@ fn execute_operation_sync_with_config(
             )
         }
 
+        WALOperationType::Quarantine { .. } | WALOperationType::Restore { .. } => {
+            execute_managed_quarantine(operation)?;
+            Ok(ExecutionOutcome::Completed)
+        }
+
         WALOperationType::Copy { source, destination } => {
             if !source.exists() {
                 return Err(format!("Source not found: {}", source.display()));
@@ -851,20 +873,33 @@ fn perform_move(source: &Path, destination: &Path) -> Result<(), String> {
         }
     }
 
-    // Try rename first (same filesystem), fall back to copy+delete
-    if fs::rename(source, destination).is_err() {
-        if source.is_dir() {
-            copy_dir_all(source, destination)?;
-            fs::remove_dir_all(source)
-                .map_err(|e| format!("Failed to remove source: {}", e))?;
-        } else {
-            fs::copy(source, destination)
-                .map_err(|e| format!("Failed to copy: {}", e))?;
-            fs::remove_file(source)
-                .map_err(|e| format!("Failed to remove source: {}", e))?;
+    // Rename, or copy, verify and delete across filesystems
+    move_path(source, destination)
+}
+
+/// Run a quarantine or restore through the quarantine manager
+fn execute_managed_quarantine(operation: &WALOperationType) -> Result<(), String> {
+    match operation {
+        WALOperationType::Quarantine {
+            path,
+            item_id: Some(item_id),
+            ..
+        } => {
+            if PathValidator::is_protected_path(path) {
+                return Err(format!("Cannot quarantine protected path: {}", path.display()));
+            }
+            QuarantineManager::new()?
+                .quarantine_with_id(path, item_id)
+                .map(|_| ())
+        }
+        WALOperationType::Restore { item_id, path, .. } => {
+            QuarantineManager::new()?.restore_to(item_id, path)
         }
+        _ => Err(format!(
+            "Not a managed quarantine operation: {}",
+            operation.description()
+        )),
     }
-    Ok(())
 }
 
 /// Helper function to perform a copy operation
@@ -924,21 +959,8 @@ fn execute_operation_sync(operation: &WALOperationType) -> Result<(), String> {
                 }
             }
 
-            // Try rename first (same filesystem), fall back to copy+delete
-            if fs::rename(source, destination).is_err() {
-                if source.is_dir() {
-                    copy_dir_all(source, destination)?;
-                    fs::remove_dir_all(source)
-                        .map_err(|e| format!("Failed to remove source: {}", e))?;
-                } else {
-                    fs::copy(source, destination)
-                        .map_err(|e| format!("Failed to copy: {}", e))?;
-                    fs::remove_file(source)
-                        .map_err(|e| format!("Failed to remove source: {}", e))?;
-                }
-            }
-
-            Ok(())
+            // Rename, or copy, verify and delete across filesystems
+            move_path(source, destination)
         }
 
         WALOperationType::Rename { path, new_name } => {
@@ -966,6 +988,7 @@ fn execute_operation_sync(operation: &WALOperationType) -> Result<(), String> {
         WALOperationType::Quarantine {
             path,
             quarantine_path,
+            item_id: None,
         } => {
             execute_operation_sync(&WALOperationType::Move {
                 source: path.clone(),
@@ -973,6 +996,10 @@ fn execute_operation_sync(operation: &WALOperationType) -> Result<(), String> {
             })
         }
 
+        WALOperationType::Quarantine { .. } | WALOperationType::Restore { .. } => {
+            execute_managed_quarantine(operation)
+        }
+
         WALOperationType::Copy {
             source,
             destination,
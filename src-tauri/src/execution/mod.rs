@@ -9,14 +9,21 @@
 //! The `state_validator` submodule provides tools for validating that filesystem
 //! state matches expected state from VFS simulation before execution. This prevents
 //! issues where files have been modified between planning and execution.
+//!
+//! # Plan Conversion
+//!
+//! The `plan` submodule converts an `OrganizePlan` into VFS simulation
+//! operations and a WAL journal, shared by the Tauri commands and the CLI.
 
 #![allow(dead_code)]
 #![allow(unused_imports)]
 
 pub mod dag;
 pub mod executor;
+pub mod plan;
 pub mod state_validator;
 
 pub use dag::*;
 pub use executor::*;
+pub use plan::*;
 pub use state_validator::*;
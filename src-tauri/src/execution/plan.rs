@@ -0,0 +1,515 @@
+//! Organize plan conversion
+//!
+//! Turns an `OrganizePlan` into the structures the rest of the pipeline works
+//! with: simulated operations for VFS validation and a WAL journal for
+//! execution. Shared by the Tauri commands and the headless CLI so both
+//! interpret plans identically. Operations staged on the shadow VFS are
+//! turned into a plan the same way, so they execute through the WAL too.
+
+use crate::jobs::{OrganizeOperation, OrganizePlan};
+use crate::vfs::{ShadowVFS, SimulatedOperation};
+use crate::wal::entry::{WALJournal, WALOperationType};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Convert plan operations into VFS simulation operations.
+///
+/// Operations the simulator does not model (or that are missing required
+/// fields) are skipped; execution reports those when building the journal.
+pub fn simulated_operations_from_plan(plan: &OrganizePlan) -> Vec<SimulatedOperation> {
+    plan.operations.iter().filter_map(simulated_operation).collect()
+}
+
+/// Convert one plan operation into a VFS simulation operation.
+pub fn simulated_operation(op: &OrganizeOperation) -> Option<SimulatedOperation> {
+    match op.op_type.as_str() {
+        "move" => {
+            let src = op.source.as_ref()?;
+            let dest = op.destination.as_ref()?;
+            Some(SimulatedOperation::Move {
+                source: src.clone(),
+                destination: dest.clone(),
+            })
+        }
+        "create_folder" => {
+            let path = op.path.as_ref()?;
+            Some(SimulatedOperation::CreateFolder { path: path.clone() })
+        }
+        "delete" | "trash" | "quarantine" => {
+            let path = op.path.as_ref().or(op.source.as_ref())?;
+            Some(SimulatedOperation::Delete { path: path.clone() })
+        }
+        "rename" => {
+            // Rename is a move to the same directory with a new name
+            let path = op.path.as_ref()?;
+            let new_name = op.new_name.as_ref()?;
+            let path_buf = PathBuf::from(path);
+            let new_path = path_buf
+                .parent()
+                .map(|p| p.join(new_name))
+                .unwrap_or_else(|| PathBuf::from(new_name));
+            Some(SimulatedOperation::Move {
+                source: path.clone(),
+                destination: new_path.to_string_lossy().to_string(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Path an operation takes its item from and the path it leaves it at
+fn operation_paths(op: &OrganizeOperation) -> (Option<PathBuf>, Option<PathBuf>) {
+    match op.op_type.as_str() {
+        "move" => (
+            op.source.as_ref().map(PathBuf::from),
+            op.destination.as_ref().map(PathBuf::from),
+        ),
+        "rename" => {
+            let path = op.path.as_ref().map(PathBuf::from);
+            let renamed = match (&path, &op.new_name) {
+                (Some(path), Some(new_name)) => path.parent().map(|p| p.join(new_name)),
+                _ => None,
+            };
+            (path, renamed)
+        }
+        "delete" | "trash" | "quarantine" => {
+            (op.path.as_ref().or(op.source.as_ref()).map(PathBuf::from), None)
+        }
+        _ => (None, None),
+    }
+}
+
+/// Indices of the operations each operation has to wait for.
+///
+/// An operation on a folder waits for the operations taking items out of
+/// it, and a move waits for the operation vacating its destination.
+fn path_dependencies(operations: &[OrganizeOperation]) -> Vec<Vec<usize>> {
+    let paths: Vec<_> = operations.iter().map(operation_paths).collect();
+    let sources: HashMap<&Path, usize> = paths
+        .iter()
+        .enumerate()
+        .filter_map(|(index, (source, _))| source.as_deref().map(|source| (source, index)))
+        .collect();
+
+    let mut dependencies = vec![Vec::new(); operations.len()];
+    for (index, (source, target)) in paths.iter().enumerate() {
+        if let Some(source) = source {
+            for ancestor in source.ancestors().skip(1) {
+                if let Some(&outer) = sources.get(ancestor) {
+                    dependencies[outer].push(index);
+                }
+            }
+        }
+        if let Some(&vacating) = target.as_deref().and_then(|target| sources.get(target)) {
+            if vacating != index {
+                dependencies[index].push(vacating);
+            }
+        }
+    }
+    dependencies
+}
+
+/// Build a WAL journal from a plan.
+///
+/// The journal uses the plan ID as its job ID. Moves into a folder created
+/// earlier in the plan depend on that folder's creation. Operations on a
+/// folder depend on the operations moving or quarantining items inside it,
+/// and moves depend on whatever vacates their destination.
+pub fn journal_from_plan(plan: &OrganizePlan) -> Result<WALJournal, String> {
+    let target_folder = PathBuf::from(&plan.target_folder);
+    let mut journal = WALJournal::new(plan.plan_id.clone(), target_folder);
+
+    // Track folder creation operations for dependencies
+    let mut folder_op_ids: HashMap<String, uuid::Uuid> = HashMap::new();
+
+    // Convert operations to WAL entries with dependencies
+    for op in &plan.operations {
+        let wal_op = match op.op_type.as_str() {
+            "create_folder" => {
+                let path = op.path.as_ref().ok_or_else(|| {
+                    format!(
+                        "Operation '{}' (create_folder) missing required field 'path'",
+                        op.op_id
+                    )
+                })?;
+                WALOperationType::CreateFolder {
+                    path: PathBuf::from(path),
+                }
+            }
+            "move" => {
+                let src = op.source.as_ref().ok_or_else(|| {
+                    format!(
+                        "Operation '{}' (move) missing required field 'source'",
+                        op.op_id
+                    )
+                })?;
+                let dst = op.destination.as_ref().ok_or_else(|| {
+                    format!(
+                        "Operation '{}' (move) missing required field 'destination'",
+                        op.op_id
+                    )
+                })?;
+                WALOperationType::Move {
+                    source: PathBuf::from(src),
+                    destination: PathBuf::from(dst),
+                }
+            }
+            "rename" => {
+                let path = op.path.as_ref().ok_or_else(|| {
+                    format!(
+                        "Operation '{}' (rename) missing required field 'path'",
+                        op.op_id
+                    )
+                })?;
+                let new_name = op.new_name.as_ref().ok_or_else(|| {
+                    format!(
+                        "Operation '{}' (rename) missing required field 'newName'",
+                        op.op_id
+                    )
+                })?;
+                WALOperationType::Rename {
+                    path: PathBuf::from(path),
+                    new_name: new_name.clone(),
+                }
+            }
+            "trash" | "quarantine" => {
+                let path = op.path.as_ref().ok_or_else(|| {
+                    format!(
+                        "Operation '{}' ({}) missing required field 'path'",
+                        op.op_id, op.op_type
+                    )
+                })?;
+                WALOperationType::quarantine(PathBuf::from(path))?
+            }
+            unknown_type => {
+                return Err(format!(
+                    "Operation '{}' has unknown type '{}'",
+                    op.op_id, unknown_type
+                ));
+            }
+        };
+
+        // Moves depend on their destination folder being created
+        let mut depends_on = Vec::new();
+        if let WALOperationType::Move { destination, .. } = &wal_op {
+            if let Some(parent) = destination.parent() {
+                let parent_str = parent.to_string_lossy().to_string();
+                if let Some(&folder_op_id) = folder_op_ids.get(&parent_str) {
+                    depends_on.push(folder_op_id);
+                }
+            }
+        }
+
+        let created_folder = match &wal_op {
+            WALOperationType::CreateFolder { path } => Some(path.to_string_lossy().to_string()),
+            _ => None,
+        };
+
+        let op_id = if depends_on.is_empty() {
+            journal.add_operation(wal_op)
+        } else {
+            journal.add_operation_with_deps(wal_op, depends_on)
+        }
+        .map_err(|e| format!("Failed to add operation: {}", e))?;
+
+        // Track folder creation for dependency resolution
+        if let Some(path_str) = created_folder {
+            folder_op_ids.insert(path_str, op_id);
+        }
+    }
+
+    // Every plan operation added exactly one entry, in order
+    let ids: Vec<uuid::Uuid> = journal.entries.iter().map(|entry| entry.id).collect();
+    for (entry, waits_for) in journal
+        .entries
+        .iter_mut()
+        .zip(path_dependencies(&plan.operations))
+    {
+        for index in waits_for {
+            if !entry.depends_on.contains(&ids[index]) {
+                entry.depends_on.push(ids[index]);
+            }
+        }
+    }
+
+    Ok(journal)
+}
+
+/// Order operations so each comes after the ones it depends on, keeping
+/// the given order otherwise; operations caught in a dependency cycle are
+/// left at the end in their original order
+fn order_by_dependencies(operations: Vec<OrganizeOperation>) -> Vec<OrganizeOperation> {
+    let dependencies = path_dependencies(&operations);
+    let mut waiting: Vec<usize> = dependencies.iter().map(Vec::len).collect();
+    let mut dependents = vec![Vec::new(); operations.len()];
+    for (index, waits_for) in dependencies.iter().enumerate() {
+        for &dependency in waits_for {
+            dependents[dependency].push(index);
+        }
+    }
+
+    let mut ready: BTreeSet<usize> =
+        (0..operations.len()).filter(|&index| waiting[index] == 0).collect();
+    let mut order = Vec::with_capacity(operations.len());
+    while let Some(index) = ready.pop_first() {
+        order.push(index);
+        for &dependent in &dependents[index] {
+            waiting[dependent] -= 1;
+            if waiting[dependent] == 0 {
+                ready.insert(dependent);
+            }
+        }
+    }
+    let ordered: HashSet<usize> = order.iter().copied().collect();
+    order.extend((0..operations.len()).filter(|index| !ordered.contains(index)));
+
+    let mut slots: Vec<Option<OrganizeOperation>> = operations.into_iter().map(Some).collect();
+    order.into_iter().filter_map(|index| slots[index].take()).collect()
+}
+
+/// Build a plan from the operations staged on a shadow VFS.
+///
+/// Folder creations come first (parents before children), then moves, then
+/// quarantines, except that items are taken out of a folder before the
+/// folder itself is moved or quarantined and a path is vacated before
+/// something moves into it. A move that keeps the parent folder becomes a
+/// rename.
+pub fn plan_from_staged(vfs: &ShadowVFS, plan_id: String, description: String) -> OrganizePlan {
+    let mut creates: Vec<&PathBuf> = vfs.staged_creates().iter().collect();
+    let mut moves: Vec<(&PathBuf, &PathBuf)> = vfs.staged_moves().iter().collect();
+    let mut deletes: Vec<&PathBuf> = vfs.staged_deletes().iter().collect();
+    creates.sort();
+    moves.sort();
+    deletes.sort();
+
+    let operation = |op_type: &str| OrganizeOperation {
+        op_id: uuid::Uuid::new_v4().to_string(),
+        op_type: op_type.to_string(),
+        source: None,
+        destination: None,
+        path: None,
+        new_name: None,
+    };
+
+    let mut operations = Vec::new();
+    for path in creates {
+        operations.push(OrganizeOperation {
+            path: Some(path.to_string_lossy().to_string()),
+            ..operation("create_folder")
+        });
+    }
+    for (source, destination) in moves {
+        let new_name = destination.file_name().map(|n| n.to_string_lossy().to_string());
+        match new_name {
+            Some(new_name) if source.parent() == destination.parent() => {
+                operations.push(OrganizeOperation {
+                    path: Some(source.to_string_lossy().to_string()),
+                    new_name: Some(new_name),
+                    ..operation("rename")
+                });
+            }
+            _ => operations.push(OrganizeOperation {
+                source: Some(source.to_string_lossy().to_string()),
+                destination: Some(destination.to_string_lossy().to_string()),
+                ..operation("move")
+            }),
+        }
+    }
+    for path in deletes {
+        operations.push(OrganizeOperation {
+            path: Some(path.to_string_lossy().to_string()),
+            ..operation("quarantine")
+        });
+    }
+
+    OrganizePlan {
+        plan_id,
+        description,
+        operations: order_by_dependencies(operations),
+        target_folder: vfs.root().to_string_lossy().to_string(),
+        simplification_recommended: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::OrganizeOperation;
+
+    fn op(op_id: &str, op_type: &str) -> OrganizeOperation {
+        OrganizeOperation {
+            op_id: op_id.to_string(),
+            op_type: op_type.to_string(),
+            source: None,
+            destination: None,
+            path: None,
+            new_name: None,
+        }
+    }
+
+    fn plan(operations: Vec<OrganizeOperation>) -> OrganizePlan {
+        OrganizePlan {
+            plan_id: "plan-1".to_string(),
+            description: "test".to_string(),
+            operations,
+            target_folder: "/root".to_string(),
+            simplification_recommended: None,
+        }
+    }
+
+    #[test]
+    fn test_journal_move_depends_on_created_folder() {
+        let mut create = op("1", "create_folder");
+        create.path = Some("/root/docs".to_string());
+        let mut mv = op("2", "move");
+        mv.source = Some("/root/a.txt".to_string());
+        mv.destination = Some("/root/docs/a.txt".to_string());
+
+        let journal = journal_from_plan(&plan(vec![create, mv])).unwrap();
+        assert_eq!(journal.job_id, "plan-1");
+        assert_eq!(journal.entries.len(), 2);
+        assert_eq!(journal.entries[1].depends_on, vec![journal.entries[0].id]);
+    }
+
+    #[test]
+    fn test_journal_rejects_unknown_type() {
+        let err = journal_from_plan(&plan(vec![op("1", "explode")])).unwrap_err();
+        assert!(err.contains("unknown type"));
+    }
+
+    #[test]
+    fn test_simulated_rename_becomes_move() {
+        let mut rename = op("1", "rename");
+        rename.path = Some("/root/a.txt".to_string());
+        rename.new_name = Some("b.txt".to_string());
+
+        let ops = simulated_operations_from_plan(&plan(vec![rename, op("2", "move")]));
+        assert_eq!(ops.len(), 1);
+        match &ops[0] {
+            SimulatedOperation::Move {
+                source,
+                destination,
+            } => {
+                assert_eq!(source, "/root/a.txt");
+                assert_eq!(destination, "/root/b.txt");
+            }
+            other => panic!("unexpected operation: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_plan_from_staged() {
+        use crate::vfs::FileNode;
+
+        let mut vfs = ShadowVFS::new(PathBuf::from("/root"));
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            vfs.insert_linked(FileNode::file(PathBuf::from("/root").join(name)));
+        }
+        vfs.stage_create_folder_all(&PathBuf::from("/root/x/y")).unwrap();
+        vfs.stage_move(PathBuf::from("/root/a.txt"), PathBuf::from("/root/x/y/a.txt"))
+            .unwrap();
+        vfs.stage_move(PathBuf::from("/root/b.txt"), PathBuf::from("/root/d.txt"))
+            .unwrap();
+        vfs.stage_delete(PathBuf::from("/root/c.txt")).unwrap();
+
+        let plan = plan_from_staged(&vfs, "chat-1".to_string(), "test".to_string());
+        assert_eq!(plan.target_folder, "/root");
+        let types: Vec<&str> = plan.operations.iter().map(|op| op.op_type.as_str()).collect();
+        assert_eq!(types, ["create_folder", "create_folder", "move", "rename", "quarantine"]);
+        assert_eq!(plan.operations[0].path.as_deref(), Some("/root/x"));
+        assert_eq!(plan.operations[3].new_name.as_deref(), Some("d.txt"));
+
+        // Folders are created before the move into them
+        let journal = journal_from_plan(&plan).unwrap();
+        assert_eq!(journal.entries[2].depends_on, vec![journal.entries[1].id]);
+    }
+
+    #[test]
+    fn test_staged_items_leave_a_folder_before_it_moves() {
+        use crate::vfs::FileNode;
+
+        let mut vfs = ShadowVFS::new(PathBuf::from("/root"));
+        for path in ["/root/Inbox", "/root/Clients", "/root/A"] {
+            vfs.insert_linked(FileNode::directory(PathBuf::from(path)));
+        }
+        for path in ["/root/Inbox/contract.pdf", "/root/A/y"] {
+            vfs.insert_linked(FileNode::file(PathBuf::from(path)));
+        }
+        vfs.stage_move(PathBuf::from("/root/Inbox"), PathBuf::from("/root/Archive"))
+            .unwrap();
+        vfs.stage_move(
+            PathBuf::from("/root/Inbox/contract.pdf"),
+            PathBuf::from("/root/Clients/contract.pdf"),
+        )
+        .unwrap();
+        vfs.stage_move(PathBuf::from("/root/A"), PathBuf::from("/root/B"))
+            .unwrap();
+        vfs.stage_delete(PathBuf::from("/root/A/y")).unwrap();
+
+        let plan = plan_from_staged(&vfs, "chat-2".to_string(), "test".to_string());
+        // Moves within a folder become renames, so look operations up by
+        // the path they take their item from
+        let position = |path: &str| {
+            plan.operations
+                .iter()
+                .position(|op| {
+                    op.source.as_deref() == Some(path) || op.path.as_deref() == Some(path)
+                })
+                .unwrap()
+        };
+        assert!(position("/root/Inbox/contract.pdf") < position("/root/Inbox"));
+        assert!(position("/root/A/y") < position("/root/A"));
+
+        // The journal makes each folder move wait for what leaves it
+        let journal = journal_from_plan(&plan).unwrap();
+        let entry = |index: usize| &journal.entries[index];
+        let inbox = entry(position("/root/Inbox"));
+        assert!(inbox
+            .depends_on
+            .contains(&entry(position("/root/Inbox/contract.pdf")).id));
+        let folder_a = entry(position("/root/A"));
+        assert!(folder_a.depends_on.contains(&entry(position("/root/A/y")).id));
+    }
+
+    #[tokio::test]
+    async fn test_staged_plan_journal_executes() {
+        use crate::execution::ExecutionEngine;
+        use crate::vfs::FileNode;
+        use crate::wal::journal::WALManager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("files");
+        for folder in ["Inbox", "Clients"] {
+            std::fs::create_dir_all(root.join(folder)).unwrap();
+        }
+        std::fs::write(root.join("Inbox/contract.pdf"), b"contract").unwrap();
+        std::fs::write(root.join("Inbox/memo.txt"), b"memo").unwrap();
+
+        let mut vfs = ShadowVFS::new(root.clone());
+        for folder in ["Inbox", "Clients"] {
+            vfs.insert_linked(FileNode::directory(root.join(folder)));
+        }
+        for file in ["Inbox/contract.pdf", "Inbox/memo.txt"] {
+            vfs.insert_linked(FileNode::file(root.join(file)));
+        }
+        vfs.stage_move(root.join("Inbox"), root.join("Archive")).unwrap();
+        vfs.stage_move(root.join("Inbox/contract.pdf"), root.join("Clients/contract.pdf"))
+            .unwrap();
+
+        let plan = plan_from_staged(&vfs, "chat-exec".to_string(), "test".to_string());
+        let journal = journal_from_plan(&plan).unwrap();
+        let manager = WALManager::with_dir(dir.path().join("wal"));
+        manager.save_journal(&journal).unwrap();
+
+        let result = ExecutionEngine::with_manager(manager)
+            .execute_journal("chat-exec")
+            .await
+            .unwrap();
+        assert!(result.success, "{:?}", result.errors);
+
+        assert_eq!(std::fs::read(root.join("Clients/contract.pdf")).unwrap(), b"contract");
+        assert_eq!(std::fs::read(root.join("Archive/memo.txt")).unwrap(), b"memo");
+        assert!(!root.join("Archive/contract.pdf").exists());
+        assert!(!root.join("Inbox").exists());
+    }
+}
@@ -326,6 +326,7 @@ pub fn extract_source_paths(operations: &[crate::wal::entry::WALOperationType])
             WALOperationType::Copy { source, .. } => Some(source.clone()),
             WALOperationType::DeleteFolder { path } => Some(path.clone()),
             WALOperationType::Quarantine { path, .. } => Some(path.clone()),
+            WALOperationType::Restore { .. } => None, // Restored from the quarantine manifest
             WALOperationType::CreateFolder { .. } => None, // No source to check
         })
         .collect()
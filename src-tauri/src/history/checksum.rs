@@ -1,16 +1,13 @@
 //! SHA-256 checksum utilities for file integrity verification.
 
 use crate::history::entry::FileChecksum;
+use crate::services::hashing::HashService;
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
-use std::fs::{File, Metadata};
-use std::io::{BufReader, Read};
-use std::path::Path;
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
-/// Buffer size for reading files (8KB)
-const BUFFER_SIZE: usize = 8192;
-
 /// Extract modification time from metadata as unix timestamp
 fn get_mtime(metadata: &Metadata) -> u64 {
     metadata
@@ -22,6 +19,8 @@ fn get_mtime(metadata: &Metadata) -> u64 {
 }
 
 /// Compute SHA-256 checksum for a file or directory
+///
+/// File hashes go through the shared digest cache.
 pub fn compute_file_checksum(path: &Path) -> Result<FileChecksum, String> {
     let metadata = std::fs::metadata(path)
         .map_err(|e| format!("Failed to read metadata for {}: {}", path.display(), e))?;
@@ -36,31 +35,8 @@ pub fn compute_file_checksum(path: &Path) -> Result<FileChecksum, String> {
         });
     }
 
-    // Open file for reading
-    let file = File::open(path)
-        .map_err(|e| format!("Failed to open file {}: {}", path.display(), e))?;
-
-    let mut reader = BufReader::new(file);
-    let mut hasher = Sha256::new();
-    let mut buffer = [0u8; BUFFER_SIZE];
-
-    // Read and hash in chunks
-    loop {
-        let bytes_read = reader
-            .read(&mut buffer)
-            .map_err(|e| format!("Failed to read file {}: {}", path.display(), e))?;
-
-        if bytes_read == 0 {
-            break;
-        }
-
-        hasher.update(&buffer[..bytes_read]);
-    }
-
-    let hash = hasher.finalize();
-
     Ok(FileChecksum {
-        sha256: hex::encode(hash),
+        sha256: HashService::global().sha256(path)?,
         size: metadata.len(),
         mtime: get_mtime(&metadata),
         is_directory: false,
@@ -87,12 +63,39 @@ pub fn verify_checksum(path: &Path, expected: &FileChecksum) -> Result<bool, Str
 }
 
 /// Compute checksums for a list of paths
+///
+/// Files are hashed in parallel.
 #[allow(dead_code)]
 pub fn compute_checksums_batch(paths: &[&Path]) -> HashMap<String, FileChecksum> {
     let mut checksums = HashMap::new();
 
+    let files: Vec<PathBuf> = paths
+        .iter()
+        .filter(|path| path.is_file())
+        .map(|path| path.to_path_buf())
+        .collect();
+    let mut hashes: HashMap<&Path, Result<String, String>> = files
+        .iter()
+        .map(PathBuf::as_path)
+        .zip(HashService::global().sha256_many(&files))
+        .collect();
+
     for path in paths {
-        match compute_file_checksum(path) {
+        let checksum = match hashes.remove(path) {
+            Some(hash) => hash.and_then(|sha256| {
+                let metadata = std::fs::metadata(path).map_err(|e| {
+                    format!("Failed to read metadata for {}: {}", path.display(), e)
+                })?;
+                Ok(FileChecksum {
+                    sha256,
+                    size: metadata.len(),
+                    mtime: get_mtime(&metadata),
+                    is_directory: false,
+                })
+            }),
+            None => compute_file_checksum(path),
+        };
+        match checksum {
             Ok(checksum) => {
                 checksums.insert(path.to_string_lossy().to_string(), checksum);
             }
@@ -166,6 +169,7 @@ pub fn hash_folder_path(folder_path: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs::File;
     use std::io::Write;
     use tempfile::TempDir;
 
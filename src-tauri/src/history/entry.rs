@@ -1,14 +1,20 @@
 //! Data structures for organization history and undo operations.
+//!
+//! Sessions form a tree: each records the session that was current when it
+//! ran. Undo walks back from the head towards the root and redo walks
+//! forward along one branch, so running a new session after an undo starts
+//! a new branch instead of discarding the undone one.
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 /// Schema version for forward compatibility
-pub const HISTORY_SCHEMA_VERSION: u32 = 1;
+pub const HISTORY_SCHEMA_VERSION: u32 = 2;
 
-/// Maximum number of sessions to retain per folder
-pub const MAX_SESSIONS_PER_FOLDER: usize = 10;
+/// Serialized size budget for one folder's history (64 MiB); the oldest
+/// sessions are dropped past it
+pub const MAX_HISTORY_BYTES_PER_FOLDER: usize = 64 * 1024 * 1024;
 
 /// Operation record (mirrors WALOperationType for serialization)
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -30,6 +36,15 @@ pub enum OperationRecord {
         path: String,
         #[serde(rename = "quarantinePath")]
         quarantine_path: String,
+        #[serde(rename = "itemId", default, skip_serializing_if = "Option::is_none")]
+        item_id: Option<String>,
+    },
+    Restore {
+        #[serde(rename = "itemId")]
+        item_id: String,
+        path: String,
+        #[serde(rename = "quarantinePath")]
+        quarantine_path: String,
     },
     Copy {
         source: String,
@@ -81,10 +96,29 @@ impl OperationRecord {
             OperationRecord::Quarantine {
                 path,
                 quarantine_path,
+                item_id: Some(item_id),
+            } => OperationRecord::Restore {
+                item_id: item_id.clone(),
+                path: path.clone(),
+                quarantine_path: quarantine_path.clone(),
+            },
+            OperationRecord::Quarantine {
+                path,
+                quarantine_path,
+                item_id: None,
             } => OperationRecord::Move {
                 source: quarantine_path.clone(),
                 destination: path.clone(),
             },
+            OperationRecord::Restore {
+                item_id,
+                path,
+                quarantine_path,
+            } => OperationRecord::Quarantine {
+                path: path.clone(),
+                quarantine_path: quarantine_path.clone(),
+                item_id: Some(item_id.clone()),
+            },
             OperationRecord::Copy {
                 source: _,
                 destination,
@@ -117,12 +151,12 @@ impl OperationRecord {
             OperationRecord::Rename { path, new_name } => {
                 format!("Rename: {} → {}", path, new_name)
             }
-            OperationRecord::Quarantine {
-                path,
-                quarantine_path: _,
-            } => {
+            OperationRecord::Quarantine { path, .. } => {
                 format!("Quarantine: {}", path)
             }
+            OperationRecord::Restore { path, .. } => {
+                format!("Restore from quarantine: {}", path)
+            }
             OperationRecord::Copy {
                 source,
                 destination,
@@ -168,6 +202,23 @@ pub struct HistoryOperation {
     pub result_checksums: HashMap<String, FileChecksum>,
 }
 
+impl HistoryOperation {
+    /// The operation that reverts this one, with its undo being this one
+    ///
+    /// Checksums swap sides, so conflict checks against the inverse look at
+    /// the files this operation started from.
+    pub fn inverse(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            sequence: self.sequence,
+            operation: self.undo_operation.clone(),
+            undo_operation: self.operation.clone(),
+            source_checksums: self.result_checksums.clone(),
+            result_checksums: self.source_checksums.clone(),
+        }
+    }
+}
+
 /// Organization session representing one complete organization run
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -188,6 +239,10 @@ pub struct HistorySession {
     pub files_affected: usize,
     /// Whether this session has been undone
     pub undone: bool,
+    /// Session that was current when this one ran (None at the start of
+    /// history, or when that session has been dropped)
+    #[serde(default)]
+    pub parent_session_id: Option<String>,
 }
 
 impl HistorySession {
@@ -207,8 +262,15 @@ impl HistorySession {
             files_affected: self.files_affected,
             operation_count: self.operations.len(),
             undone: self.undone,
+            parent_session_id: self.parent_session_id.clone(),
+            is_head: false,
         }
     }
+
+    /// Serialized size, as counted against the history quota
+    fn serialized_size(&self) -> usize {
+        serde_json::to_vec(self).map(|bytes| bytes.len()).unwrap_or(0)
+    }
 }
 
 /// Lightweight session summary for listing
@@ -222,6 +284,9 @@ pub struct SessionSummary {
     pub files_affected: usize,
     pub operation_count: usize,
     pub undone: bool,
+    pub parent_session_id: Option<String>,
+    /// Whether the folder is currently at this session
+    pub is_head: bool,
 }
 
 /// Per-folder history file containing all sessions
@@ -234,8 +299,12 @@ pub struct FolderHistory {
     pub folder_path: String,
     /// Hash of folder path (used for filename)
     pub folder_hash: String,
-    /// All sessions for this folder (most recent first)
+    /// All sessions for this folder, on every branch (most recent first)
     pub sessions: Vec<HistorySession>,
+    /// Session the folder is currently at (None before the first session
+    /// or after undoing everything)
+    #[serde(default)]
+    pub head: Option<String>,
     /// When this history was last updated
     pub last_updated: DateTime<Utc>,
 }
@@ -248,18 +317,55 @@ impl FolderHistory {
             folder_path,
             folder_hash,
             sessions: Vec::new(),
+            head: None,
             last_updated: Utc::now(),
         }
     }
 
-    /// Add a session, enforcing the retention limit
+    /// Upgrade a history written by an older schema
+    ///
+    /// Version 1 histories were linear: each session's parent is the next
+    /// older one, and the head is the newest session not undone.
+    pub fn migrate(&mut self) {
+        if self.version >= HISTORY_SCHEMA_VERSION {
+            return;
+        }
+        let parents: Vec<Option<String>> = self
+            .sessions
+            .iter()
+            .skip(1)
+            .map(|s| Some(s.session_id.clone()))
+            .chain(std::iter::once(None))
+            .collect();
+        for (session, parent) in self.sessions.iter_mut().zip(parents) {
+            session.parent_session_id = parent;
+        }
+        self.head = self
+            .sessions
+            .iter()
+            .find(|s| !s.undone)
+            .map(|s| s.session_id.clone());
+        self.version = HISTORY_SCHEMA_VERSION;
+    }
+
+    /// Add a session on top of the head, enforcing the size quota
     pub fn add_session(&mut self, session: HistorySession) {
+        self.add_session_with_quota(session, MAX_HISTORY_BYTES_PER_FOLDER);
+    }
+
+    fn add_session_with_quota(&mut self, mut session: HistorySession, max_bytes: usize) {
+        session.parent_session_id = self.head.clone();
+        self.head = Some(session.session_id.clone());
+
         // Insert at the beginning (most recent first)
         self.sessions.insert(0, session);
 
-        // Enforce retention limit
-        if self.sessions.len() > MAX_SESSIONS_PER_FOLDER {
-            self.sessions.pop();
+        // Drop the oldest sessions past the quota, never the head
+        let mut total: usize = self.sessions.iter().map(|s| s.serialized_size()).sum();
+        while total > max_bytes && self.sessions.len() > 1 {
+            if let Some(dropped) = self.sessions.pop() {
+                total -= dropped.serialized_size();
+            }
         }
 
         self.last_updated = Utc::now();
@@ -267,7 +373,13 @@ impl FolderHistory {
 
     /// Get session summaries
     pub fn get_summaries(&self) -> Vec<SessionSummary> {
-        self.sessions.iter().map(|s| s.to_summary()).collect()
+        self.sessions
+            .iter()
+            .map(|s| SessionSummary {
+                is_head: self.head.as_deref() == Some(s.session_id.as_str()),
+                ..s.to_summary()
+            })
+            .collect()
     }
 
     /// Find a session by ID
@@ -275,18 +387,110 @@ impl FolderHistory {
         self.sessions.iter().find(|s| s.session_id == session_id)
     }
 
-    /// Mark sessions as undone up to and including the target session
-    pub fn mark_sessions_undone(&mut self, up_to_session_id: &str) {
-        let mut found = false;
+    /// Parent of a session, if it is still in the history
+    fn parent_of(&self, session: &HistorySession) -> Option<&HistorySession> {
+        session
+            .parent_session_id
+            .as_deref()
+            .and_then(|id| self.find_session(id))
+    }
+
+    /// Sessions to undo to get back to before `target`: the head, its
+    /// parent and so on down to `target` (most recent first)
+    pub fn undo_chain(&self, target_session_id: &str) -> Result<Vec<&HistorySession>, String> {
+        let target = self
+            .find_session(target_session_id)
+            .ok_or_else(|| format!("Session {} not found", target_session_id))?;
+        if target.undone {
+            return Err(format!("Session {} is already undone", target_session_id));
+        }
+
+        let mut chain = Vec::new();
+        let mut current = self.head.as_deref().and_then(|id| self.find_session(id));
+        while let Some(session) = current {
+            chain.push(session);
+            if session.session_id == target_session_id {
+                return Ok(chain);
+            }
+            current = self.parent_of(session);
+        }
+        Err(format!(
+            "Session {} is not on the current branch",
+            target_session_id
+        ))
+    }
+
+    /// Undone sessions to redo to reach `target`, from the child of the head
+    /// down to `target` (oldest first)
+    pub fn redo_chain(&self, target_session_id: &str) -> Result<Vec<&HistorySession>, String> {
+        let target = self
+            .find_session(target_session_id)
+            .ok_or_else(|| format!("Session {} not found", target_session_id))?;
+        if !target.undone {
+            return Err(format!("Session {} is not undone", target_session_id));
+        }
+
+        let mut chain = vec![target];
+        let mut current = self.parent_of(target);
+        while let Some(session) = current.filter(|s| s.undone) {
+            chain.push(session);
+            current = self.parent_of(session);
+        }
+
+        // The branch must grow out of the session the folder is at
+        let branch_point = current.map(|s| s.session_id.as_str());
+        if branch_point != self.head.as_deref() {
+            return Err(format!(
+                "Session {} is on another branch; undo back to {} first",
+                target_session_id,
+                branch_point.unwrap_or("the start of history")
+            ));
+        }
+
+        chain.reverse();
+        Ok(chain)
+    }
+
+    /// Mark the head chain as undone down to and including the target
+    /// session; the head moves to the target's parent
+    pub fn mark_sessions_undone(&mut self, up_to_session_id: &str) -> Result<(), String> {
+        let chain: Vec<String> = self
+            .undo_chain(up_to_session_id)?
+            .iter()
+            .map(|s| s.session_id.clone())
+            .collect();
+        let new_head = self
+            .find_session(up_to_session_id)
+            .and_then(|target| self.parent_of(target))
+            .map(|s| s.session_id.clone());
+
         for session in &mut self.sessions {
-            if !found {
+            if chain.contains(&session.session_id) {
                 session.undone = true;
             }
-            if session.session_id == up_to_session_id {
-                found = true;
+        }
+        self.head = new_head;
+        self.last_updated = Utc::now();
+        Ok(())
+    }
+
+    /// Mark the redo chain up to the target session as applied again; the
+    /// head moves to the target
+    pub fn mark_sessions_redone(&mut self, target_session_id: &str) -> Result<(), String> {
+        let chain: Vec<String> = self
+            .redo_chain(target_session_id)?
+            .iter()
+            .map(|s| s.session_id.clone())
+            .collect();
+
+        for session in &mut self.sessions {
+            if chain.contains(&session.session_id) {
+                session.undone = false;
             }
         }
+        self.head = Some(target_session_id.to_string());
         self.last_updated = Utc::now();
+        Ok(())
     }
 }
 
@@ -395,29 +599,93 @@ mod tests {
         );
     }
 
+    fn test_session(id: &str) -> HistorySession {
+        HistorySession {
+            session_id: id.to_string(),
+            user_instruction: "test".to_string(),
+            plan_description: "test".to_string(),
+            executed_at: Utc::now(),
+            target_folder: "test".to_string(),
+            operations: vec![],
+            files_affected: 0,
+            undone: false,
+            parent_session_id: None,
+        }
+    }
+
     #[test]
     fn test_folder_history_retention() {
         let mut history = FolderHistory::new("test".to_string(), "abc123".to_string());
+        let budget = test_session("session-00").serialized_size() * 13;
 
-        // Add more than MAX_SESSIONS_PER_FOLDER sessions
+        // Far more sessions than the old fixed limit of ten fit in the quota
         for i in 0..15 {
-            let session = HistorySession {
-                session_id: format!("session-{}", i),
-                user_instruction: "test".to_string(),
-                plan_description: "test".to_string(),
-                executed_at: Utc::now(),
-                target_folder: "test".to_string(),
-                operations: vec![],
-                files_affected: 0,
-                undone: false,
-            };
-            history.add_session(session);
+            history.add_session_with_quota(test_session(&format!("session-{:02}", i)), budget);
         }
 
-        // Should only keep MAX_SESSIONS_PER_FOLDER
-        assert_eq!(history.sessions.len(), MAX_SESSIONS_PER_FOLDER);
+        // The oldest sessions beyond the quota are dropped
+        assert!(history.sessions.len() > 10 && history.sessions.len() < 15);
+        assert_eq!(
+            history.sessions.last().unwrap().session_id,
+            format!("session-{:02}", 15 - history.sessions.len())
+        );
 
         // Most recent should be first
         assert_eq!(history.sessions[0].session_id, "session-14");
+        assert_eq!(history.head.as_deref(), Some("session-14"));
+    }
+
+    #[test]
+    fn test_undo_then_new_session_keeps_branch_for_redo() {
+        let mut history = FolderHistory::new("test".to_string(), "abc123".to_string());
+        for id in ["a", "b", "c"] {
+            history.add_session(test_session(id));
+        }
+
+        history.mark_sessions_undone("b").unwrap();
+        assert_eq!(history.head.as_deref(), Some("a"));
+        assert!(history.undo_chain("c").is_err());
+
+        // A new session branches off "a"; "b" and "c" are kept
+        history.add_session(test_session("d"));
+        assert_eq!(history.find_session("d").unwrap().parent_session_id.as_deref(), Some("a"));
+        assert_eq!(history.sessions.len(), 4);
+
+        let err = history.redo_chain("c").unwrap_err();
+        assert!(err.contains("undo back to a first"), "{}", err);
+
+        // Back at "a", the old branch can be redone in order
+        history.mark_sessions_undone("d").unwrap();
+        let chain: Vec<_> = history
+            .redo_chain("c")
+            .unwrap()
+            .iter()
+            .map(|s| s.session_id.as_str())
+            .collect();
+        assert_eq!(chain, vec!["b", "c"]);
+
+        history.mark_sessions_redone("c").unwrap();
+        assert_eq!(history.head.as_deref(), Some("c"));
+        let undone: Vec<_> = history
+            .get_summaries()
+            .into_iter()
+            .filter(|s| s.undone)
+            .map(|s| s.session_id)
+            .collect();
+        assert_eq!(undone, vec!["d"]);
+    }
+
+    #[test]
+    fn test_migrate_linear_history() {
+        let mut history = FolderHistory::new("test".to_string(), "abc123".to_string());
+        history.version = 1;
+        history.sessions = vec![test_session("c"), test_session("b"), test_session("a")];
+        history.sessions[0].undone = true;
+
+        history.migrate();
+        assert_eq!(history.head.as_deref(), Some("b"));
+        assert_eq!(history.sessions[0].parent_session_id.as_deref(), Some("b"));
+        assert_eq!(history.sessions[2].parent_session_id, None);
+        assert_eq!(history.redo_chain("c").unwrap().len(), 1);
     }
 }
@@ -4,14 +4,26 @@
 //! - `entry`: Data structures for history sessions and operations
 //! - `checksum`: SHA-256 file integrity verification
 //! - `store`: Persistence manager for history files
+//! - `recorder`: Conversion of executed WAL journals into history operations
 //! - `undo`: Undo algorithm with conflict detection
+//! - `redo`: Replay of undone sessions on the current branch
+//! - `selective`: Undo of chosen operations within a session
+//! - `snapshot`: Folder manifests and drift reports
 
 mod checksum;
 mod entry;
+mod recorder;
+mod redo;
+mod selective;
+mod snapshot;
 mod store;
 mod undo;
 
 pub use checksum::*;
 pub use entry::*;
+pub use recorder::*;
+pub use redo::*;
+pub use selective::*;
+pub use snapshot::*;
 pub use store::*;
 pub use undo::*;
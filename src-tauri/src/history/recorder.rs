@@ -0,0 +1,266 @@
+//! Conversion of executed WAL journals into history records.
+//!
+//! Shared by every code path that executes a journal and wants the result to
+//! be undoable through the history store (plan execution from the app or the
+//! CLI, inbox rules).
+
+use crate::history::checksum::compute_file_checksum;
+use crate::history::entry::{FileChecksum, HistoryOperation, HistorySession, OperationRecord};
+use crate::history::store::HistoryStore;
+use crate::jobs::OrganizePlan;
+use crate::wal::entry::{WALJournal, WALOperationType};
+use chrono::Utc;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Build history operations from the entries of an executed journal
+pub fn history_operations_from_journal(
+    journal: &WALJournal,
+) -> Result<Vec<HistoryOperation>, String> {
+    let mut history_ops = Vec::new();
+
+    for (i, entry) in journal.entries.iter().enumerate() {
+        // Convert WAL operation to history operation record
+        let operation = wal_to_operation_record(&entry.operation)?;
+        let undo_operation = operation.inverse();
+
+        // Compute checksums for source and result files
+        let (source_checksums, result_checksums) = compute_operation_checksums(&entry.operation);
+
+        history_ops.push(HistoryOperation {
+            id: entry.id.to_string(),
+            sequence: i as u32,
+            operation,
+            undo_operation,
+            source_checksums,
+            result_checksums,
+        });
+    }
+
+    Ok(history_ops)
+}
+
+/// Save organization history for multi-level undo.
+///
+/// This creates a history session from the completed execution and saves it
+/// to the history store. The session includes all operations with their
+/// inverse operations and checksums for integrity verification.
+///
+/// The drift baseline is not updated here: full-folder snapshots hash every
+/// file, so callers follow up with `spawn_folder_snapshot` (or
+/// `refresh_folder_snapshot` off the async runtime).
+pub fn save_plan_history(
+    plan: &OrganizePlan,
+    journal: &WALJournal,
+    user_instruction: &str,
+    files_affected: usize,
+) -> Result<(), String> {
+    let store = HistoryStore::new();
+
+    // Build history operations from journal entries
+    let history_ops = history_operations_from_journal(journal)?;
+
+    // Create the history session
+    let session = HistorySession {
+        session_id: plan.plan_id.clone(),
+        user_instruction: user_instruction.to_string(),
+        plan_description: plan.description.clone(),
+        executed_at: Utc::now(),
+        target_folder: plan.target_folder.clone(),
+        operations: history_ops,
+        files_affected,
+        undone: false,
+        parent_session_id: None,
+    };
+
+    // Save to history store
+    store.save_session(&plan.target_folder, session)?;
+
+    Ok(())
+}
+
+/// Snapshot a folder as the baseline for drift reports.
+///
+/// Hashes every file in the folder, so this blocks; history is still usable
+/// without a baseline, so failures are only logged.
+pub fn refresh_folder_snapshot(folder: &str) {
+    if let Err(e) = HistoryStore::new().snapshot_folder(folder) {
+        tracing::warn!(error = %e, folder, "Failed to snapshot folder");
+    }
+}
+
+/// Run `refresh_folder_snapshot` on the blocking pool without waiting for it
+pub fn spawn_folder_snapshot(folder: String) {
+    tokio::task::spawn_blocking(move || refresh_folder_snapshot(&folder));
+}
+
+/// Convert WALOperationType to OperationRecord
+pub fn wal_to_operation_record(wal_op: &WALOperationType) -> Result<OperationRecord, String> {
+    match wal_op {
+        WALOperationType::CreateFolder { path } => Ok(OperationRecord::CreateFolder {
+            path: path.to_string_lossy().to_string(),
+        }),
+        WALOperationType::Move {
+            source,
+            destination,
+        } => Ok(OperationRecord::Move {
+            source: source.to_string_lossy().to_string(),
+            destination: destination.to_string_lossy().to_string(),
+        }),
+        WALOperationType::Rename { path, new_name } => Ok(OperationRecord::Rename {
+            path: path.to_string_lossy().to_string(),
+            new_name: new_name.clone(),
+        }),
+        WALOperationType::Quarantine {
+            path,
+            quarantine_path,
+            item_id,
+        } => Ok(OperationRecord::Quarantine {
+            path: path.to_string_lossy().to_string(),
+            quarantine_path: quarantine_path.to_string_lossy().to_string(),
+            item_id: item_id.clone(),
+        }),
+        WALOperationType::Restore {
+            item_id,
+            path,
+            quarantine_path,
+        } => Ok(OperationRecord::Restore {
+            item_id: item_id.clone(),
+            path: path.to_string_lossy().to_string(),
+            quarantine_path: quarantine_path.to_string_lossy().to_string(),
+        }),
+        WALOperationType::Copy {
+            source,
+            destination,
+        } => Ok(OperationRecord::Copy {
+            source: source.to_string_lossy().to_string(),
+            destination: destination.to_string_lossy().to_string(),
+        }),
+        WALOperationType::DeleteFolder { path } => Ok(OperationRecord::DeleteFolder {
+            path: path.to_string_lossy().to_string(),
+        }),
+    }
+}
+
+/// Compute checksums for source and result files of an operation
+pub fn compute_operation_checksums(
+    wal_op: &WALOperationType,
+) -> (HashMap<String, FileChecksum>, HashMap<String, FileChecksum>) {
+    let source_checksums = HashMap::new();
+    let mut result_checksums = HashMap::new();
+
+    match wal_op {
+        WALOperationType::Move {
+            source: _,
+            destination,
+        } => {
+            // After move, file is at destination (source no longer exists)
+            if let Ok(checksum) = compute_file_checksum(destination) {
+                result_checksums.insert(destination.to_string_lossy().to_string(), checksum);
+            }
+        }
+        WALOperationType::Rename { path, new_name } => {
+            // After rename, file is at new path
+            let parent = path.parent().unwrap_or(Path::new(""));
+            let new_path = parent.join(new_name);
+            if let Ok(checksum) = compute_file_checksum(&new_path) {
+                result_checksums.insert(new_path.to_string_lossy().to_string(), checksum);
+            }
+        }
+        WALOperationType::CreateFolder { path } => {
+            // Folder now exists at path
+            if let Ok(checksum) = compute_file_checksum(path) {
+                result_checksums.insert(path.to_string_lossy().to_string(), checksum);
+            }
+        }
+        WALOperationType::Copy {
+            source: _,
+            destination,
+        } => {
+            // Copy creates file at destination
+            if let Ok(checksum) = compute_file_checksum(destination) {
+                result_checksums.insert(destination.to_string_lossy().to_string(), checksum);
+            }
+        }
+        WALOperationType::Quarantine {
+            path: _,
+            quarantine_path,
+            item_id: None,
+        } => {
+            // File is now at quarantine path
+            if let Ok(checksum) = compute_file_checksum(quarantine_path) {
+                result_checksums.insert(quarantine_path.to_string_lossy().to_string(), checksum);
+            }
+        }
+        WALOperationType::Quarantine { item_id: Some(_), .. } => {
+            // The quarantine manifest keeps the item's content hash
+        }
+        WALOperationType::Restore { path, .. } => {
+            // File is back at its original path
+            if let Ok(checksum) = compute_file_checksum(path) {
+                result_checksums.insert(path.to_string_lossy().to_string(), checksum);
+            }
+        }
+        WALOperationType::DeleteFolder { path: _ } => {
+            // Nothing to checksum - folder is deleted
+        }
+    }
+
+    (source_checksums, result_checksums)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_history_operations_from_journal() {
+        let dir = tempdir().unwrap();
+        let dest = dir.path().join("moved.txt");
+        std::fs::write(&dest, b"hello").unwrap();
+
+        let mut journal = WALJournal::new("job-1".to_string(), dir.path().to_path_buf());
+        journal
+            .add_operation(WALOperationType::Move {
+                source: dir.path().join("original.txt"),
+                destination: dest.clone(),
+            })
+            .unwrap();
+
+        let ops = history_operations_from_journal(&journal).unwrap();
+        assert_eq!(ops.len(), 1);
+        assert_eq!(ops[0].sequence, 0);
+        assert_eq!(
+            ops[0].undo_operation,
+            OperationRecord::Move {
+                source: dest.to_string_lossy().to_string(),
+                destination: dir
+                    .path()
+                    .join("original.txt")
+                    .to_string_lossy()
+                    .to_string(),
+            }
+        );
+        assert!(ops[0]
+            .result_checksums
+            .contains_key(&dest.to_string_lossy().to_string()));
+    }
+
+    #[test]
+    fn test_wal_to_operation_record_rename() {
+        let record = wal_to_operation_record(&WALOperationType::Rename {
+            path: PathBuf::from("/a/old.txt"),
+            new_name: "new.txt".to_string(),
+        })
+        .unwrap();
+        assert_eq!(
+            record,
+            OperationRecord::Rename {
+                path: "/a/old.txt".to_string(),
+                new_name: "new.txt".to_string(),
+            }
+        );
+    }
+}
@@ -0,0 +1,144 @@
+//! Redo of undone sessions by replaying their recorded operations.
+
+use crate::history::entry::{HistoryOperation, HistorySession, OperationRecord};
+use crate::history::store::HistoryStore;
+use crate::history::undo::{
+    check_operation_conflicts, preflight_operations, ConflictInfo, UndoPreflightResult,
+};
+use serde::{Deserialize, Serialize};
+
+/// Result of redo execution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedoResult {
+    /// Whether every operation was redone
+    pub success: bool,
+    /// Number of operations redone
+    pub operations_redone: usize,
+    /// Number of operations that failed
+    pub operations_failed: usize,
+    /// Error messages
+    pub errors: Vec<String>,
+}
+
+/// Perform preflight check before redo
+///
+/// Redo replays the sessions between the current head and the target, so
+/// each operation's source must still be in place and its destination free.
+pub fn preflight_redo(
+    folder_path: &str,
+    target_session_id: &str,
+) -> Result<UndoPreflightResult, String> {
+    let store = HistoryStore::new();
+    let history = store
+        .load_history(folder_path)?
+        .ok_or_else(|| format!("No history found for {}", folder_path))?;
+
+    let sessions_to_redo: Vec<&HistorySession> = history.redo_chain(target_session_id)?;
+
+    Ok(preflight_operations(
+        sessions_to_redo.iter().flat_map(|s| s.operations.iter()),
+        check_redo_conflicts,
+    ))
+}
+
+/// Check a single operation for conflicts when replaying it
+fn check_redo_conflicts(op: &HistoryOperation) -> Result<Vec<ConflictInfo>, String> {
+    // Creating a folder that already exists is a no-op, not a conflict
+    if matches!(op.operation, OperationRecord::CreateFolder { .. }) {
+        return Ok(Vec::new());
+    }
+    // Undoing the inverse puts files where redoing the original would
+    check_operation_conflicts(&op.inverse())
+}
+
+/// Collect redo operations from sessions
+///
+/// Returns operations in the order they were originally executed.
+pub fn collect_redo_operations(
+    folder_path: &str,
+    target_session_id: &str,
+) -> Result<Vec<OperationRecord>, String> {
+    let store = HistoryStore::new();
+    let history = store
+        .load_history(folder_path)?
+        .ok_or_else(|| format!("No history found for {}", folder_path))?;
+
+    let mut redo_operations = Vec::new();
+
+    // Sessions come oldest first
+    for session in history.redo_chain(target_session_id)? {
+        let mut operations: Vec<&HistoryOperation> = session.operations.iter().collect();
+        operations.sort_by_key(|op| op.sequence);
+        redo_operations.extend(operations.into_iter().map(|op| op.operation.clone()));
+    }
+
+    Ok(redo_operations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::entry::FileChecksum;
+    use crate::history::ConflictType;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    fn move_op(source: &str, destination: &str) -> HistoryOperation {
+        HistoryOperation {
+            id: "op-1".to_string(),
+            sequence: 0,
+            operation: OperationRecord::Move {
+                source: source.to_string(),
+                destination: destination.to_string(),
+            },
+            undo_operation: OperationRecord::Move {
+                source: destination.to_string(),
+                destination: source.to_string(),
+            },
+            source_checksums: HashMap::new(),
+            result_checksums: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_redo_move_conflicts() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("a.txt");
+        let destination = temp.path().join("sorted.txt");
+        std::fs::write(&source, b"content").unwrap();
+
+        let op = move_op(&source.to_string_lossy(), &destination.to_string_lossy());
+        assert!(check_redo_conflicts(&op).unwrap().is_empty());
+
+        // Something now occupies the destination
+        std::fs::write(&destination, b"other").unwrap();
+        let conflicts = check_redo_conflicts(&op).unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].conflict_type, ConflictType::Blocking);
+    }
+
+    #[test]
+    fn test_redo_detects_modified_source() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("a.txt");
+        std::fs::write(&source, b"changed").unwrap();
+        let source = source.to_string_lossy().to_string();
+
+        let mut op = move_op(&source, &temp.path().join("b.txt").to_string_lossy());
+        op.source_checksums.insert(
+            source.clone(),
+            FileChecksum {
+                sha256: "0".repeat(64),
+                size: 8,
+                mtime: 0,
+                is_directory: false,
+            },
+        );
+
+        let conflicts = check_redo_conflicts(&op).unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].conflict_type, ConflictType::Modified);
+        assert_eq!(conflicts[0].path, source);
+    }
+}
@@ -0,0 +1,373 @@
+//! Selective undo of individual operations within a session.
+//!
+//! Undoing part of a session can require undoing more of it: a file moved
+//! and then renamed has to be renamed back before it can move back, and a
+//! created folder can only be removed once everything moved into it has
+//! left. The plan pulls those operations in as dependencies.
+//!
+//! A partial undo is recorded as a session of its own holding the inverse
+//! operations, so it can itself be undone and redone like any other.
+
+use crate::history::entry::{FolderHistory, HistoryOperation, HistorySession, OperationRecord};
+use crate::history::store::HistoryStore;
+use crate::history::undo::{check_operation_conflicts, preflight_operations, UndoPreflightResult};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// Operations picked for undo within one session
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoSelection {
+    /// Operation IDs to undo
+    #[serde(default)]
+    pub operation_ids: Vec<String>,
+    /// Folders whose operations to undo (anything moved, copied, renamed
+    /// or created inside them)
+    #[serde(default)]
+    pub folders: Vec<String>,
+}
+
+/// Operations a selective undo will revert
+#[derive(Debug, Clone)]
+pub struct SelectiveUndoPlan {
+    /// Session the operations belong to
+    pub session_id: String,
+    /// Instruction of that session, for describing the undo
+    pub user_instruction: String,
+    /// Operations to undo, most recent first
+    pub operations: Vec<HistoryOperation>,
+    /// Operations added because selected ones depend on them
+    pub dependent_operation_ids: Vec<String>,
+}
+
+/// Result of preflight check before a selective undo
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelectiveUndoPreflight {
+    /// Conflicts for the planned operations
+    pub preflight: UndoPreflightResult,
+    /// Every operation that will be undone, most recent first
+    pub operation_ids: Vec<String>,
+    /// Operations added to the selection as dependencies
+    pub dependent_operation_ids: Vec<String>,
+}
+
+impl SelectiveUndoPlan {
+    /// Session recording the operations that were undone
+    ///
+    /// `undone` indexes into `operations`. The recorded operations are the
+    /// inverses, in the order they ran.
+    pub fn to_session(&self, undone: &[usize], target_folder: &str) -> HistorySession {
+        let operations: Vec<HistoryOperation> = undone
+            .iter()
+            .filter_map(|&i| self.operations.get(i))
+            .enumerate()
+            .map(|(sequence, op)| HistoryOperation {
+                sequence: sequence as u32,
+                ..op.inverse()
+            })
+            .collect();
+
+        HistorySession {
+            session_id: format!("undo-{}-{}", self.session_id, Utc::now().timestamp_millis()),
+            user_instruction: format!("Undo part of \"{}\"", self.user_instruction),
+            plan_description: format!(
+                "Undid {} of the operations from session {}",
+                operations.len(),
+                self.session_id
+            ),
+            executed_at: Utc::now(),
+            target_folder: target_folder.to_string(),
+            files_affected: operations.len(),
+            operations,
+            undone: false,
+            parent_session_id: None,
+        }
+    }
+}
+
+/// Plan a selective undo from a folder's stored history
+pub fn plan_selective_undo(
+    folder_path: &str,
+    session_id: &str,
+    selection: &UndoSelection,
+) -> Result<SelectiveUndoPlan, String> {
+    let store = HistoryStore::new();
+    let history = store
+        .load_history(folder_path)?
+        .ok_or_else(|| format!("No history found for {}", folder_path))?;
+    plan_from_history(&history, session_id, selection)
+}
+
+/// Perform preflight check before a selective undo
+pub fn preflight_selective_undo(
+    folder_path: &str,
+    session_id: &str,
+    selection: &UndoSelection,
+) -> Result<SelectiveUndoPreflight, String> {
+    let plan = plan_selective_undo(folder_path, session_id, selection)?;
+    Ok(SelectiveUndoPreflight {
+        preflight: preflight_operations(&plan.operations, check_operation_conflicts),
+        operation_ids: plan.operations.iter().map(|op| op.id.clone()).collect(),
+        dependent_operation_ids: plan.dependent_operation_ids,
+    })
+}
+
+fn plan_from_history(
+    history: &FolderHistory,
+    session_id: &str,
+    selection: &UndoSelection,
+) -> Result<SelectiveUndoPlan, String> {
+    // Only sessions the folder is currently built on can be partly undone
+    history.undo_chain(session_id)?;
+    let session = history
+        .find_session(session_id)
+        .ok_or_else(|| format!("Session {} not found", session_id))?;
+
+    let mut operations: Vec<&HistoryOperation> = session.operations.iter().collect();
+    operations.sort_by_key(|op| op.sequence);
+
+    let mut selected = BTreeSet::new();
+    for id in &selection.operation_ids {
+        let index = operations
+            .iter()
+            .position(|op| &op.id == id)
+            .ok_or_else(|| format!("Operation {} not found in session {}", id, session_id))?;
+        selected.insert(index);
+    }
+    for folder in &selection.folders {
+        let folder = Path::new(folder);
+        for (index, op) in operations.iter().enumerate() {
+            if touched_paths(&op.operation).iter().any(|p| p.starts_with(folder)) {
+                selected.insert(index);
+            }
+        }
+    }
+    if selected.is_empty() {
+        return Err("No operations selected".to_string());
+    }
+
+    // Pull in every later operation a selected one depends on
+    let mut dependents = BTreeSet::new();
+    let mut pending: Vec<usize> = selected.iter().copied().collect();
+    while let Some(index) = pending.pop() {
+        for later in index + 1..operations.len() {
+            if must_undo_first(&operations[later].operation, &operations[index].operation)
+                && !selected.contains(&later)
+                && dependents.insert(later)
+            {
+                pending.push(later);
+            }
+        }
+    }
+
+    let dependent_operation_ids = dependents
+        .iter()
+        .map(|&i| operations[i].id.clone())
+        .collect();
+    selected.extend(&dependents);
+    let operations = selected.iter().rev().map(|&i| operations[i].clone()).collect();
+
+    Ok(SelectiveUndoPlan {
+        session_id: session_id.to_string(),
+        user_instruction: session.user_instruction.clone(),
+        operations,
+        dependent_operation_ids,
+    })
+}
+
+/// Whether `later` has to be undone before `earlier` can be
+///
+/// That is when `later` acted on what `earlier` produced, occupies the
+/// place `earlier` would restore to, or put something inside a folder
+/// `earlier` created.
+fn must_undo_first(later: &OperationRecord, earlier: &OperationRecord) -> bool {
+    let later_source = source_path(later);
+    let later_result = result_path(later);
+
+    if let Some(produced) = result_path(earlier) {
+        if later_source.as_ref().is_some_and(|p| p.starts_with(&produced)) {
+            return true;
+        }
+        if matches!(earlier, OperationRecord::CreateFolder { .. })
+            && later_result.as_ref().is_some_and(|p| p.starts_with(&produced))
+        {
+            return true;
+        }
+    }
+    match (source_path(earlier), later_result) {
+        (Some(restored), Some(occupied)) => restored == occupied,
+        _ => false,
+    }
+}
+
+/// Path an operation took its input from
+fn source_path(op: &OperationRecord) -> Option<PathBuf> {
+    match op {
+        OperationRecord::Move { source, .. } => Some(PathBuf::from(source)),
+        OperationRecord::Rename { path, .. } => Some(PathBuf::from(path)),
+        OperationRecord::Quarantine { path, .. } => Some(PathBuf::from(path)),
+        OperationRecord::DeleteFolder { path } => Some(PathBuf::from(path)),
+        // A copy leaves its source in place
+        OperationRecord::Copy { .. } | OperationRecord::CreateFolder { .. } => None,
+        // A restore takes its input from the quarantine manifest
+        OperationRecord::Restore { .. } => None,
+    }
+}
+
+/// Path an operation left its output at
+fn result_path(op: &OperationRecord) -> Option<PathBuf> {
+    match op {
+        OperationRecord::Move { destination, .. } => Some(PathBuf::from(destination)),
+        OperationRecord::Copy { destination, .. } => Some(PathBuf::from(destination)),
+        OperationRecord::Rename { path, new_name } => Some(
+            Path::new(path)
+                .parent()
+                .unwrap_or(Path::new(""))
+                .join(new_name),
+        ),
+        OperationRecord::CreateFolder { path } => Some(PathBuf::from(path)),
+        OperationRecord::Quarantine {
+            quarantine_path,
+            item_id: None,
+            ..
+        } => Some(PathBuf::from(quarantine_path)),
+        // Managed items are stored by content, not at a path of their own
+        OperationRecord::Quarantine { item_id: Some(_), .. } => None,
+        OperationRecord::Restore { path, .. } => Some(PathBuf::from(path)),
+        OperationRecord::DeleteFolder { .. } => None,
+    }
+}
+
+/// Paths an operation read from or wrote to
+fn touched_paths(op: &OperationRecord) -> Vec<PathBuf> {
+    source_path(op).into_iter().chain(result_path(op)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn op(id: &str, sequence: u32, operation: OperationRecord) -> HistoryOperation {
+        HistoryOperation {
+            id: id.to_string(),
+            sequence,
+            undo_operation: operation.inverse(),
+            operation,
+            source_checksums: HashMap::new(),
+            result_checksums: HashMap::new(),
+        }
+    }
+
+    fn mv(source: &str, destination: &str) -> OperationRecord {
+        OperationRecord::Move {
+            source: source.to_string(),
+            destination: destination.to_string(),
+        }
+    }
+
+    fn test_history() -> FolderHistory {
+        let mut history = FolderHistory::new("/t".to_string(), "abc123".to_string());
+        history.add_session(HistorySession {
+            session_id: "s1".to_string(),
+            user_instruction: "sort".to_string(),
+            plan_description: "sort".to_string(),
+            executed_at: Utc::now(),
+            target_folder: "/t".to_string(),
+            operations: vec![
+                op(
+                    "mkdir",
+                    0,
+                    OperationRecord::CreateFolder {
+                        path: "/t/docs".to_string(),
+                    },
+                ),
+                op("move-a", 1, mv("/t/a.txt", "/t/docs/a.txt")),
+                op(
+                    "rename-a",
+                    2,
+                    OperationRecord::Rename {
+                        path: "/t/docs/a.txt".to_string(),
+                        new_name: "b.txt".to_string(),
+                    },
+                ),
+                op("move-c", 3, mv("/t/c.txt", "/t/docs/c.txt")),
+                op("move-x", 4, mv("/t/x.jpg", "/t/y.jpg")),
+            ],
+            files_affected: 3,
+            undone: false,
+            parent_session_id: None,
+        });
+        history
+    }
+
+    fn ids(plan: &SelectiveUndoPlan) -> Vec<&str> {
+        plan.operations.iter().map(|op| op.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_selected_operation_pulls_in_later_dependents() {
+        let history = test_history();
+
+        let selection = UndoSelection {
+            operation_ids: vec!["move-a".to_string()],
+            ..Default::default()
+        };
+        let plan = plan_from_history(&history, "s1", &selection).unwrap();
+        assert_eq!(ids(&plan), vec!["rename-a", "move-a"]);
+        assert_eq!(plan.dependent_operation_ids, vec!["rename-a"]);
+
+        let selection = UndoSelection {
+            operation_ids: vec!["move-x".to_string()],
+            ..Default::default()
+        };
+        let plan = plan_from_history(&history, "s1", &selection).unwrap();
+        assert_eq!(ids(&plan), vec!["move-x"]);
+        assert!(plan.dependent_operation_ids.is_empty());
+    }
+
+    #[test]
+    fn test_created_folder_needs_its_contents_undone() {
+        let history = test_history();
+
+        let selection = UndoSelection {
+            operation_ids: vec!["mkdir".to_string()],
+            ..Default::default()
+        };
+        let plan = plan_from_history(&history, "s1", &selection).unwrap();
+        assert_eq!(ids(&plan), vec!["move-c", "rename-a", "move-a", "mkdir"]);
+
+        // Selecting the folder picks the same operations
+        let selection = UndoSelection {
+            folders: vec!["/t/docs".to_string()],
+            ..Default::default()
+        };
+        let by_folder = plan_from_history(&history, "s1", &selection).unwrap();
+        assert_eq!(ids(&by_folder), ids(&plan));
+        assert!(by_folder.dependent_operation_ids.is_empty());
+    }
+
+    #[test]
+    fn test_partial_undo_session_records_inverses() {
+        let mut history = test_history();
+        let selection = UndoSelection {
+            operation_ids: vec!["move-a".to_string()],
+            ..Default::default()
+        };
+        let plan = plan_from_history(&history, "s1", &selection).unwrap();
+
+        // Only the rename went through
+        let session = plan.to_session(&[0], "/t");
+        assert_eq!(session.operations.len(), 1);
+        assert_eq!(session.operations[0].operation, plan.operations[0].undo_operation);
+        assert_eq!(session.operations[0].sequence, 0);
+
+        history.add_session(session);
+        assert!(plan_from_history(&history, "s1", &selection).is_ok());
+        assert!(plan_from_history(&history, "missing", &selection).is_err());
+        assert!(plan_from_history(&history, "s1", &UndoSelection::default()).is_err());
+    }
+}
@@ -0,0 +1,302 @@
+//! Folder snapshots and drift reports.
+//!
+//! A snapshot records the size, mtime and SHA-256 of every file in a folder
+//! after an organization session. Diffing it against the folder as it is now
+//! shows how the folder drifted since: files added, removed, modified, and
+//! moved (a removed and an added file with the same content).
+
+use crate::services::hashing::HashService;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+/// One file in a snapshot
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotEntry {
+    /// Path relative to the snapshot folder
+    pub path: String,
+    /// File size in bytes
+    pub size: u64,
+    /// Modification time (unix timestamp)
+    pub mtime: u64,
+    /// SHA-256 hash of file content (hex-encoded)
+    pub sha256: String,
+}
+
+/// Manifest of a folder's files at a point in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderSnapshot {
+    /// Folder path (canonical)
+    pub folder_path: String,
+    /// Session the folder was at when the snapshot was taken
+    pub session_id: Option<String>,
+    /// When the snapshot was taken
+    pub captured_at: DateTime<Utc>,
+    /// Files, sorted by path
+    pub entries: Vec<SnapshotEntry>,
+}
+
+/// A file found at a new path with unchanged content
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MovedFile {
+    pub from: String,
+    pub to: String,
+}
+
+/// Differences between a snapshot and a later state of the folder
+///
+/// Paths are relative to the folder.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub moved: Vec<MovedFile>,
+    pub modified: Vec<String>,
+    /// Number of files left as they were
+    pub unchanged: usize,
+}
+
+/// How a folder drifted since its last snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriftReport {
+    pub folder_path: String,
+    /// Session the snapshot was taken after
+    pub session_id: Option<String>,
+    /// When the snapshot was taken
+    pub snapshot_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub diff: SnapshotDiff,
+    /// Absolute paths of files that are new or changed where they are,
+    /// i.e. what a re-run of organization has to look at
+    pub delta_paths: Vec<String>,
+}
+
+impl FolderSnapshot {
+    /// Capture the files in `folder`
+    ///
+    /// Hidden files and folders are skipped. Files whose size and mtime
+    /// match an entry in `previous` keep its hash; the rest are hashed in
+    /// parallel.
+    pub fn capture(
+        folder: &Path,
+        session_id: Option<String>,
+        previous: Option<&FolderSnapshot>,
+    ) -> Result<Self, String> {
+        let known: HashMap<&str, &SnapshotEntry> = previous
+            .map(|p| p.entries.iter().map(|e| (e.path.as_str(), e)).collect())
+            .unwrap_or_default();
+
+        let mut entries = Vec::new();
+        let mut unhashed: Vec<(usize, PathBuf)> = Vec::new();
+        let walker = WalkDir::new(folder)
+            .min_depth(1)
+            .into_iter()
+            .filter_entry(|e| !e.file_name().to_string_lossy().starts_with('.'));
+
+        for entry in walker {
+            let entry = entry.map_err(|e| format!("Failed to read {}: {}", folder.display(), e))?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+
+            let relative = entry
+                .path()
+                .strip_prefix(folder)
+                .unwrap_or(entry.path())
+                .to_string_lossy()
+                .to_string();
+            let metadata = fs::metadata(entry.path())
+                .map_err(|e| format!("Failed to read metadata for {}: {}", relative, e))?;
+            let size = metadata.len();
+            let mtime = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+
+            let sha256 = match known.get(relative.as_str()) {
+                Some(prev) if prev.size == size && prev.mtime == mtime => prev.sha256.clone(),
+                _ => {
+                    unhashed.push((entries.len(), entry.path().to_path_buf()));
+                    String::new()
+                }
+            };
+
+            entries.push(SnapshotEntry {
+                path: relative,
+                size,
+                mtime,
+                sha256,
+            });
+        }
+
+        let paths: Vec<PathBuf> = unhashed.iter().map(|(_, path)| path.clone()).collect();
+        for ((index, _), hash) in unhashed.iter().zip(HashService::global().sha256_many(&paths)) {
+            entries[*index].sha256 = hash?;
+        }
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Ok(Self {
+            folder_path: folder.to_string_lossy().to_string(),
+            session_id,
+            captured_at: Utc::now(),
+            entries,
+        })
+    }
+
+    /// What changed between this snapshot and a later one
+    pub fn diff(&self, later: &FolderSnapshot) -> SnapshotDiff {
+        let before: HashMap<&str, &SnapshotEntry> =
+            self.entries.iter().map(|e| (e.path.as_str(), e)).collect();
+        let after: HashMap<&str, &SnapshotEntry> =
+            later.entries.iter().map(|e| (e.path.as_str(), e)).collect();
+
+        let mut diff = SnapshotDiff::default();
+        let mut added = Vec::new();
+        for entry in &later.entries {
+            match before.get(entry.path.as_str()) {
+                Some(old) if old.sha256 == entry.sha256 => diff.unchanged += 1,
+                Some(_) => diff.modified.push(entry.path.clone()),
+                None => added.push(entry),
+            }
+        }
+
+        // Removed files, by content, for matching against added ones.
+        // Empty files all hash alike, so they never count as moved.
+        let mut removed_by_hash: HashMap<&str, Vec<&SnapshotEntry>> = HashMap::new();
+        for entry in self.entries.iter().rev() {
+            if !after.contains_key(entry.path.as_str()) {
+                if entry.size > 0 {
+                    removed_by_hash.entry(&entry.sha256).or_default().push(entry);
+                } else {
+                    diff.removed.push(entry.path.clone());
+                }
+            }
+        }
+
+        for entry in added {
+            match removed_by_hash
+                .get_mut(entry.sha256.as_str())
+                .and_then(|candidates| candidates.pop())
+            {
+                Some(origin) => diff.moved.push(MovedFile {
+                    from: origin.path.clone(),
+                    to: entry.path.clone(),
+                }),
+                None => diff.added.push(entry.path.clone()),
+            }
+        }
+        diff.removed.extend(
+            removed_by_hash
+                .into_values()
+                .flatten()
+                .map(|e| e.path.clone()),
+        );
+        diff.removed.sort();
+
+        diff
+    }
+
+    /// Compare this snapshot against the folder as it is now
+    pub fn drift(&self) -> Result<DriftReport, String> {
+        let folder = Path::new(&self.folder_path);
+        let current = Self::capture(folder, None, Some(self))?;
+        let diff = self.diff(&current);
+
+        let delta_paths = diff
+            .added
+            .iter()
+            .chain(diff.moved.iter().map(|m| &m.to))
+            .chain(&diff.modified)
+            .map(|p| folder.join(p).to_string_lossy().to_string())
+            .collect();
+
+        Ok(DriftReport {
+            folder_path: self.folder_path.clone(),
+            session_id: self.session_id.clone(),
+            snapshot_at: self.captured_at,
+            diff,
+            delta_paths,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_drift_report() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("docs/report.pdf"), b"report").unwrap();
+        fs::write(root.join("notes.txt"), b"notes").unwrap();
+        fs::write(root.join("old.txt"), b"old").unwrap();
+        fs::write(root.join("keep.txt"), b"keep").unwrap();
+        fs::write(root.join(".DS_Store"), b"hidden").unwrap();
+
+        let snapshot = FolderSnapshot::capture(root, Some("s1".to_string()), None).unwrap();
+        assert_eq!(snapshot.entries.len(), 4);
+
+        fs::rename(root.join("docs/report.pdf"), root.join("report.pdf")).unwrap();
+        fs::write(root.join("notes.txt"), b"edited notes").unwrap();
+        fs::remove_file(root.join("old.txt")).unwrap();
+        fs::write(root.join("new.txt"), b"new").unwrap();
+
+        let report = snapshot.drift().unwrap();
+        assert_eq!(report.session_id.as_deref(), Some("s1"));
+        assert_eq!(report.diff.added, vec!["new.txt"]);
+        assert_eq!(report.diff.removed, vec!["old.txt"]);
+        assert_eq!(report.diff.modified, vec!["notes.txt"]);
+        assert_eq!(
+            report.diff.moved,
+            vec![MovedFile {
+                from: "docs/report.pdf".to_string(),
+                to: "report.pdf".to_string(),
+            }]
+        );
+        assert_eq!(report.diff.unchanged, 1);
+        assert_eq!(report.delta_paths.len(), 3);
+        assert!(report.delta_paths.iter().all(|p| Path::new(p).is_absolute()));
+    }
+
+    #[test]
+    fn test_duplicate_content_moves_pair_once() {
+        let entry = |path: &str, sha256: &str| SnapshotEntry {
+            path: path.to_string(),
+            size: 4,
+            mtime: 0,
+            sha256: sha256.to_string(),
+        };
+        let snapshot = |entries| FolderSnapshot {
+            folder_path: "/t".to_string(),
+            session_id: None,
+            captured_at: Utc::now(),
+            entries,
+        };
+
+        let before = snapshot(vec![entry("a.txt", "h1"), entry("b.txt", "h1")]);
+        let after = snapshot(vec![
+            entry("x/a.txt", "h1"),
+            entry("x/b.txt", "h1"),
+            entry("x/c.txt", "h1"),
+        ]);
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.moved.len(), 2);
+        assert_eq!(diff.added, vec!["x/c.txt"]);
+        assert!(diff.removed.is_empty());
+    }
+}
@@ -5,13 +5,23 @@ use crate::history::entry::{
     FolderHistory, FolderIndexEntry, HistoryIndex, HistorySession, HistorySummary,
     SessionSummary,
 };
+use crate::history::snapshot::FolderSnapshot;
 use chrono::Utc;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use std::fs::{self, File};
 use std::io::{BufReader, BufWriter, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-/// History file extension
-const HISTORY_EXTENSION: &str = "history.json";
+/// History file extension (gzip-compressed JSON)
+const HISTORY_EXTENSION: &str = "history.json.gz";
+
+/// Uncompressed history file extension written by older versions
+const LEGACY_HISTORY_EXTENSION: &str = "history.json";
+
+/// Snapshot file extension (gzip-compressed JSON)
+const SNAPSHOT_EXTENSION: &str = "snapshot.json.gz";
 
 /// Index filename
 const INDEX_FILENAME: &str = "index.json";
@@ -86,7 +96,12 @@ fn validate_folder_path(folder_path: &str) -> Result<PathBuf, String> {
 ///
 /// Files are stored in `~/.config/sentinel/history/`:
 /// - `index.json` - Global index of all organized folders
-/// - `{folder_hash}.history.json` - Per-folder session history
+/// - `{folder_hash}.history.json.gz` - Per-folder session history
+///
+/// - `{folder_hash}.snapshot.json.gz` - Folder contents after the last session
+///
+/// Histories written by older versions as plain `{folder_hash}.history.json`
+/// are read and replaced by the compressed file on the next write.
 pub struct HistoryStore {
     history_dir: PathBuf,
 }
@@ -119,6 +134,18 @@ impl HistoryStore {
             .join(format!("{}.{}", folder_hash, HISTORY_EXTENSION))
     }
 
+    /// Get the path for a folder's uncompressed history file
+    fn legacy_history_file_path(&self, folder_hash: &str) -> PathBuf {
+        self.history_dir
+            .join(format!("{}.{}", folder_hash, LEGACY_HISTORY_EXTENSION))
+    }
+
+    /// Get the path for a folder's snapshot file
+    fn snapshot_file_path(&self, folder_hash: &str) -> PathBuf {
+        self.history_dir
+            .join(format!("{}.{}", folder_hash, SNAPSHOT_EXTENSION))
+    }
+
     /// Get the path for the global index
     fn index_file_path(&self) -> PathBuf {
         self.history_dir.join(INDEX_FILENAME)
@@ -158,6 +185,51 @@ impl HistoryStore {
         Ok(())
     }
 
+    /// Atomically write a folder history as compressed JSON, replacing any
+    /// uncompressed file left by older versions
+    fn write_history(&self, history: &FolderHistory) -> Result<(), String> {
+        self.write_compressed(&self.history_file_path(&history.folder_hash), history)?;
+
+        let legacy_path = self.legacy_history_file_path(&history.folder_hash);
+        if legacy_path.exists() {
+            let _ = fs::remove_file(&legacy_path);
+        }
+
+        Ok(())
+    }
+
+    /// Atomically write gzip-compressed JSON to a file
+    fn write_compressed<T: serde::Serialize>(&self, path: &Path, data: &T) -> Result<(), String> {
+        let temp_path = path.with_extension("tmp");
+
+        let file = File::create(&temp_path)
+            .map_err(|e| format!("Failed to create temp file: {}", e))?;
+
+        let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+
+        serde_json::to_writer(&mut encoder, data)
+            .map_err(|e| format!("Failed to serialize: {}", e))?;
+
+        let mut writer = encoder
+            .finish()
+            .map_err(|e| format!("Failed to compress: {}", e))?;
+
+        writer
+            .flush()
+            .map_err(|e| format!("Failed to flush: {}", e))?;
+
+        // Sync to disk
+        writer
+            .get_ref()
+            .sync_all()
+            .map_err(|e| format!("Failed to sync: {}", e))?;
+
+        // Atomic rename
+        fs::rename(&temp_path, path).map_err(|e| format!("Failed to rename: {}", e))?;
+
+        Ok(())
+    }
+
     /// Save a session to history
     pub fn save_session(&self, folder_path: &str, session: HistorySession) -> Result<(), String> {
         // Validate the folder path before processing
@@ -165,18 +237,17 @@ impl HistoryStore {
         let folder_path = canonical.to_string_lossy().to_string();
 
         let folder_hash = Self::folder_hash(&folder_path);
-        let history_path = self.history_file_path(&folder_hash);
 
         // Load existing history or create new
         let mut history = self.load_history_internal(&folder_path)?.unwrap_or_else(|| {
             FolderHistory::new(folder_path.clone(), folder_hash.clone())
         });
 
-        // Add the session (enforces retention limit)
+        // Add the session on top of the head (enforces the size quota)
         history.add_session(session);
 
         // Save history file
-        self.atomic_write(&history_path, &history)?;
+        self.write_history(&history)?;
 
         // Update global index
         self.update_index_entry(&folder_hash, &folder_path, history.sessions.len())?;
@@ -201,18 +272,23 @@ impl HistoryStore {
     fn load_history_internal(&self, folder_path: &str) -> Result<Option<FolderHistory>, String> {
         let folder_hash = Self::folder_hash(folder_path);
         let history_path = self.history_file_path(&folder_hash);
-
-        if !history_path.exists() {
+        let legacy_path = self.legacy_history_file_path(&folder_hash);
+
+        let parsed = if history_path.exists() {
+            let file = File::open(&history_path)
+                .map_err(|e| format!("Failed to open history file: {}", e))?;
+            serde_json::from_reader(GzDecoder::new(BufReader::new(file)))
+        } else if legacy_path.exists() {
+            let file = File::open(&legacy_path)
+                .map_err(|e| format!("Failed to open history file: {}", e))?;
+            serde_json::from_reader(BufReader::new(file))
+        } else {
             return Ok(None);
-        }
-
-        let file = File::open(&history_path)
-            .map_err(|e| format!("Failed to open history file: {}", e))?;
-
-        let reader = BufReader::new(file);
+        };
 
-        let history: FolderHistory = serde_json::from_reader(reader)
-            .map_err(|e| format!("Failed to parse history file: {}", e))?;
+        let mut history: FolderHistory =
+            parsed.map_err(|e| format!("Failed to parse history file: {}", e))?;
+        history.migrate();
 
         Ok(Some(history))
     }
@@ -226,6 +302,7 @@ impl HistoryStore {
         };
         let folder_hash = Self::folder_hash(&canonical.to_string_lossy());
         self.history_file_path(&folder_hash).exists()
+            || self.legacy_history_file_path(&folder_hash).exists()
     }
 
     /// Get session summaries for a folder
@@ -278,23 +355,87 @@ impl HistoryStore {
         &self,
         folder_path: &str,
         up_to_session_id: &str,
+    ) -> Result<(), String> {
+        self.update_history(folder_path, |history| {
+            history.mark_sessions_undone(up_to_session_id)
+        })
+    }
+
+    /// Mark sessions as redone
+    pub fn mark_sessions_redone(
+        &self,
+        folder_path: &str,
+        target_session_id: &str,
+    ) -> Result<(), String> {
+        self.update_history(folder_path, |history| {
+            history.mark_sessions_redone(target_session_id)
+        })
+    }
+
+    /// Load, modify and save the history of a folder
+    fn update_history(
+        &self,
+        folder_path: &str,
+        update: impl FnOnce(&mut FolderHistory) -> Result<(), String>,
     ) -> Result<(), String> {
         // Validate the folder path
         let canonical = validate_folder_path(folder_path)?;
         let folder_path = canonical.to_string_lossy().to_string();
 
-        let folder_hash = Self::folder_hash(&folder_path);
-        let history_path = self.history_file_path(&folder_hash);
-
         let mut history = self
             .load_history_internal(&folder_path)?
             .ok_or_else(|| format!("No history found for {}", folder_path))?;
 
-        history.mark_sessions_undone(up_to_session_id);
+        update(&mut history)?;
 
-        self.atomic_write(&history_path, &history)?;
+        self.write_history(&history)
+    }
 
-        Ok(())
+    /// Snapshot a folder as it is now, tagged with its current session
+    ///
+    /// Hashes from the previous snapshot are reused for files whose size
+    /// and mtime haven't changed.
+    pub fn snapshot_folder(&self, folder_path: &str) -> Result<FolderSnapshot, String> {
+        // Validate the folder path
+        let canonical = validate_folder_path(folder_path)?;
+        let folder_path = canonical.to_string_lossy().to_string();
+
+        let head = self
+            .load_history_internal(&folder_path)?
+            .and_then(|history| history.head);
+        let previous = self.load_snapshot_internal(&folder_path)?;
+        let snapshot = FolderSnapshot::capture(&canonical, head, previous.as_ref())?;
+
+        let folder_hash = Self::folder_hash(&folder_path);
+        self.write_compressed(&self.snapshot_file_path(&folder_hash), &snapshot)?;
+
+        tracing::debug!(
+            "Saved snapshot of {} ({} files)",
+            folder_path,
+            snapshot.entries.len()
+        );
+
+        Ok(snapshot)
+    }
+
+    /// Load the last snapshot of a folder
+    pub fn load_snapshot(&self, folder_path: &str) -> Result<Option<FolderSnapshot>, String> {
+        let canonical = validate_folder_path(folder_path)?;
+        self.load_snapshot_internal(&canonical.to_string_lossy())
+    }
+
+    fn load_snapshot_internal(&self, folder_path: &str) -> Result<Option<FolderSnapshot>, String> {
+        let snapshot_path = self.snapshot_file_path(&Self::folder_hash(folder_path));
+        if !snapshot_path.exists() {
+            return Ok(None);
+        }
+
+        let file = File::open(&snapshot_path)
+            .map_err(|e| format!("Failed to open snapshot file: {}", e))?;
+        let snapshot = serde_json::from_reader(GzDecoder::new(BufReader::new(file)))
+            .map_err(|e| format!("Failed to parse snapshot file: {}", e))?;
+
+        Ok(Some(snapshot))
     }
 
     /// Delete history for a folder
@@ -304,11 +445,16 @@ impl HistoryStore {
         let folder_path = canonical.to_string_lossy().to_string();
 
         let folder_hash = Self::folder_hash(&folder_path);
-        let history_path = self.history_file_path(&folder_hash);
 
-        if history_path.exists() {
-            fs::remove_file(&history_path)
-                .map_err(|e| format!("Failed to delete history file: {}", e))?;
+        for history_path in [
+            self.history_file_path(&folder_hash),
+            self.legacy_history_file_path(&folder_hash),
+            self.snapshot_file_path(&folder_hash),
+        ] {
+            if history_path.exists() {
+                fs::remove_file(&history_path)
+                    .map_err(|e| format!("Failed to delete history file: {}", e))?;
+            }
         }
 
         // Remove from index
@@ -384,6 +530,7 @@ impl HistoryStore {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::history::entry::HISTORY_SCHEMA_VERSION;
     use tempfile::TempDir;
 
     /// Create test store with history dir and target folders
@@ -406,6 +553,7 @@ mod tests {
             operations: vec![],
             files_affected: 5,
             undone: false,
+            parent_session_id: None,
         }
     }
 
@@ -492,4 +640,76 @@ mod tests {
         assert!(!store.has_history(&folder_path));
         assert!(store.list_folders().unwrap().is_empty());
     }
+
+    #[test]
+    fn test_snapshot_and_drift() {
+        let (store, _history_dir, target_dir) = create_test_store();
+        let folder_path = target_dir.path().to_string_lossy().to_string();
+        fs::write(target_dir.path().join("a.txt"), b"a").unwrap();
+
+        store
+            .save_session(&folder_path, create_test_session("session-1", &folder_path))
+            .unwrap();
+        let snapshot = store.snapshot_folder(&folder_path).unwrap();
+        assert_eq!(snapshot.session_id.as_deref(), Some("session-1"));
+        assert_eq!(snapshot.entries.len(), 1);
+
+        fs::write(target_dir.path().join("b.txt"), b"b").unwrap();
+        let report = store
+            .load_snapshot(&folder_path)
+            .unwrap()
+            .unwrap()
+            .drift()
+            .unwrap();
+        assert_eq!(report.diff.added, vec!["b.txt"]);
+        assert_eq!(report.diff.unchanged, 1);
+
+        store.delete_history(&folder_path).unwrap();
+        assert!(store.load_snapshot(&folder_path).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_reads_legacy_history_and_compresses_on_write() {
+        let (store, _history_dir, target_dir) = create_test_store();
+        let folder_path = target_dir.path().canonicalize().unwrap().to_string_lossy().to_string();
+        let folder_hash = HistoryStore::folder_hash(&folder_path);
+
+        // Version 1 file: pretty JSON without parents or head
+        let mut legacy = FolderHistory::new(folder_path.clone(), folder_hash.clone());
+        legacy.version = 1;
+        legacy.sessions = vec![create_test_session("session-1", &folder_path)];
+        let legacy_path = store.legacy_history_file_path(&folder_hash);
+        fs::write(&legacy_path, serde_json::to_string_pretty(&legacy).unwrap()).unwrap();
+
+        assert!(store.has_history(&folder_path));
+        store
+            .save_session(&folder_path, create_test_session("session-2", &folder_path))
+            .unwrap();
+
+        assert!(!legacy_path.exists());
+        assert!(store.history_file_path(&folder_hash).exists());
+        let history = store.load_history(&folder_path).unwrap().unwrap();
+        assert_eq!(history.version, HISTORY_SCHEMA_VERSION);
+        assert_eq!(history.sessions[0].parent_session_id.as_deref(), Some("session-1"));
+    }
+
+    #[test]
+    fn test_mark_undone_and_redone() {
+        let (store, _history_dir, target_dir) = create_test_store();
+        let folder_path = target_dir.path().to_string_lossy().to_string();
+        for id in ["session-1", "session-2"] {
+            store
+                .save_session(&folder_path, create_test_session(id, &folder_path))
+                .unwrap();
+        }
+
+        store.mark_sessions_undone(&folder_path, "session-1").unwrap();
+        let summaries = store.get_session_summaries(&folder_path).unwrap();
+        assert!(summaries.iter().all(|s| s.undone && !s.is_head));
+
+        store.mark_sessions_redone(&folder_path, "session-1").unwrap();
+        let summaries = store.get_session_summaries(&folder_path).unwrap();
+        assert!(summaries[1].is_head && !summaries[1].undone);
+        assert!(summaries[0].undone);
+    }
 }
@@ -3,6 +3,7 @@
 use crate::history::checksum::compute_file_checksum;
 use crate::history::entry::{HistoryOperation, HistorySession, OperationRecord};
 use crate::history::store::HistoryStore;
+use crate::quarantine::QuarantineManager;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
@@ -106,18 +107,20 @@ pub fn preflight_undo(
         .load_history(folder_path)?
         .ok_or_else(|| format!("No history found for {}", folder_path))?;
 
-    // Find the target session index
-    let target_idx = history
-        .sessions
-        .iter()
-        .position(|s| s.session_id == target_session_id)
-        .ok_or_else(|| format!("Session {} not found", target_session_id))?;
+    // Collect all sessions to undo (from the head back to target, inclusive)
+    let sessions_to_undo: Vec<&HistorySession> = history.undo_chain(target_session_id)?;
 
-    // Collect all operations to undo (from current to target, inclusive)
-    // Sessions are stored most-recent-first, so we take sessions from 0 to target_idx
-    let sessions_to_undo: Vec<&HistorySession> =
-        history.sessions[0..=target_idx].iter().collect();
+    Ok(preflight_operations(
+        sessions_to_undo.iter().flat_map(|s| s.operations.iter()),
+        check_operation_conflicts,
+    ))
+}
 
+/// Tally the conflicts `check` reports for each operation
+pub(crate) fn preflight_operations<'a>(
+    operations: impl IntoIterator<Item = &'a HistoryOperation>,
+    check: impl Fn(&HistoryOperation) -> Result<Vec<ConflictInfo>, String>,
+) -> UndoPreflightResult {
     let mut modified_files = Vec::new();
     let mut missing_files = Vec::new();
     let mut blocking_files = Vec::new();
@@ -125,36 +128,28 @@ pub fn preflight_undo(
     let mut conflicted_operations = 0;
     let mut total_operations = 0;
 
-    // Check each session's operations
-    for session in &sessions_to_undo {
-        // Skip already-undone sessions
-        if session.undone {
-            continue;
-        }
+    for op in operations {
+        total_operations += 1;
 
-        for op in &session.operations {
-            total_operations += 1;
-
-            // Check for conflicts based on operation type
-            match check_operation_conflicts(op) {
-                Ok(conflicts) => {
-                    if conflicts.is_empty() {
-                        safe_operations += 1;
-                    } else {
-                        conflicted_operations += 1;
-                        for conflict in conflicts {
-                            match conflict.conflict_type {
-                                ConflictType::Modified => modified_files.push(conflict),
-                                ConflictType::Deleted => missing_files.push(conflict.path),
-                                ConflictType::Blocking => blocking_files.push(conflict.path),
-                            }
+        // Check for conflicts based on operation type
+        match check(op) {
+            Ok(conflicts) => {
+                if conflicts.is_empty() {
+                    safe_operations += 1;
+                } else {
+                    conflicted_operations += 1;
+                    for conflict in conflicts {
+                        match conflict.conflict_type {
+                            ConflictType::Modified => modified_files.push(conflict),
+                            ConflictType::Deleted => missing_files.push(conflict.path),
+                            ConflictType::Blocking => blocking_files.push(conflict.path),
                         }
                     }
                 }
-                Err(e) => {
-                    tracing::warn!("Error checking operation {}: {}", op.id, e);
-                    conflicted_operations += 1;
-                }
+            }
+            Err(e) => {
+                tracing::warn!("Error checking operation {}: {}", op.id, e);
+                conflicted_operations += 1;
             }
         }
     }
@@ -163,7 +158,7 @@ pub fn preflight_undo(
     let can_proceed = blocking_files.is_empty()
         && (safe_operations > 0 || modified_files.is_empty() && missing_files.is_empty());
 
-    Ok(UndoPreflightResult {
+    UndoPreflightResult {
         can_proceed,
         modified_files,
         missing_files,
@@ -171,11 +166,13 @@ pub fn preflight_undo(
         safe_operations,
         conflicted_operations,
         total_operations,
-    })
+    }
 }
 
 /// Check a single operation for conflicts
-fn check_operation_conflicts(op: &HistoryOperation) -> Result<Vec<ConflictInfo>, String> {
+pub(crate) fn check_operation_conflicts(
+    op: &HistoryOperation,
+) -> Result<Vec<ConflictInfo>, String> {
     let mut conflicts = Vec::new();
 
     match &op.operation {
@@ -288,12 +285,24 @@ fn check_operation_conflicts(op: &HistoryOperation) -> Result<Vec<ConflictInfo>,
             }
         }
 
-        OperationRecord::Quarantine { path, quarantine_path } => {
+        OperationRecord::Quarantine {
+            path,
+            quarantine_path,
+            item_id,
+        } => {
             // To undo quarantine, move from quarantine back to original
             let qpath = Path::new(quarantine_path);
             let orig_path = Path::new(path);
 
-            if !qpath.exists() {
+            // Managed items must still be in the quarantine manifest
+            let missing = match item_id {
+                Some(id) => !matches!(
+                    QuarantineManager::new().and_then(|manager| manager.get(id)),
+                    Ok(Some(_))
+                ),
+                None => !qpath.exists(),
+            };
+            if missing {
                 conflicts.push(ConflictInfo {
                     path: quarantine_path.clone(),
                     expected_sha256: String::new(),
@@ -311,6 +320,18 @@ fn check_operation_conflicts(op: &HistoryOperation) -> Result<Vec<ConflictInfo>,
                 });
             }
         }
+
+        OperationRecord::Restore { path, .. } => {
+            // To undo a restore, the file must still be at its original path
+            if !Path::new(path).exists() {
+                conflicts.push(ConflictInfo {
+                    path: path.clone(),
+                    expected_sha256: String::new(),
+                    current_sha256: None,
+                    conflict_type: ConflictType::Deleted,
+                });
+            }
+        }
     }
 
     Ok(conflicts)
@@ -328,21 +349,10 @@ pub fn collect_undo_operations(
         .load_history(folder_path)?
         .ok_or_else(|| format!("No history found for {}", folder_path))?;
 
-    // Find the target session index
-    let target_idx = history
-        .sessions
-        .iter()
-        .position(|s| s.session_id == target_session_id)
-        .ok_or_else(|| format!("Session {} not found", target_session_id))?;
-
     let mut undo_operations = Vec::new();
 
     // Collect operations from sessions (most recent first)
-    for session in &history.sessions[0..=target_idx] {
-        if session.undone {
-            continue;
-        }
-
+    for session in history.undo_chain(target_session_id)? {
         // Add operations in reverse order within each session
         for op in session.operations.iter().rev() {
             undo_operations.push(op.undo_operation.clone());
@@ -0,0 +1,21 @@
+//! Inbox rules for watched folders.
+//!
+//! Standing rules ("move new invoices to Invoices/", "quarantine *.exe")
+//! that run automatically when the watcher sees a new file, or a file renamed
+//! into the folder.
+//!
+//! ## Modules
+//! - `rule` - Rule and action definitions
+//! - `store` - JSON persistence for rules
+//! - `runner` - Rule evaluation and WAL-backed execution
+
+#![allow(dead_code)]
+#![allow(unused_imports)]
+
+pub mod rule;
+pub mod runner;
+pub mod store;
+
+pub use rule::*;
+pub use runner::*;
+pub use store::*;
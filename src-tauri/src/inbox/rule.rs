@@ -0,0 +1,226 @@
+//! Inbox rule definitions.
+//!
+//! An inbox rule is a standing instruction attached to a watched folder:
+//! when a new file (or one renamed into the folder) matches the rule's DSL
+//! condition, the rule's action is applied to it.
+
+use crate::ai::rules::{
+    capture_pattern, DocumentContentSource, Expression, RenameContext, RenameTemplate,
+    RuleEvaluator, RuleParser, SimpleVectorIndex, VirtualFile,
+};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Action applied to a file that matches an inbox rule
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InboxAction {
+    /// Move the file into a folder (relative to the watched folder, or an
+    /// absolute path inside the user's home directory)
+    Move { destination: String },
+    /// Rename the file in place using a template (see [`RenameTemplate`])
+    Rename { template: String },
+    /// Move the file into quarantine
+    Quarantine,
+}
+
+impl InboxAction {
+    /// Get a human-readable description of the action
+    pub fn description(&self) -> String {
+        match self {
+            InboxAction::Move { destination } => format!("Move to {}", destination),
+            InboxAction::Rename { template } => format!("Rename to {}", template),
+            InboxAction::Quarantine => "Quarantine".to_string(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// A standing rule attached to a watched folder
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InboxRule {
+    /// Unique rule ID
+    pub id: String,
+    /// User-facing rule name
+    pub name: String,
+    /// Watched folder this rule applies to
+    pub folder: String,
+    /// Rule DSL condition (e.g. `file.ext == 'pdf' AND file.name.contains('invoice')`)
+    pub condition: String,
+    /// Action to apply when the condition matches
+    pub action: InboxAction,
+    /// Whether the rule is active
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Higher priority rules are evaluated first; the first match wins
+    #[serde(default)]
+    pub priority: i32,
+    /// When the rule was created
+    pub created_at: DateTime<Utc>,
+    /// Files renamed by this rule so far; the next rename renders `{n}` as
+    /// this count plus one
+    #[serde(default)]
+    pub rename_count: u64,
+}
+
+impl InboxRule {
+    /// Create a new enabled rule with a fresh ID
+    pub fn new(name: String, folder: String, condition: String, action: InboxAction) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            folder,
+            condition,
+            action,
+            enabled: true,
+            priority: 0,
+            created_at: Utc::now(),
+            rename_count: 0,
+        }
+    }
+
+    /// Parse the rule condition into an expression
+    pub fn parse_condition(&self) -> Result<Expression, String> {
+        RuleParser::parse(&self.condition)
+            .map_err(|e| format!("Invalid condition for rule '{}': {}", self.name, e))
+    }
+
+    /// Validate the rule definition without touching the filesystem
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Rule name cannot be empty".to_string());
+        }
+        if self.folder.trim().is_empty() {
+            return Err(format!("Rule '{}' has no folder", self.name));
+        }
+
+        self.parse_condition()?;
+
+        match &self.action {
+            InboxAction::Move { destination } => {
+                if destination.trim().is_empty() {
+                    return Err(format!(
+                        "Rule '{}' has an empty move destination",
+                        self.name
+                    ));
+                }
+            }
+            InboxAction::Rename { template } => {
+                parse_rename_template(template, &self.condition).map_err(|e| {
+                    format!("Rule '{}' has an invalid rename template: {}", self.name, e)
+                })?;
+            }
+            InboxAction::Quarantine => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// Render a rename template for a file matched by `condition`.
+///
+/// See [`RenameTemplate`] for the placeholders; `counter` is the value of
+/// `{n}`. Capture groups come from the
+/// condition's `file.name MATCHES` pattern, and content or photo fields are
+/// read from the file only when the template uses them.
+pub fn render_rename_template(
+    template: &str,
+    condition: &str,
+    file: &VirtualFile,
+    counter: u64,
+) -> Result<String, String> {
+    let (template, pattern) = parse_rename_template(template, condition)?;
+    let mut context = RenameContext::new(counter);
+    if let Some(pattern) = &pattern {
+        context = context.with_captures(pattern, &file.name);
+    }
+
+    let index = SimpleVectorIndex::new();
+    let content = DocumentContentSource::open_default();
+    let fields = RuleEvaluator::new(&index).with_content(&content);
+    template.render(&context, |field| fields.get_field_value(field, file))
+}
+
+/// Parse a rename template along with the capture pattern of its condition
+fn parse_rename_template(
+    template: &str,
+    condition: &str,
+) -> Result<(RenameTemplate, Option<Regex>), String> {
+    let template = RenameTemplate::parse(template).map_err(|e| e.to_string())?;
+    let condition = RuleParser::parse(condition).map_err(|e| e.to_string())?;
+    let pattern = capture_pattern(&condition).and_then(|p| Regex::new(p).ok());
+    template.validate_captures(pattern.as_ref())?;
+    Ok((template, pattern))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_file() -> VirtualFile {
+        VirtualFile::new(
+            "invoice".to_string(),
+            Some("pdf".to_string()),
+            1024,
+            "/test/invoice.pdf".to_string(),
+            Some(1700000000000),
+            None,
+            None,
+            false,
+            false,
+        )
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_condition() {
+        let rule = InboxRule::new(
+            "Bad".to_string(),
+            "/tmp/inbox".to_string(),
+            "file.ext ==".to_string(),
+            InboxAction::Quarantine,
+        );
+        assert!(rule.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_rejects_separator_in_template() {
+        let rule = InboxRule::new(
+            "Rename".to_string(),
+            "/tmp/inbox".to_string(),
+            "file.ext == 'pdf'".to_string(),
+            InboxAction::Rename {
+                template: "../{name}.{ext}".to_string(),
+            },
+        );
+        assert!(rule.validate().is_err());
+    }
+
+    #[test]
+    fn test_render_rename_template() {
+        let rendered =
+            render_rename_template("{date}_{name}.{ext}", "file.ext == 'pdf'", &test_file(), 1);
+        assert_eq!(rendered.unwrap(), "2023-11-14_invoice.pdf");
+
+        let rendered = render_rename_template(
+            "{1|upper}-{n:02}.{ext}",
+            "file.name MATCHES '^(inv)oice'",
+            &test_file(),
+            3,
+        );
+        assert_eq!(rendered.unwrap(), "INV-03.pdf");
+    }
+
+    #[test]
+    fn test_action_serialization() {
+        let action = InboxAction::Move {
+            destination: "Invoices".to_string(),
+        };
+        let json = serde_json::to_string(&action).unwrap();
+        assert_eq!(json, r#"{"type":"move","destination":"Invoices"}"#);
+    }
+}
@@ -0,0 +1,577 @@
+//! Evaluation and execution of inbox rules.
+//!
+//! When the watcher reports a new file, or a file renamed into the folder, the
+//! folder's active rules are evaluated in priority order with the rule DSL.
+//! The first matching rule is turned into a WAL journal, executed by the
+//! `ExecutionEngine`, and recorded as a history session so it can be undone
+//! like any other run.
+
+use crate::ai::rules::{DocumentContentSource, RuleEvaluator, SimpleVectorIndex, VirtualFile};
+use crate::execution::ExecutionEngine;
+use crate::history::{history_operations_from_journal, HistorySession, HistoryStore};
+use crate::inbox::rule::{render_rename_template, InboxAction, InboxRule};
+use crate::inbox::store::InboxRuleStore;
+use crate::quarantine::QuarantineManager;
+use crate::security::PathValidator;
+use crate::wal::entry::{WALJournal, WALOperationType};
+use crate::wal::journal::WALManager;
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// How long a path touched by an inbox rule is ignored by the watcher.
+///
+/// Covers both files currently being processed (the debouncer often reports
+/// a create and a modify for the same download) and files produced by a rule
+/// (a rename shows up as a new file in the same folder).
+const SUPPRESSION_WINDOW: Duration = Duration::from_secs(10);
+
+/// Payload emitted after an inbox rule has been applied
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InboxRuleApplied {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub watched_folder: String,
+    pub source_path: String,
+    pub result_path: String,
+    /// History session ID (usable with `history_undo_execute`)
+    pub session_id: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// Operations needed to apply a rule to one file
+#[derive(Debug, Clone)]
+pub struct InboxPlan {
+    pub operations: Vec<WALOperationType>,
+    /// Where the file ends up once the plan has executed
+    pub result_path: PathBuf,
+}
+
+/// Shared inbox state: the rule store plus watcher loop suppression
+pub struct InboxState {
+    store: RwLock<InboxRuleStore>,
+    recent_paths: Mutex<HashMap<PathBuf, Instant>>,
+}
+
+/// Global inbox state
+pub type InboxHandle = Arc<InboxState>;
+
+/// Create a new inbox handle backed by the default rule store
+pub fn create_inbox_handle() -> InboxHandle {
+    Arc::new(InboxState::new(InboxRuleStore::new()))
+}
+
+impl InboxState {
+    pub fn new(store: InboxRuleStore) -> Self {
+        Self {
+            store: RwLock::new(store),
+            recent_paths: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Read access to the rule store
+    pub fn read_store(&self) -> RwLockReadGuard<'_, InboxRuleStore> {
+        self.store.read().unwrap_or_else(|poisoned| {
+            tracing::warn!("Inbox rule store lock was poisoned, recovering...");
+            poisoned.into_inner()
+        })
+    }
+
+    /// Write access to the rule store
+    pub fn write_store(&self) -> RwLockWriteGuard<'_, InboxRuleStore> {
+        self.store.write().unwrap_or_else(|poisoned| {
+            tracing::warn!("Inbox rule store lock was poisoned, recovering...");
+            poisoned.into_inner()
+        })
+    }
+
+    /// Claim a path for processing.
+    ///
+    /// Returns false if the path was claimed or produced by a rule within the
+    /// suppression window.
+    pub fn try_claim(&self, path: &Path) -> bool {
+        let mut recent = self
+            .recent_paths
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = Instant::now();
+        recent.retain(|_, at| now.duration_since(*at) < SUPPRESSION_WINDOW);
+
+        if recent.contains_key(path) {
+            return false;
+        }
+        recent.insert(path.to_path_buf(), now);
+        true
+    }
+
+    /// Mark a path as produced by a rule so the watcher ignores it
+    pub fn suppress(&self, path: &Path) {
+        let mut recent = self
+            .recent_paths
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        recent.insert(path.to_path_buf(), Instant::now());
+    }
+}
+
+/// Find the first rule whose condition matches the file.
+///
+/// Rules are expected in evaluation order. Rules with unparseable conditions
+/// or evaluation errors are logged and skipped. `content` is shared across a
+/// batch of files so documents are parsed at most once.
+pub fn find_matching_rule(
+    rules: &[InboxRule],
+    file: &VirtualFile,
+    content: &DocumentContentSource,
+) -> Option<InboxRule> {
+    let index = SimpleVectorIndex::build_from_files(std::slice::from_ref(file));
+    let evaluator = RuleEvaluator::new(&index).with_content(content);
+
+    for rule in rules {
+        let expr = match rule.parse_condition() {
+            Ok(expr) => expr,
+            Err(e) => {
+                tracing::warn!(rule = %rule.name, error = %e, "Skipping inbox rule");
+                continue;
+            }
+        };
+
+        match evaluator.evaluate(&expr, file) {
+            Ok(true) => return Some(rule.clone()),
+            Ok(false) => {}
+            Err(e) => {
+                tracing::warn!(rule = %rule.name, error = %e, "Inbox rule evaluation failed");
+            }
+        }
+    }
+
+    None
+}
+
+/// Resolve a move destination for a rule.
+///
+/// Relative destinations must stay inside the watched folder; absolute
+/// destinations must stay inside the user's home directory.
+pub fn resolve_move_destination(
+    destination: &str,
+    watched_folder: &Path,
+) -> Result<PathBuf, String> {
+    if destination.starts_with('/') {
+        let home = dirs::home_dir().ok_or("Could not determine home directory")?;
+        PathValidator::validate_destination(destination, &home, true)
+    } else {
+        PathValidator::validate_destination(destination, watched_folder, false)
+    }
+}
+
+/// Pick a path in `dir` named `file_name`, adding a counter suffix if taken
+fn unique_path_in(dir: &Path, file_name: &str) -> PathBuf {
+    let candidate = dir.join(file_name);
+    if !candidate.exists() {
+        return candidate;
+    }
+
+    let name = Path::new(file_name);
+    let stem = name
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "file".to_string());
+    let ext = name
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+
+    for counter in 1..=1000 {
+        let candidate = dir.join(format!("{}_{}{}", stem, counter, ext));
+        if !candidate.exists() {
+            return candidate;
+        }
+    }
+    dir.join(format!("{}_{}{}", stem, uuid::Uuid::new_v4(), ext))
+}
+
+/// Build the operations needed to apply a rule's action to a file.
+///
+/// Returns a plan with no operations when the action would leave the file
+/// where it is (e.g. a rename template that renders to the current name).
+pub fn plan_rule_action(
+    rule: &InboxRule,
+    file_path: &Path,
+    watched_folder: &Path,
+) -> Result<InboxPlan, String> {
+    let file_name = file_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Invalid file path: {}", file_path.display()))?;
+
+    let mut operations = Vec::new();
+
+    let result_path = match &rule.action {
+        InboxAction::Move { destination } => {
+            let dest_dir = resolve_move_destination(destination, watched_folder)?;
+            if file_path.parent() == Some(dest_dir.as_path()) {
+                return Ok(InboxPlan {
+                    operations,
+                    result_path: file_path.to_path_buf(),
+                });
+            }
+            if !dest_dir.exists() {
+                operations.push(WALOperationType::CreateFolder {
+                    path: dest_dir.clone(),
+                });
+            }
+            let dest = unique_path_in(&dest_dir, &file_name);
+            operations.push(WALOperationType::Move {
+                source: file_path.to_path_buf(),
+                destination: dest.clone(),
+            });
+            dest
+        }
+        InboxAction::Rename { template } => {
+            let file = VirtualFile::from_path(file_path)
+                .map_err(|e| format!("Failed to read {}: {}", file_path.display(), e))?;
+            let counter = rule.rename_count + 1;
+            let new_name = render_rename_template(template, &rule.condition, &file, counter)
+                .map_err(|e| format!("Rule '{}' cannot rename {}: {}", rule.name, file_name, e))?;
+            if new_name == file_name {
+                return Ok(InboxPlan {
+                    operations,
+                    result_path: file_path.to_path_buf(),
+                });
+            }
+            let parent = file_path
+                .parent()
+                .ok_or_else(|| format!("Cannot determine parent of {}", file_path.display()))?;
+            let target = unique_path_in(parent, &new_name);
+            let target_name = target
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or(new_name);
+            operations.push(WALOperationType::Rename {
+                path: file_path.to_path_buf(),
+                new_name: target_name,
+            });
+            target
+        }
+        InboxAction::Quarantine => {
+            operations.push(WALOperationType::quarantine(file_path.to_path_buf())?);
+            // Quarantined items are stored by content under the quarantine root
+            QuarantineManager::new()?.base_path().clone()
+        }
+    };
+
+    Ok(InboxPlan {
+        operations,
+        result_path,
+    })
+}
+
+/// Apply a rule to a file through the WAL and record it in history.
+///
+/// Returns `Ok(None)` when the rule had nothing to do.
+pub async fn apply_rule(
+    rule: &InboxRule,
+    file_path: &Path,
+    watched_folder: &Path,
+) -> Result<Option<InboxRuleApplied>, String> {
+    let plan = plan_rule_action(rule, file_path, watched_folder)?;
+    apply_plan(rule, plan, file_path, watched_folder).await
+}
+
+/// Execute a plan from `plan_rule_action` through the WAL and record it in
+/// history; returns `Ok(None)` for an empty plan
+pub async fn apply_plan(
+    rule: &InboxRule,
+    plan: InboxPlan,
+    file_path: &Path,
+    watched_folder: &Path,
+) -> Result<Option<InboxRuleApplied>, String> {
+    if plan.operations.is_empty() {
+        return Ok(None);
+    }
+
+    let job_id = format!("inbox-{}", uuid::Uuid::new_v4());
+    let mut journal = WALJournal::new(job_id.clone(), watched_folder.to_path_buf());
+
+    // Moves depend on their destination folder being created first
+    let mut previous = None;
+    for op in plan.operations {
+        let id = match previous {
+            Some(dep) => journal.add_operation_with_deps(op, vec![dep])?,
+            None => journal.add_operation(op)?,
+        };
+        previous = Some(id);
+    }
+
+    let wal_manager = WALManager::new();
+    wal_manager
+        .save_journal(&journal)
+        .map_err(|e| format!("Failed to save WAL journal: {}", e.message))?;
+
+    let engine = ExecutionEngine::new();
+    let result = engine.execute_journal(&job_id).await?;
+
+    let mut applied = InboxRuleApplied {
+        rule_id: rule.id.clone(),
+        rule_name: rule.name.clone(),
+        watched_folder: watched_folder.to_string_lossy().to_string(),
+        source_path: file_path.to_string_lossy().to_string(),
+        result_path: plan.result_path.to_string_lossy().to_string(),
+        session_id: job_id.clone(),
+        success: result.success,
+        error: None,
+    };
+
+    if !result.success {
+        // Leave the journal in place so recovery can resume or roll it back
+        applied.error = Some(result.errors.join("; "));
+        return Ok(Some(applied));
+    }
+
+    let _ = wal_manager.discard_journal(&job_id);
+
+    let session = HistorySession {
+        session_id: job_id,
+        user_instruction: format!("Inbox rule: {}", rule.name),
+        plan_description: format!("{} ({})", rule.action.description(), rule.condition),
+        executed_at: Utc::now(),
+        target_folder: watched_folder.to_string_lossy().to_string(),
+        operations: history_operations_from_journal(&journal)?,
+        files_affected: 1,
+        undone: false,
+        parent_session_id: None,
+    };
+
+    if let Err(e) = HistoryStore::new().save_session(&applied.watched_folder, session) {
+        tracing::warn!(
+            error = %e,
+            folder = %applied.watched_folder,
+            "Failed to save inbox rule history (undo will not be available)"
+        );
+    }
+
+    Ok(Some(applied))
+}
+
+/// Evaluate a folder's inbox rules against a file reported by the watcher.
+///
+/// Blocking (rule conditions and templates may parse documents), so the
+/// watcher calls it off the debouncer thread with one `content` source per
+/// batch of events. The plan is built here and its output path suppressed
+/// before execution starts on the async runtime, so the rule's own rename or
+/// move never comes back through the watcher. The outcome is emitted as
+/// `sentinel://inbox-rule-applied`. Returns true if the path was handled (or
+/// recently handled) by a rule.
+pub fn process_file_event(
+    app: &AppHandle,
+    inbox: &InboxHandle,
+    path: &Path,
+    watched_folder: &str,
+    content: &DocumentContentSource,
+) -> bool {
+    let rules = inbox.read_store().active_rules_for_folder(watched_folder);
+    if rules.is_empty() {
+        return false;
+    }
+
+    if !inbox.try_claim(path) {
+        tracing::debug!(path = %path.display(), "Skipping recently handled inbox path");
+        return true;
+    }
+
+    let file = match VirtualFile::from_path(path) {
+        Ok(file) => file,
+        Err(_) => return false,
+    };
+
+    let mut rule = match find_matching_rule(&rules, &file, content) {
+        Some(rule) => rule,
+        None => return false,
+    };
+
+    // Count the rename before it runs so files handled concurrently by the
+    // same rule get distinct `{n}` values
+    if matches!(rule.action, InboxAction::Rename { .. }) {
+        match inbox.write_store().count_rename(&rule.id) {
+            Ok(previous) => rule.rename_count = previous,
+            Err(e) => tracing::warn!(rule = %rule.name, error = %e, "Failed to count inbox rename"),
+        }
+    }
+
+    let watched_folder = PathBuf::from(watched_folder);
+    let plan = plan_rule_action(&rule, path, &watched_folder);
+    if let Ok(plan) = &plan {
+        inbox.suppress(&plan.result_path);
+    }
+
+    let app = app.clone();
+    let inbox = Arc::clone(inbox);
+    let path = path.to_path_buf();
+
+    tauri::async_runtime::spawn(async move {
+        tracing::info!(rule = %rule.name, path = %path.display(), "Applying inbox rule");
+
+        let outcome = match plan {
+            Ok(plan) => apply_plan(&rule, plan, &path, &watched_folder).await,
+            Err(e) => Err(e),
+        };
+        let applied = match outcome {
+            Ok(Some(applied)) => applied,
+            Ok(None) => return,
+            Err(e) => InboxRuleApplied {
+                rule_id: rule.id.clone(),
+                rule_name: rule.name.clone(),
+                watched_folder: watched_folder.to_string_lossy().to_string(),
+                source_path: path.to_string_lossy().to_string(),
+                result_path: path.to_string_lossy().to_string(),
+                session_id: String::new(),
+                success: false,
+                error: Some(e),
+            },
+        };
+
+        if applied.success {
+            inbox.suppress(Path::new(&applied.result_path));
+        } else {
+            tracing::warn!(
+                rule = %rule.name,
+                path = %path.display(),
+                error = ?applied.error,
+                "Inbox rule failed"
+            );
+        }
+
+        if let Err(e) = app.emit("sentinel://inbox-rule-applied", &applied) {
+            tracing::warn!(error = %e, "Failed to emit inbox rule event");
+        }
+    });
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn rule_with(action: InboxAction, condition: &str) -> InboxRule {
+        InboxRule::new(
+            "test".to_string(),
+            "/unused".to_string(),
+            condition.to_string(),
+            action,
+        )
+    }
+
+    #[test]
+    fn test_find_matching_rule_respects_order() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("invoice.pdf");
+        std::fs::write(&path, b"pdf").unwrap();
+        let file = VirtualFile::from_path(&path).unwrap();
+
+        let first = rule_with(InboxAction::Quarantine, "file.ext == 'jpg'");
+        let second = rule_with(InboxAction::Quarantine, "file.name.contains('invoice')");
+        let third = rule_with(InboxAction::Quarantine, "file.ext == 'pdf'");
+
+        let content = DocumentContentSource::new(None);
+        let matched =
+            find_matching_rule(&[first, second.clone(), third], &file, &content).unwrap();
+        assert_eq!(matched.id, second.id);
+    }
+
+    #[test]
+    fn test_plan_move_creates_folder_and_avoids_collision() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("invoice.pdf");
+        std::fs::write(&path, b"pdf").unwrap();
+
+        let rule = rule_with(
+            InboxAction::Move {
+                destination: "Invoices".to_string(),
+            },
+            "file.ext == 'pdf'",
+        );
+        let plan = plan_rule_action(&rule, &path, dir.path()).unwrap();
+        assert_eq!(plan.operations.len(), 2);
+        assert!(matches!(
+            plan.operations[0],
+            WALOperationType::CreateFolder { .. }
+        ));
+        assert!(plan.result_path.ends_with("Invoices/invoice.pdf"));
+
+        std::fs::create_dir(dir.path().join("Invoices")).unwrap();
+        std::fs::write(dir.path().join("Invoices/invoice.pdf"), b"old").unwrap();
+        let plan = plan_rule_action(&rule, &path, dir.path()).unwrap();
+        assert_eq!(plan.operations.len(), 1);
+        assert!(plan.result_path.ends_with("Invoices/invoice_1.pdf"));
+    }
+
+    #[test]
+    fn test_plan_move_rejects_escape() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("a.txt");
+        std::fs::write(&path, b"a").unwrap();
+
+        let rule = rule_with(
+            InboxAction::Move {
+                destination: "../elsewhere".to_string(),
+            },
+            "file.ext == 'txt'",
+        );
+        assert!(plan_rule_action(&rule, &path, dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_plan_rename_noop_when_name_unchanged() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("report.txt");
+        std::fs::write(&path, b"a").unwrap();
+
+        let rule = rule_with(
+            InboxAction::Rename {
+                template: "{name}.{ext}".to_string(),
+            },
+            "file.ext == 'txt'",
+        );
+        let plan = plan_rule_action(&rule, &path, dir.path()).unwrap();
+        assert!(plan.operations.is_empty());
+    }
+
+    #[test]
+    fn test_plan_rename_numbers_from_rename_count() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("scan.pdf");
+        std::fs::write(&path, b"pdf").unwrap();
+
+        let mut rule = rule_with(
+            InboxAction::Rename {
+                template: "scan-{n:03}.{ext}".to_string(),
+            },
+            "file.ext == 'pdf'",
+        );
+        rule.rename_count = 4;
+        let plan = plan_rule_action(&rule, &path, dir.path()).unwrap();
+        assert!(plan.result_path.ends_with("scan-005.pdf"));
+    }
+
+    #[test]
+    fn test_try_claim_suppresses_repeat_events() {
+        let dir = tempdir().unwrap();
+        let state = InboxState::new(InboxRuleStore::with_path(dir.path().join("rules.json")));
+        let path = dir.path().join("file.txt");
+
+        assert!(state.try_claim(&path));
+        assert!(!state.try_claim(&path));
+
+        let produced = dir.path().join("renamed.txt");
+        state.suppress(&produced);
+        assert!(!state.try_claim(&produced));
+    }
+}
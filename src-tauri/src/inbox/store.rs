@@ -0,0 +1,266 @@
+//! Persistence for inbox rules.
+//!
+//! Rules are stored in a single JSON file at
+//! `~/.config/sentinel/inbox_rules.json` and kept in memory for the watcher.
+
+use crate::inbox::rule::InboxRule;
+use crate::wal::io::atomic_write;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Rules filename inside the sentinel config directory
+const RULES_FILENAME: &str = "inbox_rules.json";
+
+/// Schema version for forward compatibility
+pub const INBOX_RULES_VERSION: u32 = 1;
+
+/// Maximum number of rules across all folders
+pub const MAX_INBOX_RULES: usize = 200;
+
+/// On-disk representation of the rules file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InboxRulesFile {
+    version: u32,
+    rules: Vec<InboxRule>,
+}
+
+/// Normalize a folder path so rules and watcher paths compare equal
+pub fn normalize_folder(folder: &str) -> String {
+    Path::new(folder)
+        .canonicalize()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| folder.trim_end_matches('/').to_string())
+}
+
+/// In-memory rule set backed by a JSON file
+pub struct InboxRuleStore {
+    path: PathBuf,
+    rules: Vec<InboxRule>,
+}
+
+impl Default for InboxRuleStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InboxRuleStore {
+    /// Create a store at the default location, loading any saved rules
+    pub fn new() -> Self {
+        let path = dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("sentinel")
+            .join(RULES_FILENAME);
+        Self::with_path(path)
+    }
+
+    /// Create a store backed by a specific file (for testing)
+    pub fn with_path(path: PathBuf) -> Self {
+        let rules = match Self::load(&path) {
+            Ok(rules) => rules,
+            Err(e) => {
+                tracing::warn!(path = %path.display(), error = %e, "Failed to load inbox rules");
+                Vec::new()
+            }
+        };
+        Self { path, rules }
+    }
+
+    fn load(path: &Path) -> Result<Vec<InboxRule>, String> {
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read inbox rules: {}", e))?;
+        let file: InboxRulesFile = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse inbox rules: {}", e))?;
+        Ok(file.rules)
+    }
+
+    fn persist(&self) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create config directory: {}", e))?;
+        }
+        let file = InboxRulesFile {
+            version: INBOX_RULES_VERSION,
+            rules: self.rules.clone(),
+        };
+        let json = serde_json::to_vec_pretty(&file)
+            .map_err(|e| format!("Failed to serialize inbox rules: {}", e))?;
+        atomic_write(&self.path, &json)?;
+        Ok(())
+    }
+
+    /// All rules, in insertion order
+    pub fn rules(&self) -> &[InboxRule] {
+        &self.rules
+    }
+
+    /// Get a rule by ID
+    pub fn get(&self, rule_id: &str) -> Option<&InboxRule> {
+        self.rules.iter().find(|r| r.id == rule_id)
+    }
+
+    /// All rules attached to a folder, in insertion order
+    pub fn rules_for_folder(&self, folder: &str) -> Vec<InboxRule> {
+        let folder = normalize_folder(folder);
+        self.rules
+            .iter()
+            .filter(|r| r.folder == folder)
+            .cloned()
+            .collect()
+    }
+
+    /// Enabled rules for a folder, highest priority first
+    pub fn active_rules_for_folder(&self, folder: &str) -> Vec<InboxRule> {
+        let mut rules: Vec<InboxRule> = self
+            .rules_for_folder(folder)
+            .into_iter()
+            .filter(|r| r.enabled)
+            .collect();
+        // Stable sort keeps insertion order among equal priorities
+        rules.sort_by(|a, b| b.priority.cmp(&a.priority));
+        rules
+    }
+
+    /// Insert or replace a rule (matched by ID) and persist
+    pub fn upsert(&mut self, mut rule: InboxRule) -> Result<InboxRule, String> {
+        rule.validate()?;
+        rule.folder = normalize_folder(&rule.folder);
+
+        if let Some(existing) = self.rules.iter_mut().find(|r| r.id == rule.id) {
+            rule.created_at = existing.created_at;
+            rule.rename_count = existing.rename_count;
+            *existing = rule.clone();
+        } else {
+            if self.rules.len() >= MAX_INBOX_RULES {
+                return Err(format!(
+                    "Inbox rule limit reached ({} rules)",
+                    MAX_INBOX_RULES
+                ));
+            }
+            self.rules.push(rule.clone());
+        }
+
+        self.persist()?;
+        Ok(rule)
+    }
+
+    /// Remove a rule and persist
+    pub fn remove(&mut self, rule_id: &str) -> Result<(), String> {
+        let before = self.rules.len();
+        self.rules.retain(|r| r.id != rule_id);
+        if self.rules.len() == before {
+            return Err(format!("Inbox rule not found: {}", rule_id));
+        }
+        self.persist()
+    }
+
+    /// Enable or disable a rule and persist
+    pub fn set_enabled(&mut self, rule_id: &str, enabled: bool) -> Result<InboxRule, String> {
+        let rule = self
+            .rules
+            .iter_mut()
+            .find(|r| r.id == rule_id)
+            .ok_or_else(|| format!("Inbox rule not found: {}", rule_id))?;
+        rule.enabled = enabled;
+        let updated = rule.clone();
+        self.persist()?;
+        Ok(updated)
+    }
+
+    /// Count one more rename by a rule and persist; returns the previous count
+    pub fn count_rename(&mut self, rule_id: &str) -> Result<u64, String> {
+        let rule = self
+            .rules
+            .iter_mut()
+            .find(|r| r.id == rule_id)
+            .ok_or_else(|| format!("Inbox rule not found: {}", rule_id))?;
+        let previous = rule.rename_count;
+        rule.rename_count += 1;
+        self.persist()?;
+        Ok(previous)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inbox::rule::InboxAction;
+    use tempfile::tempdir;
+
+    fn rule(folder: &Path, name: &str, priority: i32) -> InboxRule {
+        let mut rule = InboxRule::new(
+            name.to_string(),
+            folder.to_string_lossy().to_string(),
+            "file.ext == 'pdf'".to_string(),
+            InboxAction::Quarantine,
+        );
+        rule.priority = priority;
+        rule
+    }
+
+    #[test]
+    fn test_upsert_persists_and_reloads() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("rules.json");
+
+        let mut store = InboxRuleStore::with_path(path.clone());
+        let saved = store.upsert(rule(dir.path(), "PDFs", 0)).unwrap();
+
+        let reloaded = InboxRuleStore::with_path(path);
+        assert_eq!(reloaded.rules().len(), 1);
+        assert_eq!(reloaded.get(&saved.id).unwrap().name, "PDFs");
+    }
+
+    #[test]
+    fn test_active_rules_sorted_by_priority() {
+        let dir = tempdir().unwrap();
+        let mut store = InboxRuleStore::with_path(dir.path().join("rules.json"));
+        store.upsert(rule(dir.path(), "low", 0)).unwrap();
+        let high = store.upsert(rule(dir.path(), "high", 10)).unwrap();
+        let disabled = store.upsert(rule(dir.path(), "off", 20)).unwrap();
+        store.set_enabled(&disabled.id, false).unwrap();
+
+        let active = store.active_rules_for_folder(&dir.path().to_string_lossy());
+        assert_eq!(active.len(), 2);
+        assert_eq!(active[0].id, high.id);
+    }
+
+    #[test]
+    fn test_upsert_rejects_invalid_rule() {
+        let dir = tempdir().unwrap();
+        let mut store = InboxRuleStore::with_path(dir.path().join("rules.json"));
+        let mut bad = rule(dir.path(), "bad", 0);
+        bad.condition = "file.size >".to_string();
+        assert!(store.upsert(bad).is_err());
+        assert!(store.rules().is_empty());
+    }
+
+    #[test]
+    fn test_rename_count_persists_across_edits() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("rules.json");
+        let mut store = InboxRuleStore::with_path(path.clone());
+        let saved = store.upsert(rule(dir.path(), "PDFs", 0)).unwrap();
+
+        assert_eq!(store.count_rename(&saved.id).unwrap(), 0);
+        assert_eq!(store.count_rename(&saved.id).unwrap(), 1);
+        // Editing the rule keeps its count
+        store.upsert(saved.clone()).unwrap();
+
+        let reloaded = InboxRuleStore::with_path(path);
+        assert_eq!(reloaded.get(&saved.id).unwrap().rename_count, 2);
+        assert!(store.count_rename("missing").is_err());
+    }
+
+    #[test]
+    fn test_remove_unknown_rule() {
+        let dir = tempdir().unwrap();
+        let mut store = InboxRuleStore::with_path(dir.path().join("rules.json"));
+        assert!(store.remove("missing").is_err());
+    }
+}
@@ -1,11 +1,17 @@
 mod ai;
+pub mod archive;
 mod billing;
+pub mod cli;
 mod commands;
+mod duplicates;
 mod execution;
 mod file_coordination;
 mod history;
+mod inbox;
 mod jobs;
+pub mod media;
 mod models;
+pub mod pdf;
 pub mod quarantine;
 mod rate_limit;
 mod security;
@@ -19,6 +25,7 @@ mod wal;
 use billing::BillingState;
 use commands::*;
 use commands::grok::{GrokState, GrokAbortFlag};
+use inbox::create_inbox_handle;
 use rate_limit::RateLimitState;
 use services::watcher::create_watcher_handle;
 use tracing_subscriber::EnvFilter;
@@ -43,7 +50,9 @@ pub fn run() {
         .init();
 
     let watcher_handle = create_watcher_handle();
+    let inbox_handle = create_inbox_handle();
     let vector_state = VectorState::default();
+    let vector_sync_queue = VectorSyncQueue::start(&vector_state);
     let tree_state = TreeState::default();
     let vfs_state = create_vfs_state();
     let quarantine_state = create_quarantine_state()
@@ -87,7 +96,9 @@ pub fn run() {
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_store::Builder::new().build())
         .manage(watcher_handle)
+        .manage(inbox_handle)
         .manage(vector_state)
+        .manage(vector_sync_queue)
         .manage(tree_state)
         .manage(vfs_state)
         .manage(quarantine_state)
@@ -120,10 +131,19 @@ pub fn run() {
             get_watcher_status,
             add_watched_folder,
             remove_watched_folder,
+            // Inbox rule commands
+            inbox_list_rules,
+            inbox_save_rule,
+            inbox_delete_rule,
+            inbox_set_rule_enabled,
+            inbox_preview_rule,
             // AI commands
             set_api_key,
             delete_api_key,
             get_configured_providers,
+            get_llm_settings,
+            set_llm_settings,
+            test_llm_provider,
             get_rename_suggestion,
             apply_rename,
             undo_rename,
@@ -181,10 +201,13 @@ pub fn run() {
             vfs_get_stats,
             vfs_validate_plan,
             vfs_simulate_plan,
+            vfs_dry_run_plan,
             vfs_stage_move,
             vfs_stage_create_folder,
             vfs_stage_delete,
             vfs_apply_staged,
+            vfs_preview_staged,
+            vfs_execute_staged,
             vfs_clear_staged,
             vfs_has_staged,
             vfs_clear,
@@ -195,6 +218,11 @@ pub fn run() {
             quarantine_cleanup,
             quarantine_permanent_delete,
             quarantine_check,
+            // Duplicate commands
+            find_duplicate_files,
+            plan_deduplication,
+            scan_similar_images,
+            plan_similar_image_cleanup,
             // WAL commands
             wal_check_recovery,
             wal_resume_job,
@@ -226,6 +254,10 @@ pub fn run() {
             grok_get_api_key,
             grok_abort_plan,
             grok_reset_abort,
+            grok_ocr_available,
+            grok_get_ocr_settings,
+            grok_set_ocr_settings,
+            grok_set_folder_ocr_policy,
             // Billing commands
             get_daily_usage,
             get_usage_history,
@@ -247,6 +279,12 @@ pub fn run() {
             history_get_session_detail,
             history_undo_preflight,
             history_undo_execute,
+            history_selective_undo_preflight,
+            history_selective_undo_execute,
+            history_redo_preflight,
+            history_redo_execute,
+            history_folder_drift,
+            history_snapshot_folder,
             history_delete,
             history_list_folders,
         ])
@@ -0,0 +1,485 @@
+//! EXIF reader
+//!
+//! Minimal TIFF/EXIF parser covering the tags photo organization needs:
+//! capture time, camera, lens, dimensions and GPS. Works on any seekable
+//! source, so TIFF-based RAW files are read entry by entry instead of
+//! being loaded whole.
+
+use super::{GpsPosition, PhotoMetadata};
+use chrono::{FixedOffset, Local, NaiveDateTime, TimeZone};
+use std::io::{self, Read, Seek, SeekFrom};
+
+// IFD0
+const TAG_IMAGE_WIDTH: u16 = 0x0100;
+const TAG_IMAGE_HEIGHT: u16 = 0x0101;
+const TAG_MAKE: u16 = 0x010F;
+const TAG_MODEL: u16 = 0x0110;
+const TAG_DATE_TIME: u16 = 0x0132;
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_GPS_IFD: u16 = 0x8825;
+// Exif IFD
+const TAG_DATE_TIME_ORIGINAL: u16 = 0x9003;
+const TAG_DATE_TIME_DIGITIZED: u16 = 0x9004;
+const TAG_OFFSET_TIME_ORIGINAL: u16 = 0x9011;
+const TAG_PIXEL_X: u16 = 0xA002;
+const TAG_PIXEL_Y: u16 = 0xA003;
+const TAG_LENS_MAKE: u16 = 0xA433;
+const TAG_LENS_MODEL: u16 = 0xA434;
+// GPS IFD
+const TAG_GPS_LATITUDE_REF: u16 = 0x0001;
+const TAG_GPS_LATITUDE: u16 = 0x0002;
+const TAG_GPS_LONGITUDE_REF: u16 = 0x0003;
+const TAG_GPS_LONGITUDE: u16 = 0x0004;
+const TAG_GPS_ALTITUDE_REF: u16 = 0x0005;
+const TAG_GPS_ALTITUDE: u16 = 0x0006;
+
+/// Guard against corrupt files declaring huge IFDs or values
+const MAX_IFD_ENTRIES: u16 = 1024;
+const MAX_VALUE_BYTES: u64 = 64 * 1024;
+
+/// One IFD entry; `data_offset` is absolute within the source
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    tag: u16,
+    kind: u16,
+    count: u32,
+    data_offset: u64,
+}
+
+struct Tiff<R> {
+    reader: R,
+    /// Offset of the TIFF header within the source
+    base: u64,
+    little_endian: bool,
+}
+
+impl<R: Read + Seek> Tiff<R> {
+    fn open(mut reader: R, base: u64) -> io::Result<Self> {
+        reader.seek(SeekFrom::Start(base))?;
+        let mut order = [0u8; 2];
+        reader.read_exact(&mut order)?;
+        let little_endian = match &order {
+            b"II" => true,
+            b"MM" => false,
+            _ => return Err(invalid("not a TIFF header")),
+        };
+        Ok(Self {
+            reader,
+            base,
+            little_endian,
+        })
+    }
+
+    fn bytes_at(&mut self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        self.reader.seek(SeekFrom::Start(offset))?;
+        let mut buffer = vec![0u8; len];
+        self.reader.read_exact(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    fn u16_from(&self, b: &[u8]) -> u16 {
+        if self.little_endian {
+            u16::from_le_bytes([b[0], b[1]])
+        } else {
+            u16::from_be_bytes([b[0], b[1]])
+        }
+    }
+
+    fn u32_from(&self, b: &[u8]) -> u32 {
+        if self.little_endian {
+            u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+        } else {
+            u32::from_be_bytes([b[0], b[1], b[2], b[3]])
+        }
+    }
+
+    /// Offset of IFD0, relative to the TIFF header
+    fn first_ifd(&mut self) -> io::Result<u32> {
+        let header = self.bytes_at(self.base + 4, 4)?;
+        Ok(self.u32_from(&header))
+    }
+
+    fn read_ifd(&mut self, offset: u32) -> io::Result<Vec<Entry>> {
+        let start = self.base + offset as u64;
+        let count = self.bytes_at(start, 2)?;
+        let count = self.u16_from(&count);
+        if count > MAX_IFD_ENTRIES {
+            return Err(invalid("IFD too large"));
+        }
+        let raw = self.bytes_at(start + 2, count as usize * 12)?;
+
+        Ok(raw
+            .chunks_exact(12)
+            .enumerate()
+            .map(|(i, e)| {
+                let kind = self.u16_from(&e[2..4]);
+                let count = self.u32_from(&e[4..8]);
+                let size = type_size(kind) * count as u64;
+                // Values of up to four bytes are stored in the entry itself
+                let data_offset = if size <= 4 {
+                    start + 2 + i as u64 * 12 + 8
+                } else {
+                    self.base + self.u32_from(&e[8..12]) as u64
+                };
+                Entry {
+                    tag: self.u16_from(&e[0..2]),
+                    kind,
+                    count,
+                    data_offset,
+                }
+            })
+            .collect())
+    }
+
+    fn value_bytes(&mut self, entry: &Entry) -> Option<Vec<u8>> {
+        let size = type_size(entry.kind) * entry.count as u64;
+        if size == 0 || size > MAX_VALUE_BYTES {
+            return None;
+        }
+        self.bytes_at(entry.data_offset, size as usize).ok()
+    }
+
+    fn ascii(&mut self, entry: &Entry) -> Option<String> {
+        if entry.kind != 2 && entry.kind != 7 {
+            return None;
+        }
+        let bytes = self.value_bytes(entry)?;
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        let s = String::from_utf8_lossy(&bytes[..end]).trim().to_string();
+        Some(s).filter(|s| !s.is_empty())
+    }
+
+    /// First value of a BYTE, SHORT or LONG entry
+    fn uint(&mut self, entry: &Entry) -> Option<u32> {
+        let bytes = self.value_bytes(entry)?;
+        match entry.kind {
+            1 | 7 => Some(bytes[0] as u32),
+            3 => Some(self.u16_from(&bytes) as u32),
+            4 => Some(self.u32_from(&bytes)),
+            _ => None,
+        }
+    }
+
+    fn find_ascii(&mut self, entries: &[Entry], tag: u16) -> Option<String> {
+        find(entries, tag).and_then(|e| self.ascii(&e))
+    }
+
+    fn find_uint(&mut self, entries: &[Entry], tag: u16) -> Option<u32> {
+        find(entries, tag).and_then(|e| self.uint(&e))
+    }
+
+    fn rationals(&mut self, entry: &Entry) -> Option<Vec<f64>> {
+        if entry.kind != 5 {
+            return None;
+        }
+        let bytes = self.value_bytes(entry)?;
+        Some(
+            bytes
+                .chunks_exact(8)
+                .map(|r| {
+                    let denominator = self.u32_from(&r[4..8]);
+                    if denominator == 0 {
+                        0.0
+                    } else {
+                        self.u32_from(&r[0..4]) as f64 / denominator as f64
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+fn type_size(kind: u16) -> u64 {
+    match kind {
+        1 | 2 | 6 | 7 => 1,
+        3 | 8 => 2,
+        4 | 9 | 11 => 4,
+        5 | 10 | 12 => 8,
+        _ => 0,
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn find(entries: &[Entry], tag: u16) -> Option<Entry> {
+    entries.iter().find(|e| e.tag == tag).copied()
+}
+
+/// Parse EXIF from a TIFF structure starting at `base` in `reader`
+pub fn read_tiff<R: Read + Seek>(reader: R, base: u64) -> io::Result<PhotoMetadata> {
+    let mut tiff = Tiff::open(reader, base)?;
+    let ifd0_offset = tiff.first_ifd()?;
+    let ifd0 = tiff.read_ifd(ifd0_offset)?;
+    let mut metadata = PhotoMetadata {
+        camera_make: tiff.find_ascii(&ifd0, TAG_MAKE),
+        camera_model: tiff.find_ascii(&ifd0, TAG_MODEL),
+        width: tiff.find_uint(&ifd0, TAG_IMAGE_WIDTH),
+        height: tiff.find_uint(&ifd0, TAG_IMAGE_HEIGHT),
+        ..Default::default()
+    };
+    let mut taken = tiff.find_ascii(&ifd0, TAG_DATE_TIME);
+    let mut offset = None;
+
+    let exif_ifd = tiff
+        .find_uint(&ifd0, TAG_EXIF_IFD)
+        .and_then(|o| tiff.read_ifd(o).ok());
+    if let Some(exif) = exif_ifd {
+        if let Some(original) = tiff
+            .find_ascii(&exif, TAG_DATE_TIME_ORIGINAL)
+            .or_else(|| tiff.find_ascii(&exif, TAG_DATE_TIME_DIGITIZED))
+        {
+            taken = Some(original);
+            offset = tiff.find_ascii(&exif, TAG_OFFSET_TIME_ORIGINAL);
+        }
+        // The IFD0 size of RAW files is often the embedded preview
+        if let Some(width) = tiff.find_uint(&exif, TAG_PIXEL_X) {
+            metadata.width = Some(width);
+        }
+        if let Some(height) = tiff.find_uint(&exif, TAG_PIXEL_Y) {
+            metadata.height = Some(height);
+        }
+        let lens_make = tiff.find_ascii(&exif, TAG_LENS_MAKE);
+        metadata.lens = tiff.find_ascii(&exif, TAG_LENS_MODEL).map(|model| match lens_make {
+            Some(make) if !model.starts_with(&make) => format!("{} {}", make, model),
+            _ => model,
+        });
+    }
+    metadata.taken_at = taken.and_then(|t| parse_exif_datetime(&t, offset.as_deref()));
+
+    let gps_ifd = tiff
+        .find_uint(&ifd0, TAG_GPS_IFD)
+        .and_then(|o| tiff.read_ifd(o).ok());
+    if let Some(gps) = gps_ifd {
+        metadata.gps = read_gps(&mut tiff, &gps);
+    }
+
+    Ok(metadata)
+}
+
+fn read_gps<R: Read + Seek>(tiff: &mut Tiff<R>, gps: &[Entry]) -> Option<GpsPosition> {
+    let mut coordinate = |value_tag, ref_tag, negative: &str| -> Option<f64> {
+        let dms = find(gps, value_tag).and_then(|e| tiff.rationals(&e))?;
+        let degrees = dms.first()?
+            + dms.get(1).unwrap_or(&0.0) / 60.0
+            + dms.get(2).unwrap_or(&0.0) / 3600.0;
+        let reference = tiff.find_ascii(gps, ref_tag);
+        Some(if reference.as_deref() == Some(negative) {
+            -degrees
+        } else {
+            degrees
+        })
+    };
+
+    let latitude = coordinate(TAG_GPS_LATITUDE, TAG_GPS_LATITUDE_REF, "S")?;
+    let longitude = coordinate(TAG_GPS_LONGITUDE, TAG_GPS_LONGITUDE_REF, "W")?;
+    let altitude = find(gps, TAG_GPS_ALTITUDE)
+        .and_then(|e| tiff.rationals(&e))
+        .and_then(|v| v.first().copied())
+        .map(|alt| {
+            // Reference 1 means below sea level
+            let below = tiff.find_uint(gps, TAG_GPS_ALTITUDE_REF) == Some(1);
+            if below {
+                -alt
+            } else {
+                alt
+            }
+        });
+
+    Some(GpsPosition {
+        latitude,
+        longitude,
+        altitude,
+    })
+}
+
+/// Parse an EXIF timestamp (`YYYY:MM:DD HH:MM:SS`) into unix milliseconds
+///
+/// EXIF times are wall-clock times of the camera. With an `OffsetTime`
+/// (`+02:00`) they are exact; without one they are read as local time.
+pub fn parse_exif_datetime(value: &str, offset: Option<&str>) -> Option<i64> {
+    let value = value.trim();
+    // Some cameras write dashes instead of colons in the date
+    let naive = NaiveDateTime::parse_from_str(value, "%Y:%m:%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S"))
+        .ok()?;
+
+    if let Some(offset) = offset.and_then(parse_offset) {
+        return offset
+            .from_local_datetime(&naive)
+            .single()
+            .map(|dt| dt.timestamp_millis());
+    }
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|dt| dt.timestamp_millis())
+}
+
+fn parse_offset(s: &str) -> Option<FixedOffset> {
+    let s = s.trim();
+    let (sign, rest) = match s.as_bytes().first()? {
+        b'+' => (1, &s[1..]),
+        b'-' => (-1, &s[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = rest.split_once(':')?;
+    let seconds = hours.parse::<i32>().ok()? * 3600 + minutes.parse::<i32>().ok()? * 60;
+    FixedOffset::east_opt(sign * seconds)
+}
+
+/// Minimal TIFF/EXIF writer used by the media tests
+#[cfg(test)]
+pub(crate) mod testdata {
+    /// (tag, type, count, encoded value)
+    type RawEntry = (u16, u16, u32, Vec<u8>);
+
+    pub fn ascii(tag: u16, s: &str) -> RawEntry {
+        let mut bytes = s.as_bytes().to_vec();
+        bytes.push(0);
+        (tag, 2, bytes.len() as u32, bytes)
+    }
+
+    fn short(tag: u16, v: u16, le: bool) -> RawEntry {
+        let b = if le { v.to_le_bytes() } else { v.to_be_bytes() };
+        (tag, 3, 1, b.to_vec())
+    }
+
+    fn long(tag: u16, v: u32, le: bool) -> RawEntry {
+        let b = if le { v.to_le_bytes() } else { v.to_be_bytes() };
+        (tag, 4, 1, b.to_vec())
+    }
+
+    fn rationals(tag: u16, values: &[(u32, u32)], le: bool) -> RawEntry {
+        let mut bytes = Vec::new();
+        for (n, d) in values {
+            for v in [n, d] {
+                bytes.extend(if le { v.to_le_bytes() } else { v.to_be_bytes() });
+            }
+        }
+        (tag, 5, values.len() as u32, bytes)
+    }
+
+    fn ifd_len(entries: &[RawEntry]) -> u32 {
+        let data: usize = entries
+            .iter()
+            .filter(|e| e.3.len() > 4)
+            .map(|e| e.3.len() + e.3.len() % 2)
+            .sum();
+        (2 + entries.len() * 12 + 4 + data) as u32
+    }
+
+    fn write_ifd(out: &mut Vec<u8>, entries: &[RawEntry], le: bool) {
+        let start = out.len() as u32;
+        let u16b = |v: u16| if le { v.to_le_bytes() } else { v.to_be_bytes() };
+        let u32b = |v: u32| if le { v.to_le_bytes() } else { v.to_be_bytes() };
+
+        out.extend(u16b(entries.len() as u16));
+        let mut data_offset = start + 2 + entries.len() as u32 * 12 + 4;
+        let mut data = Vec::new();
+        for (tag, kind, count, value) in entries {
+            out.extend(u16b(*tag));
+            out.extend(u16b(*kind));
+            out.extend(u32b(*count));
+            if value.len() <= 4 {
+                let mut inline = value.clone();
+                inline.resize(4, 0);
+                out.extend(inline);
+            } else {
+                out.extend(u32b(data_offset));
+                data.extend(value);
+                if value.len() % 2 == 1 {
+                    data.push(0);
+                }
+                data_offset += value.len() as u32 + value.len() as u32 % 2;
+            }
+        }
+        out.extend(u32b(0)); // no next IFD
+        out.extend(data);
+    }
+
+    /// A TIFF block as found in a JPEG APP1 segment or a TIFF file
+    pub fn sample_tiff(le: bool) -> Vec<u8> {
+        let exif = vec![
+            ascii(super::TAG_DATE_TIME_ORIGINAL, "2023:07:14 18:30:05"),
+            ascii(super::TAG_OFFSET_TIME_ORIGINAL, "+02:00"),
+            long(super::TAG_PIXEL_X, 4032, le),
+            short(super::TAG_PIXEL_Y, 3024, le),
+            ascii(super::TAG_LENS_MODEL, "EF 24-70mm f/2.8L"),
+        ];
+        let gps = vec![
+            ascii(super::TAG_GPS_LATITUDE_REF, "N"),
+            rationals(super::TAG_GPS_LATITUDE, &[(48, 1), (51, 1), (2403, 100)], le),
+            ascii(super::TAG_GPS_LONGITUDE_REF, "W"),
+            rationals(super::TAG_GPS_LONGITUDE, &[(2, 1), (17, 1), (4002, 100)], le),
+            rationals(super::TAG_GPS_ALTITUDE, &[(355, 10)], le),
+        ];
+        let mut ifd0 = vec![
+            ascii(super::TAG_MAKE, "Canon"),
+            ascii(super::TAG_MODEL, "Canon EOS R6"),
+            ascii(super::TAG_DATE_TIME, "2024:01:01 00:00:00"),
+            long(super::TAG_EXIF_IFD, 0, le),
+            long(super::TAG_GPS_IFD, 0, le),
+        ];
+
+        let exif_offset = 8 + ifd_len(&ifd0);
+        let gps_offset = exif_offset + ifd_len(&exif);
+        ifd0[3] = long(super::TAG_EXIF_IFD, exif_offset, le);
+        ifd0[4] = long(super::TAG_GPS_IFD, gps_offset, le);
+
+        let mut out = Vec::new();
+        out.extend(if le { b"II" } else { b"MM" });
+        out.extend(if le { 42u16.to_le_bytes() } else { 42u16.to_be_bytes() });
+        out.extend(if le { 8u32.to_le_bytes() } else { 8u32.to_be_bytes() });
+        write_ifd(&mut out, &ifd0, le);
+        write_ifd(&mut out, &exif, le);
+        write_ifd(&mut out, &gps, le);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_read_tiff_both_byte_orders() {
+        for le in [true, false] {
+            let metadata = read_tiff(Cursor::new(testdata::sample_tiff(le)), 0).unwrap();
+
+            assert_eq!(metadata.camera_make.as_deref(), Some("Canon"));
+            assert_eq!(metadata.camera().as_deref(), Some("Canon EOS R6"));
+            assert_eq!(metadata.lens.as_deref(), Some("EF 24-70mm f/2.8L"));
+            assert_eq!((metadata.width, metadata.height), (Some(4032), Some(3024)));
+            // DateTimeOriginal wins over IFD0 DateTime; 18:30 at +02:00
+            assert_eq!(metadata.taken_at, Some(1_689_352_205_000));
+
+            let gps = metadata.gps.unwrap();
+            assert!((gps.latitude - 48.856_675).abs() < 1e-5);
+            assert!((gps.longitude + 2.294_450).abs() < 1e-5);
+            assert_eq!(gps.altitude, Some(35.5));
+        }
+    }
+
+    #[test]
+    fn test_tiff_inside_larger_buffer() {
+        let mut data = b"Exif\0\0".to_vec();
+        data.extend(testdata::sample_tiff(true));
+        let metadata = read_tiff(Cursor::new(data), 6).unwrap();
+        assert_eq!(metadata.camera_make.as_deref(), Some("Canon"));
+
+        assert!(read_tiff(Cursor::new(b"garbage".to_vec()), 0).is_err());
+    }
+
+    #[test]
+    fn test_parse_exif_datetime() {
+        assert_eq!(
+            parse_exif_datetime("2023:07:14 18:30:05", Some("-05:00")),
+            Some(1_689_377_405_000)
+        );
+        assert_eq!(parse_exif_datetime("0000:00:00 00:00:00", None), None);
+        assert!(parse_exif_datetime("2023:07:14 18:30:05", None).is_some());
+    }
+}
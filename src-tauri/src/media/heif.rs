@@ -0,0 +1,310 @@
+//! HEIF metadata
+//!
+//! HEIC/HEIF/AVIF files are ISO base media files. The EXIF block is stored
+//! as an item of type `Exif`: `meta/iinf` names the item and `meta/iloc`
+//! says where its bytes are in the file.
+
+use super::exif::read_tiff;
+use super::PhotoMetadata;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+
+/// The meta box holds only item tables; anything bigger is corrupt
+const MAX_META_BYTES: u64 = 4 * 1024 * 1024;
+const MAX_EXIF_BYTES: u64 = 1024 * 1024;
+
+/// Read metadata from a HEIF-family file
+pub fn read_heif<R: Read + Seek>(reader: &mut R) -> io::Result<PhotoMetadata> {
+    let end = reader.seek(SeekFrom::End(0))?;
+    let (meta_start, meta_len) = find_box(reader, 0, end, b"meta")?
+        .ok_or_else(|| invalid("no meta box"))?;
+    if meta_len > MAX_META_BYTES {
+        return Err(invalid("meta box too large"));
+    }
+    let mut meta = vec![0u8; meta_len as usize];
+    reader.seek(SeekFrom::Start(meta_start))?;
+    reader.read_exact(&mut meta)?;
+
+    // meta is a full box: skip version and flags
+    let children = Bytes::new(meta.get(4..).ok_or_else(|| invalid("truncated meta box"))?);
+    let mut exif_item = None;
+    let mut locations = Vec::new();
+    for (kind, payload) in children.boxes() {
+        match &kind {
+            b"iinf" => exif_item = find_exif_item(payload),
+            b"iloc" => locations = parse_iloc(payload).ok_or_else(|| invalid("bad iloc box"))?,
+            _ => {}
+        }
+    }
+
+    let item = exif_item.ok_or_else(|| invalid("no Exif item"))?;
+    let (offset, length) = locations
+        .into_iter()
+        .find(|(id, _, _)| *id == item)
+        .map(|(_, offset, length)| (offset, length))
+        .ok_or_else(|| invalid("Exif item has no location"))?;
+    if !(4..=MAX_EXIF_BYTES).contains(&length) {
+        return Err(invalid("bad Exif item size"));
+    }
+
+    let mut data = vec![0u8; length as usize];
+    reader.seek(SeekFrom::Start(offset))?;
+    reader.read_exact(&mut data)?;
+    // The item starts with the offset of the TIFF header past these 4 bytes
+    let header_offset = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as u64;
+    read_tiff(Cursor::new(data), 4 + header_offset)
+}
+
+/// Find a top-level box between `start` and `end`; returns its payload range
+fn find_box<R: Read + Seek>(
+    reader: &mut R,
+    start: u64,
+    end: u64,
+    kind: &[u8; 4],
+) -> io::Result<Option<(u64, u64)>> {
+    let mut pos = start;
+    while pos.checked_add(8).is_some_and(|header_end| header_end <= end) {
+        reader.seek(SeekFrom::Start(pos))?;
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+        let mut size = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as u64;
+        let mut header_len = 8;
+        if size == 1 {
+            let mut large = [0u8; 8];
+            reader.read_exact(&mut large)?;
+            size = u64::from_be_bytes(large);
+            header_len = 16;
+        } else if size == 0 {
+            size = end - pos;
+        }
+        if size < header_len {
+            return Err(invalid("bad box size"));
+        }
+        // The box has to end past its start and inside the range
+        let box_end = pos
+            .checked_add(size)
+            .filter(|&box_end| box_end > pos && box_end <= end)
+            .ok_or_else(|| invalid("bad box size"))?;
+        if &header[4..8] == kind {
+            return Ok(Some((pos + header_len, size - header_len)));
+        }
+        pos = box_end;
+    }
+    Ok(None)
+}
+
+/// Item ID of the `Exif` item listed in an iinf payload
+fn find_exif_item(payload: &[u8]) -> Option<u32> {
+    let mut bytes = Bytes::new(payload);
+    let version = bytes.u8()?;
+    bytes.skip(3)?;
+    if version == 0 {
+        bytes.u16()?;
+    } else {
+        bytes.u32()?;
+    }
+
+    for (kind, infe) in bytes.boxes() {
+        if &kind != b"infe" {
+            continue;
+        }
+        let mut infe = Bytes::new(infe);
+        let version = infe.u8()?;
+        infe.skip(3)?;
+        // Versions 0 and 1 carry no item type
+        if version < 2 {
+            continue;
+        }
+        let id = if version == 2 { infe.u16()? as u32 } else { infe.u32()? };
+        infe.u16()?; // protection index
+        if infe.take(4)? == b"Exif" {
+            return Some(id);
+        }
+    }
+    None
+}
+
+/// (item ID, absolute offset, length) of every single-extent item stored
+/// in the file itself
+fn parse_iloc(payload: &[u8]) -> Option<Vec<(u32, u64, u64)>> {
+    let mut bytes = Bytes::new(payload);
+    let version = bytes.u8()?;
+    bytes.skip(3)?;
+    let sizes = bytes.u8()?;
+    let (offset_size, length_size) = (sizes >> 4, sizes & 0x0F);
+    let sizes = bytes.u8()?;
+    let base_offset_size = sizes >> 4;
+    let index_size = if version >= 1 { sizes & 0x0F } else { 0 };
+    let item_count = if version < 2 { bytes.u16()? as u32 } else { bytes.u32()? };
+
+    let mut items = Vec::new();
+    for _ in 0..item_count {
+        let id = if version < 2 { bytes.u16()? as u32 } else { bytes.u32()? };
+        let construction_method = if version >= 1 { bytes.u16()? & 0x0F } else { 0 };
+        bytes.u16()?; // data reference index
+        let base_offset = bytes.uint(base_offset_size)?;
+        let extent_count = bytes.u16()?;
+        let mut extents = Vec::new();
+        for _ in 0..extent_count {
+            bytes.uint(index_size)?;
+            let offset = bytes.uint(offset_size)?;
+            let length = bytes.uint(length_size)?;
+            extents.push((base_offset.checked_add(offset)?, length));
+        }
+        // Only file offsets (method 0) in one piece are supported
+        if construction_method == 0 && extents.len() == 1 {
+            items.push((id, extents[0].0, extents[0].1));
+        }
+    }
+    Some(items)
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Big-endian reader over an in-memory box payload
+struct Bytes<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Bytes<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let slice = self.data.get(self.pos..self.pos.checked_add(n)?)?;
+        self.pos += n;
+        Some(slice)
+    }
+
+    fn skip(&mut self, n: usize) -> Option<()> {
+        self.take(n).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_be_bytes(self.take(2)?.try_into().ok()?))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_be_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    /// Unsigned integer of 0, 4 or 8 bytes, as sized in iloc
+    fn uint(&mut self, size: u8) -> Option<u64> {
+        match size {
+            0 => Some(0),
+            4 => self.u32().map(u64::from),
+            8 => Some(u64::from_be_bytes(self.take(8)?.try_into().ok()?)),
+            _ => None,
+        }
+    }
+
+    /// Remaining data as a sequence of (type, payload) boxes
+    fn boxes(mut self) -> impl Iterator<Item = ([u8; 4], &'a [u8])> {
+        std::iter::from_fn(move || {
+            let size = self.u32()? as usize;
+            let kind: [u8; 4] = self.take(4)?.try_into().ok()?;
+            let payload = self.take(size.checked_sub(8)?)?;
+            Some((kind, payload))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::exif::testdata::sample_tiff;
+
+    fn boxed(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut out = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+        out.extend(kind);
+        out.extend(payload);
+        out
+    }
+
+    fn sample_heic() -> Vec<u8> {
+        let mut exif_item = 6u32.to_be_bytes().to_vec();
+        exif_item.extend(b"Exif\0\0");
+        exif_item.extend(sample_tiff(true));
+
+        let mut infe = vec![2, 0, 0, 0];
+        infe.extend(1u16.to_be_bytes());
+        infe.extend(0u16.to_be_bytes());
+        infe.extend(b"Exif\0");
+        let mut iinf = vec![0, 0, 0, 0];
+        iinf.extend(1u16.to_be_bytes());
+        iinf.extend(boxed(b"infe", &infe));
+
+        let iloc = |offset: u32| {
+            let mut iloc = vec![0, 0, 0, 0, 0x44, 0x00];
+            iloc.extend(1u16.to_be_bytes()); // item count
+            iloc.extend(1u16.to_be_bytes()); // item ID
+            iloc.extend(0u16.to_be_bytes()); // data reference
+            iloc.extend(1u16.to_be_bytes()); // extent count
+            iloc.extend(offset.to_be_bytes());
+            iloc.extend((exif_item.len() as u32).to_be_bytes());
+            iloc
+        };
+        let meta = |offset: u32| {
+            let mut meta = vec![0, 0, 0, 0];
+            meta.extend(boxed(b"hdlr", &[0; 24]));
+            meta.extend(boxed(b"iinf", &iinf));
+            meta.extend(boxed(b"iloc", &iloc(offset)));
+            boxed(b"meta", &meta)
+        };
+
+        let mut out = boxed(b"ftyp", b"heic\0\0\0\0mif1heic");
+        // mdat payload starts after ftyp, meta and the mdat header
+        let data_offset = out.len() + meta(0).len() + 8;
+        out.extend(meta(data_offset as u32));
+        out.extend(boxed(b"mdat", &exif_item));
+        out
+    }
+
+    #[test]
+    fn test_read_heif_exif_item() {
+        let metadata = read_heif(&mut Cursor::new(sample_heic())).unwrap();
+        assert_eq!(metadata.camera().as_deref(), Some("Canon EOS R6"));
+        assert_eq!(metadata.taken_at, Some(1_689_352_205_000));
+    }
+
+    #[test]
+    fn test_read_heif_without_meta() {
+        let file = boxed(b"ftyp", b"heic\0\0\0\0");
+        assert!(read_heif(&mut Cursor::new(file)).is_err());
+    }
+
+    #[test]
+    fn test_bad_box_sizes_are_rejected() {
+        // A 64-bit size that would wrap around past the end
+        let mut file = 1u32.to_be_bytes().to_vec();
+        file.extend(b"ftyp");
+        file.extend(u64::MAX.to_be_bytes());
+        file.extend([0; 8]);
+        let err = read_heif(&mut Cursor::new(file)).unwrap_err();
+        assert_eq!(err.to_string(), "bad box size");
+
+        // A box claiming more bytes than the file has
+        let mut file = boxed(b"ftyp", b"heic\0\0\0\0");
+        file[..4].copy_from_slice(&1000u32.to_be_bytes());
+        let err = read_heif(&mut Cursor::new(file)).unwrap_err();
+        assert_eq!(err.to_string(), "bad box size");
+
+        // iloc offsets that overflow are a bad iloc box
+        let mut iloc = vec![0, 0, 0, 0, 0x88, 0x80];
+        iloc.extend(1u16.to_be_bytes());
+        iloc.extend(1u16.to_be_bytes());
+        iloc.extend(0u16.to_be_bytes());
+        iloc.extend(u64::MAX.to_be_bytes()); // base offset
+        iloc.extend(1u16.to_be_bytes());
+        iloc.extend(1u64.to_be_bytes());
+        iloc.extend(4u64.to_be_bytes());
+        assert!(parse_iloc(&iloc).is_none());
+    }
+}
@@ -0,0 +1,182 @@
+//! JPEG metadata segments
+//!
+//! Walks the marker segments up to the image data, reading the EXIF and
+//! XMP APP1 segments and the frame header (for dimensions). Entropy-coded
+//! image data is never read.
+
+use super::exif::read_tiff;
+use super::xmp::parse_xmp;
+use super::PhotoMetadata;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+const XMP_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+
+/// Read metadata from a JPEG file
+pub fn read_jpeg<R: Read + Seek>(reader: &mut R) -> io::Result<PhotoMetadata> {
+    read_jpeg_at(reader, 0)
+}
+
+/// Read metadata from the JPEG preview embedded in a Fujifilm RAF file
+pub fn read_raf<R: Read + Seek>(reader: &mut R) -> io::Result<PhotoMetadata> {
+    let mut header = [0u8; 92];
+    reader.read_exact(&mut header)?;
+    if !header.starts_with(b"FUJIFILMCCD-RAW") {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not a RAF file"));
+    }
+    let jpeg_offset = u32::from_be_bytes([header[84], header[85], header[86], header[87]]);
+    read_jpeg_at(reader, jpeg_offset as u64)
+}
+
+fn read_jpeg_at<R: Read + Seek>(reader: &mut R, start: u64) -> io::Result<PhotoMetadata> {
+    reader.seek(SeekFrom::Start(start))?;
+    let mut soi = [0u8; 2];
+    reader.read_exact(&mut soi)?;
+    if soi != [0xFF, 0xD8] {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not a JPEG file"));
+    }
+
+    let mut exif = None;
+    let mut xmp = None;
+    let mut frame_size = None;
+
+    loop {
+        let mut marker = [0u8; 2];
+        reader.read_exact(&mut marker)?;
+        if marker[0] != 0xFF {
+            break;
+        }
+        match marker[1] {
+            // Fill byte before the real marker
+            0xFF => {
+                reader.seek(SeekFrom::Current(-1))?;
+                continue;
+            }
+            // Start of scan or end of image: no more metadata
+            0xDA | 0xD9 => break,
+            // Standalone markers without a length
+            0x01 | 0xD0..=0xD7 => continue,
+            _ => {}
+        }
+
+        let mut length = [0u8; 2];
+        reader.read_exact(&mut length)?;
+        let payload_len = u16::from_be_bytes(length).saturating_sub(2) as u64;
+        let payload_start = reader.stream_position()?;
+
+        match marker[1] {
+            0xE1 => {
+                let mut payload = vec![0u8; payload_len as usize];
+                reader.read_exact(&mut payload)?;
+                if payload.starts_with(EXIF_HEADER) && exif.is_none() {
+                    exif = read_tiff(Cursor::new(payload), EXIF_HEADER.len() as u64).ok();
+                } else if payload.starts_with(XMP_HEADER) && xmp.is_none() {
+                    xmp = Some(parse_xmp(&String::from_utf8_lossy(&payload[XMP_HEADER.len()..])));
+                }
+            }
+            // Start of frame (DHT, JPG and DAC share the range)
+            0xC0..=0xCF if !matches!(marker[1], 0xC4 | 0xC8 | 0xCC) => {
+                let mut header = [0u8; 5];
+                reader.read_exact(&mut header)?;
+                let height = u16::from_be_bytes([header[1], header[2]]) as u32;
+                let width = u16::from_be_bytes([header[3], header[4]]) as u32;
+                frame_size = Some((width, height));
+            }
+            _ => {}
+        }
+        reader.seek(SeekFrom::Start(payload_start + payload_len))?;
+    }
+
+    // EXIF wins over embedded XMP, which usually mirrors it
+    let mut metadata = xmp.unwrap_or_default();
+    if let Some(exif) = exif {
+        metadata.overlay(exif);
+    }
+    if let (None, Some((width, height))) = (metadata.width, frame_size) {
+        metadata.width = Some(width);
+        metadata.height = Some(height);
+    }
+    Ok(metadata)
+}
+
+#[cfg(test)]
+pub(crate) mod testdata {
+    use crate::media::exif::testdata::sample_tiff;
+
+    fn segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+        let mut out = vec![0xFF, marker];
+        out.extend(((payload.len() + 2) as u16).to_be_bytes());
+        out.extend(payload);
+        out
+    }
+
+    fn frame(width: u16, height: u16) -> Vec<u8> {
+        let mut sof = vec![8];
+        sof.extend(height.to_be_bytes());
+        sof.extend(width.to_be_bytes());
+        sof.extend([1, 1, 0x11, 0]);
+        segment(0xC0, &sof)
+    }
+
+    /// JPEG with the sample EXIF block and a 640x480 frame header
+    pub fn sample_jpeg() -> Vec<u8> {
+        let mut app1 = super::EXIF_HEADER.to_vec();
+        app1.extend(sample_tiff(false));
+
+        let mut out = vec![0xFF, 0xD8];
+        out.extend(segment(0xE0, b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0"));
+        out.extend(segment(0xE1, &app1));
+        out.extend(frame(640, 480));
+        out.extend(segment(0xDA, &[0; 10]));
+        out.extend([0x12, 0x34, 0xFF, 0xD9]);
+        out
+    }
+
+    /// JPEG with only an XMP packet and a frame header
+    pub fn xmp_only_jpeg(packet: &str) -> Vec<u8> {
+        let mut app1 = super::XMP_HEADER.to_vec();
+        app1.extend(packet.as_bytes());
+
+        let mut out = vec![0xFF, 0xD8];
+        out.extend(segment(0xE1, &app1));
+        out.extend(frame(800, 600));
+        out.extend([0xFF, 0xD9]);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_jpeg_exif() {
+        let metadata = read_jpeg(&mut Cursor::new(testdata::sample_jpeg())).unwrap();
+        assert_eq!(metadata.camera().as_deref(), Some("Canon EOS R6"));
+        // EXIF pixel dimensions win over the frame header
+        assert_eq!((metadata.width, metadata.height), (Some(4032), Some(3024)));
+        assert!(metadata.gps.is_some());
+    }
+
+    #[test]
+    fn test_read_jpeg_xmp_and_frame_size() {
+        let packet = r#"<rdf:Description tiff:Model="Pixel 8"/>"#;
+        let metadata = read_jpeg(&mut Cursor::new(testdata::xmp_only_jpeg(packet))).unwrap();
+        assert_eq!(metadata.camera_model.as_deref(), Some("Pixel 8"));
+        assert_eq!((metadata.width, metadata.height), (Some(800), Some(600)));
+
+        assert!(read_jpeg(&mut Cursor::new(b"\x89PNG".to_vec())).is_err());
+    }
+
+    #[test]
+    fn test_read_raf_preview() {
+        let mut raf = b"FUJIFILMCCD-RAW 0201FF383501".to_vec();
+        raf.resize(84, 0);
+        raf.extend(100u32.to_be_bytes());
+        raf.resize(100, 0);
+        raf.extend(testdata::sample_jpeg());
+
+        let metadata = read_raf(&mut Cursor::new(raf)).unwrap();
+        assert_eq!(metadata.camera_make.as_deref(), Some("Canon"));
+    }
+}
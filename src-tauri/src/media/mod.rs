@@ -0,0 +1,198 @@
+//! Media Metadata Module
+//!
+//! Reads capture metadata from photos without decoding them:
+//! - `exif`: TIFF/EXIF parser shared by every container
+//! - `jpeg`: EXIF and XMP segments of JPEG files
+//! - `heif`: EXIF item of HEIC/HEIF/AVIF files
+//! - `xmp`: XMP packets, embedded or in `.xmp` sidecars
+//!
+//! TIFF-based RAW formats (DNG, CR2, NEF, ARW, ...) are read directly by
+//! the EXIF parser. Sidecar values override embedded ones, since sidecars
+//! hold corrections made in photo editors.
+
+mod exif;
+mod heif;
+mod jpeg;
+mod xmp;
+
+pub use exif::parse_exif_datetime;
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+/// Extensions of files that may carry EXIF/XMP capture metadata
+pub const PHOTO_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "heic", "heif", "avif", "tif", "tiff", "dng", "cr2", "nef", "arw", "orf",
+    "rw2", "pef", "srw", "raf",
+];
+
+/// GPS position in decimal degrees
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GpsPosition {
+    /// Positive north
+    pub latitude: f64,
+    /// Positive east
+    pub longitude: f64,
+    /// Meters above sea level
+    pub altitude: Option<f64>,
+}
+
+/// Capture metadata of a photo
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhotoMetadata {
+    /// Capture time (unix milliseconds)
+    pub taken_at: Option<i64>,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub lens: Option<String>,
+    /// Pixel dimensions of the full image
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub gps: Option<GpsPosition>,
+}
+
+impl PhotoMetadata {
+    /// Camera name, e.g. "Apple iPhone 15" or "Canon EOS R6"
+    ///
+    /// The make is prepended unless the model already starts with it.
+    pub fn camera(&self) -> Option<String> {
+        match (&self.camera_make, &self.camera_model) {
+            (Some(make), Some(model)) => {
+                let brand = make.split_whitespace().next().unwrap_or(make);
+                if model.to_lowercase().starts_with(&brand.to_lowercase()) {
+                    Some(model.clone())
+                } else {
+                    Some(format!("{} {}", brand, model))
+                }
+            }
+            (None, Some(model)) => Some(model.clone()),
+            (Some(make), None) => Some(make.clone()),
+            (None, None) => None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Take every field `other` has, keeping ours where it has none
+    fn overlay(&mut self, other: PhotoMetadata) {
+        self.taken_at = other.taken_at.or(self.taken_at);
+        self.camera_make = other.camera_make.or(self.camera_make.take());
+        self.camera_model = other.camera_model.or(self.camera_model.take());
+        self.lens = other.lens.or(self.lens.take());
+        self.width = other.width.or(self.width);
+        self.height = other.height.or(self.height);
+        self.gps = other.gps.or(self.gps);
+    }
+}
+
+/// Whether the file type can carry photo metadata (by extension)
+pub fn is_photo(path: &Path) -> bool {
+    path.extension()
+        .map(|e| PHOTO_EXTENSIONS.contains(&e.to_string_lossy().to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Read capture metadata from a photo and its XMP sidecar
+///
+/// Returns None for unsupported files and for photos without any metadata.
+/// Unreadable or malformed metadata is treated as absent.
+pub fn read_photo_metadata(path: &Path) -> Option<PhotoMetadata> {
+    if !is_photo(path) {
+        return None;
+    }
+
+    let mut metadata = match read_embedded(path) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            tracing::debug!(path = %path.display(), error = %e, "No embedded photo metadata");
+            PhotoMetadata::default()
+        }
+    };
+
+    if let Some(sidecar) = find_sidecar(path) {
+        match std::fs::read_to_string(&sidecar) {
+            Ok(packet) => metadata.overlay(xmp::parse_xmp(&packet)),
+            Err(e) => {
+                tracing::debug!(path = %sidecar.display(), error = %e, "Unreadable XMP sidecar")
+            }
+        }
+    }
+
+    Some(metadata).filter(|m| !m.is_empty())
+}
+
+fn read_embedded(path: &Path) -> std::io::Result<PhotoMetadata> {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let mut reader = BufReader::new(File::open(path)?);
+
+    match ext.as_str() {
+        "jpg" | "jpeg" => jpeg::read_jpeg(&mut reader),
+        "heic" | "heif" | "avif" => heif::read_heif(&mut reader),
+        // Fujifilm RAF wraps a JPEG preview that carries the EXIF
+        "raf" => jpeg::read_raf(&mut reader),
+        _ => exif::read_tiff(reader, 0),
+    }
+}
+
+/// `IMG_1234.xmp` (Lightroom, darktable) or `IMG_1234.CR2.xmp` (digiKam)
+fn find_sidecar(path: &Path) -> Option<PathBuf> {
+    let file_name = path.file_name()?.to_string_lossy();
+    [
+        path.with_extension("xmp"),
+        path.with_extension("XMP"),
+        path.with_file_name(format!("{}.xmp", file_name)),
+    ]
+    .into_iter()
+    .find(|p| p.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_camera_name() {
+        let mut metadata = PhotoMetadata {
+            camera_make: Some("Apple".to_string()),
+            camera_model: Some("iPhone 15 Pro".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(metadata.camera().as_deref(), Some("Apple iPhone 15 Pro"));
+
+        metadata.camera_make = Some("NIKON CORPORATION".to_string());
+        metadata.camera_model = Some("NIKON Z 6".to_string());
+        assert_eq!(metadata.camera().as_deref(), Some("NIKON Z 6"));
+    }
+
+    #[test]
+    fn test_sidecar_overrides_embedded() {
+        let dir = tempfile::tempdir().unwrap();
+        let photo = dir.path().join("IMG_0001.jpg");
+        std::fs::write(&photo, jpeg::testdata::sample_jpeg()).unwrap();
+        std::fs::write(
+            dir.path().join("IMG_0001.xmp"),
+            r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF>
+                <rdf:Description exif:DateTimeOriginal="2020-05-01T09:00:00Z"/>
+            </rdf:RDF></x:xmpmeta>"#,
+        )
+        .unwrap();
+
+        let metadata = read_photo_metadata(&photo).unwrap();
+        assert_eq!(metadata.taken_at, Some(1_588_323_600_000));
+        // Everything else still comes from the embedded EXIF
+        assert_eq!(metadata.camera().as_deref(), Some("Canon EOS R6"));
+
+        let other = dir.path().join("notes.txt");
+        std::fs::write(&other, "not a photo").unwrap();
+        assert!(read_photo_metadata(&other).is_none());
+    }
+}
@@ -0,0 +1,160 @@
+//! XMP packets
+//!
+//! XMP properties may be written as attributes (`exif:Make="Canon"`) or as
+//! elements (`<exif:Make>Canon</exif:Make>`) depending on the writer, so
+//! both forms are matched. Only the properties mirrored in
+//! [`PhotoMetadata`] are read.
+
+use super::exif::parse_exif_datetime;
+use super::{GpsPosition, PhotoMetadata};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+use regex::Regex;
+
+/// Parse the photo properties out of an XMP packet
+pub fn parse_xmp(packet: &str) -> PhotoMetadata {
+    let get = |names: &[&str]| names.iter().find_map(|name| property(packet, name));
+
+    let latitude = get(&["exif:GPSLatitude"]).and_then(|v| parse_gps_coordinate(&v));
+    let longitude = get(&["exif:GPSLongitude"]).and_then(|v| parse_gps_coordinate(&v));
+    let altitude = get(&["exif:GPSAltitude"]).and_then(|v| parse_rational(&v)).map(|alt| {
+        if get(&["exif:GPSAltitudeRef"]).as_deref() == Some("1") {
+            -alt
+        } else {
+            alt
+        }
+    });
+
+    PhotoMetadata {
+        taken_at: get(&["exif:DateTimeOriginal", "photoshop:DateCreated", "xmp:CreateDate"])
+            .and_then(|v| parse_xmp_date(&v)),
+        camera_make: get(&["tiff:Make"]),
+        camera_model: get(&["tiff:Model"]),
+        lens: get(&["exifEX:LensModel", "aux:Lens"]),
+        width: get(&["exif:PixelXDimension", "tiff:ImageWidth"]).and_then(|v| v.parse().ok()),
+        height: get(&["exif:PixelYDimension", "tiff:ImageLength"]).and_then(|v| v.parse().ok()),
+        gps: match (latitude, longitude) {
+            (Some(latitude), Some(longitude)) => Some(GpsPosition {
+                latitude,
+                longitude,
+                altitude,
+            }),
+            _ => None,
+        },
+    }
+}
+
+/// Value of a simple property, in attribute or element form
+fn property(packet: &str, name: &str) -> Option<String> {
+    let name = regex::escape(name);
+    let pattern = format!(
+        r#"{name}\s*=\s*"([^"]*)"|{name}\s*=\s*'([^']*)'|<{name}(?:\s[^>]*)?>([^<]*)</{name}>"#
+    );
+    let captures = Regex::new(&pattern).ok()?.captures(packet)?;
+    let value = (1..=3).find_map(|i| captures.get(i))?.as_str().trim();
+    Some(unescape(value)).filter(|v| !v.is_empty())
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// XMP dates are ISO 8601 with optional time, seconds and offset
+fn parse_xmp_date(value: &str) -> Option<i64> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(dt.timestamp_millis());
+    }
+    let naive = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(value, fmt).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+        });
+    match naive {
+        Some(naive) => Local
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|dt| dt.timestamp_millis()),
+        // Some writers copy the EXIF form verbatim
+        None => parse_exif_datetime(value, None),
+    }
+}
+
+/// `48,51.4005N` or `48,51,24.03N` to signed decimal degrees
+fn parse_gps_coordinate(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let direction = value.chars().last()?;
+    let parts: Vec<f64> = value[..value.len() - direction.len_utf8()]
+        .split(',')
+        .map(|p| p.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .ok()?;
+    let degrees = parts.first()?
+        + parts.get(1).unwrap_or(&0.0) / 60.0
+        + parts.get(2).unwrap_or(&0.0) / 3600.0;
+    match direction.to_ascii_uppercase() {
+        'N' | 'E' => Some(degrees),
+        'S' | 'W' => Some(-degrees),
+        _ => None,
+    }
+}
+
+/// `355/10` or a plain number
+fn parse_rational(value: &str) -> Option<f64> {
+    match value.split_once('/') {
+        Some((n, d)) => {
+            let d: f64 = d.trim().parse().ok()?;
+            (d != 0.0).then_some(n.trim().parse::<f64>().ok()? / d)
+        }
+        None => value.trim().parse().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_xmp_attribute_and_element_forms() {
+        let packet = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+            <rdf:RDF>
+              <rdf:Description rdf:about=""
+                  tiff:Make="SONY"
+                  tiff:Model="ILCE-7M4"
+                  exif:PixelXDimension="7008"
+                  exif:GPSLatitude="48,51.4005N"
+                  exif:GPSLongitude="2,17,40.02W"
+                  exif:GPSAltitude="355/10"
+                  exif:GPSAltitudeRef="1">
+                <exif:DateTimeOriginal>2023-07-14T18:30:05.12+02:00</exif:DateTimeOriginal>
+                <aux:Lens>FE 35mm F1.4 GM</aux:Lens>
+              </rdf:Description>
+            </rdf:RDF>
+          </x:xmpmeta>"#;
+
+        let metadata = parse_xmp(packet);
+        assert_eq!(metadata.camera().as_deref(), Some("SONY ILCE-7M4"));
+        assert_eq!(metadata.lens.as_deref(), Some("FE 35mm F1.4 GM"));
+        assert_eq!(metadata.width, Some(7008));
+        assert_eq!(metadata.height, None);
+        assert_eq!(metadata.taken_at, Some(1_689_352_205_120));
+
+        let gps = metadata.gps.unwrap();
+        assert!((gps.latitude - 48.856_675).abs() < 1e-6);
+        assert!((gps.longitude + 2.294_450).abs() < 1e-6);
+        assert_eq!(gps.altitude, Some(-35.5));
+    }
+
+    #[test]
+    fn test_parse_xmp_without_photo_properties() {
+        assert!(parse_xmp("<x:xmpmeta/>").is_empty());
+        assert_eq!(parse_gps_coordinate("12,30Q"), None);
+        assert_eq!(parse_rational("1/0"), None);
+    }
+}
@@ -0,0 +1,340 @@
+//! Color spaces
+//!
+//! Every color ends up as sRGB. ICC profiles are reduced to their component
+//! count, CMYK is converted naively, and Separation/DeviceN tint transforms
+//! are only evaluated for exponential (type 2) functions; other tints fall
+//! back to a gray ramp, which keeps spot-color artwork visible.
+
+use lopdf::{Dictionary, Document, Object};
+
+use super::{decoded_content, deref, get, number};
+
+/// Deepest chain of color spaces built on other color spaces
+const MAX_DEPTH: usize = 8;
+
+#[derive(Debug, Clone)]
+pub enum ColorSpace {
+    Gray,
+    Rgb,
+    Cmyk,
+    /// CIE L*a*b*; the document's white point is mapped to sRGB white
+    Lab {
+        /// Ranges of a* and b*
+        range: [f32; 4],
+    },
+    Indexed {
+        base: Box<ColorSpace>,
+        /// Base color components of each index, `base.components()` each
+        lookup: Vec<u8>,
+    },
+    /// Separation and DeviceN
+    Tint {
+        components: usize,
+        function: Option<Exponential>,
+        alternate: Box<ColorSpace>,
+    },
+    /// Tiling and shading patterns, which aren't painted
+    Pattern,
+}
+
+/// Exponential interpolation function (PDF function type 2)
+#[derive(Debug, Clone)]
+pub struct Exponential {
+    c0: Vec<f32>,
+    c1: Vec<f32>,
+    exponent: f32,
+}
+
+impl Exponential {
+    fn parse(doc: &Document, object: &Object) -> Option<Self> {
+        let dict = match deref(doc, object) {
+            Object::Dictionary(dict) => dict,
+            Object::Stream(stream) => &stream.dict,
+            _ => return None,
+        };
+        if get(doc, dict, b"FunctionType").and_then(number) != Some(2.0) {
+            return None;
+        }
+        let array = |key: &[u8], default: f32| -> Vec<f32> {
+            get(doc, dict, key)
+                .and_then(|o| o.as_array().ok())
+                .map(|items| items.iter().filter_map(number).collect())
+                .unwrap_or_else(|| vec![default])
+        };
+        Some(Self {
+            c0: array(b"C0", 0.0),
+            c1: array(b"C1", 1.0),
+            exponent: get(doc, dict, b"N").and_then(number).unwrap_or(1.0),
+        })
+    }
+
+    fn eval(&self, t: f32) -> Vec<f32> {
+        let t = t.clamp(0.0, 1.0).powf(self.exponent);
+        self.c0
+            .iter()
+            .zip(&self.c1)
+            .map(|(c0, c1)| c0 + t * (c1 - c0))
+            .collect()
+    }
+}
+
+impl ColorSpace {
+    /// Resolve a color space operand: a name, possibly defined in the
+    /// resources, or an array
+    pub fn resolve(doc: &Document, object: &Object, resources: &Dictionary) -> Option<Self> {
+        Self::resolve_depth(doc, object, resources, 0)
+    }
+
+    fn resolve_depth(
+        doc: &Document,
+        object: &Object,
+        resources: &Dictionary,
+        depth: usize,
+    ) -> Option<Self> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        match deref(doc, object) {
+            Object::Name(name) => match name.as_slice() {
+                b"DeviceGray" | b"G" | b"CalGray" => Some(Self::Gray),
+                b"DeviceRGB" | b"RGB" | b"CalRGB" => Some(Self::Rgb),
+                b"DeviceCMYK" | b"CMYK" => Some(Self::Cmyk),
+                b"Pattern" => Some(Self::Pattern),
+                _ => {
+                    let defined = get(doc, resources, b"ColorSpace")
+                        .and_then(|o| o.as_dict().ok())
+                        .and_then(|spaces| get(doc, spaces, name))?;
+                    Self::resolve_depth(doc, defined, resources, depth + 1)
+                }
+            },
+            Object::Array(items) => Self::from_array(doc, items, resources, depth),
+            _ => None,
+        }
+    }
+
+    fn from_array(
+        doc: &Document,
+        items: &[Object],
+        resources: &Dictionary,
+        depth: usize,
+    ) -> Option<Self> {
+        let family = deref(doc, items.first()?).as_name().ok()?;
+        let param = |i: usize| items.get(i).map(|o| deref(doc, o));
+        match family {
+            b"DeviceGray" | b"G" | b"CalGray" => Some(Self::Gray),
+            b"DeviceRGB" | b"RGB" | b"CalRGB" => Some(Self::Rgb),
+            b"DeviceCMYK" | b"CMYK" => Some(Self::Cmyk),
+            b"Pattern" => Some(Self::Pattern),
+            b"ICCBased" => {
+                let dict = &param(1)?.as_stream().ok()?.dict;
+                if let Some(alternate) = get(doc, dict, b"Alternate") {
+                    if let Some(space) = Self::resolve_depth(doc, alternate, resources, depth + 1) {
+                        return Some(space);
+                    }
+                }
+                match get(doc, dict, b"N").and_then(number).map(|n| n as i64) {
+                    Some(1) => Some(Self::Gray),
+                    Some(4) => Some(Self::Cmyk),
+                    _ => Some(Self::Rgb),
+                }
+            }
+            b"Lab" => {
+                let dict = param(1).and_then(|o| o.as_dict().ok());
+                let numbers = |key: &[u8]| -> Vec<f32> {
+                    dict.and_then(|d| get(doc, d, key))
+                        .and_then(|o| o.as_array().ok())
+                        .map(|items| items.iter().filter_map(number).collect())
+                        .unwrap_or_default()
+                };
+                let range = numbers(b"Range");
+                Some(Self::Lab {
+                    range: match range[..] {
+                        [a0, a1, b0, b1] => [a0, a1, b0, b1],
+                        _ => [-100.0, 100.0, -100.0, 100.0],
+                    },
+                })
+            }
+            b"Indexed" | b"I" => {
+                let base = Self::resolve_depth(doc, items.get(1)?, resources, depth + 1)?;
+                let lookup = match param(3)? {
+                    Object::String(bytes, _) => bytes.clone(),
+                    Object::Stream(stream) => decoded_content(stream),
+                    _ => return None,
+                };
+                Some(Self::Indexed {
+                    base: Box::new(base),
+                    lookup,
+                })
+            }
+            b"Separation" | b"DeviceN" => {
+                let components = if family == b"Separation" {
+                    1
+                } else {
+                    param(1)?.as_array().ok()?.len().max(1)
+                };
+                let alternate = Self::resolve_depth(doc, items.get(2)?, resources, depth + 1)
+                    .unwrap_or(Self::Gray);
+                Some(Self::Tint {
+                    components,
+                    function: items.get(3).and_then(|f| Exponential::parse(doc, f)),
+                    alternate: Box::new(alternate),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Number of color components
+    pub fn components(&self) -> usize {
+        match self {
+            Self::Gray | Self::Indexed { .. } | Self::Pattern => 1,
+            Self::Rgb | Self::Lab { .. } => 3,
+            Self::Cmyk => 4,
+            Self::Tint { components, .. } => *components,
+        }
+    }
+
+    /// Initial color after selecting the space (black, or full tint)
+    pub fn initial_color(&self) -> Vec<f32> {
+        match self {
+            Self::Cmyk => vec![0.0, 0.0, 0.0, 1.0],
+            Self::Tint { components, .. } => vec![1.0; *components],
+            _ => vec![0.0; self.components()],
+        }
+    }
+
+    /// Default `Decode` range of each component in an image
+    pub fn image_decode(&self, bits: u32) -> Vec<(f32, f32)> {
+        match self {
+            Self::Indexed { .. } => vec![(0.0, ((1u32 << bits.min(16)) - 1) as f32)],
+            Self::Lab { range, .. } => vec![(0.0, 100.0), (range[0], range[1]), (range[2], range[3])],
+            _ => vec![(0.0, 1.0); self.components()],
+        }
+    }
+
+    /// Convert color components to sRGB, each 0..1
+    pub fn to_rgb(&self, c: &[f32]) -> [f32; 3] {
+        let at = |i: usize| c.get(i).copied().unwrap_or(0.0);
+        match self {
+            Self::Gray | Self::Pattern => {
+                let g = at(0).clamp(0.0, 1.0);
+                [g, g, g]
+            }
+            Self::Rgb => [
+                at(0).clamp(0.0, 1.0),
+                at(1).clamp(0.0, 1.0),
+                at(2).clamp(0.0, 1.0),
+            ],
+            Self::Cmyk => {
+                let k = at(3).clamp(0.0, 1.0);
+                [
+                    (1.0 - at(0).clamp(0.0, 1.0)) * (1.0 - k),
+                    (1.0 - at(1).clamp(0.0, 1.0)) * (1.0 - k),
+                    (1.0 - at(2).clamp(0.0, 1.0)) * (1.0 - k),
+                ]
+            }
+            Self::Lab { .. } => lab_to_rgb(at(0), at(1), at(2)),
+            Self::Indexed { base, lookup } => {
+                let n = base.components();
+                let index = at(0).round().max(0.0) as usize;
+                let mut components = [0.0f32; 4];
+                for (i, slot) in components.iter_mut().enumerate().take(n.min(4)) {
+                    let byte = lookup.get(index * n + i).copied().unwrap_or(0);
+                    *slot = f32::from(byte) / 255.0;
+                }
+                if let Self::Lab { .. } = **base {
+                    // Lab lookups hold L in 0..100 and a*/b* offset by 128
+                    components[0] *= 100.0;
+                    components[1] = components[1] * 255.0 - 128.0;
+                    components[2] = components[2] * 255.0 - 128.0;
+                }
+                base.to_rgb(&components[..n.min(4)])
+            }
+            Self::Tint {
+                function,
+                alternate,
+                ..
+            } => match function {
+                Some(function) => alternate.to_rgb(&function.eval(at(0))),
+                None => {
+                    let tint = c.iter().fold(0.0f32, |a, b| a.max(*b)).clamp(0.0, 1.0);
+                    let g = 1.0 - tint;
+                    [g, g, g]
+                }
+            },
+        }
+    }
+}
+
+/// CIE L*a*b* (D65) to sRGB
+fn lab_to_rgb(l: f32, a: f32, b: f32) -> [f32; 3] {
+    let fy = (l + 16.0) / 116.0;
+    let fx = fy + a / 500.0;
+    let fz = fy - b / 200.0;
+    let finv = |t: f32| {
+        if t > 6.0 / 29.0 {
+            t * t * t
+        } else {
+            3.0 * (6.0f32 / 29.0).powi(2) * (t - 4.0 / 29.0)
+        }
+    };
+    let x = finv(fx) * 0.9505;
+    let y = finv(fy);
+    let z = finv(fz) * 1.089;
+
+    let linear = [
+        3.2406 * x - 1.5372 * y - 0.4986 * z,
+        -0.9689 * x + 1.8758 * y + 0.0415 * z,
+        0.0557 * x - 0.2040 * y + 1.0570 * z,
+    ];
+    linear.map(|v| {
+        let v = v.clamp(0.0, 1.0);
+        if v <= 0.003_130_8 {
+            12.92 * v
+        } else {
+            1.055 * v.powf(1.0 / 2.4) - 0.055
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::StringFormat;
+
+    #[test]
+    fn test_resolve_and_convert() {
+        let doc = Document::new();
+        let mut spaces = Dictionary::new();
+        spaces.set(
+            "CS0",
+            Object::Array(vec![
+                Object::Name(b"Indexed".to_vec()),
+                Object::Name(b"DeviceRGB".to_vec()),
+                Object::Integer(1),
+                Object::String(vec![255, 0, 0, 0, 0, 255], StringFormat::Hexadecimal),
+            ]),
+        );
+        let mut resources = Dictionary::new();
+        resources.set("ColorSpace", Object::Dictionary(spaces));
+
+        let indexed =
+            ColorSpace::resolve(&doc, &Object::Name(b"CS0".to_vec()), &resources).unwrap();
+        assert_eq!(indexed.components(), 1);
+        assert_eq!(indexed.image_decode(8), vec![(0.0, 255.0)]);
+        assert_eq!(indexed.to_rgb(&[1.0]), [0.0, 0.0, 1.0]);
+
+        assert_eq!(ColorSpace::Cmyk.to_rgb(&[0.0, 1.0, 1.0, 0.0]), [1.0, 0.0, 0.0]);
+        let white = lab_to_rgb(100.0, 0.0, 0.0);
+        assert!(white.iter().all(|c| *c > 0.99));
+
+        // Spot color without a usable function: full tint is black
+        let tint = ColorSpace::Tint {
+            components: 1,
+            function: None,
+            alternate: Box::new(ColorSpace::Cmyk),
+        };
+        assert_eq!(tint.to_rgb(&tint.initial_color()), [0.0, 0.0, 0.0]);
+        assert!(ColorSpace::resolve(&doc, &Object::Name(b"Missing".to_vec()), &resources).is_none());
+    }
+}
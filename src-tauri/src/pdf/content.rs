@@ -0,0 +1,474 @@
+//! Content stream tokenizer
+//!
+//! lopdf's content parser gives up on a whole stream at the first oddity
+//! and cannot read filtered inline images, so pages are tokenized here:
+//! malformed tokens are dropped, and `BI ... ID ... EI` inline images
+//! become a single `BI` operation whose operand is the image stream, with
+//! abbreviated keys expanded.
+
+use lopdf::content::Operation;
+use lopdf::{Dictionary, Object, Stream, StringFormat};
+
+/// Deepest nesting of arrays and dictionaries in an operand
+const MAX_NESTING: usize = 32;
+
+/// Split a content stream into operations
+pub fn parse(data: &[u8]) -> Vec<Operation> {
+    let mut lexer = Lexer { data, pos: 0 };
+    let mut operations = Vec::new();
+    let mut operands = Vec::new();
+
+    while let Some(token) = lexer.next_token() {
+        match token {
+            Token::Keyword(word) => match word {
+                b"true" => operands.push(Object::Boolean(true)),
+                b"false" => operands.push(Object::Boolean(false)),
+                b"null" => operands.push(Object::Null),
+                b"BI" => {
+                    operands.clear();
+                    if let Some(image) = lexer.inline_image() {
+                        operations.push(Operation::new("BI", vec![Object::Stream(image)]));
+                    }
+                }
+                _ => operations.push(Operation::new(
+                    &String::from_utf8_lossy(word),
+                    std::mem::take(&mut operands),
+                )),
+            },
+            token => {
+                if let Some(object) = lexer.object(token, 0) {
+                    operands.push(object);
+                }
+            }
+        }
+    }
+    operations
+}
+
+enum Token<'a> {
+    Number(&'a [u8]),
+    Name(Vec<u8>),
+    String(Vec<u8>, StringFormat),
+    Keyword(&'a [u8]),
+    ArrayStart,
+    ArrayEnd,
+    DictStart,
+    DictEnd,
+}
+
+struct Lexer<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\n' | b'\r' | b'\x0C' | b'\0')
+}
+
+fn is_delimiter(byte: u8) -> bool {
+    matches!(
+        byte,
+        b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%'
+    )
+}
+
+fn is_regular(byte: u8) -> bool {
+    !is_whitespace(byte) && !is_delimiter(byte)
+}
+
+impl<'a> Lexer<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(byte) = self.peek() {
+            if is_whitespace(byte) {
+                self.pos += 1;
+            } else if byte == b'%' {
+                while let Some(byte) = self.peek() {
+                    if byte == b'\n' || byte == b'\r' {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn regular_run(&mut self) -> &'a [u8] {
+        let start = self.pos;
+        while self.peek().is_some_and(is_regular) {
+            self.pos += 1;
+        }
+        &self.data[start..self.pos]
+    }
+
+    fn next_token(&mut self) -> Option<Token<'a>> {
+        loop {
+            self.skip_whitespace();
+            let byte = self.peek()?;
+            match byte {
+                b'/' => {
+                    self.pos += 1;
+                    return Some(Token::Name(decode_name(self.regular_run())));
+                }
+                b'(' => {
+                    self.pos += 1;
+                    return Some(Token::String(self.literal_string(), StringFormat::Literal));
+                }
+                b'<' if self.data.get(self.pos + 1) == Some(&b'<') => {
+                    self.pos += 2;
+                    return Some(Token::DictStart);
+                }
+                b'<' => {
+                    self.pos += 1;
+                    return Some(Token::String(self.hex_string(), StringFormat::Hexadecimal));
+                }
+                b'>' if self.data.get(self.pos + 1) == Some(&b'>') => {
+                    self.pos += 2;
+                    return Some(Token::DictEnd);
+                }
+                b'[' => {
+                    self.pos += 1;
+                    return Some(Token::ArrayStart);
+                }
+                b']' => {
+                    self.pos += 1;
+                    return Some(Token::ArrayEnd);
+                }
+                // Stray delimiters and PostScript braces carry nothing
+                b'>' | b')' | b'{' | b'}' => self.pos += 1,
+                b'+' | b'-' | b'.' | b'0'..=b'9' => return Some(Token::Number(self.regular_run())),
+                _ => return Some(Token::Keyword(self.regular_run())),
+            }
+        }
+    }
+
+    /// Build an operand from a token, reading nested arrays and dictionaries
+    fn object(&mut self, token: Token<'a>, depth: usize) -> Option<Object> {
+        match token {
+            Token::Number(text) => Some(number(text)),
+            Token::Name(name) => Some(Object::Name(name)),
+            Token::String(bytes, format) => Some(Object::String(bytes, format)),
+            Token::ArrayStart if depth < MAX_NESTING => {
+                let mut items = Vec::new();
+                loop {
+                    match self.next_token()? {
+                        Token::ArrayEnd => return Some(Object::Array(items)),
+                        Token::Keyword(b"true") => items.push(Object::Boolean(true)),
+                        Token::Keyword(b"false") => items.push(Object::Boolean(false)),
+                        Token::Keyword(_) | Token::DictEnd => {}
+                        token => items.extend(self.object(token, depth + 1)),
+                    }
+                }
+            }
+            Token::DictStart if depth < MAX_NESTING => {
+                let mut dict = Dictionary::new();
+                loop {
+                    match self.next_token()? {
+                        Token::DictEnd => return Some(Object::Dictionary(dict)),
+                        Token::Name(key) => {
+                            let value = self.next_token()?;
+                            if let Some(value) = self.value(value, depth + 1) {
+                                dict.set(key, value);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// A dictionary value, which may also be a keyword like `true`
+    fn value(&mut self, token: Token<'a>, depth: usize) -> Option<Object> {
+        match token {
+            Token::Keyword(b"true") => Some(Object::Boolean(true)),
+            Token::Keyword(b"false") => Some(Object::Boolean(false)),
+            Token::Keyword(_) => None,
+            token => self.object(token, depth),
+        }
+    }
+
+    fn literal_string(&mut self) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut depth = 1;
+        while let Some(byte) = self.peek() {
+            self.pos += 1;
+            match byte {
+                b'(' => {
+                    depth += 1;
+                    out.push(byte);
+                }
+                b')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                    out.push(byte);
+                }
+                b'\\' => {
+                    let Some(escaped) = self.peek() else { break };
+                    self.pos += 1;
+                    match escaped {
+                        b'n' => out.push(b'\n'),
+                        b'r' => out.push(b'\r'),
+                        b't' => out.push(b'\t'),
+                        b'b' => out.push(b'\x08'),
+                        b'f' => out.push(b'\x0C'),
+                        b'0'..=b'7' => {
+                            let mut value = u32::from(escaped - b'0');
+                            for _ in 0..2 {
+                                match self.peek() {
+                                    Some(digit @ b'0'..=b'7') => {
+                                        value = value * 8 + u32::from(digit - b'0');
+                                        self.pos += 1;
+                                    }
+                                    _ => break,
+                                }
+                            }
+                            out.push(value as u8);
+                        }
+                        // Line continuation
+                        b'\r' => {
+                            if self.peek() == Some(b'\n') {
+                                self.pos += 1;
+                            }
+                        }
+                        b'\n' => {}
+                        other => out.push(other),
+                    }
+                }
+                _ => out.push(byte),
+            }
+        }
+        out
+    }
+
+    fn hex_string(&mut self) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut high: Option<u8> = None;
+        while let Some(byte) = self.peek() {
+            self.pos += 1;
+            if byte == b'>' {
+                break;
+            }
+            let Some(nibble) = (byte as char).to_digit(16) else {
+                continue;
+            };
+            match high.take() {
+                Some(h) => out.push(h << 4 | nibble as u8),
+                None => high = Some(nibble as u8),
+            }
+        }
+        if let Some(h) = high {
+            out.push(h << 4);
+        }
+        out
+    }
+
+    /// Read an inline image after `BI`: the dictionary up to `ID`, then the
+    /// data up to `EI`
+    fn inline_image(&mut self) -> Option<Stream> {
+        let mut dict = Dictionary::new();
+        loop {
+            match self.next_token()? {
+                Token::Keyword(b"ID") => break,
+                Token::Name(key) => {
+                    let value = self.next_token()?;
+                    if let Some(value) = self.value(value, 1) {
+                        dict.set(expand_inline_key(&key), value);
+                    }
+                }
+                _ => {}
+            }
+        }
+        // A single whitespace byte separates ID from the data
+        if self.peek().is_some_and(is_whitespace) {
+            self.pos += 1;
+        }
+
+        let start = self.pos;
+        let end = match unfiltered_length(&dict) {
+            Some(length) if start + length <= self.data.len() => {
+                self.pos = start + length;
+                self.skip_whitespace();
+                if self.data[self.pos..].starts_with(b"EI") {
+                    self.pos += 2;
+                } else {
+                    self.find_end_marker(start);
+                }
+                start + length
+            }
+            _ => self.find_end_marker(start),
+        };
+        Some(Stream::new(dict, self.data[start..end].to_vec()))
+    }
+
+    /// Find `EI` surrounded by whitespace; returns where the data ends and
+    /// moves past the marker
+    fn find_end_marker(&mut self, start: usize) -> usize {
+        let data = self.data;
+        let mut i = start;
+        while i + 1 < data.len() {
+            let delimited_before = i == start || is_whitespace(data[i - 1]);
+            let delimited_after = data.get(i + 2).is_none_or(|b| !is_regular(*b));
+            if data[i] == b'E' && data[i + 1] == b'I' && delimited_before && delimited_after {
+                self.pos = i + 2;
+                return if i > start { i - 1 } else { i };
+            }
+            i += 1;
+        }
+        self.pos = data.len();
+        data.len()
+    }
+}
+
+/// Byte length of an unfiltered inline image, if the dictionary gives it
+fn unfiltered_length(dict: &Dictionary) -> Option<usize> {
+    if dict.has(b"Filter") {
+        return None;
+    }
+    let int = |key: &[u8]| dict.get(key).ok()?.as_i64().ok();
+    let width = usize::try_from(int(b"Width")?).ok()?;
+    let height = usize::try_from(int(b"Height")?).ok()?;
+    let image_mask = matches!(dict.get(b"ImageMask"), Ok(Object::Boolean(true)));
+    let (components, bits) = if image_mask {
+        (1, 1)
+    } else {
+        let components = match dict.get(b"ColorSpace") {
+            Ok(Object::Name(name)) => match name.as_slice() {
+                b"G" | b"DeviceGray" | b"I" | b"Indexed" => 1,
+                b"RGB" | b"DeviceRGB" => 3,
+                b"CMYK" | b"DeviceCMYK" => 4,
+                _ => return None,
+            },
+            Ok(Object::Array(array)) if matches!(array.first(), Some(Object::Name(n)) if n == b"I" || n == b"Indexed") => 1,
+            _ => return None,
+        };
+        (components, usize::try_from(int(b"BitsPerComponent")?).ok()?)
+    };
+    let row = (width * components * bits).div_ceil(8);
+    Some(row * height)
+}
+
+/// Full key names for the abbreviations allowed in inline images
+fn expand_inline_key(key: &[u8]) -> Vec<u8> {
+    let full: &[u8] = match key {
+        b"W" => b"Width",
+        b"H" => b"Height",
+        b"BPC" => b"BitsPerComponent",
+        b"CS" => b"ColorSpace",
+        b"D" => b"Decode",
+        b"DP" => b"DecodeParms",
+        b"F" => b"Filter",
+        b"IM" => b"ImageMask",
+        b"I" => b"Interpolate",
+        b"L" => b"Length",
+        other => other,
+    };
+    full.to_vec()
+}
+
+/// Decode `#xx` escapes in a name
+fn decode_name(raw: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(raw.len());
+    let mut i = 0;
+    while i < raw.len() {
+        let escaped = raw
+            .get(i + 1..i + 3)
+            .filter(|_| raw[i] == b'#')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(raw[i]);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Parse a number token; malformed numbers like `--5` or `1.2.3` keep
+/// their longest valid prefix, or become 0
+fn number(text: &[u8]) -> Object {
+    let text = std::str::from_utf8(text).unwrap_or("0");
+    let text = text.trim_start_matches('+');
+    let negative = text.starts_with('-');
+    let digits = text.trim_start_matches('-');
+    let end = digits
+        .char_indices()
+        .scan(false, |seen_dot, (i, c)| match c {
+            '0'..='9' => Some(i + 1),
+            '.' if !*seen_dot => {
+                *seen_dot = true;
+                Some(i + 1)
+            }
+            _ => None,
+        })
+        .last()
+        .unwrap_or(0);
+    let digits = &digits[..end];
+    let sign = if negative { -1.0 } else { 1.0 };
+
+    if digits.contains('.') {
+        Object::Real(sign * digits.parse::<f32>().unwrap_or(0.0))
+    } else {
+        let value = digits.parse::<i64>().unwrap_or(0);
+        Object::Integer(if negative { -value } else { value })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operators_and_operands() {
+        let ops = parse(
+            b"q 1 0 0 1 -5 .5 cm % comment\n/F#201 12 Tf [(A\\(b\\)) -120 <41 4>] TJ\
+              << /MCID 3 /Open true >> BDC Q",
+        );
+        let names: Vec<&str> = ops.iter().map(|o| o.operator.as_str()).collect();
+        assert_eq!(names, ["q", "cm", "Tf", "TJ", "BDC", "Q"]);
+
+        assert_eq!(ops[1].operands[4], Object::Integer(-5));
+        assert_eq!(ops[1].operands[5], Object::Real(0.5));
+        assert_eq!(ops[2].operands[0], Object::Name(b"F 1".to_vec()));
+
+        let Object::Array(items) = &ops[3].operands[0] else {
+            panic!("TJ takes an array");
+        };
+        assert_eq!(items[0].as_str().unwrap(), b"A(b)");
+        assert_eq!(items[2].as_str().unwrap(), b"A@");
+
+        let dict = ops[4].operands[0].as_dict().unwrap();
+        assert_eq!(dict.get(b"Open").unwrap(), &Object::Boolean(true));
+    }
+
+    #[test]
+    fn test_inline_images() {
+        // Unfiltered data may contain "EI"; its length comes from the header
+        let ops = parse(b"BI /W 2 /H 1 /CS /G /BPC 8 ID EI EI Q BI /W 1 /H 1 /F /AHx ID 00> EI q");
+        let names: Vec<&str> = ops.iter().map(|o| o.operator.as_str()).collect();
+        assert_eq!(names, ["BI", "Q", "BI", "q"]);
+
+        let first = ops[0].operands[0].as_stream().unwrap();
+        assert_eq!(first.content, b"EI");
+        assert_eq!(first.dict.get(b"Width").unwrap().as_i64().unwrap(), 2);
+        assert_eq!(first.dict.get(b"ColorSpace").unwrap().as_name().unwrap(), b"G");
+
+        let second = ops[2].operands[0].as_stream().unwrap();
+        assert_eq!(second.content, b"00>");
+        assert_eq!(second.dict.get(b"Filter").unwrap().as_name().unwrap(), b"AHx");
+    }
+}
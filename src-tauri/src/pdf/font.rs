@@ -0,0 +1,706 @@
+//! Fonts
+//!
+//! Glyph outlines come from embedded TrueType, OpenType and bare CFF fonts
+//! (read with ttf-parser). Fonts that aren't embedded, like the standard
+//! 14, are substituted with a similar installed system font when one
+//! exists. Type 1 (`/FontFile`) and Type 3 fonts have no outlines here;
+//! the renderer greeks their glyphs as bars, which still shows the layout.
+//!
+//! Simple fonts map codes through their encoding's glyph names. The upper
+//! halves of StandardEncoding and MacRomanEncoding are approximated by
+//! WinAnsiEncoding. Composite fonts use two-byte codes as CIDs, which is
+//! exact for `Identity-H`/`Identity-V` (by far the most common encodings).
+
+use lopdf::{Dictionary, Document, Object};
+use once_cell::sync::Lazy;
+use resvg::tiny_skia::{Path, PathBuilder, Transform};
+use resvg::usvg::fontdb;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+use ttf_parser::GlyphId;
+
+use super::{decoded_content, deref, get, number};
+
+/// Installed fonts, loaded the first time a document needs a substitute
+static SYSTEM_FONTS: Lazy<fontdb::Database> = Lazy::new(|| {
+    let mut db = fontdb::Database::new();
+    db.load_system_fonts();
+    tracing::debug!("[pdf] Loaded {} system font faces", db.len());
+    db
+});
+
+/// FontDescriptor flag bits
+const FLAG_FIXED_PITCH: i64 = 1;
+const FLAG_SERIF: i64 = 1 << 1;
+const FLAG_SYMBOLIC: i64 = 1 << 2;
+
+/// What to draw for a character code
+pub enum Glyph {
+    /// Outline in text space (1 unit = font size)
+    Outline(Rc<Path>),
+    /// Nothing to draw (spaces, empty glyphs)
+    Blank,
+    /// The font has no outlines; draw a placeholder bar
+    Missing,
+}
+
+/// Font program that provides the outlines
+enum Outlines {
+    Sfnt { data: Vec<u8>, index: u32 },
+    Cff { data: Vec<u8> },
+}
+
+impl Outlines {
+    /// Glyph outline in font units, plus the font matrix to text space
+    fn outline(&self, gid: GlyphId) -> Option<(Path, Transform)> {
+        let mut builder = OutlineBuilder(PathBuilder::new());
+        let matrix = match self {
+            Self::Sfnt { data, index } => {
+                let face = ttf_parser::Face::parse(data, *index).ok()?;
+                face.outline_glyph(gid, &mut builder)?;
+                let scale = 1.0 / f32::from(face.units_per_em().max(1));
+                Transform::from_scale(scale, scale)
+            }
+            Self::Cff { data } => {
+                let table = ttf_parser::cff::Table::parse(data)?;
+                table.outline(gid, &mut builder).ok()?;
+                let m = table.matrix();
+                Transform::from_row(m.sx, m.ky, m.kx, m.sy, m.tx, m.ty)
+            }
+        };
+        Some((builder.0.finish()?, matrix))
+    }
+}
+
+struct OutlineBuilder(PathBuilder);
+
+impl ttf_parser::OutlineBuilder for OutlineBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.0.move_to(x, y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.0.line_to(x, y);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.0.quad_to(x1, y1, x, y);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.0.cubic_to(x1, y1, x2, y2, x, y);
+    }
+
+    fn close(&mut self) {
+        self.0.close();
+    }
+}
+
+enum Encoding {
+    /// One-byte codes mapped to glyph ids and Unicode
+    Simple {
+        glyphs: Box<[Option<GlyphId>; 256]>,
+        unicode: Box<[Option<char>; 256]>,
+    },
+    /// Two-byte codes used as CIDs
+    Composite { cid_to_gid: Option<Vec<u16>> },
+}
+
+/// A font resource ready for drawing
+pub struct Font {
+    encoding: Encoding,
+    outlines: Option<Outlines>,
+    /// Glyph widths: simple fonts by code, composite fonts by CID ranges
+    widths: BTreeMap<u32, (u32, f32)>,
+    default_width: f32,
+    /// Take advances from the font program (simple fonts without /Widths)
+    program_widths: bool,
+    /// Glyph space width units to text space (1/1000 for all but Type 3)
+    width_scale: f32,
+    cache: RefCell<HashMap<u32, Option<Rc<Path>>>>,
+}
+
+impl Font {
+    /// Load a font dictionary; never fails, since any font can be greeked
+    pub fn load(doc: &Document, dict: &Dictionary) -> Self {
+        let subtype = get(doc, dict, b"Subtype")
+            .and_then(|o| o.as_name().ok())
+            .unwrap_or(b"Type1");
+        if subtype == b"Type0" {
+            Self::load_composite(doc, dict)
+        } else {
+            Self::load_simple(doc, dict, subtype == b"Type3")
+        }
+    }
+
+    /// Split a string into character codes
+    pub fn codes(&self, bytes: &[u8]) -> Vec<u32> {
+        match self.encoding {
+            Encoding::Simple { .. } => bytes.iter().map(|b| u32::from(*b)).collect(),
+            Encoding::Composite { .. } => bytes
+                .chunks(2)
+                .map(|pair| match *pair {
+                    [high, low] => u32::from(high) << 8 | u32::from(low),
+                    [single] => u32::from(single),
+                    _ => 0,
+                })
+                .collect(),
+        }
+    }
+
+    /// Whether codes are single bytes (word spacing applies to code 32)
+    pub fn is_simple(&self) -> bool {
+        matches!(self.encoding, Encoding::Simple { .. })
+    }
+
+    /// Horizontal advance of a code in text space (1 unit = font size)
+    pub fn width(&self, code: u32) -> f32 {
+        let width = self
+            .widths
+            .range(..=code)
+            .next_back()
+            .filter(|(_, (last, _))| code <= *last)
+            .map(|(_, (_, width))| *width)
+            .or_else(|| {
+                self.program_widths
+                    .then(|| self.outline_advance(code))
+                    .flatten()
+            })
+            .unwrap_or(self.default_width);
+        width * self.width_scale
+    }
+
+    /// The glyph to draw for a code
+    pub fn glyph(&self, code: u32) -> Glyph {
+        if let Encoding::Simple { unicode, .. } = &self.encoding {
+            if unicode[code as usize & 0xFF].is_some_and(char::is_whitespace) {
+                return Glyph::Blank;
+            }
+        }
+        if self.outlines.is_none() {
+            return Glyph::Missing;
+        }
+        let mut cache = self.cache.borrow_mut();
+        let path = cache.entry(code).or_insert_with(|| {
+            let gid = self.glyph_id(code)?;
+            let (path, matrix) = self.outlines.as_ref()?.outline(gid)?;
+            path.transform(matrix).map(Rc::new)
+        });
+        match path {
+            Some(path) => Glyph::Outline(Rc::clone(path)),
+            None => Glyph::Blank,
+        }
+    }
+
+    fn glyph_id(&self, code: u32) -> Option<GlyphId> {
+        match &self.encoding {
+            Encoding::Simple { glyphs, .. } => glyphs[code as usize & 0xFF],
+            Encoding::Composite { cid_to_gid } => match cid_to_gid {
+                Some(map) => map.get(code as usize).map(|gid| GlyphId(*gid)),
+                None => u16::try_from(code).ok().map(GlyphId),
+            },
+        }
+    }
+
+    /// Advance from the font program, for substituted fonts without widths
+    fn outline_advance(&self, code: u32) -> Option<f32> {
+        let Some(Outlines::Sfnt { data, index }) = &self.outlines else {
+            return None;
+        };
+        let face = ttf_parser::Face::parse(data, *index).ok()?;
+        let advance = face.glyph_hor_advance(self.glyph_id(code)?)?;
+        Some(f32::from(advance) * 1000.0 / f32::from(face.units_per_em().max(1)))
+    }
+
+    fn load_simple(doc: &Document, dict: &Dictionary, type3: bool) -> Self {
+        let descriptor = get(doc, dict, b"FontDescriptor").and_then(|o| o.as_dict().ok());
+        let flags = descriptor
+            .and_then(|d| get(doc, d, b"Flags"))
+            .and_then(|o| o.as_i64().ok())
+            .unwrap_or(0);
+        let base_font = base_font(doc, dict);
+
+        let embedded = descriptor.and_then(|d| embedded_outlines(doc, d));
+        let is_embedded = embedded.is_some();
+        let outlines = if type3 {
+            None
+        } else {
+            embedded.or_else(|| system_outlines(&base_font, flags))
+        };
+
+        // Glyph names per code: the base encoding, then /Differences
+        let encoding = get(doc, dict, b"Encoding");
+        let (base, differences) = match encoding {
+            Some(Object::Name(name)) => (Some(name.as_slice()), None),
+            Some(Object::Dictionary(enc)) => (
+                get(doc, enc, b"BaseEncoding").and_then(|o| o.as_name().ok()),
+                get(doc, enc, b"Differences").and_then(|o| o.as_array().ok()),
+            ),
+            _ => (None, None),
+        };
+        let symbolic = flags & FLAG_SYMBOLIC != 0 && base.is_none();
+        let builtin = encoding.is_none() || symbolic;
+
+        let mut names: Vec<Option<String>> = (0..=255u8)
+            .map(|code| standard_name(code, base).map(str::to_string))
+            .collect();
+        let mut differs = [false; 256];
+        if let Some(items) = differences {
+            let mut code = 0usize;
+            for item in items {
+                match deref(doc, item) {
+                    Object::Integer(start) => code = (*start).clamp(0, 255) as usize,
+                    Object::Name(name) if code < 256 => {
+                        names[code] = Some(String::from_utf8_lossy(name).into_owned());
+                        differs[code] = true;
+                        code += 1;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut glyphs = Box::new([None; 256]);
+        let mut unicode = Box::new([None; 256]);
+        for code in 0..256 {
+            let name = names[code].as_deref();
+            unicode[code] = name.and_then(glyph_name_to_unicode);
+            if let Some(outlines) = &outlines {
+                glyphs[code] = simple_glyph_id(
+                    outlines,
+                    code as u8,
+                    name,
+                    unicode[code],
+                    differs[code],
+                    builtin && is_embedded,
+                );
+            }
+        }
+        if builtin && is_embedded {
+            // Built-in encodings don't tell which codes are spaces
+            unicode[32] = Some(' ');
+        }
+
+        // Widths by code
+        let first = get(doc, dict, b"FirstChar").and_then(number).unwrap_or(0.0) as u32;
+        let mut widths = BTreeMap::new();
+        if let Some(items) = get(doc, dict, b"Widths").and_then(|o| o.as_array().ok()) {
+            for (i, item) in items.iter().enumerate() {
+                if let Some(width) = number(deref(doc, item)) {
+                    let code = first + i as u32;
+                    widths.insert(code, (code, width));
+                }
+            }
+        }
+        let missing_width = descriptor
+            .and_then(|d| get(doc, d, b"MissingWidth"))
+            .and_then(number);
+
+        let width_scale = if type3 {
+            get(doc, dict, b"FontMatrix")
+                .and_then(|o| o.as_array().ok())
+                .and_then(|m| m.first())
+                .and_then(|o| number(deref(doc, o)))
+                .unwrap_or(0.001)
+        } else {
+            0.001
+        };
+
+        Self {
+            encoding: Encoding::Simple { glyphs, unicode },
+            outlines,
+            default_width: missing_width.unwrap_or(if widths.is_empty() { 500.0 } else { 0.0 }),
+            program_widths: widths.is_empty(),
+            widths,
+            width_scale,
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    fn load_composite(doc: &Document, dict: &Dictionary) -> Self {
+        let cid_font = get(doc, dict, b"DescendantFonts")
+            .and_then(|o| o.as_array().ok())
+            .and_then(|fonts| fonts.first())
+            .and_then(|o| deref(doc, o).as_dict().ok());
+        if let Some(Object::Name(cmap)) = get(doc, dict, b"Encoding") {
+            if !cmap.starts_with(b"Identity") {
+                tracing::debug!(
+                    "[pdf] CMap {} read as Identity",
+                    String::from_utf8_lossy(cmap)
+                );
+            }
+        }
+
+        let descriptor = cid_font
+            .and_then(|f| get(doc, f, b"FontDescriptor"))
+            .and_then(|o| o.as_dict().ok());
+        let flags = descriptor
+            .and_then(|d| get(doc, d, b"Flags"))
+            .and_then(|o| o.as_i64().ok())
+            .unwrap_or(0);
+        let outlines = descriptor
+            .and_then(|d| embedded_outlines(doc, d))
+            .or_else(|| system_outlines(&base_font(doc, dict), flags));
+
+        // CID -> glyph id: /CIDToGIDMap for TrueType, the charset for
+        // CID-keyed CFF, identity otherwise
+        let cid_to_gid = match &outlines {
+            Some(Outlines::Cff { data }) => ttf_parser::cff::Table::parse(data).and_then(|t| {
+                let mut map = Vec::new();
+                for gid in 0..t.number_of_glyphs() {
+                    let cid = t.glyph_cid(GlyphId(gid))? as usize;
+                    if map.len() <= cid {
+                        map.resize(cid + 1, 0);
+                    }
+                    map[cid] = gid;
+                }
+                Some(map)
+            }),
+            _ => cid_font
+                .and_then(|f| get(doc, f, b"CIDToGIDMap"))
+                .and_then(|o| o.as_stream().ok())
+                .map(|stream| {
+                    decoded_content(stream)
+                        .chunks_exact(2)
+                        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                        .collect()
+                }),
+        };
+
+        // /W: [c [w1 w2 ...]] or [c_first c_last w]
+        let mut widths = BTreeMap::new();
+        let items = cid_font
+            .and_then(|f| get(doc, f, b"W"))
+            .and_then(|o| o.as_array().ok())
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let mut i = 0;
+        while i < items.len() {
+            let Some(start) = number(deref(doc, &items[i])) else {
+                break;
+            };
+            let start = start.max(0.0) as u32;
+            match items.get(i + 1).map(|o| deref(doc, o)) {
+                Some(Object::Array(list)) => {
+                    for (offset, width) in list.iter().enumerate() {
+                        if let Some(width) = number(deref(doc, width)) {
+                            let cid = start + offset as u32;
+                            widths.insert(cid, (cid, width));
+                        }
+                    }
+                    i += 2;
+                }
+                Some(last) => {
+                    let last = number(last).unwrap_or(0.0).max(0.0) as u32;
+                    let width = items
+                        .get(i + 2)
+                        .and_then(|o| number(deref(doc, o)))
+                        .unwrap_or(0.0);
+                    widths.insert(start, (last.max(start), width));
+                    i += 3;
+                }
+                None => break,
+            }
+        }
+
+        Self {
+            encoding: Encoding::Composite { cid_to_gid },
+            outlines,
+            widths,
+            default_width: cid_font
+                .and_then(|f| get(doc, f, b"DW"))
+                .and_then(number)
+                .unwrap_or(1000.0),
+            program_widths: false,
+            width_scale: 0.001,
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+}
+
+fn base_font(doc: &Document, dict: &Dictionary) -> String {
+    let name = get(doc, dict, b"BaseFont")
+        .and_then(|o| o.as_name().ok())
+        .map(|n| String::from_utf8_lossy(n).into_owned())
+        .unwrap_or_default();
+    // Drop the subset tag, e.g. "ABCDEF+Calibri"
+    match name.split_once('+') {
+        Some((tag, rest)) if tag.len() == 6 => rest.to_string(),
+        _ => name,
+    }
+}
+
+/// The embedded font program of a descriptor, if it has outlines we read
+fn embedded_outlines(doc: &Document, descriptor: &Dictionary) -> Option<Outlines> {
+    let (stream, sfnt) = if let Some(file) = get(doc, descriptor, b"FontFile2") {
+        (file.as_stream().ok()?, true)
+    } else {
+        let file = get(doc, descriptor, b"FontFile3")?.as_stream().ok()?;
+        let subtype = get(doc, &file.dict, b"Subtype").and_then(|o| o.as_name().ok());
+        (file, subtype == Some(b"OpenType".as_slice()))
+    };
+    let data = decoded_content(stream);
+
+    if sfnt {
+        ttf_parser::Face::parse(&data, 0).ok()?;
+        Some(Outlines::Sfnt { data, index: 0 })
+    } else {
+        ttf_parser::cff::Table::parse(&data)?;
+        Some(Outlines::Cff { data })
+    }
+}
+
+/// An installed font resembling a non-embedded one
+fn system_outlines(base_font: &str, flags: i64) -> Option<Outlines> {
+    let lower = base_font.to_lowercase();
+    if lower.contains("symbol") || lower.contains("dingbats") {
+        return None;
+    }
+    let family = base_font
+        .split(['-', ','])
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches("MT")
+        .trim_end_matches("PS");
+
+    use fontdb::Family;
+    let mut families = vec![Family::Name(family)];
+    if lower.contains("courier") || lower.contains("mono") || flags & FLAG_FIXED_PITCH != 0 {
+        families.extend([
+            Family::Name("Courier New"),
+            Family::Name("Liberation Mono"),
+            Family::Name("DejaVu Sans Mono"),
+            Family::Monospace,
+        ]);
+    } else if lower.contains("times")
+        || lower.contains("roman")
+        || (lower.contains("serif") && !lower.contains("sans"))
+        || flags & FLAG_SERIF != 0
+    {
+        families.extend([
+            Family::Name("Times New Roman"),
+            Family::Name("Liberation Serif"),
+            Family::Name("DejaVu Serif"),
+            Family::Serif,
+        ]);
+    } else {
+        families.extend([
+            Family::Name("Arial"),
+            Family::Name("Liberation Sans"),
+            Family::Name("Arimo"),
+            Family::Name("DejaVu Sans"),
+            Family::SansSerif,
+        ]);
+    }
+
+    let bold = ["bold", "black", "heavy", "semibold"]
+        .iter()
+        .any(|w| lower.contains(w));
+    let italic = lower.contains("italic") || lower.contains("oblique");
+    let query = fontdb::Query {
+        families: &families,
+        weight: if bold {
+            fontdb::Weight::BOLD
+        } else {
+            fontdb::Weight::NORMAL
+        },
+        stretch: fontdb::Stretch::Normal,
+        style: if italic {
+            fontdb::Style::Italic
+        } else {
+            fontdb::Style::Normal
+        },
+    };
+    let id = SYSTEM_FONTS.query(&query)?;
+    SYSTEM_FONTS.with_face_data(id, |data, index| Outlines::Sfnt {
+        data: data.to_vec(),
+        index,
+    })
+}
+
+/// Glyph id of a simple font code
+fn simple_glyph_id(
+    outlines: &Outlines,
+    code: u8,
+    name: Option<&str>,
+    unicode: Option<char>,
+    from_differences: bool,
+    builtin: bool,
+) -> Option<GlyphId> {
+    match outlines {
+        Outlines::Cff { data } => {
+            let table = ttf_parser::cff::Table::parse(data)?;
+            let by_name = name.and_then(|n| table.glyph_index_by_name(n));
+            if from_differences || !builtin {
+                by_name.or_else(|| table.glyph_index(code))
+            } else {
+                table.glyph_index(code).or(by_name)
+            }
+            .filter(|gid| gid.0 != 0)
+        }
+        Outlines::Sfnt { data, index } => {
+            let face = ttf_parser::Face::parse(data, *index).ok()?;
+            let by_name = || name.and_then(|n| face.glyph_index_by_name(n));
+            let by_unicode = || unicode.and_then(|c| face.glyph_index(c));
+            // Symbolic TrueType fonts map raw codes, often at 0xF000 + code
+            let by_code = || {
+                let cmap = face.tables().cmap?;
+                cmap.subtables.into_iter().find_map(|table| {
+                    table
+                        .glyph_index(0xF000 + u32::from(code))
+                        .or_else(|| table.glyph_index(u32::from(code)))
+                })
+            };
+            if builtin {
+                by_code().or_else(by_unicode)
+            } else {
+                by_unicode().or_else(by_name).or_else(by_code)
+            }
+            .filter(|gid| gid.0 != 0)
+        }
+    }
+}
+
+/// Glyph names of codes 0x20-0xFF in WinAnsiEncoding ("" if undefined)
+const WIN_ANSI_NAMES: [&str; 224] = [
+    "space", "exclam", "quotedbl", "numbersign", "dollar", "percent", "ampersand", "quotesingle",
+    "parenleft", "parenright", "asterisk", "plus", "comma", "hyphen", "period", "slash",
+    "zero", "one", "two", "three", "four", "five", "six", "seven",
+    "eight", "nine", "colon", "semicolon", "less", "equal", "greater", "question",
+    "at", "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O",
+    "P", "Q", "R", "S", "T", "U", "V", "W", "X", "Y", "Z",
+    "bracketleft", "backslash", "bracketright", "asciicircum", "underscore",
+    "grave", "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m", "n", "o",
+    "p", "q", "r", "s", "t", "u", "v", "w", "x", "y", "z",
+    "braceleft", "bar", "braceright", "asciitilde", "",
+    "Euro", "", "quotesinglbase", "florin", "quotedblbase", "ellipsis", "dagger", "daggerdbl",
+    "circumflex", "perthousand", "Scaron", "guilsinglleft", "OE", "", "Zcaron", "",
+    "", "quoteleft", "quoteright", "quotedblleft", "quotedblright", "bullet", "endash", "emdash",
+    "tilde", "trademark", "scaron", "guilsinglright", "oe", "", "zcaron", "Ydieresis",
+    "space", "exclamdown", "cent", "sterling", "currency", "yen", "brokenbar", "section",
+    "dieresis", "copyright", "ordfeminine", "guillemotleft", "logicalnot", "hyphen", "registered", "macron",
+    "degree", "plusminus", "twosuperior", "threesuperior", "acute", "mu", "paragraph", "periodcentered",
+    "cedilla", "onesuperior", "ordmasculine", "guillemotright", "onequarter", "onehalf", "threequarters", "questiondown",
+    "Agrave", "Aacute", "Acircumflex", "Atilde", "Adieresis", "Aring", "AE", "Ccedilla",
+    "Egrave", "Eacute", "Ecircumflex", "Edieresis", "Igrave", "Iacute", "Icircumflex", "Idieresis",
+    "Eth", "Ntilde", "Ograve", "Oacute", "Ocircumflex", "Otilde", "Odieresis", "multiply",
+    "Oslash", "Ugrave", "Uacute", "Ucircumflex", "Udieresis", "Yacute", "Thorn", "germandbls",
+    "agrave", "aacute", "acircumflex", "atilde", "adieresis", "aring", "ae", "ccedilla",
+    "egrave", "eacute", "ecircumflex", "edieresis", "igrave", "iacute", "icircumflex", "idieresis",
+    "eth", "ntilde", "ograve", "oacute", "ocircumflex", "otilde", "odieresis", "divide",
+    "oslash", "ugrave", "uacute", "ucircumflex", "udieresis", "yacute", "thorn", "ydieresis",
+];
+
+/// Common glyph names outside WinAnsiEncoding
+const EXTRA_NAMES: &[(&str, char)] = &[
+    ("fi", '\u{FB01}'),
+    ("fl", '\u{FB02}'),
+    ("ff", '\u{FB00}'),
+    ("ffi", '\u{FB03}'),
+    ("ffl", '\u{FB04}'),
+    ("minus", '\u{2212}'),
+    ("nbspace", '\u{A0}'),
+    ("sfthyphen", '\u{AD}'),
+    ("dotlessi", '\u{131}'),
+    ("Lslash", '\u{141}'),
+    ("lslash", '\u{142}'),
+    ("fraction", '\u{2044}'),
+    ("ring", '\u{2DA}'),
+    ("caron", '\u{2C7}'),
+    ("breve", '\u{2D8}'),
+    ("dotaccent", '\u{2D9}'),
+    ("hungarumlaut", '\u{2DD}'),
+    ("ogonek", '\u{2DB}'),
+];
+
+/// Glyph name of a code in a base encoding (WinAnsi unless it's Standard)
+fn standard_name(code: u8, base: Option<&[u8]>) -> Option<&'static str> {
+    let standard = matches!(base, None | Some(b"StandardEncoding"));
+    match code {
+        0x27 if standard => Some("quoteright"),
+        0x60 if standard => Some("quoteleft"),
+        0x20.. => Some(WIN_ANSI_NAMES[usize::from(code - 0x20)]).filter(|n| !n.is_empty()),
+        _ => None,
+    }
+}
+
+/// Unicode character of a glyph name: WinAnsi names, a few common extras,
+/// and the `uniXXXX`/`uXXXX` forms. Suffixes like `.sc` are ignored and
+/// ligatures like `f_i` resolve to their first part.
+pub fn glyph_name_to_unicode(name: &str) -> Option<char> {
+    let name = name.split('.').next().unwrap_or(name);
+    let name = name.split('_').next().unwrap_or(name);
+    if let Some(position) = WIN_ANSI_NAMES.iter().position(|n| *n == name) {
+        return Some(cp1252(position as u8 + 0x20));
+    }
+    if let Some((_, c)) = EXTRA_NAMES.iter().find(|(n, _)| *n == name) {
+        return Some(*c);
+    }
+    let hex = name
+        .strip_prefix("uni")
+        .filter(|h| h.len() >= 4)
+        .map(|h| &h[..4])
+        .or_else(|| name.strip_prefix('u').filter(|h| (4..=6).contains(&h.len())))?;
+    u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+}
+
+/// Windows-1252 byte to char: Latin-1 apart from the 0x80-0x9F block
+fn cp1252(byte: u8) -> char {
+    const HIGH: [u16; 32] = [
+        0x20AC, 0, 0x201A, 0x0192, 0x201E, 0x2026, 0x2020, 0x2021, 0x02C6, 0x2030, 0x0160,
+        0x2039, 0x0152, 0, 0x017D, 0, 0, 0x2018, 0x2019, 0x201C, 0x201D, 0x2022, 0x2013,
+        0x2014, 0x02DC, 0x2122, 0x0161, 0x203A, 0x0153, 0, 0x017E, 0x0178,
+    ];
+    match byte {
+        0x80..=0x9F => char::from_u32(u32::from(HIGH[usize::from(byte - 0x80)])).unwrap_or('\0'),
+        _ => byte as char,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::dictionary;
+
+    #[test]
+    fn test_glyph_names() {
+        assert_eq!(glyph_name_to_unicode("A"), Some('A'));
+        assert_eq!(glyph_name_to_unicode("eacute"), Some('\u{E9}'));
+        assert_eq!(glyph_name_to_unicode("quotedblleft"), Some('\u{201C}'));
+        assert_eq!(glyph_name_to_unicode("uni20AC"), Some('\u{20AC}'));
+        assert_eq!(glyph_name_to_unicode("u1F600"), Some('\u{1F600}'));
+        assert_eq!(glyph_name_to_unicode("a.sc"), Some('a'));
+        assert_eq!(glyph_name_to_unicode("f_i"), Some('f'));
+        assert_eq!(glyph_name_to_unicode("g123"), None);
+        assert_eq!(standard_name(0x27, None), Some("quoteright"));
+        assert_eq!(standard_name(0x27, Some(b"WinAnsiEncoding")), Some("quotesingle"));
+    }
+
+    #[test]
+    fn test_composite_widths_and_codes() {
+        let doc = Document::new();
+        let cid_font = lopdf::dictionary! {
+            "Type" => "Font",
+            "Subtype" => "CIDFontType2",
+            "DW" => 500,
+            "W" => vec![
+                10.into(), Object::Array(vec![600.into(), 700.into()]),
+                20.into(), 30.into(), 250.into(),
+            ],
+        };
+        let font = lopdf::dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type0",
+            "BaseFont" => "ZZZZZZ+NoSuchFont",
+            "Encoding" => "Identity-H",
+            "DescendantFonts" => vec![Object::Dictionary(cid_font)],
+        };
+        let font = Font::load(&doc, &font);
+        assert!(!font.is_simple());
+        assert_eq!(font.codes(&[0, 10, 0, 11, 1]), [10, 11, 1]);
+        for (code, width) in [(10, 0.6), (11, 0.7), (25, 0.25), (31, 0.5)] {
+            assert!((font.width(code) - width).abs() < 1e-6, "width of {}", code);
+        }
+    }
+}
@@ -0,0 +1,548 @@
+//! Image XObjects and inline images
+//!
+//! Images are decoded straight to the size they will roughly cover on the
+//! page, sampling rows and columns of large scans instead of decoding them
+//! at full resolution. Supported: the Flate, LZW, ASCII85, ASCIIHex and
+//! RunLength filters, DCT (JPEG) and CCITT fax data, 1-16 bit samples with
+//! `Decode` arrays, stencil masks, soft masks and color key masks. JPEG 2000
+//! and JBIG2 images are drawn as gray placeholders.
+
+use image::{imageops::FilterType, ImageFormat};
+use lopdf::{Dictionary, Document, Object, Stream};
+use resvg::tiny_skia::{IntSize, Pixmap};
+
+use super::color::ColorSpace;
+use super::{apply_filter, deref, get, inflates_too_large, number};
+
+/// Largest JPEG decoded in full before sampling, in pixels
+const MAX_JPEG_PIXELS: u64 = 40_000_000;
+
+/// Color of images whose codec isn't supported
+const PLACEHOLDER: [u8; 4] = [208, 208, 208, 255];
+
+/// Data left after the stream filters ran
+enum Encoded {
+    Raw(Vec<u8>),
+    Jpeg(Vec<u8>),
+    Unsupported(String),
+}
+
+/// Straight (not premultiplied) RGBA pixels at output size
+struct Raster {
+    width: u32,
+    height: u32,
+    pixels: Vec<[u8; 4]>,
+}
+
+/// Decode an image to a premultiplied pixmap of at most `max_width` x
+/// `max_height` pixels
+///
+/// `stencil` is the fill color used for image masks (`/ImageMask true`).
+pub fn decode(
+    doc: &Document,
+    stream: &Stream,
+    resources: &Dictionary,
+    stencil: [u8; 3],
+    max_width: u32,
+    max_height: u32,
+) -> Result<Pixmap, String> {
+    let (width, height) = dimensions(doc, &stream.dict)?;
+    let out_width = width.min(max_width.max(1));
+    let out_height = height.min(max_height.max(1));
+
+    let mut raster = decode_raster(doc, stream, resources, stencil, out_width, out_height)?;
+
+    // Soft masks and explicit masks become the alpha channel
+    if let Some(mask) = get(doc, &stream.dict, b"SMask").and_then(|o| o.as_stream().ok()) {
+        if let Ok(alpha) = decode_raster(doc, mask, resources, [0; 3], out_width, out_height) {
+            for (pixel, a) in raster.pixels.iter_mut().zip(&alpha.pixels) {
+                pixel[3] = (u16::from(pixel[3]) * u16::from(a[0]) / 255) as u8;
+            }
+        }
+    } else if let Some(mask) = get(doc, &stream.dict, b"Mask").and_then(|o| o.as_stream().ok()) {
+        // Explicit masks are stencils: sample 0 paints the image
+        if let Ok(alpha) = decode_raster(doc, mask, resources, [0; 3], out_width, out_height) {
+            for (pixel, a) in raster.pixels.iter_mut().zip(&alpha.pixels) {
+                pixel[3] = (u16::from(pixel[3]) * u16::from(a[3]) / 255) as u8;
+            }
+        }
+    }
+
+    to_pixmap(raster)
+}
+
+fn dimensions(doc: &Document, dict: &Dictionary) -> Result<(u32, u32), String> {
+    let side = |key: &[u8]| {
+        get(doc, dict, key)
+            .and_then(number)
+            .filter(|v| *v >= 1.0 && *v <= f32::from(u16::MAX))
+            .map(|v| v as u32)
+            .ok_or_else(|| format!("Image has no valid /{}", String::from_utf8_lossy(key)))
+    };
+    Ok((side(b"Width")?, side(b"Height")?))
+}
+
+/// Decode an image or mask, sampled to exactly `out_width` x `out_height`
+fn decode_raster(
+    doc: &Document,
+    stream: &Stream,
+    resources: &Dictionary,
+    stencil: [u8; 3],
+    out_width: u32,
+    out_height: u32,
+) -> Result<Raster, String> {
+    let dict = &stream.dict;
+    let (width, height) = dimensions(doc, dict)?;
+    let image_mask = matches!(get(doc, dict, b"ImageMask"), Some(Object::Boolean(true)));
+
+    let (data, bits) = match run_filters(doc, stream) {
+        Encoded::Raw(data) => {
+            let bits = if image_mask {
+                1
+            } else {
+                get(doc, dict, b"BitsPerComponent")
+                    .and_then(number)
+                    .map_or(8, |b| b as u32)
+            };
+            (data, bits)
+        }
+        Encoded::Jpeg(data) => return decode_jpeg(&data, width, height, out_width, out_height),
+        Encoded::Unsupported(filter) => {
+            tracing::debug!("[pdf] Skipping image with unsupported filter {}", filter);
+            return Ok(Raster {
+                width: 1,
+                height: 1,
+                pixels: vec![PLACEHOLDER],
+            });
+        }
+    };
+    if !matches!(bits, 1 | 2 | 4 | 8 | 16) {
+        return Err(format!("Unsupported image depth: {} bits", bits));
+    }
+
+    let decode_array: Vec<f32> = get(doc, dict, b"Decode")
+        .and_then(|o| o.as_array().ok())
+        .map(|items| items.iter().filter_map(number).collect())
+        .unwrap_or_default();
+
+    let samples = Samples {
+        data: &data,
+        width,
+        height,
+        components: 1,
+        bits,
+    };
+
+    if image_mask {
+        // Sample 0 paints with the fill color, unless Decode is [1 0]
+        let inverted = decode_array.first().is_some_and(|d| *d > 0.5);
+        let pixels = samples.resample(out_width, out_height, |raw| {
+            let paint = (raw[0] == 0) != inverted;
+            let [r, g, b] = stencil;
+            [r, g, b, if paint { 255 } else { 0 }]
+        });
+        return Ok(Raster {
+            width: out_width,
+            height: out_height,
+            pixels,
+        });
+    }
+
+    let space = get(doc, dict, b"ColorSpace")
+        .and_then(|cs| ColorSpace::resolve(doc, cs, resources))
+        .unwrap_or(ColorSpace::Gray);
+    let components = space.components();
+    let samples = Samples {
+        components,
+        ..samples
+    };
+
+    // A malformed Decode array (odd length, too short) falls back to the
+    // color space default
+    let ranges: Vec<(f32, f32)> = decode_array
+        .chunks_exact(2)
+        .take(components)
+        .map(|d| (d[0], d[1]))
+        .collect();
+    let ranges = if ranges.len() == components {
+        ranges
+    } else {
+        space.image_decode(bits)
+    };
+    // Sample value -> component lookup, per component (16-bit samples use
+    // their high byte)
+    let table_bits = bits.min(8);
+    let levels = 1usize << table_bits;
+    let max_value = (levels - 1) as f32;
+    let tables: Vec<Vec<f32>> = ranges
+        .iter()
+        .map(|(min, max)| {
+            (0..levels)
+                .map(|v| min + v as f32 * (max - min) / max_value)
+                .collect()
+        })
+        .collect();
+    let lookup = |component: usize, raw: u16| -> f32 {
+        let value = if bits == 16 { raw >> 8 } else { raw } as usize;
+        tables[component][value.min(levels - 1)]
+    };
+
+    let color_key: Vec<u16> = get(doc, dict, b"Mask")
+        .and_then(|o| o.as_array().ok())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(number)
+                .map(|v| v.max(0.0) as u16)
+                .collect()
+        })
+        .filter(|key: &Vec<u16>| key.len() >= components * 2)
+        .unwrap_or_default();
+    let keyed_out = |raw: &[u16]| {
+        !color_key.is_empty()
+            && raw
+                .iter()
+                .zip(color_key.chunks_exact(2))
+                .all(|(v, range)| range[0] <= *v && *v <= range[1])
+    };
+
+    let to_u8 = |c: f32| (c * 255.0 + 0.5) as u8;
+
+    // One-component images get a precomputed palette
+    let palette: Option<Vec<[u8; 3]>> = (components == 1).then(|| {
+        (0..levels)
+            .map(|v| {
+                let value = if bits == 16 { (v as u16) << 8 } else { v as u16 };
+                space.to_rgb(&[lookup(0, value)]).map(to_u8)
+            })
+            .collect()
+    });
+
+    let pixels = samples.resample(out_width, out_height, |raw| {
+        let alpha = if keyed_out(raw) { 0 } else { 255 };
+        let [r, g, b] = match &palette {
+            Some(palette) => {
+                let index = if bits == 16 { raw[0] >> 8 } else { raw[0] };
+                palette[(index as usize).min(levels - 1)]
+            }
+            None => {
+                let mut values = [0.0f32; 32];
+                for (i, v) in raw.iter().enumerate().take(32) {
+                    values[i] = lookup(i, *v);
+                }
+                space.to_rgb(&values[..components.min(32)]).map(to_u8)
+            }
+        };
+        [r, g, b, alpha]
+    });
+
+    Ok(Raster {
+        width: out_width,
+        height: out_height,
+        pixels,
+    })
+}
+
+/// Packed image samples, rows padded to whole bytes
+struct Samples<'a> {
+    data: &'a [u8],
+    width: u32,
+    height: u32,
+    components: usize,
+    bits: u32,
+}
+
+impl Samples<'_> {
+    /// Read the sample `index` of a row; data past the end reads as 0
+    fn sample(&self, row: &[u8], index: usize) -> u16 {
+        let bits = self.bits as usize;
+        match bits {
+            8 => row.get(index).copied().map_or(0, u16::from),
+            16 => {
+                let high = row.get(index * 2).copied().unwrap_or(0);
+                let low = row.get(index * 2 + 1).copied().unwrap_or(0);
+                u16::from_be_bytes([high, low])
+            }
+            _ => {
+                let offset = index * bits;
+                let byte = row.get(offset / 8).copied().unwrap_or(0);
+                let shift = 8 - bits - offset % 8;
+                u16::from((byte >> shift) & ((1u8 << bits) - 1))
+            }
+        }
+    }
+
+    /// Nearest-neighbor sample every output pixel through `pixel`, which
+    /// receives the raw component values
+    fn resample(
+        &self,
+        out_width: u32,
+        out_height: u32,
+        mut pixel: impl FnMut(&[u16]) -> [u8; 4],
+    ) -> Vec<[u8; 4]> {
+        let stride = (self.width as usize * self.components * self.bits as usize).div_ceil(8);
+        let columns: Vec<usize> = (0..out_width)
+            .map(|x| (x as u64 * u64::from(self.width) / u64::from(out_width)) as usize)
+            .collect();
+        let mut raw = vec![0u16; self.components];
+        let mut pixels = Vec::with_capacity(out_width as usize * out_height as usize);
+
+        for y in 0..out_height {
+            let source_row = (y as u64 * u64::from(self.height) / u64::from(out_height)) as usize;
+            let start = (source_row * stride).min(self.data.len());
+            let end = (start + stride).min(self.data.len());
+            let row = &self.data[start..end];
+            for &x in &columns {
+                for (c, value) in raw.iter_mut().enumerate() {
+                    *value = self.sample(row, x * self.components + c);
+                }
+                pixels.push(pixel(&raw));
+            }
+        }
+        pixels
+    }
+}
+
+/// Run the stream's filters, stopping at an image codec
+fn run_filters(doc: &Document, stream: &Stream) -> Encoded {
+    let filters: Vec<Vec<u8>> = match get(doc, &stream.dict, b"Filter") {
+        Some(Object::Name(name)) => vec![name.clone()],
+        Some(Object::Array(items)) => items
+            .iter()
+            .filter_map(|o| deref(doc, o).as_name().ok().map(<[u8]>::to_vec))
+            .collect(),
+        _ => Vec::new(),
+    };
+    let params: Vec<Option<&Object>> = match get(doc, &stream.dict, b"DecodeParms") {
+        Some(Object::Array(items)) => items.iter().map(|o| Some(deref(doc, o))).collect(),
+        Some(params) => vec![Some(params)],
+        None => Vec::new(),
+    };
+
+    let mut data = stream.content.clone();
+    for (i, filter) in filters.iter().enumerate() {
+        let params = params
+            .get(i)
+            .copied()
+            .flatten()
+            .and_then(|p| p.as_dict().ok());
+        data = match filter.as_slice() {
+            b"FlateDecode" | b"Fl" => lopdf_filter(b"FlateDecode", data, params),
+            b"LZWDecode" | b"LZW" => lopdf_filter(b"LZWDecode", data, params),
+            b"ASCII85Decode" | b"A85" => lopdf_filter(b"ASCII85Decode", data, None),
+            b"ASCIIHexDecode" | b"AHx" => ascii_hex(&data),
+            b"RunLengthDecode" | b"RL" => run_length(&data),
+            b"DCTDecode" | b"DCT" => return Encoded::Jpeg(data),
+            b"CCITTFaxDecode" | b"CCF" => {
+                return match ccitt(doc, &data, params, &stream.dict) {
+                    Some(data) => Encoded::Raw(data),
+                    None => Encoded::Unsupported("CCITTFaxDecode (corrupt)".to_string()),
+                }
+            }
+            other => return Encoded::Unsupported(String::from_utf8_lossy(other).into_owned()),
+        };
+    }
+    Encoded::Raw(data)
+}
+
+/// Decode with one of the filters lopdf implements (including predictors)
+fn lopdf_filter(filter: &[u8], data: Vec<u8>, params: Option<&Dictionary>) -> Vec<u8> {
+    if inflates_too_large(filter, &data) {
+        return Vec::new();
+    }
+    apply_filter(filter, data, params).unwrap_or_default()
+}
+
+fn ascii_hex(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() / 2);
+    let mut high: Option<u8> = None;
+    for &byte in data {
+        if byte == b'>' {
+            break;
+        }
+        let Some(nibble) = (byte as char).to_digit(16) else {
+            continue;
+        };
+        match high.take() {
+            Some(h) => out.push(h << 4 | nibble as u8),
+            None => high = Some(nibble as u8),
+        }
+    }
+    if let Some(h) = high {
+        out.push(h << 4);
+    }
+    out
+}
+
+fn run_length(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() * 2);
+    let mut i = 0;
+    while let Some(&length) = data.get(i) {
+        i += 1;
+        match length {
+            128 => break,
+            0..=127 => {
+                let end = (i + length as usize + 1).min(data.len());
+                out.extend_from_slice(&data[i..end]);
+                i = end;
+            }
+            _ => {
+                let Some(&byte) = data.get(i) else { break };
+                out.extend(std::iter::repeat_n(byte, 257 - length as usize));
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Decode CCITT group 3 (one-dimensional) or group 4 fax data to packed
+/// 1-bit rows
+fn ccitt(
+    doc: &Document,
+    data: &[u8],
+    params: Option<&Dictionary>,
+    image: &Dictionary,
+) -> Option<Vec<u8>> {
+    let param = |key: &[u8]| params.and_then(|p| get(doc, p, key));
+    let k = param(b"K").and_then(number).unwrap_or(0.0);
+    let columns = param(b"Columns").and_then(number).unwrap_or(1728.0) as u16;
+    let rows = param(b"Rows")
+        .or_else(|| get(doc, image, b"Height"))
+        .and_then(number)
+        .map(|r| r as u16);
+    let black_is_1 = matches!(param(b"BlackIs1"), Some(Object::Boolean(true)));
+    if columns == 0 {
+        return None;
+    }
+
+    let stride = (columns as usize).div_ceil(8);
+    let mut out = Vec::new();
+    let mut push_line = |transitions: &[u16]| {
+        let mut row = vec![0u8; stride];
+        for (x, color) in fax::decoder::pels(transitions, columns).enumerate() {
+            let bit = (color == fax::Color::Black) == black_is_1;
+            if bit {
+                row[x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+        out.extend_from_slice(&row);
+    };
+
+    let decoded = if k < 0.0 {
+        fax::decoder::decode_g4(data.iter().copied(), columns, rows, &mut push_line)
+    } else {
+        fax::decoder::decode_g3(data.iter().copied(), &mut push_line)
+    };
+    // Keep whatever rows were decoded before an error
+    if decoded.is_none() && out.is_empty() {
+        return None;
+    }
+    Some(out)
+}
+
+fn decode_jpeg(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    out_width: u32,
+    out_height: u32,
+) -> Result<Raster, String> {
+    if u64::from(width) * u64::from(height) > MAX_JPEG_PIXELS {
+        return Err(format!("JPEG too large: {}x{}", width, height));
+    }
+    let image = image::load_from_memory_with_format(data, ImageFormat::Jpeg)
+        .map_err(|e| format!("Failed to decode JPEG: {}", e))?;
+    let image = if image.width() != out_width || image.height() != out_height {
+        image.resize_exact(out_width, out_height, FilterType::Triangle)
+    } else {
+        image
+    };
+    let rgba = image.to_rgba8();
+    Ok(Raster {
+        width: rgba.width(),
+        height: rgba.height(),
+        pixels: rgba.pixels().map(|p| p.0).collect(),
+    })
+}
+
+fn to_pixmap(raster: Raster) -> Result<Pixmap, String> {
+    let size = IntSize::from_wh(raster.width, raster.height).ok_or("Empty image")?;
+    let mut data = Vec::with_capacity(raster.pixels.len() * 4);
+    for [r, g, b, a] in raster.pixels {
+        let premultiply = |c: u8| ((u16::from(c) * u16::from(a) + 127) / 255) as u8;
+        data.extend_from_slice(&[premultiply(r), premultiply(g), premultiply(b), a]);
+    }
+    Pixmap::from_vec(data, size).ok_or_else(|| "Invalid image size".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::dictionary;
+
+    fn image_stream(dict: Dictionary, data: &[u8]) -> Stream {
+        Stream::new(dict, data.to_vec())
+    }
+
+    #[test]
+    fn test_raw_samples_and_decode_arrays() {
+        let doc = Document::new();
+        let resources = Dictionary::new();
+
+        // 4x1 gray at 2 bits, inverted by Decode
+        let gray = image_stream(
+            lopdf::dictionary! {
+                "Width" => 4, "Height" => 1, "BitsPerComponent" => 2,
+                "ColorSpace" => "DeviceGray",
+                "Decode" => vec![1.into(), 0.into()],
+            },
+            &[0b0001_1011],
+        );
+        let pixmap = decode(&doc, &gray, &resources, [0; 3], 100, 100).unwrap();
+        let values: Vec<u8> = pixmap.pixels().iter().map(|p| p.red()).collect();
+        assert_eq!(values, [255, 170, 85, 0]);
+
+        // Odd-length Decode array: the complete pair is used and the extra
+        // value ignored rather than indexed past
+        let malformed = image_stream(
+            lopdf::dictionary! {
+                "Width" => 4, "Height" => 1, "BitsPerComponent" => 2,
+                "ColorSpace" => "DeviceGray",
+                "Decode" => vec![1.into(), 0.into(), 1.into()],
+            },
+            &[0b0001_1011],
+        );
+        let pixmap = decode(&doc, &malformed, &resources, [0; 3], 100, 100).unwrap();
+        let values: Vec<u8> = pixmap.pixels().iter().map(|p| p.red()).collect();
+        assert_eq!(values, [255, 170, 85, 0]);
+
+        // Stencil mask sampled down to 2x1, hex encoded
+        let mask = image_stream(
+            lopdf::dictionary! {
+                "Width" => 4, "Height" => 1, "ImageMask" => true,
+                "Filter" => "AHx",
+            },
+            b"3F>",
+        );
+        let pixmap = decode(&doc, &mask, &resources, [255, 0, 0], 2, 2).unwrap();
+        assert_eq!((pixmap.width(), pixmap.height()), (2, 1));
+        let alpha: Vec<u8> = pixmap.pixels().iter().map(|p| p.alpha()).collect();
+        assert_eq!(alpha, [255, 0]);
+        assert_eq!(pixmap.pixels()[0].red(), 255);
+    }
+
+    #[test]
+    fn test_filters() {
+        assert_eq!(run_length(&[2, b'a', b'b', b'c', 254, b'x', 128]), b"abcxxx");
+        assert_eq!(ascii_hex(b"48 65 6c6C 6>"), b"Hell\x60");
+
+        let doc = Document::new();
+        let stream = image_stream(
+            lopdf::dictionary! { "Filter" => "JPXDecode", "Width" => 8, "Height" => 8 },
+            b"",
+        );
+        let pixmap = decode(&doc, &stream, &Dictionary::new(), [0; 3], 8, 8).unwrap();
+        assert_eq!(pixmap.width(), 1);
+    }
+}
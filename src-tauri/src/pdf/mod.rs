@@ -0,0 +1,497 @@
+//! PDF Page Rendering Module
+//!
+//! Renders PDF pages to images without a native library, for thumbnails
+//! and for vision/OCR sampling when the optional `pdfium` feature is off:
+//! - `content`: content stream tokenizer, including inline images
+//! - `render`: graphics state interpreter drawing with tiny-skia
+//! - `color`: color spaces to sRGB
+//! - `image`: image XObject decoding (filters, masks, JPEG, CCITT fax)
+//! - `font`: glyph outlines from embedded or substitute system fonts
+//!
+//! The goal is a faithful preview rather than print fidelity: shadings,
+//! patterns, blend modes, Type 1 font programs and JPEG 2000/JBIG2 images
+//! are left out (glyphs without outlines are drawn as gray bars, images
+//! as gray boxes). Parsing uses lopdf, which also decrypts documents with
+//! an empty user password.
+
+mod color;
+mod content;
+mod font;
+mod image;
+mod render;
+
+use ::image::RgbaImage;
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+use resvg::tiny_skia::{Pixmap, Transform};
+use std::io::Read;
+use std::path::Path;
+
+/// Deepest /Parent chain followed for inherited page attributes
+const MAX_PAGE_TREE_DEPTH: usize = 32;
+
+/// Largest size a Flate stream may inflate to, in bytes
+const MAX_INFLATED_SIZE: u64 = 64 << 20;
+
+/// US Letter, for pages without a usable MediaBox
+const DEFAULT_PAGE_BOX: [f32; 4] = [0.0, 0.0, 612.0, 792.0];
+
+/// How large to render a page
+#[derive(Debug, Clone, Copy)]
+pub struct RenderOptions {
+    /// Resolution in pixels per inch (72 = one pixel per point)
+    pub dpi: f32,
+    /// Longest side of the image in pixels; the resolution is lowered to fit
+    pub max_dimension: u32,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            dpi: 150.0,
+            max_dimension: 1600,
+        }
+    }
+}
+
+/// A parsed PDF document
+pub struct PdfDocument {
+    doc: Document,
+    /// Page object ids in page order
+    pages: Vec<ObjectId>,
+}
+
+impl PdfDocument {
+    /// Open a PDF file
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("Failed to read PDF: {}", e))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Parse a PDF held in memory
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let doc = Document::load_mem(bytes).map_err(|e| format!("Failed to parse PDF: {}", e))?;
+        if doc.is_encrypted() && doc.encryption_state.is_none() {
+            return Err("PDF is password protected".to_string());
+        }
+        let pages = doc.get_pages().into_values().collect();
+        Ok(Self { doc, pages })
+    }
+
+    /// Number of pages
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Render a page (0-based) on a white background
+    pub fn render_page(&self, index: usize, options: &RenderOptions) -> Result<RgbaImage, String> {
+        let page_id = *self
+            .pages
+            .get(index)
+            .ok_or_else(|| format!("Page {} out of range ({} pages)", index, self.pages.len()))?;
+        let page = self
+            .doc
+            .get_dictionary(page_id)
+            .map_err(|e| format!("Failed to read page {}: {}", index, e))?;
+
+        let [x0, y0, x1, y1] = self.page_box(page);
+        let rotation = self
+            .inherited(page, b"Rotate")
+            .and_then(number)
+            .map_or(0, |r| (r as i64).rem_euclid(360) / 90 * 90);
+        let (width, height) = if rotation % 180 == 0 {
+            (x1 - x0, y1 - y0)
+        } else {
+            (y1 - y0, x1 - x0)
+        };
+
+        let mut scale = options.dpi.max(1.0) / 72.0;
+        let longest = width.max(height) * scale;
+        if longest > options.max_dimension as f32 {
+            scale *= options.max_dimension as f32 / longest;
+        }
+        let pixel_width = ((width * scale).round() as u32).max(1);
+        let pixel_height = ((height * scale).round() as u32).max(1);
+        let pixmap = Pixmap::new(pixel_width, pixel_height)
+            .ok_or_else(|| format!("Invalid page size {}x{}", pixel_width, pixel_height))?;
+
+        // Page space to pixels: flip y, then apply the page rotation
+        let s = scale;
+        let page_transform = match rotation {
+            90 => Transform::from_row(0.0, s, s, 0.0, -y0 * s, -x0 * s),
+            180 => Transform::from_row(-s, 0.0, 0.0, s, x1 * s, -y0 * s),
+            270 => Transform::from_row(0.0, -s, -s, 0.0, y1 * s, x1 * s),
+            _ => Transform::from_row(s, 0.0, 0.0, -s, -x0 * s, y1 * s),
+        };
+
+        let empty = Dictionary::new();
+        let resources = self
+            .inherited(page, b"Resources")
+            .and_then(|o| o.as_dict().ok())
+            .unwrap_or(&empty);
+
+        let mut renderer = render::Renderer::new(&self.doc, pixmap, page_transform);
+        renderer.run(&self.page_content(page_id), resources);
+        let pixmap = renderer.finish();
+
+        // The page is opaque, so premultiplied data is plain RGBA
+        RgbaImage::from_raw(pixel_width, pixel_height, pixmap.take())
+            .ok_or_else(|| "Failed to create page image".to_string())
+    }
+
+    /// The visible page area: the CropBox clipped to the MediaBox
+    fn page_box(&self, page: &Dictionary) -> [f32; 4] {
+        let rect = |key: &[u8]| -> Option<[f32; 4]> {
+            let items = self.inherited(page, key)?.as_array().ok()?;
+            let values: Vec<f32> = items
+                .iter()
+                .filter_map(|o| number(deref(&self.doc, o)))
+                .collect();
+            match values[..] {
+                [a, b, c, d] if a != c && b != d => Some([a.min(c), b.min(d), a.max(c), b.max(d)]),
+                _ => None,
+            }
+        };
+        let media = rect(b"MediaBox").unwrap_or(DEFAULT_PAGE_BOX);
+        match rect(b"CropBox") {
+            Some(crop) => {
+                let clipped = [
+                    crop[0].max(media[0]),
+                    crop[1].max(media[1]),
+                    crop[2].min(media[2]),
+                    crop[3].min(media[3]),
+                ];
+                if clipped[0] < clipped[2] && clipped[1] < clipped[3] {
+                    clipped
+                } else {
+                    media
+                }
+            }
+            None => media,
+        }
+    }
+
+    /// A page attribute, inherited from the page tree if needed
+    fn inherited<'a>(&'a self, page: &'a Dictionary, key: &[u8]) -> Option<&'a Object> {
+        let mut node = page;
+        for _ in 0..MAX_PAGE_TREE_DEPTH {
+            if let Some(value) = get(&self.doc, node, key) {
+                return Some(value);
+            }
+            node = get(&self.doc, node, b"Parent")?.as_dict().ok()?;
+        }
+        None
+    }
+
+    /// All content streams of a page, decoded and joined
+    fn page_content(&self, page_id: ObjectId) -> Vec<u8> {
+        let mut data = Vec::new();
+        for id in self.doc.get_page_contents(page_id) {
+            if let Ok(stream) = self.doc.get_object(id).and_then(Object::as_stream) {
+                data.extend_from_slice(&decoded_content(stream));
+                // Streams split at token boundaries, which must stay apart
+                data.push(b'\n');
+            }
+        }
+        data
+    }
+}
+
+/// A stream's content with its filters applied
+///
+/// Content lopdf can't decode is returned as stored, and Flate data that
+/// would inflate past `MAX_INFLATED_SIZE` is dropped.
+fn decoded_content(stream: &Stream) -> Vec<u8> {
+    let Ok(filters) = stream.filters() else {
+        return stream.content.clone();
+    };
+    let params = stream.dict.get(b"DecodeParms").and_then(Object::as_dict).ok();
+
+    let mut data = stream.content.clone();
+    for filter in filters {
+        if inflates_too_large(filter, &data) {
+            tracing::debug!("[pdf] Skipping stream that inflates past the size limit");
+            return Vec::new();
+        }
+        match apply_filter(filter, data, params) {
+            Ok(decoded) => data = decoded,
+            Err(_) => return stream.content.clone(),
+        }
+    }
+    data
+}
+
+/// Run one of the filters lopdf implements (including predictors)
+fn apply_filter(
+    filter: &[u8],
+    data: Vec<u8>,
+    params: Option<&Dictionary>,
+) -> lopdf::Result<Vec<u8>> {
+    let mut dict = Dictionary::new();
+    dict.set("Filter", Object::Name(filter.to_vec()));
+    if let Some(params) = params {
+        dict.set("DecodeParms", Object::Dictionary(params.clone()));
+    }
+    Stream::new(dict, data).decompressed_content()
+}
+
+/// Whether `filter` is Flate and `data` inflates past `MAX_INFLATED_SIZE`
+///
+/// Inflates into a scratch buffer, so a compression bomb is caught before
+/// anything is allocated for it.
+fn inflates_too_large(filter: &[u8], data: &[u8]) -> bool {
+    if filter != b"FlateDecode" {
+        return false;
+    }
+    let mut decoder = flate2::read::ZlibDecoder::new(data);
+    let mut buffer = vec![0; 64 * 1024];
+    let mut total = 0u64;
+    loop {
+        match decoder.read(&mut buffer) {
+            Ok(0) | Err(_) => return false,
+            Ok(n) => {
+                total += n as u64;
+                if total > MAX_INFLATED_SIZE {
+                    return true;
+                }
+            }
+        }
+    }
+}
+
+/// Follow a reference to its object; unresolvable references stay as is
+fn deref<'a>(doc: &'a Document, object: &'a Object) -> &'a Object {
+    doc.dereference(object)
+        .map(|(_, object)| object)
+        .unwrap_or(object)
+}
+
+/// Dictionary entry with references followed
+fn get<'a>(doc: &'a Document, dict: &'a Dictionary, key: &[u8]) -> Option<&'a Object> {
+    dict.get(key).ok().map(|object| deref(doc, object))
+}
+
+/// Integer or real number
+fn number(object: &Object) -> Option<f32> {
+    match object {
+        Object::Integer(i) => Some(*i as f32),
+        Object::Real(r) => Some(*r),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{dictionary, Stream};
+
+    /// Build a PDF whose pages have the given content streams
+    fn build_pdf(pages: &[(&str, Dictionary)], rotate: i64) -> Vec<u8> {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+        });
+
+        let mut kids = Vec::new();
+        for (content, xobjects) in pages {
+            let content_id = doc.add_object(Stream::new(dictionary! {}, content.as_bytes().to_vec()));
+            // Streams can only be stored as indirect objects
+            let mut resources = Dictionary::new();
+            for (name, xobject) in xobjects.iter() {
+                resources.set(name.clone(), doc.add_object(xobject.clone()));
+            }
+            let page_id = doc.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "Contents" => content_id,
+                "Resources" => dictionary! {
+                    "Font" => dictionary! { "F1" => font_id },
+                    "XObject" => resources,
+                },
+            });
+            kids.push(page_id.into());
+        }
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Count" => kids.len() as i64,
+                "Kids" => kids,
+                "MediaBox" => vec![0.into(), 0.into(), 200.into(), 100.into()],
+                "Rotate" => rotate,
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        doc.trailer.set("Root", catalog_id);
+
+        let mut bytes = Vec::new();
+        doc.save_to(&mut bytes).unwrap();
+        bytes
+    }
+
+    fn at_72_dpi() -> RenderOptions {
+        RenderOptions {
+            dpi: 72.0,
+            max_dimension: 1000,
+        }
+    }
+
+    fn pixel(image: &RgbaImage, x: u32, y: u32) -> [u8; 3] {
+        let [r, g, b, _] = image.get_pixel(x, y).0;
+        [r, g, b]
+    }
+
+    #[test]
+    fn test_paths_and_page_geometry() {
+        let content = "1 0 0 rg 10 10 50 30 re f 0 0 1 RG 4 w 100 50 m 190 50 l S";
+        let pdf = PdfDocument::from_bytes(&build_pdf(&[(content, dictionary! {})], 0)).unwrap();
+        assert_eq!(pdf.page_count(), 1);
+
+        let image = pdf.render_page(0, &at_72_dpi()).unwrap();
+        assert_eq!(image.dimensions(), (200, 100));
+        // PDF y grows upwards: the rectangle sits at the bottom left
+        assert_eq!(pixel(&image, 30, 75), [255, 0, 0]);
+        assert_eq!(pixel(&image, 30, 20), [255, 255, 255]);
+        assert_eq!(pixel(&image, 150, 50), [0, 0, 255]);
+        assert!(pdf.render_page(1, &at_72_dpi()).is_err());
+
+        // max_dimension lowers the resolution
+        let small = pdf
+            .render_page(
+                0,
+                &RenderOptions {
+                    dpi: 300.0,
+                    max_dimension: 100,
+                },
+            )
+            .unwrap();
+        assert_eq!(small.dimensions(), (100, 50));
+    }
+
+    #[test]
+    fn test_rotation_and_clipping() {
+        let content = "q 0 0 100 100 re W n 0 g 0 0 200 100 re f Q";
+        let pdf = PdfDocument::from_bytes(&build_pdf(&[(content, dictionary! {})], 90)).unwrap();
+        let image = pdf.render_page(0, &at_72_dpi()).unwrap();
+        assert_eq!(image.dimensions(), (100, 200));
+        // Rotated clockwise, the left half of the page is now the top half
+        assert_eq!(pixel(&image, 50, 50), [0, 0, 0]);
+        assert_eq!(pixel(&image, 50, 150), [255, 255, 255]);
+    }
+
+    #[test]
+    fn test_images_and_forms() {
+        let image = Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Image",
+                "Width" => 2,
+                "Height" => 1,
+                "ColorSpace" => "DeviceRGB",
+                "BitsPerComponent" => 8,
+            },
+            vec![255, 0, 0, 0, 0, 255],
+        );
+        let form = Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Form",
+                "BBox" => vec![0.into(), 0.into(), 10.into(), 10.into()],
+                "Matrix" => vec![1.into(), 0.into(), 0.into(), 1.into(), 150.into(), 10.into()],
+            },
+            b"0 1 0 rg 0 0 50 50 re f".to_vec(),
+        );
+        let xobjects = dictionary! {
+            "Im1" => Object::Stream(image),
+            "Fm1" => Object::Stream(form),
+        };
+        let content = "q 100 0 0 50 0 50 cm /Im1 Do Q /Fm1 Do \
+                       BI /W 1 /H 1 /CS /G /BPC 8 /F /AHx ID 80> EI";
+        let pdf = PdfDocument::from_bytes(&build_pdf(&[(content, xobjects)], 0)).unwrap();
+        let image = pdf.render_page(0, &at_72_dpi()).unwrap();
+
+        assert_eq!(pixel(&image, 10, 25), [255, 0, 0]);
+        assert_eq!(pixel(&image, 90, 25), [0, 0, 255]);
+        // The form is clipped to its 10x10 bounding box
+        assert_eq!(pixel(&image, 155, 85), [0, 255, 0]);
+        assert_eq!(pixel(&image, 170, 70), [255, 255, 255]);
+        // The inline image fills the unit square at the origin
+        assert_eq!(pixel(&image, 0, 99), [128, 128, 128]);
+    }
+
+    #[test]
+    fn test_text_marks_the_page() {
+        let content = "BT /F1 24 Tf 10 40 Td (Hello) Tj ET";
+        let pdf = PdfDocument::from_bytes(&build_pdf(&[(content, dictionary! {}), ("", dictionary! {})], 0))
+            .unwrap();
+        assert_eq!(pdf.page_count(), 2);
+
+        // With a substitute system font the glyphs are drawn, without one
+        // they are greeked; either way the line is visible
+        let image = pdf.render_page(0, &at_72_dpi()).unwrap();
+        let inked = (10..80)
+            .flat_map(|x| (45..62).map(move |y| (x, y)))
+            .filter(|(x, y)| pixel(&image, *x, *y)[0] < 200)
+            .count();
+        assert!(inked > 20, "only {} dark pixels", inked);
+
+        let blank = pdf.render_page(1, &at_72_dpi()).unwrap();
+        assert!(blank.pixels().all(|p| p.0 == [255, 255, 255, 255]));
+        assert!(PdfDocument::from_bytes(b"not a pdf").is_err());
+    }
+
+    #[test]
+    fn test_self_referencing_forms_stay_bounded() {
+        // A form drawing itself eight times would take 8^12 runs unbounded
+        let mut content = "/Fm1 Do ".repeat(8);
+        content.push_str("0 0 1 rg 0 0 200 100 re f");
+        let form = Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Form",
+                "BBox" => vec![0.into(), 0.into(), 200.into(), 100.into()],
+            },
+            content.into_bytes(),
+        );
+        let bytes = build_pdf(&[("/Fm1 Do", dictionary! { "Fm1" => Object::Stream(form) })], 0);
+
+        // Point the form's resources back at itself
+        let mut doc = Document::load_mem(&bytes).unwrap();
+        let form_id = doc
+            .objects
+            .iter()
+            .find(|(_, o)| o.as_stream().is_ok_and(|s| s.dict.has(b"BBox")))
+            .map(|(id, _)| *id)
+            .unwrap();
+        if let Ok(Object::Stream(stream)) = doc.get_object_mut(form_id) {
+            stream.dict.set(
+                "Resources",
+                dictionary! { "XObject" => dictionary! { "Fm1" => form_id } },
+            );
+        }
+        let mut bytes = Vec::new();
+        doc.save_to(&mut bytes).unwrap();
+
+        let pdf = PdfDocument::from_bytes(&bytes).unwrap();
+        let image = pdf.render_page(0, &at_72_dpi()).unwrap();
+        assert_eq!(pixel(&image, 100, 50), [0, 0, 255]);
+    }
+
+    #[test]
+    fn test_decoded_content() {
+        use flate2::{write::ZlibEncoder, Compression};
+        use std::io::Write;
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"0 0 1 rg").unwrap();
+        let flate = Stream::new(dictionary! { "Filter" => "FlateDecode" }, encoder.finish().unwrap());
+        assert_eq!(decoded_content(&flate), b"0 0 1 rg");
+
+        // Unsupported filters leave the content as stored
+        let unknown = Stream::new(dictionary! { "Filter" => "Foo" }, b"raw".to_vec());
+        assert_eq!(decoded_content(&unknown), b"raw");
+        assert!(!inflates_too_large(b"FlateDecode", &flate.content));
+    }
+}
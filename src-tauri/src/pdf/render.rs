@@ -0,0 +1,712 @@
+//! Content stream interpreter
+//!
+//! Runs page and form content against a tiny-skia pixmap: the graphics
+//! state, paths with fills, strokes, dashes and clipping, the text
+//! operators, images and form XObjects. Shadings, pattern fills, blend
+//! modes and soft-mask groups are skipped, and optional content is always
+//! shown. Damaged operators are ignored one by one so the rest of the page
+//! still renders.
+
+use lopdf::content::Operation;
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+use resvg::tiny_skia::{
+    BlendMode, Color, FillRule, FilterQuality, LineCap, LineJoin, Mask, Paint, Path, PathBuilder,
+    Pixmap, PixmapPaint, Rect, Stroke, StrokeDash, Transform,
+};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::color::ColorSpace;
+use super::font::{Font, Glyph};
+use super::{content, decoded_content, deref, get, image, number};
+
+/// Deepest nesting of form XObjects
+const MAX_FORM_DEPTH: usize = 12;
+
+/// Work allowed per page, in operators run; forms drawing themselves many
+/// times over stop here instead of growing exponentially
+const MAX_PAGE_WORK: usize = 5_000_000;
+
+/// Work charged for painting a path or a text string
+const PAINT_WORK: usize = 10;
+
+/// Work charged for running a form or painting an image, which clip to or
+/// resample at page size (at most 500 per page)
+const XOBJECT_WORK: usize = 10_000;
+
+/// Deepest `q` nesting kept; deeper saves are ignored
+const MAX_STATE_DEPTH: usize = 256;
+
+/// Images are decoded at up to this many pixels per device pixel
+const IMAGE_OVERSAMPLING: f32 = 2.0;
+
+/// Largest decoded image side, in pixels
+const MAX_IMAGE_SIDE: u32 = 4096;
+
+/// Opacity of the bars drawn for glyphs without outlines
+const GREEKING_ALPHA: f32 = 0.45;
+
+#[derive(Clone)]
+struct GraphicsState {
+    /// User space to device pixels
+    ctm: Transform,
+    clip: Option<Rc<Mask>>,
+    fill_space: Rc<ColorSpace>,
+    /// None while a pattern is selected, which isn't painted
+    fill: Option<[f32; 3]>,
+    stroke_space: Rc<ColorSpace>,
+    stroke: Option<[f32; 3]>,
+    fill_alpha: f32,
+    stroke_alpha: f32,
+    line_width: f32,
+    line_cap: LineCap,
+    line_join: LineJoin,
+    miter_limit: f32,
+    dash: Option<(Vec<f32>, f32)>,
+    char_spacing: f32,
+    word_spacing: f32,
+    horizontal_scale: f32,
+    leading: f32,
+    font: Option<Rc<Font>>,
+    font_size: f32,
+    render_mode: i64,
+    rise: f32,
+}
+
+impl GraphicsState {
+    fn new(ctm: Transform) -> Self {
+        Self {
+            ctm,
+            clip: None,
+            fill_space: Rc::new(ColorSpace::Gray),
+            fill: Some([0.0; 3]),
+            stroke_space: Rc::new(ColorSpace::Gray),
+            stroke: Some([0.0; 3]),
+            fill_alpha: 1.0,
+            stroke_alpha: 1.0,
+            line_width: 1.0,
+            line_cap: LineCap::Butt,
+            line_join: LineJoin::Miter,
+            miter_limit: 10.0,
+            dash: None,
+            char_spacing: 0.0,
+            word_spacing: 0.0,
+            horizontal_scale: 1.0,
+            leading: 0.0,
+            font: None,
+            font_size: 0.0,
+            render_mode: 0,
+            rise: 0.0,
+        }
+    }
+}
+
+pub struct Renderer<'a> {
+    doc: &'a Document,
+    pixmap: Pixmap,
+    state: GraphicsState,
+    stack: Vec<GraphicsState>,
+    path: PathBuilder,
+    pending_clip: Option<FillRule>,
+    text_matrix: Transform,
+    line_matrix: Transform,
+    /// Fonts loaded so far, by object id
+    fonts: HashMap<ObjectId, Rc<Font>>,
+    /// Parsed form XObject content, by stream address in the document
+    forms: HashMap<usize, Rc<Vec<Operation>>>,
+    depth: usize,
+    /// Work left for this page (see `MAX_PAGE_WORK`)
+    work_left: usize,
+}
+
+impl<'a> Renderer<'a> {
+    /// A white page of `pixmap`'s size, drawn through `page_transform`
+    pub fn new(doc: &'a Document, mut pixmap: Pixmap, page_transform: Transform) -> Self {
+        pixmap.fill(Color::WHITE);
+        Self {
+            doc,
+            pixmap,
+            state: GraphicsState::new(page_transform),
+            stack: Vec::new(),
+            path: PathBuilder::new(),
+            pending_clip: None,
+            text_matrix: Transform::identity(),
+            line_matrix: Transform::identity(),
+            fonts: HashMap::new(),
+            forms: HashMap::new(),
+            depth: 0,
+            work_left: MAX_PAGE_WORK,
+        }
+    }
+
+    pub fn finish(self) -> Pixmap {
+        self.pixmap
+    }
+
+    /// Run a content stream
+    pub fn run(&mut self, data: &[u8], resources: &Dictionary) {
+        self.run_operations(&content::parse(data), resources);
+    }
+
+    fn run_operations(&mut self, operations: &[Operation], resources: &Dictionary) {
+        for operation in operations {
+            let work = match operation.operator.as_str() {
+                "S" | "s" | "f" | "F" | "f*" | "B" | "B*" | "b" | "b*" | "Tj" | "TJ" | "'"
+                | "\"" => PAINT_WORK,
+                _ => 1,
+            };
+            if !self.charge(work) {
+                return;
+            }
+            self.operation(operation, resources);
+        }
+    }
+
+    /// Take `work` from the page budget; false once it is used up
+    fn charge(&mut self, work: usize) -> bool {
+        match self.work_left.checked_sub(work) {
+            Some(left) => {
+                self.work_left = left;
+                true
+            }
+            None => {
+                if self.work_left > 0 {
+                    tracing::debug!("[pdf] Page work budget exhausted, stopping");
+                }
+                self.work_left = 0;
+                false
+            }
+        }
+    }
+
+    fn operation(&mut self, op: &Operation, resources: &Dictionary) {
+        let n = |i: usize| op.operands.get(i).and_then(number).unwrap_or(0.0);
+        let state = &mut self.state;
+
+        match op.operator.as_str() {
+            // Graphics state
+            "q" if self.stack.len() < MAX_STATE_DEPTH => self.stack.push(state.clone()),
+            "Q" => {
+                if let Some(saved) = self.stack.pop() {
+                    self.state = saved;
+                }
+            }
+            "cm" if op.operands.len() == 6 => {
+                state.ctm = state
+                    .ctm
+                    .pre_concat(Transform::from_row(n(0), n(1), n(2), n(3), n(4), n(5)));
+            }
+            "w" => state.line_width = n(0),
+            "J" => state.line_cap = line_cap(n(0)),
+            "j" => state.line_join = line_join(n(0)),
+            "M" => state.miter_limit = n(0).max(1.0),
+            "d" => state.dash = dash(self.doc, op.operands.first(), n(1)),
+            "gs" => {
+                if let Some(dict) = op
+                    .operands
+                    .first()
+                    .and_then(|name| self.resource(resources, b"ExtGState", name))
+                    .and_then(|o| o.as_dict().ok())
+                {
+                    self.ext_gstate(dict);
+                }
+            }
+
+            // Path construction
+            "m" => self.path.move_to(n(0), n(1)),
+            "l" => self.path.line_to(n(0), n(1)),
+            "c" => self.path.cubic_to(n(0), n(1), n(2), n(3), n(4), n(5)),
+            "v" => {
+                let current = self.path.last_point().unwrap_or_default();
+                self.path.cubic_to(current.x, current.y, n(0), n(1), n(2), n(3));
+            }
+            "y" => self.path.cubic_to(n(0), n(1), n(2), n(3), n(2), n(3)),
+            "h" => self.path.close(),
+            "re" => {
+                let (x, y, w, h) = (n(0), n(1), n(2), n(3));
+                self.path.move_to(x, y);
+                self.path.line_to(x + w, y);
+                self.path.line_to(x + w, y + h);
+                self.path.line_to(x, y + h);
+                self.path.close();
+            }
+
+            // Path painting and clipping
+            "S" => self.paint(false, None, true),
+            "s" => self.paint(true, None, true),
+            "f" | "F" => self.paint(false, Some(FillRule::Winding), false),
+            "f*" => self.paint(false, Some(FillRule::EvenOdd), false),
+            "B" => self.paint(false, Some(FillRule::Winding), true),
+            "B*" => self.paint(false, Some(FillRule::EvenOdd), true),
+            "b" => self.paint(true, Some(FillRule::Winding), true),
+            "b*" => self.paint(true, Some(FillRule::EvenOdd), true),
+            "n" => self.paint(false, None, false),
+            "W" => self.pending_clip = Some(FillRule::Winding),
+            "W*" => self.pending_clip = Some(FillRule::EvenOdd),
+
+            // Color
+            "CS" | "cs" => {
+                let space = op
+                    .operands
+                    .first()
+                    .and_then(|o| ColorSpace::resolve(self.doc, o, resources))
+                    .unwrap_or(ColorSpace::Gray);
+                let color = paintable(&space, &space.initial_color());
+                if op.operator == "CS" {
+                    state.stroke_space = Rc::new(space);
+                    state.stroke = color;
+                } else {
+                    state.fill_space = Rc::new(space);
+                    state.fill = color;
+                }
+            }
+            "SC" | "SCN" => {
+                state.stroke = components(op).and_then(|c| paintable(&state.stroke_space, &c));
+            }
+            "sc" | "scn" => {
+                state.fill = components(op).and_then(|c| paintable(&state.fill_space, &c));
+            }
+            "G" | "RG" | "K" | "g" | "rg" | "k" => {
+                let space = match op.operator.as_str() {
+                    "G" | "g" => ColorSpace::Gray,
+                    "RG" | "rg" => ColorSpace::Rgb,
+                    _ => ColorSpace::Cmyk,
+                };
+                let values: Vec<f32> = op.operands.iter().filter_map(number).collect();
+                let color = Some(space.to_rgb(&values));
+                if op.operator.chars().all(|c| c.is_ascii_uppercase()) {
+                    state.stroke_space = Rc::new(space);
+                    state.stroke = color;
+                } else {
+                    state.fill_space = Rc::new(space);
+                    state.fill = color;
+                }
+            }
+
+            // Text
+            "BT" => {
+                self.text_matrix = Transform::identity();
+                self.line_matrix = Transform::identity();
+            }
+            "Tc" => state.char_spacing = n(0),
+            "Tw" => state.word_spacing = n(0),
+            "Tz" => state.horizontal_scale = n(0) / 100.0,
+            "TL" => state.leading = n(0),
+            "Ts" => state.rise = n(0),
+            "Tr" => state.render_mode = n(0) as i64,
+            "Tf" => {
+                state.font_size = n(1);
+                let font = op
+                    .operands
+                    .first()
+                    .and_then(|name| self.font(resources, name));
+                self.state.font = font;
+            }
+            "Td" => self.next_line(n(0), n(1)),
+            "TD" => {
+                state.leading = -n(1);
+                self.next_line(n(0), n(1));
+            }
+            "Tm" if op.operands.len() == 6 => {
+                let matrix = Transform::from_row(n(0), n(1), n(2), n(3), n(4), n(5));
+                self.text_matrix = matrix;
+                self.line_matrix = matrix;
+            }
+            "T*" => {
+                let leading = state.leading;
+                self.next_line(0.0, -leading);
+            }
+            "Tj" => {
+                if let Some(Object::String(bytes, _)) = op.operands.first() {
+                    self.show_text(bytes);
+                }
+            }
+            "'" => {
+                let leading = state.leading;
+                self.next_line(0.0, -leading);
+                if let Some(Object::String(bytes, _)) = op.operands.first() {
+                    self.show_text(bytes);
+                }
+            }
+            "\"" => {
+                state.word_spacing = n(0);
+                state.char_spacing = n(1);
+                self.next_line(0.0, -self.state.leading);
+                if let Some(Object::String(bytes, _)) = op.operands.get(2) {
+                    self.show_text(bytes);
+                }
+            }
+            "TJ" => {
+                if let Some(Object::Array(items)) = op.operands.first() {
+                    for item in items {
+                        match item {
+                            Object::String(bytes, _) => self.show_text(bytes),
+                            other => {
+                                if let Some(adjust) = number(other) {
+                                    let state = &self.state;
+                                    let tx = -adjust / 1000.0
+                                        * state.font_size
+                                        * state.horizontal_scale;
+                                    self.text_matrix =
+                                        self.text_matrix.pre_translate(tx, 0.0);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            // External objects
+            "Do" => {
+                let Some(stream) = op
+                    .operands
+                    .first()
+                    .and_then(|name| self.resource(resources, b"XObject", name))
+                    .and_then(|o| o.as_stream().ok())
+                else {
+                    return;
+                };
+                match get(self.doc, &stream.dict, b"Subtype").and_then(|o| o.as_name().ok()) {
+                    Some(b"Image") => self.draw_image(stream, resources),
+                    Some(b"Form") => self.run_form(stream, resources),
+                    _ => {}
+                }
+            }
+            "BI" => {
+                if let Some(Object::Stream(stream)) = op.operands.first() {
+                    self.draw_image(stream, resources);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Look up a named resource like a font or XObject
+    fn resource<'r>(
+        &self,
+        resources: &'r Dictionary,
+        category: &[u8],
+        name: &Object,
+    ) -> Option<&'r Object>
+    where
+        'a: 'r,
+    {
+        let name = name.as_name().ok()?;
+        let entries = get(self.doc, resources, category)?.as_dict().ok()?;
+        get(self.doc, entries, name)
+    }
+
+    fn font(&mut self, resources: &Dictionary, name: &Object) -> Option<Rc<Font>> {
+        let name = name.as_name().ok()?;
+        let fonts = get(self.doc, resources, b"Font")?.as_dict().ok()?;
+        let entry = fonts.get(name).ok()?;
+        if let Object::Reference(id) = entry {
+            if let Some(font) = self.fonts.get(id) {
+                return Some(Rc::clone(font));
+            }
+            let font = Rc::new(Font::load(self.doc, deref(self.doc, entry).as_dict().ok()?));
+            self.fonts.insert(*id, Rc::clone(&font));
+            return Some(font);
+        }
+        Some(Rc::new(Font::load(self.doc, entry.as_dict().ok()?)))
+    }
+
+    fn ext_gstate(&mut self, dict: &Dictionary) {
+        let doc = self.doc;
+        for (key, value) in dict.iter() {
+            let value = deref(doc, value);
+            let state = &mut self.state;
+            match key.as_slice() {
+                b"LW" => state.line_width = number(value).unwrap_or(state.line_width),
+                b"LC" => state.line_cap = line_cap(number(value).unwrap_or(0.0)),
+                b"LJ" => state.line_join = line_join(number(value).unwrap_or(0.0)),
+                b"ML" => state.miter_limit = number(value).unwrap_or(10.0).max(1.0),
+                b"CA" => state.stroke_alpha = number(value).unwrap_or(1.0).clamp(0.0, 1.0),
+                b"ca" => state.fill_alpha = number(value).unwrap_or(1.0).clamp(0.0, 1.0),
+                b"D" => {
+                    if let Ok([array, phase]) = value.as_array().map(Vec::as_slice) {
+                        state.dash = dash(doc, Some(array), number(phase).unwrap_or(0.0));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Paint the current path, then apply a pending clip
+    fn paint(&mut self, close: bool, fill: Option<FillRule>, stroke: bool) {
+        if close {
+            self.path.close();
+        }
+        let path = std::mem::replace(&mut self.path, PathBuilder::new()).finish();
+        let state = &self.state;
+        let clip = state.clip.as_deref();
+
+        if let Some(path) = &path {
+            if let (Some(rule), Some(color)) = (fill, state.fill) {
+                let paint = solid(color, state.fill_alpha);
+                self.pixmap.fill_path(path, &paint, rule, state.ctm, clip);
+            }
+            if let (true, Some(color)) = (stroke, state.stroke) {
+                let paint = solid(color, state.stroke_alpha);
+                let stroke = self.stroke_style(state.line_width);
+                self.pixmap
+                    .stroke_path(path, &paint, &stroke, state.ctm, clip);
+            }
+        }
+
+        if let Some(rule) = self.pending_clip.take() {
+            self.clip(path.as_ref(), rule);
+        }
+    }
+
+    /// Intersect the clip with a path; an empty path clips everything
+    fn clip(&mut self, path: Option<&Path>, rule: FillRule) {
+        let Some(mut mask) = Mask::new(self.pixmap.width(), self.pixmap.height()) else {
+            return;
+        };
+        if let Some(path) = path {
+            match &self.state.clip {
+                Some(current) => {
+                    mask = (**current).clone();
+                    mask.intersect_path(path, rule, true, self.state.ctm);
+                }
+                None => mask.fill_path(path, rule, true, self.state.ctm),
+            }
+        }
+        self.state.clip = Some(Rc::new(mask));
+    }
+
+    /// Stroke settings for a line width in user space; lines thinner than
+    /// a device pixel are drawn as hairlines so they stay visible
+    fn stroke_style(&self, width: f32) -> Stroke {
+        let ctm = self.state.ctm;
+        let scale = (ctm.sx * ctm.sy - ctm.kx * ctm.ky).abs().sqrt();
+        Stroke {
+            width: if width * scale < 1.0 { 0.0 } else { width },
+            miter_limit: self.state.miter_limit,
+            line_cap: self.state.line_cap,
+            line_join: self.state.line_join,
+            dash: self
+                .state
+                .dash
+                .clone()
+                .and_then(|(array, phase)| StrokeDash::new(array, phase)),
+        }
+    }
+
+    fn next_line(&mut self, tx: f32, ty: f32) {
+        self.line_matrix = self.line_matrix.pre_translate(tx, ty);
+        self.text_matrix = self.line_matrix;
+    }
+
+    fn show_text(&mut self, bytes: &[u8]) {
+        let Some(font) = self.state.font.clone() else {
+            return;
+        };
+        let state = &self.state;
+        let size = state.font_size;
+        let scale = state.horizontal_scale;
+        let mode = state.render_mode;
+        let fills = matches!(mode, 0 | 2 | 4 | 6);
+        let strokes = matches!(mode, 1 | 2 | 5 | 6);
+
+        for code in font.codes(bytes) {
+            let width = font.width(code);
+            let state = &self.state;
+            let transform = state
+                .ctm
+                .pre_concat(self.text_matrix)
+                .pre_concat(Transform::from_row(size * scale, 0.0, 0.0, size, 0.0, state.rise));
+            let clip = state.clip.as_deref();
+
+            match font.glyph(code) {
+                Glyph::Outline(path) => {
+                    if let (true, Some(color)) = (fills, state.fill) {
+                        let paint = solid(color, state.fill_alpha);
+                        self.pixmap
+                            .fill_path(&path, &paint, FillRule::Winding, transform, clip);
+                    }
+                    if let (true, Some(color)) = (strokes, state.stroke) {
+                        let paint = solid(color, state.stroke_alpha);
+                        let mut stroke = self.stroke_style(state.line_width);
+                        stroke.width /= size.abs().max(f32::EPSILON);
+                        self.pixmap
+                            .stroke_path(&path, &paint, &stroke, transform, clip);
+                    }
+                }
+                Glyph::Missing if width > 0.0 && (fills || strokes) => {
+                    // Greek the glyph as an x-height bar
+                    let color = if fills { state.fill } else { state.stroke };
+                    let bar = Rect::from_ltrb(width * 0.08, 0.0, width * 0.92, 0.5);
+                    if let (Some(color), Some(bar)) = (color, bar) {
+                        let paint = solid(color, state.fill_alpha * GREEKING_ALPHA);
+                        self.pixmap.fill_rect(bar, &paint, transform, clip);
+                    }
+                }
+                _ => {}
+            }
+
+            let mut advance = width * size + state.char_spacing;
+            if code == 32 && font.is_simple() {
+                advance += state.word_spacing;
+            }
+            self.text_matrix = self.text_matrix.pre_translate(advance * scale, 0.0);
+        }
+    }
+
+    fn draw_image(&mut self, stream: &Stream, resources: &Dictionary) {
+        if !self.charge(XOBJECT_WORK) {
+            return;
+        }
+        let state = &self.state;
+        let ctm = state.ctm;
+        // Device size of the unit square the image fills
+        let side = |length: f32| {
+            ((length * IMAGE_OVERSAMPLING).ceil() as u32).clamp(1, MAX_IMAGE_SIDE)
+        };
+        let max_width = side(ctm.sx.hypot(ctm.ky));
+        let max_height = side(ctm.kx.hypot(ctm.sy));
+        let stencil = state.fill.unwrap_or([0.5; 3]).map(|c| (c * 255.0).round() as u8);
+
+        let image = match image::decode(self.doc, stream, resources, stencil, max_width, max_height)
+        {
+            Ok(image) => image,
+            Err(e) => {
+                tracing::debug!("[pdf] Skipping image: {}", e);
+                return;
+            }
+        };
+        let (width, height) = (image.width() as f32, image.height() as f32);
+        // Image space: the unit square, with the first row at the top
+        let transform = ctm.pre_concat(Transform::from_row(
+            1.0 / width,
+            0.0,
+            0.0,
+            -1.0 / height,
+            0.0,
+            1.0,
+        ));
+        let paint = PixmapPaint {
+            opacity: state.fill_alpha,
+            blend_mode: BlendMode::SourceOver,
+            quality: FilterQuality::Bilinear,
+        };
+        self.pixmap.draw_pixmap(
+            0,
+            0,
+            image.as_ref(),
+            &paint,
+            transform,
+            state.clip.as_deref(),
+        );
+    }
+
+    fn run_form(&mut self, stream: &Stream, resources: &Dictionary) {
+        if self.depth >= MAX_FORM_DEPTH || !self.charge(XOBJECT_WORK) {
+            return;
+        }
+        let doc = self.doc;
+        // Forms drawn repeatedly are decoded and parsed once
+        let key = std::ptr::from_ref(stream) as usize;
+        let operations = match self.forms.get(&key) {
+            Some(operations) => Rc::clone(operations),
+            None => {
+                let data = decoded_content(stream);
+                let operations = Rc::new(content::parse(&data));
+                self.forms.insert(key, Rc::clone(&operations));
+                operations
+            }
+        };
+        let form_resources = get(doc, &stream.dict, b"Resources")
+            .and_then(|o| o.as_dict().ok())
+            .unwrap_or(resources);
+
+        let saved_depth = self.stack.len();
+        self.stack.push(self.state.clone());
+
+        let numbers = |key: &[u8]| -> Vec<f32> {
+            get(doc, &stream.dict, key)
+                .and_then(|o| o.as_array().ok())
+                .map(|items| items.iter().filter_map(|o| number(deref(doc, o))).collect())
+                .unwrap_or_default()
+        };
+        if let [a, b, c, d, e, f] = numbers(b"Matrix")[..] {
+            self.state.ctm = self.state.ctm.pre_concat(Transform::from_row(a, b, c, d, e, f));
+        }
+        if let [x0, y0, x1, y1] = numbers(b"BBox")[..] {
+            let bbox = Rect::from_ltrb(x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1))
+                .map(PathBuilder::from_rect);
+            self.clip(bbox.as_ref(), FillRule::Winding);
+        }
+
+        self.depth += 1;
+        self.run_operations(&operations, form_resources);
+        self.depth -= 1;
+
+        // Forms can leave unbalanced q operators behind
+        self.stack.truncate(saved_depth + 1);
+        if let Some(saved) = self.stack.pop() {
+            self.state = saved;
+        }
+    }
+}
+
+/// Color operands, or None for a pattern name
+fn components(op: &Operation) -> Option<Vec<f32>> {
+    if matches!(op.operands.last(), Some(Object::Name(_))) {
+        return None;
+    }
+    Some(op.operands.iter().filter_map(number).collect())
+}
+
+/// The sRGB color to paint, or None for patterns
+fn paintable(space: &ColorSpace, components: &[f32]) -> Option<[f32; 3]> {
+    match space {
+        ColorSpace::Pattern => None,
+        space => Some(space.to_rgb(components)),
+    }
+}
+
+fn solid(color: [f32; 3], alpha: f32) -> Paint<'static> {
+    let mut paint = Paint::default();
+    let [r, g, b] = color.map(|c| c.clamp(0.0, 1.0));
+    paint.set_color(Color::from_rgba(r, g, b, alpha.clamp(0.0, 1.0)).unwrap_or(Color::BLACK));
+    paint.anti_alias = true;
+    paint
+}
+
+fn line_cap(value: f32) -> LineCap {
+    match value as i64 {
+        1 => LineCap::Round,
+        2 => LineCap::Square,
+        _ => LineCap::Butt,
+    }
+}
+
+fn line_join(value: f32) -> LineJoin {
+    match value as i64 {
+        1 => LineJoin::Round,
+        2 => LineJoin::Bevel,
+        _ => LineJoin::Miter,
+    }
+}
+
+/// A dash pattern; an empty array means solid lines
+fn dash(doc: &Document, array: Option<&Object>, phase: f32) -> Option<(Vec<f32>, f32)> {
+    let mut lengths: Vec<f32> = deref(doc, array?)
+        .as_array()
+        .ok()?
+        .iter()
+        .filter_map(|o| number(deref(doc, o)))
+        .collect();
+    if lengths.is_empty() || lengths.iter().any(|l| *l < 0.0) || lengths.iter().all(|l| *l == 0.0)
+    {
+        return None;
+    }
+    // tiny-skia wants an even number of lengths, as PostScript repeats odd ones
+    if lengths.len() % 2 == 1 {
+        lengths.extend(lengths.clone());
+    }
+    Some((lengths, phase))
+}
@@ -0,0 +1,232 @@
+//! Quarantine manifest
+//!
+//! One SQLite database in the home quarantine root records every item in
+//! every quarantine root. Several items can share one object when their
+//! content is identical; an object is deleted with its last item.
+
+use super::QuarantinedItem;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use std::path::{Path, PathBuf};
+
+/// Manifest file name inside the home quarantine root
+pub const MANIFEST_FILE: &str = "manifest.db";
+
+const ITEM_COLUMNS: &str =
+    "id, path, name, original_path, quarantine_date, size, is_directory, content_hash";
+
+/// SQLite index of quarantined items
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    db_path: PathBuf,
+}
+
+impl Manifest {
+    /// Open or create the manifest in `root`
+    pub fn open(root: &Path) -> Result<Self, String> {
+        let manifest = Self {
+            db_path: root.join(MANIFEST_FILE),
+        };
+        manifest
+            .conn()?
+            .execute_batch(
+                r#"
+                PRAGMA journal_mode = WAL;
+
+                CREATE TABLE IF NOT EXISTS items (
+                    id TEXT PRIMARY KEY,
+                    path TEXT NOT NULL,
+                    name TEXT NOT NULL,
+                    original_path TEXT NOT NULL,
+                    quarantine_date INTEGER NOT NULL,
+                    last_used INTEGER NOT NULL,
+                    size INTEGER NOT NULL,
+                    is_directory INTEGER NOT NULL,
+                    content_hash TEXT
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_items_path ON items(path);
+                CREATE INDEX IF NOT EXISTS idx_items_original_path ON items(original_path);
+                CREATE INDEX IF NOT EXISTS idx_items_last_used ON items(last_used);
+                "#,
+            )
+            .map_err(|e| format!("Failed to initialize quarantine manifest: {}", e))?;
+        Ok(manifest)
+    }
+
+    fn conn(&self) -> Result<Connection, String> {
+        Connection::open(&self.db_path)
+            .map_err(|e| format!("Failed to open quarantine manifest: {}", e))
+    }
+
+    /// Record an item; it counts as used now
+    pub fn insert(&self, item: &QuarantinedItem) -> Result<(), String> {
+        self.conn()?
+            .execute(
+                r#"
+                INSERT INTO items (id, path, name, original_path, quarantine_date, last_used,
+                                   size, is_directory, content_hash)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                "#,
+                params![
+                    item.id,
+                    path_key(&item.path),
+                    item.name,
+                    path_key(&item.original_path),
+                    item.quarantine_date.timestamp_millis(),
+                    Utc::now().timestamp_millis(),
+                    item.size as i64,
+                    item.is_directory,
+                    item.content_hash,
+                ],
+            )
+            .map_err(|e| format!("Failed to record quarantined item: {}", e))?;
+        Ok(())
+    }
+
+    /// Drop an item's record
+    pub fn remove(&self, id: &str) -> Result<(), String> {
+        self.conn()?
+            .execute("DELETE FROM items WHERE id = ?1", params![id])
+            .map_err(|e| format!("Failed to update quarantine manifest: {}", e))?;
+        Ok(())
+    }
+
+    /// Mark an item as used now, moving it to the back of the eviction order
+    pub fn touch(&self, id: &str) -> Result<(), String> {
+        self.conn()?
+            .execute(
+                "UPDATE items SET last_used = ?2 WHERE id = ?1",
+                params![id, Utc::now().timestamp_millis()],
+            )
+            .map_err(|e| format!("Failed to update quarantine manifest: {}", e))?;
+        Ok(())
+    }
+
+    /// All items, newest first
+    pub fn list(&self) -> Result<Vec<QuarantinedItem>, String> {
+        self.query(
+            &format!("SELECT {} FROM items ORDER BY quarantine_date DESC", ITEM_COLUMNS),
+            params![],
+        )
+    }
+
+    /// Item with manifest id `id`
+    pub fn get(&self, id: &str) -> Result<Option<QuarantinedItem>, String> {
+        self.conn()?
+            .query_row(
+                &format!("SELECT {} FROM items WHERE id = ?1", ITEM_COLUMNS),
+                params![id],
+                item_from_row,
+            )
+            .optional()
+            .map_err(|e| format!("Failed to query quarantine manifest: {}", e))
+    }
+
+    /// Newest item stored at `path`
+    pub fn find_by_path(&self, path: &Path) -> Result<Option<QuarantinedItem>, String> {
+        self.find("path", path)
+    }
+
+    /// Newest item quarantined from `original_path`
+    pub fn find_by_original_path(
+        &self,
+        original_path: &Path,
+    ) -> Result<Option<QuarantinedItem>, String> {
+        self.find("original_path", original_path)
+    }
+
+    fn find(&self, column: &str, path: &Path) -> Result<Option<QuarantinedItem>, String> {
+        self.conn()?
+            .query_row(
+                &format!(
+                    "SELECT {} FROM items WHERE {} = ?1 ORDER BY quarantine_date DESC LIMIT 1",
+                    ITEM_COLUMNS, column
+                ),
+                params![path_key(path)],
+                item_from_row,
+            )
+            .optional()
+            .map_err(|e| format!("Failed to query quarantine manifest: {}", e))
+    }
+
+    /// Number of items stored at `path`
+    pub fn references(&self, path: &Path) -> Result<usize, String> {
+        self.conn()?
+            .query_row(
+                "SELECT COUNT(*) FROM items WHERE path = ?1",
+                params![path_key(path)],
+                |row| row.get::<_, i64>(0),
+            )
+            .map(|count| count as usize)
+            .map_err(|e| format!("Failed to query quarantine manifest: {}", e))
+    }
+
+    /// Items quarantined before `cutoff`
+    pub fn expired(&self, cutoff: DateTime<Utc>) -> Result<Vec<QuarantinedItem>, String> {
+        self.query(
+            &format!(
+                "SELECT {} FROM items WHERE quarantine_date < ?1 ORDER BY quarantine_date",
+                ITEM_COLUMNS
+            ),
+            params![cutoff.timestamp_millis()],
+        )
+    }
+
+    /// All items, least recently used first
+    pub fn least_recently_used(&self) -> Result<Vec<QuarantinedItem>, String> {
+        self.query(
+            &format!("SELECT {} FROM items ORDER BY last_used, quarantine_date", ITEM_COLUMNS),
+            params![],
+        )
+    }
+
+    /// Bytes stored on disk under quarantine root `root`; shared objects
+    /// count once
+    pub fn stored_bytes_in(&self, root: &Path) -> Result<u64, String> {
+        let prefix = path_key(&root.join(""));
+        self.conn()?
+            .query_row(
+                "SELECT COALESCE(SUM(size), 0) FROM (SELECT MAX(size) AS size FROM items \
+                 WHERE substr(path, 1, length(?1)) = ?1 GROUP BY path)",
+                params![prefix],
+                |row| row.get::<_, i64>(0),
+            )
+            .map(|bytes| bytes as u64)
+            .map_err(|e| format!("Failed to query quarantine manifest: {}", e))
+    }
+
+    fn query(
+        &self,
+        sql: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Vec<QuarantinedItem>, String> {
+        let conn = self.conn()?;
+        let mut stmt = conn
+            .prepare(sql)
+            .map_err(|e| format!("Failed to query quarantine manifest: {}", e))?;
+        let items = stmt
+            .query_map(params, item_from_row)
+            .map_err(|e| format!("Failed to query quarantine manifest: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read quarantine manifest: {}", e))?;
+        Ok(items)
+    }
+}
+
+fn item_from_row(row: &Row<'_>) -> rusqlite::Result<QuarantinedItem> {
+    Ok(QuarantinedItem {
+        id: row.get(0)?,
+        path: PathBuf::from(row.get::<_, String>(1)?),
+        name: row.get(2)?,
+        original_path: PathBuf::from(row.get::<_, String>(3)?),
+        quarantine_date: DateTime::from_timestamp_millis(row.get(4)?).unwrap_or_default(),
+        size: row.get::<_, i64>(5)? as u64,
+        is_directory: row.get(6)?,
+        content_hash: row.get(7)?,
+    })
+}
+
+fn path_key(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
@@ -3,17 +3,49 @@
 //! Provides safe deletion functionality by moving files to a quarantine
 //! directory instead of permanently deleting them. Supports restoration
 //! and automatic cleanup of old entries.
+//!
+//! - `store`: Content-addressed objects and cross-filesystem moves
+//! - `manifest`: SQLite index of quarantined items
+//!
+//! Items on another filesystem go to a quarantine root at that volume's
+//! mount point, so quarantining never copies a file off its drive unless
+//! that root can't be created.
+//!
+//! The size quota applies to each quarantine root separately and is only
+//! enforced by `cleanup`, which never evicts items that undo or WAL recovery
+//! may still restore.
 
+mod manifest;
+mod store;
+
+use crate::history::{HistoryStore, OperationRecord};
+use crate::wal::entry::WALOperationType;
+use crate::wal::journal::WALManager;
 use chrono::{DateTime, Duration, Utc};
+use manifest::{Manifest, MANIFEST_FILE};
 use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
+pub use store::move_path;
+
+/// Name of the quarantine root created at the mount point of other volumes
+pub const VOLUME_ROOT_NAME: &str = ".sentinel-quarantine";
+
+/// Default size quota per quarantine root for [`QuarantineManager::new`] (10 GiB)
+pub const DEFAULT_QUOTA_BYTES: u64 = 10 * 1024 * 1024 * 1024;
+
 /// A quarantined file or directory
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct QuarantinedItem {
-    /// Current path in quarantine
+    /// Manifest ID
+    #[serde(default)]
+    pub id: String,
+
+    /// Current path in quarantine; items with identical content share it
     pub path: PathBuf,
 
     /// Original file/directory name
@@ -30,6 +62,11 @@ pub struct QuarantinedItem {
 
     /// Whether this is a directory
     pub is_directory: bool,
+
+    /// SHA-256 of the content (a tree hash for directories), checked on
+    /// restore. Items quarantined by older versions have none.
+    #[serde(default)]
+    pub content_hash: Option<String>,
 }
 
 /// Statistics from a cleanup operation
@@ -46,24 +83,109 @@ pub struct CleanupStats {
     pub errors: usize,
 }
 
+/// Quarantined items that undo or WAL recovery may still restore
+#[derive(Debug, Default)]
+pub struct ItemReferences {
+    ids: HashSet<String>,
+    /// Quarantine paths of items quarantined by older versions, without an ID
+    paths: HashSet<PathBuf>,
+}
+
+impl ItemReferences {
+    /// Items referenced by history sessions that are not undone and by WAL
+    /// journals that are still open
+    pub fn load() -> Result<Self, String> {
+        let mut references = Self::default();
+
+        let history = HistoryStore::new();
+        for folder in history.list_folders()? {
+            let Some(folder_history) = history.load_history(&folder.folder_path)? else {
+                continue;
+            };
+            for session in folder_history.sessions.iter().filter(|s| !s.undone) {
+                for op in &session.operations {
+                    references.add_record(&op.operation);
+                    references.add_record(&op.undo_operation);
+                }
+            }
+        }
+
+        let wal = WALManager::new();
+        for job_id in wal.list_journals().map_err(|e| e.message)? {
+            let Some(journal) = wal.load_journal(&job_id).map_err(|e| e.message)? else {
+                continue;
+            };
+            for entry in &journal.entries {
+                references.add_operation(&entry.operation);
+                references.add_operation(&entry.undo_operation);
+            }
+        }
+
+        Ok(references)
+    }
+
+    fn add_record(&mut self, record: &OperationRecord) {
+        match record {
+            OperationRecord::Quarantine {
+                item_id: Some(id), ..
+            }
+            | OperationRecord::Restore { item_id: id, .. } => {
+                self.ids.insert(id.clone());
+            }
+            OperationRecord::Quarantine {
+                quarantine_path, ..
+            } => {
+                self.paths.insert(PathBuf::from(quarantine_path));
+            }
+            _ => {}
+        }
+    }
+
+    fn add_operation(&mut self, operation: &WALOperationType) {
+        match operation {
+            WALOperationType::Quarantine {
+                item_id: Some(id), ..
+            }
+            | WALOperationType::Restore { item_id: id, .. } => {
+                self.ids.insert(id.clone());
+            }
+            WALOperationType::Quarantine {
+                quarantine_path, ..
+            } => {
+                self.paths.insert(quarantine_path.clone());
+            }
+            _ => {}
+        }
+    }
+
+    fn contains(&self, item: &QuarantinedItem) -> bool {
+        self.ids.contains(&item.id) || self.paths.contains(&item.path)
+    }
+}
+
 /// Manages the quarantine directory for safe deletion
 ///
 /// Files are moved to a quarantine directory instead of being permanently
 /// deleted. They can be restored or are automatically cleaned up after
-/// the retention period expires.
+/// the retention period expires, or earlier by `cleanup` when their
+/// quarantine root grows past the size quota (least recently used first).
 #[derive(Debug, Clone)]
 pub struct QuarantineManager {
-    /// Base path for quarantine storage
+    /// Base path for quarantine storage (home quarantine root and manifest)
     base_path: PathBuf,
 
     /// Number of days to retain quarantined items
     retention_days: u32,
+
+    /// Maximum bytes kept in quarantine, if limited
+    max_bytes: Option<u64>,
 }
 
 impl QuarantineManager {
     /// Create a new QuarantineManager with default settings
     ///
-    /// Uses ~/.sentinel/quarantine as the base path and 30 days retention
+    /// Uses ~/.sentinel/quarantine as the base path, 30 days retention and
+    /// a [`DEFAULT_QUOTA_BYTES`] quota
     pub fn new() -> Result<Self, String> {
         let base_path = dirs::home_dir()
             .ok_or("Could not determine home directory")?
@@ -73,17 +195,25 @@ impl QuarantineManager {
         Ok(Self {
             base_path,
             retention_days: 30,
+            max_bytes: Some(DEFAULT_QUOTA_BYTES),
         })
     }
 
-    /// Create a QuarantineManager with custom settings
+    /// Create a QuarantineManager with custom settings and no size quota
     pub fn with_config(base_path: PathBuf, retention_days: u32) -> Self {
         Self {
             base_path,
             retention_days,
+            max_bytes: None,
         }
     }
 
+    /// Limit the bytes kept in each quarantine root
+    pub fn with_quota(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
     /// Get the base quarantine path
     pub fn base_path(&self) -> &PathBuf {
         &self.base_path
@@ -94,6 +224,11 @@ impl QuarantineManager {
         self.retention_days
     }
 
+    /// Get the size quota in bytes, if any
+    pub fn quota(&self) -> Option<u64> {
+        self.max_bytes
+    }
+
     /// Ensure the quarantine directory exists
     fn ensure_quarantine_dir(&self) -> Result<(), String> {
         if !self.base_path.exists() {
@@ -103,109 +238,282 @@ impl QuarantineManager {
         Ok(())
     }
 
-    /// Generate a unique quarantine path for an item
-    fn generate_quarantine_path(&self, original_path: &Path) -> PathBuf {
-        let timestamp = Utc::now().format("%Y%m%d_%H%M%S_%3f").to_string();
-        let name = original_path
+    /// Open the manifest, importing items left by older versions
+    fn manifest(&self) -> Result<Manifest, String> {
+        self.ensure_quarantine_dir()?;
+        let manifest = Manifest::open(&self.base_path)?;
+        self.import_legacy_items(&manifest)?;
+        Ok(manifest)
+    }
+
+    /// Record items stored by older versions, which moved each item into
+    /// the base path as `<timestamp>_<name>` with a `.quarantine.json`
+    /// sidecar holding its metadata
+    fn import_legacy_items(&self, manifest: &Manifest) -> Result<(), String> {
+        let entries = fs::read_dir(&self.base_path)
+            .map_err(|e| format!("Failed to read quarantine directory: {}", e))?;
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name == store::OBJECTS_DIR
+                || name == store::STAGING_DIR
+                || name.starts_with(MANIFEST_FILE)
+                || name.ends_with(".quarantine.json")
+                || manifest.find_by_path(&path)?.is_some()
+            {
+                continue;
+            }
+
+            let sidecar = path.with_extension("quarantine.json");
+            let item = fs::read_to_string(&sidecar)
+                .ok()
+                .and_then(|json| serde_json::from_str::<QuarantinedItem>(&json).ok());
+            let item = match item {
+                Some(item) => QuarantinedItem {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    path: path.clone(),
+                    content_hash: None,
+                    ..item
+                },
+                None => match self.untracked_item(&path) {
+                    Some(item) => item,
+                    None => continue,
+                },
+            };
+            manifest.insert(&item)?;
+            let _ = fs::remove_file(&sidecar);
+        }
+        Ok(())
+    }
+
+    /// Item for a legacy entry whose sidecar is missing
+    fn untracked_item(&self, path: &Path) -> Option<QuarantinedItem> {
+        let metadata = fs::symlink_metadata(path).ok()?;
+        let name = path
             .file_name()
             .map(|n| n.to_string_lossy().to_string())
             .unwrap_or_else(|| "unknown".to_string());
 
-        self.base_path.join(format!("{}_{}", timestamp, name))
+        // Try to extract original name from quarantine name (timestamp_name)
+        let original_name = name
+            .split('_')
+            .skip(3) // Skip timestamp parts
+            .collect::<Vec<_>>()
+            .join("_");
+
+        let quarantine_date = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .and_then(|d| chrono::DateTime::from_timestamp(d.as_secs() as i64, 0))
+            .unwrap_or_else(Utc::now);
+
+        Some(QuarantinedItem {
+            id: uuid::Uuid::new_v4().to_string(),
+            path: path.to_path_buf(),
+            name: if original_name.is_empty() {
+                name
+            } else {
+                original_name
+            },
+            original_path: PathBuf::new(), // Unknown
+            quarantine_date,
+            size: if metadata.is_dir() {
+                self.calculate_dir_size(path)
+            } else {
+                metadata.len()
+            },
+            is_directory: metadata.is_dir(),
+            content_hash: None,
+        })
+    }
+
+    /// Quarantine root a path would be moved into, without creating it
+    ///
+    /// The base path when the path is on the same filesystem, otherwise a
+    /// root at the path's mount point.
+    pub fn planned_root(&self, path: &Path) -> PathBuf {
+        let device = store::device_id(path);
+        if device.is_none() || device == store::device_id(&self.base_path) {
+            return self.base_path.clone();
+        }
+
+        match store::mount_point(path) {
+            // Quarantining a whole volume can't keep it on the volume
+            Some(mount) if mount != path => mount.join(VOLUME_ROOT_NAME),
+            _ => self.base_path.clone(),
+        }
+    }
+
+    /// Quarantine root for a path, as chosen by `planned_root`, falling back
+    /// to the base path when a volume root can't be created
+    fn root_for(&self, path: &Path) -> PathBuf {
+        let root = self.planned_root(path);
+        if root == self.base_path {
+            return root;
+        }
+        match fs::create_dir_all(&root) {
+            Ok(()) => root,
+            Err(e) => {
+                eprintln!(
+                    "[Quarantine] Cannot use {} ({}), copying to {}",
+                    root.display(),
+                    e,
+                    self.base_path.display()
+                );
+                self.base_path.clone()
+            }
+        }
     }
 
     /// Move a file or directory to quarantine
     ///
+    /// The item is moved into a staging area of its quarantine root, hashed
+    /// and stored as the object named by its hash. When identical content is
+    /// already quarantined the existing object is shared. Items on another
+    /// filesystem without a usable root are copied, verified and then
+    /// deleted.
+    ///
     /// # Arguments
     /// * `path` - The path to quarantine
     ///
     /// # Returns
-    /// * `Ok(PathBuf)` - The path where the item was quarantined
+    /// * `Ok(QuarantinedItem)` - The manifest item, with the path where the
+    ///   item was quarantined
     /// * `Err(String)` - Error message if quarantine failed
-    pub fn quarantine(&self, path: &PathBuf) -> Result<PathBuf, String> {
-        self.ensure_quarantine_dir()?;
+    pub fn quarantine(&self, path: &PathBuf) -> Result<QuarantinedItem, String> {
+        self.quarantine_with_id(path, &uuid::Uuid::new_v4().to_string())
+    }
+
+    /// Move a file or directory to quarantine as manifest item `id`
+    ///
+    /// Used by WAL operations, which choose the ID when they are planned.
+    /// Replaying an operation whose item is already quarantined returns
+    /// that item.
+    pub fn quarantine_with_id(&self, path: &PathBuf, id: &str) -> Result<QuarantinedItem, String> {
+        let manifest = self.manifest()?;
+        if let Some(item) = manifest.get(id)? {
+            if item.original_path == *path && fs::symlink_metadata(path).is_err() {
+                return Ok(item);
+            }
+            return Err(format!("Quarantine item ID {} is already in use", id));
+        }
 
         // Validate path exists
-        if !path.exists() {
-            return Err(format!("Path does not exist: {}", path.display()));
+        let metadata = fs::symlink_metadata(path)
+            .map_err(|_| format!("Path does not exist: {}", path.display()))?;
+
+        let root = self.root_for(path);
+        if path.starts_with(&root) || path.starts_with(&self.base_path) {
+            return Err(format!("Path is already in quarantine: {}", path.display()));
         }
 
-        // Get metadata before move
-        let metadata = fs::metadata(path)
-            .map_err(|e| format!("Failed to get metadata: {}", e))?;
+        let size = if metadata.is_dir() {
+            self.calculate_dir_size(path)
+        } else {
+            metadata.len()
+        };
 
-        let quarantine_path = self.generate_quarantine_path(path);
+        if let Some(max_bytes) = self.max_bytes {
+            let stored = manifest.stored_bytes_in(&root)?;
+            if stored + size > max_bytes {
+                eprintln!(
+                    "[Quarantine] {} is over its {} byte quota; \
+                     cleanup will evict unreferenced items",
+                    root.display(),
+                    max_bytes
+                );
+            }
+        }
 
-        // Move the file/directory
-        fs::rename(path, &quarantine_path)
-            .map_err(|e| format!("Failed to move to quarantine: {}", e))?;
+        let staging = store::staging_path(&root);
+        if let Some(parent) = staging.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create quarantine directory: {}", e))?;
+        }
+        store::move_path(path, &staging)?;
+
+        let (object, hash) = match self.store_object(&root, &staging) {
+            Ok(stored) => stored,
+            Err(e) => {
+                // Put the item back where it was
+                if let Err(undo) = store::move_path(&staging, path) {
+                    eprintln!(
+                        "[Quarantine] Failed to move {} back from {}: {}",
+                        path.display(),
+                        staging.display(),
+                        undo
+                    );
+                }
+                return Err(e);
+            }
+        };
 
-        // Save metadata for restoration
         let item = QuarantinedItem {
-            path: quarantine_path.clone(),
+            id: id.to_string(),
+            path: object,
             name: path
                 .file_name()
                 .map(|n| n.to_string_lossy().to_string())
                 .unwrap_or_default(),
             original_path: path.clone(),
             quarantine_date: Utc::now(),
-            size: if metadata.is_dir() {
-                self.calculate_dir_size(&quarantine_path)
-            } else {
-                metadata.len()
-            },
+            size,
             is_directory: metadata.is_dir(),
+            content_hash: Some(hash),
         };
-
-        // Save item metadata
-        self.save_item_metadata(&quarantine_path, &item)?;
+        manifest.insert(&item)?;
 
         eprintln!(
             "[Quarantine] Moved {} to {}",
             path.display(),
-            quarantine_path.display()
+            item.path.display()
         );
 
-        Ok(quarantine_path)
+        Ok(item)
     }
 
-    /// Save metadata for a quarantined item
-    fn save_item_metadata(&self, quarantine_path: &Path, item: &QuarantinedItem) -> Result<(), String> {
-        let metadata_path = quarantine_path.with_extension("quarantine.json");
-        let json = serde_json::to_string_pretty(item)
-            .map_err(|e| format!("Failed to serialize metadata: {}", e))?;
-        fs::write(&metadata_path, json)
-            .map_err(|e| format!("Failed to write metadata: {}", e))?;
-        Ok(())
-    }
+    /// Turn a staged item into an object; returns the object path and hash
+    fn store_object(&self, root: &Path, staging: &Path) -> Result<(PathBuf, String), String> {
+        let hash = store::content_hash(staging)?;
+        let object = store::object_path(root, &hash);
 
-    /// Load metadata for a quarantined item
-    fn load_item_metadata(&self, quarantine_path: &Path) -> Option<QuarantinedItem> {
-        let metadata_path = quarantine_path.with_extension("quarantine.json");
-        if metadata_path.exists() {
-            fs::read_to_string(&metadata_path)
-                .ok()
-                .and_then(|json| serde_json::from_str(&json).ok())
+        if fs::symlink_metadata(&object).is_ok() {
+            // Identical content is already stored
+            store::remove_path(staging)
+                .map_err(|e| format!("Failed to remove staged copy: {}", e))?;
         } else {
-            None
+            if let Some(parent) = object.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create quarantine directory: {}", e))?;
+            }
+            fs::rename(staging, &object)
+                .map_err(|e| format!("Failed to move to quarantine: {}", e))?;
         }
+        Ok((object, hash))
     }
 
     /// Restore a quarantined item to its original location
     ///
+    /// Content-addressed items are hashed first and refused if the stored
+    /// data no longer matches. An object shared with other items is copied
+    /// out rather than moved.
+    ///
     /// # Arguments
-    /// * `quarantine_path` - Path in quarantine
+    /// * `id` - Manifest ID of the item
     /// * `original_path` - Where to restore the item (optional, uses stored original if None)
     ///
     /// # Returns
     /// * `Ok(())` - Item was restored
     /// * `Err(String)` - Error message if restoration failed
-    pub fn restore(
-        &self,
-        quarantine_path: &PathBuf,
-        original_path: Option<PathBuf>,
-    ) -> Result<(), String> {
-        if !quarantine_path.exists() {
+    pub fn restore(&self, id: &str, original_path: Option<PathBuf>) -> Result<(), String> {
+        let manifest = self.manifest()?;
+        let item = manifest
+            .get(id)?
+            .ok_or_else(|| format!("No quarantined item with ID {}", id))?;
+        let quarantine_path = &item.path;
+        if fs::symlink_metadata(quarantine_path).is_err() {
             return Err(format!(
                 "Quarantine path does not exist: {}",
                 quarantine_path.display()
@@ -213,14 +521,9 @@ impl QuarantineManager {
         }
 
         // Determine restoration path
-        let restore_path = if let Some(path) = original_path {
-            path
-        } else {
-            // Try to load from metadata
-            self.load_item_metadata(quarantine_path)
-                .map(|item| item.original_path)
-                .ok_or_else(|| "No original path found and none provided".to_string())?
-        };
+        let restore_path = original_path
+            .or_else(|| Some(item.original_path.clone()).filter(|p| !p.as_os_str().is_empty()))
+            .ok_or_else(|| "No original path found and none provided".to_string())?;
 
         // Check if original location is available
         if restore_path.exists() {
@@ -238,16 +541,26 @@ impl QuarantineManager {
             }
         }
 
-        // Move back to original location
-        fs::rename(quarantine_path, &restore_path)
-            .map_err(|e| format!("Failed to restore from quarantine: {}", e))?;
-
-        // Clean up metadata file
-        let metadata_path = quarantine_path.with_extension("quarantine.json");
-        if metadata_path.exists() {
-            let _ = fs::remove_file(&metadata_path);
+        match &item.content_hash {
+            Some(expected) => {
+                let actual = store::content_hash(quarantine_path)?;
+                if &actual != expected {
+                    return Err(format!(
+                        "Integrity check failed for {}: quarantined data no longer matches its hash",
+                        quarantine_path.display()
+                    ));
+                }
+                if manifest.references(quarantine_path)? > 1 {
+                    store::copy_verified(quarantine_path, &restore_path, expected)?;
+                } else {
+                    store::move_path(quarantine_path, &restore_path)?;
+                }
+            }
+            None => store::move_path(quarantine_path, &restore_path)?,
         }
 
+        manifest.remove(&item.id)?;
+
         eprintln!(
             "[Quarantine] Restored {} to {}",
             quarantine_path.display(),
@@ -257,12 +570,47 @@ impl QuarantineManager {
         Ok(())
     }
 
-    /// Clean up old quarantined items that have exceeded the retention period
+    /// Restore item `id` to `path` for a WAL operation
+    ///
+    /// Replaying an operation whose item was already restored there
+    /// succeeds without changes.
+    pub fn restore_to(&self, id: &str, path: &Path) -> Result<(), String> {
+        if self.manifest()?.get(id)?.is_none() && fs::symlink_metadata(path).is_ok() {
+            return Ok(());
+        }
+        self.restore(id, Some(path.to_path_buf()))
+    }
+
+    /// Item with manifest ID `id`, if it is still quarantined
+    pub fn get(&self, id: &str) -> Result<Option<QuarantinedItem>, String> {
+        self.manifest()?.get(id)
+    }
+
+    /// Clean up quarantined items past the retention period, then evict
+    /// least recently used items from quarantine roots over their quota
+    ///
+    /// Items referenced by history sessions or open WAL journals are never
+    /// evicted; if the references can't be read, nothing is.
     ///
     /// # Returns
     /// * `Ok(CleanupStats)` - Statistics about the cleanup operation
     /// * `Err(String)` - Error message if cleanup failed
     pub fn cleanup(&self) -> Result<CleanupStats, String> {
+        let references = match self.max_bytes {
+            Some(_) => match ItemReferences::load() {
+                Ok(references) => Some(references),
+                Err(e) => {
+                    eprintln!("[Quarantine] Skipping quota eviction: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+        self.cleanup_with(references.as_ref())
+    }
+
+    /// `cleanup`, evicting only items outside `references` (none without)
+    fn cleanup_with(&self, references: Option<&ItemReferences>) -> Result<CleanupStats, String> {
         let mut stats = CleanupStats {
             items_removed: 0,
             bytes_freed: 0,
@@ -273,79 +621,14 @@ impl QuarantineManager {
             return Ok(stats);
         }
 
+        let manifest = self.manifest()?;
         let cutoff_date = Utc::now() - Duration::days(self.retention_days as i64);
 
-        let entries = fs::read_dir(&self.base_path)
-            .map_err(|e| format!("Failed to read quarantine directory: {}", e))?;
-
-        for entry_result in entries {
-            let entry = match entry_result {
-                Ok(e) => e,
-                Err(_) => {
-                    stats.errors += 1;
-                    continue;
-                }
-            };
-
-            let path = entry.path();
-
-            // Skip metadata files
-            if path
-                .extension()
-                .map(|e| e == "json")
-                .unwrap_or(false)
-            {
-                continue;
-            }
-
-            // Check if item should be cleaned up
-            let should_cleanup = if let Some(item) = self.load_item_metadata(&path) {
-                item.quarantine_date < cutoff_date
-            } else {
-                // No metadata - check file modification time
-                entry
-                    .metadata()
-                    .ok()
-                    .and_then(|m| m.modified().ok())
-                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                    .map(|d| {
-                        let file_time = chrono::DateTime::from_timestamp(d.as_secs() as i64, 0)
-                            .unwrap_or_else(Utc::now);
-                        file_time < cutoff_date
-                    })
-                    .unwrap_or(false)
-            };
-
-            if should_cleanup {
-                let size = if path.is_dir() {
-                    self.calculate_dir_size(&path)
-                } else {
-                    fs::metadata(&path).map(|m| m.len()).unwrap_or(0)
-                };
-
-                let result = if path.is_dir() {
-                    fs::remove_dir_all(&path)
-                } else {
-                    fs::remove_file(&path)
-                };
-
-                match result {
-                    Ok(_) => {
-                        stats.items_removed += 1;
-                        stats.bytes_freed += size;
-
-                        // Remove metadata file
-                        let metadata_path = path.with_extension("quarantine.json");
-                        let _ = fs::remove_file(&metadata_path);
-
-                        eprintln!("[Quarantine] Cleaned up: {}", path.display());
-                    }
-                    Err(e) => {
-                        eprintln!("[Quarantine] Failed to clean up {}: {}", path.display(), e);
-                        stats.errors += 1;
-                    }
-                }
-            }
+        for item in manifest.expired(cutoff_date)? {
+            self.remove_counted(&manifest, &item, &mut stats);
+        }
+        if let Some(references) = references {
+            self.evict_over_quota(&manifest, references, &mut stats)?;
         }
 
         eprintln!(
@@ -356,91 +639,103 @@ impl QuarantineManager {
         Ok(stats)
     }
 
-    /// List all quarantined items
-    ///
-    /// # Returns
-    /// * `Ok(Vec<QuarantinedItem>)` - List of quarantined items
-    /// * `Err(String)` - Error message if listing failed
-    pub fn list(&self) -> Result<Vec<QuarantinedItem>, String> {
-        let mut items = Vec::new();
+    /// Evict least recently used, unreferenced items until every quarantine
+    /// root fits the quota
+    fn evict_over_quota(
+        &self,
+        manifest: &Manifest,
+        references: &ItemReferences,
+        stats: &mut CleanupStats,
+    ) -> Result<(), String> {
+        let Some(max_bytes) = self.max_bytes else {
+            return Ok(());
+        };
 
-        if !self.base_path.exists() {
-            return Ok(items);
+        let items = manifest.least_recently_used()?;
+        let mut stored: HashMap<PathBuf, u64> = HashMap::new();
+        for item in &items {
+            if let Entry::Vacant(entry) = stored.entry(self.root_of(&item.path)) {
+                let bytes = manifest.stored_bytes_in(entry.key())?;
+                entry.insert(bytes);
+            }
         }
 
-        let entries = fs::read_dir(&self.base_path)
-            .map_err(|e| format!("Failed to read quarantine directory: {}", e))?;
-
-        for entry_result in entries {
-            let entry = match entry_result {
-                Ok(e) => e,
-                Err(_) => continue,
+        for item in &items {
+            if references.contains(item) {
+                continue;
+            }
+            let Some(bytes) = stored.get_mut(&self.root_of(&item.path)) else {
+                continue;
             };
-
-            let path = entry.path();
-
-            // Skip metadata files
-            if path
-                .extension()
-                .map(|e| e == "json")
-                .unwrap_or(false)
-            {
+            if *bytes <= max_bytes {
                 continue;
             }
+            let freed_before = stats.bytes_freed;
+            self.remove_counted(manifest, item, stats);
+            *bytes = bytes.saturating_sub(stats.bytes_freed - freed_before);
+        }
+        Ok(())
+    }
 
-            // Try to load metadata
-            if let Some(item) = self.load_item_metadata(&path) {
-                items.push(item);
-            } else {
-                // Create item from filesystem metadata
-                if let Ok(metadata) = fs::metadata(&path) {
-                    let name = path
-                        .file_name()
-                        .map(|n| n.to_string_lossy().to_string())
-                        .unwrap_or_else(|| "unknown".to_string());
-
-                    // Try to extract original name from quarantine name (timestamp_name)
-                    let original_name = name
-                        .split('_')
-                        .skip(3) // Skip timestamp parts
-                        .collect::<Vec<_>>()
-                        .join("_");
-
-                    let quarantine_date = metadata
-                        .modified()
-                        .ok()
-                        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                        .and_then(|d| chrono::DateTime::from_timestamp(d.as_secs() as i64, 0))
-                        .unwrap_or_else(Utc::now);
-
-                    items.push(QuarantinedItem {
-                        path: path.clone(),
-                        name: if original_name.is_empty() {
-                            name
-                        } else {
-                            original_name
-                        },
-                        original_path: PathBuf::new(), // Unknown
-                        quarantine_date,
-                        size: if metadata.is_dir() {
-                            self.calculate_dir_size(&path)
-                        } else {
-                            metadata.len()
-                        },
-                        is_directory: metadata.is_dir(),
-                    });
-                }
+    /// Quarantine root holding a stored object
+    fn root_of(&self, object: &Path) -> PathBuf {
+        if object.starts_with(&self.base_path) {
+            return self.base_path.clone();
+        }
+        object
+            .ancestors()
+            .find(|a| a.file_name().is_some_and(|n| n == VOLUME_ROOT_NAME))
+            .unwrap_or(&self.base_path)
+            .to_path_buf()
+    }
+
+    /// Remove an item, recording the outcome in `stats`
+    fn remove_counted(
+        &self,
+        manifest: &Manifest,
+        item: &QuarantinedItem,
+        stats: &mut CleanupStats,
+    ) {
+        match self.remove_item(manifest, item) {
+            Ok(freed) => {
+                stats.items_removed += 1;
+                stats.bytes_freed += freed;
+                eprintln!("[Quarantine] Cleaned up: {}", item.name);
+            }
+            Err(e) => {
+                eprintln!("[Quarantine] Failed to clean up {}: {}", item.path.display(), e);
+                stats.errors += 1;
             }
         }
+    }
 
-        // Sort by quarantine date, newest first
-        items.sort_by(|a, b| b.quarantine_date.cmp(&a.quarantine_date));
+    /// Drop an item, deleting its data unless other items share it;
+    /// returns the bytes freed on disk
+    fn remove_item(&self, manifest: &Manifest, item: &QuarantinedItem) -> Result<u64, String> {
+        let mut freed = 0;
+        if manifest.references(&item.path)? <= 1 && fs::symlink_metadata(&item.path).is_ok() {
+            store::remove_path(&item.path)
+                .map_err(|e| format!("Failed to delete {}: {}", item.path.display(), e))?;
+            freed = item.size;
+        }
+        manifest.remove(&item.id)?;
+        Ok(freed)
+    }
 
-        Ok(items)
+    /// List all quarantined items
+    ///
+    /// # Returns
+    /// * `Ok(Vec<QuarantinedItem>)` - List of quarantined items, newest first
+    /// * `Err(String)` - Error message if listing failed
+    pub fn list(&self) -> Result<Vec<QuarantinedItem>, String> {
+        if !self.base_path.exists() {
+            return Ok(Vec::new());
+        }
+        self.manifest()?.list()
     }
 
     /// Calculate the total size of a directory
-    fn calculate_dir_size(&self, path: &PathBuf) -> u64 {
+    fn calculate_dir_size(&self, path: &Path) -> u64 {
         walkdir::WalkDir::new(path)
             .into_iter()
             .filter_map(|e| e.ok())
@@ -451,53 +746,36 @@ impl QuarantineManager {
     }
 
     /// Check if an item is in quarantine
-    pub fn is_quarantined(&self, original_path: &PathBuf) -> bool {
-        self.list()
-            .map(|items| {
-                items
-                    .iter()
-                    .any(|item| item.original_path == *original_path)
-            })
+    pub fn is_quarantined(&self, original_path: &Path) -> bool {
+        self.manifest()
+            .and_then(|manifest| manifest.find_by_original_path(original_path))
+            .map(|item| item.is_some())
             .unwrap_or(false)
     }
 
     /// Get a quarantined item by its original path
-    pub fn get_by_original_path(&self, original_path: &PathBuf) -> Option<QuarantinedItem> {
-        self.list()
-            .ok()?
-            .into_iter()
-            .find(|item| item.original_path == *original_path)
+    ///
+    /// Counts as a use of the item, postponing its eviction.
+    pub fn get_by_original_path(&self, original_path: &Path) -> Option<QuarantinedItem> {
+        let manifest = self.manifest().ok()?;
+        let item = manifest.find_by_original_path(original_path).ok()??;
+        let _ = manifest.touch(&item.id);
+        Some(item)
     }
 
     /// Permanently delete a quarantined item (bypassing retention)
-    pub fn permanent_delete(&self, quarantine_path: &PathBuf) -> Result<(), String> {
-        if !quarantine_path.exists() {
-            return Err(format!(
-                "Quarantine path does not exist: {}",
-                quarantine_path.display()
-            ));
-        }
-
-        if !quarantine_path.starts_with(&self.base_path) {
-            return Err("Path is not in quarantine directory".to_string());
-        }
-
-        let result = if quarantine_path.is_dir() {
-            fs::remove_dir_all(quarantine_path)
-        } else {
-            fs::remove_file(quarantine_path)
-        };
-
-        result.map_err(|e| format!("Failed to permanently delete: {}", e))?;
-
-        // Remove metadata file
-        let metadata_path = quarantine_path.with_extension("quarantine.json");
-        let _ = fs::remove_file(&metadata_path);
-
-        eprintln!(
-            "[Quarantine] Permanently deleted: {}",
-            quarantine_path.display()
-        );
+    ///
+    /// The stored data is deleted with the last item referring to it; data
+    /// shared with other quarantined items is kept for them.
+    pub fn permanent_delete(&self, id: &str) -> Result<(), String> {
+        let manifest = self.manifest()?;
+        let item = manifest
+            .get(id)?
+            .ok_or_else(|| format!("No quarantined item with ID {}", id))?;
+        self.remove_item(&manifest, &item)
+            .map_err(|e| format!("Failed to permanently delete: {}", e))?;
+
+        eprintln!("[Quarantine] Permanently deleted: {}", item.name);
 
         Ok(())
     }
@@ -533,7 +811,7 @@ mod tests {
         file.write_all(b"Hello, World!").unwrap();
 
         // Quarantine it
-        let quarantine_path = manager.quarantine(&test_file).unwrap();
+        let quarantine_path = manager.quarantine(&test_file).unwrap().path;
 
         // Original should not exist
         assert!(!test_file.exists());
@@ -551,14 +829,14 @@ mod tests {
         let mut file = File::create(&test_file).unwrap();
         file.write_all(b"Restore me!").unwrap();
 
-        let quarantine_path = manager.quarantine(&test_file).unwrap();
+        let item = manager.quarantine(&test_file).unwrap();
 
         // Restore it
-        manager.restore(&quarantine_path, None).unwrap();
+        manager.restore(&item.id, None).unwrap();
 
         // Original should exist again
         assert!(test_file.exists());
-        assert!(!quarantine_path.exists());
+        assert!(!item.path.exists());
     }
 
     #[test]
@@ -583,9 +861,221 @@ mod tests {
         let test_file = temp_dir.path().join("delete_test.txt");
         File::create(&test_file).unwrap();
 
-        let quarantine_path = manager.quarantine(&test_file).unwrap();
-        manager.permanent_delete(&quarantine_path).unwrap();
+        let item = manager.quarantine(&test_file).unwrap();
+        manager.permanent_delete(&item.id).unwrap();
+
+        assert!(!item.path.exists());
+        assert!(manager.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_identical_content_shares_one_object() {
+        let (manager, temp_dir) = create_test_manager();
+
+        let first = temp_dir.path().join("a.txt");
+        let second = temp_dir.path().join("b.txt");
+        fs::write(&first, b"same").unwrap();
+        fs::write(&second, b"same").unwrap();
+
+        let first_item = manager.quarantine(&first).unwrap();
+        let second_item = manager.quarantine(&second).unwrap();
+        let object = first_item.path.clone();
+        assert_eq!(second_item.path, object);
+        assert_eq!(manager.list().unwrap().len(), 2);
+
+        // Each item is restored by its own ID; the first is copied out
+        // while the second still needs the object
+        manager.restore(&first_item.id, None).unwrap();
+        assert_eq!(fs::read(&first).unwrap(), b"same");
+        assert!(!second.exists());
+        assert!(object.exists());
+
+        manager.restore(&second_item.id, None).unwrap();
+        assert_eq!(fs::read(&second).unwrap(), b"same");
+        assert!(!object.exists());
+        assert!(manager.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_permanent_delete_keeps_shared_object() {
+        let (manager, temp_dir) = create_test_manager();
+
+        let first = temp_dir.path().join("a.txt");
+        let second = temp_dir.path().join("b.txt");
+        fs::write(&first, b"same").unwrap();
+        fs::write(&second, b"same").unwrap();
+
+        let first_item = manager.quarantine(&first).unwrap();
+        let second_item = manager.quarantine(&second).unwrap();
+
+        manager.permanent_delete(&first_item.id).unwrap();
+        assert!(second_item.path.exists());
+        let items = manager.list().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].id, second_item.id);
+
+        manager.restore(&second_item.id, None).unwrap();
+        assert_eq!(fs::read(&second).unwrap(), b"same");
+        assert!(!first.exists());
+
+        let err = manager.permanent_delete(&first_item.id).unwrap_err();
+        assert!(err.contains("No quarantined item"), "{}", err);
+    }
+
+    #[test]
+    fn test_replayed_operations_are_idempotent() {
+        let (manager, temp_dir) = create_test_manager();
+
+        let file = temp_dir.path().join("report.pdf");
+        fs::write(&file, b"report").unwrap();
+
+        let item = manager.quarantine_with_id(&file, "op-1").unwrap();
+        assert_eq!(item.id, "op-1");
+        let replayed = manager.quarantine_with_id(&file, "op-1").unwrap();
+        assert_eq!(replayed.path, item.path);
+        assert_eq!(manager.list().unwrap().len(), 1);
+
+        manager.restore_to("op-1", &file).unwrap();
+        manager.restore_to("op-1", &file).unwrap();
+        assert_eq!(fs::read(&file).unwrap(), b"report");
+        assert!(manager.get("op-1").unwrap().is_none());
+
+        // The ID may be reused once the item is restored, as redo does
+        manager.quarantine_with_id(&file, "op-1").unwrap();
+        let other = temp_dir.path().join("other.pdf");
+        fs::write(&other, b"other").unwrap();
+        let err = manager.quarantine_with_id(&other, "op-1").unwrap_err();
+        assert!(err.contains("already in use"), "{}", err);
+        assert!(other.exists());
+    }
+
+    #[test]
+    fn test_restore_verifies_integrity() {
+        let (manager, temp_dir) = create_test_manager();
+
+        let dir = temp_dir.path().join("album");
+        fs::create_dir_all(dir.join("day1")).unwrap();
+        fs::write(dir.join("day1/photo.jpg"), b"pixels").unwrap();
+
+        let quarantined = manager.quarantine(&dir).unwrap();
+        let object = quarantined.path.clone();
+        let item = manager.get_by_original_path(&dir).unwrap();
+        assert!(item.is_directory);
+        assert_eq!(item.size, 6);
+
+        fs::write(object.join("day1/photo.jpg"), b"tampered").unwrap();
+        let err = manager.restore(&quarantined.id, None).unwrap_err();
+        assert!(err.contains("Integrity check failed"), "{}", err);
+        assert!(!dir.exists());
+        assert!(manager.is_quarantined(&dir));
+    }
+
+    #[test]
+    fn test_quota_evicts_least_recently_used() {
+        let (manager, temp_dir) = create_test_manager();
+        let manager = manager.with_quota(20);
+        let names = |manager: &QuarantineManager| -> Vec<_> {
+            manager.list().unwrap().into_iter().map(|i| i.name).collect()
+        };
+
+        let mut paths = Vec::new();
+        for name in ["old", "used", "new"] {
+            let path = temp_dir.path().join(name);
+            fs::write(&path, format!("{:<10}", name)).unwrap();
+            manager.quarantine(&path).unwrap();
+            paths.push(path);
+            if name == "old" {
+                std::thread::sleep(std::time::Duration::from_millis(5));
+            }
+        }
+
+        // Quarantining never evicts; cleanup evicts the least recently used
+        // item until the 30 bytes fit the quota
+        assert_eq!(names(&manager), vec!["new", "used", "old"]);
+        manager.cleanup_with(None).unwrap();
+        assert_eq!(names(&manager).len(), 3);
+        manager
+            .cleanup_with(Some(&ItemReferences::default()))
+            .unwrap();
+        assert_eq!(names(&manager), vec!["new", "used"]);
+
+        // Looking an item up counts as a use, and referenced items are kept
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        manager.get_by_original_path(&paths[1]).unwrap();
+        let extra = temp_dir.path().join("extra");
+        fs::write(&extra, b"0123456789").unwrap();
+        manager.quarantine(&extra).unwrap();
+
+        let mut references = ItemReferences::default();
+        let new = manager.get_by_original_path(&paths[2]).unwrap();
+        references.ids.insert(new.id);
+        manager.cleanup_with(Some(&references)).unwrap();
+        assert_eq!(names(&manager), vec!["extra", "new"]);
+    }
+
+    #[test]
+    fn test_quota_applies_per_quarantine_root() {
+        let (manager, temp_dir) = create_test_manager();
+        let manager = manager.with_quota(20);
+        for name in ["a", "b"] {
+            let path = temp_dir.path().join(name);
+            fs::write(&path, b"0123456789").unwrap();
+            manager.quarantine(&path).unwrap();
+        }
+
+        // An item in another volume's root, as `quarantine` would store it
+        let volume_root = temp_dir.path().join("volume").join(VOLUME_ROOT_NAME);
+        fs::create_dir_all(&volume_root).unwrap();
+        let object = volume_root.join("object");
+        fs::write(&object, b"0123456789").unwrap();
+        let manifest = manager.manifest().unwrap();
+        manifest
+            .insert(&QuarantinedItem {
+                id: "on-volume".to_string(),
+                path: object.clone(),
+                name: "c".to_string(),
+                original_path: temp_dir.path().join("volume").join("c"),
+                quarantine_date: Utc::now(),
+                size: 10,
+                is_directory: false,
+                content_hash: None,
+            })
+            .unwrap();
+        assert_eq!(manager.root_of(&object), volume_root);
+
+        // 30 bytes in total, but each root is within its quota
+        let stats = manager
+            .cleanup_with(Some(&ItemReferences::default()))
+            .unwrap();
+        assert_eq!(stats.items_removed, 0);
+        assert_eq!(manager.list().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_imports_legacy_sidecar_items() {
+        let (manager, temp_dir) = create_test_manager();
+        fs::create_dir_all(manager.base_path()).unwrap();
+
+        let stored = manager.base_path().join("20240101_120000_000_notes.txt");
+        fs::write(&stored, b"legacy").unwrap();
+        let original = temp_dir.path().join("notes.txt");
+        let sidecar = serde_json::json!({
+            "path": stored,
+            "name": "notes.txt",
+            "originalPath": original,
+            "quarantineDate": "2024-01-01T12:00:00Z",
+            "size": 6,
+            "isDirectory": false,
+        });
+        let sidecar_path = stored.with_extension("quarantine.json");
+        fs::write(&sidecar_path, sidecar.to_string()).unwrap();
+
+        let items = manager.list().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].original_path, original);
+        assert!(!sidecar_path.exists());
 
-        assert!(!quarantine_path.exists());
+        manager.restore(&items[0].id, None).unwrap();
+        assert_eq!(fs::read(&original).unwrap(), b"legacy");
     }
 }
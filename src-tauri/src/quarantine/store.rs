@@ -0,0 +1,250 @@
+//! Content-addressed object store
+//!
+//! Quarantined items are stored under `objects/<first two hex>/<sha256>` in
+//! a quarantine root. Files hash their bytes; directories hash a sorted
+//! listing of relative paths and entry hashes, so identical content is
+//! stored once and every restore can be checked against the object name.
+//!
+//! Moves fall back to copy, verify and delete when source and destination
+//! are on different filesystems.
+
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// Buffer size for hashing and copying
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Directory of the object store inside a quarantine root
+pub const OBJECTS_DIR: &str = "objects";
+
+/// Directory for items being moved into a quarantine root
+pub const STAGING_DIR: &str = "staging";
+
+/// Path of the object with the given content hash
+pub fn object_path(root: &Path, hash: &str) -> PathBuf {
+    root.join(OBJECTS_DIR).join(&hash[..2]).join(hash)
+}
+
+/// Fresh staging path inside a quarantine root
+pub fn staging_path(root: &Path) -> PathBuf {
+    root.join(STAGING_DIR).join(uuid::Uuid::new_v4().to_string())
+}
+
+/// SHA-256 of a file, directory tree or symlink, without following links
+pub fn content_hash(path: &Path) -> Result<String, String> {
+    let metadata = fs::symlink_metadata(path)
+        .map_err(|e| format!("Failed to read metadata for {}: {}", path.display(), e))?;
+
+    if metadata.file_type().is_symlink() {
+        let target = fs::read_link(path)
+            .map_err(|e| format!("Failed to read link {}: {}", path.display(), e))?;
+        let mut hasher = Sha256::new();
+        hasher.update(b"link\0");
+        hasher.update(target.to_string_lossy().as_bytes());
+        return Ok(hex::encode(hasher.finalize()));
+    }
+    if !metadata.is_dir() {
+        return hash_file(path);
+    }
+
+    let mut entries: Vec<_> = walkdir::WalkDir::new(path)
+        .min_depth(1)
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    entries.sort_by(|a, b| a.path().cmp(b.path()));
+
+    let mut hasher = Sha256::new();
+    hasher.update(b"tree\0");
+    for entry in entries {
+        let relative = entry.path().strip_prefix(path).unwrap_or(entry.path());
+        hasher.update(relative.to_string_lossy().as_bytes());
+        hasher.update(b"\0");
+        if entry.file_type().is_dir() {
+            hasher.update(b"dir\0");
+        } else {
+            hasher.update(content_hash(entry.path())?.as_bytes());
+            hasher.update(b"\0");
+        }
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn hash_file(path: &Path) -> Result<String, String> {
+    let mut file =
+        File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Move a file or directory, copying across filesystems
+///
+/// Used for quarantine and restore, and by WAL moves.
+///
+/// A cross-device move copies `src` to `dst`, checks the copy hashes the
+/// same as the source and only then removes the source. A failed check
+/// removes the copy and leaves the source untouched.
+pub fn move_path(src: &Path, dst: &Path) -> Result<(), String> {
+    match fs::rename(src, dst) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            tracing::info!(
+                source = %src.display(),
+                destination = %dst.display(),
+                "Moving across filesystems by copy"
+            );
+            let hash = content_hash(src)?;
+            copy_verified(src, dst, &hash)?;
+            remove_path(src)
+                .map_err(|e| format!("Copied but failed to remove {}: {}", src.display(), e))
+        }
+        Err(e) => Err(format!(
+            "Failed to move {} to {}: {}",
+            src.display(),
+            dst.display(),
+            e
+        )),
+    }
+}
+
+/// Copy `src` to `dst` and check the copy hashes to `expected`
+pub fn copy_verified(src: &Path, dst: &Path, expected: &str) -> Result<(), String> {
+    if let Err(e) = copy_path(src, dst) {
+        let _ = remove_path(dst);
+        return Err(format!(
+            "Failed to copy {} to {}: {}",
+            src.display(),
+            dst.display(),
+            e
+        ));
+    }
+    let actual = content_hash(dst)?;
+    if actual != expected {
+        let _ = remove_path(dst);
+        return Err(format!(
+            "Copy of {} does not match the original (expected {}, got {})",
+            src.display(),
+            expected,
+            actual
+        ));
+    }
+    Ok(())
+}
+
+/// Copy a file, directory tree or symlink, preserving links as links
+fn copy_path(src: &Path, dst: &Path) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(src)?;
+    if metadata.file_type().is_symlink() {
+        return copy_symlink(src, dst);
+    }
+    if !metadata.is_dir() {
+        fs::copy(src, dst)?;
+        return Ok(());
+    }
+
+    fs::create_dir(dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        copy_path(&entry.path(), &dst.join(entry.file_name()))?;
+    }
+    fs::set_permissions(dst, metadata.permissions())
+}
+
+#[cfg(unix)]
+fn copy_symlink(src: &Path, dst: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(src)?, dst)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(src: &Path, _dst: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("Cannot copy symlink {}", src.display()),
+    ))
+}
+
+/// Remove a file, symlink or directory tree
+pub fn remove_path(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Device a path lives on; `None` where devices can't be compared
+#[cfg(unix)]
+pub fn device_id(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    fs::symlink_metadata(path).ok().map(|m| m.dev())
+}
+
+#[cfg(not(unix))]
+pub fn device_id(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// Topmost ancestor of `path` on the same device, i.e. its mount point
+pub fn mount_point(path: &Path) -> Option<PathBuf> {
+    let device = device_id(path)?;
+    let mut mount = path;
+    for ancestor in path.ancestors().skip(1) {
+        if device_id(ancestor) != Some(device) {
+            break;
+        }
+        mount = ancestor;
+    }
+    Some(mount.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_tree_hash_depends_on_names_and_content() {
+        let temp = TempDir::new().unwrap();
+        let make = |name: &str, file: &str, content: &[u8]| {
+            let dir = temp.path().join(name);
+            fs::create_dir_all(dir.join("sub")).unwrap();
+            fs::write(dir.join("sub").join(file), content).unwrap();
+            content_hash(&dir).unwrap()
+        };
+
+        let a = make("a", "x.txt", b"hello");
+        assert_eq!(a, make("b", "x.txt", b"hello"));
+        assert_ne!(a, make("c", "y.txt", b"hello"));
+        assert_ne!(a, make("d", "x.txt", b"world"));
+    }
+
+    #[test]
+    fn test_copy_verified_copies_tree() {
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(src.join("nested")).unwrap();
+        fs::write(src.join("nested/file.txt"), b"data").unwrap();
+        let hash = content_hash(&src).unwrap();
+
+        let dst = temp.path().join("dst");
+        copy_verified(&src, &dst, &hash).unwrap();
+        assert_eq!(fs::read(dst.join("nested/file.txt")).unwrap(), b"data");
+
+        // A wrong expectation removes the copy
+        let other = temp.path().join("other");
+        assert!(copy_verified(&src, &other, "0000").is_err());
+        assert!(!other.exists());
+    }
+}
@@ -0,0 +1,332 @@
+//! Shared content hashing
+//!
+//! SHA-256 digests are cached in SQLite keyed by the file's identity
+//! (device and inode where available, the path elsewhere) together with its
+//! size and modification time, so a file is only read again after it
+//! changes. Batches are hashed on the rayon pool.
+//!
+//! Large files also get a cheap BLAKE3 pre-hash of their size, head and
+//! tail, used to rule out most non-matching files before anything is read
+//! in full.
+
+use rayon::prelude::*;
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File, Metadata};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::{Duration, SystemTime};
+
+/// Read buffer for full hashes
+const BUFFER_SIZE: usize = 256 * 1024;
+
+/// Bytes read from each end of a file for its pre-hash
+pub const QUICK_HASH_CHUNK: u64 = 64 * 1024;
+
+/// How long a connection waits on another thread's write
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+static GLOBAL: LazyLock<HashService> = LazyLock::new(|| {
+    let Some(dir) = dirs::cache_dir().map(|p| p.join("com.sentinel.app")) else {
+        return HashService { db_path: None };
+    };
+    let opened = fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create cache directory: {}", e))
+        .and_then(|_| HashService::open(&dir.join("hashes.db")));
+    match opened {
+        Ok(service) => service,
+        Err(e) => {
+            tracing::warn!(error = %e, "Hash cache unavailable, hashing without it");
+            HashService { db_path: None }
+        }
+    }
+});
+
+/// Content hasher with a persistent digest cache
+#[derive(Debug, Clone)]
+pub struct HashService {
+    /// Cache database; None hashes without caching
+    db_path: Option<PathBuf>,
+}
+
+/// Identity and version of a file as used for cache lookups
+struct FileStamp {
+    key: String,
+    size: u64,
+    mtime: i64,
+}
+
+impl FileStamp {
+    fn of(path: &Path, metadata: &Metadata) -> Self {
+        Self {
+            key: file_key(path, metadata),
+            size: metadata.len(),
+            mtime: metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map(|d| d.as_nanos() as i64)
+                .unwrap_or(0),
+        }
+    }
+}
+
+impl HashService {
+    /// The service shared by the whole app, caching under the user cache dir
+    pub fn global() -> &'static HashService {
+        &GLOBAL
+    }
+
+    /// Open or create a digest cache at `db_path`
+    pub fn open(db_path: &Path) -> Result<Self, String> {
+        let service = Self {
+            db_path: Some(db_path.to_path_buf()),
+        };
+        service
+            .conn()?
+            .execute_batch(
+                r#"
+                PRAGMA journal_mode = WAL;
+
+                CREATE TABLE IF NOT EXISTS digests (
+                    file_key TEXT PRIMARY KEY,
+                    size INTEGER NOT NULL,
+                    mtime INTEGER NOT NULL,
+                    sha256 TEXT NOT NULL
+                );
+                "#,
+            )
+            .map_err(|e| format!("Failed to initialize hash cache: {}", e))?;
+        Ok(service)
+    }
+
+    fn conn(&self) -> Result<Connection, String> {
+        let db_path = self.db_path.as_ref().ok_or("Hash cache disabled")?;
+        let conn = Connection::open(db_path)
+            .map_err(|e| format!("Failed to open hash cache: {}", e))?;
+        conn.busy_timeout(BUSY_TIMEOUT)
+            .map_err(|e| format!("Failed to configure hash cache: {}", e))?;
+        Ok(conn)
+    }
+
+    /// SHA-256 of a file's content, from the cache when the file is unchanged
+    pub fn sha256(&self, path: &Path) -> Result<String, String> {
+        self.sha256_many(&[path.to_path_buf()])
+            .pop()
+            .unwrap_or_else(|| Err("No hash computed".to_string()))
+    }
+
+    /// SHA-256 of many files, in input order
+    ///
+    /// Cached digests are looked up together, the rest are hashed in
+    /// parallel and stored in one transaction.
+    pub fn sha256_many(&self, paths: &[PathBuf]) -> Vec<Result<String, String>> {
+        let stamps: Vec<Result<FileStamp, String>> = paths
+            .iter()
+            .map(|path| {
+                fs::metadata(path)
+                    .map(|m| FileStamp::of(path, &m))
+                    .map_err(|e| format!("Failed to read metadata for {}: {}", path.display(), e))
+            })
+            .collect();
+
+        let conn = self.db_path.as_ref().and_then(|_| match self.conn() {
+            Ok(conn) => Some(conn),
+            Err(e) => {
+                tracing::warn!(error = %e, "Hashing without cache");
+                None
+            }
+        });
+        let cached: Vec<Option<String>> = stamps
+            .iter()
+            .map(|stamp| match (stamp, &conn) {
+                (Ok(stamp), Some(conn)) => lookup(conn, stamp),
+                _ => None,
+            })
+            .collect();
+
+        let results: Vec<Result<String, String>> = paths
+            .par_iter()
+            .zip(&stamps)
+            .zip(&cached)
+            .map(|((path, stamp), cached)| match (stamp, cached) {
+                (Err(e), _) => Err(e.clone()),
+                (Ok(_), Some(digest)) => Ok(digest.clone()),
+                (Ok(_), None) => sha256_file(path),
+            })
+            .collect();
+
+        if let Some(mut conn) = conn {
+            let fresh = stamps
+                .iter()
+                .zip(&cached)
+                .zip(&results)
+                .filter_map(|((stamp, cached), result)| match (stamp, cached, result) {
+                    (Ok(stamp), None, Ok(digest)) => Some((stamp, digest)),
+                    _ => None,
+                });
+            if let Err(e) = store(&mut conn, fresh) {
+                tracing::warn!(error = %e, "Failed to update hash cache");
+            }
+        }
+
+        results
+    }
+}
+
+fn lookup(conn: &Connection, stamp: &FileStamp) -> Option<String> {
+    conn.query_row(
+        "SELECT sha256 FROM digests WHERE file_key = ?1 AND size = ?2 AND mtime = ?3",
+        params![stamp.key, stamp.size as i64, stamp.mtime],
+        |row| row.get(0),
+    )
+    .optional()
+    .unwrap_or(None)
+}
+
+fn store<'a>(
+    conn: &mut Connection,
+    digests: impl Iterator<Item = (&'a FileStamp, &'a String)>,
+) -> Result<(), String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to write hash cache: {}", e))?;
+    {
+        let mut stmt = tx
+            .prepare(
+                "INSERT OR REPLACE INTO digests (file_key, size, mtime, sha256)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )
+            .map_err(|e| format!("Failed to write hash cache: {}", e))?;
+        for (stamp, digest) in digests {
+            stmt.execute(params![stamp.key, stamp.size as i64, stamp.mtime, digest])
+                .map_err(|e| format!("Failed to write hash cache: {}", e))?;
+        }
+    }
+    tx.commit()
+        .map_err(|e| format!("Failed to write hash cache: {}", e))
+}
+
+/// SHA-256 of a file's content, read in full
+pub fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file =
+        File::open(path).map_err(|e| format!("Failed to open file {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to read file {}: {}", path.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// BLAKE3 pre-hash of a file's size and its first and last
+/// `QUICK_HASH_CHUNK` bytes; files up to two chunks are hashed whole
+///
+/// Equal files always have equal pre-hashes, so differing pre-hashes rule
+/// out a match without reading the whole file.
+pub fn quick_hash(path: &Path) -> Result<String, String> {
+    let read_err = |e: std::io::Error| format!("Failed to read file {}: {}", path.display(), e);
+
+    let mut file =
+        File::open(path).map_err(|e| format!("Failed to open file {}: {}", path.display(), e))?;
+    let size = file.metadata().map_err(read_err)?.len();
+
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&size.to_le_bytes());
+    if size <= QUICK_HASH_CHUNK * 2 {
+        let mut content = Vec::with_capacity(size as usize);
+        file.read_to_end(&mut content).map_err(read_err)?;
+        hasher.update(&content);
+    } else {
+        let mut buffer = vec![0u8; QUICK_HASH_CHUNK as usize];
+        file.read_exact(&mut buffer).map_err(read_err)?;
+        hasher.update(&buffer);
+        file.seek(SeekFrom::End(-(QUICK_HASH_CHUNK as i64)))
+            .map_err(read_err)?;
+        file.read_exact(&mut buffer).map_err(read_err)?;
+        hasher.update(&buffer);
+    }
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Pre-hashes of many files in parallel, in input order
+pub fn quick_hash_many(paths: &[PathBuf]) -> Vec<Result<String, String>> {
+    paths.par_iter().map(|path| quick_hash(path)).collect()
+}
+
+#[cfg(unix)]
+fn file_key(_path: &Path, metadata: &Metadata) -> String {
+    use std::os::unix::fs::MetadataExt;
+    format!("{}:{}", metadata.dev(), metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn file_key(path: &Path, _metadata: &Metadata) -> String {
+    path.to_string_lossy().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_cached_digest_follows_file_changes() {
+        let temp = TempDir::new().unwrap();
+        let service = HashService::open(&temp.path().join("hashes.db")).unwrap();
+        let path = temp.path().join("a.txt");
+
+        fs::write(&path, b"hello").unwrap();
+        let first = service.sha256(&path).unwrap();
+        assert_eq!(first, sha256_file(&path).unwrap());
+
+        // A cached entry for the same stamp is returned as is
+        let stamp = FileStamp::of(&path, &fs::metadata(&path).unwrap());
+        assert_eq!(lookup(&service.conn().unwrap(), &stamp), Some(first.clone()));
+
+        fs::write(&path, b"hello, world").unwrap();
+        assert_ne!(service.sha256(&path).unwrap(), first);
+    }
+
+    #[test]
+    fn test_sha256_many_keeps_order_and_errors() {
+        let temp = TempDir::new().unwrap();
+        let service = HashService::open(&temp.path().join("hashes.db")).unwrap();
+        let a = temp.path().join("a.txt");
+        let b = temp.path().join("b.txt");
+        fs::write(&a, b"a").unwrap();
+        fs::write(&b, b"b").unwrap();
+
+        let results = service.sha256_many(&[b.clone(), temp.path().join("missing"), a.clone()]);
+        assert_eq!(results[0].as_ref().unwrap(), &sha256_file(&b).unwrap());
+        assert!(results[1].is_err());
+        assert_eq!(results[2].as_ref().unwrap(), &sha256_file(&a).unwrap());
+    }
+
+    #[test]
+    fn test_quick_hash_compares_size_head_and_tail() {
+        let temp = TempDir::new().unwrap();
+        let big = vec![1u8; 3 * QUICK_HASH_CHUNK as usize];
+        let mut middle_changed = big.clone();
+        middle_changed[QUICK_HASH_CHUNK as usize + 1] = 2;
+        let mut tail_changed = big.clone();
+        *tail_changed.last_mut().unwrap() = 2;
+
+        let write = |name: &str, content: &[u8]| {
+            let path = temp.path().join(name);
+            fs::write(&path, content).unwrap();
+            quick_hash(&path).unwrap()
+        };
+        let base = write("big", &big);
+        assert_eq!(base, write("middle", &middle_changed));
+        assert_ne!(base, write("tail", &tail_changed));
+        assert_ne!(write("small-a", b"abc"), write("small-b", b"abd"));
+    }
+}
@@ -0,0 +1,243 @@
+//! Perceptual image hashing
+//!
+//! Computes 64-bit aHash, dHash and pHash fingerprints from the same
+//! `image` decode path the thumbnailer uses. Visually similar images
+//! (resized, re-encoded, burst shots) have hashes a small Hamming distance
+//! apart. Hashes are cached next to thumbnails, keyed by path and mtime.
+
+use super::thumbnails::{get_cache_dir, get_file_mtime, RASTER_EXTENSIONS};
+use image::{imageops::FilterType, DynamicImage, GrayImage};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::f64::consts::PI;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Side of the grayscale image fed to the DCT for pHash
+const DCT_SIZE: usize = 32;
+/// Side of the low-frequency block kept from the DCT
+const DCT_KEEP: usize = 8;
+
+/// Which perceptual hash to compare images with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashAlgorithm {
+    /// Pixels brighter than the mean (fast, sensitive to edits)
+    Average,
+    /// Brightness gradient between neighbours (fast, robust to exposure)
+    Difference,
+    /// Low-frequency DCT coefficients (slowest, most robust)
+    #[default]
+    Perceptual,
+}
+
+/// All perceptual hashes for one image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageHashes {
+    pub average: u64,
+    pub difference: u64,
+    pub perceptual: u64,
+}
+
+impl ImageHashes {
+    /// Compute every hash from a decoded image
+    pub fn from_image(img: &DynamicImage) -> Self {
+        Self {
+            average: average_hash(img),
+            difference: difference_hash(img),
+            perceptual: perceptual_hash(img),
+        }
+    }
+
+    pub fn get(&self, algorithm: HashAlgorithm) -> u64 {
+        match algorithm {
+            HashAlgorithm::Average => self.average,
+            HashAlgorithm::Difference => self.difference,
+            HashAlgorithm::Perceptual => self.perceptual,
+        }
+    }
+
+    fn encode(&self) -> String {
+        format!(
+            "{:016x} {:016x} {:016x}",
+            self.average, self.difference, self.perceptual
+        )
+    }
+
+    fn decode(s: &str) -> Option<Self> {
+        let mut parts = s.split_whitespace().map(|p| u64::from_str_radix(p, 16));
+        Some(Self {
+            average: parts.next()?.ok()?,
+            difference: parts.next()?.ok()?,
+            perceptual: parts.next()?.ok()?,
+        })
+    }
+}
+
+/// Number of differing bits between two hashes
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Whether the image crate can decode this file (by extension)
+pub fn is_hashable(path: &Path) -> bool {
+    path.extension()
+        .map(|e| RASTER_EXTENSIONS.contains(&e.to_string_lossy().to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Get the hashes for an image file, computing and caching them if needed
+pub fn get_image_hashes(path: &Path) -> Result<ImageHashes, String> {
+    let cache_path = cache_path(path);
+    if let Some(cached) = cache_path
+        .as_ref()
+        .and_then(|p| fs::read_to_string(p).ok())
+        .and_then(|s| ImageHashes::decode(&s))
+    {
+        return Ok(cached);
+    }
+
+    let img = image::open(path)
+        .map_err(|e| format!("Failed to open image {}: {}", path.display(), e))?;
+    let hashes = ImageHashes::from_image(&img);
+
+    if let Some(cache_path) = cache_path {
+        if let Some(dir) = cache_path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let _ = fs::write(&cache_path, hashes.encode());
+    }
+
+    Ok(hashes)
+}
+
+/// Cache file for an image's hashes; changes when the file is modified
+fn cache_path(path: &Path) -> Option<PathBuf> {
+    let cache_dir = get_cache_dir()?;
+    let mtime = get_file_mtime(path)?;
+    let mut hasher = Sha256::new();
+    hasher.update(path.to_string_lossy().as_bytes());
+    let hash = hasher.finalize();
+    let hash_hex: String = hash.iter().take(8).map(|b| format!("{:02x}", b)).collect();
+    Some(cache_dir.join(format!("{}_{}.phash", hash_hex, mtime)))
+}
+
+fn grayscale(img: &DynamicImage, width: u32, height: u32) -> GrayImage {
+    img.resize_exact(width, height, FilterType::Triangle).to_luma8()
+}
+
+/// aHash: 8x8 grayscale, bit set where the pixel is brighter than the mean
+fn average_hash(img: &DynamicImage) -> u64 {
+    let pixels = grayscale(img, 8, 8).into_raw();
+    let mean = pixels.iter().map(|&p| p as u32).sum::<u32>() / pixels.len() as u32;
+    pixels
+        .iter()
+        .enumerate()
+        .fold(0u64, |hash, (i, &p)| if p as u32 > mean { hash | 1 << i } else { hash })
+}
+
+/// dHash: 9x8 grayscale, bit set where a pixel is brighter than its right neighbour
+fn difference_hash(img: &DynamicImage) -> u64 {
+    let gray = grayscale(img, 9, 8);
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            if gray.get_pixel(x, y)[0] > gray.get_pixel(x + 1, y)[0] {
+                hash |= 1 << (y * 8 + x);
+            }
+        }
+    }
+    hash
+}
+
+/// pHash: 32x32 grayscale DCT, bit set where a low-frequency coefficient
+/// is above the median of the non-DC coefficients
+fn perceptual_hash(img: &DynamicImage) -> u64 {
+    let gray = grayscale(img, DCT_SIZE as u32, DCT_SIZE as u32);
+
+    // cos((2x + 1) * u * pi / 2N) for the frequencies we keep
+    let mut cosines = [[0f64; DCT_SIZE]; DCT_KEEP];
+    for (u, row) in cosines.iter_mut().enumerate() {
+        for (x, c) in row.iter_mut().enumerate() {
+            *c = ((2 * x + 1) as f64 * u as f64 * PI / (2 * DCT_SIZE) as f64).cos();
+        }
+    }
+
+    // Separable DCT-II: rows, then columns, low frequencies only
+    let mut rows = [[0f64; DCT_KEEP]; DCT_SIZE];
+    for (y, row) in rows.iter_mut().enumerate() {
+        for (u, out) in row.iter_mut().enumerate() {
+            *out = (0..DCT_SIZE)
+                .map(|x| gray.get_pixel(x as u32, y as u32)[0] as f64 * cosines[u][x])
+                .sum();
+        }
+    }
+    let mut coefficients = [0f64; DCT_KEEP * DCT_KEEP];
+    for v in 0..DCT_KEEP {
+        for u in 0..DCT_KEEP {
+            coefficients[v * DCT_KEEP + u] = (0..DCT_SIZE).map(|y| rows[y][u] * cosines[v][y]).sum();
+        }
+    }
+
+    // The DC term is the overall brightness and would dominate the median
+    let mut ac: Vec<f64> = coefficients[1..].to_vec();
+    ac.sort_by(|a, b| a.total_cmp(b));
+    let median = ac[ac.len() / 2];
+
+    coefficients
+        .iter()
+        .enumerate()
+        .fold(0u64, |hash, (i, &c)| if c > median { hash | 1 << i } else { hash })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    /// A smooth pattern with some structure so every hash has signal
+    fn pattern(width: u32, height: u32, invert: bool) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+            let fx = x as f64 / width as f64;
+            let fy = y as f64 / height as f64;
+            let v = (((fx * 6.0).sin() + (fy * 4.0).cos() + ((fx + fy) * 13.0).sin() * 0.5) * 50.0
+                + 128.0) as u8;
+            let v = if invert { 255 - v } else { v };
+            Rgb([v, v / 2, 255 - v])
+        }))
+    }
+
+    #[test]
+    fn test_resized_copy_is_close() {
+        let original = ImageHashes::from_image(&pattern(400, 300, false));
+        let resized = ImageHashes::from_image(&pattern(400, 300, false).resize_exact(
+            133,
+            100,
+            FilterType::Lanczos3,
+        ));
+        let different = ImageHashes::from_image(&pattern(400, 300, true));
+
+        for algorithm in [
+            HashAlgorithm::Average,
+            HashAlgorithm::Difference,
+            HashAlgorithm::Perceptual,
+        ] {
+            let near = hamming_distance(original.get(algorithm), resized.get(algorithm));
+            let far = hamming_distance(original.get(algorithm), different.get(algorithm));
+            assert!(near <= 6, "{:?}: resized copy at distance {}", algorithm, near);
+            assert!(far >= 20, "{:?}: different image at distance {}", algorithm, far);
+        }
+    }
+
+    #[test]
+    fn test_cache_encoding_round_trips() {
+        let hashes = ImageHashes {
+            average: 1,
+            difference: u64::MAX,
+            perceptual: 0xdead_beef,
+        };
+        assert_eq!(ImageHashes::decode(&hashes.encode()), Some(hashes));
+        assert_eq!(ImageHashes::decode("garbage"), None);
+    }
+}
@@ -1,2 +1,4 @@
+pub mod hashing;
+pub mod image_hash;
 pub mod thumbnails;
 pub mod watcher;
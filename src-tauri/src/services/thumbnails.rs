@@ -11,8 +11,13 @@ use std::time::SystemTime;
 
 const DEFAULT_THUMBNAIL_SIZE: u32 = 96;
 
+/// Raster formats decoded with the image crate
+pub(crate) const RASTER_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "gif", "webp", "bmp", "ico", "tiff", "tif",
+];
+
 /// Get the cache directory for thumbnails
-fn get_cache_dir() -> Option<PathBuf> {
+pub(super) fn get_cache_dir() -> Option<PathBuf> {
     dirs::cache_dir().map(|p| p.join("com.sentinel.app").join("thumbnails"))
 }
 
@@ -26,7 +31,7 @@ fn get_cache_key(file_path: &str, size: u32, mtime: u64) -> String {
 }
 
 /// Get the modification time of a file as unix timestamp
-fn get_file_mtime(path: &Path) -> Option<u64> {
+pub(super) fn get_file_mtime(path: &Path) -> Option<u64> {
     fs::metadata(path)
         .ok()?
         .modified()
@@ -138,11 +143,21 @@ fn generate_video_thumbnail(path: &Path, size: u32) -> Result<Vec<u8>, String> {
     Ok(buffer.into_inner())
 }
 
-/// Generate thumbnail for a PDF file using ffmpeg (it supports PDF)
+/// Generate thumbnail for a PDF file from its first page (built-in renderer, no ffmpeg)
 fn generate_pdf_thumbnail(path: &Path, size: u32) -> Result<Vec<u8>, String> {
-    // ffmpeg can extract first page from PDF
-    // Alternatively, we could use a dedicated PDF library
-    generate_video_thumbnail(path, size)
+    // A high resolution so the page is scaled down to fit the thumbnail size
+    let options = crate::pdf::RenderOptions {
+        dpi: 300.0,
+        max_dimension: size,
+    };
+    let img = crate::pdf::PdfDocument::open(path)?.render_page(0, &options)?;
+
+    // Encode as PNG
+    let mut buffer = Cursor::new(Vec::new());
+    img.write_to(&mut buffer, ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode PDF thumbnail: {}", e))?;
+
+    Ok(buffer.into_inner())
 }
 
 /// Generate thumbnail for an SVG file using resvg
@@ -213,9 +228,7 @@ pub fn get_thumbnail(file_path: &str, size: Option<u32>) -> Result<String, Strin
     // Generate based on file type
     let thumbnail_data = match extension.as_str() {
         // Images
-        "jpg" | "jpeg" | "png" | "gif" | "webp" | "bmp" | "ico" | "tiff" | "tif" => {
-            generate_image_thumbnail(path, size)?
-        }
+        ext if RASTER_EXTENSIONS.contains(&ext) => generate_image_thumbnail(path, size)?,
         // SVG (vector graphics)
         "svg" => generate_svg_thumbnail(path, size)?,
         // Videos
@@ -234,7 +247,7 @@ pub fn get_thumbnail(file_path: &str, size: Option<u32>) -> Result<String, Strin
     Ok(STANDARD.encode(&thumbnail_data))
 }
 
-/// Clear the thumbnail cache
+/// Clear the thumbnail cache, including cached perceptual hashes
 pub fn clear_cache() -> Result<u64, String> {
     let cache_dir = get_cache_dir().ok_or("Failed to get cache directory")?;
 
@@ -244,7 +257,11 @@ pub fn clear_cache() -> Result<u64, String> {
 
     let mut count = 0u64;
     for entry in fs::read_dir(&cache_dir).map_err(|e| format!("Failed to read cache dir: {}", e))?.flatten() {
-        if entry.path().extension().map(|e| e == "webp" || e == "png").unwrap_or(false)
+        if entry
+            .path()
+            .extension()
+            .map(|e| e == "webp" || e == "png" || e == "phash")
+            .unwrap_or(false)
             && fs::remove_file(entry.path()).is_ok()
         {
             count += 1;
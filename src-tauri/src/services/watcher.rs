@@ -1,10 +1,14 @@
+use crate::ai::rules::DocumentContentSource;
+use crate::commands::vector::VectorSyncQueue;
+use crate::inbox::{process_file_event, InboxHandle};
+use notify::event::{ModifyKind, RenameMode};
 use notify::{EventKind, RecommendedWatcher, RecursiveMode};
 use notify_debouncer_full::{new_debouncer, DebouncedEvent, Debouncer, RecommendedCache};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager};
 
 /// Event payload sent to frontend
 #[derive(Clone, serde::Serialize)]
@@ -69,11 +73,7 @@ pub fn start_watcher(
         None,
         move |result: Result<Vec<DebouncedEvent>, Vec<notify::Error>>| {
             match result {
-                Ok(events) => {
-                    for event in events {
-                        handle_file_event(&app_clone, &event, &watched_folder);
-                    }
-                }
+                Ok(events) => handle_file_events(&app_clone, &events, &watched_folder),
                 Err(errors) => {
                     for error in errors {
                         eprintln!("Watcher error: {:?}", error);
@@ -126,11 +126,7 @@ pub fn add_watched_folder(
         None,
         move |result: Result<Vec<DebouncedEvent>, Vec<notify::Error>>| {
             match result {
-                Ok(events) => {
-                    for event in events {
-                        handle_file_event(&app_clone, &event, &watched_folder);
-                    }
-                }
+                Ok(events) => handle_file_events(&app_clone, &events, &watched_folder),
                 Err(errors) => {
                     for error in errors {
                         eprintln!("Watcher error: {:?}", error);
@@ -221,12 +217,64 @@ pub fn get_all_watching_paths(handle: &WatcherHandle) -> Vec<String> {
     }
 }
 
-/// Handle a file event
-fn handle_file_event(app: &AppHandle, event: &DebouncedEvent, watched_folder: &str) {
-    // Only handle create events for new files
+/// A file the watcher reports to inbox rules and the frontend
+struct NewFile {
+    path: PathBuf,
+    file_name: String,
+    size: u64,
+    /// Created in the folder (announced to the frontend), not renamed into it
+    created: bool,
+}
+
+/// Handle a batch of debounced events
+fn handle_file_events(app: &AppHandle, events: &[DebouncedEvent], watched_folder: &str) {
+    let mut new_files = Vec::new();
+    for event in events {
+        handle_file_event(app, event, watched_folder, &mut new_files);
+    }
+    if new_files.is_empty() {
+        return;
+    }
+
+    // Inbox rules and previews read file contents; keep them off the
+    // debouncer thread so later events are not held up
+    let app = app.clone();
+    let watched_folder = watched_folder.to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        process_new_files(&app, new_files, &watched_folder);
+    });
+}
+
+/// Handle a file event, collecting files for inbox rules and the frontend
+fn handle_file_event(
+    app: &AppHandle,
+    event: &DebouncedEvent,
+    watched_folder: &str,
+    new_files: &mut Vec<NewFile>,
+) {
+    // Keep the semantic index current, including deletions and renames away;
+    // embedding runs on the queue's own thread
+    if matches!(
+        event.kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    ) {
+        if let Some(queue) = app.try_state::<VectorSyncQueue>() {
+            for path in &event.paths {
+                queue.enqueue(path.clone());
+            }
+        }
+    }
+
+    // New files are announced to the frontend; files renamed into the folder
+    // only run through inbox rules. Content changes never re-run rules, so a
+    // file a rule already handled is not handled again when it is edited.
     let is_create = matches!(event.kind, EventKind::Create(_));
+    let is_rename_into = matches!(
+        event.kind,
+        EventKind::Modify(ModifyKind::Name(RenameMode::To | RenameMode::Both))
+    );
 
-    if !is_create {
+    if !is_create && !is_rename_into {
         return;
     }
 
@@ -287,20 +335,49 @@ fn handle_file_event(app: &AppHandle, event: &DebouncedEvent, watched_folder: &s
             continue;
         }
 
-        let extension = path
+        new_files.push(NewFile {
+            path: path.clone(),
+            file_name,
+            size: metadata.len(),
+            created: is_create,
+        });
+    }
+}
+
+/// Run inbox rules on new files and announce created files nobody claimed
+///
+/// Blocking; one content source is shared by the whole batch.
+fn process_new_files(app: &AppHandle, files: Vec<NewFile>, watched_folder: &str) {
+    let inbox = app.try_state::<InboxHandle>();
+    let content = DocumentContentSource::open_default();
+
+    for file in files {
+        // Apply inbox rules; a file claimed by a rule is not announced
+        if let Some(inbox) = &inbox {
+            if process_file_event(app, inbox.inner(), &file.path, watched_folder, &content) {
+                continue;
+            }
+        }
+
+        if !file.created {
+            continue;
+        }
+
+        let extension = file
+            .path
             .extension()
             .map(|e| e.to_string_lossy().to_string());
 
         // Read content preview (first 4KB for text files) - pass watched_folder for security check
-        let content_preview = read_content_preview(path, &extension, watched_folder);
+        let content_preview = read_content_preview(&file.path, &extension, watched_folder);
 
         let file_event = FileChangeEvent {
             id: uuid::Uuid::new_v4().to_string(),
             event_type: "created".to_string(),
-            path: path.to_string_lossy().to_string(),
-            file_name,
+            path: file.path.to_string_lossy().to_string(),
+            file_name: file.file_name,
             extension,
-            size: metadata.len(),
+            size: file.size,
             content_preview,
             watched_folder: watched_folder.to_string(),
         };
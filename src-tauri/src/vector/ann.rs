@@ -0,0 +1,686 @@
+//! Approximate Nearest-Neighbour Search
+//!
+//! A linear cosine scan is fine for a few thousand documents but becomes the
+//! bottleneck past ~100k. [`AnnIndex`] keeps normalized embeddings keyed by
+//! path and answers top-k queries:
+//! - below `exact_threshold` documents with an exact scan
+//! - above it with an HNSW graph (Malkov & Yashunin, 2016), built once the
+//!   threshold is crossed and maintained incrementally afterwards
+//!
+//! Removals are tombstones: a removed node keeps its vector and links so
+//! searches can still route through it, but it is never returned. The graph
+//! is rebuilt when more than a quarter of its nodes are dead.
+//!
+//! Recall and latency against the exact scan can be measured with:
+//! `cargo test --release ann_benchmark -- --ignored --nocapture`
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::hash::Hash;
+
+/// Number of documents from which searches use the HNSW graph
+pub const DEFAULT_EXACT_THRESHOLD: usize = 10_000;
+
+/// HNSW tuning parameters
+#[derive(Debug, Clone, Copy)]
+pub struct HnswParams {
+    /// Links per node on upper layers (twice as many on layer 0)
+    pub m: usize,
+    /// Candidate list size while inserting
+    pub ef_construction: usize,
+    /// Minimum candidate list size while searching
+    pub ef_search: usize,
+}
+
+impl Default for HnswParams {
+    fn default() -> Self {
+        Self {
+            m: 16,
+            ef_construction: 100,
+            ef_search: 64,
+        }
+    }
+}
+
+/// Nearest-neighbour index over normalized embeddings
+pub struct AnnIndex<K> {
+    params: HnswParams,
+    exact_threshold: usize,
+    /// Normalized vectors by node id, kept for removed nodes until compaction
+    vectors: Vec<Vec<f32>>,
+    /// Key of each node id; None once removed
+    keys: Vec<Option<K>>,
+    /// Live node id of each key
+    ids: HashMap<K, u32>,
+    /// Graph over `vectors`, present once the index crossed the threshold
+    graph: Option<Hnsw>,
+}
+
+impl<K: Clone + Eq + Hash> Default for AnnIndex<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Clone + Eq + Hash> AnnIndex<K> {
+    /// Create an index with default parameters
+    pub fn new() -> Self {
+        Self::with_params(HnswParams::default(), DEFAULT_EXACT_THRESHOLD)
+    }
+
+    /// Create an index that switches to the graph at `exact_threshold` documents
+    pub fn with_params(params: HnswParams, exact_threshold: usize) -> Self {
+        Self {
+            params,
+            exact_threshold,
+            vectors: Vec::new(),
+            keys: Vec::new(),
+            ids: HashMap::new(),
+            graph: None,
+        }
+    }
+
+    /// Number of live documents
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Check if the index is empty
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Whether searches currently use the HNSW graph
+    pub fn uses_graph(&self) -> bool {
+        self.graph.is_some() && self.len() >= self.exact_threshold
+    }
+
+    /// Insert or replace the embedding of a key
+    pub fn insert(&mut self, key: K, embedding: &[f32]) {
+        self.remove(&key);
+
+        let id = self.vectors.len() as u32;
+        self.vectors.push(normalize(embedding));
+        self.keys.push(Some(key.clone()));
+        self.ids.insert(key, id);
+
+        match &mut self.graph {
+            Some(graph) => graph.insert(id, &self.vectors),
+            None if self.ids.len() >= self.exact_threshold => self.rebuild(),
+            None => {}
+        }
+    }
+
+    /// Remove a key; returns false if it was not indexed
+    pub fn remove(&mut self, key: &K) -> bool {
+        let Some(id) = self.ids.remove(key) else {
+            return false;
+        };
+        self.keys[id as usize] = None;
+        if let Some(graph) = &mut self.graph {
+            graph.remove(id);
+        }
+
+        let dead = self.vectors.len() - self.ids.len();
+        if dead > self.vectors.len() / 4 {
+            self.compact();
+        }
+        true
+    }
+
+    /// Remove everything
+    pub fn clear(&mut self) {
+        self.vectors.clear();
+        self.keys.clear();
+        self.ids.clear();
+        self.graph = None;
+    }
+
+    /// Cosine similarity between a key's embedding and a query
+    pub fn similarity(&self, key: &K, query: &[f32]) -> Option<f32> {
+        let id = *self.ids.get(key)?;
+        Some(dot(&self.vectors[id as usize], &normalize(query)))
+    }
+
+    /// The `k` most similar keys to `query`, best first, with cosine scores
+    pub fn search(&self, query: &[f32], k: usize) -> Vec<(K, f32)> {
+        if k == 0 || self.is_empty() {
+            return Vec::new();
+        }
+        match &self.graph {
+            Some(graph) if self.uses_graph() => {
+                let query = normalize(query);
+                let ef = self.params.ef_search.max(k);
+                graph
+                    .search(&query, k, ef, &self.vectors)
+                    .into_iter()
+                    .filter_map(|(id, score)| Some((self.keys[id as usize].clone()?, score)))
+                    .collect()
+            }
+            _ => self.search_exact(query, k),
+        }
+    }
+
+    /// The `k` most similar keys by exhaustive scan
+    pub fn search_exact(&self, query: &[f32], k: usize) -> Vec<(K, f32)> {
+        let query = normalize(query);
+        let mut heap: BinaryHeap<Reverse<Scored>> = BinaryHeap::with_capacity(k + 1);
+        for (id, vector) in self.vectors.iter().enumerate() {
+            if self.keys[id].is_none() {
+                continue;
+            }
+            heap.push(Reverse(Scored(dot(&query, vector), id as u32)));
+            if heap.len() > k {
+                heap.pop();
+            }
+        }
+
+        let mut results: Vec<Scored> = heap.into_iter().map(|Reverse(s)| s).collect();
+        results.sort_by(|a, b| b.cmp(a));
+        results
+            .into_iter()
+            .filter_map(|Scored(score, id)| Some((self.keys[id as usize].clone()?, score)))
+            .collect()
+    }
+
+    /// Drop tombstones and renumber nodes
+    fn compact(&mut self) {
+        let mut vectors = Vec::with_capacity(self.ids.len());
+        let mut keys = Vec::with_capacity(self.ids.len());
+        self.ids.clear();
+        for (vector, key) in self.vectors.drain(..).zip(self.keys.drain(..)) {
+            if let Some(key) = key {
+                self.ids.insert(key.clone(), vectors.len() as u32);
+                vectors.push(vector);
+                keys.push(Some(key));
+            }
+        }
+        self.vectors = vectors;
+        self.keys = keys;
+
+        if self.graph.is_some() {
+            self.rebuild();
+        }
+    }
+
+    /// Build the graph from all live vectors
+    fn rebuild(&mut self) {
+        let mut graph = Hnsw::new(self.params);
+        for (id, key) in self.keys.iter().enumerate() {
+            if key.is_some() {
+                graph.insert(id as u32, &self.vectors);
+            }
+        }
+        self.graph = Some(graph);
+    }
+}
+
+/// Similarity score of a node, ordered by score
+#[derive(Debug, Clone, Copy)]
+struct Scored(f32, u32);
+
+impl PartialEq for Scored {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Scored {}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0).then(self.1.cmp(&other.1))
+    }
+}
+
+/// Hierarchical navigable small-world graph over externally stored vectors
+struct Hnsw {
+    params: HnswParams,
+    /// Links of each node id per layer; empty for ids not in the graph
+    links: Vec<Vec<Vec<u32>>>,
+    /// Removed nodes, traversed but never returned or linked to
+    removed: HashSet<u32>,
+    entry: Option<u32>,
+    max_layer: usize,
+    /// Level generator multiplier, 1 / ln(M)
+    level_mult: f64,
+    rng: u64,
+}
+
+impl Hnsw {
+    fn new(params: HnswParams) -> Self {
+        Self {
+            params,
+            links: Vec::new(),
+            removed: HashSet::new(),
+            entry: None,
+            max_layer: 0,
+            level_mult: 1.0 / (params.m.max(2) as f64).ln(),
+            rng: 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+
+    /// Deterministic xorshift so that rebuilt graphs are reproducible
+    fn random_level(&mut self) -> usize {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        let uniform = ((self.rng >> 11) as f64 + 1.0) / (1u64 << 53) as f64;
+        (-uniform.ln() * self.level_mult).floor() as usize
+    }
+
+    fn max_links(&self, layer: usize) -> usize {
+        if layer == 0 {
+            self.params.m * 2
+        } else {
+            self.params.m
+        }
+    }
+
+    fn insert(&mut self, id: u32, vectors: &[Vec<f32>]) {
+        let vector = &vectors[id as usize];
+        let level = self.random_level();
+        if self.links.len() <= id as usize {
+            self.links.resize(id as usize + 1, Vec::new());
+        }
+        self.links[id as usize] = vec![Vec::new(); level + 1];
+
+        let Some(mut entry) = self.entry else {
+            self.entry = Some(id);
+            self.max_layer = level;
+            return;
+        };
+
+        // Greedy descent through layers above the new node's level
+        for layer in (level + 1..=self.max_layer).rev() {
+            entry = self.greedy(vector, entry, layer, vectors);
+        }
+
+        let mut entries = vec![entry];
+        for layer in (0..=level.min(self.max_layer)).rev() {
+            let candidates =
+                self.search_layer(vector, &entries, self.params.ef_construction, layer, vectors);
+            let neighbours = self.select(&candidates, self.params.m, vectors);
+
+            for &neighbour in &neighbours {
+                self.links[neighbour as usize][layer].push(id);
+                if self.links[neighbour as usize][layer].len() > self.max_links(layer) {
+                    self.shrink(neighbour, layer, vectors);
+                }
+            }
+            self.links[id as usize][layer] = neighbours;
+            if !candidates.is_empty() {
+                entries = candidates.iter().map(|s| s.1).collect();
+            }
+        }
+
+        if level > self.max_layer {
+            self.max_layer = level;
+            self.entry = Some(id);
+        }
+    }
+
+    /// Mark a node as removed and move the entry point off it, to the live
+    /// node with the most layers
+    fn remove(&mut self, id: u32) {
+        self.removed.insert(id);
+        if self.entry != Some(id) {
+            return;
+        }
+        let replacement = self
+            .links
+            .iter()
+            .enumerate()
+            .filter(|(n, layers)| !layers.is_empty() && !self.removed.contains(&(*n as u32)))
+            .max_by_key(|(_, layers)| layers.len());
+        match replacement {
+            Some((n, layers)) => {
+                self.entry = Some(n as u32);
+                self.max_layer = layers.len() - 1;
+            }
+            None => {
+                self.entry = None;
+                self.max_layer = 0;
+            }
+        }
+    }
+
+    /// Re-select a node's links after it exceeded the maximum
+    fn shrink(&mut self, node: u32, layer: usize, vectors: &[Vec<f32>]) {
+        let base = &vectors[node as usize];
+        let mut candidates: Vec<Scored> = self.links[node as usize][layer]
+            .iter()
+            .map(|&n| Scored(dot(base, &vectors[n as usize]), n))
+            .collect();
+        candidates.sort_by(|a, b| b.cmp(a));
+        let keep = self.select(&candidates, self.max_links(layer), vectors);
+        self.links[node as usize][layer] = keep;
+    }
+
+    /// Neighbour selection heuristic: prefer candidates that are closer to
+    /// the base node than to any already selected neighbour, which keeps links
+    /// spread across clusters. Fills up with the closest rejected ones.
+    /// `candidates` must be sorted best first.
+    fn select(&self, candidates: &[Scored], m: usize, vectors: &[Vec<f32>]) -> Vec<u32> {
+        let mut selected: Vec<u32> = Vec::with_capacity(m);
+        let mut rejected: Vec<u32> = Vec::new();
+        for &Scored(score, id) in candidates {
+            if selected.len() >= m {
+                break;
+            }
+            let vector = &vectors[id as usize];
+            let diverse = selected
+                .iter()
+                .all(|&s| dot(vector, &vectors[s as usize]) < score);
+            if diverse {
+                selected.push(id);
+            } else {
+                rejected.push(id);
+            }
+        }
+        for id in rejected {
+            if selected.len() >= m {
+                break;
+            }
+            selected.push(id);
+        }
+        selected
+    }
+
+    /// Follow the best link until no neighbour is closer
+    fn greedy(&self, query: &[f32], mut current: u32, layer: usize, vectors: &[Vec<f32>]) -> u32 {
+        let mut best = dot(query, &vectors[current as usize]);
+        loop {
+            let mut improved = false;
+            for &n in &self.links[current as usize][layer] {
+                let score = dot(query, &vectors[n as usize]);
+                if score > best {
+                    best = score;
+                    current = n;
+                    improved = true;
+                }
+            }
+            if !improved {
+                return current;
+            }
+        }
+    }
+
+    /// Best-first search of one layer; returns up to `ef` live nodes, best
+    /// first. Removed nodes are expanded at their real distance but left out
+    /// of the results.
+    fn search_layer(
+        &self,
+        query: &[f32],
+        entries: &[u32],
+        ef: usize,
+        layer: usize,
+        vectors: &[Vec<f32>],
+    ) -> Vec<Scored> {
+        let mut visited: HashSet<u32> = entries.iter().copied().collect();
+        // Max-heap of nodes to expand, min-heap of current results
+        let mut candidates: BinaryHeap<Scored> = BinaryHeap::new();
+        let mut results: BinaryHeap<Reverse<Scored>> = BinaryHeap::new();
+
+        for &e in entries {
+            let scored = Scored(dot(query, &vectors[e as usize]), e);
+            candidates.push(scored);
+            if !self.removed.contains(&e) {
+                results.push(Reverse(scored));
+            }
+        }
+        while results.len() > ef {
+            results.pop();
+        }
+
+        while let Some(current) = candidates.pop() {
+            let worst = results.peek().map_or(f32::MIN, |Reverse(s)| s.0);
+            if current.0 < worst && results.len() >= ef {
+                break;
+            }
+            let Some(layers) = self.links.get(current.1 as usize) else {
+                continue;
+            };
+            let Some(neighbours) = layers.get(layer) else {
+                continue;
+            };
+            for &n in neighbours {
+                if !visited.insert(n) {
+                    continue;
+                }
+                let scored = Scored(dot(query, &vectors[n as usize]), n);
+                let worst = results.peek().map_or(f32::MIN, |Reverse(s)| s.0);
+                if results.len() < ef || scored.0 > worst {
+                    candidates.push(scored);
+                    if !self.removed.contains(&n) {
+                        results.push(Reverse(scored));
+                        if results.len() > ef {
+                            results.pop();
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut found: Vec<Scored> = results.into_iter().map(|Reverse(s)| s).collect();
+        found.sort_by(|a, b| b.cmp(a));
+        found
+    }
+
+    fn search(&self, query: &[f32], k: usize, ef: usize, vectors: &[Vec<f32>]) -> Vec<(u32, f32)> {
+        let Some(mut entry) = self.entry else {
+            return Vec::new();
+        };
+        for layer in (1..=self.max_layer).rev() {
+            entry = self.greedy(query, entry, layer, vectors);
+        }
+        self.search_layer(query, &[entry], ef, 0, vectors)
+            .into_iter()
+            .take(k)
+            .map(|Scored(score, id)| (id, score))
+            .collect()
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn normalize(v: &[f32]) -> Vec<f32> {
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        return v.to_vec();
+    }
+    v.iter().map(|x| x / norm).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    /// Deterministic clustered vectors, roughly like embeddings of file names
+    fn clustered_vectors(count: usize, dim: usize, clusters: usize) -> Vec<Vec<f32>> {
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 40) as f32 / (1u64 << 24) as f32 - 0.5
+        };
+        let centers: Vec<Vec<f32>> = (0..clusters)
+            .map(|_| (0..dim).map(|_| next()).collect())
+            .collect();
+        (0..count)
+            .map(|i| {
+                centers[i % clusters]
+                    .iter()
+                    .map(|c| c + next() * 0.6)
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn build(vectors: &[Vec<f32>], threshold: usize) -> AnnIndex<usize> {
+        let mut index = AnnIndex::with_params(HnswParams::default(), threshold);
+        for (i, v) in vectors.iter().enumerate() {
+            index.insert(i, v);
+        }
+        index
+    }
+
+    fn recall_at(index: &AnnIndex<usize>, queries: &[Vec<f32>], k: usize) -> f64 {
+        let mut hits = 0;
+        for q in queries {
+            let exact: HashSet<usize> = index.search_exact(q, k).into_iter().map(|r| r.0).collect();
+            hits += index
+                .search(q, k)
+                .into_iter()
+                .filter(|r| exact.contains(&r.0))
+                .count();
+        }
+        hits as f64 / (queries.len() * k) as f64
+    }
+
+    #[test]
+    fn test_small_index_is_exact() {
+        let vectors = clustered_vectors(200, 16, 8);
+        let index = build(&vectors, DEFAULT_EXACT_THRESHOLD);
+        assert!(!index.uses_graph());
+
+        let results = index.search(&vectors[5], 3);
+        assert_eq!(results[0].0, 5);
+        assert!((results[0].1 - 1.0).abs() < 1e-5);
+        assert!(results.windows(2).all(|w| w[0].1 >= w[1].1));
+    }
+
+    #[test]
+    fn test_graph_recall() {
+        let vectors = clustered_vectors(3_000, 32, 30);
+        let index = build(&vectors, 500);
+        assert!(index.uses_graph());
+
+        let queries = clustered_vectors(50, 32, 30);
+        let recall = recall_at(&index, &queries, 10);
+        assert!(recall >= 0.9, "recall@10 = {}", recall);
+    }
+
+    #[test]
+    fn test_remove_and_replace() {
+        let vectors = clustered_vectors(1_000, 16, 10);
+        let mut index = build(&vectors, 100);
+
+        assert!(index.remove(&7));
+        assert!(!index.remove(&7));
+        assert!(index.search(&vectors[7], 5).iter().all(|r| r.0 != 7));
+
+        // Replacing moves the key to the new vector
+        index.insert(8, &vectors[9]);
+        assert_eq!(index.len(), 999);
+        assert!((index.similarity(&8, &vectors[9]).unwrap() - 1.0).abs() < 1e-5);
+
+        // Removing many keys compacts and rebuilds without losing the rest
+        for i in 0..400 {
+            index.remove(&i);
+        }
+        assert_eq!(index.len(), 600);
+        let hit = index.search(&vectors[900], 1);
+        assert_eq!(hit[0].0, 900);
+    }
+
+    #[test]
+    fn test_removing_the_entry_point() {
+        let vectors = clustered_vectors(200, 16, 4);
+        let mut index = build(&vectors, 100);
+
+        // Remove entry points one by one, staying under the compaction limit
+        for _ in 0..40 {
+            let entry = index.graph.as_ref().unwrap().entry.unwrap();
+            let removed = index.keys[entry as usize].unwrap();
+            assert!(index.remove(&removed));
+
+            let hits = index.search(&vectors[removed], 5);
+            assert_eq!(hits.len(), 5);
+            assert!(hits.iter().all(|(key, _)| *key != removed));
+        }
+        assert!(index.uses_graph());
+    }
+
+    #[test]
+    fn test_recall_after_bulk_removals() {
+        let vectors = clustered_vectors(3_000, 32, 30);
+        // A narrow search, so results depend on routing through tombstones
+        let params = HnswParams {
+            m: 8,
+            ef_construction: 100,
+            ef_search: 10,
+        };
+        let mut index = AnnIndex::with_params(params, 500);
+        for (i, v) in vectors.iter().enumerate() {
+            index.insert(i, v);
+        }
+
+        // Thin out 8 of the 30 clusters, staying under the compaction limit
+        let thinned = |i: usize| i % 30 < 8;
+        for i in (0..3_000).filter(|&i| thinned(i) && i / 30 % 10 != 0) {
+            assert!(index.remove(&i));
+        }
+        assert!(index.uses_graph());
+        assert_eq!(index.vectors.len(), 3_000);
+
+        let queries: Vec<Vec<f32>> = clustered_vectors(60, 32, 30)
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| thinned(*i))
+            .map(|(_, q)| q)
+            .collect();
+        let recall = recall_at(&index, &queries, 10);
+        assert!(recall >= 0.9, "recall@10 = {}", recall);
+    }
+
+    /// Recall and latency of HNSW against the exact scan at embedding scale.
+    /// Run with: cargo test --release ann_benchmark -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn ann_benchmark() {
+        let dim = 384;
+        for count in [10_000, 100_000] {
+            let vectors = clustered_vectors(count, dim, count / 50);
+            let queries = clustered_vectors(200, dim, count / 50);
+
+            let start = Instant::now();
+            let index = build(&vectors, 0);
+            let build_time = start.elapsed();
+
+            let start = Instant::now();
+            for q in &queries {
+                index.search_exact(q, 10);
+            }
+            let exact = start.elapsed() / queries.len() as u32;
+
+            let start = Instant::now();
+            for q in &queries {
+                index.search(q, 10);
+            }
+            let approx = start.elapsed() / queries.len() as u32;
+
+            println!(
+                "n={:>7} build={:>8.2?} exact={:>9.2?}/q hnsw={:>9.2?}/q recall@10={:.3}",
+                count,
+                build_time,
+                exact,
+                approx,
+                recall_at(&index, &queries, 10)
+            );
+        }
+    }
+}
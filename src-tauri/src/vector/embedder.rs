@@ -3,7 +3,7 @@
 //! Handles fastembed integration for generating text embeddings.
 //! Uses the AllMiniLmL6V2 model by default for fast, quality embeddings.
 
-use super::{VectorConfig, VectorDocument, VectorIndex};
+use super::{FileStamp, VectorConfig, VectorDocument, VectorIndex};
 use fastembed::{InitOptions, TextEmbedding};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -162,6 +162,160 @@ impl VectorIndex {
         Ok(indexed_count)
     }
 
+    /// Insert a precomputed embedding, assigning semantic tags
+    pub fn insert_embedding(&mut self, path: PathBuf, text: String, embedding: Vec<f32>) {
+        let tags = self.compute_tags(&embedding);
+        self.insert_document(VectorDocument {
+            path,
+            text,
+            embedding,
+            tags,
+        });
+    }
+
+    /// Index files, embedding only those not already in the attached store
+    ///
+    /// Unchanged files (same mtime and size) are loaded without reading them;
+    /// `preview` is only called for new or modified files.
+    ///
+    /// # Arguments
+    /// * `files` - Vector of (path, name) tuples
+    /// * `preview` - Content preview for a file, combined with its name
+    pub fn index_incremental<F>(
+        &mut self,
+        files: Vec<(PathBuf, String)>,
+        preview: F,
+    ) -> Result<IncrementalStats, String>
+    where
+        F: Fn(&Path) -> Option<String>,
+    {
+        let mut stats = IncrementalStats::default();
+        let mut pending: Vec<(PathBuf, Option<FileStamp>, String)> = Vec::new();
+
+        for (path, name) in files {
+            let stamp = FileStamp::of(&path);
+            if let (Some(store), Some(stamp)) = (&self.store, stamp) {
+                if let Some(stored) = store.lookup_file(&path, stamp)? {
+                    self.insert_embedding(path, stored.text, stored.embedding);
+                    stats.reused += 1;
+                    continue;
+                }
+            }
+
+            let text = embedding_text(&name, preview(&path).as_deref());
+            let stored = match &self.store {
+                Some(store) => store.lookup_text(&text)?,
+                None => None,
+            };
+            match stored {
+                Some(embedding) => {
+                    // Same text as a known file (e.g. moved); just record the new path
+                    if let (Some(store), Some(stamp)) = (&self.store, stamp) {
+                        store.put_file(&path, stamp, &text, &embedding)?;
+                    }
+                    self.insert_embedding(path, text, embedding);
+                    stats.reused += 1;
+                }
+                None => pending.push((path, stamp, text)),
+            }
+        }
+
+        // Embed the remaining files in batches of 100 for memory efficiency
+        for chunk in pending.chunks(100) {
+            let texts: Vec<&str> = chunk.iter().map(|(_, _, t)| t.as_str()).collect();
+            let embeddings = self.embedder.get_embeddings_batch(texts)?;
+            if embeddings.len() != chunk.len() {
+                return Err(format!(
+                    "Embedding count mismatch: expected {}, got {}",
+                    chunk.len(),
+                    embeddings.len()
+                ));
+            }
+
+            if let Some(store) = &self.store {
+                let entries: Vec<_> = chunk
+                    .iter()
+                    .zip(&embeddings)
+                    .map(|((path, stamp, text), embedding)| {
+                        (
+                            stamp.map(|s| (path.as_path(), s)),
+                            text.as_str(),
+                            embedding.as_slice(),
+                        )
+                    })
+                    .collect();
+                if let Err(e) = store.put_many(&entries) {
+                    eprintln!("[VectorIndex] Warning: Failed to persist embeddings: {}", e);
+                }
+            }
+
+            for ((path, _, text), embedding) in chunk.iter().cloned().zip(embeddings) {
+                self.insert_embedding(path, text, embedding);
+                stats.embedded += 1;
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Embed a file that was created or modified, for `insert_prepared`
+    ///
+    /// Only needs shared access, so callers can embed while holding a read
+    /// lock and take the write lock just to insert. Stored embeddings are
+    /// reused when the file or its text is unchanged; new ones are persisted.
+    pub fn prepare_file(&self, path: &Path, preview: Option<&str>) -> Result<PreparedFile, String> {
+        let stamp = FileStamp::of(path);
+        if let (Some(store), Some(stamp)) = (&self.store, stamp) {
+            if let Some(stored) = store.lookup_file(path, stamp)? {
+                return Ok(PreparedFile {
+                    path: path.to_path_buf(),
+                    text: stored.text,
+                    embedding: stored.embedding,
+                });
+            }
+        }
+
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let text = embedding_text(&name, preview);
+        let stored = match &self.store {
+            Some(store) => store.lookup_text(&text)?,
+            None => None,
+        };
+        let embedding = match stored {
+            Some(embedding) => embedding,
+            None => self.embedder.get_embedding(&text)?,
+        };
+
+        if let (Some(store), Some(stamp)) = (&self.store, stamp) {
+            if let Err(e) = store.put_file(path, stamp, &text, &embedding) {
+                eprintln!("[VectorIndex] Warning: Failed to persist embedding: {}", e);
+            }
+        }
+
+        Ok(PreparedFile {
+            path: path.to_path_buf(),
+            text,
+            embedding,
+        })
+    }
+
+    /// Insert a file embedded by `prepare_file`, replacing its old entry
+    pub fn insert_prepared(&mut self, file: PreparedFile) {
+        self.insert_embedding(file.path, file.text, file.embedding);
+    }
+
+    /// Drop a file that was deleted or moved away
+    pub fn remove_file(&mut self, path: &Path) -> Result<(), String> {
+        self.remove_document(&path.to_path_buf());
+        match &self.store {
+            Some(store) => store.remove_file(path),
+            None => Ok(()),
+        }
+    }
+
     /// Compute semantic tags for a document based on similarity to category embeddings
     ///
     /// Returns tags for categories that exceed the similarity threshold
@@ -182,6 +336,38 @@ impl VectorIndex {
     }
 }
 
+/// Result of an incremental indexing pass
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IncrementalStats {
+    /// Files whose embedding was loaded from the store
+    pub reused: usize,
+    /// Files that had to be embedded
+    pub embedded: usize,
+}
+
+impl IncrementalStats {
+    /// Total number of indexed files
+    pub fn total(&self) -> usize {
+        self.reused + self.embedded
+    }
+}
+
+/// A file embedded outside the index write lock (see `prepare_file`)
+#[derive(Debug, Clone)]
+pub struct PreparedFile {
+    path: PathBuf,
+    text: String,
+    embedding: Vec<f32>,
+}
+
+/// Text used to embed a file: its name plus an optional content preview
+fn embedding_text(name: &str, preview: Option<&str>) -> String {
+    match preview {
+        Some(p) if !p.is_empty() => format!("{} {}", name, p),
+        _ => name.to_string(),
+    }
+}
+
 /// Compute cosine similarity between two vectors
 ///
 /// Returns a value between -1.0 and 1.0, where 1.0 means identical direction
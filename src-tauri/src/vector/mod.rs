@@ -5,15 +5,19 @@
 
 #![allow(dead_code)]
 
+pub mod ann;
 pub mod embedder;
 pub mod search;
+pub mod store;
 
+pub use ann::{AnnIndex, HnswParams};
 pub use embedder::*;
+pub use store::{EmbeddingStore, FileStamp};
 
 use fastembed::EmbeddingModel;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Configuration for the vector index
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -55,6 +59,14 @@ impl VectorModelType {
             VectorModelType::BgeSmallEnV15 => EmbeddingModel::BGESmallENV15,
         }
     }
+
+    /// Stable identifier used to key persisted embeddings
+    pub fn id(&self) -> &'static str {
+        match self {
+            VectorModelType::AllMiniLmL6V2 => "all-minilm-l6-v2",
+            VectorModelType::BgeSmallEnV15 => "bge-small-en-v1.5",
+        }
+    }
 }
 
 /// A document in the vector index with its embedding
@@ -77,10 +89,16 @@ pub struct VectorIndex {
     embedder: VectorEmbedder,
     /// Indexed documents keyed by path
     documents: HashMap<PathBuf, VectorDocument>,
+    /// Nearest-neighbour index over the document embeddings
+    ann: AnnIndex<PathBuf>,
     /// Configuration
     config: VectorConfig,
     /// Pre-computed category embeddings for tag assignment
     category_embeddings: HashMap<String, Vec<f32>>,
+    /// Persistent embeddings; without a store every file is re-embedded
+    store: Option<EmbeddingStore>,
+    /// Folder this index was built for
+    root: Option<PathBuf>,
 }
 
 impl VectorIndex {
@@ -112,11 +130,35 @@ impl VectorIndex {
         Ok(Self {
             embedder,
             documents: HashMap::new(),
+            ann: AnnIndex::new(),
             config,
             category_embeddings,
+            store: None,
+            root: None,
         })
     }
 
+    /// Persist embeddings in the given store and reuse them across sessions
+    pub fn with_store(mut self, store: EmbeddingStore) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Get the persistent store, if any
+    pub fn store(&self) -> Option<&EmbeddingStore> {
+        self.store.as_ref()
+    }
+
+    /// Folder this index was built for
+    pub fn root(&self) -> Option<&Path> {
+        self.root.as_deref()
+    }
+
+    /// Set the folder this index was built for
+    pub fn set_root(&mut self, root: PathBuf) {
+        self.root = Some(root);
+    }
+
     /// Get the number of indexed documents
     pub fn len(&self) -> usize {
         self.documents.len()
@@ -147,6 +189,11 @@ impl VectorIndex {
         &self.documents
     }
 
+    /// Get the nearest-neighbour index over the documents
+    pub fn ann(&self) -> &AnnIndex<PathBuf> {
+        &self.ann
+    }
+
     /// Get category embeddings for tag assignment
     pub fn category_embeddings(&self) -> &HashMap<String, Vec<f32>> {
         &self.category_embeddings
@@ -154,17 +201,20 @@ impl VectorIndex {
 
     /// Insert a document into the index
     pub fn insert_document(&mut self, doc: VectorDocument) {
+        self.ann.insert(doc.path.clone(), &doc.embedding);
         self.documents.insert(doc.path.clone(), doc);
     }
 
     /// Remove a document from the index
     pub fn remove_document(&mut self, path: &PathBuf) -> Option<VectorDocument> {
+        self.ann.remove(path);
         self.documents.remove(path)
     }
 
     /// Clear all documents from the index
     #[allow(dead_code)]
     pub fn clear(&mut self) {
+        self.ann.clear();
         self.documents.clear();
     }
 }
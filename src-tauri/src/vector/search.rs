@@ -1,7 +1,8 @@
 //! Vector Search Module
 //!
 //! Provides semantic search capabilities over the indexed documents.
-//! Uses cosine similarity to find documents matching a query.
+//! Uses cosine similarity to find documents matching a query; large indexes
+//! are searched through the HNSW graph of [`super::AnnIndex`].
 
 use super::{cosine_similarity, VectorIndex};
 use std::path::PathBuf;
@@ -29,22 +30,9 @@ impl VectorIndex {
         // Generate query embedding
         let query_embedding = self.embedder().get_embedding(query)?;
 
-        // Compute similarity with all documents
-        let mut results: Vec<(PathBuf, f32)> = self
-            .documents()
-            .iter()
-            .map(|(path, doc)| {
-                let score = cosine_similarity(&query_embedding, &doc.embedding);
-                (path.clone(), score)
-            })
-            .filter(|(_, score)| *score >= self.config().similarity_threshold)
-            .collect();
-
-        // Sort by similarity (descending)
-        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-
-        // Limit results
-        results.truncate(self.config().max_results);
+        // Top results by similarity (descending)
+        let mut results = self.ann().search(&query_embedding, self.config().max_results);
+        results.retain(|(_, score)| *score >= self.config().similarity_threshold);
 
         Ok(results)
     }
@@ -102,21 +90,9 @@ impl VectorIndex {
             .get_document(path)
             .ok_or_else(|| format!("Document not found: {:?}", path))?;
 
-        let source_embedding = &doc.embedding;
-
-        let mut results: Vec<(PathBuf, f32)> = self
-            .documents()
-            .iter()
-            .filter(|(p, _)| *p != path) // Exclude self
-            .map(|(p, d)| {
-                let score = cosine_similarity(source_embedding, &d.embedding);
-                (p.clone(), score)
-            })
-            .filter(|(_, score)| *score >= self.config().similarity_threshold)
-            .collect();
-
-        // Sort by similarity (descending)
-        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        // One extra result, since the document itself is the best match
+        let mut results = self.ann().search(&doc.embedding, limit + 1);
+        results.retain(|(p, score)| p != path && *score >= self.config().similarity_threshold);
 
         results.truncate(limit);
         Ok(results)
@@ -0,0 +1,354 @@
+//! Persistent Embedding Store
+//!
+//! SQLite-backed storage for embeddings so that re-opening a folder only
+//! embeds new or changed files.
+//!
+//! Two tables:
+//! - `embeddings`: vectors keyed by model and SHA-256 of the embedded text.
+//!   Identical texts (a file moved to another folder, or two folders with the
+//!   same file names) share one vector.
+//! - `files`: path -> (mtime, size, text hash). A file whose mtime and size are
+//!   unchanged is served without reading it at all.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Modification stamp used to detect changed files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    /// Modification time in unix ms
+    pub mtime: i64,
+    /// Size in bytes
+    pub size: u64,
+}
+
+impl FileStamp {
+    /// Read the stamp of a file from disk
+    pub fn of(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        let mtime = metadata
+            .modified()
+            .ok()?
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?
+            .as_millis() as i64;
+        Some(Self {
+            mtime,
+            size: metadata.len(),
+        })
+    }
+}
+
+/// An embedding loaded from the store
+#[derive(Debug, Clone)]
+pub struct StoredEmbedding {
+    /// Text that was embedded
+    pub text: String,
+    /// The embedding vector
+    pub embedding: Vec<f32>,
+}
+
+/// Entry for [`EmbeddingStore::put_many`]: optional file path and stamp,
+/// embedded text and its embedding
+pub type StoreEntry<'a> = (Option<(&'a Path, FileStamp)>, &'a str, &'a [f32]);
+
+/// SQLite-backed embedding store for one embedding model
+pub struct EmbeddingStore {
+    conn: Mutex<Connection>,
+    /// Model identifier; vectors of different models never mix
+    model: String,
+}
+
+impl EmbeddingStore {
+    /// Open or create the store in `dir` for the given model
+    pub fn open(dir: &Path, model: &str) -> Result<Self, String> {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create vector store directory: {}", e))?;
+
+        let conn = Connection::open(dir.join("embeddings.db"))
+            .map_err(|e| format!("Failed to open vector store: {}", e))?;
+        conn.execute_batch(
+            r#"
+            PRAGMA journal_mode = WAL;
+
+            CREATE TABLE IF NOT EXISTS embeddings (
+                model TEXT NOT NULL,
+                text_hash TEXT NOT NULL,
+                text TEXT NOT NULL,
+                embedding BLOB NOT NULL,
+                PRIMARY KEY (model, text_hash)
+            );
+
+            CREATE TABLE IF NOT EXISTS files (
+                model TEXT NOT NULL,
+                path TEXT NOT NULL,
+                mtime INTEGER NOT NULL,
+                size INTEGER NOT NULL,
+                text_hash TEXT NOT NULL,
+                PRIMARY KEY (model, path)
+            );
+            "#,
+        )
+        .map_err(|e| format!("Failed to initialize vector store: {}", e))?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+            model: model.to_string(),
+        })
+    }
+
+    /// Open the store in the app cache directory, next to the Grok content cache
+    pub fn open_default(model: &str) -> Result<Self, String> {
+        let dir = dirs::cache_dir()
+            .ok_or("Failed to get cache directory")?
+            .join("com.sentinel.filemanager")
+            .join("vector_index");
+        Self::open(&dir, model)
+    }
+
+    fn conn(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.conn
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Embedding of a file if it is unchanged since it was stored
+    pub fn lookup_file(
+        &self,
+        path: &Path,
+        stamp: FileStamp,
+    ) -> Result<Option<StoredEmbedding>, String> {
+        self.conn()
+            .query_row(
+                r#"
+                SELECT e.text, e.embedding
+                FROM files f
+                JOIN embeddings e ON e.model = f.model AND e.text_hash = f.text_hash
+                WHERE f.model = ?1 AND f.path = ?2 AND f.mtime = ?3 AND f.size = ?4
+                "#,
+                params![self.model, path_key(path), stamp.mtime, stamp.size as i64],
+                |row| {
+                    Ok(StoredEmbedding {
+                        text: row.get(0)?,
+                        embedding: decode_embedding(&row.get::<_, Vec<u8>>(1)?),
+                    })
+                },
+            )
+            .optional()
+            .map_err(|e| format!("Vector store query failed: {}", e))
+    }
+
+    /// Embedding previously computed for exactly this text
+    pub fn lookup_text(&self, text: &str) -> Result<Option<Vec<f32>>, String> {
+        self.conn()
+            .query_row(
+                "SELECT embedding FROM embeddings WHERE model = ?1 AND text_hash = ?2",
+                params![self.model, text_hash(text)],
+                |row| Ok(decode_embedding(&row.get::<_, Vec<u8>>(0)?)),
+            )
+            .optional()
+            .map_err(|e| format!("Vector store query failed: {}", e))
+    }
+
+    /// Store embeddings in one transaction.
+    /// Entries with a path and stamp are also recorded as that file's current version.
+    pub fn put_many(
+        &self,
+        entries: &[StoreEntry<'_>],
+    ) -> Result<(), String> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let mut conn = self.conn();
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to start vector store transaction: {}", e))?;
+        for (file, text, embedding) in entries {
+            let hash = text_hash(text);
+            tx.execute(
+                "INSERT OR REPLACE INTO embeddings (model, text_hash, text, embedding) VALUES (?1, ?2, ?3, ?4)",
+                params![self.model, hash, text, encode_embedding(embedding)],
+            )
+            .map_err(|e| format!("Failed to store embedding: {}", e))?;
+
+            if let Some((path, stamp)) = file {
+                tx.execute(
+                    "INSERT OR REPLACE INTO files (model, path, mtime, size, text_hash) VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![self.model, path_key(path), stamp.mtime, stamp.size as i64, hash],
+                )
+                .map_err(|e| format!("Failed to store file entry: {}", e))?;
+            }
+        }
+        tx.commit()
+            .map_err(|e| format!("Failed to commit vector store: {}", e))
+    }
+
+    /// Store the embedding of a file's current version
+    pub fn put_file(
+        &self,
+        path: &Path,
+        stamp: FileStamp,
+        text: &str,
+        embedding: &[f32],
+    ) -> Result<(), String> {
+        self.put_many(&[(Some((path, stamp)), text, embedding)])
+    }
+
+    /// Forget a file. Its embedding stays available to files with the same text.
+    pub fn remove_file(&self, path: &Path) -> Result<(), String> {
+        self.conn()
+            .execute(
+                "DELETE FROM files WHERE model = ?1 AND path = ?2",
+                params![self.model, path_key(path)],
+            )
+            .map(|_| ())
+            .map_err(|e| format!("Failed to remove file entry: {}", e))
+    }
+
+    /// Forget files under `root` that are not in `keep`, along with their
+    /// embeddings unless another file still uses them.
+    /// Returns the number of files removed.
+    pub fn prune(&self, root: &Path, keep: &HashSet<PathBuf>) -> Result<usize, String> {
+        let mut conn = self.conn();
+        let stale: Vec<(String, String)> = {
+            let mut stmt = conn
+                .prepare("SELECT path, text_hash FROM files WHERE model = ?1")
+                .map_err(|e| format!("Failed to prepare query: {}", e))?;
+            let rows = stmt
+                .query_map(params![self.model], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                })
+                .map_err(|e| format!("Vector store query failed: {}", e))?;
+            rows.filter_map(|r| r.ok())
+                .filter(|(p, _)| {
+                    let path = Path::new(p);
+                    path.starts_with(root) && !keep.contains(path)
+                })
+                .collect()
+        };
+
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to start vector store transaction: {}", e))?;
+        for (path, hash) in &stale {
+            tx.execute(
+                "DELETE FROM files WHERE model = ?1 AND path = ?2",
+                params![self.model, path],
+            )
+            .map_err(|e| format!("Failed to remove file entry: {}", e))?;
+            tx.execute(
+                r#"
+                DELETE FROM embeddings WHERE model = ?1 AND text_hash = ?2
+                    AND NOT EXISTS (SELECT 1 FROM files WHERE model = ?1 AND text_hash = ?2)
+                "#,
+                params![self.model, hash],
+            )
+            .map_err(|e| format!("Failed to prune embeddings: {}", e))?;
+        }
+        tx.commit()
+            .map_err(|e| format!("Failed to commit vector store: {}", e))?;
+
+        Ok(stale.len())
+    }
+
+    /// Number of stored embeddings for this model
+    pub fn count(&self) -> Result<usize, String> {
+        self.conn()
+            .query_row(
+                "SELECT COUNT(*) FROM embeddings WHERE model = ?1",
+                params![self.model],
+                |row| row.get::<_, i64>(0),
+            )
+            .map(|n| n as usize)
+            .map_err(|e| format!("Vector store query failed: {}", e))
+    }
+}
+
+fn path_key(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+fn text_hash(text: &str) -> String {
+    format!("{:x}", Sha256::digest(text.as_bytes()))
+}
+
+fn encode_embedding(embedding: &[f32]) -> Vec<u8> {
+    embedding.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn decode_embedding(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stamp(mtime: i64) -> FileStamp {
+        FileStamp { mtime, size: 10 }
+    }
+
+    #[test]
+    fn test_file_entry_invalidated_by_stamp() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = EmbeddingStore::open(dir.path(), "test-model").unwrap();
+        let path = Path::new("/docs/report.pdf");
+
+        store.put_file(path, stamp(1), "report pdf", &[0.5, -1.0, 2.0]).unwrap();
+
+        let hit = store.lookup_file(path, stamp(1)).unwrap().unwrap();
+        assert_eq!(hit.text, "report pdf");
+        assert_eq!(hit.embedding, vec![0.5, -1.0, 2.0]);
+        assert!(store.lookup_file(path, stamp(2)).unwrap().is_none());
+
+        // The vector is still reusable by text after the file changed
+        assert!(store.lookup_text("report pdf").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_models_are_isolated_and_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let store = EmbeddingStore::open(dir.path(), "a").unwrap();
+            store.put_many(&[(None, "invoice", &[1.0])]).unwrap();
+        }
+
+        let reopened = EmbeddingStore::open(dir.path(), "a").unwrap();
+        assert_eq!(reopened.lookup_text("invoice").unwrap(), Some(vec![1.0]));
+
+        let other = EmbeddingStore::open(dir.path(), "b").unwrap();
+        assert!(other.lookup_text("invoice").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_prune_removes_missing_files_under_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = EmbeddingStore::open(dir.path(), "m").unwrap();
+        let kept = PathBuf::from("/root/a/kept.txt");
+        let gone = PathBuf::from("/root/a/gone.txt");
+        let elsewhere = PathBuf::from("/root/b/other.txt");
+        store
+            .put_many(&[
+                (Some((&kept, stamp(1))), "kept", &[1.0]),
+                (Some((&gone, stamp(1))), "gone", &[2.0]),
+                (Some((&elsewhere, stamp(1))), "other", &[3.0]),
+            ])
+            .unwrap();
+
+        let keep: HashSet<PathBuf> = [kept.clone()].into_iter().collect();
+        assert_eq!(store.prune(Path::new("/root/a"), &keep).unwrap(), 1);
+
+        assert!(store.lookup_file(&kept, stamp(1)).unwrap().is_some());
+        assert!(store.lookup_file(&gone, stamp(1)).unwrap().is_none());
+        assert!(store.lookup_file(&elsewhere, stamp(1)).unwrap().is_some());
+        assert!(store.lookup_text("gone").unwrap().is_none());
+        assert_eq!(store.count().unwrap(), 2);
+    }
+}
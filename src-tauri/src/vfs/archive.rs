@@ -0,0 +1,160 @@
+//! Archive members as virtual VFS nodes
+//!
+//! Indexed archives get their members as child nodes at virtual paths below
+//! the archive file (`backup.zip/2024/march.pdf`), so search, rules and the
+//! agent see inside them. Member nodes carry the archive path in
+//! `FileNode::archive` and are read-only: the ShadowVFS rejects staging
+//! anything inside an archive.
+
+use chrono::{TimeZone, Utc};
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::graph::ShadowVFS;
+use super::node::FileNode;
+use crate::archive::{self, ArchiveListing};
+
+/// Insert the members of an archive below its node
+///
+/// Folders that only exist implicitly in member names are created too.
+/// `previews` maps member names to content previews. Returns the number of
+/// file members inserted.
+pub fn insert_archive_members(
+    vfs: &mut ShadowVFS,
+    archive_path: &Path,
+    listing: &ArchiveListing,
+    previews: &HashMap<String, String>,
+) -> usize {
+    let mut inserted = 0;
+
+    for member in &listing.members {
+        let path = archive::member_path(archive_path, &member.name);
+
+        // Implicit folders, outermost first
+        let folders: Vec<_> = path
+            .ancestors()
+            .skip(1)
+            .take_while(|p| *p != archive_path)
+            .map(Path::to_path_buf)
+            .collect();
+        for folder in folders.into_iter().rev() {
+            if !vfs.exists(&folder) {
+                vfs.insert_linked(
+                    FileNode::directory(folder).with_archive(archive_path.to_path_buf()),
+                );
+            }
+        }
+
+        if vfs.exists(&path) {
+            continue;
+        }
+
+        let mut node = if member.is_dir {
+            FileNode::directory(path)
+        } else {
+            FileNode::file(path).with_size(member.size)
+        }
+        .with_archive(archive_path.to_path_buf());
+
+        node.modified_at = member
+            .modified_at
+            .and_then(|ms| Utc.timestamp_millis_opt(ms).single());
+        if let Some(ext) = &node.extension {
+            if let Some(mime) = mime_guess::from_ext(ext).first() {
+                node.mime_type = Some(mime.to_string());
+            }
+        }
+        if let Some(preview) = previews.get(&member.name) {
+            node.content_preview = Some(preview.clone());
+        }
+
+        if !member.is_dir {
+            inserted += 1;
+        }
+        vfs.insert_linked(node);
+    }
+
+    inserted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::{ArchiveFormat, ArchiveMember};
+    use crate::vfs::VFSError;
+    use std::path::PathBuf;
+
+    fn member(name: &str, size: u64) -> ArchiveMember {
+        ArchiveMember {
+            name: name.to_string(),
+            size,
+            modified_at: Some(1_700_000_000_000),
+            is_dir: false,
+        }
+    }
+
+    fn archive_vfs() -> ShadowVFS {
+        let mut vfs = ShadowVFS::new(PathBuf::from("/root"));
+        vfs.insert_linked(FileNode::file(PathBuf::from("/root/backup.zip")).with_size(500));
+        vfs.insert_linked(FileNode::directory(PathBuf::from("/root/docs")));
+
+        let listing = ArchiveListing {
+            format: ArchiveFormat::Zip,
+            members: vec![member("2024/march.pdf", 300), member("readme.txt", 20)],
+            truncated: false,
+        };
+        let previews = HashMap::from([("readme.txt".to_string(), "Backup of 2024".to_string())]);
+        let inserted =
+            insert_archive_members(&mut vfs, Path::new("/root/backup.zip"), &listing, &previews);
+        assert_eq!(inserted, 2);
+        vfs
+    }
+
+    #[test]
+    fn test_members_become_child_nodes() {
+        let vfs = archive_vfs();
+        let archive = vfs.get(&PathBuf::from("/root/backup.zip")).unwrap();
+        assert_eq!(archive.children.len(), 2);
+
+        let pdf = vfs
+            .get(&PathBuf::from("/root/backup.zip/2024/march.pdf"))
+            .unwrap();
+        assert!(pdf.is_archive_member());
+        assert_eq!(pdf.archive, Some(PathBuf::from("/root/backup.zip")));
+        assert_eq!(pdf.mime_type.as_deref(), Some("application/pdf"));
+        assert!(pdf.modified_at.is_some());
+
+        let folder = vfs.get(&PathBuf::from("/root/backup.zip/2024")).unwrap();
+        assert!(folder.is_directory() && folder.is_archive_member());
+
+        assert_eq!(vfs.search_content("backup of").len(), 1);
+        // Members don't add to the size; the archive already counts
+        assert_eq!(vfs.total_size(), 500);
+    }
+
+    #[test]
+    fn test_archives_are_read_only() {
+        let mut vfs = archive_vfs();
+        let member = PathBuf::from("/root/backup.zip/readme.txt");
+
+        assert!(matches!(
+            vfs.stage_move(member.clone(), PathBuf::from("/root/docs/readme.txt")),
+            Err(VFSError::InvalidOperation(_))
+        ));
+        assert!(matches!(
+            vfs.stage_delete(member),
+            Err(VFSError::InvalidOperation(_))
+        ));
+        assert!(vfs
+            .stage_create_folder(PathBuf::from("/root/backup.zip/new"))
+            .is_err());
+
+        // The archive itself still moves, and only it counts for coverage
+        vfs.stage_move(
+            PathBuf::from("/root/backup.zip"),
+            PathBuf::from("/root/docs/backup.zip"),
+        )
+        .unwrap();
+        assert_eq!(vfs.coverage(), 1.0);
+    }
+}
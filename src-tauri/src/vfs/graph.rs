@@ -7,7 +7,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 use super::node::{FileNode, VFSNodeType};
@@ -63,6 +63,14 @@ pub struct ShadowVFS {
     /// Staged moves: source -> destination
     staged_moves: HashMap<PathBuf, PathBuf>,
 
+    /// Destinations of staged moves, for collision checks
+    #[serde(default)]
+    staged_destinations: HashSet<PathBuf>,
+
+    /// Paths the staged plan accounts for (moved, deleted or explicitly marked)
+    #[serde(default)]
+    covered: HashSet<PathBuf>,
+
     /// When this VFS was last scanned from the real filesystem
     last_scan: Option<DateTime<Utc>>,
 
@@ -85,6 +93,8 @@ impl ShadowVFS {
             staged_creates: HashSet::new(),
             staged_deletes: HashSet::new(),
             staged_moves: HashMap::new(),
+            staged_destinations: HashSet::new(),
+            covered: HashSet::new(),
             last_scan: None,
             total_size_bytes: 0,
         }
@@ -133,8 +143,11 @@ impl ShadowVFS {
     }
 
     /// Insert a node into the VFS
+    ///
+    /// Archive members don't count towards the total size; their archive
+    /// already does.
     pub fn insert(&mut self, node: FileNode) {
-        if node.is_file() {
+        if node.is_file() && !node.is_archive_member() {
             self.total_size_bytes += node.size;
         }
         self.nodes.insert(node.path.clone(), node);
@@ -169,20 +182,109 @@ impl ShadowVFS {
         Ok(children)
     }
 
+    /// Insert a node and link it into its parent's children
+    pub fn insert_linked(&mut self, mut node: FileNode) {
+        if let Some(parent) = node.path.parent() {
+            let parent = parent.to_path_buf();
+            if let Some(parent_node) = self.nodes.get_mut(&parent) {
+                parent_node.add_child(node.path.clone());
+            }
+            node.parent = Some(parent);
+        }
+        self.insert(node);
+    }
+
+    /// Check if a path is taken in the planned state: an existing node,
+    /// a folder staged for creation or the destination of a staged move
+    pub fn is_claimed(&self, path: &Path) -> bool {
+        (self.nodes.contains_key(path) && !self.staged_deletes.contains(path))
+            || self.staged_creates.contains(path)
+            || self.staged_destinations.contains(path)
+    }
+
+    /// Check if a path is inside an indexed archive: a virtual member, or
+    /// any path below an archive file or one of its members
+    ///
+    /// Archives are read-only; nothing can be moved, created or deleted in
+    /// them.
+    pub fn is_inside_archive(&self, path: &Path) -> bool {
+        path.ancestors()
+            .skip(1)
+            .filter_map(|p| self.nodes.get(p))
+            .any(|node| node.is_archive_member() || (node.is_file() && !node.children.is_empty()))
+    }
+
+    /// Folder staged to move away or be deleted that `path` lies inside
+    fn leaving_ancestor<'a>(&self, path: &'a Path) -> Option<&'a Path> {
+        path.ancestors()
+            .skip(1)
+            .find(|p| self.staged_moves.contains_key(*p) || self.staged_deletes.contains(*p))
+    }
+
+    /// Reject putting anything inside a folder that is leaving, since the
+    /// item would leave with it
+    fn reject_leaving_parent(&self, path: &Path) -> Result<(), VFSError> {
+        if let Some(leaving) = self.leaving_ancestor(path) {
+            return Err(VFSError::InvalidOperation(format!(
+                "{} is inside {}, which is staged to move or be deleted",
+                path.display(),
+                leaving.display()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Reject moving or deleting a folder that has items staged to go into it
+    fn reject_incoming(&self, path: &Path) -> Result<(), VFSError> {
+        let incoming = self
+            .staged_destinations
+            .iter()
+            .chain(&self.staged_creates)
+            .find(|p| p.starts_with(path) && p.as_path() != path);
+        if let Some(incoming) = incoming {
+            return Err(VFSError::InvalidOperation(format!(
+                "{} is staged to go into {}",
+                incoming.display(),
+                path.display()
+            )));
+        }
+        Ok(())
+    }
+
+    fn reject_archive_path(&self, path: &Path) -> Result<(), VFSError> {
+        if self.is_inside_archive(path) {
+            return Err(VFSError::InvalidOperation(format!(
+                "{} is inside an archive",
+                path.display()
+            )));
+        }
+        Ok(())
+    }
+
     /// Stage a move operation
     ///
     /// Validates that:
-    /// - Source exists
-    /// - Destination doesn't exist (unless staged for delete)
+    /// - Source exists and is not already being moved
+    /// - Destination is not claimed (existing, created or another move's target)
     /// - No cycle would be created
+    /// - Neither path is inside an archive
+    /// - Destination is not inside a folder staged to move or be deleted,
+    ///   and nothing is staged to go into the source
     pub fn stage_move(&mut self, src: PathBuf, dest: PathBuf) -> Result<(), VFSError> {
         // Validate source exists
         if !self.nodes.contains_key(&src) || self.staged_deletes.contains(&src) {
             return Err(VFSError::PathNotFound(src.display().to_string()));
         }
 
+        if self.staged_moves.contains_key(&src) {
+            return Err(VFSError::InvalidOperation(format!(
+                "{} is already staged for a move",
+                src.display()
+            )));
+        }
+
         // Check for path collision (unless destination is staged for delete)
-        if self.nodes.contains_key(&dest) && !self.staged_deletes.contains(&dest) {
+        if self.is_claimed(&dest) {
             return Err(VFSError::PathCollision {
                 source_path: src.display().to_string(),
                 target: dest.display().to_string(),
@@ -197,6 +299,11 @@ impl ShadowVFS {
             });
         }
 
+        self.reject_archive_path(&src)?;
+        self.reject_archive_path(&dest)?;
+        self.reject_leaving_parent(&dest)?;
+        self.reject_incoming(&src)?;
+
         // Validate destination parent exists
         if let Some(dest_parent) = dest.parent() {
             let parent_path = dest_parent.to_path_buf();
@@ -218,6 +325,8 @@ impl ShadowVFS {
             ));
         }
 
+        self.covered.insert(src.clone());
+        self.staged_destinations.insert(dest.clone());
         self.staged_moves.insert(src, dest);
         Ok(())
     }
@@ -227,9 +336,16 @@ impl ShadowVFS {
     /// Validates that:
     /// - Path doesn't already exist
     /// - Parent directory exists
+    /// - Path is not inside an archive or a folder staged to move or be
+    ///   deleted
     pub fn stage_create_folder(&mut self, path: PathBuf) -> Result<(), VFSError> {
+        self.reject_archive_path(&path)?;
+        self.reject_leaving_parent(&path)?;
+
         // Check for collision
-        if self.nodes.contains_key(&path) && !self.staged_deletes.contains(&path) {
+        if (self.nodes.contains_key(&path) && !self.staged_deletes.contains(&path))
+            || self.staged_destinations.contains(&path)
+        {
             return Err(VFSError::PathCollision {
                 source_path: path.display().to_string(),
                 target: path.display().to_string(),
@@ -255,14 +371,26 @@ impl ShadowVFS {
     /// Stage a deletion
     ///
     /// Validates that:
-    /// - Path exists
+    /// - Path exists and is not staged for a move
     /// - Path is not root
+    /// - Path is not inside an archive
+    /// - Nothing is staged to go into it
     pub fn stage_delete(&mut self, path: PathBuf) -> Result<(), VFSError> {
         // Validate path exists
         if !self.nodes.contains_key(&path) {
             return Err(VFSError::PathNotFound(path.display().to_string()));
         }
 
+        if self.staged_moves.contains_key(&path) {
+            return Err(VFSError::InvalidOperation(format!(
+                "{} is already staged for a move",
+                path.display()
+            )));
+        }
+
+        self.reject_archive_path(&path)?;
+        self.reject_incoming(&path)?;
+
         // Cannot delete root
         if path == self.root {
             return Err(VFSError::CannotModifyRoot(
@@ -270,6 +398,7 @@ impl ShadowVFS {
             ));
         }
 
+        self.covered.insert(path.clone());
         self.staged_deletes.insert(path);
         Ok(())
     }
@@ -328,6 +457,42 @@ impl ShadowVFS {
         self.staged_creates.clear();
         self.staged_deletes.clear();
         self.staged_moves.clear();
+        self.staged_destinations.clear();
+        self.covered.clear();
+    }
+
+    /// Mark a path as accounted for by the plan without staging an
+    /// operation, e.g. a file a rule matched that is already in place
+    pub fn mark_covered(&mut self, path: PathBuf) {
+        self.covered.insert(path);
+    }
+
+    /// Check if a path or one of its ancestors is covered by the plan
+    pub fn is_covered(&self, path: &Path) -> bool {
+        path.ancestors().any(|p| self.covered.contains(p))
+    }
+
+    /// Number of files covered by the plan (archive members excluded)
+    pub fn covered_file_count(&self) -> usize {
+        self.files()
+            .into_iter()
+            .filter(|node| !node.is_archive_member() && self.is_covered(&node.path))
+            .count()
+    }
+
+    /// Fraction of files covered by the plan (1.0 when there are no files)
+    ///
+    /// Archive members move with their archive, so only real files count.
+    pub fn coverage(&self) -> f64 {
+        let total = self
+            .files()
+            .into_iter()
+            .filter(|node| !node.is_archive_member())
+            .count();
+        if total == 0 {
+            return 1.0;
+        }
+        self.covered_file_count() as f64 / total as f64
     }
 
     /// Get all staged creates
@@ -352,6 +517,29 @@ impl ShadowVFS {
             || !self.staged_moves.is_empty()
     }
 
+    /// Hash of the staged operations
+    ///
+    /// Lets an approval be checked against the exact changes that were
+    /// previewed: staging or clearing anything changes the hash.
+    pub fn staged_hash(&self) -> String {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let mut creates: Vec<&PathBuf> = self.staged_creates.iter().collect();
+        let mut deletes: Vec<&PathBuf> = self.staged_deletes.iter().collect();
+        let mut moves: Vec<(&PathBuf, &PathBuf)> = self.staged_moves.iter().collect();
+        creates.sort();
+        deletes.sort();
+        moves.sort();
+
+        let mut hasher = DefaultHasher::new();
+        self.root.hash(&mut hasher);
+        creates.hash(&mut hasher);
+        deletes.hash(&mut hasher);
+        moves.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+
     /// Search for nodes by content preview
     pub fn search_content(&self, query: &str) -> Vec<&FileNode> {
         self.nodes
@@ -418,7 +606,7 @@ impl ShadowVFS {
     pub fn remove(&mut self, path: &PathBuf) -> Option<FileNode> {
         if let Some(node) = self.nodes.remove(path) {
             // Update size tracking
-            if node.is_file() {
+            if node.is_file() && !node.is_archive_member() {
                 self.total_size_bytes = self.total_size_bytes.saturating_sub(node.size);
             }
 
@@ -540,6 +728,84 @@ mod tests {
         assert!(matches!(result, Err(VFSError::PathCollision { .. })));
     }
 
+    #[test]
+    fn test_stage_move_into_claimed_destination() {
+        let mut vfs = create_test_vfs();
+        let mut notes = FileNode::file(PathBuf::from("/root/docs/notes.txt"));
+        notes.parent = Some(PathBuf::from("/root/docs"));
+        vfs.insert(notes);
+
+        vfs.stage_move(
+            PathBuf::from("/root/docs/readme.txt"),
+            PathBuf::from("/root/readme.txt"),
+        )
+        .unwrap();
+
+        // Another move onto the same destination collides right away
+        let result = vfs.stage_move(
+            PathBuf::from("/root/docs/notes.txt"),
+            PathBuf::from("/root/readme.txt"),
+        );
+        assert!(matches!(result, Err(VFSError::PathCollision { .. })));
+
+        // A source can only be moved once
+        let result = vfs.stage_move(
+            PathBuf::from("/root/docs/readme.txt"),
+            PathBuf::from("/root/other.txt"),
+        );
+        assert!(matches!(result, Err(VFSError::InvalidOperation(_))));
+    }
+
+    #[test]
+    fn test_nothing_goes_into_a_leaving_folder() {
+        let mut vfs = create_test_vfs();
+        let mut archive = FileNode::directory(PathBuf::from("/root/archive"));
+        archive.parent = Some(PathBuf::from("/root"));
+        vfs.insert(archive);
+
+        vfs.stage_move(PathBuf::from("/root/docs"), PathBuf::from("/root/papers"))
+            .unwrap();
+        let result = vfs.stage_create_folder(PathBuf::from("/root/docs/new"));
+        assert!(matches!(result, Err(VFSError::InvalidOperation(_))));
+        // Taking an item out of it is fine
+        vfs.stage_delete(PathBuf::from("/root/docs/readme.txt")).unwrap();
+        let result = vfs.stage_delete(PathBuf::from("/root/docs"));
+        assert!(matches!(result, Err(VFSError::InvalidOperation(_))));
+
+        // A folder with items staged to go into it can't leave
+        vfs.clear_staged();
+        vfs.stage_move(
+            PathBuf::from("/root/docs/readme.txt"),
+            PathBuf::from("/root/archive/readme.txt"),
+        )
+        .unwrap();
+        let result = vfs.stage_delete(PathBuf::from("/root/archive"));
+        assert!(matches!(result, Err(VFSError::InvalidOperation(_))));
+        let result = vfs.stage_move(PathBuf::from("/root/archive"), PathBuf::from("/root/old"));
+        assert!(matches!(result, Err(VFSError::InvalidOperation(_))));
+    }
+
+    #[test]
+    fn test_coverage() {
+        let mut vfs = create_test_vfs();
+        let mut notes = FileNode::file(PathBuf::from("/root/docs/notes.txt"));
+        notes.parent = Some(PathBuf::from("/root/docs"));
+        vfs.insert(notes);
+        assert_eq!(vfs.coverage(), 0.0);
+
+        vfs.stage_delete(PathBuf::from("/root/docs/notes.txt")).unwrap();
+        vfs.mark_covered(PathBuf::from("/root/docs/readme.txt"));
+        assert_eq!(vfs.covered_file_count(), 1); // Deleted files are no longer listed
+        assert_eq!(vfs.coverage(), 1.0);
+
+        // Moving a folder covers everything inside it
+        vfs.clear_staged();
+        vfs.stage_move(PathBuf::from("/root/docs"), PathBuf::from("/root/papers"))
+            .unwrap();
+        assert!(vfs.is_covered(Path::new("/root/docs/readme.txt")));
+        assert_eq!(vfs.coverage(), 1.0);
+    }
+
     #[test]
     fn test_stage_create_folder() {
         let mut vfs = create_test_vfs();
@@ -587,4 +853,19 @@ mod tests {
         vfs.clear_staged();
         assert!(!vfs.has_staged_operations());
     }
+
+    #[test]
+    fn test_staged_hash_tracks_changes() {
+        let mut vfs = create_test_vfs();
+        let empty = vfs.staged_hash();
+
+        vfs.stage_create_folder(PathBuf::from("/root/a")).unwrap();
+        vfs.stage_create_folder(PathBuf::from("/root/b")).unwrap();
+        let staged = vfs.staged_hash();
+        assert_ne!(staged, empty);
+        assert_eq!(vfs.clone().staged_hash(), staged);
+
+        vfs.clear_staged();
+        assert_eq!(vfs.staged_hash(), empty);
+    }
 }
@@ -0,0 +1,482 @@
+//! Dry-run impact estimates
+//!
+//! Replays a plan on a copy of the VFS like [`validate_plan`](super::validate_plan)
+//! and additionally works out what each operation will cost on the real
+//! filesystem: whether a move (or a removal into the quarantine root) is a
+//! same-device rename or a cross-device copy and delete, how many bytes have
+//! to be copied to each destination
+//! device and whether they fit, and roughly how long the whole plan takes.
+//! Operations whose target path the [`PathValidator`] would reject are
+//! flagged so they can be fixed before execution begins.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::graph::ShadowVFS;
+use super::simulator::{simulate_operation, SimulatedOperation};
+use crate::quarantine::QuarantineManager;
+use crate::security::PathValidator;
+
+/// Estimated cost of a rename, folder creation or quarantine, in milliseconds
+const METADATA_OP_MS: u64 = 2;
+
+/// Assumed copy throughput for cross-device moves (100 MiB/s)
+///
+/// Deliberately conservative: external and network drives are the usual
+/// reason a move crosses devices.
+const COPY_BYTES_PER_SEC: u64 = 100 * 1024 * 1024;
+
+/// How an operation will be carried out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImpactKind {
+    /// Move within one device, a metadata-only rename
+    Rename,
+    /// Move across devices, copied then deleted
+    CrossDeviceCopy,
+    /// New folder
+    CreateFolder,
+    /// Removal into quarantine, copied when the quarantine root is on
+    /// another device
+    Delete,
+}
+
+/// Dry-run result for one operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationImpact {
+    /// Position of the operation in the plan
+    pub index: usize,
+    pub kind: ImpactKind,
+    /// Source path for moves
+    pub source: Option<String>,
+    /// Path the operation creates or removes
+    pub target: String,
+    /// Size of the file or folder being moved or deleted
+    pub bytes: u64,
+    /// Bytes copied to another device (0 unless the operation crosses devices)
+    pub bytes_copied: u64,
+    /// Why the simulator or path validator rejects the operation
+    pub error: Option<String>,
+}
+
+/// Space needed on a destination device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceSpace {
+    /// An existing folder on the device that receives copies
+    pub path: String,
+    /// Bytes the plan copies onto the device
+    pub bytes_required: u64,
+    /// Bytes free on the device, if it could be determined
+    pub bytes_available: Option<u64>,
+    /// Whether the copies fit (assumed when free space is unknown)
+    pub sufficient: bool,
+}
+
+/// Impact report for a whole plan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunReport {
+    /// Per-operation results, in plan order
+    pub operations: Vec<OperationImpact>,
+    /// Moves done as renames
+    pub renames: usize,
+    /// Moves done as copy and delete
+    pub cross_device_moves: usize,
+    /// Total bytes copied across devices
+    pub bytes_to_copy: u64,
+    /// Devices receiving copies
+    pub devices: Vec<DeviceSpace>,
+    /// Rough wall-clock estimate for executing the plan
+    pub estimated_duration_ms: u64,
+    /// Operations with an error
+    pub rejected_operations: usize,
+    /// Conflicts between operations found after replaying the whole plan
+    pub errors: Vec<String>,
+    /// Fraction of files the plan accounts for
+    pub coverage: f64,
+    /// Whether the plan can run: no errors and enough space everywhere
+    pub valid: bool,
+}
+
+/// Estimate the impact of a plan without changing the VFS or the filesystem
+///
+/// `root` is the folder the plan organizes; every target path must stay
+/// inside it. Deletes are classified against the default quarantine, the
+/// one plan execution moves them into.
+pub fn dry_run_plan(
+    vfs: &ShadowVFS,
+    root: &Path,
+    operations: Vec<SimulatedOperation>,
+) -> DryRunReport {
+    let quarantine = QuarantineManager::new().ok();
+    dry_run_plan_with_quarantine(vfs, root, operations, quarantine.as_ref())
+}
+
+/// [`dry_run_plan`] with deletes going into `quarantine`
+///
+/// Without a quarantine, deletes are assumed to stay on their device.
+pub fn dry_run_plan_with_quarantine(
+    vfs: &ShadowVFS,
+    root: &Path,
+    operations: Vec<SimulatedOperation>,
+    quarantine: Option<&QuarantineManager>,
+) -> DryRunReport {
+    let mut scratch = vfs.clone();
+    let mut impacts = Vec::with_capacity(operations.len());
+    let mut copies_by_device: BTreeMap<DeviceKey, DeviceSpace> = BTreeMap::new();
+    let sizes = SizeIndex::new(vfs);
+
+    for (index, op) in operations.into_iter().enumerate() {
+        let mut impact = match &op {
+            SimulatedOperation::Move {
+                source,
+                destination,
+            } => {
+                let bytes = sizes.size_of(Path::new(source));
+                let crosses = crosses_device(
+                    Path::new(source),
+                    Path::new(destination),
+                    bytes,
+                    &mut copies_by_device,
+                );
+                let (kind, bytes_copied) = if crosses {
+                    (ImpactKind::CrossDeviceCopy, bytes)
+                } else {
+                    (ImpactKind::Rename, 0)
+                };
+
+                OperationImpact {
+                    index,
+                    kind,
+                    source: Some(source.clone()),
+                    target: destination.clone(),
+                    bytes,
+                    bytes_copied,
+                    error: None,
+                }
+            }
+            SimulatedOperation::CreateFolder { path } => OperationImpact {
+                index,
+                kind: ImpactKind::CreateFolder,
+                source: None,
+                target: path.clone(),
+                bytes: 0,
+                bytes_copied: 0,
+                error: None,
+            },
+            SimulatedOperation::Delete { path } => {
+                let bytes = sizes.size_of(Path::new(path));
+                let crosses = quarantine.is_some_and(|quarantine| {
+                    crosses_device(
+                        Path::new(path),
+                        &quarantine.planned_root(Path::new(path)),
+                        bytes,
+                        &mut copies_by_device,
+                    )
+                });
+
+                OperationImpact {
+                    index,
+                    kind: ImpactKind::Delete,
+                    source: None,
+                    target: path.clone(),
+                    bytes,
+                    bytes_copied: if crosses { bytes } else { 0 },
+                    error: None,
+                }
+            }
+        };
+
+        impact.error = PathValidator::validate_destination(&impact.target, root, true)
+            .err()
+            .or_else(|| simulate_operation(&mut scratch, op).err().map(|e| e.to_string()));
+        impacts.push(impact);
+    }
+
+    let errors: Vec<String> = scratch
+        .validate_staged()
+        .err()
+        .map(|conflicts| conflicts.iter().map(|e| e.to_string()).collect())
+        .unwrap_or_default();
+
+    let mut devices: Vec<DeviceSpace> = copies_by_device.into_values().collect();
+    for device in &mut devices {
+        device.sufficient = device
+            .bytes_available
+            .is_none_or(|available| device.bytes_required <= available);
+    }
+
+    let renames = impacts
+        .iter()
+        .filter(|i| i.kind == ImpactKind::Rename)
+        .count();
+    let cross_device_moves = impacts
+        .iter()
+        .filter(|i| i.kind == ImpactKind::CrossDeviceCopy)
+        .count();
+    let bytes_to_copy: u64 = impacts.iter().map(|i| i.bytes_copied).sum();
+    let rejected_operations = impacts.iter().filter(|i| i.error.is_some()).count();
+    let estimated_duration_ms = impacts.len() as u64 * METADATA_OP_MS
+        + bytes_to_copy.saturating_mul(1000) / COPY_BYTES_PER_SEC;
+
+    DryRunReport {
+        valid: rejected_operations == 0
+            && errors.is_empty()
+            && devices.iter().all(|d| d.sufficient),
+        operations: impacts,
+        renames,
+        cross_device_moves,
+        bytes_to_copy,
+        devices,
+        estimated_duration_ms,
+        rejected_operations,
+        errors,
+        coverage: scratch.coverage(),
+    }
+}
+
+/// Whether moving `source` to `destination` crosses devices
+///
+/// A crossing move is copied, so its bytes are added to the space required
+/// on the destination device.
+fn crosses_device(
+    source: &Path,
+    destination: &Path,
+    bytes: u64,
+    copies_by_device: &mut BTreeMap<DeviceKey, DeviceSpace>,
+) -> bool {
+    let destination_device = device_of(destination);
+    let same_device = match (device_of(source), &destination_device) {
+        (Some(from), Some(to)) => from.0 == to.0,
+        // Can't tell; rename is what the executor tries first
+        _ => true,
+    };
+    if same_device {
+        return false;
+    }
+
+    if let Some((key, existing)) = destination_device {
+        copies_by_device
+            .entry(key)
+            .or_insert_with(|| DeviceSpace {
+                path: existing.to_string_lossy().to_string(),
+                bytes_required: 0,
+                bytes_available: fs4::available_space(&existing).ok(),
+                sufficient: true,
+            })
+            .bytes_required += bytes;
+    }
+    true
+}
+
+/// Scanned sizes of files and folders, summed per folder once per report
+struct SizeIndex<'a> {
+    vfs: &'a ShadowVFS,
+    /// Total size of the files under each scanned folder
+    folders: HashMap<&'a Path, u64>,
+}
+
+impl<'a> SizeIndex<'a> {
+    fn new(vfs: &'a ShadowVFS) -> Self {
+        let mut folders: HashMap<&'a Path, u64> = HashMap::new();
+        // Archive members are already counted in their archive's size
+        let files = vfs
+            .iter()
+            .filter(|(_, n)| !n.is_directory() && !n.is_archive_member());
+        for (path, node) in files {
+            for ancestor in path.ancestors().skip(1) {
+                *folders.entry(ancestor).or_default() += node.size;
+                if ancestor == vfs.root().as_path() {
+                    break;
+                }
+            }
+        }
+        Self { vfs, folders }
+    }
+
+    /// Size of a file, or of all files under a folder, as scanned
+    ///
+    /// Falls back to the filesystem for paths the VFS doesn't know about.
+    fn size_of(&self, path: &Path) -> u64 {
+        match self.vfs.get(&path.to_path_buf()) {
+            Some(node) if node.is_directory() => self.folders.get(path).copied().unwrap_or(0),
+            Some(node) => node.size,
+            None => fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+        }
+    }
+}
+
+/// Identifies a device: its id where the platform has one, else the path
+/// prefix (drive letter or UNC share)
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct DeviceKey(String);
+
+/// Device of a path and the closest existing ancestor it was read from
+///
+/// Paths that don't exist yet live on the device of their nearest
+/// existing ancestor.
+fn device_of(path: &Path) -> Option<(DeviceKey, PathBuf)> {
+    let existing = path.ancestors().find(|p| p.exists())?;
+    let key = device_id(existing).map(|id| id.to_string()).or_else(|| {
+        match path.components().next() {
+            Some(Component::Prefix(prefix)) => {
+                Some(prefix.as_os_str().to_string_lossy().to_uppercase())
+            }
+            _ => None,
+        }
+    })?;
+    Some((DeviceKey(key), existing.to_path_buf()))
+}
+
+#[cfg(unix)]
+fn device_id(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path).ok().map(|m| m.dev())
+}
+
+#[cfg(not(unix))]
+fn device_id(_path: &Path) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::{ArchiveFormat, ArchiveListing, ArchiveMember};
+    use crate::vfs::{insert_archive_members, FileNode};
+    use tempfile::TempDir;
+
+    fn scanned_vfs(root: &Path) -> ShadowVFS {
+        let mut vfs = ShadowVFS::new(root.to_path_buf());
+        let mut docs = FileNode::directory(root.join("docs"));
+        docs.parent = Some(root.to_path_buf());
+        let mut report = FileNode::file(root.join("docs/report.pdf"));
+        report.parent = Some(root.join("docs"));
+        report.size = 4096;
+        docs.add_child(root.join("docs/report.pdf"));
+        if let Some(node) = vfs.get_mut(&root.to_path_buf()) {
+            node.add_child(root.join("docs"));
+        }
+        vfs.insert(docs);
+        vfs.insert(report);
+        vfs
+    }
+
+    #[test]
+    fn test_same_device_move_is_a_rename() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("docs/report.pdf"), vec![0u8; 4096]).unwrap();
+        let vfs = scanned_vfs(&root);
+
+        let report = dry_run_plan(
+            &vfs,
+            &root,
+            vec![
+                SimulatedOperation::create_folder(root.join("archive").to_string_lossy()),
+                SimulatedOperation::move_op(
+                    root.join("docs").to_string_lossy(),
+                    root.join("archive/docs").to_string_lossy(),
+                ),
+            ],
+        );
+
+        assert!(report.valid, "{:?}", report);
+        assert_eq!(report.renames, 1);
+        assert_eq!(report.cross_device_moves, 0);
+        assert_eq!(report.bytes_to_copy, 0);
+        assert_eq!(report.operations[1].bytes, 4096);
+        assert!(report.devices.is_empty());
+        assert_eq!(report.estimated_duration_ms, 2 * METADATA_OP_MS);
+    }
+
+    #[test]
+    fn test_archive_members_are_not_counted_twice() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("docs")).unwrap();
+        let mut vfs = scanned_vfs(&root);
+        let archive = root.join("docs/backup.zip");
+        vfs.insert_linked(FileNode::file(archive.clone()).with_size(1000));
+        let listing = ArchiveListing {
+            format: ArchiveFormat::Zip,
+            members: vec![ArchiveMember {
+                name: "2024/march.pdf".to_string(),
+                size: 3000,
+                modified_at: None,
+                is_dir: false,
+            }],
+            truncated: false,
+        };
+        insert_archive_members(&mut vfs, &archive, &listing, &HashMap::new());
+
+        let report = dry_run_plan(
+            &vfs,
+            &root,
+            vec![SimulatedOperation::move_op(
+                root.join("docs").to_string_lossy(),
+                root.join("moved").to_string_lossy(),
+            )],
+        );
+
+        assert!(report.valid, "{:?}", report);
+        // report.pdf and the zip itself, not the member inside it
+        assert_eq!(report.operations[0].bytes, 4096 + 1000);
+    }
+
+    #[test]
+    fn test_delete_is_classified_against_the_quarantine() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("docs/report.pdf"), vec![0u8; 4096]).unwrap();
+        let vfs = scanned_vfs(&root);
+        let quarantine = QuarantineManager::with_config(temp.path().join("quarantine"), 30);
+
+        let report = dry_run_plan_with_quarantine(
+            &vfs,
+            &root,
+            vec![SimulatedOperation::delete(root.join("docs").to_string_lossy())],
+            Some(&quarantine),
+        );
+
+        assert!(report.valid, "{:?}", report);
+        assert_eq!(report.operations[0].kind, ImpactKind::Delete);
+        // Folder size comes from the scanned files under it
+        assert_eq!(report.operations[0].bytes, 4096);
+        // The quarantine shares the folder's device, so nothing is copied
+        assert_eq!(report.operations[0].bytes_copied, 0);
+        assert!(report.devices.is_empty());
+    }
+
+    #[test]
+    fn test_rejected_destination_is_flagged() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("docs")).unwrap();
+        let vfs = scanned_vfs(&root);
+
+        let outside = temp.path().parent().unwrap().join("escaped.pdf");
+        let report = dry_run_plan(
+            &vfs,
+            &root,
+            vec![SimulatedOperation::move_op(
+                root.join("docs/report.pdf").to_string_lossy(),
+                outside.to_string_lossy(),
+            )],
+        );
+
+        assert!(!report.valid);
+        assert_eq!(report.rejected_operations, 1);
+        let error = report.operations[0].error.as_deref().unwrap();
+        assert!(error.contains("escapes root"), "{}", error);
+        // The VFS itself is left alone
+        assert!(!vfs.has_staged_operations());
+    }
+}
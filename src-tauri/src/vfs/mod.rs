@@ -3,13 +3,22 @@
 //! Provides an in-memory shadow filesystem that mirrors the real filesystem.
 //! This enables simulation of file operations before committing changes,
 //! allowing for validation, conflict detection, and undo/redo capabilities.
+//!
+//! This is the single planning model: the organize agent builds its plans
+//! here and the `vfs_*` plan commands replay them with the same rules.
 
+pub mod archive;
 pub mod graph;
+pub mod impact;
 pub mod node;
+pub mod planning;
 pub mod scanner;
 pub mod simulator;
 
+pub use archive::*;
 pub use graph::*;
+pub use impact::*;
 pub use node::*;
+pub use planning::*;
 pub use scanner::*;
 pub use simulator::*;
@@ -3,6 +3,7 @@
 //! Represents individual nodes in the virtual filesystem tree.
 //! Each node can be a file, directory, or symlink with associated metadata.
 
+use crate::media::PhotoMetadata;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
@@ -62,7 +63,8 @@ pub struct FileNode {
     /// Parent directory path (None for root)
     pub parent: Option<PathBuf>,
 
-    /// Child paths (only populated for directories)
+    /// Child paths (populated for directories, and for archives whose
+    /// members were indexed)
     pub children: Vec<PathBuf>,
 
     /// Whether this node has staged (uncommitted) changes
@@ -74,6 +76,17 @@ pub struct FileNode {
 
     /// Whether this file/directory is hidden
     pub is_hidden: bool,
+
+    /// Capture metadata (EXIF/XMP) for photos
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exif: Option<PhotoMetadata>,
+
+    /// Archive file this node lives in, for virtual archive members
+    ///
+    /// Members have virtual paths below the archive (e.g.
+    /// `backup.zip/2024/march.pdf`) and don't exist on disk.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive: Option<PathBuf>,
 }
 
 impl FileNode {
@@ -108,6 +121,8 @@ impl FileNode {
             is_staged: false,
             original_path: None,
             is_hidden,
+            exif: None,
+            archive: None,
         }
     }
 
@@ -141,6 +156,11 @@ impl FileNode {
         self.node_type == VFSNodeType::Symlink
     }
 
+    /// Check if this node is a virtual member of an archive
+    pub fn is_archive_member(&self) -> bool {
+        self.archive.is_some()
+    }
+
     /// Set the parent path
     pub fn with_parent(mut self, parent: PathBuf) -> Self {
         self.parent = Some(parent);
@@ -183,6 +203,12 @@ impl FileNode {
         self
     }
 
+    /// Mark as a virtual member of an archive
+    pub fn with_archive(mut self, archive: PathBuf) -> Self {
+        self.archive = Some(archive);
+        self
+    }
+
     /// Mark as staged with original path
     pub fn mark_staged(&mut self, original: PathBuf) {
         self.is_staged = true;
@@ -0,0 +1,232 @@
+//! Plan Construction
+//!
+//! Helpers for building an organize plan directly on the shadow VFS.
+//! Planners (such as the organize agent) stage every operation they emit
+//! here, so the collision and coverage rules are exactly the ones
+//! `validate_plan` applies when the finished plan is replayed.
+
+use std::path::{Path, PathBuf};
+
+use super::graph::{ShadowVFS, VFSError};
+
+/// Where a placed file ends up
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placement {
+    /// Folders staged for creation to hold the file, parents first
+    pub created_folders: Vec<PathBuf>,
+    /// Final destination, with a counter suffix if the name was taken
+    pub destination: PathBuf,
+}
+
+impl ShadowVFS {
+    /// Stage creation of a folder and any missing ancestors
+    ///
+    /// Returns the newly staged folders, parents first. Folders that exist
+    /// or are already staged are not returned.
+    pub fn stage_create_folder_all(&mut self, path: &Path) -> Result<Vec<PathBuf>, VFSError> {
+        if !path.starts_with(self.root()) {
+            return Err(VFSError::InvalidOperation(format!(
+                "{} is outside {}",
+                path.display(),
+                self.root().display()
+            )));
+        }
+
+        let mut missing = Vec::new();
+        for ancestor in path.ancestors() {
+            if ancestor == self.root().as_path() || self.staged_creates().contains(ancestor) {
+                break;
+            }
+            match self.get(&ancestor.to_path_buf()) {
+                Some(node) if node.is_directory() => break,
+                Some(_) => {
+                    return Err(VFSError::InvalidOperation(format!(
+                        "{} is not a directory",
+                        ancestor.display()
+                    )))
+                }
+                None => missing.push(ancestor.to_path_buf()),
+            }
+        }
+
+        missing.reverse();
+        for folder in &missing {
+            self.stage_create_folder(folder.clone())?;
+        }
+        Ok(missing)
+    }
+
+    /// Find a free path for `file_name` in `folder`
+    ///
+    /// Appends a counter on collision: file.pdf -> file_1.pdf, file_2.pdf, ...
+    pub fn unique_destination(&self, folder: &Path, file_name: &str) -> PathBuf {
+        let candidate = folder.join(file_name);
+        if !self.is_claimed(&candidate) {
+            return candidate;
+        }
+
+        let name = Path::new(file_name);
+        let stem = name
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| file_name.to_string());
+        let ext = name
+            .extension()
+            .map(|e| format!(".{}", e.to_string_lossy()))
+            .unwrap_or_default();
+
+        for counter in 1..=1000 {
+            let candidate = folder.join(format!("{}_{}{}", stem, counter, ext));
+            if !self.is_claimed(&candidate) {
+                return candidate;
+            }
+        }
+
+        // Safety limit - use UUID suffix
+        folder.join(format!("{}_{}{}", stem, uuid::Uuid::new_v4(), ext))
+    }
+
+    /// Stage moving `source` into `folder` as `file_name`
+    ///
+    /// Creates missing folders and resolves name collisions. Returns None
+    /// (and only marks the source as covered) when it is already in place.
+    pub fn stage_placement(
+        &mut self,
+        source: &Path,
+        folder: &Path,
+        file_name: &str,
+    ) -> Result<Option<Placement>, VFSError> {
+        if folder.join(file_name) == source {
+            self.mark_covered(source.to_path_buf());
+            return Ok(None);
+        }
+
+        // Checked up front so a rejected move leaves no folders staged
+        if !self.exists(&source.to_path_buf()) {
+            return Err(VFSError::PathNotFound(source.display().to_string()));
+        }
+        if self.staged_moves().contains_key(source) {
+            return Err(VFSError::InvalidOperation(format!(
+                "{} is already staged for a move",
+                source.display()
+            )));
+        }
+        if folder.starts_with(source) {
+            return Err(VFSError::CycleDetected {
+                source_path: source.display().to_string(),
+                target_path: folder.display().to_string(),
+            });
+        }
+
+        let created_folders = self.stage_create_folder_all(folder)?;
+        let destination = self.unique_destination(folder, file_name);
+        self.stage_move(source.to_path_buf(), destination.clone())?;
+
+        Ok(Some(Placement {
+            created_folders,
+            destination,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::{validate_plan, FileNode, SimulatedOperation};
+
+    fn create_test_vfs() -> ShadowVFS {
+        let mut vfs = ShadowVFS::new(PathBuf::from("/root"));
+        vfs.insert_linked(FileNode::directory(PathBuf::from("/root/a")));
+        vfs.insert_linked(FileNode::directory(PathBuf::from("/root/b")));
+        vfs.insert_linked(FileNode::file(PathBuf::from("/root/a/report.pdf")));
+        vfs.insert_linked(FileNode::file(PathBuf::from("/root/b/report.pdf")));
+        vfs.insert_linked(FileNode::file(PathBuf::from("/root/notes.txt")));
+        vfs
+    }
+
+    #[test]
+    fn test_placement_resolves_collisions_and_creates_parents() {
+        let mut vfs = create_test_vfs();
+
+        let first = vfs
+            .stage_placement(
+                Path::new("/root/a/report.pdf"),
+                Path::new("/root/Docs/2024"),
+                "report.pdf",
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            first.created_folders,
+            vec![PathBuf::from("/root/Docs"), PathBuf::from("/root/Docs/2024")]
+        );
+        assert_eq!(first.destination, PathBuf::from("/root/Docs/2024/report.pdf"));
+
+        let second = vfs
+            .stage_placement(
+                Path::new("/root/b/report.pdf"),
+                Path::new("/root/Docs/2024"),
+                "report.pdf",
+            )
+            .unwrap()
+            .unwrap();
+        assert!(second.created_folders.is_empty());
+        assert_eq!(second.destination, PathBuf::from("/root/Docs/2024/report_1.pdf"));
+
+        // Already in place: covered, nothing staged
+        let in_place = vfs
+            .stage_placement(Path::new("/root/notes.txt"), Path::new("/root"), "notes.txt")
+            .unwrap();
+        assert!(in_place.is_none());
+        assert_eq!(vfs.coverage(), 1.0);
+        assert_eq!(vfs.staged_moves().len(), 2);
+    }
+
+    #[test]
+    fn test_rejected_placement_stages_nothing() {
+        let mut vfs = create_test_vfs();
+        let result = vfs.stage_placement(
+            Path::new("/root/missing.pdf"),
+            Path::new("/root/New"),
+            "missing.pdf",
+        );
+        assert!(matches!(result, Err(VFSError::PathNotFound(_))));
+        assert!(!vfs.has_staged_operations());
+
+        let result = vfs.stage_create_folder_all(Path::new("/root/notes.txt/inner"));
+        assert!(matches!(result, Err(VFSError::InvalidOperation(_))));
+    }
+
+    #[test]
+    fn test_planned_operations_replay_cleanly() {
+        let mut planned = create_test_vfs();
+        let fresh = planned.clone();
+        let mut operations = Vec::new();
+
+        for (source, folder) in [
+            ("/root/a/report.pdf", "/root/Docs/2024"),
+            ("/root/b/report.pdf", "/root/Docs/2024"),
+            ("/root/notes.txt", "/root/Docs"),
+        ] {
+            let source = Path::new(source);
+            let name = source.file_name().unwrap().to_string_lossy().to_string();
+            let placement = planned
+                .stage_placement(source, Path::new(folder), &name)
+                .unwrap()
+                .unwrap();
+            for created in placement.created_folders {
+                operations.push(SimulatedOperation::create_folder(
+                    created.to_string_lossy(),
+                ));
+            }
+            operations.push(SimulatedOperation::move_op(
+                source.to_string_lossy(),
+                placement.destination.to_string_lossy(),
+            ));
+        }
+
+        let validation = validate_plan(&fresh, operations);
+        assert!(validation.errors.is_empty(), "{:?}", validation.errors);
+        assert_eq!(validation.coverage, planned.coverage());
+    }
+}
@@ -5,13 +5,22 @@
 
 use chrono::{TimeZone, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
+use super::archive::insert_archive_members;
 use super::graph::ShadowVFS;
 use super::node::{FileNode, VFSNodeType};
+use crate::{archive, media};
+
+/// Archive members read for content previews, per archive
+const MAX_ARCHIVE_PREVIEWS: usize = 100;
+
+/// Largest archive member read for a content preview
+const MAX_ARCHIVE_PREVIEW_MEMBER: u64 = 1024 * 1024;
 
 /// Configuration for the VFS scanner
 #[derive(Debug, Clone)]
@@ -28,6 +37,15 @@ pub struct JWalkScanner {
     /// Whether to extract content previews
     extract_previews: bool,
 
+    /// Whether to read EXIF/XMP metadata of photos
+    extract_photo_metadata: bool,
+
+    /// Whether to list ZIP archives and add their members as nodes
+    index_archives: bool,
+
+    /// Whether to also index TAR/7z archives, which are read in full
+    index_streamed_archives: bool,
+
     /// File extensions to extract content from
     previewable_extensions: Vec<String>,
 }
@@ -39,6 +57,9 @@ impl Default for JWalkScanner {
             max_preview_size: 1024,
             num_threads: get_num_cpus().min(4),
             extract_previews: true,
+            extract_photo_metadata: true,
+            index_archives: true,
+            index_streamed_archives: false,
             previewable_extensions: vec![
                 "txt".to_string(),
                 "md".to_string(),
@@ -89,6 +110,14 @@ pub struct ScanStats {
     /// Number of content previews extracted
     pub content_previews_extracted: usize,
 
+    /// Number of photos with EXIF/XMP metadata
+    #[serde(default)]
+    pub photo_metadata_extracted: usize,
+
+    /// Number of archive members added as virtual nodes
+    #[serde(default)]
+    pub archive_members_indexed: usize,
+
     /// Number of files skipped due to errors
     pub errors: usize,
 }
@@ -123,6 +152,26 @@ impl JWalkScanner {
         self
     }
 
+    /// Enable or disable photo metadata (EXIF/XMP) extraction
+    pub fn with_extract_photo_metadata(mut self, extract: bool) -> Self {
+        self.extract_photo_metadata = extract;
+        self
+    }
+
+    /// Enable or disable indexing the members of archives
+    pub fn with_index_archives(mut self, index: bool) -> Self {
+        self.index_archives = index;
+        self
+    }
+
+    /// Also index TAR and 7z archives up to `archive::MAX_STREAMED_ARCHIVE_SIZE`
+    ///
+    /// Only takes effect while archive indexing is enabled.
+    pub fn with_index_streamed_archives(mut self, index: bool) -> Self {
+        self.index_streamed_archives = index;
+        self
+    }
+
     /// Scan a directory and populate the VFS
     ///
     /// Uses jwalk for parallel directory traversal, significantly
@@ -135,6 +184,8 @@ impl JWalkScanner {
             total_size_bytes: 0,
             scan_duration_ms: 0,
             content_previews_extracted: 0,
+            photo_metadata_extracted: 0,
+            archive_members_indexed: 0,
             errors: 0,
         };
 
@@ -179,7 +230,22 @@ impl JWalkScanner {
                                 }
                             }
 
+                            let is_archive = self.indexes_archive(&node);
                             vfs.insert(node);
+
+                            if is_archive {
+                                match self.index_archive(&path, vfs) {
+                                    Ok(count) => stats.archive_members_indexed += count,
+                                    Err(e) => {
+                                        eprintln!(
+                                            "[VFS Scanner] Error indexing archive {}: {}",
+                                            path.display(),
+                                            e
+                                        );
+                                        stats.errors += 1;
+                                    }
+                                }
+                            }
                         }
                         Err(e) => {
                             eprintln!("[VFS Scanner] Error processing {}: {}", path.display(), e);
@@ -270,9 +336,59 @@ impl JWalkScanner {
             }
         }
 
+        // Read capture metadata for photos
+        if self.extract_photo_metadata && node_type == VFSNodeType::File {
+            node.exif = media::read_photo_metadata(&path);
+            if node.exif.is_some() {
+                stats.photo_metadata_extracted += 1;
+            }
+        }
+
         Ok(node)
     }
 
+    /// Whether the members of a scanned file get indexed
+    fn indexes_archive(&self, node: &FileNode) -> bool {
+        if !self.index_archives || !node.is_file() {
+            return false;
+        }
+        match archive::ArchiveFormat::detect(&node.path) {
+            Some(format) if format.has_index() => true,
+            Some(_) => {
+                self.index_streamed_archives && node.size <= archive::MAX_STREAMED_ARCHIVE_SIZE
+            }
+            None => false,
+        }
+    }
+
+    /// List an archive and insert its members below the archive node
+    ///
+    /// Previews of previewable members are read in the same pass, in memory
+    /// only.
+    fn index_archive(&self, path: &Path, vfs: &mut ShadowVFS) -> Result<usize, String> {
+        let wanted = |m: &archive::ArchiveMember| {
+            m.size <= MAX_ARCHIVE_PREVIEW_MEMBER
+                && m.extension().is_some_and(|e| self.is_previewable(&e))
+        };
+        let limit = if self.extract_previews {
+            MAX_ARCHIVE_PREVIEWS
+        } else {
+            0
+        };
+        let (listing, contents) = archive::list_and_read(path, &wanted, limit)?;
+
+        let mut previews = HashMap::new();
+        for (member, bytes) in contents {
+            let end = bytes.len().min(self.max_preview_size);
+            let preview = String::from_utf8_lossy(&bytes[..end]).trim().to_string();
+            if !preview.is_empty() {
+                previews.insert(member.name, preview);
+            }
+        }
+
+        Ok(insert_archive_members(vfs, path, &listing, &previews))
+    }
+
     /// Get a content preview from a file
     ///
     /// Returns the first `max_bytes` of a text file, or None if:
@@ -397,6 +513,64 @@ mod tests {
         assert_eq!(stats.total_files, 2);
     }
 
+    #[tokio::test]
+    async fn test_index_archives() {
+        let temp_dir = create_test_dir();
+        let root = temp_dir.path().to_path_buf();
+
+        let mut zip = zip::ZipWriter::new(File::create(root.join("old.zip")).unwrap());
+        zip.start_file("letters/note.txt", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"Dear landlord").unwrap();
+        zip.finish().unwrap();
+
+        let mut vfs = ShadowVFS::new(root.clone());
+        let stats = JWalkScanner::new().scan(&root, &mut vfs).await.unwrap();
+        assert_eq!(stats.archive_members_indexed, 1);
+
+        let member = vfs.get(&root.join("old.zip/letters/note.txt")).unwrap();
+        assert_eq!(member.archive, Some(root.join("old.zip")));
+        assert_eq!(member.content_preview.as_deref(), Some("Dear landlord"));
+
+        let mut vfs = ShadowVFS::new(root.clone());
+        let stats = JWalkScanner::new()
+            .with_index_archives(false)
+            .scan(&root, &mut vfs)
+            .await
+            .unwrap();
+        assert_eq!(stats.archive_members_indexed, 0);
+        assert!(vfs.get(&root.join("old.zip/letters")).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_streamed_archives_are_opt_in() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().to_path_buf();
+
+        let mut tar = tar::Builder::new(File::create(root.join("old.tar")).unwrap());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, "note.txt", &b"hello"[..])
+            .unwrap();
+        tar.finish().unwrap();
+
+        let mut vfs = ShadowVFS::new(root.clone());
+        let stats = JWalkScanner::new().scan(&root, &mut vfs).await.unwrap();
+        assert_eq!(stats.archive_members_indexed, 0);
+
+        let mut vfs = ShadowVFS::new(root.clone());
+        let stats = JWalkScanner::new()
+            .with_index_streamed_archives(true)
+            .scan(&root, &mut vfs)
+            .await
+            .unwrap();
+        assert_eq!(stats.archive_members_indexed, 1);
+        let member = vfs.get(&root.join("old.tar/note.txt")).unwrap();
+        assert_eq!(member.content_preview.as_deref(), Some("hello"));
+    }
+
     #[test]
     fn test_is_previewable() {
         let scanner = JWalkScanner::new();
@@ -125,8 +125,9 @@ pub fn apply_all_staged(vfs: &mut ShadowVFS) -> Result<(), Vec<VFSError>> {
                 node.parent = Some(new_parent);
             }
 
-            // If this is a directory, update children paths recursively
-            if node.is_directory() {
+            // If this is a directory (or an archive with indexed members),
+            // update children paths recursively
+            if node.is_directory() || !node.children.is_empty() {
                 update_children_paths(vfs, &src, &dest);
             }
 
@@ -183,6 +184,15 @@ fn update_children_paths(vfs: &mut ShadowVFS, old_parent: &Path, new_parent: &Pa
         if let Some(mut node) = vfs.remove(&old_path) {
             node.path = new_path.clone();
 
+            // Archive members follow their archive
+            if let Some(archive) = node.archive.take() {
+                node.archive = Some(match archive.strip_prefix(old_parent) {
+                    Ok(relative) if !relative.as_os_str().is_empty() => new_parent.join(relative),
+                    Ok(_) => new_parent.to_path_buf(),
+                    Err(_) => archive,
+                });
+            }
+
             // Update parent reference
             if let Some(parent) = new_path.parent() {
                 node.parent = Some(parent.to_path_buf());
@@ -236,17 +246,7 @@ pub fn simulate_plan(
     let mut errors = Vec::new();
 
     for op in operations {
-        let result = match op {
-            SimulatedOperation::Move { source, destination } => {
-                simulate_move(vfs, PathBuf::from(&source), PathBuf::from(&destination))
-            }
-            SimulatedOperation::CreateFolder { path } => {
-                simulate_create_folder(vfs, PathBuf::from(&path))
-            }
-            SimulatedOperation::Delete { path } => simulate_delete(vfs, PathBuf::from(&path)),
-        };
-
-        if let Err(e) = result {
+        if let Err(e) = simulate_operation(vfs, op) {
             errors.push(e.to_string());
         }
     }
@@ -258,6 +258,45 @@ pub fn simulate_plan(
     }
 }
 
+/// Stage a single simulated operation
+pub fn simulate_operation(vfs: &mut ShadowVFS, op: SimulatedOperation) -> Result<(), VFSError> {
+    match op {
+        SimulatedOperation::Move { source, destination } => {
+            simulate_move(vfs, PathBuf::from(&source), PathBuf::from(&destination))
+        }
+        SimulatedOperation::CreateFolder { path } => {
+            simulate_create_folder(vfs, PathBuf::from(&path))
+        }
+        SimulatedOperation::Delete { path } => simulate_delete(vfs, PathBuf::from(&path)),
+    }
+}
+
+/// Outcome of validating a plan against a VFS
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanValidation {
+    /// Error messages for operations that would fail
+    pub errors: Vec<String>,
+    /// Fraction of files the plan accounts for
+    pub coverage: f64,
+}
+
+/// Validate a plan without changing the VFS
+///
+/// The plan is replayed on a copy, on top of anything already staged, so
+/// validating the same plan twice gives the same result.
+pub fn validate_plan(vfs: &ShadowVFS, operations: Vec<SimulatedOperation>) -> PlanValidation {
+    let mut scratch = vfs.clone();
+    let mut errors = simulate_plan(&mut scratch, operations).err().unwrap_or_default();
+    if let Err(conflicts) = scratch.validate_staged() {
+        errors.extend(conflicts.iter().map(|e| e.to_string()));
+    }
+
+    PlanValidation {
+        errors,
+        coverage: scratch.coverage(),
+    }
+}
+
 /// Rollback all staged changes without applying them
 pub fn rollback_staged(vfs: &mut ShadowVFS) {
     vfs.clear_staged();
@@ -404,6 +443,22 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_validate_plan_is_repeatable() {
+        let vfs = create_test_vfs();
+        let operations = vec![
+            SimulatedOperation::create_folder("/root/projects"),
+            SimulatedOperation::move_op("/root/docs/readme.txt", "/root/projects/readme.txt"),
+        ];
+
+        let first = validate_plan(&vfs, operations.clone());
+        let second = validate_plan(&vfs, operations);
+        assert!(first.errors.is_empty());
+        assert_eq!(first, second);
+        assert_eq!(first.coverage, 1.0);
+        assert!(!vfs.has_staged_operations());
+    }
+
     #[test]
     fn test_simulate_plan_with_errors() {
         let mut vfs = create_test_vfs();
@@ -8,6 +8,8 @@ use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use uuid::Uuid;
 
+use crate::quarantine::QuarantineManager;
+
 /// Status of a WAL entry
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -39,9 +41,21 @@ pub enum WALOperationType {
     /// Rename a file or folder
     Rename { path: PathBuf, new_name: String },
     /// Move a file to quarantine (temporary holding area)
+    ///
+    /// With an `item_id` the quarantine manager stores the item under that
+    /// manifest ID and `quarantine_path` is its quarantine root. Journals
+    /// from older versions have none and move the item to `quarantine_path`.
     Quarantine {
         path: PathBuf,
         quarantine_path: PathBuf,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        item_id: Option<String>,
+    },
+    /// Restore quarantine manifest item `item_id` to `path`
+    Restore {
+        item_id: String,
+        path: PathBuf,
+        quarantine_path: PathBuf,
     },
     /// Copy a file or folder from source to destination
     Copy {
@@ -53,6 +67,16 @@ pub enum WALOperationType {
 }
 
 impl WALOperationType {
+    /// Quarantine `path` through the quarantine manager as a new item
+    pub fn quarantine(path: PathBuf) -> Result<WALOperationType, String> {
+        let manager = QuarantineManager::new()?;
+        Ok(WALOperationType::Quarantine {
+            path,
+            quarantine_path: manager.base_path().clone(),
+            item_id: Some(Uuid::new_v4().to_string()),
+        })
+    }
+
     /// Generate the inverse (undo) operation for this operation type.
     ///
     /// Returns an operation that, when executed, will reverse the effect
@@ -97,6 +121,16 @@ impl WALOperationType {
             WALOperationType::Quarantine {
                 path,
                 quarantine_path,
+                item_id: Some(item_id),
+            } => Ok(WALOperationType::Restore {
+                item_id: item_id.clone(),
+                path: path.clone(),
+                quarantine_path: quarantine_path.clone(),
+            }),
+            WALOperationType::Quarantine {
+                path,
+                quarantine_path,
+                item_id: None,
             } => {
                 // Inverse of quarantine is move back from quarantine
                 Ok(WALOperationType::Move {
@@ -104,6 +138,15 @@ impl WALOperationType {
                     destination: path.clone(),
                 })
             }
+            WALOperationType::Restore {
+                item_id,
+                path,
+                quarantine_path,
+            } => Ok(WALOperationType::Quarantine {
+                path: path.clone(),
+                quarantine_path: quarantine_path.clone(),
+                item_id: Some(item_id.clone()),
+            }),
             WALOperationType::Copy {
                 source: _,
                 destination,
@@ -150,6 +193,7 @@ impl WALOperationType {
             WALOperationType::Quarantine {
                 path,
                 quarantine_path,
+                ..
             } => {
                 format!(
                     "Quarantine {} -> {}",
@@ -157,6 +201,9 @@ impl WALOperationType {
                     quarantine_path.display()
                 )
             }
+            WALOperationType::Restore { path, .. } => {
+                format!("Restore {} from quarantine", path.display())
+            }
             WALOperationType::Copy {
                 source,
                 destination,
@@ -438,6 +485,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_quarantine_inverse() {
+        let op = WALOperationType::Quarantine {
+            path: PathBuf::from("/docs/old.txt"),
+            quarantine_path: PathBuf::from("/quarantine"),
+            item_id: Some("item-1".to_string()),
+        };
+        let inverse = op.inverse().unwrap();
+        assert_eq!(
+            inverse,
+            WALOperationType::Restore {
+                item_id: "item-1".to_string(),
+                path: PathBuf::from("/docs/old.txt"),
+                quarantine_path: PathBuf::from("/quarantine"),
+            }
+        );
+        assert_eq!(inverse.inverse().unwrap(), op);
+
+        // Journals from older versions have no item ID and move back
+        let legacy: WALOperationType = serde_json::from_value(serde_json::json!({
+            "type": "quarantine",
+            "path": "/docs/old.txt",
+            "quarantine_path": "/quarantine/op-1",
+        }))
+        .unwrap();
+        assert_eq!(
+            legacy.inverse().unwrap(),
+            WALOperationType::Move {
+                source: PathBuf::from("/quarantine/op-1"),
+                destination: PathBuf::from("/docs/old.txt"),
+            }
+        );
+    }
+
     #[test]
     fn test_rename_inverse_error_no_filename() {
         // Root path has no filename
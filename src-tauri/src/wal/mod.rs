@@ -9,6 +9,7 @@
 //! - `io` - Safe I/O utilities (atomic writes, fsync, symlink detection)
 //! - `journal` - Journal persistence with file locking
 //! - `recovery` - Recovery operations for interrupted jobs
+//! - `segment` - Append-only, checksummed on-disk record format
 
 #![allow(dead_code)]
 #![allow(unused_imports)]
@@ -17,6 +18,7 @@ pub mod entry;
 pub mod io;
 pub mod journal;
 pub mod recovery;
+pub mod segment;
 
 pub use entry::*;
 pub use io::{atomic_write, copy_dir_safe, file_type_no_follow, is_symlink, FileTypeInfo, SafeIoError};
@@ -11,6 +11,7 @@
 use super::entry::{WALJournal, WALOperationType, WALStatus};
 use super::io::{copy_dir_safe, is_symlink};
 use super::journal::WALManager;
+use crate::quarantine::{move_path, QuarantineManager};
 use crate::security::PathValidator;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -58,6 +59,11 @@ pub struct RecoveryResult {
 pub fn check_for_recovery() -> Result<Option<RecoveryInfo>, String> {
     let manager = WALManager::new();
 
+    // Convert journals written by older versions to the segment format
+    if let Err(e) = manager.migrate_legacy_journals() {
+        tracing::warn!(error = %e, "Failed to migrate legacy WAL journals");
+    }
+
     let journal = match manager.find_incomplete_journal() {
         Ok(Some(j)) => j,
         Ok(None) => return Ok(None),
@@ -133,8 +139,8 @@ pub fn resume_journal(job_id: &str) -> Result<RecoveryResult, String> {
         // Mark as in progress
         if let Some(e) = journal.get_entry_mut(entry_id) {
             e.mark_in_progress();
+            manager.record_entry_status(job_id, e).map_err(|e| e.message)?;
         }
-        manager.save_journal(&journal).map_err(|e| e.message)?;
 
         // Execute the operation
         match execute_operation(&entry.operation) {
@@ -155,7 +161,9 @@ pub fn resume_journal(job_id: &str) -> Result<RecoveryResult, String> {
             }
         }
 
-        manager.save_journal(&journal).map_err(|e| e.message)?;
+        if let Some(e) = journal.get_entry(entry_id) {
+            manager.record_entry_status(job_id, e).map_err(|e| e.message)?;
+        }
     }
 
     // If all complete, discard the journal
@@ -238,7 +246,9 @@ pub fn rollback_journal(job_id: &str) -> Result<RecoveryResult, String> {
             }
         }
 
-        manager.save_journal(&journal).map_err(|e| e.message)?;
+        if let Some(e) = journal.get_entry(entry_id) {
+            manager.record_entry_status(job_id, e).map_err(|e| e.message)?;
+        }
     }
 
     // Also mark any pending entries as rolled back
@@ -337,23 +347,8 @@ fn execute_operation(operation: &WALOperationType) -> Result<(), String> {
                 return Err(format!("Cannot move protected path: {}", source.display()));
             }
 
-            // Try rename first (same filesystem), fall back to copy+delete
-            if fs::rename(source, destination).is_err() {
-                if source.is_dir() {
-                    // Use symlink-safe copy
-                    copy_dir_safe(source, destination)
-                        .map_err(|e| format!("Failed to copy directory: {}", e))?;
-                    fs::remove_dir_all(source)
-                        .map_err(|e| format!("Failed to remove source: {}", e))?;
-                } else {
-                    fs::copy(source, destination)
-                        .map_err(|e| format!("Failed to copy: {}", e))?;
-                    fs::remove_file(source)
-                        .map_err(|e| format!("Failed to remove source: {}", e))?;
-                }
-            }
-
-            Ok(())
+            // Rename, or copy, verify and delete across filesystems
+            move_path(source, destination)
         }
 
         WALOperationType::Rename { path, new_name } => {
@@ -384,15 +379,29 @@ fn execute_operation(operation: &WALOperationType) -> Result<(), String> {
         WALOperationType::Quarantine {
             path,
             quarantine_path,
+            item_id,
         } => {
-            // Security: Check path is not a symlink before quarantine
-            ensure_not_symlink(path, "quarantine")?;
-
-            // Quarantine is just a move to a special location
-            execute_operation(&WALOperationType::Move {
-                source: path.clone(),
-                destination: quarantine_path.clone(),
-            })
+            let Some(item_id) = item_id else {
+                // Security: Check path is not a symlink before quarantine
+                ensure_not_symlink(path, "quarantine")?;
+
+                // Older journals quarantine by moving to a special location
+                return execute_operation(&WALOperationType::Move {
+                    source: path.clone(),
+                    destination: quarantine_path.clone(),
+                });
+            };
+
+            if PathValidator::is_protected_path(path) {
+                return Err(format!("Cannot quarantine protected path: {}", path.display()));
+            }
+            QuarantineManager::new()?
+                .quarantine_with_id(path, item_id)
+                .map(|_| ())
+        }
+
+        WALOperationType::Restore { item_id, path, .. } => {
+            QuarantineManager::new()?.restore_to(item_id, path)
         }
 
         WALOperationType::Copy {
@@ -0,0 +1,444 @@
+//! Append-only WAL segment format
+//!
+//! A segment file (`{job_id}.wal.log`) replaces the rewrite-per-transition
+//! JSON journal. It is a small file header followed by framed records:
+//!
+//! ```text
+//! file header:  "SWAL" | version (u32 LE)
+//! record:       payload length (u32 LE) | CRC32 of payload (u32 LE) | JSON payload
+//! ```
+//!
+//! The first record is always the journal header. Entry records carry a full
+//! `WALEntry`; status records carry a single state transition, so marking an
+//! entry complete is one small append instead of a full journal rewrite.
+//!
+//! ## Crash Safety
+//! A crash mid-append can leave a truncated or partially written final
+//! record. Replay stops at the last intact record and reports the valid
+//! length so the next append truncates the torn tail. A bad record followed
+//! by more data is real corruption and is reported as an error.
+
+use super::entry::{WALEntry, WALJournal, WALStatus};
+use super::journal::{WALError, WALErrorKind, MAX_JOURNAL_ENTRIES};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Magic bytes at the start of every segment
+pub const SEGMENT_MAGIC: &[u8; 4] = b"SWAL";
+
+/// Current segment format version
+pub const SEGMENT_VERSION: u32 = 1;
+
+/// Size of the file header (magic + version)
+const FILE_HEADER_LEN: usize = 8;
+
+/// Size of a record frame header (length + checksum)
+const RECORD_HEADER_LEN: usize = 8;
+
+/// Maximum size of a single record payload (1 MB)
+const MAX_RECORD_SIZE: usize = 1024 * 1024;
+
+/// A single record in a segment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SegmentRecord {
+    /// Journal metadata (always the first record)
+    Header {
+        job_id: String,
+        target_folder: PathBuf,
+        started_at: DateTime<Utc>,
+        version: u32,
+    },
+    /// A journal entry, including its status at the time it was written
+    Entry { entry: WALEntry },
+    /// A status transition for a previously written entry
+    Status {
+        entry_id: Uuid,
+        status: WALStatus,
+        error: Option<String>,
+        updated_at: DateTime<Utc>,
+    },
+}
+
+impl SegmentRecord {
+    /// Build a status record from an entry's current state
+    pub fn status_of(entry: &WALEntry) -> Self {
+        SegmentRecord::Status {
+            entry_id: entry.id,
+            status: entry.status,
+            error: entry.error.clone(),
+            updated_at: entry.updated_at,
+        }
+    }
+}
+
+/// Result of replaying a segment
+#[derive(Debug, Clone)]
+pub struct SegmentReplay {
+    /// The journal reconstructed from all intact records
+    pub journal: WALJournal,
+    /// Length in bytes of the intact prefix (where the next append goes)
+    pub valid_len: u64,
+    /// Whether a torn final record was dropped
+    pub torn_tail: bool,
+    /// Number of status records applied (used to schedule compaction)
+    pub status_records: usize,
+}
+
+fn serialization_error(message: String) -> WALError {
+    WALError {
+        message,
+        kind: WALErrorKind::SerializationError,
+    }
+}
+
+fn io_error(message: String) -> WALError {
+    WALError {
+        message,
+        kind: WALErrorKind::IoError,
+    }
+}
+
+/// Encode a record into its framed on-disk form
+pub fn encode_record(record: &SegmentRecord) -> Result<Vec<u8>, WALError> {
+    let payload = serde_json::to_vec(record)
+        .map_err(|e| serialization_error(format!("Failed to serialize WAL record: {}", e)))?;
+
+    if payload.len() > MAX_RECORD_SIZE {
+        return Err(WALError {
+            message: format!(
+                "WAL record exceeds maximum size: {} bytes > {} bytes",
+                payload.len(),
+                MAX_RECORD_SIZE
+            ),
+            kind: WALErrorKind::LimitExceeded,
+        });
+    }
+
+    let mut frame = Vec::with_capacity(RECORD_HEADER_LEN + payload.len());
+    frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    frame.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+    frame.extend_from_slice(&payload);
+    Ok(frame)
+}
+
+/// Encode a whole journal as a compact segment (header + one record per entry)
+pub fn encode_journal(journal: &WALJournal) -> Result<Vec<u8>, WALError> {
+    let mut data = Vec::new();
+    data.extend_from_slice(SEGMENT_MAGIC);
+    data.extend_from_slice(&SEGMENT_VERSION.to_le_bytes());
+
+    data.extend(encode_record(&SegmentRecord::Header {
+        job_id: journal.job_id.clone(),
+        target_folder: journal.target_folder.clone(),
+        started_at: journal.started_at,
+        version: journal.version,
+    })?);
+
+    for entry in &journal.entries {
+        data.extend(encode_record(&SegmentRecord::Entry {
+            entry: entry.clone(),
+        })?);
+    }
+
+    Ok(data)
+}
+
+/// Replay a segment from its raw bytes
+pub fn replay_bytes(data: &[u8]) -> Result<SegmentReplay, WALError> {
+    if data.len() < FILE_HEADER_LEN || &data[0..4] != SEGMENT_MAGIC {
+        return Err(serialization_error(
+            "Not a WAL segment (bad magic)".to_string(),
+        ));
+    }
+
+    let version = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+    if version > SEGMENT_VERSION {
+        return Err(WALError {
+            message: format!("Unsupported WAL segment version: {}", version),
+            kind: WALErrorKind::InvalidState,
+        });
+    }
+
+    let mut journal: Option<WALJournal> = None;
+    let mut positions: HashMap<Uuid, usize> = HashMap::new();
+    let mut status_records = 0;
+    let mut torn_tail = false;
+    let mut offset = FILE_HEADER_LEN;
+
+    while offset < data.len() {
+        let remaining = data.len() - offset;
+        if remaining < RECORD_HEADER_LEN {
+            // Frame header itself was cut short
+            torn_tail = true;
+            break;
+        }
+
+        let header = &data[offset..offset + RECORD_HEADER_LEN];
+        let len = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let checksum = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        let end = offset + RECORD_HEADER_LEN + len;
+
+        if end > data.len() {
+            // Payload runs past end of file
+            torn_tail = true;
+            break;
+        }
+
+        let payload = &data[offset + RECORD_HEADER_LEN..end];
+        let record = if len <= MAX_RECORD_SIZE && crc32fast::hash(payload) == checksum {
+            serde_json::from_slice::<SegmentRecord>(payload).ok()
+        } else {
+            None
+        };
+
+        let record = match record {
+            Some(record) => record,
+            None if end == data.len() => {
+                // Final record was partially written
+                torn_tail = true;
+                break;
+            }
+            None => {
+                return Err(serialization_error(format!(
+                    "Corrupt WAL record at offset {}",
+                    offset
+                )));
+            }
+        };
+
+        match record {
+            SegmentRecord::Header {
+                job_id,
+                target_folder,
+                started_at,
+                version,
+            } => {
+                if journal.is_some() {
+                    return Err(serialization_error(format!(
+                        "Duplicate WAL header at offset {}",
+                        offset
+                    )));
+                }
+                journal = Some(WALJournal {
+                    job_id,
+                    target_folder,
+                    started_at,
+                    entries: Vec::new(),
+                    version,
+                });
+            }
+            SegmentRecord::Entry { entry } => {
+                let journal = journal.as_mut().ok_or_else(|| {
+                    serialization_error("WAL segment is missing its header".to_string())
+                })?;
+                if journal.entries.len() >= MAX_JOURNAL_ENTRIES {
+                    return Err(WALError {
+                        message: format!(
+                            "Journal exceeds maximum entry count: {}",
+                            MAX_JOURNAL_ENTRIES
+                        ),
+                        kind: WALErrorKind::LimitExceeded,
+                    });
+                }
+                positions.insert(entry.id, journal.entries.len());
+                journal.entries.push(entry);
+            }
+            SegmentRecord::Status {
+                entry_id,
+                status,
+                error,
+                updated_at,
+            } => {
+                let journal = journal.as_mut().ok_or_else(|| {
+                    serialization_error("WAL segment is missing its header".to_string())
+                })?;
+                match positions.get(&entry_id) {
+                    Some(&index) => {
+                        let entry = &mut journal.entries[index];
+                        entry.status = status;
+                        entry.error = error;
+                        entry.updated_at = updated_at;
+                    }
+                    None => {
+                        tracing::warn!(entry_id = %entry_id, "Ignoring WAL status for unknown entry");
+                    }
+                }
+                status_records += 1;
+            }
+        }
+
+        offset = end;
+    }
+
+    let journal = journal
+        .ok_or_else(|| serialization_error("WAL segment is missing its header".to_string()))?;
+
+    Ok(SegmentReplay {
+        journal,
+        valid_len: offset as u64,
+        torn_tail,
+        status_records,
+    })
+}
+
+/// Replay a segment file from disk
+pub fn replay_segment(path: &Path) -> Result<SegmentReplay, WALError> {
+    let data =
+        fs::read(path).map_err(|e| io_error(format!("Failed to read WAL segment: {}", e)))?;
+    replay_bytes(&data)
+}
+
+/// Append records to a segment at `valid_len`, dropping any torn tail.
+///
+/// Syncs the data before returning. Returns the new segment length.
+pub fn append_records(
+    path: &Path,
+    valid_len: u64,
+    records: &[SegmentRecord],
+) -> Result<u64, WALError> {
+    let mut frames = Vec::new();
+    for record in records {
+        frames.extend(encode_record(record)?);
+    }
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .open(path)
+        .map_err(|e| io_error(format!("Failed to open WAL segment: {}", e)))?;
+
+    let current_len = file
+        .metadata()
+        .map_err(|e| io_error(format!("Failed to stat WAL segment: {}", e)))?
+        .len();
+    if current_len > valid_len {
+        tracing::warn!(
+            path = %path.display(),
+            dropped_bytes = current_len - valid_len,
+            "Truncating torn WAL record"
+        );
+        file.set_len(valid_len)
+            .map_err(|e| io_error(format!("Failed to truncate WAL segment: {}", e)))?;
+    }
+
+    file.seek(SeekFrom::Start(valid_len))
+        .map_err(|e| io_error(format!("Failed to seek WAL segment: {}", e)))?;
+    file.write_all(&frames)
+        .map_err(|e| io_error(format!("Failed to append to WAL segment: {}", e)))?;
+    file.sync_data()
+        .map_err(|e| io_error(format!("Failed to sync WAL segment: {}", e)))?;
+
+    Ok(valid_len + frames.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wal::entry::WALOperationType;
+    use tempfile::tempdir;
+
+    fn test_journal(entries: usize) -> WALJournal {
+        let mut journal = WALJournal::new("seg-job".to_string(), PathBuf::from("/test"));
+        for i in 0..entries {
+            journal
+                .add_operation(WALOperationType::CreateFolder {
+                    path: PathBuf::from(format!("/test/folder-{}", i)),
+                })
+                .unwrap();
+        }
+        journal
+    }
+
+    #[test]
+    fn test_encode_and_replay_roundtrip() {
+        let journal = test_journal(3);
+        let data = encode_journal(&journal).unwrap();
+
+        let replay = replay_bytes(&data).unwrap();
+        assert_eq!(replay.journal.job_id, "seg-job");
+        assert_eq!(replay.journal.entries.len(), 3);
+        assert_eq!(replay.valid_len, data.len() as u64);
+        assert!(!replay.torn_tail);
+    }
+
+    #[test]
+    fn test_status_records_applied_in_order() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("seg.wal.log");
+        let mut journal = test_journal(2);
+        let data = encode_journal(&journal).unwrap();
+        fs::write(&path, &data).unwrap();
+
+        let entry_id = journal.entries[0].id;
+        journal.entries[0].mark_in_progress();
+        let len = append_records(
+            &path,
+            data.len() as u64,
+            &[SegmentRecord::status_of(&journal.entries[0])],
+        )
+        .unwrap();
+        journal.entries[0].mark_complete();
+        append_records(&path, len, &[SegmentRecord::status_of(&journal.entries[0])]).unwrap();
+
+        let replay = replay_segment(&path).unwrap();
+        assert_eq!(replay.status_records, 2);
+        assert_eq!(
+            replay.journal.get_entry(entry_id).unwrap().status,
+            WALStatus::Complete
+        );
+        assert_eq!(replay.journal.entries[1].status, WALStatus::Pending);
+    }
+
+    #[test]
+    fn test_torn_tail_is_dropped_and_truncated() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("seg.wal.log");
+        let mut journal = test_journal(1);
+        let mut data = encode_journal(&journal).unwrap();
+        let valid_len = data.len();
+
+        // Simulate a crash halfway through appending a status record
+        journal.entries[0].mark_complete();
+        let frame = encode_record(&SegmentRecord::status_of(&journal.entries[0])).unwrap();
+        data.extend_from_slice(&frame[..frame.len() / 2]);
+        fs::write(&path, &data).unwrap();
+
+        let replay = replay_segment(&path).unwrap();
+        assert!(replay.torn_tail);
+        assert_eq!(replay.valid_len, valid_len as u64);
+        assert_eq!(replay.journal.entries[0].status, WALStatus::Pending);
+
+        // Next append overwrites the torn bytes
+        append_records(
+            &path,
+            replay.valid_len,
+            &[SegmentRecord::status_of(&journal.entries[0])],
+        )
+        .unwrap();
+        let replay = replay_segment(&path).unwrap();
+        assert!(!replay.torn_tail);
+        assert_eq!(replay.journal.entries[0].status, WALStatus::Complete);
+    }
+
+    #[test]
+    fn test_corruption_before_tail_is_an_error() {
+        let journal = test_journal(2);
+        let mut data = encode_journal(&journal).unwrap();
+
+        // Flip a byte inside the header record's payload
+        data[FILE_HEADER_LEN + RECORD_HEADER_LEN + 2] ^= 0xFF;
+
+        let err = replay_bytes(&data).unwrap_err();
+        assert!(matches!(err.kind, WALErrorKind::SerializationError));
+    }
+
+    #[test]
+    fn test_rejects_bad_magic() {
+        assert!(replay_bytes(b"{\"jobId\":\"x\"}").is_err());
+    }
+}
@@ -3,6 +3,7 @@ import { readFile } from '@tauri-apps/plugin-fs';
 import { ChevronLeft, ChevronRight, X, Info, Loader2 } from 'lucide-react';
 import type { PhotoEntry } from '../../types/photo';
 import { cn, formatFileSize, formatAbsoluteDate } from '../../lib/utils';
+import { photoTimestamp } from '../../stores/photo-store';
 
 interface LightboxProps {
   photos: PhotoEntry[];
@@ -149,7 +150,7 @@ export function Lightbox({ photos, currentIndex, onClose, onNext, onPrev }: Ligh
           <div className="grid grid-cols-2 gap-4">
             <div>
               <p className="text-xs text-white/40 uppercase tracking-wider mb-1">Date</p>
-              <p className="text-sm">{formatAbsoluteDate(currentPhoto.createdAt || currentPhoto.modifiedAt)}</p>
+              <p className="text-sm">{formatAbsoluteDate(photoTimestamp(currentPhoto))}</p>
             </div>
             <div>
               <p className="text-xs text-white/40 uppercase tracking-wider mb-1">Size</p>
@@ -162,6 +163,16 @@ export function Lightbox({ photos, currentIndex, onClose, onNext, onPrev }: Ligh
             <p className="text-sm">{currentPhoto.extension?.toUpperCase() || 'Unknown'}</p>
           </div>
 
+          {currentPhoto.exif?.cameraModel && (
+            <div>
+              <p className="text-xs text-white/40 uppercase tracking-wider mb-1">Camera</p>
+              <p className="text-sm">{currentPhoto.exif.cameraModel}</p>
+              {currentPhoto.exif.lens && (
+                <p className="text-xs text-white/60">{currentPhoto.exif.lens}</p>
+              )}
+            </div>
+          )}
+
           <div>
             <p className="text-xs text-white/40 uppercase tracking-wider mb-1">Location</p>
             <p className="text-xs text-white/70 break-all leading-relaxed">{currentPhoto.path}</p>
@@ -11,7 +11,11 @@ import type {
   HistorySession,
   UndoPreflightResult,
   UndoResult,
+  RedoResult,
   ConflictResolution,
+  UndoSelection,
+  SelectiveUndoPreflight,
+  DriftReport,
   FolderIndexEntry,
 } from '../types/history';
 
@@ -36,6 +40,8 @@ interface HistoryState {
   isRunningPreflight: boolean;
   isUndoing: boolean;
   undoProgress: { completed: number; total: number } | null;
+  isRedoing: boolean;
+  redoProgress: { completed: number; total: number } | null;
 
   // Global folder list
   allFolders: FolderIndexEntry[];
@@ -58,6 +64,25 @@ interface HistoryActions {
   runPreflight: () => Promise<void>;
   executeUndo: (resolution: ConflictResolution) => Promise<UndoResult>;
 
+  // Selective undo of operations within one session
+  runSelectivePreflight: (
+    sessionId: string,
+    selection: UndoSelection
+  ) => Promise<SelectiveUndoPreflight | null>;
+  executeSelectiveUndo: (
+    sessionId: string,
+    selection: UndoSelection,
+    resolution: ConflictResolution
+  ) => Promise<UndoResult>;
+
+  // Redo of undone sessions (shares the modal's target session)
+  runRedoPreflight: () => Promise<void>;
+  executeRedo: () => Promise<RedoResult>;
+
+  // Drift since the last snapshot (null when there is none)
+  getDrift: (folderPath: string) => Promise<DriftReport | null>;
+  acceptDrift: (folderPath: string) => Promise<void>;
+
   // Management
   deleteHistory: (folderPath: string) => Promise<void>;
   loadAllFolders: () => Promise<void>;
@@ -85,6 +110,8 @@ export const useHistoryStore = create<HistoryStore>((set, get) => ({
   isRunningPreflight: false,
   isUndoing: false,
   undoProgress: null,
+  isRedoing: false,
+  redoProgress: null,
   allFolders: [],
 
   // Load history for a folder
@@ -188,6 +215,8 @@ export const useHistoryStore = create<HistoryStore>((set, get) => ({
       isRunningPreflight: false,
       isUndoing: false,
       undoProgress: null,
+      isRedoing: false,
+      redoProgress: null,
     });
   },
 
@@ -276,6 +305,195 @@ export const useHistoryStore = create<HistoryStore>((set, get) => ({
     }
   },
 
+  // Run preflight check before a selective undo
+  runSelectivePreflight: async (sessionId: string, selection: UndoSelection) => {
+    const { currentFolder } = get();
+    if (!currentFolder) return null;
+
+    set({ isRunningPreflight: true, error: null });
+
+    try {
+      const result = await invoke<SelectiveUndoPreflight>('history_selective_undo_preflight', {
+        folderPath: currentFolder,
+        sessionId,
+        selection,
+      });
+
+      set({ preflightResult: result.preflight, isRunningPreflight: false });
+      return result;
+    } catch (error) {
+      set({
+        error: String(error),
+        isRunningPreflight: false,
+      });
+      return null;
+    }
+  },
+
+  // Undo selected operations of a session
+  executeSelectiveUndo: async (
+    sessionId: string,
+    selection: UndoSelection,
+    resolution: ConflictResolution
+  ): Promise<UndoResult> => {
+    const { currentFolder } = get();
+    if (!currentFolder) {
+      return {
+        success: false,
+        operationsUndone: 0,
+        operationsSkipped: 0,
+        errors: ['No folder selected'],
+      };
+    }
+
+    set({ isUndoing: true, undoProgress: { completed: 0, total: 0 }, error: null });
+
+    // Set up progress listener
+    let unlisten: UnlistenFn | null = null;
+
+    try {
+      unlisten = await listen<{ completed: number; total: number }>(
+        'undo-progress',
+        (event) => {
+          set({ undoProgress: event.payload });
+        }
+      );
+
+      const result = await invoke<UndoResult>('history_selective_undo_execute', {
+        folderPath: currentFolder,
+        sessionId,
+        selection,
+        resolution,
+      });
+
+      // Reload history to pick up the session recording the partial undo
+      await get().loadHistory(currentFolder);
+
+      set({ isUndoing: false, preflightResult: null, undoProgress: null });
+
+      return result;
+    } catch (error) {
+      set({
+        error: String(error),
+        isUndoing: false,
+      });
+
+      return {
+        success: false,
+        operationsUndone: 0,
+        operationsSkipped: 0,
+        errors: [String(error)],
+      };
+    } finally {
+      if (unlisten) {
+        unlisten();
+      }
+    }
+  },
+
+  // Run preflight check before redo
+  runRedoPreflight: async () => {
+    const { currentFolder, targetSessionId } = get();
+    if (!currentFolder || !targetSessionId) return;
+
+    set({ isRunningPreflight: true, error: null });
+
+    try {
+      const result = await invoke<UndoPreflightResult>('history_redo_preflight', {
+        folderPath: currentFolder,
+        targetSessionId,
+      });
+
+      set({ preflightResult: result, isRunningPreflight: false });
+    } catch (error) {
+      set({
+        error: String(error),
+        isRunningPreflight: false,
+      });
+    }
+  },
+
+  // Execute redo operation
+  executeRedo: async (): Promise<RedoResult> => {
+    const { currentFolder, targetSessionId } = get();
+    if (!currentFolder || !targetSessionId) {
+      return {
+        success: false,
+        operationsRedone: 0,
+        operationsFailed: 0,
+        errors: ['No folder or session selected'],
+      };
+    }
+
+    set({ isRedoing: true, redoProgress: { completed: 0, total: 0 }, error: null });
+
+    // Set up progress listener
+    let unlisten: UnlistenFn | null = null;
+
+    try {
+      unlisten = await listen<{ completed: number; total: number }>(
+        'redo-progress',
+        (event) => {
+          set({ redoProgress: event.payload });
+        }
+      );
+
+      const result = await invoke<RedoResult>('history_redo_execute', {
+        folderPath: currentFolder,
+        targetSessionId,
+      });
+
+      // Reload history after redo
+      await get().loadHistory(currentFolder);
+
+      set({
+        isRedoing: false,
+        isUndoModalOpen: false,
+        targetSessionId: null,
+        targetSession: null,
+        preflightResult: null,
+        redoProgress: null,
+      });
+
+      return result;
+    } catch (error) {
+      set({
+        error: String(error),
+        isRedoing: false,
+      });
+
+      return {
+        success: false,
+        operationsRedone: 0,
+        operationsFailed: 0,
+        errors: [String(error)],
+      };
+    } finally {
+      if (unlisten) {
+        unlisten();
+      }
+    }
+  },
+
+  // Report how a folder changed since its last snapshot
+  getDrift: async (folderPath: string) => {
+    try {
+      return await invoke<DriftReport | null>('history_folder_drift', { folderPath });
+    } catch (error) {
+      set({ error: String(error) });
+      return null;
+    }
+  },
+
+  // Take the folder's current state as the new baseline
+  acceptDrift: async (folderPath: string) => {
+    try {
+      await invoke('history_snapshot_folder', { folderPath });
+    } catch (error) {
+      set({ error: String(error) });
+    }
+  },
+
   // Delete history for a folder
   deleteHistory: async (folderPath: string) => {
     try {
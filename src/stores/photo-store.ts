@@ -52,11 +52,16 @@ function formatDateLabel(dateKey: string): string {
   });
 }
 
+/** Capture time from EXIF when available, else filesystem time */
+export function photoTimestamp(photo: PhotoEntry): number | null {
+  return photo.exif?.takenAt ?? photo.createdAt ?? photo.modifiedAt;
+}
+
 function groupPhotosByDate(photos: PhotoEntry[]): PhotoGroup[] {
   const groups = new Map<string, PhotoEntry[]>();
 
   for (const photo of photos) {
-    const timestamp = photo.createdAt ?? photo.modifiedAt;
+    const timestamp = photoTimestamp(photo);
     if (!timestamp) continue;
 
     const dateKey = new Date(timestamp).toISOString().split('T')[0];
@@ -132,7 +137,7 @@ export const usePhotoStore = create<PhotoState & PhotoActions>((set, get) => ({
       let cmp = 0;
       switch (field) {
         case 'date':
-          cmp = (a.createdAt ?? 0) - (b.createdAt ?? 0);
+          cmp = (photoTimestamp(a) ?? 0) - (photoTimestamp(b) ?? 0);
           break;
         case 'name':
           cmp = a.name.localeCompare(b.name);
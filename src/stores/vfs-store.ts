@@ -1,7 +1,12 @@
 import { create } from 'zustand';
 import type { FileEntry } from '../types/file';
 import type { GhostFileEntry, GhostState } from '../types/ghost';
-import type { VfsEvent, ConflictPayload, IndexingProgressPayload } from '../types/vfs';
+import type {
+  VfsEvent,
+  ConflictPayload,
+  IndexingProgressPayload,
+  DryRunReport,
+} from '../types/vfs';
 import { isIndexingProgressPayload, isConflictPayload } from '../types/vfs';
 import type { OrganizePlan } from './organize/plan-store';
 
@@ -18,6 +23,8 @@ export interface VfsValidationResult {
   planHash: string;
   /** Plan ID for reference */
   planId: string;
+  /** Fraction of files the plan accounts for (0 to 1) */
+  coverage: number;
 }
 
 /**
@@ -71,6 +78,8 @@ interface VfsState {
   simulatedPlanId: string | null;
   /** Cached validation result from backend (includes plan hash for sync) */
   validationResult: VfsValidationResult | null;
+  /** Impact estimate from vfs_dry_run_plan for the current plan */
+  dryRunReport: DryRunReport | null;
 }
 
 interface VfsActions {
@@ -94,6 +103,8 @@ interface VfsActions {
   setValidationResult: (result: VfsValidationResult | null) => void;
   /** Get the cached plan hash (for sync validation) */
   getPlanHash: () => string | null;
+  /** Set dry-run impact estimate from backend */
+  setDryRunReport: (report: DryRunReport | null) => void;
 }
 
 /**
@@ -147,6 +158,7 @@ export const useVfsStore = create<VfsState & VfsActions>((set, get) => ({
   targetFolder: null,
   simulatedPlanId: null,
   validationResult: null,
+  dryRunReport: null,
 
   initializeVfs: (targetFolder: string) => {
     set({
@@ -159,6 +171,7 @@ export const useVfsStore = create<VfsState & VfsActions>((set, get) => ({
       conflicts: [],
       simulatedPlanId: null,
       validationResult: null,
+      dryRunReport: null,
     });
   },
 
@@ -354,6 +367,7 @@ export const useVfsStore = create<VfsState & VfsActions>((set, get) => ({
       targetFolder: null,
       simulatedPlanId: null,
       validationResult: null,
+      dryRunReport: null,
     });
   },
 
@@ -374,4 +388,8 @@ export const useVfsStore = create<VfsState & VfsActions>((set, get) => ({
   },
 
   getPlanHash: () => get().validationResult?.planHash ?? null,
+
+  setDryRunReport: (report: DryRunReport | null) => {
+    set({ dryRunReport: report });
+  },
 }));
@@ -23,6 +23,9 @@ export interface SessionSummary {
   filesAffected: number;
   operationCount: number;
   undone: boolean;
+  parentSessionId: string | null;
+  /** Whether this session is the current state of the folder */
+  isHead: boolean;
 }
 
 /**
@@ -33,6 +36,7 @@ export type OperationType =
   | 'move'
   | 'rename'
   | 'quarantine'
+  | 'restore'
   | 'copy'
   | 'delete_folder';
 
@@ -65,7 +69,8 @@ export type OperationRecord =
   | { type: 'create_folder'; path: string }
   | { type: 'move'; source: string; destination: string }
   | { type: 'rename'; path: string; newName: string }
-  | { type: 'quarantine'; path: string; quarantinePath: string }
+  | { type: 'quarantine'; path: string; quarantinePath: string; itemId?: string }
+  | { type: 'restore'; itemId: string; path: string; quarantinePath: string }
   | { type: 'copy'; source: string; destination: string }
   | { type: 'delete_folder'; path: string };
 
@@ -81,6 +86,7 @@ export interface HistorySession {
   operations: HistoryOperation[];
   filesAffected: number;
   undone: boolean;
+  parentSessionId?: string | null;
 }
 
 /**
@@ -126,6 +132,60 @@ export interface UndoResult {
   errors: string[];
 }
 
+/**
+ * Operations picked for a selective undo within one session
+ */
+export interface UndoSelection {
+  operationIds?: string[];
+  /** Undo everything moved, copied, renamed or created inside these folders */
+  folders?: string[];
+}
+
+/**
+ * Result of preflight check before a selective undo
+ */
+export interface SelectiveUndoPreflight {
+  preflight: UndoPreflightResult;
+  /** Every operation that will be undone, most recent first */
+  operationIds: string[];
+  /** Operations added to the selection because others depend on them */
+  dependentOperationIds: string[];
+}
+
+/**
+ * Result of redo execution
+ */
+export interface RedoResult {
+  success: boolean;
+  operationsRedone: number;
+  operationsFailed: number;
+  errors: string[];
+}
+
+/**
+ * A file found at a new path with unchanged content
+ */
+export interface MovedFile {
+  from: string;
+  to: string;
+}
+
+/**
+ * How a folder changed since its last snapshot (paths relative to the folder)
+ */
+export interface DriftReport {
+  folderPath: string;
+  sessionId: string | null;
+  snapshotAt: string; // ISO date string
+  added: string[];
+  removed: string[];
+  moved: MovedFile[];
+  modified: string[];
+  unchanged: number;
+  /** Absolute paths of new or changed files, for re-running organization */
+  deltaPaths: string[];
+}
+
 /**
  * Folder index entry (from global index)
  */
@@ -1,3 +1,19 @@
+export interface GpsPosition {
+  latitude: number;
+  longitude: number;
+  altitude: number | null;
+}
+
+export interface PhotoMetadata {
+  takenAt: number | null;
+  cameraMake: string | null;
+  cameraModel: string | null;
+  lens: string | null;
+  width: number | null;
+  height: number | null;
+  gps: GpsPosition | null;
+}
+
 export interface PhotoEntry {
   path: string;
   name: string;
@@ -5,6 +21,7 @@ export interface PhotoEntry {
   createdAt: number | null;
   modifiedAt: number | null;
   extension: string | null;
+  exif: PhotoMetadata | null;
 }
 
 export interface PhotoGroup {
@@ -124,3 +124,58 @@ export interface DiffNode {
   /** Whether this folder is expanded in the UI */
   isExpanded?: boolean;
 }
+
+/**
+ * How a dry-run expects an operation to be carried out.
+ */
+export type ImpactKind = 'rename' | 'cross_device_copy' | 'create_folder' | 'delete';
+
+/**
+ * Dry-run result for one plan operation (from vfs_dry_run_plan).
+ */
+export interface OperationImpact {
+  /** Position of the operation in the plan */
+  index: number;
+  kind: ImpactKind;
+  /** Source path for moves */
+  source: string | null;
+  /** Path the operation creates or removes */
+  target: string;
+  /** Size of the file or folder being moved or deleted */
+  bytes: number;
+  /** Bytes copied to another device */
+  bytesCopied: number;
+  /** Why the simulator or path validator rejects the operation */
+  error: string | null;
+}
+
+/**
+ * Space needed on a device receiving cross-device copies.
+ */
+export interface DeviceSpace {
+  /** An existing folder on the device */
+  path: string;
+  bytesRequired: number;
+  /** Free bytes, null if unknown */
+  bytesAvailable: number | null;
+  sufficient: boolean;
+}
+
+/**
+ * Byte-level impact estimate for a whole plan.
+ */
+export interface DryRunReport {
+  operations: OperationImpact[];
+  renames: number;
+  crossDeviceMoves: number;
+  bytesToCopy: number;
+  devices: DeviceSpace[];
+  estimatedDurationMs: number;
+  rejectedOperations: number;
+  /** Conflicts between operations */
+  errors: string[];
+  /** Fraction of files the plan accounts for (0 to 1) */
+  coverage: number;
+  /** No errors and enough space on every device */
+  valid: boolean;
+}